@@ -12,15 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-import { describe, it, before, after } from 'node:test';
+import { describe, it, before, after, beforeEach, afterEach } from 'node:test';
 import assert from 'node:assert/strict';
 import { createRequire } from 'node:module';
 import fs from 'node:fs';
 import path from 'node:path';
 import os from 'node:os';
+import { MessageChannel, Worker } from 'node:worker_threads';
 
 const require = createRequire(import.meta.url);
-const { Database } = require('../index.js');
+const { Database, DatabaseManager, RemoteDatabase, sql } = require('../index.js');
 
 // ============================================================
 // Database open/close
@@ -1663,3 +1664,5745 @@ describe('Vector support', () => {
     assert.equal(rows[0].id, 2);
   });
 });
+
+// ============================================================
+// Time-travel queries
+// ============================================================
+
+describe('Time-travel queries', () => {
+  let db;
+  let before_update;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE history (id INTEGER PRIMARY KEY, status TEXT)');
+    db.executeSync("INSERT INTO history VALUES (1, 'draft'), (2, 'draft')");
+    await new Promise(r => setTimeout(r, 20));
+    before_update = new Date();
+    await new Promise(r => setTimeout(r, 20));
+    db.executeSync("UPDATE history SET status = 'published' WHERE id = 1");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should read current data without asOf', () => {
+    const rows = db.querySync('SELECT status FROM history ORDER BY id');
+    assert.deepEqual(rows.map(r => r.status), ['published', 'draft']);
+  });
+
+  it('should read a historical snapshot with a Date', async () => {
+    const rows = await db.query('SELECT id, status FROM history ORDER BY id', [], {
+      asOf: before_update,
+    });
+    assert.deepEqual(rows.map(r => r.status), ['draft', 'draft']);
+  });
+
+  it('should read a historical snapshot with an ISO string', () => {
+    const raw = db.queryRawSync('SELECT id, status FROM history ORDER BY id', null, {
+      asOf: before_update.toISOString(),
+    });
+    assert.deepEqual(raw.rows, [[1, 'draft'], [2, 'draft']]);
+  });
+
+  it('should support asOf on queryOne', async () => {
+    const row = await db.queryOne("SELECT COUNT(*) AS n FROM history WHERE status = 'draft'", [], {
+      asOf: before_update,
+    });
+    assert.equal(row.n, 2);
+  });
+
+  it('should read derived tables, CTE bodies and set operations at the same point', () => {
+    const asOf = { asOf: before_update };
+    assert.deepEqual(
+      db.querySync('SELECT s.status FROM (SELECT * FROM history WHERE id = 1) s', [], asOf),
+      [{ status: 'draft' }],
+    );
+    assert.deepEqual(
+      db.querySync('WITH h AS (SELECT * FROM history) SELECT h.status FROM h WHERE h.id = 1', [], asOf),
+      [{ status: 'draft' }],
+    );
+    assert.deepEqual(
+      db.querySync('SELECT status FROM history WHERE id = 1 UNION ALL SELECT status FROM history WHERE id = 1', [], asOf),
+      [{ status: 'draft' }, { status: 'draft' }],
+    );
+  });
+
+  it('should read tables whose quoted names have escaped quotes or non-ASCII characters', async () => {
+    db.execSync('CREATE TABLE "a""b" (id INTEGER PRIMARY KEY, v TEXT); CREATE TABLE "a""""é" (id INTEGER PRIMARY KEY, v TEXT)');
+    db.executeSync(`INSERT INTO "a""b" VALUES (1, 'old')`);
+    db.executeSync(`INSERT INTO "a""""é" VALUES (1, 'old')`);
+    await new Promise(r => setTimeout(r, 20));
+    const asOf = { asOf: new Date() };
+    await new Promise(r => setTimeout(r, 20));
+    db.executeSync(`UPDATE "a""b" SET v = 'new'`);
+    db.executeSync(`UPDATE "a""""é" SET v = 'new'`);
+    assert.deepEqual(db.querySync('SELECT v FROM "a""b" WHERE id = 1', [], asOf), [{ v: 'old' }]);
+    assert.deepEqual(db.querySync('SELECT t.v FROM "a""""é" t WHERE t.id = 1', [], asOf), [{ v: 'old' }]);
+    assert.deepEqual(
+      db.querySync('SELECT x.v FROM "a""b" x JOIN "a""""é" y ON x.id = y.id WHERE x.id = 1', [], asOf),
+      [{ v: 'old' }],
+    );
+  });
+
+  it('should reject subqueries in expressions', () => {
+    for (const sql of [
+      'SELECT * FROM history WHERE id IN (SELECT id FROM history)',
+      'SELECT * FROM history WHERE EXISTS (SELECT 1 FROM history)',
+      'SELECT (SELECT COUNT(*) FROM history) AS n FROM history',
+    ]) {
+      assert.throws(() => db.querySync(sql, [], { asOf: before_update }), /not supported for queries with subqueries/);
+    }
+  });
+
+  it('should reject asOf in a transaction', async () => {
+    const tx = db.beginSync();
+    assert.throws(() => tx.querySync('SELECT * FROM history', [], { asOf: before_update }), /not supported in a transaction/);
+    await assert.rejects(async () => tx.query('SELECT * FROM history', [], { asOf: before_update }), /not supported in a transaction/);
+    assert.equal(tx.querySync('SELECT * FROM history', []).length, 2);
+    tx.rollbackSync();
+  });
+
+  it('should reject asOf on non-SELECT statements', () => {
+    assert.throws(
+      () => db.querySync("UPDATE history SET status = 'x'", [], { asOf: before_update }),
+      /only supported for SELECT/
+    );
+  });
+
+  it('should reject unsupported asOf values', () => {
+    assert.throws(
+      () => db.querySync('SELECT * FROM history', [], { asOf: true }),
+      /asOf must be/
+    );
+  });
+});
+
+// ============================================================
+// Checkpoints
+// ============================================================
+
+describe('Checkpoints', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)');
+    db.execSync('CREATE TABLE events (kind TEXT)');
+    db.executeSync('INSERT INTO accounts VALUES (1, 100), (2, 200), (3, 300)');
+    db.executeSync("INSERT INTO events VALUES ('open'), ('open')");
+    await db.createCheckpoint('initial');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should restore updated, deleted, and inserted rows', async () => {
+    db.executeSync('UPDATE accounts SET balance = 0 WHERE id = 1');
+    db.executeSync('DELETE FROM accounts WHERE id = 2');
+    db.executeSync('INSERT INTO accounts VALUES (4, 400)');
+
+    await db.restoreCheckpoint('initial');
+    const rows = db.querySync('SELECT id, balance FROM accounts ORDER BY id');
+    assert.deepEqual(rows.map(r => [r.id, r.balance]), [[1, 100], [2, 200], [3, 300]]);
+  });
+
+  it('should restore tables without a primary key', async () => {
+    db.executeSync("INSERT INTO events VALUES ('close')");
+    await db.restoreCheckpoint('initial');
+    const rows = db.querySync('SELECT kind FROM events');
+    assert.deepEqual(rows.map(r => r.kind), ['open', 'open']);
+  });
+
+  it('should allow restoring the same checkpoint repeatedly', async () => {
+    db.executeSync('DELETE FROM accounts');
+    await db.restoreCheckpoint('initial');
+    db.executeSync('UPDATE accounts SET balance = balance + 1');
+    await db.restoreCheckpoint('initial');
+    const row = db.queryOneSync('SELECT SUM(balance) AS total FROM accounts');
+    assert.equal(row.total, 600);
+  });
+
+  it('should replace a checkpoint with the same name', async () => {
+    db.executeSync('UPDATE accounts SET balance = 1 WHERE id = 1');
+    await db.createCheckpoint('later');
+    db.executeSync('UPDATE accounts SET balance = 2 WHERE id = 1');
+    await db.createCheckpoint('later');
+    db.executeSync('UPDATE accounts SET balance = 3 WHERE id = 1');
+
+    await db.restoreCheckpoint('later');
+    assert.equal(db.queryOneSync('SELECT balance FROM accounts WHERE id = 1').balance, 2);
+    await db.restoreCheckpoint('initial');
+  });
+
+  it('should reject unknown checkpoints', async () => {
+    await assert.rejects(() => db.restoreCheckpoint('missing'), /Checkpoint 'missing' not found/);
+  });
+
+  it('should reject restoring a dropped table', async () => {
+    db.execSync('CREATE TABLE scratch (id INTEGER PRIMARY KEY)');
+    await db.createCheckpoint('with_scratch');
+    db.execSync('DROP TABLE scratch');
+    await assert.rejects(() => db.restoreCheckpoint('with_scratch'), /was dropped/);
+  });
+});
+
+describe('Change events', () => {
+  let db;
+  let events;
+  const flush = () => new Promise(resolve => setTimeout(resolve, 20));
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)');
+    db.execSync('CREATE TABLE log (msg TEXT)');
+    db.on('change', (change) => events.push(change));
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  beforeEach(() => {
+    events = [];
+  });
+
+  it('should report inserts, updates, and deletes with row ids', async () => {
+    db.executeSync("INSERT INTO items VALUES (1, 'a'), (2, 'b')");
+    await db.execute('UPDATE items SET name = $1 WHERE id = $2', ['z', 2]);
+    await db.exec('DELETE FROM items WHERE id = 1');
+    await flush();
+    assert.deepEqual(events, [
+      { op: 'insert', table: 'items', rowId: 1 },
+      { op: 'insert', table: 'items', rowId: 2 },
+      { op: 'update', table: 'items', rowId: 2 },
+      { op: 'delete', table: 'items', rowId: 1 },
+    ]);
+  });
+
+  it('should report prepared statement writes', async () => {
+    const stmt = db.prepare('INSERT INTO items VALUES ($1, $2)');
+    stmt.executeSync([10, 'x']);
+    await stmt.execute([11, 'y']);
+    await flush();
+    assert.deepEqual(events.map(e => e.rowId), [10, 11]);
+  });
+
+  it('should report transaction writes only after commit', async () => {
+    const tx = await db.begin();
+    await tx.execute('INSERT INTO items VALUES ($1, $2)', [20, 'tx']);
+    await tx.execute('DELETE FROM items WHERE id = $1', [10]);
+    await flush();
+    assert.equal(events.length, 0);
+    await tx.commit();
+    await flush();
+    assert.deepEqual(events, [
+      { op: 'insert', table: 'items', rowId: 20 },
+      { op: 'delete', table: 'items', rowId: 10 },
+    ]);
+  });
+
+  it('should drop transaction writes on rollback', async () => {
+    const tx = db.beginSync();
+    tx.executeSync("INSERT INTO items VALUES (30, 'gone')");
+    tx.rollbackSync();
+    await flush();
+    assert.equal(events.length, 0);
+  });
+
+  it('should report null row ids for tables without a primary key', async () => {
+    db.executeSync("INSERT INTO log VALUES ('one'), ('two')");
+    db.executeSync('DELETE FROM log');
+    await flush();
+    assert.deepEqual(events.map(e => [e.op, e.rowId]), [
+      ['insert', null], ['insert', null], ['delete', null], ['delete', null],
+    ]);
+  });
+
+  it('should report rows rewritten by restoreCheckpoint', async () => {
+    await db.createCheckpoint('before');
+    db.executeSync('DELETE FROM items WHERE id = 20');
+    await flush();
+    events = [];
+    await db.restoreCheckpoint('before');
+    await flush();
+    assert.deepEqual(events, [{ op: 'insert', table: 'items', rowId: 20 }]);
+  });
+
+  it('should reject unsupported events', () => {
+    assert.throws(() => db.on('close', () => {}), /Unsupported event/);
+  });
+});
+
+describe('Statement policies', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE orders (id INTEGER PRIMARY KEY, total INTEGER)');
+    db.execSync('CREATE TABLE secrets (id INTEGER PRIMARY KEY, value TEXT)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  afterEach(() => {
+    db.setPolicy(null);
+  });
+
+  it('should reject denied statement types', () => {
+    db.setPolicy({ deny: ['DROP', 'alter table'] });
+    assert.throws(() => db.execSync('DROP TABLE orders'), /DROP TABLE statements are not allowed/);
+    assert.throws(() => db.execSync('ALTER TABLE orders ADD COLUMN note TEXT'), /ALTER TABLE/);
+    db.executeSync('INSERT INTO orders VALUES (1, 10)');
+  });
+
+  it('should check every statement of a batch before running any', () => {
+    db.setPolicy({ deny: ['DELETE'] });
+    assert.throws(() => db.execSync('INSERT INTO orders VALUES (2, 20); DELETE FROM orders'), /policy violation/);
+    db.setPolicy(null);
+    assert.equal(db.queryOneSync('SELECT * FROM orders WHERE id = 2'), null);
+  });
+
+  it('should only allow listed tables, including in subqueries', async () => {
+    db.setPolicy({ allowTables: ['orders'] });
+    assert.ok(Array.isArray(db.querySync('SELECT * FROM orders')));
+    assert.throws(() => db.querySync('SELECT * FROM secrets'), /table 'secrets' is not allowed/);
+    assert.throws(
+      () => db.querySync('SELECT * FROM orders WHERE id IN (SELECT id FROM secrets)'),
+      /table 'secrets'/,
+    );
+    assert.throws(() => db.querySync('WITH s AS (SELECT * FROM secrets) SELECT * FROM s'), /table 'secrets'/);
+    await assert.rejects(async () => db.query('SELECT * FROM Secrets'), /table 'secrets'/);
+  });
+
+  it('should not let a CTE name hide the table it is named after', () => {
+    db.setPolicy({ allowTables: ['orders'] });
+    assert.throws(
+      () => db.querySync('WITH secrets AS (SELECT * FROM secrets) SELECT * FROM secrets'),
+      /table 'secrets' is not allowed/,
+    );
+    assert.throws(
+      () => db.querySync('WITH secrets AS (SELECT * FROM orders) SELECT * FROM orders WHERE id IN (SELECT id FROM secrets)'),
+      /table 'secrets' is not allowed/,
+    );
+    assert.ok(Array.isArray(
+      db.querySync('WITH secrets AS (SELECT id FROM orders), s2 AS (SELECT * FROM secrets) SELECT * FROM secrets UNION ALL SELECT * FROM s2'),
+    ));
+    assert.deepEqual(
+      db.querySync('WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 3) SELECT * FROM n'),
+      [{ x: 1 }, { x: 2 }, { x: 3 }],
+    );
+  });
+
+  it('should apply to transactions and existing prepared statements', () => {
+    const stmt = db.prepare('SELECT * FROM secrets');
+    db.setPolicy({ allowTables: ['orders'] });
+    assert.throws(() => stmt.querySync(), /policy violation/);
+    assert.throws(() => db.prepare('DELETE FROM secrets'), /policy violation/);
+    const tx = db.beginSync();
+    assert.throws(() => tx.executeSync('DELETE FROM secrets'), /policy violation/);
+    tx.rollbackSync();
+  });
+
+  it('should reject unknown statement types in the deny list', () => {
+    assert.throws(() => db.setPolicy({ deny: ['DESTROY'] }), /Unknown statement type 'DESTROY'/);
+  });
+});
+
+describe('Describe', () => {
+  it('should describe an in-memory database', async () => {
+    const db = await Database.open(':memory:');
+    const info = db.describe();
+    assert.equal(info.path, 'memory://');
+    assert.equal(info.options.persistence, false);
+    assert.equal(info.options.sync, 'normal');
+    assert.equal(typeof info.engineVersion, 'string');
+    assert.equal(typeof info.bindingVersion, 'string');
+    assert.equal(info.features.v8Helpers, Database.capabilities().build.v8Helpers);
+    await db.close();
+  });
+
+  it('should report options in effect and redact unknown parameters', async () => {
+    const tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-describe-'));
+    const dbPath = path.join(tmpDir, 'describe.db');
+    try {
+      const db = await Database.open(`${dbPath}?sync=full&keep_snapshots=3&api_token=s3cret`);
+      const info = db.describe();
+      assert.equal(info.path, `file://${dbPath}?sync=full&keep_snapshots=3&api_token=***`);
+      assert.ok(!info.path.includes('s3cret'));
+      assert.equal(info.options.persistence, true);
+      assert.equal(info.options.sync, 'full');
+      assert.equal(info.options.keepSnapshots, 3);
+      assert.equal(info.options.snapshotInterval, 300);
+      await db.close();
+    } finally {
+      fs.rmSync(tmpDir, { recursive: true, force: true });
+    }
+  });
+});
+
+describe('Configuration', () => {
+  it('should apply open options from the first statement', async () => {
+    const tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-config-'));
+    const dbPath = path.join(tmpDir, 'config.db');
+    try {
+      const db = await Database.open(dbPath, { sync: 'full', keepSnapshots: 2, walCompression: false });
+      assert.equal(db.config('sync'), 'full');
+      assert.equal(db.config('keepSnapshots'), 2);
+      assert.equal(db.config('walCompression'), false);
+      assert.equal(db.config('persistence'), true);
+      await db.close();
+    } finally {
+      fs.rmSync(tmpDir, { recursive: true, force: true });
+    }
+  });
+
+  it('should reject invalid open options', () => {
+    assert.throws(() => Database.open('./never-created', { sync: 'fast' }), /Invalid sync mode 'fast'/);
+    assert.throws(() => Database.open(':memory:', { sync: 'full' }), /only supported for file-based/);
+  });
+
+  it('should change runtime settings', async () => {
+    const db = await Database.open(':memory:');
+    assert.equal(db.config('sync', 'none'), 'none');
+    assert.equal(db.config('snapshotInterval', 60), 60);
+    assert.equal(db.config('snapshotInterval'), 60);
+    await db.close();
+  });
+
+  it('should reject unknown keys and settings fixed at open time', async () => {
+    const db = await Database.open(':memory:');
+    assert.throws(() => db.config('pageCacheSize'), /Unknown config key/);
+    assert.throws(() => db.config('walMaxSize', 1024), /cannot be changed after opening/);
+    assert.throws(() => db.config('keepSnapshots', -1), /non-negative integer/);
+    assert.throws(() => db.config('sync', 2), /must be a string/);
+    await db.close();
+  });
+});
+
+describe('Capabilities', () => {
+  it('should report engine and binding capabilities without a database', () => {
+    const caps = Database.capabilities();
+    assert.equal(typeof caps.engineVersion, 'string');
+    assert.equal(caps.sql.timeTravel, true);
+    assert.equal(caps.vector.supported, true);
+    assert.equal(caps.vector.maxDimensions, 65535);
+    assert.equal(caps.binding.bigintResults, false);
+    assert.ok(caps.functions.aggregate.includes('COUNT'));
+    assert.ok(caps.functions.window.includes('ROW_NUMBER'));
+    assert.ok(caps.functions.scalar.includes('VEC_DISTANCE_COSINE'));
+  });
+
+  it('should match the version reported by describe()', async () => {
+    const db = await Database.open(':memory:');
+    assert.equal(Database.capabilities().engineVersion, db.describe().engineVersion);
+    await db.close();
+  });
+});
+
+describe('Transaction options', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE parents (id INTEGER PRIMARY KEY)');
+    db.execSync('CREATE TABLE children (id INTEGER PRIMARY KEY, parent_id INTEGER REFERENCES parents(id))');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should accept an empty options object', async () => {
+    const tx = await db.begin({});
+    tx.executeSync('INSERT INTO parents VALUES (1)');
+    await tx.commit();
+    const txSync = db.beginSync({ deferConstraints: false });
+    txSync.rollbackSync();
+  });
+
+  it('should reject deferConstraints while the engine checks constraints immediately', () => {
+    assert.equal(Database.capabilities().sql.deferredConstraints, false);
+    assert.throws(() => db.begin({ deferConstraints: true }), /deferConstraints is not supported/);
+    assert.throws(() => db.beginSync({ deferConstraints: true }), /deferConstraints is not supported/);
+    assert.throws(() => db.executeSync('INSERT INTO children VALUES (1, 99)'), /foreign key/);
+  });
+});
+
+describe('Query plans', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE plan_a (id INTEGER PRIMARY KEY, g INTEGER, name TEXT)');
+    db.execSync('CREATE INDEX plan_a_g ON plan_a(g)');
+    db.execSync('CREATE TABLE plan_b (id INTEGER PRIMARY KEY, a_id INTEGER)');
+    for (let i = 1; i <= 8; i++) {
+      db.executeSync('INSERT INTO plan_a VALUES ($1, $2, $3)', [i, i % 2, `n${i}`]);
+      db.executeSync('INSERT INTO plan_b VALUES ($1, $2)', [i, i]);
+    }
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should explain the cached plan as a tree without executing', async () => {
+    const stmt = db.prepare('SELECT * FROM plan_a WHERE g = 1');
+    const result = await stmt.explain();
+    assert.equal(result.analyzed, false);
+    assert.equal(result.plan.operation, 'SELECT');
+    assert.equal(result.plan.properties.Columns, '*');
+    assert.equal(result.plan.actualRows, null);
+    const [scan] = result.plan.children;
+    assert.equal(scan.operation, 'Index Scan using plan_a_g on plan_a');
+    assert.equal(scan.properties['Index Cond'], 'g = 1');
+    assert.equal(result.text[0], 'SELECT');
+  });
+
+  it('should nest join inputs and lift planner estimates', () => {
+    const stmt = db.prepare(
+      'SELECT plan_a.name FROM plan_a JOIN plan_b ON plan_a.id = plan_b.a_id ORDER BY plan_a.name'
+    );
+    const { plan } = stmt.explainSync();
+    assert.equal(plan.properties['Order By'], 'plan_a.name ASC');
+    const [join] = plan.children;
+    assert.match(join.operation, /Join\)$/);
+    assert.equal(typeof join.estimatedRows, 'number');
+    assert.equal(typeof join.estimatedCost, 'number');
+    assert.equal(join.properties['Join Cond'], '(plan_a.id = plan_b.a_id)');
+    assert.equal(join.children.length, 2);
+  });
+
+  it('should report actual rows and timing with explainAnalyze', async () => {
+    const stmt = db.prepare('SELECT * FROM plan_a WHERE g = $1');
+    const result = await stmt.explainAnalyze([0]);
+    assert.equal(result.analyzed, true);
+    assert.equal(result.plan.actualRows, 4);
+    assert.equal(typeof result.plan.actualTimeMs, 'number');
+    assert.ok(result.plan.actualTimeMs >= 0);
+    assert.equal(stmt.explainAnalyzeSync([1]).plan.actualRows, 4);
+  });
+
+  it('should explain DML and only apply it when analyzing', () => {
+    const stmt = db.prepare('UPDATE plan_a SET name = $1 WHERE id = $2');
+    const { plan } = stmt.explainSync();
+    assert.equal(plan.operation, 'UPDATE plan_a');
+    assert.equal(plan.properties.Set, '1 column(s)');
+    assert.equal(db.queryOneSync('SELECT name FROM plan_a WHERE id = 1').name, 'n1');
+    stmt.explainAnalyzeSync(['renamed', 1]);
+    assert.equal(db.queryOneSync('SELECT name FROM plan_a WHERE id = 1').name, 'renamed');
+    assert.throws(() => stmt.explainAnalyzeSync(), /requires 2 parameters/);
+  });
+
+  it('should enforce the statement policy', () => {
+    const stmt = db.prepare('DELETE FROM plan_b WHERE id = 1');
+    db.setPolicy({ deny: ['DELETE'] });
+    try {
+      assert.throws(() => stmt.explainSync(), /SQL policy violation/);
+    } finally {
+      db.setPolicy(null);
+    }
+  });
+});
+
+describe('Profile events', () => {
+  let db;
+  let events;
+  const flush = () => new Promise(resolve => setTimeout(resolve, 20));
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE prof (id INTEGER PRIMARY KEY, v TEXT)');
+    db.on('profile', (event) => events.push(event));
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  beforeEach(() => {
+    events = [];
+  });
+
+  it('should report sync and async statements with row counts', async () => {
+    db.executeSync("INSERT INTO prof VALUES (1, 'a'), (2, 'b')");
+    db.querySync('SELECT * FROM prof');
+    await db.queryOne('SELECT * FROM prof WHERE id = $1', [1]);
+    await db.exec('UPDATE prof SET v = v; DELETE FROM prof WHERE id = 99');
+    await flush();
+    assert.deepEqual(events.map(e => [e.sql, e.rows]), [
+      ["INSERT INTO prof VALUES (1, 'a'), (2, 'b')", 2],
+      ['SELECT * FROM prof', 2],
+      ['SELECT * FROM prof WHERE id = $1', 1],
+      ['UPDATE prof SET v = v', 2],
+      ['DELETE FROM prof WHERE id = 99', 0],
+    ]);
+    for (const event of events) {
+      assert.equal(typeof event.durationMs, 'number');
+      assert.ok(event.durationMs >= 0);
+      assert.equal(event.plan, null);
+    }
+  });
+
+  it('should report prepared statements and transactions', async () => {
+    const stmt = db.prepare('SELECT v FROM prof WHERE id = $1');
+    stmt.queryRawSync([1]);
+    await stmt.query([2]);
+    const tx = await db.begin();
+    await tx.execute('UPDATE prof SET v = $1 WHERE id = $2', ['z', 1]);
+    tx.querySync('SELECT * FROM prof');
+    await tx.commit();
+    db.prepare('INSERT INTO prof VALUES ($1, $2)').executeBatchSync([[3, 'c'], [4, 'd']]);
+    await flush();
+    assert.deepEqual(events.map(e => [e.sql, e.rows]), [
+      ['SELECT v FROM prof WHERE id = $1', 1],
+      ['SELECT v FROM prof WHERE id = $1', 1],
+      ['UPDATE prof SET v = $1 WHERE id = $2', 1],
+      ['SELECT * FROM prof', 2],
+      ['INSERT INTO prof VALUES ($1, $2)', 2],
+    ]);
+  });
+
+  it('should apply thresholdMs and attach plans on request', async () => {
+    const slow = [];
+    const planned = [];
+    db.on('profile', (event) => slow.push(event), { thresholdMs: 60_000 });
+    db.on('profile', (event) => planned.push(event), { plan: true });
+    db.querySync('SELECT * FROM prof WHERE id = 1');
+    await flush();
+    assert.equal(slow.length, 0);
+    assert.equal(planned.length, 1);
+    assert.equal(planned[0].plan.plan.operation, 'SELECT');
+    assert.equal(events[0].plan, null);
+  });
+
+  it('should not report failed statements and reject bad options', async () => {
+    assert.throws(() => db.querySync('SELECT * FROM missing_table'));
+    await flush();
+    assert.equal(events.length, 0);
+    assert.throws(() => db.on('change', () => {}, { plan: true }), /only supported for 'profile'/);
+    assert.throws(() => db.on('query', () => {}), /Unsupported event/);
+  });
+});
+
+describe('deleteMany', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE dm (id INTEGER PRIMARY KEY, tag TEXT)');
+    db.execSync('CREATE TABLE dm_nokey (name TEXT)');
+    db.executeBatchSync(
+      'INSERT INTO dm VALUES ($1, $2)',
+      Array.from({ length: 50 }, (_, i) => [i + 1, i % 2 ? 'odd' : 'even'])
+    );
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  const count = () => db.queryOneSync('SELECT COUNT(*) AS c FROM dm').c;
+
+  it('should delete by primary key across chunks', async () => {
+    const ids = Array.from({ length: 30 }, (_, i) => i + 1);
+    assert.equal(db.deleteManySync('dm', ids, { chunkSize: 7 }).changes, 30);
+    assert.equal(count(), 20);
+    assert.equal((await db.deleteMany('dm', [31, 32, 999])).changes, 2);
+    assert.equal(count(), 18);
+    assert.equal(db.deleteManySync('dm', []).changes, 0);
+  });
+
+  it('should match an explicit key column', () => {
+    assert.equal(db.deleteManySync('dm', ['odd'], { keyColumn: 'tag' }).changes, 25);
+    assert.equal(count(), 25);
+  });
+
+  it('should report one statement per chunk to listeners', async () => {
+    const profiles = [];
+    const changes = [];
+    db.on('profile', (e) => profiles.push(e.sql));
+    db.on('change', (e) => changes.push(e.rowId));
+    db.deleteManySync('dm', [1, 2, 3], { chunkSize: 2 });
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.deepEqual(profiles, [
+      'DELETE FROM "dm" WHERE "id" IN ($1, $2)',
+      'DELETE FROM "dm" WHERE "id" IN ($1)',
+    ]);
+    assert.deepEqual(changes, [1, 2, 3]);
+  });
+
+  it('should validate arguments and honor the statement policy', () => {
+    assert.throws(() => db.deleteManySync('dm_nokey', ['a']), /pass options.keyColumn/);
+    assert.throws(() => db.deleteManySync('dm', 1), /keys must be an array/);
+    assert.throws(() => db.deleteManySync('dm', [1], { chunkSize: 0 }), /chunkSize must be at least 1/);
+    db.setPolicy({ deny: ['DELETE'] });
+    assert.throws(() => db.deleteManySync('dm', [1]), /SQL policy violation/);
+    assert.equal(count(), 50);
+  });
+});
+
+describe('Array parameters', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE ap (id INTEGER PRIMARY KEY, tag TEXT, data JSON)');
+    db.execSync("INSERT INTO ap VALUES (1, 'a', NULL), (2, 'b', NULL), (3, 'c', NULL), (4, 'd', NULL)");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  const ids = (rows) => rows.map(r => r.id);
+
+  it('should expand positional arrays in IN lists', async () => {
+    assert.deepEqual(ids(db.querySync('SELECT id FROM ap WHERE id IN ($1) ORDER BY id', [[1, 3]])), [1, 3]);
+    assert.deepEqual(
+      ids(db.querySync('SELECT id FROM ap WHERE tag <> ? AND id IN (?) ORDER BY id', ['b', [1, 2, 4]])),
+      [1, 4]
+    );
+    assert.deepEqual(
+      ids(await db.query('SELECT id FROM ap WHERE id IN ($1) AND id <> $2 ORDER BY id', [[2, 3, 4], 3])),
+      [2, 4]
+    );
+  });
+
+  it('should expand named arrays in IN lists', async () => {
+    assert.deepEqual(
+      ids(db.querySync('SELECT id FROM ap WHERE id IN (:ids) OR tag = :tag ORDER BY id', { ids: [1], tag: 'd' })),
+      [1, 4]
+    );
+    assert.deepEqual(await db.queryOne('SELECT COUNT(*) AS c FROM ap WHERE tag IN (:tags)', { tags: ['a', 'c'] }), { c: 2 });
+  });
+
+  it('should treat empty arrays as empty lists', () => {
+    assert.deepEqual(db.querySync('SELECT id FROM ap WHERE id IN (?)', [[]]), []);
+    assert.equal(db.querySync('SELECT id FROM ap WHERE id NOT IN (:ids)', { ids: [] }).length, 4);
+  });
+
+  it('should expand arrays bound to prepared statements', async () => {
+    const stmt = db.prepare('SELECT id FROM ap WHERE id IN ($1) ORDER BY id');
+    assert.deepEqual(ids(stmt.querySync([[2, 3]])), [2, 3]);
+    assert.deepEqual(ids(stmt.querySync([[4]])), [4]);
+    assert.deepEqual(ids(stmt.querySync([1])), [1]);
+    assert.deepEqual(ids(await stmt.query([[1, 2, 3]])), [1, 2, 3]);
+    assert.deepEqual(stmt.explainSync([[1, 2]]).plan.children[0].properties.Filter, 'id IN ($1, $2)');
+  });
+
+  it('should keep binding arrays outside IN lists as JSON', () => {
+    assert.equal(db.executeSync('UPDATE ap SET data = $1 WHERE id IN ($2)', [[1, 2], [1, 2]]).changes, 2);
+    assert.deepEqual(
+      db.querySync('SELECT id, data FROM ap WHERE data IS NOT NULL ORDER BY id'),
+      [{ id: 1, data: '[1,2]' }, { id: 2, data: '[1,2]' }]
+    );
+  });
+});
+
+describe('Plan cache', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE pc (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should only cache prepared statements by default', () => {
+    assert.equal(db.planCache.automatic, false);
+    db.executeSync('INSERT INTO pc VALUES ($1, $2)', [1, 'a']);
+    db.querySync('SELECT name FROM pc');
+    assert.equal(db.planCache.stats().misses, 0);
+    db.prepare('SELECT name FROM pc');
+    db.prepare('SELECT name FROM pc');
+    const stats = db.planCache.stats();
+    assert.equal(stats.entries, 1);
+    assert.equal(stats.misses, 1);
+    assert.equal(stats.hits, 1);
+  });
+
+  it('should count hits and misses across calls and prepare()', async () => {
+    const before = db.planCache.stats();
+    assert.deepEqual(before, { entries: 0, maxSize: 1000, hits: 0, misses: 0, evictions: 0, sqlBytes: 0 });
+    db.planCache.automatic = true;
+    db.executeSync('INSERT INTO pc VALUES ($1, $2)', [1, 'a']);
+    await db.execute('INSERT INTO pc VALUES ($1, $2)', [2, 'b']);
+    const stmt = db.prepare('SELECT name FROM pc WHERE id = $1');
+    assert.deepEqual(db.querySync('SELECT name FROM pc WHERE id = $1', [2]), [{ name: 'b' }]);
+    assert.deepEqual(stmt.querySync([1]), [{ name: 'a' }]);
+    const stats = db.planCache.stats();
+    assert.equal(stats.entries, 2);
+    assert.equal(stats.misses, 2);
+    assert.equal(stats.hits, 2);
+    assert.equal(stats.sqlBytes, 'INSERT INTO pc VALUES ($1, $2)'.length + 'SELECT name FROM pc WHERE id = $1'.length);
+  });
+
+  it('should evict least recently used plans when shrunk', () => {
+    db.planCache.automatic = true;
+    for (let i = 0; i < 5; i++) db.querySync(`SELECT ${i} AS n`);
+    db.querySync('SELECT 0 AS n');
+    db.planCache.maxSize = 2;
+    assert.equal(db.planCache.maxSize, 2);
+    const stats = db.planCache.stats();
+    assert.equal(stats.entries, 2);
+    assert.equal(stats.evictions, 3);
+    const hits = stats.hits;
+    db.querySync('SELECT 0 AS n');
+    db.querySync('SELECT 4 AS n');
+    assert.equal(db.planCache.stats().hits, hits + 2);
+  });
+
+  it('should clear plans but keep prepared statements working', () => {
+    const stmt = db.prepare('SELECT COUNT(*) AS c FROM pc');
+    db.planCache.clear();
+    assert.equal(db.planCache.stats().entries, 0);
+    assert.deepEqual(stmt.queryOneSync(), { c: 0 });
+    db.planCache.maxSize = 0;
+    db.querySync('SELECT 1 AS n');
+    assert.equal(db.planCache.stats().entries, 0);
+  });
+
+  it('should leave multi-statement and unparsable SQL to the engine', () => {
+    db.planCache.automatic = true;
+    db.execSync("INSERT INTO pc VALUES (1, 'a'); INSERT INTO pc VALUES (2, 'b')");
+    assert.throws(() => db.prepare('NOT SQL AT ALL'));
+    assert.throws(() => db.querySync('SELECT FROM WHERE'));
+    assert.equal(db.planCache.stats().entries, 0);
+  });
+
+  it('should keep a separate cache per handle', async () => {
+    const tenant = await Database.open(':memory:');
+    tenant.prepare('SELECT name FROM pc');
+    assert.equal(tenant.planCache.stats().entries, 1);
+    assert.equal(db.planCache.stats().entries, 0);
+    db.prepare('SELECT name FROM pc');
+    assert.equal(db.planCache.stats().misses, 1);
+  });
+});
+
+describe('tree', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE cat (id INTEGER PRIMARY KEY, parent_id INTEGER, name TEXT)');
+    db.execSync(`INSERT INTO cat VALUES
+      (1, NULL, 'root'), (3, 1, 'b'), (2, 1, 'a'), (4, 2, 'a1'), (5, NULL, 'other')`);
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  const names = (nodes) => nodes.map((n) => [n.name, names(n.children)]);
+
+  it('should nest every tree under its children', async () => {
+    const trees = await db.tree('cat', { parentColumn: 'parent_id' });
+    assert.deepEqual(names(trees), [
+      ['root', [['a', [['a1', []]]], ['b', []]]],
+      ['other', []],
+    ]);
+    assert.deepEqual(Object.keys(trees[0]), ['id', 'parent_id', 'name', 'children']);
+    assert.equal(trees[0].children[0].children[0].id, 4);
+  });
+
+  it('should start from rootId and stop at maxDepth', () => {
+    assert.deepEqual(names(db.treeSync('cat', { parentColumn: 'parent_id', rootId: 2 })), [
+      ['a', [['a1', []]]],
+    ]);
+    const shallow = db.treeSync('cat', { parentColumn: 'parent_id', rootId: 1, maxDepth: 1 });
+    assert.deepEqual(names(shallow), [['root', [['a', []], ['b', []]]]]);
+    assert.deepEqual(db.treeSync('cat', { parentColumn: 'parent_id', rootId: 99 }), []);
+  });
+
+  it('should honor idColumn and childrenKey', () => {
+    db.execSync('CREATE TABLE org (name TEXT, boss TEXT)');
+    db.execSync("INSERT INTO org VALUES ('ceo', NULL), ('cto', 'ceo'), ('dev', 'cto')");
+    const [ceo] = db.treeSync('org', { parentColumn: 'boss', idColumn: 'name', childrenKey: 'reports' });
+    assert.equal(ceo.reports[0].name, 'cto');
+    assert.equal(ceo.reports[0].reports[0].name, 'dev');
+    assert.throws(() => db.treeSync('org', { parentColumn: 'boss' }), /no single-column primary key/);
+  });
+
+  it('should include each node once when the data has a cycle', () => {
+    db.execSync('UPDATE cat SET parent_id = 4 WHERE id = 1');
+    const [root] = db.treeSync('cat', { parentColumn: 'parent_id', rootId: 1, maxDepth: 10 });
+    assert.deepEqual(names([root]), [['root', [['a', [['a1', []]]], ['b', []]]]]);
+  });
+});
+
+describe('Resource disposal', () => {
+  it('should close the database on dispose', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE t (id INTEGER)');
+    db[Symbol.dispose]();
+    assert.throws(() => db.querySync('SELECT * FROM t'));
+    db.closeSync();
+    await db[Symbol.asyncDispose]();
+  });
+
+  it('should roll back a transaction unless committed', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE t (id INTEGER)');
+
+    const tx = db.beginSync();
+    assert.equal(tx.active, true);
+    tx.executeSync('INSERT INTO t VALUES (1)');
+    tx[Symbol.dispose]();
+    assert.equal(tx.active, false);
+    assert.equal(db.queryOneSync('SELECT COUNT(*) AS c FROM t').c, 0);
+
+    const committed = await db.begin();
+    await committed.execute('INSERT INTO t VALUES (2)');
+    await committed.commit();
+    assert.equal(committed.active, false);
+    await committed[Symbol.asyncDispose]();
+    committed[Symbol.dispose]();
+    assert.equal(db.queryOneSync('SELECT COUNT(*) AS c FROM t').c, 1);
+
+    const pending = await db.begin();
+    await pending.execute('INSERT INTO t VALUES (3)');
+    await pending[Symbol.asyncDispose]();
+    assert.equal(db.queryOneSync('SELECT COUNT(*) AS c FROM t').c, 1);
+    await db.close();
+  });
+
+  it('should finalize a prepared statement', async () => {
+    const db = await Database.open(':memory:');
+    const stmt = db.prepare('SELECT 1 AS n');
+    assert.equal(stmt.finalized, false);
+    const running = stmt.query();
+    stmt[Symbol.dispose]();
+    assert.equal(stmt.finalized, true);
+    assert.deepEqual(await running, [{ n: 1 }]);
+    assert.throws(() => stmt.querySync(), /Statement is finalized/);
+    await assert.rejects(async () => stmt.execute(), /Statement is finalized/);
+    stmt.finalize();
+    await stmt[Symbol.asyncDispose]();
+    await db.close();
+  });
+});
+
+describe('topNPerGroup', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE staff (id INTEGER PRIMARY KEY, dept TEXT, name TEXT, salary FLOAT)');
+    db.execSync(`INSERT INTO staff VALUES
+      (1, 'eng', 'ann', 10), (2, 'eng', 'bob', 30), (3, 'eng', 'cat', 20),
+      (4, 'ops', 'dan', 5), (5, 'ops', 'eve', 5), (6, 'ops', 'fay', 7)`);
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should keep the first n rows of each group in rank order', async () => {
+    const rows = await db.topNPerGroup('staff', { group: 'dept', orderBy: 'salary DESC', n: 2 });
+    assert.deepEqual(rows.map((r) => r.id), [2, 3, 6, 4]);
+    assert.deepEqual(Object.keys(rows[0]), ['id', 'dept', 'name', 'salary']);
+    const sync = db.topNPerGroupSync('staff', { group: ['dept'], orderBy: ['salary', 'id DESC'], n: 1 });
+    assert.deepEqual(sync.map((r) => r.name), ['ann', 'eve']);
+  });
+
+  it('should keep ties with ties: true', () => {
+    const rows = db.topNPerGroupSync('staff', { group: 'dept', orderBy: 'salary', n: 1, ties: true });
+    assert.deepEqual(rows.map((r) => r.id).sort(), [1, 4, 5]);
+  });
+
+  it('should reject bad options', () => {
+    assert.throws(
+      () => db.topNPerGroupSync('staff', { group: 'dept', orderBy: 'salary sideways', n: 1 }),
+      /Invalid orderBy term/
+    );
+    assert.throws(() => db.topNPerGroupSync('staff', { group: 'team', orderBy: 'salary', n: 1 }), /Column 'team' not found/);
+    assert.throws(() => db.topNPerGroupSync('staff', { group: [], orderBy: 'salary', n: 1 }), /group must name/);
+    assert.throws(() => db.topNPerGroupSync('staff', { group: 'dept', orderBy: 'salary', n: 0 }), /n must be at least 1/);
+  });
+});
+
+describe('interrupt', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT)');
+    const rows = [];
+    for (let i = 0; i < 20000; i++) rows.push(`(${i}, 'v${i}')`);
+    db.execSync(`INSERT INTO t VALUES ${rows.join(', ')}`);
+    db.execSync('CREATE TABLE log (id INTEGER PRIMARY KEY)');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should reject pending statements and leave later ones running', async () => {
+    const stmt = db.prepare('SELECT * FROM t');
+    const tx = await db.begin();
+    const pending = [
+      db.query('SELECT * FROM t ORDER BY v DESC'),
+      db.query('SELECT * FROM t'),
+      db.queryOne('SELECT COUNT(*) AS c FROM t'),
+      stmt.query(),
+      tx.query('SELECT * FROM t'),
+    ];
+    db.interrupt();
+    for (const result of await Promise.allSettled(pending)) {
+      assert.equal(result.status, 'rejected');
+      assert.equal(result.reason.message, 'Interrupted');
+      assert.equal(result.reason.code, 'Cancelled');
+    }
+    assert.deepEqual(await db.queryOne('SELECT COUNT(*) AS c FROM t'), { c: 20000 });
+    assert.equal((await stmt.query()).length, 20000);
+    assert.equal((await tx.query('SELECT * FROM t')).length, 20000);
+    await tx.rollback();
+  });
+
+  it('should stop an exec() batch between statements', async () => {
+    const slow = db.query('SELECT * FROM t ORDER BY v DESC');
+    // The sort keeps the batch busy until interrupt() has been called.
+    const batch = db.exec(
+      'SELECT * FROM t ORDER BY v DESC; INSERT INTO log VALUES (1); INSERT INTO log VALUES (2); INSERT INTO log VALUES (3)'
+    );
+    db.interrupt();
+    const results = await Promise.allSettled([slow, batch]);
+    assert.deepEqual(results.map((r) => r.reason?.message), ['Interrupted', 'Interrupted']);
+    assert.ok(db.queryOneSync('SELECT COUNT(*) AS c FROM log').c < 3);
+  });
+
+  it('should do nothing when no statement is running', async () => {
+    db.interrupt();
+    assert.deepEqual(await db.query('SELECT id FROM log'), []);
+  });
+});
+
+describe('retry', () => {
+  async function lockedRow(options) {
+    const db = await Database.open(':memory:', options);
+    await db.exec('CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER)');
+    await db.execute('INSERT INTO t VALUES (1, 0)');
+    const tx = await db.begin();
+    await tx.execute('UPDATE t SET n = n + 1 WHERE id = $1', [1]);
+    return { db, tx };
+  }
+
+  it('should fail on a write conflict without the option', async () => {
+    const { db, tx } = await lockedRow();
+    await assert.rejects(db.execute('UPDATE t SET n = n + 10 WHERE id = 1'), /uncommitted changes/);
+    await tx.rollback();
+    await db.close();
+  });
+
+  it('should retry until the conflicting transaction commits', async () => {
+    const { db, tx } = await lockedRow({ retry: { retries: 20, backoffMs: 5 } });
+    setTimeout(() => tx.commit(), 30);
+    const result = await db.execute('UPDATE t SET n = n + 10 WHERE id = 1');
+    assert.equal(result.changes, 1);
+    assert.equal(result.warnings.length, 1);
+    assert.match(result.warnings[0], /^Retried (once|\d+ times) after (a )?write conflicts?$/);
+    const stmt = db.prepare('UPDATE t SET n = n + $1 WHERE id = 1');
+    assert.equal((await stmt.execute([100])).changes, 1);
+    assert.deepEqual(await db.query('SELECT n FROM t'), [{ n: 111 }]);
+    await db.close();
+  });
+
+  it('should throw the last error once the retries run out', async () => {
+    const { db, tx } = await lockedRow({ retry: { retries: 2, backoffMs: 1, jitter: false } });
+    await assert.rejects(db.execute('UPDATE t SET n = 5 WHERE id = 1'), /uncommitted changes/);
+    await assert.rejects(db.query('SELECT * FROM missing'), /not found/);
+    await tx.rollback();
+    await db.close();
+  });
+});
+
+describe('runtimeInfo', () => {
+  it('should use the V8 helpers under Node unless built without them', () => {
+    const info = Database.runtimeInfo();
+    assert.equal(info.runtime, 'node');
+    assert.equal(info.nodeVersion, process.version);
+    assert.ok(info.napiVersion >= 8);
+    if (Database.capabilities().build.v8Helpers) {
+      assert.equal(info.resultPath, 'v8');
+      assert.equal(info.fallbackReason, null);
+    } else {
+      assert.equal(info.resultPath, 'napi');
+      assert.equal(typeof info.fallbackReason, 'string');
+    }
+  });
+
+  it('should build the same results through N-API when the helpers are disabled', async () => {
+    const { execFileSync } = await import('node:child_process');
+    const script = `
+      const { Database } = require(${JSON.stringify(require.resolve('../index.js'))});
+      (async () => {
+        const db = await Database.open(':memory:');
+        db.execSync('CREATE TABLE t (id INTEGER PRIMARY KEY, parent INTEGER, name TEXT, v VECTOR(2))');
+        db.execSync("INSERT INTO t VALUES (1, NULL, 'a', '[1, 2]'), (2, 1, NULL, NULL)");
+        const out = {
+          info: Database.runtimeInfo(),
+          v8Helpers: Database.capabilities().build.v8Helpers,
+          rows: await db.query('SELECT * FROM t ORDER BY id'),
+          one: db.queryOneSync('SELECT name FROM t WHERE id = 1'),
+          none: await db.queryOne('SELECT name FROM t WHERE id = 3'),
+          raw: db.queryRawSync('SELECT id, name FROM t ORDER BY id'),
+          run: await db.execute("UPDATE t SET name = 'b' WHERE id = 2"),
+          tree: db.treeSync('t', { parentColumn: 'parent' }),
+          insert: db.executeSync('INSERT INTO t (id) VALUES (3), (4)'),
+        };
+        process.stdout.write(JSON.stringify(out, (k, v) => (v instanceof Float32Array ? Array.from(v) : v)));
+      })();
+    `;
+    const out = JSON.parse(
+      execFileSync(process.execPath, ['-e', script], {
+        env: { ...process.env, STOOLAP_DISABLE_V8_HELPERS: '1' },
+        encoding: 'utf8',
+      })
+    );
+    assert.equal(out.info.resultPath, 'napi');
+    assert.match(out.info.fallbackReason, /STOOLAP_DISABLE_V8_HELPERS/);
+    assert.equal(out.v8Helpers, false);
+    assert.deepEqual(out.rows, [
+      { id: 1, parent: null, name: 'a', v: [1, 2] },
+      { id: 2, parent: 1, name: null, v: null },
+    ]);
+    assert.deepEqual(out.one, { name: 'a' });
+    assert.equal(out.none, null);
+    assert.deepEqual(out.raw, { columns: ['id', 'name'], rows: [[1, 'a'], [2, null]] });
+    assert.equal(out.run.changes, 1);
+    assert.equal(out.run.lastInsertRowId, null);
+    assert.equal(typeof out.run.durationMs, 'number');
+    assert.deepEqual(out.run.warnings, []);
+    assert.equal(out.insert.lastInsertRowId, 4);
+    assert.deepEqual(out.tree, [
+      { id: 1, parent: null, name: 'a', v: [1, 2], children: [{ id: 2, parent: 1, name: 'b', v: null, children: [] }] },
+    ]);
+  });
+});
+
+describe('formatSql', () => {
+  it('should put each clause on its own line and indent subqueries', () => {
+    const sql = "select id, count(*) as n from t left join u on u.id = t.uid where t.x = -1 and t.id in (select id from v) group by id order by n desc limit 5";
+    assert.equal(
+      Database.formatSql(sql),
+      [
+        'SELECT id, count(*) AS n',
+        'FROM t',
+        'LEFT JOIN u ON u.id = t.uid',
+        'WHERE t.x = -1 AND t.id IN (',
+        '  SELECT id',
+        '  FROM v',
+        ')',
+        'GROUP BY id',
+        'ORDER BY n DESC',
+        'LIMIT 5;',
+      ].join('\n')
+    );
+    assert.equal(
+      Database.formatSql("create table t (id integer primary key, note text default 'it''s')", { indent: '\t' }),
+      "CREATE TABLE t (\n\tid INTEGER PRIMARY KEY,\n\tnote TEXT DEFAULT 'it''s'\n);"
+    );
+  });
+
+  it('should print one line per statement with indent: 0', () => {
+    const sql = 'select  *\nfrom "My Table"\nwhere a = :a;  -- by a\ndelete from t where id = ?';
+    assert.equal(
+      Database.formatSql(sql, { indent: 0 }),
+      'SELECT * FROM "My Table" WHERE a = :a;\n-- by a\nDELETE FROM t WHERE id = ?;'
+    );
+  });
+
+  it('should keep results unchanged and be idempotent', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE t (id INTEGER PRIMARY KEY, p INTEGER, note TEXT)');
+    db.execSync("INSERT INTO t VALUES (1, NULL, 'a -- b'), (2, 1, 'it''s'), (3, 2, NULL)");
+    const sql = `with recursive r as (select id, 0 as d from t where p is null
+      union all select c.id, r.d + 1 from t c, r where c.p = r.id)
+      select id, d, case when d > 0 then -d else d end as neg from r order by d`;
+    const formatted = Database.formatSql(sql, { indent: 4 });
+    assert.deepEqual(db.querySync(formatted), db.querySync(sql));
+    assert.equal(Database.formatSql(formatted, { indent: 4 }), formatted);
+    assert.deepEqual(db.querySync(Database.formatSql('SELECT note FROM t ORDER BY id')), [
+      { note: 'a -- b' },
+      { note: "it's" },
+      { note: null },
+    ]);
+    await db.close();
+  });
+
+  it('should throw on SQL that does not parse', () => {
+    assert.throws(() => Database.formatSql('SELECT FROM'), /cannot be used here/);
+  });
+});
+
+describe('RunResult', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.exec(`
+      CREATE TABLE rr (id INTEGER PRIMARY KEY AUTO_INCREMENT, name TEXT);
+      CREATE TABLE rr_nokey (name TEXT);
+    `);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should report every field', async () => {
+    const result = await db.execute('INSERT INTO rr (name) VALUES ($1)', ['a']);
+    assert.deepEqual(Object.keys(result), ['changes', 'lastInsertRowId', 'durationMs', 'warnings']);
+    assert.equal(result.changes, 1);
+    assert.equal(result.lastInsertRowId, 1);
+    assert.ok(result.durationMs >= 0);
+    assert.deepEqual(result.warnings, []);
+  });
+
+  it('should report the last id of a multi-row insert', async () => {
+    assert.equal(db.executeSync('INSERT INTO rr (name) VALUES ($1), ($2)', ['b', 'c']).lastInsertRowId, 3);
+    assert.equal(db.executeSync('INSERT INTO rr VALUES (10, $1)', ['d']).lastInsertRowId, 10);
+    const stmt = db.prepare('INSERT INTO rr (name) VALUES ($1)');
+    assert.equal((await stmt.execute(['e'])).lastInsertRowId, 11);
+    assert.equal(stmt.executeSync(['f']).lastInsertRowId, 12);
+  });
+
+  it('should be null when no key was inserted', async () => {
+    assert.equal((await db.execute("UPDATE rr SET name = 'x' WHERE id = 1")).lastInsertRowId, null);
+    assert.equal(db.executeSync("INSERT INTO rr_nokey VALUES ('x')").lastInsertRowId, null);
+    assert.equal(db.executeBatchSync('INSERT INTO rr VALUES ($1, $2)', [[20, 'g'], [21, 'h']]).lastInsertRowId, null);
+    const tx = await db.begin();
+    const result = await tx.execute("UPDATE rr SET name = 'y' WHERE id = 1");
+    assert.equal(result.changes, 1);
+    assert.equal(result.lastInsertRowId, null);
+    assert.deepEqual(result.warnings, []);
+    await tx.commit();
+  });
+
+  it('should report ids while change listeners are registered', async () => {
+    const listened = await Database.open(':memory:');
+    await listened.exec('CREATE TABLE rr_listened (id INTEGER PRIMARY KEY AUTO_INCREMENT, name TEXT)');
+    const events = [];
+    listened.on('change', (change) => events.push(change));
+    const sql = 'INSERT INTO rr_listened (name) VALUES ($1), ($2)';
+    assert.equal((await listened.execute(sql, ['a', 'b'])).lastInsertRowId, 2);
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.deepEqual(events.map(e => e.rowId), [1, 2]);
+    await listened.close();
+  });
+});
+
+describe('autoAnalyze', () => {
+  const statsFor = async (db, table) => {
+    // _sys_table_stats doesn't exist until the first ANALYZE.
+    for (let i = 0; i < 50; i++) {
+      const rows = await db.query('SELECT row_count FROM _sys_table_stats WHERE table_name = $1', [table]).catch(() => []);
+      if (rows.length > 0) return rows[0].row_count;
+      await new Promise(resolve => setTimeout(resolve, 10));
+    }
+    return null;
+  };
+
+  it('should analyze a table once enough rows changed', async () => {
+    const db = await Database.open(':memory:', { autoAnalyze: { minChanges: 10 } });
+    await db.exec('CREATE TABLE aa (id INTEGER PRIMARY KEY, n INTEGER)');
+    for (let i = 1; i < 10; i++) await db.execute('INSERT INTO aa VALUES ($1, $1)', [i]);
+    await new Promise(resolve => setTimeout(resolve, 50));
+    await assert.rejects(db.query('SELECT * FROM _sys_table_stats'), /not found/);
+    db.executeSync('INSERT INTO aa VALUES (10, 10)');
+    assert.equal(await statsFor(db, 'aa'), 10);
+    await db.close();
+  });
+
+  it('should count transactions on commit and scale with the table', async () => {
+    const db = await Database.open(':memory:', { autoAnalyze: { minChanges: 5, threshold: 1 } });
+    await db.exec('CREATE TABLE ab (id INTEGER PRIMARY KEY, n INTEGER)');
+    db.executeBatchSync('INSERT INTO ab VALUES ($1, 0)', [[1], [2], [3], [4], [5]]);
+    assert.equal(await statsFor(db, 'ab'), 5);
+    // 1x the 5 rows analyzed must change before the next refresh.
+    const tx = await db.begin();
+    for (let id = 6; id <= 9; id++) await tx.execute('INSERT INTO ab VALUES ($1, 0)', [id]);
+    await tx.commit();
+    await db.execute('UPDATE ab SET n = 1 WHERE id = 1');
+    const refreshed = async () => {
+      for (let i = 0; i < 50; i++) {
+        if ((await statsFor(db, 'ab')) === 9) return true;
+        await new Promise(resolve => setTimeout(resolve, 10));
+      }
+      return false;
+    };
+    assert.ok(await refreshed());
+    await db.close();
+  });
+
+  it('should reject a negative threshold', () => {
+    assert.throws(() => Database.open(':memory:', { autoAnalyze: { threshold: -1 } }), /non-negative/);
+  });
+});
+
+describe('dedicatedThread', () => {
+  it('should settle async calls in the order they were made', async () => {
+    const db = await Database.open(':memory:', { dedicatedThread: true });
+    await db.exec("CREATE TABLE dt (id INTEGER PRIMARY KEY, s TEXT)");
+    await db.execute("INSERT INTO dt VALUES (1, '')");
+    const settled = [];
+    const calls = [];
+    for (let i = 0; i < 20; i++) {
+      calls.push(db.execute('UPDATE dt SET s = s || $1 WHERE id = 1', [String(i % 10)]).then(() => settled.push(i)));
+    }
+    calls.push(db.queryOne('SELECT s FROM dt').then((row) => settled.push(row.s)));
+    await Promise.all(calls);
+    assert.deepEqual(settled, [...Array(20).keys(), '01234567890123456789']);
+
+    const tx = await db.begin();
+    const stmt = db.prepare('SELECT s FROM dt WHERE id = $1');
+    await tx.execute("UPDATE dt SET s = 'tx' WHERE id = 1");
+    await tx.commit();
+    assert.deepEqual(await stmt.query([1]), [{ s: 'tx' }]);
+    await assert.rejects(db.query('SELECT * FROM missing'), /not found/);
+    await db.close();
+  });
+
+  it('should not wait for a busy libuv pool', async () => {
+    const { execFileSync } = await import('node:child_process');
+    const script = `
+      const { Database } = require(${JSON.stringify(require.resolve('../index.js'))});
+      const crypto = require('node:crypto');
+      (async () => {
+        const out = {};
+        for (const dedicatedThread of [false, true]) {
+          const db = await Database.open(':memory:', { dedicatedThread });
+          const order = [];
+          const hash = new Promise(resolve => crypto.pbkdf2('x', 'y', 200000, 32, 'sha256', () => resolve(order.push('hash'))));
+          const query = db.query('SELECT 1 AS one').then(() => order.push('query'));
+          await Promise.all([hash, query]);
+          out[dedicatedThread] = order;
+          await db.close();
+        }
+        process.stdout.write(JSON.stringify(out));
+      })();
+    `;
+    const out = JSON.parse(
+      execFileSync(process.execPath, ['-e', script], {
+        env: { ...process.env, UV_THREADPOOL_SIZE: '1' },
+        encoding: 'utf8',
+      })
+    );
+    assert.deepEqual(out, { false: ['hash', 'query'], true: ['query', 'hash'] });
+  });
+});
+
+describe('readers', () => {
+  it('should run concurrent queries on reader handles', async () => {
+    const db = await Database.open(':memory:', { readers: 3 });
+    await db.exec('CREATE TABLE rd (id INTEGER PRIMARY KEY, n INTEGER)');
+    const insert = db.prepare('INSERT INTO rd VALUES ($1, $2)');
+    for (let i = 1; i <= 200; i++) insert.executeSync([i, i % 7]);
+
+    const stmt = db.prepare('SELECT COUNT(*) AS c FROM rd WHERE n = $1');
+    const results = await Promise.all([
+      ...Array.from({ length: 7 }, (_, n) => db.queryOne('SELECT COUNT(*) AS c FROM rd WHERE n = $1', [n])),
+      ...Array.from({ length: 7 }, (_, n) => stmt.queryOne([n])),
+      db.queryRaw('SELECT id FROM rd WHERE id <= 2 ORDER BY id'),
+    ]);
+    const counts = results.slice(0, 14).map((row) => row.c);
+    assert.equal(counts.slice(0, 7).reduce((a, b) => a + b), 200);
+    assert.deepEqual(counts.slice(7), counts.slice(0, 7));
+    assert.deepEqual(results[14].rows, [[1], [2]]);
+    await db.close();
+  });
+
+  it('should see writes made through the main handle', async () => {
+    const db = await Database.open(':memory:', { readers: 2 });
+    await db.exec('CREATE TABLE rw (id INTEGER PRIMARY KEY, n INTEGER)');
+    await db.execute('INSERT INTO rw VALUES (1, 0)');
+    const read = () => Promise.all([1, 2, 3].map(() => db.queryOne('SELECT n FROM rw WHERE n >= 0')));
+    assert.deepEqual(await read(), [{ n: 0 }, { n: 0 }, { n: 0 }]);
+
+    const calls = [];
+    for (let i = 1; i <= 10; i++) {
+      calls.push(db.execute('UPDATE rw SET n = n + 1 WHERE id = 1'));
+      calls.push(db.query('SELECT n FROM rw WHERE n >= 0'));
+    }
+    await Promise.all(calls);
+    assert.deepEqual(await read(), [{ n: 10 }, { n: 10 }, { n: 10 }]);
+
+    const tx = await db.begin();
+    await tx.execute('UPDATE rw SET n = 20 WHERE id = 1');
+    await tx.commit();
+    db.executeSync('UPDATE rw SET n = n + 1 WHERE id = 1');
+    assert.deepEqual(await read(), [{ n: 21 }, { n: 21 }, { n: 21 }]);
+    await db.close();
+  });
+
+  it('should run statements that are not a single SELECT as writes', async () => {
+    const db = await Database.open(':memory:', { readers: 2 });
+    await db.exec('CREATE TABLE rq (id INTEGER PRIMARY KEY)');
+    await db.query('INSERT INTO rq VALUES (1)');
+    assert.deepEqual(await db.query('/* first */ SELECT id FROM rq'), [{ id: 1 }]);
+    await db.query('SELECT 1; INSERT INTO rq VALUES (2)');
+    assert.deepEqual(await db.query('SELECT id FROM rq WHERE id > 0 ORDER BY id'), [{ id: 1 }, { id: 2 }]);
+    await db.close();
+  });
+
+  it('should reject more than 64 readers', () => {
+    assert.throws(() => Database.open(':memory:', { readers: 65 }), /readers must be at most 64/);
+  });
+});
+
+describe('pipeline', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    await db.exec('CREATE TABLE pl (id INTEGER PRIMARY KEY, s TEXT)');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should run queued calls in order and resolve one result each', async () => {
+    const pipeline = db.pipeline();
+    assert.equal(
+      pipeline
+        .execute('INSERT INTO pl VALUES ($1, $2)', [1, 'a'])
+        .execute('INSERT INTO pl VALUES (:id, :s)', { id: 2, s: 'b' })
+        .query('SELECT * FROM pl WHERE id IN ($1) ORDER BY id', [[1, 2]]),
+      pipeline
+    );
+    assert.equal(pipeline.length, 3);
+    const [first, second, rows] = await pipeline.run();
+    assert.equal(first.changes, 1);
+    assert.equal(first.lastInsertRowId, 1);
+    assert.equal(second.lastInsertRowId, 2);
+    assert.deepEqual(rows, [{ id: 1, s: 'a' }, { id: 2, s: 'b' }]);
+    assert.equal(pipeline.length, 0);
+
+    pipeline.execute("UPDATE pl SET s = 'c' WHERE id = 1").query('SELECT s FROM pl WHERE id = 1');
+    const [update, after] = await pipeline.run();
+    assert.equal(update.changes, 1);
+    assert.deepEqual(after, [{ s: 'c' }]);
+    assert.deepEqual(await db.pipeline().run(), []);
+  });
+
+  it('should stop at the first failing step and keep earlier ones', async () => {
+    const run = db
+      .pipeline()
+      .execute("INSERT INTO pl VALUES (1, 'a')")
+      .query('SELECT * FROM missing_pl')
+      .execute("INSERT INTO pl VALUES (2, 'b')")
+      .run();
+    await assert.rejects(run, /^Error: Pipeline step 2: .*not found/);
+    assert.deepEqual(db.querySync('SELECT id FROM pl'), [{ id: 1 }]);
+  });
+
+  it('should check the policy when calls are queued', () => {
+    db.setPolicy({ deny: ['DELETE'] });
+    const pipeline = db.pipeline();
+    assert.throws(() => pipeline.execute('DELETE FROM pl'), /DELETE/);
+    assert.equal(pipeline.length, 0);
+  });
+
+  it('should cancel unfinished steps on interrupt', async () => {
+    const values = [];
+    for (let i = 0; i < 20000; i++) values.push(`(${i}, 'v${i}')`);
+    db.execSync(`CREATE TABLE pl_big (id INTEGER PRIMARY KEY, v TEXT); INSERT INTO pl_big VALUES ${values.join(', ')}`);
+    const run = db
+      .pipeline()
+      .query('SELECT * FROM pl_big ORDER BY v DESC')
+      .execute("INSERT INTO pl VALUES (1, 'a')")
+      .run();
+    db.interrupt();
+    await assert.rejects(run, /Pipeline step 1: Interrupted/);
+    assert.deepEqual(db.querySync('SELECT id FROM pl'), []);
+  });
+});
+
+describe('task events', () => {
+  it('should report enqueue, dequeue and complete for each async call', async () => {
+    const db = await Database.open(':memory:');
+    const values = [];
+    for (let i = 0; i < 20000; i++) values.push(`(${i}, 'v${i}')`);
+    db.execSync(`CREATE TABLE tq_big (id INTEGER PRIMARY KEY, v TEXT); INSERT INTO tq_big VALUES ${values.join(', ')}`);
+    const events = [];
+    db.on('task', (event) => events.push(event));
+
+    const sorted = db.query('SELECT * FROM tq_big ORDER BY v DESC');
+    const { queued, running } = db.taskQueue;
+    assert.equal(queued + running, 1);
+    await sorted;
+    await db.execute('SELECT * FROM missing_tq').catch(() => {});
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.deepEqual({ ...db.taskQueue }, { queued: 0, running: 0 });
+
+    assert.deepEqual(
+      events.map(({ phase, method, queued, running, ok }) => [phase, method, queued, running, ok]),
+      [
+        ['enqueue', 'query', 1, 0, true],
+        ['dequeue', 'query', 0, 1, true],
+        ['complete', 'query', 0, 0, true],
+        ['enqueue', 'execute', 1, 0, true],
+        ['dequeue', 'execute', 0, 1, true],
+        ['complete', 'execute', 0, 0, false],
+      ]
+    );
+    assert.equal(events[0].waitMs, 0);
+    assert.ok(events[2].waitMs >= 0 && events[2].runMs > 0);
+    assert.equal(events[1].runMs, 0);
+    await db.close();
+  });
+
+  it('should count statements, transactions and pipelines of the database', async () => {
+    const db = await Database.open(':memory:', { dedicatedThread: true });
+    const methods = [];
+    db.on('task', (event) => event.phase === 'dequeue' && methods.push(event.method));
+    await db.exec('CREATE TABLE tq (id INTEGER PRIMARY KEY)');
+    const stmt = db.prepare('INSERT INTO tq VALUES ($1)');
+    const tx = await db.begin();
+    await Promise.all([stmt.execute([1]), tx.query('SELECT 1'), tx.commit(), db.pipeline().query('SELECT 1').run()]);
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.deepEqual(methods, ['exec', 'begin', 'execute', 'query', 'commit', 'pipeline']);
+    assert.deepEqual({ ...db.taskQueue }, { queued: 0, running: 0 });
+    await db.close();
+  });
+
+  it('should reject options for task listeners', async () => {
+    const db = await Database.open(':memory:');
+    assert.throws(() => db.on('task', () => {}, { thresholdMs: 1 }), /only supported for 'profile'/);
+    await db.close();
+  });
+});
+
+describe('executeBatch', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.exec('CREATE TABLE eb (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should insert every param set in one async call', async () => {
+    const result = await db.executeBatch('INSERT INTO eb VALUES ($1, $2)', [[1, 'a'], [2, 'b'], [3, 'c']]);
+    assert.equal(result.changes, 3);
+    assert.equal(result.lastInsertRowId, null);
+    assert.deepEqual(await db.query('SELECT id FROM eb ORDER BY id'), [{ id: 1 }, { id: 2 }, { id: 3 }]);
+  });
+
+  it('should roll back the whole batch when a row fails', async () => {
+    await assert.rejects(db.executeBatch('INSERT INTO eb VALUES ($1, $2)', [[10, 'x'], [1, 'dup']]));
+    assert.equal(await db.queryOne('SELECT * FROM eb WHERE id = 10'), null);
+  });
+
+  it('should convert params before returning the promise', () => {
+    assert.throws(() => db.executeBatch('INSERT INTO eb VALUES ($1, $2)', 'nope'), /paramsArray must be an array/);
+    assert.throws(() => db.executeBatch('INSERT INTO eb VALUES ($1, $2)', [[20, 'y'], 'nope']), /array/);
+  });
+
+  it('should run with a prepared statement and in a transaction', async () => {
+    const update = db.prepare('UPDATE eb SET name = $1 WHERE id = $2');
+    assert.equal((await update.executeBatch([['A', 1], ['B', 2]])).changes, 2);
+
+    const tx = await db.begin();
+    assert.equal((await tx.executeBatch('DELETE FROM eb WHERE id = $1', [[1], [2]])).changes, 2);
+    assert.equal((await db.query('SELECT id FROM eb')).length, 3);
+    await tx.commit();
+    assert.deepEqual(await db.query('SELECT id, name FROM eb'), [{ id: 3, name: 'c' }]);
+  });
+
+  it('should emit change events for the batch', async () => {
+    const events = [];
+    db.on('change', (event) => events.push(event));
+    await db.executeBatch('INSERT INTO eb VALUES ($1, $2)', [[30, 'p'], [31, 'q']]);
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.deepEqual(events.filter(e => e.table === 'eb').map(e => [e.op, e.rowId]), [['insert', 30], ['insert', 31]]);
+  });
+
+  it('should be interrupted between rows and roll back', async () => {
+    db.execSync('CREATE TABLE eb_big (id INTEGER PRIMARY KEY)');
+    const rows = [];
+    for (let i = 0; i < 20000; i++) rows.push([i]);
+    const run = db.executeBatch('INSERT INTO eb_big VALUES ($1)', rows);
+    db.interrupt();
+    await assert.rejects(run, /Interrupted/);
+    assert.deepEqual(await db.query('SELECT id FROM eb_big'), []);
+  });
+});
+
+describe('Multiple result sets', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should return one result per statement from exec()', async () => {
+    const results = await db.exec(`
+      CREATE TABLE mr (id INTEGER PRIMARY KEY, name TEXT);
+      INSERT INTO mr VALUES (1, 'a'), (2, 'b');
+      SELECT id, name FROM mr ORDER BY id;
+      -- trailing comment
+    `);
+    assert.deepEqual(results, [
+      { statement: 'CREATE TABLE mr (id INTEGER PRIMARY KEY, name TEXT)', changes: 0 },
+      { statement: "INSERT INTO mr VALUES (1, 'a'), (2, 'b')", changes: 2 },
+      { statement: 'SELECT id, name FROM mr ORDER BY id', columns: ['id', 'name'], rows: [[1, 'a'], [2, 'b']] },
+    ]);
+  });
+
+  it('should return RETURNING and SHOW rows from queryMulti()', async () => {
+    const results = await db.queryMulti("UPDATE mr SET name = 'c' WHERE id = 1 RETURNING id, name; DELETE FROM mr WHERE id = 2; SHOW TABLES");
+    assert.deepEqual(results[0], { statement: "UPDATE mr SET name = 'c' WHERE id = 1 RETURNING id, name", columns: ['id', 'name'], rows: [[1, 'c']] });
+    assert.deepEqual(results[1], { statement: 'DELETE FROM mr WHERE id = 2', changes: 1 });
+    assert.deepEqual(results[2].columns, ['table_name']);
+    assert.ok(results[2].rows.some(([name]) => name === 'mr'));
+  });
+
+  it('should return results from execSync() and queryMultiSync()', () => {
+    assert.deepEqual(db.execSync("INSERT INTO mr VALUES (3, 'x')"), [
+      { statement: "INSERT INTO mr VALUES (3, 'x')", changes: 1 },
+    ]);
+    assert.deepEqual(db.queryMultiSync('SELECT COUNT(*) AS n FROM mr; SELECT MAX(id) AS m FROM mr'), [
+      { statement: 'SELECT COUNT(*) AS n FROM mr', columns: ['n'], rows: [[2]] },
+      { statement: 'SELECT MAX(id) AS m FROM mr', columns: ['m'], rows: [[3]] },
+    ]);
+  });
+
+  it('should keep change events for RETURNING statements', async () => {
+    const events = [];
+    db.on('change', (event) => events.push(event));
+    await db.exec("INSERT INTO mr VALUES (4, 'r') RETURNING id; DELETE FROM mr WHERE id = 4");
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.deepEqual(events.filter(e => e.table === 'mr').map(e => e.op), ['insert', 'delete']);
+  });
+});
+
+describe('serializeWrites', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:', { serializeWrites: ['SW_Hot'] });
+    await db.exec(`
+      CREATE TABLE sw_hot (id INTEGER PRIMARY KEY, n INTEGER);
+      CREATE TABLE sw_cold (id INTEGER PRIMARY KEY, n INTEGER);
+      INSERT INTO sw_hot VALUES (1, 0);
+      INSERT INTO sw_cold VALUES (1, 0);
+    `);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should make a write wait for a transaction instead of conflicting', async () => {
+    const tx = await db.begin();
+    await tx.execute('UPDATE sw_hot SET n = n + 1 WHERE id = 1');
+    const done = db.execute('UPDATE sw_hot SET n = n + 1 WHERE id = 1');
+    await new Promise(resolve => setTimeout(resolve, 20));
+    const [waiting] = db.writeQueue;
+    assert.equal(waiting.table, 'sw_hot');
+    assert.equal(waiting.locked, true);
+    assert.equal(waiting.waiting, 1);
+
+    await tx.commit();
+    assert.equal((await done).changes, 1);
+    assert.deepEqual(await db.query('SELECT n FROM sw_hot'), [{ n: 2 }]);
+    const [stats] = db.writeQueue;
+    assert.equal(stats.locked, false);
+    assert.equal(stats.waiting, 0);
+    assert.ok(stats.waits >= 1);
+    assert.ok(stats.maxWaitMs >= 10);
+    assert.ok(stats.totalWaitMs >= stats.maxWaitMs);
+  });
+
+  it('should release the table on rollback and pass errors through', async () => {
+    const tx = await db.begin();
+    await tx.executeBatch('UPDATE sw_hot SET n = $1 WHERE id = 1', [[10]]);
+    const failing = assert.rejects(db.execute('INSERT INTO sw_hot VALUES (1, 0)'), /primary key/);
+    const pipeline = db.pipeline().execute('UPDATE sw_hot SET n = n + 1 WHERE id = 1').query('SELECT n FROM sw_hot').run();
+    await tx.rollback();
+    await failing;
+    const [, rows] = await pipeline;
+    assert.deepEqual(rows, [{ n: 3 }]);
+    assert.equal(db.writeQueue[0].locked, false);
+  });
+
+  it('should not queue writes to other tables or queries', async () => {
+    const tx = await db.begin();
+    await tx.execute('UPDATE sw_hot SET n = 0 WHERE id = 1');
+    assert.deepEqual(await db.query('SELECT n FROM sw_hot'), [{ n: 3 }]);
+    assert.equal((await db.execute('UPDATE sw_cold SET n = 1 WHERE id = 1')).changes, 1);
+    assert.equal(db.writeQueue[0].waiting, 0);
+    await tx.commit();
+    assert.deepEqual(db.writeQueue.map(s => s.table), ['sw_hot']);
+  });
+
+  it('should report no tables when the option is not set', async () => {
+    const plain = await Database.open(':memory:');
+    assert.deepEqual(plain.writeQueue, []);
+    await plain.close();
+  });
+});
+
+describe('memoryBudget', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:', { memoryBudget: 0.05 });
+    await db.execute('CREATE TABLE mb_rows (id INTEGER PRIMARY KEY, s TEXT)');
+    const rows = [];
+    for (let i = 0; i < 2000; i++) rows.push([i, 'x'.repeat(100)]);
+    await db.executeBatch('INSERT INTO mb_rows VALUES ($1, $2)', rows);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should reject queries whose results exceed the budget', async () => {
+    await assert.rejects(db.query('SELECT * FROM mb_rows'), /exceed the memory budget of 0.05 MB/);
+    await assert.rejects(db.queryRaw('SELECT * FROM mb_rows'), /memory budget/);
+    await assert.rejects(db.exec('SELECT * FROM mb_rows'), /memory budget/);
+    assert.equal((await db.query('SELECT * FROM mb_rows LIMIT 10')).length, 10);
+    const stats = db.memoryBudget;
+    assert.equal(stats.rejected, 3);
+    assert.equal(stats.usedMb, 0);
+    assert.ok(Math.abs(stats.limitMb - 0.05) < 1e-6);
+  });
+
+  it('should change and remove the budget at runtime', async () => {
+    db.setMemoryBudget(null);
+    assert.equal(db.memoryBudget.limitMb, undefined);
+    assert.equal((await db.query('SELECT * FROM mb_rows')).length, 2000);
+    db.setMemoryBudget(0.01);
+    const tx = await db.begin();
+    await assert.rejects(tx.query('SELECT * FROM mb_rows'), /memory budget/);
+    await tx.rollback();
+    db.setMemoryBudget(0.05);
+  });
+
+  it('should reject invalid budgets', () => {
+    assert.throws(() => db.setMemoryBudget(0), /positive number of megabytes/);
+    assert.throws(() => Database.open(':memory:', { memoryBudget: -1 }), /positive number of megabytes/);
+  });
+});
+
+describe('execute() with RETURNING', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE ret (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should return the rows and count them as changes', async () => {
+    const result = await db.execute("INSERT INTO ret VALUES (1, 'a'), (2, 'b') RETURNING id, name");
+    assert.equal(result.changes, 2);
+    assert.deepEqual(result.rows, [{ id: 1, name: 'a' }, { id: 2, name: 'b' }]);
+    const updated = db.executeSync('UPDATE ret SET name = UPPER(name) WHERE id > $1 RETURNING id, name', [1]);
+    assert.equal(updated.changes, 1);
+    assert.deepEqual(updated.rows, [{ id: 2, name: 'B' }]);
+  });
+
+  it('should return rows from prepared statements and pipelines', async () => {
+    const stmt = db.prepare('DELETE FROM ret WHERE id = $1 RETURNING name');
+    assert.deepEqual((await stmt.execute([1])).rows, [{ name: 'a' }]);
+    const none = stmt.executeSync([1]);
+    assert.equal(none.changes, 0);
+    assert.deepEqual(none.rows, []);
+    const [run] = await db.pipeline().execute("INSERT INTO ret VALUES (3, 'c') RETURNING id").run();
+    assert.deepEqual(run.rows, [{ id: 3 }]);
+  });
+
+  it('should leave rows out for statements without RETURNING', async () => {
+    const result = await db.execute("INSERT INTO ret VALUES (4, 'd')");
+    assert.equal(result.rows, undefined);
+    assert.equal(result.lastInsertRowId, 4);
+  });
+
+  it('should emit change events for RETURNING statements', async () => {
+    const events = [];
+    db.on('change', (event) => events.push(event));
+    await db.execute('DELETE FROM ret WHERE id >= 3 RETURNING id');
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.deepEqual(events.filter(e => e.table === 'ret').map(e => e.op), ['delete', 'delete']);
+  });
+});
+
+describe('lowMemory query option', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE lm (id INTEGER PRIMARY KEY, name TEXT, score FLOAT, tags TEXT)');
+    const rows = [];
+    for (let i = 1; i <= 300; i++) {
+      rows.push([i, `name-${i}`, i / 4, i % 3 === 0 ? null : `t${i % 7}`]);
+    }
+    db.executeBatchSync('INSERT INTO lm VALUES ($1, $2, $3, $4)', rows);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should return the same rows as a normal query', async () => {
+    const sql = 'SELECT * FROM lm ORDER BY id';
+    const expected = await db.query(sql);
+    assert.equal(expected.length, 300);
+    assert.deepEqual(await db.query(sql, [], { lowMemory: true }), expected);
+    assert.deepEqual(db.querySync(sql, null, { lowMemory: true }), expected);
+  });
+
+  it('should return the same raw results as a normal query', async () => {
+    const sql = 'SELECT id, name FROM lm WHERE id <= $1 ORDER BY id';
+    const expected = db.queryRawSync(sql, [50]);
+    assert.deepEqual(await db.queryRaw(sql, [50], { lowMemory: true }), expected);
+    assert.deepEqual(db.queryRawSync(sql, [50], { lowMemory: true }), expected);
+    assert.deepEqual(await db.queryRaw('SELECT id FROM lm WHERE id < 0', [], { lowMemory: true }), {
+      columns: ['id'],
+      rows: [],
+    });
+  });
+
+  it('should apply to pipeline queries', async () => {
+    const [rows, all] = await db.pipeline()
+      .query('SELECT id FROM lm WHERE id <= 3 ORDER BY id', [], { lowMemory: true })
+      .query('SELECT COUNT(*) AS n FROM lm')
+      .run();
+    assert.deepEqual(rows, [{ id: 1 }, { id: 2 }, { id: 3 }]);
+    assert.deepEqual(all, [{ n: 300 }]);
+  });
+
+  it('should release its rows from the memory budget', async () => {
+    db.setMemoryBudget(16);
+    try {
+      const rows = await db.query('SELECT * FROM lm', [], { lowMemory: true });
+      assert.equal(rows.length, 300);
+      assert.equal(db.memoryBudget.usedMb, 0);
+    } finally {
+      db.setMemoryBudget(null);
+    }
+  });
+
+  it('should combine with asOf', async () => {
+    const before_update = new Date();
+    await new Promise(resolve => setTimeout(resolve, 10));
+    await db.execute("UPDATE lm SET name = 'changed' WHERE id = 1");
+    const [row] = await db.query('SELECT name FROM lm WHERE id = 1', [], {
+      asOf: before_update,
+      lowMemory: true,
+    });
+    assert.equal(row.name, 'name-1');
+  });
+});
+
+describe('PreparedStatement parameters', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE params (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should report positional parameters', () => {
+    assert.equal(db.prepare('INSERT INTO params VALUES ($1, $2)').parameterCount, 2);
+    assert.equal(db.prepare('SELECT * FROM params WHERE id = ? OR id = ?').parameterCount, 2);
+    assert.equal(db.prepare('SELECT * FROM params WHERE id = $2 OR id = $1').parameterCount, 2);
+    const none = db.prepare('SELECT * FROM params');
+    assert.equal(none.parameterCount, 0);
+    assert.deepEqual(none.parameterNames, []);
+  });
+
+  it('should report named parameters in order of first use', () => {
+    const stmt = db.prepare('SELECT * FROM params WHERE id = :id OR name = :name OR id = :id');
+    assert.equal(stmt.parameterCount, 2);
+    assert.deepEqual(stmt.parameterNames, ['id', 'name']);
+  });
+
+  it('should reject the wrong number of positional parameters', async () => {
+    const insert = db.prepare('INSERT INTO params VALUES ($1, $2)');
+    assert.throws(() => insert.executeSync([1]), /Statement requires 2 parameters, got 1/);
+    assert.throws(() => insert.executeSync([1, 'a', 'extra']), /got 3/);
+    assert.throws(() => insert.execute(), /got 0/);
+    assert.throws(() => db.prepare('SELECT * FROM params WHERE id = ?').querySync([]), /requires 1 parameter, got 0/);
+    assert.equal(db.querySync('SELECT * FROM params').length, 0);
+  });
+
+  it('should check every row of a batch', async () => {
+    const insert = db.prepare('INSERT INTO params VALUES ($1, $2)');
+    assert.throws(() => insert.executeBatchSync([[1, 'a'], [2]]), /paramsArray\[1\]: Statement requires 2 parameters, got 1/);
+    await assert.rejects(async () => insert.executeBatch([[1]]), /paramsArray\[0\]/);
+    assert.equal(insert.executeBatchSync([[1, 'a'], [2, 'b']]).changes, 2);
+  });
+
+  it('should count an IN list array as one parameter', () => {
+    const stmt = db.prepare('SELECT id FROM params WHERE id IN (?) AND name <> ? ORDER BY id');
+    assert.equal(stmt.parameterCount, 2);
+    assert.deepEqual(stmt.querySync([[1, 2], 'x']), [{ id: 1 }, { id: 2 }]);
+  });
+
+  it('should explain without parameters', () => {
+    const stmt = db.prepare('UPDATE params SET name = $1 WHERE id = $2');
+    assert.equal(stmt.explainSync().plan.operation, 'UPDATE params');
+    assert.throws(() => stmt.explainAnalyzeSync(['x']), /requires 2 parameters/);
+  });
+});
+
+describe('statementReport', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE usage (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should count executions per SQL text', async () => {
+    const started = Date.now();
+    const insert = db.prepare('INSERT INTO usage VALUES ($1, $2)');
+    insert.executeSync([1, 'a']);
+    await insert.execute([2, 'b']);
+    db.prepare('INSERT INTO usage VALUES ($1, $2)').executeBatchSync([[3, 'c'], [4, 'd'], [5, 'e']]);
+    const lookup = db.prepare('SELECT * FROM usage WHERE id = $1');
+    lookup.queryOneSync([1]);
+    await lookup.query([2]);
+    lookup.explainSync();
+
+    const report = db.statementReport();
+    const [first, second] = report;
+    assert.equal(first.sql, 'INSERT INTO usage VALUES ($1, $2)');
+    assert.equal(first.prepared, 2);
+    assert.equal(first.executions, 5);
+    assert.ok(first.preparedAt >= started && first.preparedAt <= Date.now());
+    assert.ok(first.lastUsedAt >= first.preparedAt);
+    assert.equal(second.sql, 'SELECT * FROM usage WHERE id = $1');
+    assert.equal(second.executions, 2);
+  });
+
+  it('should list statements that were never run', () => {
+    const stmt = db.prepare('DELETE FROM usage WHERE id = $1');
+    stmt.finalize();
+    const entry = db.statementReport().find(e => e.sql === 'DELETE FROM usage WHERE id = $1');
+    assert.equal(entry.executions, 0);
+    assert.equal(entry.lastUsedAt, null);
+  });
+
+  it('should not count calls rejected before they bind', () => {
+    const stmt = db.prepare('SELECT name FROM usage WHERE id = $1 AND name = $2');
+    assert.throws(() => stmt.querySync([1]), /requires 2 parameters/);
+    const entry = db.statementReport().find(e => e.sql === stmt.sql);
+    assert.equal(entry.executions, 0);
+  });
+
+  it('should filter by time since last use', async () => {
+    const recent = db.prepare('SELECT COUNT(*) AS n FROM usage');
+    recent.querySync();
+    assert.equal(db.statementReport({ unusedForMs: 60000 }).length, 0);
+    await new Promise(resolve => setTimeout(resolve, 30));
+    recent.querySync();
+    const idle = db.statementReport({ unusedForMs: 20 }).map(e => e.sql);
+    assert.ok(idle.includes('DELETE FROM usage WHERE id = $1'));
+    assert.ok(!idle.includes('SELECT COUNT(*) AS n FROM usage'));
+  });
+});
+
+describe('pluck and expand', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE shape_users (id INTEGER PRIMARY KEY, name TEXT)');
+    await db.execute('CREATE TABLE shape_orders (id INTEGER PRIMARY KEY, user_id INTEGER, name TEXT)');
+    await db.execute("INSERT INTO shape_users VALUES (1, 'ann'), (2, 'bob')");
+    await db.execute("INSERT INTO shape_orders VALUES (10, 1, 'book'), (11, 2, 'pen')");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should pluck the first column', async () => {
+    const stmt = db.prepare('SELECT name, id FROM shape_users WHERE id >= $1 ORDER BY id').pluck();
+    assert.deepEqual(stmt.querySync([1]), ['ann', 'bob']);
+    assert.deepEqual(await stmt.query([1]), ['ann', 'bob']);
+    assert.equal(stmt.queryOneSync([2]), 'bob');
+    assert.equal(await stmt.queryOne([2]), 'bob');
+    assert.equal(stmt.queryOneSync([3]), null);
+    assert.deepEqual(stmt.queryRawSync([2]), { columns: ['name', 'id'], rows: [['bob', 2]] });
+  });
+
+  it('should expand joined columns by table', async () => {
+    const stmt = db.prepare(`
+      SELECT u.id, u.name, o.name, COUNT(*) AS n
+      FROM shape_users u JOIN shape_orders o ON o.user_id = u.id
+      GROUP BY u.id, u.name, o.name ORDER BY u.id
+    `).expand();
+    const expected = [
+      { u: { id: 1, name: 'ann' }, o: { name: 'book' }, $: { n: 1 } },
+      { u: { id: 2, name: 'bob' }, o: { name: 'pen' }, $: { n: 1 } },
+    ];
+    assert.deepEqual(stmt.querySync(), expected);
+    assert.deepEqual(await stmt.query(), expected);
+    assert.deepEqual(await stmt.queryOne(), expected[0]);
+  });
+
+  it('should place star and unqualified columns', () => {
+    const star = db.prepare(
+      'SELECT * FROM shape_users JOIN shape_orders ON shape_orders.user_id = shape_users.id ORDER BY shape_users.id'
+    ).expand();
+    assert.deepEqual(star.queryOneSync(), {
+      shape_users: { id: 1, name: 'ann' },
+      shape_orders: { id: 10, user_id: 1, name: 'book' },
+    });
+    const bare = db.prepare(
+      'SELECT x.name, user_id FROM shape_users AS x JOIN shape_orders ON user_id = x.id ORDER BY x.id'
+    ).expand();
+    assert.deepEqual(bare.queryOneSync(), { x: { name: 'ann' }, shape_orders: { user_id: 1 } });
+  });
+
+  it('should toggle modes off and replace each other', () => {
+    const stmt = db.prepare('SELECT id, name FROM shape_users ORDER BY id');
+    assert.equal(stmt.pluck(), stmt);
+    assert.equal(stmt.expand(), stmt);
+    assert.deepEqual(stmt.queryOneSync(), { shape_users: { id: 1, name: 'ann' } });
+    stmt.pluck(false);
+    assert.deepEqual(stmt.queryOneSync(), { shape_users: { id: 1, name: 'ann' } });
+    stmt.expand(false);
+    assert.deepEqual(stmt.queryOneSync(), { id: 1, name: 'ann' });
+  });
+
+  it('should reject statements that return no rows', () => {
+    assert.throws(
+      () => db.prepare('UPDATE shape_users SET name = $1 WHERE id = 1').pluck(),
+      /only for statements that return data/
+    );
+    const returning = db.prepare("UPDATE shape_users SET name = 'ann' WHERE id = 1 RETURNING id").pluck();
+    assert.deepEqual(returning.querySync(), [1]);
+  });
+});
+
+describe('Transaction maxDurationMs', () => {
+  let db;
+  const timeouts = [];
+
+  before(async () => {
+    db = await Database.open(':memory:', { serializeWrites: ['tto_hot'] });
+    await db.execute('CREATE TABLE tto_hot (id INTEGER PRIMARY KEY, n INTEGER)');
+    db.on('transactionTimeout', event => timeouts.push(event));
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should roll back a transaction held open too long', async () => {
+    const tx = await db.begin({ maxDurationMs: 30 });
+    await tx.execute('INSERT INTO tto_hot VALUES (1, 1)');
+    // Waits for the table the transaction holds, until the rollback frees it
+    await db.execute('INSERT INTO tto_hot VALUES (2, 2)');
+    assert.equal(tx.active, false);
+    await assert.rejects(tx.commit(), /no longer active/);
+    assert.deepEqual(await db.query('SELECT id FROM tto_hot'), [{ id: 2 }]);
+    await new Promise(resolve => setTimeout(resolve, 20));
+    assert.equal(timeouts.length, 1);
+    assert.equal(timeouts[0].maxDurationMs, 30);
+    assert.ok(timeouts[0].durationMs >= 30);
+  });
+
+  it('should leave transactions that end in time alone', async () => {
+    const tx = db.beginSync({ maxDurationMs: 20 });
+    tx.executeSync('INSERT INTO tto_hot VALUES (3, 3)');
+    tx.commitSync();
+    await new Promise(resolve => setTimeout(resolve, 50));
+    assert.equal(timeouts.length, 1);
+    assert.equal((await db.queryOne('SELECT COUNT(*) AS n FROM tto_hot')).n, 2);
+  });
+
+  it('should reject invalid durations', () => {
+    assert.throws(() => db.beginSync({ maxDurationMs: 0 }), /maxDurationMs must be a positive number/);
+    assert.throws(() => db.beginSync({ maxDurationMs: Infinity }), /maxDurationMs must be a positive number/);
+  });
+});
+
+describe('idleTransactionMs', () => {
+  let db;
+  const idle = [];
+
+  before(async () => {
+    db = await Database.open(':memory:', { idleTransactionMs: 30 });
+    await db.execute('CREATE TABLE idle_t (id INTEGER PRIMARY KEY)');
+    db.on('transactionIdle', event => idle.push(event));
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should warn about an idle transaction with the stack of begin()', async () => {
+    async function beginAndForget() {
+      return db.begin();
+    }
+    const tx = await beginAndForget();
+    await tx.execute('INSERT INTO idle_t VALUES (1)');
+    await new Promise(resolve => setTimeout(resolve, 80));
+    assert.equal(idle.length, 1);
+    assert.ok(idle[0].idleMs >= 30);
+    assert.ok(idle[0].openMs >= idle[0].idleMs);
+    assert.match(idle[0].stack, /beginAndForget/);
+    assert.equal(tx.active, true);
+
+    // Warns again only after new activity
+    await tx.query('SELECT * FROM idle_t');
+    await new Promise(resolve => setTimeout(resolve, 80));
+    assert.equal(idle.length, 2);
+    await tx.rollback();
+  });
+
+  it('should not warn about transactions that keep working or end', async () => {
+    const count = idle.length;
+    const tx = db.beginSync();
+    for (let i = 0; i < 5; i++) {
+      tx.executeSync('INSERT INTO idle_t VALUES ($1)', [10 + i]);
+      await new Promise(resolve => setTimeout(resolve, 10));
+    }
+    tx.commitSync();
+    await new Promise(resolve => setTimeout(resolve, 60));
+    assert.equal(idle.length, count);
+  });
+
+  it('should reject invalid durations', () => {
+    assert.throws(
+      () => Database.open(':memory:', { idleTransactionMs: -1 }),
+      /idleTransactionMs must be a positive number/
+    );
+  });
+});
+
+describe('asyncStackTraces', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:', {
+      asyncStackTraces: true,
+      serializeWrites: ['ast_hot'],
+    });
+    await db.execute('CREATE TABLE ast_hot (id INTEGER PRIMARY KEY)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should append the call site to errors of async calls', async () => {
+    async function loadMissing() {
+      return db.query('SELECT * FROM ast_missing');
+    }
+    const err = await loadMissing().catch(e => e);
+    assert.ok(err instanceof Error);
+    assert.match(err.message, /ast_missing/);
+    assert.match(err.message, /\n\s+at loadMissing /);
+    assert.match(err.stack, /at loadMissing /);
+
+    function runPrepared() {
+      return db.prepare('INSERT INTO ast_hot VALUES ($1)').execute([1]).then(() =>
+        db.prepare('INSERT INTO ast_hot VALUES ($1)').execute([1])
+      );
+    }
+    await assert.rejects(runPrepared(), /\n\s+at .*index\.spec\.mjs/);
+  });
+
+  it('should keep the stack of a serialized write that waited', async () => {
+    const tx = await db.begin();
+    await tx.execute('INSERT INTO ast_hot VALUES (2)');
+    function insertDuplicate() {
+      return db.execute('INSERT INTO ast_hot VALUES (2)');
+    }
+    const rejected = assert.rejects(insertDuplicate(), /\n\s+at insertDuplicate /);
+    await tx.commit();
+    await rejected;
+  });
+
+  it('should leave errors alone without the option', async () => {
+    const plain = await Database.open(':memory:');
+    async function loadMissing() {
+      return plain.query('SELECT * FROM ast_missing');
+    }
+    const err = await loadMissing().catch(e => e);
+    assert.match(err.message, /ast_missing/);
+    assert.doesNotMatch(err.message, /loadMissing/);
+    await plain.close();
+  });
+});
+
+describe('columnCase', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:', { columnCase: 'camel' });
+    await db.execute(
+      'CREATE TABLE cc_users (user_id INTEGER PRIMARY KEY, first_name TEXT, _row_flag BOOLEAN)'
+    );
+    await db.execute("INSERT INTO cc_users VALUES (1, 'Alice', true)");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should convert column names of database and transaction results', async () => {
+    assert.deepEqual(await db.query('SELECT * FROM cc_users'), [
+      { userId: 1, firstName: 'Alice', _rowFlag: true },
+    ]);
+    assert.deepEqual(db.queryOneSync('SELECT user_id, COUNT(*) FROM cc_users GROUP BY user_id'), {
+      userId: 1,
+      'COUNT(*)': 1,
+    });
+    const raw = await db.queryRaw('SELECT user_id, first_name FROM cc_users');
+    assert.deepEqual(raw.columns, ['userId', 'firstName']);
+    const run = await db.execute(
+      "INSERT INTO cc_users VALUES (2, 'Bob', false) RETURNING user_id, first_name"
+    );
+    assert.deepEqual(run.rows, [{ userId: 2, firstName: 'Bob' }]);
+    const [script] = await db.exec('SELECT first_name FROM cc_users WHERE user_id = 1');
+    assert.deepEqual(script.columns, ['firstName']);
+
+    const tx = await db.begin();
+    assert.deepEqual(await tx.queryOne('SELECT first_name FROM cc_users WHERE user_id = 2'), {
+      firstName: 'Bob',
+    });
+    assert.deepEqual(tx.querySync('SELECT user_id FROM cc_users WHERE user_id = 1'), [
+      { userId: 1 },
+    ]);
+    await tx.rollback();
+
+    const [rows] = await db.pipeline().query('SELECT first_name FROM cc_users WHERE user_id = 1').run();
+    assert.deepEqual(rows, [{ firstName: 'Alice' }]);
+  });
+
+  it('should let a prepared statement override the database setting', async () => {
+    const stmt = db.prepare('SELECT user_id, first_name FROM cc_users WHERE user_id = 1');
+    assert.deepEqual(stmt.queryOneSync(), { userId: 1, firstName: 'Alice' });
+    assert.equal(stmt.columnCase('asIs'), stmt);
+    assert.deepEqual(await stmt.queryOne(), { user_id: 1, first_name: 'Alice' });
+
+    const expanded = db
+      .prepare('SELECT c.user_id, c.first_name FROM cc_users c WHERE c.user_id = 1')
+      .expand();
+    assert.deepEqual(expanded.queryOneSync(), { c: { userId: 1, firstName: 'Alice' } });
+  });
+
+  it('should convert camelCase names to snake_case', async () => {
+    const stmt = db.prepare('SELECT user_id AS "userId", first_name AS "HTTPName" FROM cc_users WHERE user_id = 1');
+    assert.deepEqual(stmt.columnCase('snake').queryOneSync(), { user_id: 1, http_name: 'Alice' });
+  });
+
+  it('should reject unknown cases', () => {
+    assert.throws(
+      () => Database.open(':memory:', { columnCase: 'pascal' }),
+      /Invalid columnCase 'pascal'/
+    );
+    assert.throws(() => db.prepare('SELECT 1').columnCase('upper'), /Invalid columnCase/);
+  });
+});
+
+describe('error cause', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE ec_users (id INTEGER PRIMARY KEY, email TEXT UNIQUE)');
+    await db.execute("INSERT INTO ec_users VALUES (1, 'a@example.com')");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should carry the engine error kind and fields on async errors', async () => {
+    await assert.rejects(db.execute("INSERT INTO ec_users VALUES (1, 'b@example.com')"), (err) => {
+      assert.ok(err.cause instanceof Error);
+      assert.equal(err.cause.kind, 'PrimaryKeyConstraint');
+      assert.equal(err.cause.rowId, 1);
+      assert.equal(err.cause.message, err.message);
+      return true;
+    });
+    await assert.rejects(db.query('SELECT * FROM ec_missing'), (err) => {
+      assert.equal(err.cause.kind, 'TableOrViewNotFound');
+      assert.equal(err.cause.table, 'ec_missing');
+      return true;
+    });
+  });
+
+  it('should carry them on sync errors', () => {
+    assert.throws(
+      () => db.executeSync("INSERT INTO ec_users VALUES (2, 'a@example.com')"),
+      (err) => {
+        assert.equal(err.cause.kind, 'UniqueConstraint');
+        assert.equal(err.cause.column, 'email');
+        assert.equal(typeof err.cause.index, 'string');
+        return true;
+      }
+    );
+    const stmt = db.prepare('INSERT INTO ec_users VALUES ($1, $2)');
+    assert.throws(() => stmt.executeSync([1, 'c@example.com']), (err) => {
+      assert.equal(err.cause.kind, 'PrimaryKeyConstraint');
+      return true;
+    });
+  });
+
+  it('should keep the cause when the message is wrapped', async () => {
+    const pipeline = db.pipeline().execute("INSERT INTO ec_users VALUES (1, 'd@example.com')");
+    await assert.rejects(pipeline.run(), (err) => {
+      assert.match(err.message, /^Pipeline step 1: /);
+      assert.equal(err.cause.kind, 'PrimaryKeyConstraint');
+      return true;
+    });
+  });
+
+  it('should not add a cause to binding errors', () => {
+    assert.throws(() => db.prepare('SELECT $1').querySync([1, 2]), (err) => {
+      assert.equal(err.cause, undefined);
+      return true;
+    });
+  });
+});
+
+describe('prepared statement column keys', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE ck_items (id INTEGER PRIMARY KEY, item_name TEXT)');
+    await db.execute("INSERT INTO ck_items VALUES (1, 'a'), (2, 'b')");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should reuse column names across sync calls', () => {
+    const stmt = db.prepare('SELECT id, item_name FROM ck_items ORDER BY id');
+    for (let i = 0; i < 3; i++) {
+      assert.deepEqual(stmt.querySync(), [
+        { id: 1, item_name: 'a' },
+        { id: 2, item_name: 'b' },
+      ]);
+      assert.deepEqual(stmt.queryOneSync(), { id: 1, item_name: 'a' });
+      assert.deepEqual(stmt.queryRawSync().columns, ['id', 'item_name']);
+    }
+  });
+
+  it('should pick up changed columns and case', () => {
+    const stmt = db.prepare('SELECT * FROM ck_items ORDER BY id LIMIT 1');
+    assert.deepEqual(stmt.querySync(), [{ id: 1, item_name: 'a' }]);
+    stmt.columnCase('camel');
+    assert.deepEqual(stmt.queryOneSync(), { id: 1, itemName: 'a' });
+    db.executeSync('ALTER TABLE ck_items ADD COLUMN item_count INTEGER');
+    assert.deepEqual(stmt.querySync(), [{ id: 1, itemName: 'a', itemCount: null }]);
+    assert.deepEqual(stmt.queryRawSync().columns, ['id', 'itemName', 'itemCount']);
+  });
+});
+
+describe('large text values', () => {
+  let db;
+  const ascii = 'a'.repeat(40 * 1024) + 'z';
+  const accented = 'é'.repeat(20 * 1024);
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE lt_docs (id INTEGER PRIMARY KEY, body TEXT)');
+    await db.execute('INSERT INTO lt_docs VALUES (1, $1), (2, $2), (3, $3)', [ascii, accented, 'short']);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should return large and ASCII text intact on every path', async () => {
+    const sql = 'SELECT body FROM lt_docs ORDER BY id';
+    const expected = [ascii, accented, 'short'];
+    assert.deepEqual((await db.query(sql)).map((r) => r.body), expected);
+    assert.deepEqual(db.querySync(sql).map((r) => r.body), expected);
+    assert.deepEqual((await db.query(sql, [], { lowMemory: true })).map((r) => r.body), expected);
+    assert.deepEqual(db.queryRawSync(sql).rows.map((r) => r[0]), expected);
+    assert.deepEqual(db.prepare(sql).pluck().querySync(), expected);
+    assert.equal((await db.queryOne('SELECT body FROM lt_docs WHERE id = 1')).body, ascii);
+  });
+
+  it('should keep strings valid after the rows are gone', async () => {
+    const body = db.queryOneSync('SELECT body FROM lt_docs WHERE id = 1').body;
+    await db.execute('DELETE FROM lt_docs WHERE id = 1');
+    assert.equal(body.length, ascii.length);
+    assert.equal(body, ascii);
+  });
+});
+
+describe('columns option', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE co_users (id INTEGER PRIMARY KEY, user_name TEXT, email TEXT, age INTEGER)');
+    await db.execute("INSERT INTO co_users VALUES (1, 'a', 'a@x', 30), (2, 'b', 'b@x', 40)");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should keep only the named columns, in order', async () => {
+    const sql = 'SELECT * FROM co_users ORDER BY id';
+    const options = { columns: ['email', 'id'] };
+    const expected = [{ email: 'a@x', id: 1 }, { email: 'b@x', id: 2 }];
+    for (const rows of [await db.query(sql, [], options), db.querySync(sql, [], options)]) {
+      assert.deepEqual(rows, expected);
+      assert.deepEqual(Object.keys(rows[0]), ['email', 'id']);
+    }
+    assert.deepEqual(await db.queryOne(sql, [], { columns: ['AGE'] }), { age: 30 });
+    assert.deepEqual(db.queryOneSync(sql, [], { columns: ['age'] }), { age: 30 });
+    const raw = { columns: ['user_name'], rows: [['a'], ['b']] };
+    assert.deepEqual(await db.queryRaw(sql, [], { columns: ['user_name'] }), raw);
+    assert.deepEqual(db.queryRawSync(sql, [], { columns: ['user_name'], lowMemory: true }), raw);
+    assert.deepEqual(await db.query(sql, [], { columns: ['id'], lowMemory: true }), [{ id: 1 }, { id: 2 }]);
+    const [rows] = await db.pipeline().query(sql, [], { columns: ['id'] }).run();
+    assert.deepEqual(rows, [{ id: 1 }, { id: 2 }]);
+  });
+
+  it('should project prepared statement results', async () => {
+    const stmt = db.prepare('SELECT * FROM co_users WHERE id >= $1 ORDER BY id');
+    assert.deepEqual(stmt.querySync([2], { columns: ['user_name'] }), [{ user_name: 'b' }]);
+    assert.deepEqual(await stmt.query([2], { columns: ['age', 'id'] }), [{ age: 40, id: 2 }]);
+    assert.deepEqual(stmt.queryOneSync([1], { columns: ['email'] }), { email: 'a@x' });
+    assert.deepEqual(await stmt.queryOne([1], { columns: ['id'] }), { id: 1 });
+    assert.deepEqual(stmt.queryRawSync([2], { columns: ['id'] }), { columns: ['id'], rows: [[2]] });
+    assert.deepEqual(stmt.querySync([2]), [{ id: 2, user_name: 'b', email: 'b@x', age: 40 }]);
+    stmt.columnCase('camel');
+    assert.deepEqual(await stmt.queryRaw([2], { columns: ['user_name'] }), { columns: ['userName'], rows: [['b']] });
+    stmt.pluck();
+    assert.deepEqual(stmt.querySync([1], { columns: ['email'] }), ['a@x', 'b@x']);
+    assert.equal(await stmt.queryOne([1], { columns: ['age'] }), 30);
+    stmt.expand();
+    assert.deepEqual(stmt.queryOneSync([1], { columns: ['email', 'id'] }), { coUsers: { email: 'a@x', id: 1 } });
+  });
+
+  it('should reject unknown, repeated and empty column lists', async () => {
+    const sql = 'SELECT id, email FROM co_users';
+    assert.throws(() => db.querySync(sql, [], { columns: ['age'] }), /Unknown column 'age' in columns: the result has id, email/);
+    await assert.rejects(db.query(sql, [], { columns: ['nope'] }), /Unknown column 'nope'/);
+    assert.throws(() => db.querySync(sql, [], { columns: ['id', 'ID'] }), /Column 'ID' is named twice in columns/);
+    assert.throws(() => db.prepare(sql).querySync([], { columns: [] }), /columns must name at least one column/);
+  });
+});
+
+describe('duplicate columns', () => {
+  const sql = 'SELECT a.id, b.id, a.n FROM dc_a a JOIN dc_b b ON b.a_id = a.id';
+  let db;
+  let strict;
+  let nested;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    // Handles on the same in-memory engine, closed with `db`
+    strict = await Database.open(':memory:', { duplicateColumns: 'error' });
+    nested = await Database.open(':memory:', { duplicateColumns: 'expand' });
+    await db.execute('CREATE TABLE dc_a (id INTEGER PRIMARY KEY, n TEXT)');
+    await db.execute('CREATE TABLE dc_b (id INTEGER PRIMARY KEY, a_id INTEGER, id_1 TEXT)');
+    await db.execute("INSERT INTO dc_a VALUES (1, 'x')");
+    await db.execute("INSERT INTO dc_b VALUES (7, 1, 'y')");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should suffix later duplicates by default', async () => {
+    const expected = { id: 1, id_1: 7, n: 'x' };
+    assert.deepEqual(await db.query(sql), [expected]);
+    assert.deepEqual(db.querySync(sql), [expected]);
+    assert.deepEqual(await db.queryOne(sql), expected);
+    assert.deepEqual(db.prepare(sql).queryOneSync(), expected);
+    assert.deepEqual(db.querySync(sql, [], { lowMemory: true }), [expected]);
+    assert.deepEqual(db.queryRawSync(sql).columns, ['id', 'id', 'n']);
+    const taken = 'SELECT a.id, b.id_1, b.id FROM dc_a a JOIN dc_b b ON b.a_id = a.id';
+    assert.deepEqual(db.queryOneSync(taken), { id: 1, id_1: 'y', id_2: 7 });
+  });
+
+  it('should throw with duplicateColumns error', async () => {
+    await assert.rejects(strict.query(sql), /Duplicate column name 'id' in result/);
+    assert.throws(() => strict.querySync(sql), /Duplicate column name 'id' in result/);
+    assert.throws(() => strict.prepare(sql).queryOneSync(), /Duplicate column name 'id'/);
+    assert.deepEqual(strict.queryRawSync(sql).columns, ['id', 'id', 'n']);
+    assert.deepEqual(strict.querySync(sql, [], { columns: ['id', 'n'] }), [{ id: 1, n: 'x' }]);
+    assert.deepEqual(strict.querySync('SELECT id FROM dc_a'), [{ id: 1 }]);
+  });
+
+  it('should return one object per table with duplicateColumns expand', async () => {
+    const expected = { a: { id: 1, n: 'x' }, b: { id: 7 } };
+    assert.deepEqual(await nested.query(sql), [expected]);
+    assert.deepEqual(nested.querySync(sql), [expected]);
+    assert.deepEqual(await nested.queryOne(sql), expected);
+    assert.deepEqual(await nested.prepare(sql).query(), [expected]);
+    assert.deepEqual(nested.prepare(sql).queryOneSync(), expected);
+    assert.deepEqual(nested.querySync('SELECT id, n FROM dc_a'), [{ id: 1, n: 'x' }]);
+  });
+
+  it('should reject an invalid duplicateColumns', () => {
+    assert.throws(
+      () => Database.open(':memory:', { duplicateColumns: 'merge' }),
+      /Invalid duplicateColumns 'merge': expected 'suffix', 'error', or 'expand'/,
+    );
+  });
+});
+
+describe('offset and limit options', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE pg_items (id INTEGER PRIMARY KEY, label TEXT)');
+    await db.execute("INSERT INTO pg_items VALUES (1, 'a'), (2, 'b'), (3, 'c'), (4, 'd'), (5, 'e')");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should page query results', async () => {
+    const sql = 'SELECT id FROM pg_items ORDER BY id';
+    const options = { offset: 1, limit: 2 };
+    assert.deepEqual(await db.query(sql, [], options), [{ id: 2 }, { id: 3 }]);
+    assert.deepEqual(db.querySync(sql, [], options), [{ id: 2 }, { id: 3 }]);
+    assert.deepEqual(db.querySync(sql, [], { ...options, lowMemory: true }), [{ id: 2 }, { id: 3 }]);
+    assert.deepEqual(await db.queryRaw(sql, [], { offset: 3 }), { columns: ['id'], rows: [[4], [5]] });
+    assert.deepEqual(db.queryRawSync(sql, [], { limit: 1 }), { columns: ['id'], rows: [[1]] });
+    assert.deepEqual(await db.queryOne(sql, [], { offset: 2 }), { id: 3 });
+    assert.equal(db.queryOneSync(sql, [], { offset: 5 }), null);
+    assert.deepEqual(db.querySync(sql, [], { offset: 10 }), []);
+    assert.deepEqual(await db.query(sql, [], { limit: 0 }), []);
+    const [rows] = await db.pipeline().query(sql, [], { offset: 4 }).run();
+    assert.deepEqual(rows, [{ id: 5 }]);
+  });
+
+  it('should page prepared statement results', async () => {
+    const stmt = db.prepare('SELECT id, label FROM pg_items WHERE id > $1 ORDER BY id');
+    assert.deepEqual(await stmt.query([1], { offset: 1, limit: 1 }), [{ id: 3, label: 'c' }]);
+    assert.deepEqual(stmt.querySync([1], { limit: 2, columns: ['label'] }), [{ label: 'b' }, { label: 'c' }]);
+    assert.deepEqual(stmt.queryOneSync([0], { offset: 4 }), { id: 5, label: 'e' });
+    assert.deepEqual(await stmt.queryRaw([3], { offset: 1 }), { columns: ['id', 'label'], rows: [[5, 'e']] });
+    stmt.pluck();
+    assert.deepEqual(stmt.querySync([0], { offset: 3 }), [4, 5]);
+    assert.deepEqual(stmt.querySync([0]), [1, 2, 3, 4, 5]);
+  });
+
+  it('should reject negative and fractional values', async () => {
+    const sql = 'SELECT id FROM pg_items';
+    assert.throws(() => db.querySync(sql, [], { offset: -1 }), /offset must be a non-negative integer/);
+    assert.throws(() => db.querySync(sql, [], { limit: 1.5 }), /limit must be a non-negative integer/);
+    assert.throws(() => db.prepare(sql).querySync([], { limit: NaN }), /limit must be a non-negative integer/);
+    assert.throws(() => db.query(sql, [], { offset: Infinity }), /offset must be a non-negative integer/);
+  });
+});
+
+describe('custom types', () => {
+  let db;
+
+  class Money {
+    constructor(cents) {
+      this.cents = cents;
+    }
+  }
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.registerType({
+      test: (value) => value instanceof Money,
+      serialize: (money) => money.cents,
+      deserialize: (cents) => new Money(cents),
+      columns: ['price'],
+    });
+    await db.execute('CREATE TABLE ct_items (id INTEGER PRIMARY KEY, price INTEGER, qty INTEGER)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should serialize parameters and revive named columns', async () => {
+    await db.execute('INSERT INTO ct_items VALUES ($1, $2, $3)', [1, new Money(250), 2]);
+    db.executeSync('INSERT INTO ct_items VALUES (:id, :price, :qty)', { id: 2, price: new Money(99), qty: 5 });
+    await db.execute('INSERT INTO ct_items VALUES ($1, $2, $3)', [3, null, 1]);
+
+    const rows = await db.query('SELECT * FROM ct_items ORDER BY id');
+    assert.deepEqual(rows, [
+      { id: 1, price: new Money(250), qty: 2 },
+      { id: 2, price: new Money(99), qty: 5 },
+      { id: 3, price: null, qty: 1 },
+    ]);
+    assert.ok(rows[0].price instanceof Money);
+    assert.deepEqual(db.querySync('SELECT * FROM ct_items ORDER BY id'), rows);
+    assert.deepEqual(await db.queryOne('SELECT price FROM ct_items WHERE id = 2'), { price: new Money(99) });
+    assert.deepEqual(db.queryRawSync('SELECT id, price FROM ct_items WHERE id = 1').rows, [[1, new Money(250)]]);
+  });
+
+  it('should apply to statements, transactions and pipelines', async () => {
+    const stmt = db.prepare('SELECT price FROM ct_items WHERE price IN (?) ORDER BY id');
+    assert.deepEqual(stmt.pluck().querySync([[new Money(250), new Money(99)]]), [new Money(250), new Money(99)]);
+
+    const tx = await db.begin();
+    await tx.execute('UPDATE ct_items SET price = $1 WHERE id = 3', [new Money(10)]);
+    assert.deepEqual(tx.queryOneSync('SELECT price FROM ct_items WHERE id = 3'), { price: new Money(10) });
+    await tx.rollback();
+
+    const pipeline = db.pipeline();
+    pipeline.query('SELECT price FROM ct_items WHERE id = $1', [1]);
+    const [result] = await pipeline.run();
+    assert.deepEqual(result, [{ price: new Money(250) }]);
+  });
+
+  it('should try types in order and leave undefined to the next', async () => {
+    const other = await Database.open(':memory:');
+    other.registerType({ deserialize: (value, column) => (column === 'a' ? `first ${value}` : undefined) });
+    other.registerType({ deserialize: (value) => `second ${value}` });
+    assert.deepEqual(other.querySync("SELECT 'x' AS a, 'y' AS b"), [{ a: 'first x', b: 'second y' }]);
+  });
+
+  it('should propagate errors thrown by hooks', async () => {
+    const other = await Database.open(':memory:');
+    other.registerType({
+      test: (value) => value instanceof Money,
+      serialize: () => {
+        throw new Error('cannot store money');
+      },
+    });
+    assert.throws(() => other.querySync('SELECT $1 AS v', [new Money(1)]), /cannot store money/);
+    other.registerType({
+      deserialize: () => {
+        throw new Error('cannot revive');
+      },
+    });
+    await assert.rejects(other.query('SELECT 1 AS v'), /cannot revive/);
+  });
+
+  it('should store UUID Buffers as TEXT and return lowercase strings', async () => {
+    const other = await Database.open(':memory:');
+    other.registerType({
+      test: (value) => Buffer.isBuffer(value) && value.length === 16,
+      serialize: (bytes) => bytes.toString('hex').replace(/(.{8})(.{4})(.{4})(.{4})/, '$1-$2-$3-$4-'),
+      deserialize: (text) => text.toLowerCase(),
+      columns: ['uid'],
+    });
+    other.executeSync('CREATE TABLE ct_uuids (id INTEGER PRIMARY KEY, uid TEXT)');
+    const bytes = Buffer.from('6ec0bd7f11c043da975e2a8ad9ebae0b', 'hex');
+    other.executeSync('INSERT INTO ct_uuids VALUES ($1, $2)', [1, bytes]);
+    other.executeSync('INSERT INTO ct_uuids VALUES ($1, $2)', [2, '6EC0BD7F-11C0-43DA-975E-2A8AD9EBAE0B']);
+    assert.deepEqual(other.querySync('SELECT uid FROM ct_uuids ORDER BY id'), [
+      { uid: '6ec0bd7f-11c0-43da-975e-2a8ad9ebae0b' },
+      { uid: '6ec0bd7f-11c0-43da-975e-2a8ad9ebae0b' },
+    ]);
+    assert.deepEqual(other.querySync('SELECT id FROM ct_uuids WHERE uid = $1', [bytes]), [{ id: 1 }]);
+  });
+
+  it('should validate the options', () => {
+    assert.throws(() => db.registerType({ test: () => true }), /needs both test and serialize/);
+    assert.throws(() => db.registerType({ serialize: (v) => v }), /needs both test and serialize/);
+    assert.throws(() => db.registerType({}), /needs test and serialize, deserialize, or both/);
+    assert.throws(
+      () => db.registerType({ test: () => true, serialize: (v) => v, columns: ['a'] }),
+      /columns only applies to deserialize/,
+    );
+  });
+});
+
+describe('question mark placeholders', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE qm_items (id INTEGER PRIMARY KEY, label TEXT, qty INTEGER)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should bind ? in order across calls', async () => {
+    await db.execute('INSERT INTO qm_items VALUES (?, ?, ?)', [1, 'a', 2]);
+    db.executeSync('INSERT INTO qm_items VALUES (?, ?, ?)', [2, 'b', 3]);
+    db.executeBatchSync('INSERT INTO qm_items VALUES (?, ?, ?)', [[3, 'c', 1], [4, 'd', 1]]);
+
+    assert.deepEqual(await db.query('SELECT id FROM qm_items WHERE qty = ? AND id > ? ORDER BY id', [1, 3]), [{ id: 4 }]);
+    assert.deepEqual(db.queryOneSync('SELECT label FROM qm_items WHERE id = ?', [2]), { label: 'b' });
+    assert.deepEqual(db.prepare('SELECT id FROM qm_items WHERE label = ?').querySync(['c']), [{ id: 3 }]);
+
+    const tx = await db.begin();
+    assert.deepEqual(await tx.queryOne('SELECT qty FROM qm_items WHERE id = ?', [1]), { qty: 2 });
+    await tx.rollback();
+  });
+
+  it('should expand ? IN lists and check the count', () => {
+    assert.deepEqual(
+      db.querySync('SELECT id FROM qm_items WHERE id IN (?) AND qty = ? ORDER BY id', [[1, 3, 4], 1]),
+      [{ id: 3 }, { id: 4 }]
+    );
+    assert.deepEqual(db.querySync("SELECT '?' AS q, ? AS v", [5]), [{ q: '?', v: 5 }]);
+    assert.throws(() => db.prepare('SELECT ? AS a, ? AS b').querySync([1]), /requires 2 parameters, got 1/);
+  });
+});
+
+describe('bindIdentifiers', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.exec(`
+      CREATE TABLE bi_orders (id INTEGER PRIMARY KEY, Total INTEGER);
+      CREATE VIEW bi_big AS SELECT id FROM bi_orders WHERE Total > 10;
+      INSERT INTO bi_orders VALUES (1, 5), (2, 50);
+    `);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should splice checked, quoted identifiers', async () => {
+    const sql = db.bindIdentifiers('SELECT {{column}} FROM {{table}} WHERE id = $1', { column: 'total', table: 'BI_ORDERS' });
+    assert.equal(sql, 'SELECT "Total" FROM "bi_orders" WHERE id = $1');
+    assert.deepEqual(await db.query(sql, [2]), [{ Total: 50 }]);
+    assert.equal(db.bindIdentifiers('SELECT {{ c }} FROM {{v}}', { c: 'bi_big.id', v: 'bi_big' }), 'SELECT "bi_big"."id" FROM "bi_big"');
+  });
+
+  it('should leave braces in literals, quoted identifiers and comments alone', () => {
+    const sql = `SELECT '{{a}}' AS s, "{{b}}" FROM bi_orders -- {{c}}`;
+    assert.equal(db.bindIdentifiers(sql, {}), sql);
+  });
+
+  it('should reject unknown, missing and unused identifiers', () => {
+    assert.throws(() => db.bindIdentifiers('SELECT {{c}} FROM bi_orders', { c: 'nope' }), /'nope' for {{c}} is not a table, view or column/);
+    assert.throws(
+      () => db.bindIdentifiers('SELECT {{c}} FROM bi_orders', { c: 'id"; DROP TABLE bi_orders; --' }),
+      /is not a table, view or column/
+    );
+    assert.throws(() => db.bindIdentifiers('SELECT {{c}} FROM bi_orders', {}), /No identifier given for {{c}}/);
+    assert.throws(() => db.bindIdentifiers('SELECT id FROM bi_orders', { c: 'id' }), /'c' has no {{c}} placeholder/);
+    assert.throws(() => db.bindIdentifiers('SELECT {{c} FROM bi_orders', {}), /Invalid identifier placeholder at offset 7/);
+    assert.throws(() => db.bindIdentifiers('SELECT {{c}} FROM', { c: 'id' }), /expected table name/);
+  });
+});
+
+describe('Temporal values', () => {
+  // A stand-in for the parts of Temporal the binding uses, since Node
+  // doesn't ship it yet: the classes, toString() and Instant.from().
+  class Value {
+    constructor(text) {
+      this.text = text;
+    }
+    toString() {
+      return this.text;
+    }
+  }
+  class Instant extends Value {
+    static from(text) {
+      return new Instant(String(text));
+    }
+  }
+  class PlainDateTime extends Value {}
+  class PlainDate extends Value {}
+
+  let db;
+  let plain;
+
+  before(async () => {
+    assert.throws(() => Database.open(':memory:', { temporal: true }), /temporal needs the Temporal API/);
+    globalThis.Temporal = { Instant, PlainDateTime, PlainDate };
+    db = await Database.open(':memory:', { temporal: true });
+    plain = await Database.open(':memory:');
+    await db.execute('CREATE TABLE tp_events (id INTEGER PRIMARY KEY, at TIMESTAMP)');
+  });
+
+  after(async () => {
+    await db.close();
+    delete globalThis.Temporal;
+  });
+
+  it('should bind Temporal values and return Instants', async () => {
+    db.executeSync('INSERT INTO tp_events VALUES ($1, $2)', [1, new Instant('2024-01-02T03:04:05.123456789Z')]);
+    await db.execute('INSERT INTO tp_events VALUES ($1, $2)', [2, new PlainDateTime('2024-01-02T03:04:05.5')]);
+    db.executeSync('INSERT INTO tp_events VALUES (:id, :at)', { id: 3, at: new PlainDate('2024-01-02[u-ca=iso8601]') });
+    db.executeSync('INSERT INTO tp_events VALUES ($1, $2)', [4, new Date('2024-05-05T00:00:00Z')]);
+
+    const expected = [
+      { id: 1, at: new Instant('2024-01-02T03:04:05.123456789Z') },
+      { id: 2, at: new Instant('2024-01-02T03:04:05.500Z') },
+      { id: 3, at: new Instant('2024-01-02T00:00:00Z') },
+      { id: 4, at: new Instant('2024-05-05T00:00:00Z') },
+    ];
+    assert.deepEqual(db.querySync('SELECT * FROM tp_events ORDER BY id'), expected);
+    assert.deepEqual(await db.query('SELECT * FROM tp_events ORDER BY id'), expected);
+    assert.deepEqual(await db.queryOne('SELECT at FROM tp_events WHERE id = 1'), { at: expected[0].at });
+    assert.deepEqual(db.prepare('SELECT at FROM tp_events WHERE id = 2').pluck().querySync(), [expected[1].at]);
+  });
+
+  it('should keep ISO strings without the option', () => {
+    assert.deepEqual(plain.querySync('SELECT at FROM tp_events WHERE id = 1'), [{ at: '2024-01-02T03:04:05Z' }]);
+  });
+
+  it('should reject Temporal values it cannot read', () => {
+    assert.throws(() => db.querySync('SELECT $1 AS v', [new PlainDate('soon')]), /Cannot bind Temporal.PlainDate 'soon' as a TIMESTAMP/);
+    assert.equal(Database.capabilities().binding.temporal, true);
+  });
+});
+
+describe('syncBusy', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should time sync calls per method', async () => {
+    assert.deepEqual(db.syncBusy, { calls: 0, totalMs: 0, p99Ms: 0, maxMs: 0, methods: [] });
+
+    db.executeSync('CREATE TABLE sb_items (id INTEGER PRIMARY KEY)');
+    for (let i = 0; i < 10; i++) {
+      db.executeSync('INSERT INTO sb_items VALUES ($1)', [i]);
+    }
+    db.prepare('SELECT * FROM sb_items').querySync();
+    assert.throws(() => db.querySync('SELEC'));
+    const tx = db.beginSync();
+    tx.querySync('SELECT id FROM sb_items');
+    tx.rollbackSync();
+    await db.query('SELECT 1');
+
+    const busy = db.syncBusy;
+    assert.equal(busy.calls, 16);
+    const byMethod = Object.fromEntries(busy.methods.map((m) => [m.method, m]));
+    assert.deepEqual(
+      Object.keys(byMethod).sort(),
+      ['beginSync', 'executeSync', 'querySync', 'rollbackSync']
+    );
+    assert.equal(byMethod.executeSync.calls, 11);
+    assert.equal(byMethod.querySync.calls, 3);
+    for (const m of [busy, ...busy.methods]) {
+      assert.ok(m.totalMs > 0);
+      assert.ok(m.p99Ms > 0 && m.p99Ms <= m.maxMs && m.maxMs <= m.totalMs);
+    }
+    const totals = busy.methods.map((m) => m.totalMs);
+    assert.deepEqual(totals, [...totals].sort((a, b) => b - a));
+  });
+});
+
+describe('queryRaw preview', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE pv_items (id INTEGER PRIMARY KEY, label TEXT, meta JSON)');
+    await db.execute('CREATE TABLE pv_tags (id INTEGER PRIMARY KEY, item_id INTEGER)');
+    for (let i = 1; i <= 5; i++) {
+      await db.execute('INSERT INTO pv_items VALUES ($1, $2, $3)', [i, `item ${i}`, { n: i }]);
+      await db.execute('INSERT INTO pv_tags VALUES ($1, $2)', [i, i]);
+    }
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should return the first rows as objects alongside every raw row', async () => {
+    const sql = 'SELECT id, label FROM pv_items ORDER BY id';
+    for (const result of [await db.queryRaw(sql, [], { preview: 2 }), db.queryRawSync(sql, [], { preview: 2 })]) {
+      assert.deepEqual(result.columns, ['id', 'label']);
+      assert.equal(result.rows.length, 5);
+      assert.deepEqual(result.preview, [
+        { id: 1, label: 'item 1' },
+        { id: 2, label: 'item 2' },
+      ]);
+    }
+    assert.equal((await db.queryRaw(sql)).preview, undefined);
+    assert.deepEqual((await db.queryRaw(sql, [], { preview: 0 })).preview, []);
+    assert.equal((await db.queryRaw(sql, [], { preview: 100 })).preview.length, 5);
+  });
+
+  it('should share values and key duplicate columns like query()', async () => {
+    const result = await db.queryRaw('SELECT * FROM pv_items WHERE id = 1', [], { preview: 1 });
+    assert.equal(result.preview[0].meta, result.rows[0][2]);
+
+    const sql = 'SELECT i.id, t.id FROM pv_items i JOIN pv_tags t ON t.item_id = i.id WHERE i.id = 3';
+    const joined = db.queryRawSync(sql, [], { preview: 1 });
+    assert.deepEqual(joined.columns, ['id', 'id']);
+    assert.deepEqual(joined.preview, db.querySync(sql));
+  });
+
+  it('should combine with columns and limit', async () => {
+    const result = await db.queryRaw('SELECT * FROM pv_items ORDER BY id', [], {
+      columns: ['label'],
+      offset: 1,
+      limit: 2,
+      preview: 1,
+    });
+    assert.deepEqual(result.rows, [['item 2'], ['item 3']]);
+    assert.deepEqual(result.preview, [{ label: 'item 2' }]);
+  });
+
+  it('should reject an invalid preview', async () => {
+    assert.throws(() => db.queryRawSync('SELECT 1', [], { preview: -1 }), /preview must be a non-negative integer/);
+    assert.throws(() => db.queryRaw('SELECT 1', [], { preview: 1.5 }), /preview must be a non-negative integer/);
+  });
+});
+
+describe('timestamp format', () => {
+  let db;
+  const at = '2024-01-15 10:30:00.123456789';
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE tf_events (id INTEGER PRIMARY KEY, at TIMESTAMP)');
+    await db.execute('INSERT INTO tf_events VALUES ($1, $2)', [1, at]);
+    await db.execute('INSERT INTO tf_events VALUES ($1, $2)', [2, new Date('2024-01-15T10:30:59.999Z')]);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  const ats = async (options) => {
+    const other = await Database.open(':memory:', options);
+    const sql = 'SELECT at FROM tf_events ORDER BY id';
+    const sync = other.querySync(sql).map((row) => row.at);
+    assert.deepEqual((await other.queryRaw(sql)).rows.map(([value]) => value), sync);
+    return sync;
+  };
+
+  it('should default to whole seconds in UTC', async () => {
+    assert.deepEqual(await ats({}), ['2024-01-15T10:30:00Z', '2024-01-15T10:30:59Z']);
+    assert.deepEqual(await ats({ timestampPrecision: 's', timezone: 'utc' }), await ats({}));
+  });
+
+  it('should keep milliseconds or microseconds, cut rather than rounded', async () => {
+    assert.deepEqual(await ats({ timestampPrecision: 'ms' }), ['2024-01-15T10:30:00.123Z', '2024-01-15T10:30:59.999Z']);
+    assert.deepEqual(await ats({ timestampPrecision: 'us' }), [
+      '2024-01-15T10:30:00.123456Z',
+      '2024-01-15T10:30:59.999000Z',
+    ]);
+  });
+
+  it('should write fixed offsets and the local time zone', async () => {
+    assert.deepEqual(await ats({ timezone: '+05:30' }), ['2024-01-15T16:00:00+05:30', '2024-01-15T16:00:59+05:30']);
+    assert.deepEqual(await ats({ timezone: '-08:00', timestampPrecision: 'ms' }), [
+      '2024-01-15T02:30:00.123-08:00',
+      '2024-01-15T02:30:59.999-08:00',
+    ]);
+
+    const [local] = await ats({ timezone: 'local' });
+    const date = new Date('2024-01-15T10:30:00Z');
+    const offset = -date.getTimezoneOffset();
+    const pad = (n) => String(Math.abs(n)).padStart(2, '0');
+    const shifted = new Date(date.getTime() + offset * 60000).toISOString().slice(0, 19);
+    const sign = offset < 0 ? '-' : '+';
+    assert.equal(local, `${shifted}${sign}${pad(Math.trunc(offset / 60))}:${pad(offset % 60)}`);
+  });
+
+  it('should reject invalid options', () => {
+    assert.throws(() => Database.open(':memory:', { timestampPrecision: 'ns' }), /Invalid timestampPrecision 'ns'/);
+    for (const timezone of ['+5:30', '+24:00', 'Europe/Paris']) {
+      assert.throws(() => Database.open(':memory:', { timezone }), /Invalid timezone/);
+    }
+  });
+});
+
+describe('prepareAsync', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE pa_items (id INTEGER PRIMARY KEY, label TEXT)');
+    await db.execute("INSERT INTO pa_items VALUES (1, 'a'), (2, 'b')");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should resolve to a prepared statement', async () => {
+    const stmt = await db.prepareAsync('SELECT label FROM pa_items WHERE id = $1');
+    assert.equal(stmt.sql, 'SELECT label FROM pa_items WHERE id = $1');
+    assert.equal(stmt.parameterCount, 1);
+    assert.deepEqual(stmt.queryOneSync([2]), { label: 'b' });
+    assert.deepEqual(await stmt.query([1]), [{ label: 'a' }]);
+    const [entry] = db.statementReport().filter((e) => e.sql === stmt.sql);
+    assert.equal(entry.prepared, 1);
+  });
+
+  it('should prepare large SQL', async () => {
+    const ids = Array.from({ length: 20000 }, (_, i) => i).join(', ');
+    const stmt = await db.prepareAsync(`SELECT COUNT(*) AS n FROM pa_items WHERE id IN (${ids})`);
+    assert.deepEqual(stmt.querySync(), [{ n: 2 }]);
+  });
+
+  it('should reject invalid SQL and interrupted calls', async () => {
+    await assert.rejects(db.prepareAsync('SELEC 1'));
+    // Parsing a long IN list keeps the call busy until interrupt() has been
+    // called.
+    const ids = Array.from({ length: 200000 }, (_, i) => i).join(', ');
+    const pending = db.prepareAsync(`SELECT * FROM pa_items WHERE id IN (${ids})`);
+    db.interrupt();
+    await assert.rejects(pending, { message: 'Interrupted', code: 'Cancelled' });
+    assert.ok(await db.prepareAsync('SELECT * FROM pa_items'));
+  });
+
+  it('should reject SQL over maxSqlBytes before parsing it', async () => {
+    const sql = 'SELECT * FROM pa_items';
+    assert.ok(db.prepare(sql, { maxSqlBytes: sql.length }));
+    assert.throws(() => db.prepare(sql, { maxSqlBytes: 10 }), /SQL is 22 bytes, more than maxSqlBytes \(10\)/);
+    assert.throws(() => db.prepareAsync(`${sql} INVALID`, { maxSqlBytes: 10 }), /more than maxSqlBytes/);
+    assert.throws(() => db.prepare(sql, { maxSqlBytes: -1 }), /maxSqlBytes must be a non-negative integer/);
+  });
+});
+
+describe('maxRows', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE mr_items (id INTEGER PRIMARY KEY, label TEXT)');
+    for (let i = 1; i <= 5; i++) db.executeSync('INSERT INTO mr_items VALUES ($1, $2)', [i, `item${i}`]);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should reject results over maxRows', async () => {
+    const sql = 'SELECT id FROM mr_items ORDER BY id';
+    await assert.rejects(db.query(sql, [], { maxRows: 4 }), /Query returned more than maxRows \(4\) rows/);
+    await assert.rejects(db.queryRaw(sql, [], { maxRows: 4 }), /more than maxRows/);
+    assert.throws(() => db.querySync(sql, [], { maxRows: 4 }), /more than maxRows/);
+    assert.throws(() => db.queryRawSync(sql, [], { maxRows: 4 }), /more than maxRows/);
+    const rows = await db.query(sql, [], { maxRows: 5 });
+    assert.equal(rows.length, 5);
+    assert.equal(rows.truncated, undefined);
+  });
+
+  it('should truncate with a flag', async () => {
+    const sql = 'SELECT id FROM mr_items ORDER BY id';
+    const options = { maxRows: 2, onMaxRows: 'truncate' };
+    const rows = await db.query(sql, [], options);
+    assert.deepEqual([...rows], [{ id: 1 }, { id: 2 }]);
+    assert.equal(rows.truncated, true);
+    const raw = db.queryRawSync(sql, [], options);
+    assert.deepEqual(raw.rows, [[1], [2]]);
+    assert.equal(raw.truncated, true);
+    assert.equal(db.querySync(sql, [], options).truncated, true);
+    assert.equal((await db.queryRaw(sql, [], options)).truncated, true);
+  });
+
+  it('should count rows after offset and let a smaller limit win', async () => {
+    const sql = 'SELECT id FROM mr_items ORDER BY id';
+    assert.equal((await db.query(sql, [], { offset: 2, maxRows: 3 })).length, 3);
+    assert.deepEqual(db.querySync(sql, [], { limit: 2, maxRows: 2 }), [{ id: 1 }, { id: 2 }]);
+  });
+
+  it('should apply to prepared statements, transactions and pipelines', async () => {
+    const stmt = db.prepare('SELECT id FROM mr_items ORDER BY id');
+    await assert.rejects(stmt.query([], { maxRows: 1 }), /more than maxRows/);
+    assert.equal(stmt.querySync([], { maxRows: 1, onMaxRows: 'truncate' }).truncated, true);
+
+    const capped = await Database.open(':memory:', { maxRows: 3 });
+    const tx = await capped.begin();
+    await assert.rejects(tx.query('SELECT id FROM mr_items'), /more than maxRows \(3\)/);
+    assert.throws(() => tx.queryRawSync('SELECT id FROM mr_items'), /more than maxRows/);
+    await tx.rollback();
+    await assert.rejects(
+      capped.pipeline().query('SELECT id FROM mr_items').run(),
+      /Pipeline step 1: Query returned more than maxRows/,
+    );
+    const truncated = capped.prepare('SELECT id FROM mr_items').querySync([], { onMaxRows: 'truncate' });
+    assert.equal(truncated.length, 3);
+    assert.equal(truncated.truncated, true);
+    assert.equal((await capped.query('SELECT id FROM mr_items', [], { maxRows: 10 })).length, 5);
+  });
+
+  it('should use the database default for every query', async () => {
+    const capped = await Database.open(':memory:', { maxRows: 2, onMaxRows: 'truncate' });
+    const rows = await capped.query('SELECT id FROM mr_items ORDER BY id');
+    assert.deepEqual([...rows], [{ id: 1 }, { id: 2 }]);
+    assert.equal(rows.truncated, true);
+    await assert.rejects(capped.query('SELECT id FROM mr_items', [], { onMaxRows: 'error' }), /more than maxRows \(2\)/);
+    assert.equal(capped.queryOneSync('SELECT COUNT(*) AS n FROM mr_items').n, 5);
+  });
+
+  it('should validate the options', async () => {
+    assert.throws(() => db.querySync('SELECT 1', [], { maxRows: 0 }), /maxRows must be a positive integer/);
+    assert.throws(() => db.querySync('SELECT 1', [], { maxRows: 1.5 }), /maxRows must be a positive integer/);
+    assert.throws(
+      () => db.querySync('SELECT 1', [], { maxRows: 1, onMaxRows: 'drop' }),
+      /Invalid onMaxRows 'drop': expected 'error' or 'truncate'/,
+    );
+    assert.throws(() => Database.open(':memory:', { maxRows: -1 }), /maxRows must be a positive integer/);
+  });
+});
+
+// ============================================================
+// queryChunks
+// ============================================================
+
+describe('queryChunks', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE qc_items (id INTEGER PRIMARY KEY, label TEXT)');
+    for (let i = 1; i <= 25; i++) db.executeSync('INSERT INTO qc_items VALUES ($1, $2)', [i, `item${i}`]);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should deliver the rows in order, chunkSize at a time', async () => {
+    const chunks = [];
+    const count = await db.queryChunks(
+      'SELECT id, label FROM qc_items WHERE id > $1 ORDER BY id',
+      [0],
+      { chunkSize: 10 },
+      (rows) => chunks.push(rows),
+    );
+    assert.equal(count, 25);
+    assert.deepEqual(chunks.map((rows) => rows.length), [10, 10, 5]);
+    assert.deepEqual(chunks[0][0], { id: 1, label: 'item1' });
+    assert.deepEqual(chunks.flat().map((row) => row.id), Array.from({ length: 25 }, (_, i) => i + 1));
+  });
+
+  it('should resolve to 0 without calling back for an empty result', async () => {
+    let calls = 0;
+    const count = await db.queryChunks('SELECT id FROM qc_items WHERE id < 0', null, null, () => calls++);
+    assert.equal(count, 0);
+    assert.equal(calls, 0);
+  });
+
+  it('should stop and reject with what the callback throws', async () => {
+    let calls = 0;
+    await assert.rejects(
+      db.queryChunks('SELECT id FROM qc_items ORDER BY id', [], { chunkSize: 5 }, (rows) => {
+        calls++;
+        throw new RangeError(`stop at ${rows[0].id}`);
+      }),
+      (err) => err instanceof RangeError && err.message === 'stop at 1',
+    );
+    assert.equal(calls, 1);
+  });
+
+  it('should let the callback call the database', async () => {
+    const dedicated = await Database.open(':memory:', { dedicatedThread: true });
+    dedicated.execSync('CREATE TABLE qc_seen (id INTEGER PRIMARY KEY)');
+    dedicated.execSync('INSERT INTO qc_seen VALUES (1), (2), (3)');
+    const writes = [];
+    const count = await dedicated.queryChunks('SELECT id FROM qc_seen', [], { chunkSize: 1 }, (rows) => {
+      writes.push(dedicated.execute('UPDATE qc_seen SET id = id + 10 WHERE id = $1', [rows[0].id]));
+    });
+    await Promise.all(writes);
+    assert.equal(count, 3);
+    assert.deepEqual(dedicated.querySync('SELECT id FROM qc_seen ORDER BY id').map((r) => r.id), [11, 12, 13]);
+    await dedicated.close();
+  });
+
+  it('should validate chunkSize', async () => {
+    assert.throws(() => db.queryChunks('SELECT 1', [], { chunkSize: 0 }, () => {}), /chunkSize must be at least 1/);
+  });
+});
+
+describe('record and replay', () => {
+  let db;
+  let tmpDir;
+
+  before(async () => {
+    tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-record-'));
+    db = await Database.open(':memory:');
+  });
+
+  after(async () => {
+    await db.close();
+    fs.rmSync(tmpDir, { recursive: true, force: true });
+  });
+
+  it('should record statements with their parameters and replay them', async () => {
+    const file = path.join(tmpDir, 'workload.jsonl');
+    db.record(file);
+    db.execSync('CREATE TABLE rr_items (id INTEGER PRIMARY KEY, price FLOAT, at TIMESTAMP, label TEXT)');
+    db.executeSync('INSERT INTO rr_items VALUES ($1, $2, $3, $4)', [1, 2.5, new Date('2024-01-02T03:04:05.678Z'), 'a']);
+    await db.execute('INSERT INTO rr_items VALUES (:id, :price, NULL, :label)', { id: 2, price: 3.75, label: 'b' });
+    await db.executeBatch('INSERT INTO rr_items VALUES ($1, NULL, NULL, $2)', [[3, 'c'], [4, 'd']]);
+    const tx = await db.begin();
+    await tx.execute('UPDATE rr_items SET label = $1 WHERE id = $2', ['z', 1]);
+    await tx.commit();
+    await db.prepare('SELECT label FROM rr_items WHERE id = $1').query([2]);
+    db.stopRecording();
+
+    const lines = fs.readFileSync(file, 'utf8').trim().split('\n').map((line) => JSON.parse(line));
+    assert.equal(lines.length, 6);
+    assert.deepEqual(lines[1].params, [1, 2.5, { timestamp: '2024-01-02T03:04:05.678Z' }, 'a']);
+    assert.deepEqual(lines[2].params, { id: 2, price: 3.75, label: 'b' });
+    assert.deepEqual(lines[3].batch, [[3, 'c'], [4, 'd']]);
+    assert.equal(lines[5].sql, 'SELECT label FROM rr_items WHERE id = $1');
+    assert.equal(lines[5].rows, 1);
+    assert.ok(lines.every((line) => line.durationMs >= 0));
+
+    const target = await Database.open(path.join(tmpDir, 'target.db'));
+    try {
+      const report = await target.replay(file);
+      assert.equal(report.statements, 6);
+      assert.equal(report.errors, 0);
+      assert.equal(report.sql.length, 6);
+      assert.ok(report.recordedMs > 0);
+      assert.deepEqual(target.querySync('SELECT id, price, at, label FROM rr_items ORDER BY id'), [
+        { id: 1, price: 2.5, at: '2024-01-02T03:04:05Z', label: 'z' },
+        { id: 2, price: 3.75, at: null, label: 'b' },
+        { id: 3, price: null, at: null, label: 'c' },
+        { id: 4, price: null, at: null, label: 'd' },
+      ]);
+    } finally {
+      await target.close();
+    }
+  });
+
+  it('should count failing statements and go on', async () => {
+    const file = path.join(tmpDir, 'failing.jsonl');
+    fs.writeFileSync(
+      file,
+      [
+        '{"sql":"SELECT * FROM rr_missing","durationMs":1,"rows":0}',
+        '{"sql":"SELECT $1 AS n","params":[7],"durationMs":2,"rows":1}',
+        '',
+      ].join('\n')
+    );
+    const report = await db.replay(file);
+    assert.equal(report.statements, 2);
+    assert.equal(report.errors, 1);
+    assert.equal(report.recordedMs, 3);
+    const failed = report.sql.find((entry) => entry.sql === 'SELECT * FROM rr_missing');
+    assert.equal(failed.errors, 1);
+    assert.match(failed.error, /rr_missing/);
+  });
+
+  it('should apply the policy and reject malformed recordings', async () => {
+    const file = path.join(tmpDir, 'policy.jsonl');
+    fs.writeFileSync(file, '{"sql":"DROP TABLE rr_items","durationMs":1,"rows":0}\n');
+    db.setPolicy({ deny: ['DROP'] });
+    try {
+      const report = await db.replay(file);
+      assert.equal(report.errors, 1);
+      assert.match(report.sql[0].error, /DROP/);
+    } finally {
+      db.setPolicy(null);
+    }
+
+    fs.writeFileSync(file, '{"sql":"SELECT 1"}\nnot json\n');
+    await assert.rejects(db.replay(file), /Invalid recording: line 2/);
+    await assert.rejects(db.replay(path.join(tmpDir, 'missing.jsonl')), /Cannot replay/);
+    assert.throws(() => db.record(path.join(tmpDir, 'no-such-dir', 'file.jsonl')), /Cannot record to/);
+  });
+
+  it('should stop recording on stopRecording and close', async () => {
+    const file = path.join(tmpDir, 'stopped.jsonl');
+    db.record(file);
+    db.querySync('SELECT 1 AS n');
+    db.stopRecording();
+    db.querySync('SELECT 2 AS n');
+    assert.deepEqual(
+      fs.readFileSync(file, 'utf8').trim().split('\n').map((line) => JSON.parse(line).sql),
+      ['SELECT 1 AS n']
+    );
+
+    const other = await Database.open(path.join(tmpDir, 'closed.db'));
+    const closedFile = path.join(tmpDir, 'closed.jsonl');
+    other.record(closedFile);
+    other.execSync('CREATE TABLE rr_closed (id INTEGER PRIMARY KEY)');
+    await other.close();
+    assert.equal(fs.readFileSync(closedFile, 'utf8').split('\n').length, 2);
+  });
+});
+
+describe('cursor', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE cur_items (id INTEGER PRIMARY KEY, name TEXT)');
+    db.executeBatchSync(
+      'INSERT INTO cur_items VALUES ($1, $2)',
+      Array.from({ length: 7 }, (_, i) => [i + 1, `item${i + 1}`])
+    );
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should fetch rows in chunks until the result runs out', async () => {
+    const cursor = db.openCursor('SELECT id, name FROM cur_items WHERE id > $1 ORDER BY id', [1]);
+    assert.deepEqual(cursor.columns, ['id', 'name']);
+    const first = await cursor.fetchNext(4);
+    assert.deepEqual(first.map((row) => row.id), [2, 3, 4, 5]);
+    assert.deepEqual(first[0], { id: 2, name: 'item2' });
+    assert.deepEqual((await cursor.fetchNext(4)).map((row) => row.id), [6, 7]);
+    assert.deepEqual(await cursor.fetchNext(4), []);
+    cursor.close();
+  });
+
+  it('should fetch synchronously and with named parameters', () => {
+    const cursor = db.openCursor('SELECT id FROM cur_items WHERE id <= :max ORDER BY id', {
+      max: 3,
+    });
+    assert.deepEqual(cursor.fetchNextSync(2), [{ id: 1 }, { id: 2 }]);
+    assert.deepEqual(cursor.fetchNextSync(0), []);
+    assert.deepEqual(cursor.fetchNextSync(2), [{ id: 3 }]);
+    assert.deepEqual(cursor.fetchNextSync(2), []);
+  });
+
+  it('should throw after close', async () => {
+    const cursor = db.openCursor('SELECT id FROM cur_items ORDER BY id');
+    assert.equal((await cursor.fetchNext(1)).length, 1);
+    cursor.close();
+    cursor.close();
+    await assert.rejects(cursor.fetchNext(1), /Cursor is closed/);
+    assert.throws(() => cursor.fetchNextSync(1), /Cursor is closed/);
+  });
+
+  it('should validate the count, the SQL and the policy', () => {
+    const cursor = db.openCursor('SELECT id FROM cur_items');
+    assert.throws(() => cursor.fetchNext(-1), /non-negative integer/);
+    assert.throws(() => cursor.fetchNextSync(1.5), /non-negative integer/);
+    cursor.close();
+
+    assert.throws(() => db.openCursor('SELECT * FROM cur_missing'), /cur_missing/);
+    db.setPolicy({ allowTables: ['other'] });
+    try {
+      assert.throws(() => db.openCursor('SELECT * FROM cur_items'));
+    } finally {
+      db.setPolicy(null);
+    }
+  });
+});
+
+describe('enableFaultInjection', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE fi_items (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  afterEach(() => {
+    db.disableFaultInjection();
+  });
+
+  it('should fail every async call at errorRate 1 without running it', async () => {
+    const stmt = db.prepare('SELECT * FROM fi_items');
+    db.enableFaultInjection({ errorRate: 1 });
+    await assert.rejects(db.execute("INSERT INTO fi_items VALUES (1, 'a')"), /Injected fault in execute\(\)/);
+    await assert.rejects(db.query('SELECT * FROM fi_items'), /Injected fault in query\(\)/);
+    await assert.rejects(stmt.query(), /Injected fault/);
+    await assert.rejects(db.begin(), /Injected fault/);
+    await assert.rejects(db.pipeline().query('SELECT 1').run(), /Injected fault/);
+
+    // Sync calls aren't affected, and the failed insert changed nothing.
+    assert.deepEqual(db.querySync('SELECT * FROM fi_items'), []);
+  });
+
+  it('should pass calls at errorRate 0 and after disabling', async () => {
+    db.enableFaultInjection({ errorRate: 0 });
+    assert.equal((await db.execute("INSERT INTO fi_items VALUES (2, 'b')")).changes, 1);
+    db.enableFaultInjection({ errorRate: 1 });
+    db.disableFaultInjection();
+    assert.equal((await db.query('SELECT * FROM fi_items')).length, 1);
+  });
+
+  it('should fail some calls at a partial rate', async () => {
+    db.enableFaultInjection({ errorRate: 0.5 });
+    const results = await Promise.allSettled(
+      Array.from({ length: 200 }, () => db.query('SELECT 1 AS n'))
+    );
+    const failed = results.filter((r) => r.status === 'rejected').length;
+    assert.ok(failed > 20 && failed < 180, `${failed} of 200 failed`);
+  });
+
+  it('should delay calls by up to latencyMs', async () => {
+    db.enableFaultInjection({ latencyMs: 40 });
+    const started = Date.now();
+    for (let i = 0; i < 10; i++) {
+      await db.query('SELECT 1 AS n');
+    }
+    const elapsed = Date.now() - started;
+    assert.ok(elapsed >= 40 && elapsed < 2000, `took ${elapsed}ms`);
+  });
+
+  it('should validate the options and never fail close', async () => {
+    assert.throws(() => db.enableFaultInjection({ errorRate: 1.5 }), /errorRate must be a number from 0 to 1/);
+    assert.throws(() => db.enableFaultInjection({ errorRate: -0.1 }), /errorRate/);
+    assert.throws(() => db.enableFaultInjection({ latencyMs: -1 }), /latencyMs must be a non-negative number/);
+
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-faults-'));
+    const other = await Database.open(path.join(dir, 'faults.db'));
+    other.enableFaultInjection({ errorRate: 1 });
+    await other.close();
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+});
+
+describe('PreparedStatement bind', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE bd_items (id INTEGER PRIMARY KEY, region TEXT, active BOOLEAN)');
+    db.execSync("INSERT INTO bd_items VALUES (1, 'eu', true), (2, 'us', true), (3, 'eu', false)");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should run with the bound parameters when a call has none', async () => {
+    const stmt = db.prepare('SELECT id FROM bd_items WHERE region = $1 AND active = $2 ORDER BY id');
+    assert.equal(stmt.bind(['eu', true]), stmt);
+    assert.deepEqual(stmt.querySync(), [{ id: 1 }]);
+    assert.deepEqual(await stmt.query(), [{ id: 1 }]);
+    assert.deepEqual(await stmt.queryOne(), { id: 1 });
+    assert.deepEqual(stmt.queryRawSync().rows, [[1]]);
+    assert.deepEqual(stmt.querySync(['us', true]), [{ id: 2 }]);
+    assert.deepEqual(stmt.querySync(undefined, { limit: 1 }), [{ id: 1 }]);
+    stmt.bind(null);
+    assert.throws(() => stmt.querySync(), /Statement requires 2 parameters, got 0/);
+  });
+
+  it('should rebind and unbind', () => {
+    const stmt = db.prepare('SELECT id FROM bd_items WHERE region = :region ORDER BY id').pluck();
+    stmt.bind({ region: 'eu' });
+    assert.deepEqual(stmt.querySync(), [1, 3]);
+    stmt.bind({ region: 'us' });
+    assert.deepEqual(stmt.querySync(), [2]);
+    stmt.bind();
+    assert.deepEqual(stmt.querySync({ region: 'ap' }), []);
+    assert.deepEqual(stmt.querySync({ region: 'eu' }), [1, 3]);
+  });
+
+  it('should bind parameters of writes and array parameters', async () => {
+    const insert = db.prepare('INSERT INTO bd_items VALUES ($1, $2, $3)');
+    insert.bind([10, 'ap', true]);
+    assert.equal((await insert.execute()).changes, 1);
+    assert.equal(insert.executeSync([11, 'ap', false]).changes, 1);
+
+    const some = db.prepare('SELECT id FROM bd_items WHERE id IN ($1) ORDER BY id');
+    some.bind([[10, 11]]);
+    assert.deepEqual(some.querySync(), [{ id: 10 }, { id: 11 }]);
+  });
+
+  it('should check the parameters when bound', () => {
+    const stmt = db.prepare('SELECT id FROM bd_items WHERE id = $1 AND region = $2');
+    assert.throws(() => stmt.bind([1]), /Statement requires 2 parameters, got 1/);
+    assert.throws(() => stmt.querySync(), /Statement requires 2 parameters, got 0/);
+    stmt.finalize();
+    assert.throws(() => stmt.bind([1, 'eu']), /Statement is finalized/);
+  });
+});
+
+describe('cursor prefetch', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE cp_items (id INTEGER PRIMARY KEY, name TEXT)');
+    db.executeBatchSync(
+      'INSERT INTO cp_items VALUES ($1, $2)',
+      Array.from({ length: 50 }, (_, i) => [i + 1, `item${i + 1}`])
+    );
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  const settle = () => new Promise((resolve) => setTimeout(resolve, 20));
+
+  it('should read the next rows ahead after each fetch', async () => {
+    const methods = [];
+    db.on('task', (event) => event.phase === 'complete' && methods.push(event.method));
+    const cursor = db.openCursor('SELECT id FROM cp_items ORDER BY id');
+    assert.deepEqual((await cursor.fetchNext(10)).map((row) => row.id), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    await settle();
+    assert.deepEqual(methods.slice(-2), ['fetchNext', 'prefetch']);
+    assert.ok(db.memoryBudget.usedMb > 0);
+    assert.deepEqual((await cursor.fetchNext(10)).map((row) => row.id)[0], 11);
+    cursor.close();
+    await settle();
+    assert.equal(db.memoryBudget.usedMb, 0);
+  });
+
+  it('should keep rows in order when the count changes', async () => {
+    const cursor = db.openCursor('SELECT id FROM cp_items ORDER BY id');
+    const ids = [];
+    for (const n of [7, 3, 12, 1, 20, 20]) {
+      ids.push(...(await cursor.fetchNext(n)).map((row) => row.id));
+      await settle();
+    }
+    assert.deepEqual(ids, Array.from({ length: 50 }, (_, i) => i + 1));
+    assert.deepEqual(await cursor.fetchNext(5), []);
+  });
+
+  it('should hand rows read ahead to sync fetches', async () => {
+    const cursor = db.openCursor('SELECT id FROM cp_items WHERE id <= 12 ORDER BY id');
+    assert.equal((await cursor.fetchNext(5)).length, 5);
+    await settle();
+    assert.deepEqual(cursor.fetchNextSync(3).map((row) => row.id), [6, 7, 8]);
+    assert.deepEqual(cursor.fetchNextSync(10).map((row) => row.id), [9, 10, 11, 12]);
+    assert.deepEqual(cursor.fetchNextSync(10), []);
+  });
+
+  it('should not read ahead with prefetch false', async () => {
+    const methods = [];
+    const other = await Database.open(':memory:');
+    other.on('task', (event) => event.phase === 'complete' && methods.push(event.method));
+    const cursor = other.openCursor('SELECT id FROM cp_items ORDER BY id', [], { prefetch: false });
+    const before = methods.length;
+    assert.equal((await cursor.fetchNext(10)).length, 10);
+    await settle();
+    assert.deepEqual(methods.slice(before), ['fetchNext']);
+    assert.equal((await cursor.fetchNext(10))[0].id, 11);
+    cursor.close();
+  });
+});
+
+describe('cursor fetchSize', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE cs_items (id INTEGER PRIMARY KEY, body TEXT)');
+    db.executeBatchSync(
+      'INSERT INTO cs_items VALUES ($1, $2)',
+      Array.from({ length: 3000 }, (_, i) => [i + 1, i < 1000 ? 'x' : 'y'.repeat(20000)])
+    );
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should start at 256 rows and read every row without a count', async () => {
+    const cursor = db.openCursor('SELECT id FROM cs_items ORDER BY id');
+    assert.equal(cursor.fetchSize, 256);
+    const first = await cursor.fetchNext();
+    assert.equal(first.length, 256);
+    let total = first.length;
+    for (let rows; (rows = await cursor.fetchNext()).length > 0; ) {
+      total += rows.length;
+    }
+    assert.equal(total, 3000);
+  });
+
+  it('should shrink batches of wide rows', async () => {
+    const cursor = db.openCursor('SELECT id, body FROM cs_items WHERE id > 1000 ORDER BY id');
+    await cursor.fetchNext();
+    assert.ok(cursor.fetchSize < 256, `fetchSize ${cursor.fetchSize}`);
+    assert.ok(cursor.fetchSize >= 16);
+    const rows = await cursor.fetchNext();
+    assert.ok(rows.length >= 16 && rows.length < 256, `${rows.length} rows`);
+    assert.equal(rows[0].id, 1257);
+    cursor.close();
+  });
+
+  it('should shrink batches for a slow consumer', async () => {
+    const cursor = db.openCursor('SELECT id FROM cs_items WHERE id <= 1000 ORDER BY id', [], {
+      prefetch: false,
+    });
+    await cursor.fetchNext(10);
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    await cursor.fetchNext(10);
+    assert.ok(cursor.fetchSize < 256, `fetchSize ${cursor.fetchSize}`);
+    cursor.close();
+  });
+
+  it('should use a fixed fetchSize', () => {
+    const cursor = db.openCursor('SELECT id FROM cs_items ORDER BY id', [], { fetchSize: 100 });
+    assert.equal(cursor.fetchSize, 100);
+    assert.equal(cursor.fetchNextSync().length, 100);
+    assert.equal(cursor.fetchNextSync(3).length, 3);
+    assert.equal(cursor.fetchSize, 100);
+    cursor.close();
+
+    assert.throws(() => db.openCursor('SELECT 1', [], { fetchSize: 0 }), /fetchSize must be a positive integer/);
+    assert.throws(() => db.openCursor('SELECT 1', [], { fetchSize: 2.5 }), /fetchSize must be a positive integer/);
+  });
+});
+
+describe('statement counters', () => {
+  it('should count statements and the rows they change', async () => {
+    const db = await Database.open(':memory:');
+    assert.deepEqual([db.queryCount, db.changes, db.totalChanges], [0, 0, 0]);
+
+    db.execSync('CREATE TABLE sc_items (id INTEGER PRIMARY KEY, name TEXT)');
+    assert.deepEqual([db.queryCount, db.changes, db.totalChanges], [1, 0, 0]);
+
+    await db.execute("INSERT INTO sc_items VALUES (1, 'a'), (2, 'b'), (3, 'c')");
+    assert.deepEqual([db.queryCount, db.changes, db.totalChanges], [2, 3, 3]);
+
+    await db.query('SELECT * FROM sc_items');
+    db.querySync('SELECT * FROM sc_items');
+    assert.deepEqual([db.queryCount, db.changes, db.totalChanges], [4, 3, 3]);
+
+    db.prepare("UPDATE sc_items SET name = 'z' WHERE id = $1").executeSync([1]);
+    assert.deepEqual([db.queryCount, db.changes, db.totalChanges], [5, 1, 4]);
+
+    await db.executeBatch('INSERT INTO sc_items VALUES ($1, $2)', [[4, 'd'], [5, 'e']]);
+    assert.deepEqual([db.queryCount, db.changes, db.totalChanges], [6, 2, 6]);
+
+    db.execSync("DELETE FROM sc_items WHERE id > 3; SELECT * FROM sc_items");
+    assert.deepEqual([db.queryCount, db.changes, db.totalChanges], [8, 2, 8]);
+    await db.close();
+  });
+
+  it('should count transactions, pipelines and cursors, but not failures', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE sc_tx (id INTEGER PRIMARY KEY)');
+    const start = db.queryCount;
+
+    const tx = await db.begin();
+    await tx.execute('INSERT INTO sc_tx VALUES (1), (2)');
+    await tx.rollback();
+    assert.equal(db.totalChanges, 2);
+
+    await db.pipeline().execute('INSERT INTO sc_tx VALUES (3)').query('SELECT 1').run();
+    assert.equal(db.changes, 1);
+    db.openCursor('SELECT * FROM sc_tx').close();
+    await assert.rejects(db.execute('INSERT INTO sc_missing VALUES (1)'));
+    assert.throws(() => db.querySync('SELECT * FROM sc_missing'));
+    assert.equal(db.queryCount - start, 4);
+    assert.equal(db.totalChanges, 3);
+    await db.close();
+  });
+});
+
+// ============================================================
+// close() modes
+// ============================================================
+
+describe('close modes', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE cm (id INTEGER PRIMARY KEY, v TEXT)');
+    const rows = [];
+    for (let i = 0; i < 20000; i++) rows.push(`(${i}, 'v${i}')`);
+    db.execSync(`INSERT INTO cm VALUES ${rows.join(', ')}`);
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  const isClosed = (err) => err.code === 'DatabaseClosed' && err.message === 'Database is closed';
+
+  it('should let pending calls finish when draining', async () => {
+    const pending = [
+      db.query('SELECT * FROM cm ORDER BY v DESC'),
+      db.prepare('SELECT * FROM cm').query(),
+      db.execute("INSERT INTO cm VALUES (20000, 'last')"),
+    ];
+    await db.close({ mode: 'drain' });
+    const [sorted, all, insert] = await Promise.all(pending);
+    assert.equal(sorted.length, 20000);
+    assert.equal(all.length, 20000);
+    assert.equal(insert.changes, 1);
+  });
+
+  it('should reject calls issued after close', async () => {
+    const stmt = db.prepare('SELECT * FROM cm');
+    const closing = db.close();
+    await assert.rejects(db.query('SELECT 1'), isClosed);
+    await assert.rejects(stmt.query(), isClosed);
+    await assert.rejects(db.pipeline().query('SELECT 1').run(), isClosed);
+    await closing;
+    await assert.rejects(db.begin(), isClosed);
+  });
+
+  it('should reject pending calls with DatabaseClosed when aborting', async () => {
+    const tx = await db.begin();
+    const pending = [
+      db.query('SELECT * FROM cm'),
+      db.query('SELECT * FROM cm ORDER BY v DESC'),
+      tx.query('SELECT * FROM cm'),
+    ];
+    const settled = Promise.allSettled(pending);
+    await db.close({ mode: 'abort' });
+    for (const result of await settled) {
+      assert.equal(result.status, 'rejected');
+      assert.ok(isClosed(result.reason), result.reason.message);
+      assert.ok(result.reason instanceof Error);
+    }
+  });
+
+  it('should abort what is left once timeoutMs passes', async () => {
+    db.enableFaultInjection({ latencyMs: 500 });
+    const settled = Promise.allSettled(Array.from({ length: 5 }, () => db.query('SELECT * FROM cm')));
+    const started = Date.now();
+    await db.close({ timeoutMs: 20 });
+    assert.ok(Date.now() - started < 450);
+    const results = await settled;
+    assert.ok(results.some((r) => r.status === 'rejected'));
+    for (const result of results) {
+      if (result.status === 'rejected') assert.ok(isClosed(result.reason), result.reason.message);
+    }
+    db.disableFaultInjection();
+  });
+
+  it('should reject calls queued on a dedicated thread when aborting', async () => {
+    const other = await Database.open(':memory:', { dedicatedThread: true });
+    const settled = Promise.allSettled([
+      other.query('SELECT * FROM cm'),
+      other.query('SELECT * FROM cm ORDER BY v DESC'),
+    ]);
+    await other.close({ mode: 'abort' });
+    for (const result of await settled) {
+      assert.equal(result.status, 'rejected');
+      assert.ok(isClosed(result.reason));
+    }
+  });
+
+  it('should reject async calls after closeSync()', async () => {
+    const other = await Database.open(':memory:');
+    other.closeSync();
+    await assert.rejects(other.query('SELECT 1'), isClosed);
+  });
+
+  it('should validate options', () => {
+    assert.throws(() => db.close({ mode: 'later' }), /mode must be 'drain' or 'abort'/);
+    assert.throws(() => db.close({ timeoutMs: -1 }), /timeoutMs must be a non-negative number/);
+  });
+});
+
+// ============================================================
+// Vector buffers
+// ============================================================
+
+describe('vector buffers', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE vb (id INTEGER PRIMARY KEY, emb VECTOR(3), name TEXT)');
+    db.executeSync("INSERT INTO vb VALUES (1, '[1, 2, 3]', 'a')");
+    db.executeSync("INSERT INTO vb VALUES (2, NULL, 'b')");
+    db.executeSync("INSERT INTO vb VALUES (3, '[4, 5, 6]', 'c')");
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  const floats = ({ buffer }) => Array.from(new Float32Array(buffer.buffer, buffer.byteOffset, buffer.length / 4));
+
+  it('should pack a vector column into one buffer', async () => {
+    for (const raw of [
+      await db.queryRaw('SELECT * FROM vb ORDER BY id', [], { vectors: 'buffer' }),
+      db.queryRawSync('SELECT * FROM vb ORDER BY id', [], { vectors: 'buffer' }),
+    ]) {
+      assert.deepEqual(raw.columns, ['id', 'emb', 'name']);
+      assert.deepEqual(raw.rows, [[1, null, 'a'], [2, null, 'b'], [3, null, 'c']]);
+      const { buffer, dim, dtype, stride, nulls } = raw.vectors.emb;
+      assert.ok(Buffer.isBuffer(buffer));
+      assert.equal(buffer.length, 36);
+      assert.deepEqual({ dim, dtype, stride, nulls }, { dim: 3, dtype: 'float32', stride: 12, nulls: [1] });
+      assert.deepEqual(floats(raw.vectors.emb), [1, 2, 3, 0, 0, 0, 4, 5, 6]);
+    }
+  });
+
+  it('should key columns like query() and keep other options', async () => {
+    const other = await Database.open(':memory:', { columnCase: 'camel' });
+    const raw = await other.queryRaw('SELECT emb AS doc_emb, id FROM vb ORDER BY id', [], {
+      vectors: 'buffer',
+      offset: 1,
+      preview: 1,
+    });
+    assert.deepEqual(Object.keys(raw.vectors), ['docEmb']);
+    assert.deepEqual(raw.vectors.docEmb.nulls, [0]);
+    assert.deepEqual(floats(raw.vectors.docEmb), [0, 0, 0, 4, 5, 6]);
+    assert.deepEqual(raw.preview, [{ docEmb: null, id: 2 }]);
+  });
+
+  it('should leave results without vectors and other methods alone', async () => {
+    const raw = db.queryRawSync('SELECT name FROM vb ORDER BY id', [], { vectors: 'buffer' });
+    assert.deepEqual(raw.vectors, {});
+    assert.ok(db.queryRawSync('SELECT emb FROM vb WHERE id = 1').rows[0][0] instanceof Float32Array);
+    const [row] = await db.query('SELECT emb FROM vb WHERE id = 1', [], { vectors: 'buffer' });
+    assert.ok(row.emb instanceof Float32Array);
+  });
+
+  it('should validate the option', () => {
+    assert.throws(
+      () => db.queryRawSync('SELECT 1', [], { vectors: 'tensor' }),
+      /vectors must be 'typedArray' or 'buffer'/,
+    );
+  });
+});
+
+// ============================================================
+// Health checks
+// ============================================================
+
+describe('health checks', () => {
+  it('should report whether the database is open', async () => {
+    const db = await Database.open(':memory:');
+    assert.equal(db.isOpen, true);
+    await db.close();
+    assert.equal(db.isOpen, false);
+
+    const other = await Database.open(':memory:');
+    other.closeSync();
+    assert.equal(other.isOpen, false);
+  });
+
+  it('should report whether a transaction is active', async () => {
+    const db = await Database.open(':memory:');
+    const tx = await db.begin();
+    assert.equal(tx.isActive, true);
+    await tx.commit();
+    assert.equal(tx.isActive, false);
+    const sync = db.beginSync();
+    sync.rollbackSync();
+    assert.equal(sync.isActive, false);
+    await db.close();
+  });
+
+  it('should ping an open database and reject once it is closed', async () => {
+    const db = await Database.open(':memory:', { dedicatedThread: true });
+    const before = db.queryCount;
+    const ms = await db.ping();
+    assert.equal(typeof ms, 'number');
+    assert.ok(ms >= 0);
+    assert.equal(db.queryCount, before);
+    await db.close();
+    await assert.rejects(db.ping(), (err) => err.code === 'DatabaseClosed');
+  });
+
+  it('should fail a ping when the database was closed through another handle', async () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-ping-'));
+    const db = await Database.open(path.join(dir, 'db'));
+    const other = await Database.open(path.join(dir, 'db'));
+    await other.close();
+    assert.equal(db.isOpen, false);
+    await assert.rejects(db.ping(), (err) => err.code === 'DatabaseClosed');
+    await db.close();
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+});
+
+describe('transaction toSql', () => {
+  it('should return the statements run so far with literals inlined', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT, score FLOAT, ok BOOLEAN)');
+    const tx = await db.begin({ recordSql: true });
+    await tx.execute('INSERT INTO people VALUES ($1, $2, $3, $4)', [1, "O'Brien", 1.5, true]);
+    tx.executeSync('INSERT INTO people VALUES (?, ?, ?, ?)', [2, 'Ann', 2, null]);
+    await tx.query('SELECT name FROM people WHERE id = ?', [1]);
+    assert.equal(
+      tx.toSql(),
+      "INSERT INTO people VALUES (1, 'O''Brien', 1.5, TRUE);\n" +
+        "INSERT INTO people VALUES (2, 'Ann', 2, NULL);\n" +
+        'SELECT name FROM people WHERE id = 1;\n',
+    );
+    await tx.commit();
+    assert.match(tx.toSql(), /^INSERT/);
+    await db.close();
+  });
+
+  it('should replay to the same rows', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE replayed (id INTEGER PRIMARY KEY, f FLOAT, s TEXT, j JSON, ts TIMESTAMP)');
+    const tx = db.beginSync({ recordSql: true });
+    tx.executeBatchSync('INSERT INTO replayed VALUES (?, ?, ?, ?, ?)', [
+      [1, 0.1, 'a;b -- c', '{"k": "it\'s"}', new Date('2024-01-02T03:04:05Z')],
+      [2, -1e-7, '', null, null],
+    ]);
+    tx.executeSync('UPDATE replayed SET s = ? WHERE id = ?', ['x', 2]);
+    tx.commitSync();
+
+    const read = () => db.querySync('SELECT * FROM replayed ORDER BY id');
+    const rows = read();
+    db.execSync('DELETE FROM replayed');
+    db.execSync(tx.toSql());
+    assert.deepEqual(read(), rows);
+    await db.close();
+  });
+
+  it('should keep placeholders with the params in a comment', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE placeheld (id INTEGER PRIMARY KEY, s TEXT)');
+    const tx = db.beginSync({ recordSql: true });
+    tx.executeSync('INSERT INTO placeheld VALUES ($1, $2);', [1, 'a']);
+    await tx.executeBatch('INSERT INTO placeheld VALUES (?, ?)', [[2, 'b'], [3, 'c']]);
+    tx.querySync('SELECT id FROM placeheld -- all rows');
+    assert.equal(
+      tx.toSql({ params: 'placeholders' }),
+      'INSERT INTO placeheld VALUES ($1, $2);\n-- params: [1,"a"]\n' +
+        'INSERT INTO placeheld VALUES (?, ?);\n-- batch: [[2,"b"],[3,"c"]]\n' +
+        'SELECT id FROM placeheld -- all rows\n;\n',
+    );
+    assert.throws(() => tx.toSql({ params: 'json' }), /params must be 'inline' or 'placeholders'/);
+    tx.rollbackSync();
+    await db.close();
+  });
+
+  it('should leave out failed statements', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE failing (id INTEGER PRIMARY KEY)');
+    const tx = await db.begin({ recordSql: true });
+    await tx.execute('INSERT INTO failing VALUES (?)', [1]);
+    await assert.rejects(tx.execute('INSERT INTO failing VALUES (?)', [1]));
+    assert.throws(() => tx.querySync('SELECT * FROM missing'));
+    assert.equal(tx.toSql(), 'INSERT INTO failing VALUES (1);\n');
+    await tx.rollback();
+    await db.close();
+  });
+
+  it('should throw without recordSql', async () => {
+    const db = await Database.open(':memory:');
+    const tx = db.beginSync();
+    assert.throws(() => tx.toSql(), /recordSql/);
+    tx.rollbackSync();
+    await db.close();
+  });
+});
+
+describe('column statistics', () => {
+  it('should analyze a table without statistics and read them back', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE Profiled (id INTEGER PRIMARY KEY, Name TEXT, score FLOAT, ok BOOLEAN)');
+    db.executeBatchSync('INSERT INTO Profiled VALUES (?, ?, ?, ?)', [
+      [1, 'rex', 1.5, true],
+      [2, null, null, null],
+      [3, 'amy', -2, false],
+      [4, 'amy', 7, true],
+    ]);
+    const first = await db.columnStats('profiled', 'name');
+    assert.deepEqual(first, {
+      table: 'Profiled',
+      column: 'Name',
+      rows: 4,
+      sampled: 4,
+      nullFraction: 0.25,
+      ndv: 2,
+      min: 'amy',
+      max: 'rex',
+      avgWidth: first.avgWidth,
+      analyzed: true,
+    });
+    assert.equal(typeof first.avgWidth, 'number');
+
+    const score = db.columnStatsSync('Profiled', 'score');
+    assert.equal(score.analyzed, false);
+    assert.equal(score.min, -2);
+    assert.equal(score.max, 7);
+    const ok = db.columnStatsSync('Profiled', 'ok');
+    assert.equal(ok.min, false);
+    assert.equal(ok.max, true);
+    await db.close();
+  });
+
+  it('should report the statistics of the last ANALYZE', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE stale_stats (id INTEGER PRIMARY KEY, v INTEGER)');
+    db.executeSync('INSERT INTO stale_stats VALUES (1, 10)');
+    assert.equal(db.columnStatsSync('stale_stats', 'v').rows, 1);
+    db.executeSync('INSERT INTO stale_stats VALUES (2, NULL)');
+    assert.equal(db.columnStatsSync('stale_stats', 'v').rows, 1);
+    db.execSync('ANALYZE stale_stats');
+    const stats = db.columnStatsSync('stale_stats', 'v');
+    assert.equal(stats.rows, 2);
+    assert.equal(stats.nullFraction, 0.5);
+    assert.equal(stats.max, 10);
+    await db.close();
+  });
+
+  it('should report an empty table', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE empty_stats (id INTEGER PRIMARY KEY, v TEXT)');
+    const stats = db.columnStatsSync('empty_stats', 'v');
+    assert.equal(stats.rows, 0);
+    assert.equal(stats.nullFraction, 0);
+    assert.equal(stats.ndv, 0);
+    assert.equal(stats.min, null);
+    assert.equal(stats.max, null);
+    await db.close();
+  });
+
+  it('should not analyze unless the policy allows ANALYZE', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE guarded_stats (id INTEGER PRIMARY KEY, v INTEGER)');
+    const readOnly = db.createRestrictedHandle({ allow: ['SELECT'] });
+    assert.throws(() => readOnly.columnStatsSync('guarded_stats', 'v'), /ANALYZE statements are not allowed on a restricted handle/);
+    db.setPolicy({ deny: ['ANALYZE'] });
+    await assert.rejects(db.columnStats('guarded_stats', 'v'), /ANALYZE statements are not allowed/);
+    db.setPolicy(null);
+    assert.equal(db.columnStatsSync('guarded_stats', 'v').analyzed, true);
+    db.setPolicy({ deny: ['ANALYZE'] });
+    assert.equal(readOnly.columnStatsSync('guarded_stats', 'v').analyzed, false);
+    await db.close();
+  });
+
+  it('should reject unknown tables and columns, and tables the policy hides', async () => {
+    const db = await Database.open(':memory:');
+    db.execSync('CREATE TABLE known_stats (id INTEGER PRIMARY KEY)');
+    await assert.rejects(db.columnStats('missing_stats', 'id'), /not found/);
+    assert.throws(() => db.columnStatsSync('known_stats', 'nope'), /Column 'nope' not found in table 'known_stats'/);
+    db.setPolicy({ allowTables: ['other'] });
+    assert.throws(() => db.columnStatsSync('known_stats', 'id'));
+    db.setPolicy(null);
+    await db.close();
+  });
+});
+
+describe('database manager', () => {
+  let dir;
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-manager-'));
+  });
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it('should share one open database per path', async () => {
+    const manager = new DatabaseManager();
+    const tenant = path.join(dir, 'a');
+    const db = await manager.get(tenant);
+    db.execSync('CREATE TABLE shared (id INTEGER PRIMARY KEY)');
+    const [again, concurrent] = await Promise.all([manager.get(tenant), manager.get(tenant)]);
+    again.executeSync('INSERT INTO shared VALUES (1)');
+    assert.equal(concurrent.querySync('SELECT * FROM shared').length, 1);
+    assert.equal(manager.size, 1);
+    assert.ok(manager.has(tenant));
+    assert.ok(!manager.has(path.join(dir, 'b')));
+    await manager.close();
+    assert.equal(db.isOpen, false);
+    assert.equal(manager.size, 0);
+    assert.throws(() => manager.get(tenant), /DatabaseManager is closed/);
+  });
+
+  it('should close the least recently got idle database to stay under maxOpen', async () => {
+    const manager = new DatabaseManager({ maxOpen: 2 });
+    const a = await manager.get(path.join(dir, 'a'));
+    const b = await manager.get(path.join(dir, 'b'));
+    await manager.get(path.join(dir, 'a'));
+    await manager.get(path.join(dir, 'c'));
+    assert.equal(manager.size, 2);
+    assert.ok(manager.has(path.join(dir, 'a')));
+    assert.ok(!manager.has(path.join(dir, 'b')));
+    assert.equal(a.isOpen, true);
+    assert.equal(b.isOpen, false);
+    await assert.rejects(b.query('SELECT 1'), (err) => err.code === 'DatabaseClosed');
+    const reopened = await manager.get(path.join(dir, 'b'));
+    assert.equal(reopened.isOpen, true);
+    assert.equal(manager.size, 2);
+    await manager.close();
+  });
+
+  it('should keep databases with an open transaction or pending calls', async () => {
+    const manager = new DatabaseManager({ maxOpen: 1 });
+    const a = await manager.get(path.join(dir, 'a'));
+    const tx = await a.begin();
+    assert.throws(() => manager.get(path.join(dir, 'b')), /not enough of them are idle/);
+    await tx.commit();
+    a.enableFaultInjection({ latencyMs: 50 });
+    const pending = a.query('SELECT 1');
+    assert.throws(() => manager.get(path.join(dir, 'b')), /not enough of them are idle/);
+    await pending;
+    await manager.get(path.join(dir, 'b'));
+    assert.equal(a.isOpen, false);
+    await manager.close();
+  });
+
+  it('should close databases not got within idleMs', async () => {
+    const manager = new DatabaseManager({ idleMs: 20 });
+    const a = await manager.get(path.join(dir, 'a'));
+    await new Promise((resolve) => setTimeout(resolve, 40));
+    await manager.get(path.join(dir, 'b'));
+    assert.equal(a.isOpen, false);
+    assert.deepEqual([manager.has(path.join(dir, 'a')), manager.size], [false, 1]);
+    await manager.close();
+  });
+
+  it('should open with the manager options and reopen databases closed directly', async () => {
+    const manager = new DatabaseManager({ options: { columnCase: 'camel' } });
+    const tenant = path.join(dir, 'a');
+    const db = await manager.get(tenant);
+    db.execSync('CREATE TABLE cased (user_id INTEGER PRIMARY KEY)');
+    db.executeSync('INSERT INTO cased VALUES (1)');
+    assert.deepEqual(db.querySync('SELECT user_id FROM cased'), [{ userId: 1 }]);
+    await db.close();
+    assert.equal(manager.size, 0);
+    const reopened = await manager.get(tenant);
+    assert.deepEqual(reopened.querySync('SELECT user_id FROM cased'), [{ userId: 1 }]);
+    await manager.close();
+  });
+
+  it('should reject invalid options', () => {
+    assert.throws(() => new DatabaseManager({ maxOpen: 0 }), /maxOpen must be at least 1/);
+    assert.throws(() => new DatabaseManager({ idleMs: -1 }), /idleMs must be a non-negative number/);
+  });
+});
+
+describe('index advisor', () => {
+  const fill = (db, table) => {
+    db.execSync(`CREATE TABLE ${table} (id INTEGER PRIMARY KEY, v INTEGER, w TEXT)`);
+    const rows = [];
+    for (let i = 1; i <= 20000; i++) rows.push([i, i % 1000, `w${i % 50}`]);
+    db.executeBatchSync(`INSERT INTO ${table} VALUES (?, ?, ?)`, rows);
+  };
+
+  it('should list indexes in a dry run and create those that help', async () => {
+    const db = await Database.open(':memory:');
+    fill(db, 'advised');
+    db.enableIndexAdvisor();
+    for (let i = 0; i < 3; i++) await db.query('SELECT * FROM advised WHERE v = ?', [i]);
+    await db.query('SELECT * FROM advised WHERE v = 7');
+
+    const dry = await db.applyAdvisedIndexes({ dryRun: true });
+    assert.deepEqual(
+      dry.map(({ meanMs, ...rest }) => rest),
+      [
+        {
+          table: 'advised',
+          column: 'v',
+          sql: 'CREATE INDEX "idx_advised_v" ON "advised" ("v")',
+          query: 'SELECT * FROM advised WHERE v = ?',
+          calls: 4,
+          beforeMs: null,
+          afterMs: null,
+          created: false,
+        },
+      ],
+    );
+    assert.ok(dry[0].meanMs > 0);
+    assert.doesNotMatch(JSON.stringify(db.querySync('EXPLAIN SELECT * FROM advised WHERE v = 1')), /idx_advised_v/);
+
+    const [applied] = await db.applyAdvisedIndexes();
+    assert.equal(applied.created, true);
+    assert.ok(applied.afterMs < applied.beforeMs);
+    assert.match(JSON.stringify(db.querySync('EXPLAIN SELECT * FROM advised WHERE v = 1')), /idx_advised_v/);
+    assert.deepEqual(await db.applyAdvisedIndexes(), []);
+    await db.close();
+  });
+
+  it('should not advise the primary key or columns already indexed', async () => {
+    const db = await Database.open(':memory:');
+    fill(db, 'indexed');
+    db.execSync('CREATE INDEX idx_indexed_w ON indexed (w)');
+    db.enableIndexAdvisor();
+    db.querySync('SELECT * FROM indexed WHERE id = 5');
+    db.querySync('SELECT * FROM indexed WHERE w = ?', ['w3']);
+    db.querySync('SELECT id FROM indexed WHERE w = $1 AND v > $2', ['w3', 10]);
+    db.querySync('SELECT * FROM indexed a JOIN indexed b ON a.id = b.v WHERE a.v = 1');
+    db.querySync('SELECT w FROM indexed WHERE v BETWEEN 1 AND 3 AND id > 10');
+    const advised = await db.applyAdvisedIndexes({ dryRun: true });
+    assert.deepEqual(
+      advised.map(({ column, query }) => [column, query]),
+      [['v', 'SELECT w FROM indexed WHERE v BETWEEN ? AND ? AND id > ?']],
+    );
+    await db.close();
+  });
+
+  it('should keep only the slowest fingerprints and forget them when disabled', async () => {
+    const db = await Database.open(':memory:');
+    fill(db, 'capped');
+    db.enableIndexAdvisor({ slowest: 1 });
+    db.querySync('SELECT * FROM capped WHERE v IN (1, 2, 3) ORDER BY id');
+    db.querySync('SELECT * FROM capped WHERE w = ?', ['w1']);
+    db.querySync('SELECT * FROM capped WHERE v IN (4, 5) ORDER BY id');
+    db.querySync('SELECT * FROM capped WHERE w = ?', ['w2']);
+    const kept = await db.applyAdvisedIndexes({ dryRun: true });
+    assert.equal(kept.length, 1);
+    assert.ok(
+      ['SELECT * FROM capped WHERE v IN (?) ORDER BY id', 'SELECT * FROM capped WHERE w = ?'].includes(kept[0].query),
+    );
+
+    db.disableIndexAdvisor();
+    assert.deepEqual(await db.applyAdvisedIndexes({ dryRun: true }), []);
+    db.querySync('SELECT * FROM capped WHERE v = 1');
+    assert.deepEqual(await db.applyAdvisedIndexes({ dryRun: true }), []);
+    await db.close();
+  });
+
+  it('should reject invalid options', async () => {
+    const db = await Database.open(':memory:');
+    assert.throws(() => db.enableIndexAdvisor({ slowest: 0 }), /slowest must be at least 1/);
+    assert.throws(() => db.enableIndexAdvisor({ windowMs: 0 }), /windowMs must be a positive number/);
+    await db.close();
+  });
+});
+
+// ============================================================
+// Dump and restore
+// ============================================================
+
+describe('dump and restore', () => {
+  let tmpDir;
+
+  beforeEach(() => {
+    tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-dump-'));
+  });
+
+  afterEach(() => {
+    fs.rmSync(tmpDir, { recursive: true, force: true });
+  });
+
+  async function source() {
+    const db = await Database.open(path.join(tmpDir, 'source'));
+    await db.exec(`
+      CREATE TABLE dump_items (id INTEGER PRIMARY KEY AUTO_INCREMENT, name TEXT NOT NULL DEFAULT 'x', at TIMESTAMP, v VECTOR(3), meta JSON, ok BOOLEAN, price FLOAT);
+      CREATE TABLE dump_notes (id INTEGER PRIMARY KEY, item_id INTEGER REFERENCES dump_items(id) ON DELETE CASCADE, body TEXT);
+      CREATE UNIQUE INDEX dump_items_name ON dump_items(name);
+      CREATE INDEX dump_notes_body ON dump_notes(body);
+      CREATE VIEW dump_names AS SELECT id, name FROM dump_items
+    `);
+    await db.execute(
+      `INSERT INTO dump_items (name, at, v, meta, ok, price) VALUES ('it''s', '2024-01-02 03:04:05', '[1,2,3]', '{"k":1}', true, 1.5)`,
+    );
+    await db.execute(`INSERT INTO dump_items (name) VALUES ('plain')`);
+    await db.execute(`INSERT INTO dump_notes VALUES (1, 1, NULL), (2, 2, 'n')`);
+    return db;
+  }
+
+  it('should round-trip tables, indexes, views and rows', async () => {
+    const db = await source();
+    const sql = await db.dump();
+    assert.ok(sql.indexOf('CREATE TABLE "dump_items"') < sql.indexOf('CREATE TABLE "dump_notes"'));
+    await db.close();
+
+    const copy = await Database.open(path.join(tmpDir, 'copy'));
+    assert.equal((await copy.restore(sql)).changes, 4);
+    const items = await copy.query('SELECT * FROM dump_items ORDER BY id');
+    assert.equal(items.length, 2);
+    assert.equal(items[0].name, "it's");
+    assert.equal(new Date(items[0].at).toISOString(), '2024-01-02T03:04:05.000Z');
+    assert.deepEqual(Array.from(items[0].v), [1, 2, 3]);
+    assert.deepEqual(JSON.parse(items[0].meta), { k: 1 });
+    assert.equal(items[0].ok, true);
+    assert.equal(items[0].price, 1.5);
+    assert.equal(items[1].at, null);
+    assert.deepEqual(await copy.query('SELECT name FROM dump_names ORDER BY id'), [{ name: "it's" }, { name: 'plain' }]);
+    const { lastInsertRowId } = await copy.execute(`INSERT INTO dump_items (name) VALUES ('third')`);
+    assert.equal(lastInsertRowId, 3);
+    await assert.rejects(copy.execute(`INSERT INTO dump_items (name) VALUES ('plain')`), /unique/i);
+    await copy.execute('DELETE FROM dump_items WHERE id = 1');
+    assert.deepEqual(await copy.query('SELECT id FROM dump_notes'), [{ id: 2 }]);
+    assert.equal(await copy.dump({ schemaOnly: true }), await copy.dump({ schemaOnly: true }));
+    await copy.close();
+  });
+
+  it('should leave out the statistics tables and quote names in views', async () => {
+    const db = await Database.open(path.join(tmpDir, 'quoted'));
+    await db.exec(`
+      CREATE TABLE "we""ird" (id INTEGER PRIMARY KEY, "my col" TEXT);
+      INSERT INTO "we""ird" VALUES (1, 'it''s my col');
+      CREATE VIEW weird_view AS SELECT id, "my col" FROM "we""ird" WHERE "my col" <> 'my col';
+      ANALYZE "we""ird"
+    `);
+    const sql = await db.dump();
+    assert.ok(!sql.includes('_sys_'));
+    assert.ok(sql.includes(`CREATE VIEW "weird_view" AS SELECT id, "my col" FROM "we""ird" WHERE ("my col" <> 'my col');`));
+    await db.close();
+
+    const copy = await Database.open(path.join(tmpDir, 'quoted-copy'));
+    await copy.exec('CREATE TABLE analyzed (id INTEGER PRIMARY KEY); ANALYZE analyzed');
+    assert.equal((await copy.restore(sql)).changes, 1);
+    assert.deepEqual(await copy.query('SELECT * FROM "we""ird"'), [{ id: 1, 'my col': "it's my col" }]);
+    assert.ok((await copy.dump()).includes(`CREATE VIEW "weird_view" AS SELECT id, "my col" FROM "we""ird"`));
+    await copy.close();
+  });
+
+  it('should pass the script to a callback in pieces', async () => {
+    const db = await source();
+    const whole = await db.dump({ tables: ['dump_notes'] });
+    const pieces = [];
+    assert.equal(await db.dump({ tables: ['dump_notes'] }, (sql) => { pieces.push(sql); }), undefined);
+    assert.equal(pieces.length, 2);
+    assert.equal(pieces.join(''), whole);
+    assert.ok(!whole.includes('CREATE TABLE "dump_items"'));
+    assert.ok(!whole.includes('CREATE VIEW'));
+
+    const schema = await db.dump({ schemaOnly: true });
+    assert.ok(schema.includes('CREATE VIEW "dump_names"'));
+    assert.ok(!schema.includes('INSERT'));
+
+    await assert.rejects(db.dump(null, () => { throw new Error('stop here'); }), /stop here/);
+    await db.close();
+  });
+
+  it('should split rows into INSERTs of at most 500', async () => {
+    const db = await Database.open(path.join(tmpDir, 'many'));
+    await db.execute('CREATE TABLE dump_many (id INTEGER PRIMARY KEY, v INTEGER)');
+    await db.executeBatch('INSERT INTO dump_many VALUES ($1, $2)', Array.from({ length: 1201 }, (_, i) => [i, i * 2]));
+    const pieces = [];
+    await db.dump(null, (sql) => { pieces.push(sql); });
+    assert.equal(pieces.filter((sql) => sql.startsWith('INSERT')).length, 3);
+    await db.close();
+
+    const copy = await Database.open(path.join(tmpDir, 'many-copy'));
+    await copy.restore(pieces.join(''));
+    assert.deepEqual(await copy.queryOne('SELECT COUNT(*) AS n, SUM(v) AS s FROM dump_many'), { n: 1201, s: 1201 * 1200 });
+    await copy.close();
+  });
+
+  it('should follow the statement policy', async () => {
+    const db = await source();
+    db.setPolicy({ allowTables: ['dump_items'] });
+    await assert.rejects(db.dump(), /SQL policy violation/);
+    const pieces = [];
+    await assert.rejects(db.dump({ tables: ['dump_notes'] }, (sql) => { pieces.push(sql); }), /SQL policy violation/);
+    assert.deepEqual(pieces, []);
+    assert.ok((await db.dump({ tables: ['dump_items'] })).includes('INSERT INTO "dump_items"'));
+    db.setPolicy(null);
+
+    const writer = db.createRestrictedHandle({ allow: ['INSERT'] });
+    await assert.rejects(writer.dump({ tables: ['dump_items'] }), /SQL policy violation/);
+    await assert.rejects(writer.restore('CREATE TABLE dump_more (id INTEGER PRIMARY KEY)'), /Restore statement 1: SQL policy violation/);
+    await db.close();
+  });
+
+  it('should undo a failed restore', async () => {
+    const db = await source();
+    const sql = await db.dump();
+    await db.close();
+
+    const copy = await Database.open(path.join(tmpDir, 'copy'));
+    await assert.rejects(
+      copy.restore(`${sql}INSERT INTO "dump_notes" ("id", "item_id", "body") VALUES (1, 1, 'again');\n`),
+      /Restore statement 8: /,
+    );
+    assert.deepEqual(await copy.query('SHOW TABLES'), []);
+    await assert.rejects(copy.restore('DROP TABLE dump_items'), /Restore statement 1: .*not DROP TABLE/);
+    await copy.close();
+  });
+});
+
+describe('commit window', () => {
+  it('should commit concurrent executes together', async () => {
+    const db = await Database.open(':memory:', { commitWindowMs: 20 });
+    await db.exec('CREATE TABLE grouped (id INTEGER PRIMARY KEY, v TEXT)');
+    const dequeued = [];
+    db.on('task', (event) => event.phase === 'dequeue' && dequeued.push(event.method));
+    const insert = db.prepare('INSERT INTO grouped VALUES ($1, $2)');
+    const started = Date.now();
+    const results = await Promise.all([
+      ...Array.from({ length: 20 }, (_, i) => db.execute('INSERT INTO grouped VALUES (?, ?)', [i, 'a'])),
+      insert.execute([100, 'b']),
+      db.execute("UPDATE grouped SET v = 'c' WHERE id < 5"),
+    ]);
+    assert.ok(Date.now() - started >= 15);
+    assert.deepEqual(
+      results.map(({ changes, lastInsertRowId }) => [changes, lastInsertRowId]),
+      [...Array.from({ length: 21 }, () => [1, null]), [5, null]],
+    );
+    assert.deepEqual(db.querySync("SELECT COUNT(*) AS n FROM grouped WHERE v = 'c'"), [{ n: 5 }]);
+    await new Promise((resolve) => setTimeout(resolve, 20));
+    assert.deepEqual(dequeued, ['execute']);
+    await db.close();
+  });
+
+  it('should run the calls one by one when one of them fails', async () => {
+    const db = await Database.open(':memory:', { commitWindowMs: 10 });
+    await db.exec('CREATE TABLE regrouped (id INTEGER PRIMARY KEY)');
+    const changes = [];
+    db.on('change', (event) => changes.push(event.rowId));
+    const results = await Promise.allSettled([
+      db.execute('INSERT INTO regrouped VALUES (?)', [1]),
+      db.execute('INSERT INTO regrouped VALUES (?)', [1]),
+      db.execute('INSERT INTO regrouped VALUES (?)', [2]),
+    ]);
+    assert.deepEqual(results.map((r) => r.status), ['fulfilled', 'rejected', 'fulfilled']);
+    assert.match(results[1].reason.message, /primary key constraint failed/);
+    assert.deepEqual(db.querySync('SELECT id FROM regrouped ORDER BY id'), [{ id: 1 }, { id: 2 }]);
+    await new Promise((resolve) => setTimeout(resolve, 20));
+    assert.deepEqual(changes, [1, 2]);
+    await db.close();
+  });
+
+  it('should schedule what it cannot group as usual', async () => {
+    const db = await Database.open(':memory:', { commitWindowMs: 10 });
+    await db.exec('CREATE TABLE ungrouped (id INTEGER PRIMARY KEY)');
+    const [returned, selected, named] = await Promise.all([
+      db.execute('INSERT INTO ungrouped VALUES (?) RETURNING id', [1]),
+      db.execute('SELECT 1'),
+      db.execute('INSERT INTO ungrouped VALUES (:id)', { id: 2 }),
+    ]);
+    assert.deepEqual([returned.rows, selected.changes, named.lastInsertRowId], [[{ id: 1 }], 0, 2]);
+    await db.close();
+  });
+
+  it('should let close() wait for a group', async () => {
+    const db = await Database.open(':memory:', { commitWindowMs: 30 });
+    await db.exec('CREATE TABLE closing_group (id INTEGER PRIMARY KEY)');
+    const pending = db.execute('INSERT INTO closing_group VALUES (?)', [1]);
+    await db.close();
+    assert.equal((await pending).changes, 1);
+    await assert.rejects(db.execute('INSERT INTO closing_group VALUES (?)', [2]), /Database is closed/);
+  });
+
+  it('should reject invalid windows', () => {
+    assert.throws(() => Database.open(':memory:', { commitWindowMs: -1 }), /commitWindowMs must be a non-negative number/);
+  });
+});
+
+// ============================================================
+// Worker thread bridge
+// ============================================================
+
+describe('remote database', () => {
+  const source = `
+    const { parentPort, workerData } = require('node:worker_threads');
+    const { Database } = require(workerData.binding);
+    Database.open(':memory:').then(async (db) => {
+      await db.exec('CREATE TABLE remote (id INTEGER PRIMARY KEY, name TEXT, score FLOAT, ok BOOLEAN, at TIMESTAMP, doc JSON)');
+      db.serve(workerData.port);
+      parentPort.on('message', async (message) => {
+        if (message === 'close') await db.close();
+        parentPort.postMessage('done');
+      });
+      parentPort.postMessage('ready');
+    });
+  `;
+  let worker;
+  let remote;
+
+  const tell = (message) =>
+    new Promise((resolve) => {
+      worker.once('message', resolve);
+      worker.postMessage(message);
+    });
+
+  before(async () => {
+    const { port1, port2 } = new MessageChannel();
+    worker = new Worker(source, {
+      eval: true,
+      workerData: { port: port2, binding: require.resolve('../index.js') },
+      transferList: [port2],
+    });
+    await new Promise((resolve) => worker.once('message', resolve));
+    remote = new RemoteDatabase(port1);
+  });
+
+  after(async () => {
+    remote.close();
+    await worker.terminate();
+  });
+
+  it('should run queries and writes in the worker', async () => {
+    const at = new Date('2025-01-02T03:04:05Z');
+    const inserted = await remote.execute(
+      'INSERT INTO remote VALUES ($1, $2, $3, $4, $5, $6)',
+      [1, 'ada', 1.5, true, at, { tags: ['a'] }],
+    );
+    assert.equal(inserted.changes, 1);
+    assert.equal(inserted.lastInsertRowId, 1);
+    await remote.execute('INSERT INTO remote (id, name) VALUES (:id, :name)', { id: 2, name: 'bob' });
+    assert.deepEqual(await remote.query('SELECT * FROM remote ORDER BY id'), [
+      { id: 1, name: 'ada', score: 1.5, ok: true, at: '2025-01-02T03:04:05Z', doc: '{"tags":["a"]}' },
+      { id: 2, name: 'bob', score: null, ok: null, at: null, doc: null },
+    ]);
+    assert.deepEqual(await remote.query('SELECT name FROM remote WHERE id = ?', [2]), [{ name: 'bob' }]);
+  });
+
+  it('should return RETURNING rows', async () => {
+    const result = await remote.execute("UPDATE remote SET name = 'cy' WHERE id = 2 RETURNING id, name");
+    assert.deepEqual(result.rows, [{ id: 2, name: 'cy' }]);
+  });
+
+  it('should reject with the engine error', async () => {
+    await assert.rejects(remote.query('SELECT * FROM remote_missing'), (err) => {
+      assert.equal(err.cause.kind, 'TableOrViewNotFound');
+      assert.equal(err.cause.table, 'remote_missing');
+      return true;
+    });
+    await assert.rejects(remote.execute('INSERT INTO remote (id) VALUES (1)'), /primary key constraint failed/);
+  });
+
+  it('should settle concurrent calls each with its own result', async () => {
+    const results = await Promise.all(
+      Array.from({ length: 20 }, (_, i) => remote.query('SELECT $1 + 1 AS n', [i])),
+    );
+    assert.deepEqual(results.map((rows) => rows[0].n), Array.from({ length: 20 }, (_, i) => i + 1));
+    assert.equal(remote.pending, 0);
+  });
+
+  it('should reject with DatabaseClosed once either end is closed', async () => {
+    await tell('close');
+    await assert.rejects(remote.query('SELECT 1'), (err) => err.code === 'DatabaseClosed');
+    remote.close();
+    await assert.rejects(remote.query('SELECT 1'), (err) => err.code === 'DatabaseClosed');
+  });
+});
+
+// ============================================================
+// Statement logging
+// ============================================================
+
+describe('statement logging', () => {
+  const settle = () => new Promise((resolve) => setTimeout(resolve, 20));
+
+  it('should log every statement without its params by default', async () => {
+    const db = await Database.open(':memory:');
+    await db.exec('CREATE TABLE logged (id INTEGER PRIMARY KEY, name TEXT)');
+    const events = [];
+    db.logStatements({ sink: (event) => events.push(event) });
+    await db.execute('INSERT INTO logged VALUES ($1, $2)', [1, 'a']);
+    db.querySync('SELECT * FROM logged');
+    await settle();
+    assert.deepEqual(
+      events.map(({ sql, rows, params }) => ({ sql, rows, params })),
+      [
+        { sql: 'INSERT INTO logged VALUES ($1, $2)', rows: 1, params: undefined },
+        { sql: 'SELECT * FROM logged', rows: 1, params: undefined },
+      ],
+    );
+    assert.ok(events.every((event) => event.durationMs >= 0));
+    await db.close();
+  });
+
+  it('should include params when asked', async () => {
+    const db = await Database.open(':memory:');
+    await db.exec('CREATE TABLE logged (id INTEGER PRIMARY KEY, name TEXT)');
+    const events = [];
+    db.logStatements({ sink: (event) => events.push(event), includeParams: true });
+    await db.execute('INSERT INTO logged VALUES ($1, $2)', [1, 'a']);
+    await db.query('SELECT * FROM logged WHERE name = :name', { name: 'a' });
+    await settle();
+    assert.deepEqual(events.map((event) => event.params), [[1, 'a'], { name: 'a' }]);
+    await db.close();
+  });
+
+  it('should sample statements', async () => {
+    const db = await Database.open(':memory:');
+    let logged = 0;
+    db.logStatements({ sink: () => logged++, sample: 0.5 });
+    for (let i = 0; i < 400; i++) db.querySync('SELECT $1 AS n', [i]);
+    await settle();
+    assert.ok(logged > 100 && logged < 300, `logged ${logged} of 400`);
+    logged = 0;
+    db.logStatements({ sink: () => logged++, sample: 0 });
+    for (let i = 0; i < 50; i++) db.querySync('SELECT $1 AS n', [i]);
+    await settle();
+    assert.equal(logged, 0);
+    await db.close();
+  });
+
+  it('should stop logging', async () => {
+    const db = await Database.open(':memory:');
+    const events = [];
+    db.logStatements({ sink: (event) => events.push(event) });
+    db.querySync('SELECT 1 AS one');
+    db.stopLoggingStatements();
+    db.querySync('SELECT 2 AS two');
+    await settle();
+    assert.deepEqual(events.map((event) => event.sql), ['SELECT 1 AS one']);
+    await db.close();
+  });
+
+  it('should reject a sample outside 0 to 1', async () => {
+    const db = await Database.open(':memory:');
+    assert.throws(() => db.logStatements({ sink: () => {}, sample: 2 }), /sample must be a number from 0 to 1/);
+    await db.close();
+  });
+});
+
+// ============================================================
+// Vacuum
+// ============================================================
+
+describe('vacuum', () => {
+  let dir;
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-vacuum-'));
+  });
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it('should drop deleted rows and report progress', async () => {
+    const db = await Database.open(path.join(dir, 'db'));
+    await db.exec(`
+      CREATE TABLE logs (id INTEGER PRIMARY KEY, msg TEXT);
+      CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT);
+    `);
+    await db.executeBatch('INSERT INTO logs VALUES ($1, $2)', Array.from({ length: 20 }, (_, i) => [i, `m${i}`]));
+    await db.execute('DELETE FROM logs WHERE id < 15');
+    const progress = [];
+    const report = await db.vacuum({ onProgress: (p) => progress.push(p) });
+    await new Promise((resolve) => setTimeout(resolve, 20));
+    assert.equal(report.tables, 2);
+    assert.equal(report.deletedRowsCleaned, 15);
+    assert.equal(report.rowsCopied, null);
+    assert.deepEqual(
+      progress.map((p) => [p.phase, p.done, p.total]),
+      [['vacuum', 1, 2], ['vacuum', 2, 2]],
+    );
+    assert.deepEqual(progress.map((p) => p.table).sort(), ['logs', 'tags']);
+    assert.equal((await db.query('SELECT COUNT(*) AS n FROM logs'))[0].n, 5);
+    await db.close();
+  });
+
+  it('should write a compacted copy into a new file', async () => {
+    const db = await Database.open(path.join(dir, 'db'));
+    await db.exec(`
+      CREATE TABLE users (id INTEGER PRIMARY KEY AUTO_INCREMENT, email TEXT UNIQUE NOT NULL, age INTEGER);
+      CREATE INDEX users_age ON users(age);
+      CREATE INDEX users_email_age ON users(email, age);
+      CREATE VIEW adults AS SELECT email FROM users WHERE age >= 18;
+    `);
+    await db.execute("INSERT INTO users (email, age) VALUES ('a@x', 30), ('b@x', 12), ('c@x', 45)");
+    await db.execute("DELETE FROM users WHERE email = 'b@x'");
+    const phases = [];
+    const into = path.join(dir, 'copy');
+    const report = await db.vacuum({ into, onProgress: (p) => phases.push(p.phase) });
+    await new Promise((resolve) => setTimeout(resolve, 20));
+    assert.equal(report.rowsCopied, 2);
+    assert.deepEqual(phases, ['vacuum', 'copy']);
+    await db.close();
+
+    const copy = await Database.open(into);
+    assert.deepEqual(await copy.query('SELECT id, email, age FROM users ORDER BY id'), [
+      { id: 1, email: 'a@x', age: 30 },
+      { id: 3, email: 'c@x', age: 45 },
+    ]);
+    assert.deepEqual(await copy.query('SELECT email FROM adults ORDER BY email'), [{ email: 'a@x' }, { email: 'c@x' }]);
+    const indexes = (await copy.query('SHOW INDEXES FROM users')).map((i) => i.index_name);
+    assert.ok(indexes.includes('users_age') && indexes.includes('users_email_age'), indexes.join());
+    await assert.rejects(copy.execute("INSERT INTO users (email, age) VALUES ('a@x', 1)"));
+    await copy.execute("INSERT INTO users (email, age) VALUES ('d@x', 1)");
+    assert.ok((await copy.query("SELECT id FROM users WHERE email = 'd@x'"))[0].id > 3);
+    await copy.close();
+  });
+
+  it('should refuse to vacuum into an existing path', async () => {
+    const db = await Database.open(':memory:');
+    await assert.rejects(db.vacuum({ into: dir }), /already exists/);
+    await assert.rejects(db.vacuum({ into: ':memory:' }), /must be a file path/);
+    await db.close();
+  });
+
+  it('should follow the statement policy', async () => {
+    const db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE t (id INTEGER PRIMARY KEY)');
+    db.setPolicy({ deny: ['VACUUM'] });
+    await assert.rejects(db.vacuum(), /VACUUM statements are not allowed/);
+    await db.close();
+  });
+});
+
+// ============================================================
+// Parallelism
+// ============================================================
+
+describe('parallelism', () => {
+  const ROWS = Array.from({ length: 20000 }, (_, i) => [i, i % 100]);
+
+  async function open(options) {
+    const db = await Database.open(':memory:', options);
+    await db.execute('CREATE TABLE facts (id INTEGER PRIMARY KEY, v INTEGER)');
+    await db.executeBatch('INSERT INTO facts VALUES ($1, $2)', ROWS);
+    return db;
+  }
+
+  const workers = (rows) => rows.map((r) => r.plan).join('\n').match(/workers=(\d+)/)?.[1];
+
+  it('should run parallel scans on the database parallelism', async () => {
+    const db = await open({ parallelism: 3 });
+    const stmt = db.prepare('SELECT * FROM facts WHERE v = $1');
+    const plan = await stmt.explainAnalyze([5]);
+    assert.equal(plan.parallelWorkers, 3);
+    assert.ok(plan.text.some((line) => line.includes('Parallel Seq Scan on facts (workers=3)')), plan.text.join('\n'));
+    assert.equal(stmt.explainAnalyzeSync([5]).parallelWorkers, 3);
+    assert.equal(workers(await db.query('EXPLAIN ANALYZE SELECT * FROM facts WHERE v = 5')), '3');
+    assert.equal(workers(db.querySync('EXPLAIN ANALYZE SELECT * FROM facts WHERE v = 5')), '3');
+    await db.close();
+  });
+
+  it('should take a per-query parallelism', async () => {
+    const db = await open({ parallelism: 3 });
+    const sql = 'EXPLAIN ANALYZE SELECT * FROM facts WHERE v = 5';
+    assert.equal(workers(await db.query(sql, [], { parallelism: 2 })), '2');
+    assert.equal(workers(db.querySync(sql, [], { parallelism: 4 })), '4');
+    const raw = await db.queryRaw(sql, [], { parallelism: 1 });
+    assert.match(raw.rows.flat().join('\n'), /workers=1/);
+    const stmt = db.prepare(sql);
+    assert.equal(workers(await stmt.query([], { parallelism: 2 })), '2');
+    assert.equal(workers(stmt.querySync([], { parallelism: 5 })), '5');
+    await db.close();
+  });
+
+  it('should return the same rows on any number of threads', async () => {
+    const db = await open();
+    const sql = 'SELECT COUNT(*) AS n, SUM(id) AS total FROM facts WHERE v < 10';
+    const expected = await db.query(sql);
+    const results = await Promise.all([1, 1, 2, 4, 1].map((parallelism) => db.query(sql, [], { parallelism })));
+    for (const rows of results) assert.deepEqual(rows, expected);
+    assert.equal((await db.prepare('SELECT * FROM facts WHERE v = 5').explain()).parallelWorkers, null);
+    await db.close();
+  });
+
+  it('should reject a parallelism outside 1 to 256', async () => {
+    assert.throws(() => Database.open(':memory:', { parallelism: 0 }), /parallelism must be an integer from 1 to 256/);
+    const db = await Database.open(':memory:');
+    assert.throws(() => db.query('SELECT 1', [], { parallelism: 1.5 }), /parallelism must be an integer/);
+    assert.throws(() => db.querySync('SELECT 1', [], { parallelism: 257 }), /parallelism must be an integer/);
+    await db.close();
+  });
+});
+
+// ============================================================
+// Memory usage
+// ============================================================
+
+describe('memoryUsage', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE mu_rows (id INTEGER PRIMARY KEY, s TEXT)');
+    await db.execute(`INSERT INTO mu_rows VALUES ${Array.from({ length: 2000 }, (_, i) => `(${i}, '${'x'.repeat(100)}')`).join(', ')}`);
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should report results held now and at most', async () => {
+    const before = db.memoryUsage();
+    assert.equal(before.limitMb, null);
+    assert.equal(before.resultsMb, 0);
+    assert.equal(before.walBufferMb, 0);
+
+    await db.query('SELECT * FROM mu_rows LIMIT 10');
+    const small = db.memoryUsage().largestResultMb;
+    assert.ok(small > 0);
+    await Promise.all([db.query('SELECT * FROM mu_rows'), db.query('SELECT * FROM mu_rows')]);
+    const usage = db.memoryUsage();
+    assert.equal(usage.resultsMb, 0);
+    assert.ok(usage.largestResultMb > small * 100, `${usage.largestResultMb}`);
+    assert.ok(usage.peakResultsMb >= usage.largestResultMb);
+    assert.equal(usage.rejected, 0);
+  });
+
+  it('should report the plan cache and the WAL buffer', async () => {
+    db.prepare('SELECT id FROM mu_rows WHERE id = $1');
+    const usage = db.memoryUsage();
+    assert.equal(usage.planCacheEntries, db.planCache.stats().entries);
+    assert.ok(usage.planCacheEntries > 0);
+    assert.equal(usage.planCacheMb, db.planCache.stats().sqlBytes / (1024 * 1024));
+
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-mu-'));
+    const file = await Database.open(path.join(dir, 'db'), { walBufferSize: 131072 });
+    assert.equal(file.memoryUsage().walBufferMb, 0.125);
+    await file.close();
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it('should reject calls over the budget with an OutOfMemoryBudget code', async () => {
+    db.setMemoryBudget(0.05);
+    const isOver = (err) => err.code === 'OutOfMemoryBudget' && /exceed the memory budget of 0.05 MB/.test(err.message);
+    await assert.rejects(db.query('SELECT * FROM mu_rows'), isOver);
+    await assert.rejects(db.prepare('SELECT * FROM mu_rows').query(), isOver);
+    const tx = await db.begin();
+    await assert.rejects(tx.query('SELECT * FROM mu_rows'), isOver);
+    await tx.rollback();
+    await assert.rejects(db.query('SELEC 1'), (err) => err.code !== 'OutOfMemoryBudget');
+    const usage = db.memoryUsage();
+    assert.equal(usage.rejected, 3);
+    assert.ok(Math.abs(usage.limitMb - 0.05) < 1e-6);
+    db.setMemoryBudget(null);
+  });
+});
+
+// ============================================================
+// Consistency tokens
+// ============================================================
+
+describe('consistency tokens', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:', { consistencyTokens: true });
+    await db.execute('CREATE TABLE ct_a (id INTEGER PRIMARY KEY, v INTEGER)');
+    await db.execute('CREATE TABLE ct_b (id INTEGER PRIMARY KEY, v INTEGER)');
+    await db.execute('INSERT INTO ct_a VALUES (1, 10), (2, 20)');
+    await db.execute('INSERT INTO ct_b VALUES (1, 10)');
+    await db.execute('CREATE VIEW ct_view AS SELECT id FROM ct_a');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should give query results a token', async () => {
+    const stmt = db.prepare('SELECT * FROM ct_a WHERE id = $1');
+    const results = [
+      await db.query('SELECT * FROM ct_a'),
+      await db.queryRaw('SELECT * FROM ct_a'),
+      db.querySync('SELECT * FROM ct_a'),
+      db.queryRawSync('SELECT * FROM ct_a'),
+      await stmt.query([1]),
+      await stmt.queryRaw([1]),
+      stmt.querySync([1]),
+      stmt.queryRawSync([1]),
+    ];
+    for (const result of results) {
+      assert.equal(typeof result.consistencyToken, 'string');
+      assert.equal(db.isStale(result.consistencyToken), false);
+    }
+  });
+
+  it('should go stale once a write to a table read commits', async () => {
+    const a = await db.query('SELECT * FROM ct_a');
+    const b = await db.query('SELECT * FROM ct_b');
+    await db.execute('UPDATE ct_a SET v = v + 1 WHERE id = 1');
+    assert.equal(db.isStale(a.consistencyToken), true);
+    assert.equal(db.isStale(b.consistencyToken), false);
+
+    const join = await db.query('SELECT * FROM ct_a JOIN ct_b ON ct_a.id = ct_b.id');
+    const pipeline = db.pipeline();
+    pipeline.execute('UPDATE ct_b SET v = 0');
+    await pipeline.run();
+    assert.equal(db.isStale(join.consistencyToken), true);
+  });
+
+  it('should not go stale before a transaction commits', async () => {
+    const rows = await db.query('SELECT * FROM ct_b');
+    const tx = await db.begin();
+    await tx.execute('UPDATE ct_b SET v = 1');
+    assert.equal(db.isStale(rows.consistencyToken), false);
+    await tx.commit();
+    assert.equal(db.isStale(rows.consistencyToken), true);
+  });
+
+  it('should follow views to their tables', async () => {
+    const rows = await db.query('SELECT * FROM ct_view');
+    assert.equal(db.isStale(rows.consistencyToken), false);
+    await db.execute('UPDATE ct_a SET v = 0');
+    assert.equal(db.isStale(rows.consistencyToken), true);
+  });
+
+  it('should see row count and schema changes of other handles', async () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-ct-'));
+    const file = path.join(dir, 'db');
+    const first = await Database.open(file, { consistencyTokens: true });
+    await first.execute('CREATE TABLE t (id INTEGER PRIMARY KEY, v INTEGER)');
+    const second = await Database.open(file);
+
+    const rows = await first.query('SELECT * FROM t');
+    await second.execute('INSERT INTO t VALUES (1, 1)');
+    assert.equal(first.isStale(rows.consistencyToken), true);
+
+    const altered = await first.query('SELECT * FROM t');
+    await second.execute('ALTER TABLE t ADD COLUMN w INTEGER');
+    assert.equal(first.isStale(altered.consistencyToken), true);
+
+    await second.close();
+    await first.close();
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it('should treat tokens of other databases as stale', async () => {
+    const other = await Database.open(':memory:', { consistencyTokens: true });
+    const rows = await other.query('SELECT * FROM ct_b');
+    assert.equal(other.isStale(rows.consistencyToken), false);
+    assert.equal(db.isStale(rows.consistencyToken), true);
+    await other.close();
+  });
+
+  it('should reject strings that are not tokens', () => {
+    assert.throws(() => db.isStale('nope'), /Invalid consistency token/);
+    assert.throws(() => db.isStale('1.2,x'), /Invalid consistency token/);
+  });
+
+  it('should leave results without tokens unless enabled', async () => {
+    const plain = await Database.open(':memory:');
+    const rows = await plain.query('SELECT 1 AS x');
+    assert.equal(rows.consistencyToken, undefined);
+    assert.throws(() => plain.isStale('0.0'), /consistencyTokens open option/);
+    await plain.close();
+  });
+});
+
+// ============================================================
+// Database.diffData
+// ============================================================
+
+describe('Database.diffData', () => {
+  let dir;
+  let first;
+  let second;
+
+  beforeEach(async () => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-diff-'));
+    first = path.join(dir, 'first');
+    second = path.join(dir, 'second');
+    for (const file of [first, second]) {
+      const db = await Database.open(file);
+      await db.execute('CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)');
+      await db.execute('CREATE TABLE tags (tag TEXT)');
+      await db.execute("INSERT INTO users VALUES (1, 'Ann'), (2, 'Bob'), (3, 'Cy')");
+      await db.execute("INSERT INTO tags VALUES ('a'), ('a'), ('b')");
+      await db.close();
+    }
+  });
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  async function change(sql) {
+    const db = await Database.open(second);
+    for (const statement of sql) {
+      await db.execute(statement);
+    }
+    await db.close();
+  }
+
+  it('should resolve to no tables when the rows match', async () => {
+    assert.deepEqual(await Database.diffData(first, second), []);
+    assert.equal(await Database.diffData(first, second, { format: 'sql' }), '');
+  });
+
+  it('should list inserted, updated and deleted rows by primary key', async () => {
+    await change([
+      "UPDATE users SET name = 'Bobby' WHERE id = 2",
+      'DELETE FROM users WHERE id = 3',
+      "INSERT INTO users VALUES (4, 'Dan')",
+    ]);
+    assert.deepEqual(await Database.diffData(first, second), [
+      {
+        table: 'users',
+        inserted: [{ id: 4, name: 'Dan' }],
+        updated: [{ key: 2, before: { id: 2, name: 'Bob' }, after: { id: 2, name: 'Bobby' } }],
+        deleted: [{ id: 3, name: 'Cy' }],
+      },
+    ]);
+  });
+
+  it('should match rows of keyless tables by their contents', async () => {
+    await change(["DELETE FROM tags WHERE tag = 'a'", "INSERT INTO tags VALUES ('a'), ('c')"]);
+    const [tags] = await Database.diffData(first, second, { tables: ['tags'] });
+    assert.deepEqual(tags, { table: 'tags', inserted: [{ tag: 'c' }], updated: [], deleted: [{ tag: 'a' }] });
+  });
+
+  it('should write SQL that turns the first database into the second', async () => {
+    await change([
+      "UPDATE users SET name = 'Bobby' WHERE id = 2",
+      'DELETE FROM users WHERE id = 3',
+      "INSERT INTO users VALUES (4, 'Dan')",
+      "DELETE FROM tags WHERE tag = 'b'",
+    ]);
+    const sql = await Database.diffData(first, second, { format: 'sql' });
+    assert.match(sql, /^DELETE FROM "users" WHERE "id" = 3;$/m);
+    assert.match(sql, /^UPDATE "users" SET "name" = 'Bobby' WHERE "id" = 2;$/m);
+
+    const db = await Database.open(first);
+    await db.exec(sql);
+    await db.close();
+    assert.deepEqual(await Database.diffData(first, second), []);
+  });
+
+  it('should count a table only one database has as empty in the other', async () => {
+    await change(['CREATE TABLE extra (id INTEGER PRIMARY KEY)', 'INSERT INTO extra VALUES (1)']);
+    const diff = await Database.diffData(first, second);
+    assert.deepEqual(diff.map((t) => [t.table, t.inserted.length, t.deleted.length]), [['extra', 1, 0]]);
+    const reverse = await Database.diffData(second, first, { tables: ['extra'] });
+    assert.deepEqual(reverse.map((t) => [t.table, t.inserted.length, t.deleted.length]), [['extra', 0, 1]]);
+  });
+
+  it('should reject what it cannot compare', async () => {
+    await change(['ALTER TABLE users ADD COLUMN email TEXT']);
+    await assert.rejects(Database.diffData(first, second), /Table 'users' has different columns/);
+    await assert.rejects(Database.diffData(first, second, { tables: ['nope'] }), /Table 'nope' is in neither database/);
+    await assert.rejects(Database.diffData(first, path.join(dir, 'missing')), /it does not exist/);
+    await assert.rejects(Database.diffData(':memory:', second), /diffData needs file paths/);
+    assert.throws(() => Database.diffData(first, second, { format: 'csv' }), /format must be 'rows' or 'sql'/);
+  });
+});
+
+// ============================================================
+// WAL shipping
+// ============================================================
+
+describe('WAL shipping', () => {
+  let dir;
+  let primary;
+  let standby;
+  let db;
+
+  beforeEach(async () => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), 'stoolap-wal-'));
+    primary = path.join(dir, 'primary');
+    standby = path.join(dir, 'standby');
+    db = await Database.open(primary);
+    await db.execute('CREATE TABLE ws (id INTEGER PRIMARY KEY, v TEXT)');
+    await db.execute("INSERT INTO ws VALUES (1, 'a'), (2, 'b')");
+  });
+
+  afterEach(async () => {
+    await db.close();
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  async function standbyRows() {
+    const copy = await Database.open(standby);
+    const rows = await copy.query('SELECT * FROM ws ORDER BY id');
+    await copy.close();
+    return rows;
+  }
+
+  it('should keep a standby up to date with exported segments', async () => {
+    const first = await db.exportWalSegments();
+    assert.ok(first.length > 0);
+    assert.equal(first[0].fromLsn, 0);
+    assert.ok(Buffer.isBuffer(first[0].data));
+    const lsn = first.at(-1).toLsn;
+    assert.equal(await Database.applyWalSegments(standby, first), lsn);
+    assert.deepEqual(await standbyRows(), [{ id: 1, v: 'a' }, { id: 2, v: 'b' }]);
+
+    assert.deepEqual(await db.exportWalSegments(lsn), []);
+    await db.execute("UPDATE ws SET v = 'bb' WHERE id = 2");
+    await db.execute('DELETE FROM ws WHERE id = 1');
+    await db.execute('ALTER TABLE ws ADD COLUMN n INTEGER');
+    await db.execute("INSERT INTO ws VALUES (3, 'c', 7)");
+    const next = await db.exportWalSegments(lsn);
+    assert.equal(next[0].fromLsn, lsn);
+    assert.equal(await Database.applyWalSegments(standby, next), next.at(-1).toLsn);
+    assert.deepEqual(await standbyRows(), [{ id: 2, v: 'bb', n: null }, { id: 3, v: 'c', n: 7 }]);
+  });
+
+  it('should export only whole transactions', async () => {
+    const lsn = (await db.exportWalSegments()).at(-1).toLsn;
+    const tx = await db.begin();
+    await tx.execute("INSERT INTO ws VALUES (3, 'c')");
+    assert.deepEqual(await db.exportWalSegments(lsn), []);
+    await tx.commit();
+    assert.equal((await db.exportWalSegments(lsn)).length, 1);
+  });
+
+  it('should skip segments the standby already has', async () => {
+    const segments = await db.exportWalSegments();
+    await Database.applyWalSegments(standby, segments);
+    await Database.applyWalSegments(standby, segments);
+    assert.deepEqual(await standbyRows(), [{ id: 1, v: 'a' }, { id: 2, v: 'b' }]);
+    assert.equal(await Database.applyWalSegments(standby, []), segments.at(-1).toLsn);
+  });
+
+  it('should reject segments it cannot apply', async () => {
+    const lsn = (await db.exportWalSegments()).at(-1).toLsn;
+    await db.execute("INSERT INTO ws VALUES (3, 'c')");
+    const later = await db.exportWalSegments(lsn);
+    await assert.rejects(Database.applyWalSegments(standby, later), /the standby has only applied up to LSN 0/);
+
+    const open = await Database.open(standby);
+    await assert.rejects(Database.applyWalSegments(standby, later), /it is open/);
+    await open.close();
+
+    const damaged = [{ fromLsn: 0, toLsn: 1, entries: 1, data: Buffer.alloc(64, 1) }];
+    await assert.rejects(Database.applyWalSegments(standby, damaged), /damaged/);
+    await assert.rejects(Database.applyWalSegments(':memory:', later), /needs a file path/);
+  });
+
+  it('should need a file database and a valid LSN', async () => {
+    const memory = await Database.open(':memory:');
+    assert.throws(() => memory.exportWalSegments(), /needs a file database/);
+    await memory.close();
+    assert.throws(() => db.exportWalSegments(-1), /sinceLsn must not be negative/);
+  });
+
+  it('should not export while a policy limits what may be read', async () => {
+    db.setPolicy({ allowTables: ['ws'] });
+    assert.throws(() => db.exportWalSegments(), /SQL policy violation: exportWalSegments\(\) reads every table/);
+    db.setPolicy({ deny: ['SELECT'] });
+    assert.throws(() => db.exportWalSegments(), /SELECT statements are not allowed/);
+    db.setPolicy({ deny: ['DROP'] });
+    assert.ok((await db.exportWalSegments()).length > 0);
+    db.setPolicy(null);
+
+    const readOnly = db.createRestrictedHandle({ allow: ['SELECT'] });
+    assert.throws(() => readOnly.exportWalSegments(), /exportWalSegments\(\) is not available on a restricted handle/);
+  });
+});
+
+// ============================================================
+// Temp Tables
+// ============================================================
+
+describe('withTempTable', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  async function tables() {
+    return (await db.query('SHOW TABLES')).map((row) => Object.values(row)[0]);
+  }
+
+  it('should create a table for the callback and drop it afterwards', async () => {
+    let name;
+    const result = await db.withTempTable('id INTEGER PRIMARY KEY, total FLOAT', async (t) => {
+      name = t.name;
+      assert.match(t.name, /^tmp_/);
+      assert.deepEqual(t.columns, ['id', 'total']);
+      assert.deepEqual(await tables(), [t.name]);
+      return 'done';
+    });
+    assert.equal(result, 'done');
+    assert.deepEqual(await tables(), []);
+    assert.ok(name);
+  });
+
+  it('should insert objects and arrays and query with a clause', async () => {
+    const rows = await db.withTempTable({ id: 'INTEGER', total: 'FLOAT' }, async (t) => {
+      assert.equal((await t.insert([{ id: 1, total: 5 }, [2, 10], { id: 3 }])).changes, 3);
+      assert.equal((await t.insert({ id: 4, total: 1 })).changes, 1);
+      assert.equal((await t.insert([5, 7])).changes, 1);
+      assert.equal((await t.insert([])).changes, 0);
+      const [{ n }] = await db.query(`SELECT COUNT(*) AS n FROM ${t.name}`);
+      assert.equal(n, 5);
+      assert.deepEqual(await t.query('WHERE total IS NULL'), [{ id: 3, total: null }]);
+      return t.query('WHERE total > $1 ORDER BY id', [2]);
+    });
+    assert.deepEqual(rows, [
+      { id: 1, total: 5 },
+      { id: 2, total: 10 },
+      { id: 5, total: 7 },
+    ]);
+  });
+
+  it('should drop the table when the callback rejects or throws', async () => {
+    const failure = new TypeError('report failed');
+    await assert.rejects(
+      db.withTempTable('v TEXT', async (t) => {
+        await t.insert({ v: 'x' });
+        throw failure;
+      }),
+      (err) => err === failure,
+    );
+    await assert.rejects(
+      db.withTempTable('v TEXT', () => {
+        throw new Error('sync failure');
+      }),
+      /sync failure/,
+    );
+    assert.deepEqual(await tables(), []);
+  });
+
+  it('should give each call its own table', async () => {
+    const names = await Promise.all(
+      [1, 2, 3].map((i) =>
+        db.withTempTable('v INTEGER', async (t) => {
+          await t.insert([[i], [i]]);
+          assert.deepEqual(await t.query(), [{ v: i }, { v: i }]);
+          return t.name;
+        }),
+      ),
+    );
+    assert.equal(new Set(names).size, 3);
+    assert.deepEqual(await tables(), []);
+  });
+
+  it('should refuse helpers once the table is dropped', async () => {
+    const kept = await db.withTempTable('v TEXT', (t) => t);
+    assert.throws(() => kept.query(), /was dropped/);
+    assert.throws(() => kept.insert({ v: 'x' }), /was dropped/);
+  });
+
+  it('should validate the columns', () => {
+    assert.throws(() => db.withTempTable('', () => {}), /needs at least one column/);
+    assert.throws(() => db.withTempTable({}, () => {}), /needs at least one column/);
+    assert.throws(() => db.withTempTable('v NOPE(', () => {}), /Invalid temp table columns/);
+    assert.throws(() => db.withTempTable({ v: 1 }, () => {}), /type of column 'v' must be a string/);
+  });
+});
+
+// ============================================================
+// Memoized transaction reads
+// ============================================================
+
+describe('transaction memoize', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    await db.execute('CREATE TABLE memo (id INTEGER PRIMARY KEY, v TEXT)');
+    await db.execute("INSERT INTO memo VALUES (1, 'a'), (2, 'b')");
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should reuse the rows of a repeated SELECT', async () => {
+    const tx = await db.begin({ memoize: true });
+    const first = await tx.query('SELECT * FROM memo WHERE id = $1', [1]);
+    first[0].v = 'changed by the caller';
+    assert.deepEqual(await tx.query('SELECT * FROM memo WHERE id = $1', [1]), [{ id: 1, v: 'a' }]);
+    assert.deepEqual(tx.querySync('SELECT * FROM memo WHERE id = $1', [1]), [{ id: 1, v: 'a' }]);
+    assert.deepEqual(await tx.queryRaw('SELECT * FROM memo WHERE id = $1', [1]), {
+      columns: ['id', 'v'],
+      rows: [[1, 'a']],
+    });
+    assert.deepEqual(await tx.query('SELECT * FROM memo WHERE id = $1', [2]), [{ id: 2, v: 'b' }]);
+    assert.deepEqual(tx.memoStats, { entries: 2, hits: 3, misses: 2 });
+    await tx.commit();
+  });
+
+  it('should keep queryOne results apart, including no row', async () => {
+    const tx = await db.begin({ memoize: true });
+    assert.deepEqual(await tx.queryOne('SELECT * FROM memo ORDER BY id'), { id: 1, v: 'a' });
+    assert.equal(await tx.queryOne('SELECT * FROM memo WHERE id = 9'), null);
+    assert.equal(tx.queryOneSync('SELECT * FROM memo WHERE id = 9'), null);
+    assert.equal((await tx.query('SELECT * FROM memo ORDER BY id')).length, 2);
+    assert.deepEqual(tx.memoStats, { entries: 3, hits: 1, misses: 3 });
+    await tx.rollback();
+  });
+
+  it('should clear kept rows when the transaction executes a statement', async () => {
+    const tx = await db.begin({ memoize: true });
+    await tx.query('SELECT v FROM memo WHERE id = 1');
+    await tx.execute("UPDATE memo SET v = 'x' WHERE id = 1");
+    assert.equal(tx.memoStats.entries, 0);
+    assert.deepEqual(await tx.query('SELECT v FROM memo WHERE id = 1'), [{ v: 'x' }]);
+    tx.executeBatchSync('UPDATE memo SET v = $1 WHERE id = $2', [['y', 1]]);
+    assert.deepEqual(tx.querySync('SELECT v FROM memo WHERE id = 1'), [{ v: 'y' }]);
+    assert.deepEqual(tx.memoStats, { entries: 1, hits: 0, misses: 3 });
+    await tx.commit();
+    assert.equal(tx.memoStats.entries, 0);
+  });
+
+  it('should read a snapshot, unaffected by other commits', async () => {
+    const tx = await db.begin({ memoize: true });
+    await tx.query('SELECT * FROM memo WHERE id = 1');
+    await db.execute("UPDATE memo SET v = 'z' WHERE id = 1");
+    assert.deepEqual(await tx.query('SELECT * FROM memo ORDER BY id'), [
+      { id: 1, v: 'a' },
+      { id: 2, v: 'b' },
+    ]);
+    await tx.commit();
+  });
+
+  it('should always run SELECTs that call volatile functions', async () => {
+    const tx = await db.begin({ memoize: true });
+    await tx.query('SELECT RANDOM() AS r FROM memo');
+    await tx.query('SELECT RANDOM() AS r FROM memo');
+    assert.deepEqual(tx.memoStats, { entries: 0, hits: 0, misses: 0 });
+    await tx.commit();
+  });
+
+  it('should keep nothing without the option', async () => {
+    const tx = await db.begin();
+    await tx.query('SELECT * FROM memo');
+    await tx.query('SELECT * FROM memo');
+    assert.deepEqual(tx.memoStats, { entries: 0, hits: 0, misses: 0 });
+    await tx.commit();
+  });
+});
+
+// ============================================================
+// Result Filters
+// ============================================================
+
+describe('setResultFilter', () => {
+  let db;
+
+  const mask = (table, column, value) =>
+    table === 'users' && column === 'email' && value !== null ? '***' : value;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    await db.exec(
+      'CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT); ' +
+        'CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, total FLOAT)'
+    );
+    await db.execute("INSERT INTO users VALUES (1, 'a@x.com'), (2, NULL)");
+    await db.execute('INSERT INTO orders VALUES (10, 1, 5.5)');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should pass every value through the filter, with its table and column', async () => {
+    const seen = [];
+    db.setResultFilter((table, column, value) => {
+      seen.push([table, column, value]);
+      return value;
+    });
+    await db.query('SELECT id, email, id * 2 AS twice FROM users WHERE id = 1');
+    assert.deepEqual(seen, [
+      ['users', 'id', 1],
+      ['users', 'email', 'a@x.com'],
+      [null, 'twice', 2],
+    ]);
+  });
+
+  it('should mask columns under aliases, in joins and with the columns option', async () => {
+    db.setResultFilter(mask);
+    assert.deepEqual(db.querySync('SELECT * FROM users ORDER BY id'), [
+      { id: 1, email: '***' },
+      { id: 2, email: null },
+    ]);
+    assert.deepEqual(await db.query('SELECT email AS contact FROM users WHERE id = 1'), [
+      { contact: '***' },
+    ]);
+    assert.deepEqual(
+      db.queryOneSync('SELECT o.total, u.email FROM orders o JOIN users u ON u.id = o.user_id'),
+      { total: 5.5, email: '***' }
+    );
+    assert.deepEqual(
+      await db.query('SELECT id, email FROM users WHERE id = 1', [], { columns: ['email', 'id'] }),
+      [{ email: '***', id: 1 }]
+    );
+  });
+
+  it('should filter queryChunks rows', async () => {
+    db.setResultFilter(mask);
+    const chunks = [];
+    await db.queryChunks('SELECT id, email AS contact FROM users ORDER BY id', null, { chunkSize: 1 }, (rows) => {
+      chunks.push(rows);
+    });
+    assert.deepEqual(chunks, [[{ id: 1, contact: '***' }], [{ id: 2, contact: null }]]);
+  });
+
+  it('should filter raw rows, plucked values, expanded rows and lowMemory results', async () => {
+    db.setResultFilter(mask);
+    assert.deepEqual(await db.queryRaw('SELECT * FROM users WHERE id = 1'), {
+      columns: ['id', 'email'],
+      rows: [[1, '***']],
+    });
+    const stmt = db.prepare('SELECT email FROM users ORDER BY id');
+    assert.deepEqual(stmt.pluck().querySync(), ['***', null]);
+    const joined = db.prepare('SELECT * FROM users u JOIN orders o ON o.user_id = u.id');
+    assert.deepEqual(await joined.expand().query(), [
+      { u: { id: 1, email: '***' }, o: { id: 10, user_id: 1, total: 5.5 } },
+    ]);
+    assert.deepEqual(db.querySync('SELECT email FROM users ORDER BY id', [], { lowMemory: true }), [
+      { email: '***' },
+      { email: null },
+    ]);
+  });
+
+  it('should apply to statements and transactions', async () => {
+    db.setResultFilter(mask);
+    const stmt = db.prepare('SELECT email FROM users WHERE id = $1');
+    assert.deepEqual(await stmt.queryOne([1]), { email: '***' });
+    const tx = await db.begin();
+    assert.deepEqual(tx.querySync('SELECT * FROM users WHERE id = 1'), [{ id: 1, email: '***' }]);
+    assert.deepEqual(await tx.queryRaw('SELECT email FROM users WHERE id = 1'), {
+      columns: ['email'],
+      rows: [['***']],
+    });
+    await tx.rollback();
+  });
+
+  it('should fail the call with what the filter throws', async () => {
+    const error = new Error('no access');
+    db.setResultFilter(() => {
+      throw error;
+    });
+    assert.throws(() => db.querySync('SELECT * FROM users'), (e) => e === error);
+    await assert.rejects(db.query('SELECT * FROM users'), (e) => e === error);
+  });
+
+  it('should return values as they are once removed', async () => {
+    db.setResultFilter(mask);
+    db.setResultFilter(null);
+    assert.deepEqual(await db.query('SELECT email FROM users WHERE id = 1'), [
+      { email: 'a@x.com' },
+    ]);
+  });
+});
+
+// ============================================================
+// Restricted Handles
+// ============================================================
+
+describe('createRestrictedHandle', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    await db.exec(
+      'CREATE TABLE orders (id INTEGER PRIMARY KEY, total FLOAT); ' +
+        'CREATE TABLE secrets (id INTEGER PRIMARY KEY)'
+    );
+    await db.execute('INSERT INTO orders VALUES (1, 5.5)');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should run the allowed statement types and reject the others', async () => {
+    const readOnly = db.createRestrictedHandle({ allow: ['SELECT'] });
+    assert.equal(readOnly.isRestricted, true);
+    assert.equal(db.isRestricted, false);
+    assert.deepEqual(await readOnly.query('SELECT id FROM orders'), [{ id: 1 }]);
+    await assert.rejects(
+      async () => readOnly.execute('DELETE FROM orders'),
+      /DELETE statements are not allowed on a restricted handle/
+    );
+    assert.throws(
+      () => readOnly.execSync('SELECT 1; DROP TABLE orders'),
+      /DROP TABLE statements are not allowed/
+    );
+    assert.throws(
+      () => readOnly.querySync('EXPLAIN SELECT * FROM orders'),
+      /EXPLAIN statements are not allowed/
+    );
+    assert.equal((await db.query('SELECT * FROM orders')).length, 1);
+    await db.execute('INSERT INTO orders VALUES (2, 1.0)');
+  });
+
+  it('should cover statements and transactions made from the handle', async () => {
+    const readOnly = db.createRestrictedHandle({ allow: ['select', 'explain'] });
+    assert.throws(
+      () => readOnly.prepare('INSERT INTO orders VALUES (3, 1.0)'),
+      /INSERT statements are not allowed/
+    );
+    const stmt = readOnly.prepare('SELECT total FROM orders WHERE id = $1');
+    assert.deepEqual(stmt.querySync([1]), [{ total: 5.5 }]);
+    const tx = await readOnly.begin();
+    assert.deepEqual(tx.querySync('SELECT id FROM orders'), [{ id: 1 }]);
+    assert.throws(() => tx.executeSync('UPDATE orders SET total = 0'), /UPDATE statements/);
+    await tx.rollback();
+    assert.ok(readOnly.querySync('EXPLAIN SELECT * FROM orders').length > 0);
+    assert.throws(
+      () => readOnly.querySync('EXPLAIN ANALYZE DELETE FROM orders'),
+      /DELETE statements are not allowed/
+    );
+  });
+
+  it('should apply the policy of the handle it was made from', async () => {
+    const readOnly = db.createRestrictedHandle({ allow: ['SELECT'] });
+    db.setPolicy({ allowTables: ['orders'] });
+    assert.throws(() => readOnly.querySync('SELECT * FROM secrets'), /table 'secrets'/);
+    db.setPolicy(null);
+    assert.deepEqual(readOnly.querySync('SELECT * FROM secrets'), []);
+  });
+
+  it('should require both lists of a handle restricted further', () => {
+    const reads = db.createRestrictedHandle({ allow: ['SELECT', 'INSERT'] });
+    const selects = reads.createRestrictedHandle({ allow: ['SELECT', 'DELETE'] });
+    assert.equal(reads.executeSync('INSERT INTO orders VALUES (2, 1.0)').changes, 1);
+    assert.throws(() => selects.executeSync('INSERT INTO orders VALUES (3, 1.0)'), /INSERT/);
+    assert.throws(() => selects.executeSync('DELETE FROM orders'), /DELETE/);
+    assert.equal(selects.querySync('SELECT * FROM orders').length, 2);
+  });
+
+  it('should throw from the calls that change shared settings', async () => {
+    const readOnly = db.createRestrictedHandle({ allow: ['SELECT'] });
+    assert.throws(() => readOnly.setPolicy(null), /setPolicy\(\) is not available/);
+    assert.throws(() => readOnly.setResultFilter(null), /setResultFilter\(\) is not available/);
+    assert.throws(() => readOnly.closeSync(), /closeSync\(\) is not available/);
+    assert.throws(() => readOnly.restoreCheckpoint('x'), /restoreCheckpoint\(\) is not available/);
+    assert.throws(() => readOnly.config('sync', 'full'), /config\(\) is not available/);
+    assert.equal(readOnly.config('sync'), db.config('sync'));
+    readOnly[Symbol.dispose]();
+    await readOnly[Symbol.asyncDispose]();
+    assert.equal(db.isOpen, true);
+    assert.deepEqual(readOnly.querySync('SELECT id FROM orders'), [{ id: 1 }]);
+  });
+
+  it('should throw from the calls that reach the other handles', () => {
+    const readOnly = db.createRestrictedHandle({ allow: ['SELECT'] });
+    assert.throws(() => readOnly.planCache, /planCache\(\) is not available on a restricted handle/);
+    assert.throws(() => readOnly.on('change', () => {}), /on\(\) is not available/);
+    assert.throws(() => readOnly.interrupt(), /interrupt\(\) is not available/);
+    assert.ok(db.planCache.stats());
+    db.interrupt();
+  });
+
+  it('should reject unknown statement types', () => {
+    assert.throws(
+      () => db.createRestrictedHandle({ allow: ['SELEKT'] }),
+      /Unknown statement type 'SELEKT' in allow list/
+    );
+  });
+});
+
+// ============================================================
+// Strict Statements
+// ============================================================
+
+describe('strictStatements', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:', { strictStatements: true });
+    await db.exec('CREATE TABLE t (id INTEGER PRIMARY KEY); INSERT INTO t VALUES (1)');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should reject several statements in single-statement calls', async () => {
+    await assert.rejects(
+      async () => db.query('SELECT 1 AS a; DROP TABLE t'),
+      /2 statements in a call that runs one; use exec\(\) for scripts/
+    );
+    assert.throws(() => db.executeSync('INSERT INTO t VALUES (2); DELETE FROM t'), /2 statements/);
+    assert.throws(
+      () => db.executeBatchSync('INSERT INTO t VALUES ($1); DROP TABLE t', [[3]]),
+      /2 statements/
+    );
+    const tx = await db.begin();
+    assert.throws(() => tx.querySync('SELECT 1; SELECT 2'), /2 statements/);
+    await tx.rollback();
+    assert.deepEqual(await db.query('SELECT id FROM t'), [{ id: 1 }]);
+  });
+
+  it('should not count semicolons in strings and comments, or a trailing one', () => {
+    assert.deepEqual(db.querySync("SELECT ';' AS a; -- trailing"), [{ a: ';' }]);
+    assert.equal(db.executeSync('INSERT INTO t VALUES (2);').changes, 1);
+  });
+
+  it('should still run scripts with exec() and queryMulti()', async () => {
+    const results = await db.queryMulti('SELECT 1 AS a; SELECT 2 AS b');
+    assert.equal(results.length, 2);
+    db.execSync('INSERT INTO t VALUES (5); INSERT INTO t VALUES (6)');
+    assert.equal(db.querySync('SELECT * FROM t').length, 3);
+  });
+
+  it('should run every statement of the SQL without the option', async () => {
+    const loose = await Database.open(':memory:');
+    await loose.exec('CREATE TABLE loose (id INTEGER PRIMARY KEY)');
+    await loose.query('SELECT 1 AS a; DROP TABLE loose');
+    assert.throws(() => loose.querySync('SELECT * FROM loose'), /not found/);
+    await loose.close();
+  });
+});
+
+// ============================================================
+// SQL Template Tag
+// ============================================================
+
+describe('sql template tag', () => {
+  let db;
+
+  beforeEach(async () => {
+    db = await Database.open(':memory:');
+    await db.exec('CREATE TABLE tag_users (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  afterEach(async () => {
+    await db.close();
+  });
+
+  it('should bind each interpolated value as a parameter', async () => {
+    const name = "x'); DROP TABLE tag_users; --";
+    const insert = sql`INSERT INTO tag_users VALUES (${1}, ${name})`;
+    assert.equal(insert.text, 'INSERT INTO tag_users VALUES ($1, $2)');
+    assert.deepEqual(insert.params, [1, name]);
+    assert.equal((await db.execute(insert)).changes, 1);
+    assert.deepEqual(db.querySync(sql`SELECT name FROM tag_users WHERE name = ${name}`), [{ name }]);
+  });
+
+  it('should splice nested fragments and identifiers', () => {
+    db.executeSync('INSERT INTO tag_users VALUES (1, $1), (2, $2)', ['Alice', 'Bob']);
+    const where = sql`id > ${0} AND name <> ${'Bob'}`;
+    const query = sql`SELECT ${sql.identifier('name')} FROM ${sql.identifier('tag_users')} WHERE ${where} AND id < ${5}`;
+    assert.equal(query.text, 'SELECT "name" FROM "tag_users" WHERE id > $1 AND name <> $2 AND id < $3');
+    assert.deepEqual(query.params, [0, 'Bob', 5]);
+    assert.deepEqual(db.querySync(query), [{ name: 'Alice' }]);
+    assert.equal(sql.identifier('a"b').text, '"a""b"');
+    assert.throws(() => sql.identifier(''), /non-empty string/);
+    assert.throws(() => sql('SELECT 1'), /template tag/);
+  });
+
+  it('should take options in place of params', async () => {
+    db.executeSync('INSERT INTO tag_users VALUES (1, $1), (2, $2)', ['Alice', 'Bob']);
+    const rows = await db.query(sql`SELECT * FROM tag_users WHERE id IN (${[1, 2]}) ORDER BY id`, {
+      columns: ['name'],
+    });
+    assert.deepEqual(rows, [{ name: 'Alice' }, { name: 'Bob' }]);
+    assert.deepEqual(db.queryRawSync(sql`SELECT ${7} AS n`).rows, [[7]]);
+  });
+
+  it('should work with transactions and pipelines', async () => {
+    const tx = await db.begin();
+    tx.executeSync(sql`INSERT INTO tag_users VALUES (${1}, ${'Alice'})`);
+    assert.deepEqual(await tx.queryOne(sql`SELECT name FROM tag_users WHERE id = ${1}`), { name: 'Alice' });
+    await tx.commit();
+    const results = await db
+      .pipeline()
+      .execute(sql`INSERT INTO tag_users VALUES (${2}, ${'Bob'})`)
+      .query(sql`SELECT COUNT(*) AS n FROM tag_users WHERE id >= ${1}`)
+      .run();
+    assert.equal(results[0].changes, 1);
+    assert.deepEqual(results[1], [{ n: 2 }]);
+  });
+
+  it('should stream chunks and run batches', async () => {
+    const table = sql.identifier('tag_users');
+    const insert = sql`INSERT INTO ${table} VALUES ($1, $2)`;
+    assert.equal((await db.executeBatch(insert, [[1, 'Alice'], [2, 'Bob']])).changes, 2);
+    assert.throws(
+      () => db.executeBatchSync(sql`INSERT INTO ${table} VALUES (${3}, $2)`, [['Carol']]),
+      /executeBatchSync\(\) binds paramsArray/
+    );
+    const tx = await db.begin();
+    tx.executeBatchSync(insert, [[3, 'Carol']]);
+    await tx.commit();
+
+    const chunks = [];
+    const rows = await db.queryChunks(sql`SELECT id FROM ${table} WHERE id > ${1} ORDER BY id`, { chunkSize: 1 }, (chunk) =>
+      chunks.push(chunk)
+    );
+    assert.equal(rows, 2);
+    assert.deepEqual(chunks, [[{ id: 2 }], [{ id: 3 }]]);
+  });
+});
+
+// ============================================================
+// Validating SQL
+// ============================================================
+
+describe('validate', () => {
+  let db;
+
+  before(async () => {
+    db = await Database.open(':memory:');
+  });
+
+  after(async () => {
+    await db.close();
+  });
+
+  it('should report valid SQL without checking the schema', () => {
+    assert.deepEqual(db.validate('SELECT * FROM no_such_table; SELECT 1'), { ok: true, errors: [] });
+  });
+
+  it('should give the line and column of each error', () => {
+    const result = db.validate("SELECT 1;\nSELECT 'é€', FROM t");
+    assert.equal(result.ok, false);
+    assert.equal(result.errors.length, 1);
+    assert.match(result.errors[0].message, /FROM/);
+    assert.equal(result.errors[0].line, 2);
+    assert.equal(result.errors[0].column, 14);
+  });
+
+  it('should not run anything', () => {
+    const result = db.validate('CREATE TABLE validated (id INTEGER); DROP TABLE');
+    assert.equal(result.ok, false);
+    assert.throws(() => db.querySync('SELECT * FROM validated'), /not found/);
+    assert.equal(db.validate('CREATE TABLE validated (id INTEGER)').ok, true);
+    assert.throws(() => db.querySync('SELECT * FROM validated'), /not found/);
+  });
+});
+
+// ============================================================
+// Syntax Trees
+// ============================================================
+
+describe('Database.parse', () => {
+  it('should return a tree per statement', () => {
+    const [select, insert] = Database.parse(
+      'SELECT u.name AS who FROM users u WHERE u.id = $1 ORDER BY who DESC; INSERT INTO t (a) VALUES (1)'
+    );
+    assert.equal(select.type, 'Select');
+    assert.deepEqual(select.columns, [
+      {
+        type: 'Aliased',
+        alias: 'who',
+        expression: { type: 'QualifiedIdentifier', qualifier: 'u', name: 'name' },
+      },
+    ]);
+    assert.deepEqual(select.tableExpr, { type: 'TableSource', name: 'users', alias: 'u', asOf: null });
+    assert.equal(select.whereClause.type, 'Infix');
+    assert.equal(select.whereClause.operator, '=');
+    assert.deepEqual(select.whereClause.right, { type: 'Parameter', name: '$1', index: 1 });
+    assert.equal(select.orderBy[0].ascending, false);
+    assert.equal(select.limit, null);
+    assert.equal(insert.type, 'Insert');
+    assert.equal(insert.tableName, 't');
+    assert.deepEqual(insert.values, [[{ type: 'IntegerLiteral', value: 1 }]]);
+  });
+
+  it('should spell joins, set operations and constraints as in SQL', () => {
+    const [select] = Database.parse(
+      'SELECT * FROM a LEFT JOIN b ON a.id = b.aid UNION ALL SELECT * FROM (SELECT 1) s'
+    );
+    assert.equal(select.tableExpr.type, 'JoinSource');
+    assert.equal(select.tableExpr.joinType, 'LEFT');
+    assert.equal(select.setOperations[0].operation, 'UNION ALL');
+    assert.equal(select.setOperations[0].right.tableExpr.subquery.type, 'Select');
+    const [create] = Database.parse(
+      'CREATE TABLE c (id INTEGER PRIMARY KEY, a INTEGER NOT NULL REFERENCES a(id) ON DELETE CASCADE)'
+    );
+    assert.equal(create.type, 'CreateTable');
+    assert.deepEqual(
+      create.columns.map((c) => [c.name, c.dataType, c.constraints.map((k) => k.type)]),
+      [
+        ['id', 'INTEGER', ['PrimaryKey']],
+        ['a', 'INTEGER', ['NotNull', 'References']],
+      ]
+    );
+    assert.equal(create.columns[1].constraints[1].onDelete, 'CASCADE');
+  });
+
+  it('should produce JSON and throw on SQL that does not parse', () => {
+    const tree = Database.parse('SELECT 9007199254740993, 1.5, NULL');
+    assert.deepEqual(JSON.parse(JSON.stringify(tree)), tree);
+    assert.throws(() => Database.parse('SELECT (1'), /expected/);
+  });
+});
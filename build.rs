@@ -17,9 +17,15 @@ extern crate napi_build;
 fn main() {
     napi_build::setup();
 
+    // `pure-napi` links against N-API only: no C++ helper, no V8 headers.
+    if std::env::var_os("CARGO_FEATURE_PURE_NAPI").is_some() {
+        return;
+    }
+
     let target = std::env::var("TARGET").unwrap_or_default();
 
     // Compile v8_helpers.cpp — direct V8 bulk object creation
+    println!("cargo:rerun-if-changed=src/v8_helpers.cpp");
     let node_include = node_include_dir();
     let mut build = cc::Build::new();
     build
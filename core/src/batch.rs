@@ -0,0 +1,144 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Batches: one statement run with many parameter lists.
+//!
+//! The statement is parsed once, and every list is converted, and checked
+//! against the statement's parameter count, before anything runs. [`run`]
+//! runs the lists in one transaction that commits at the end, so a failing
+//! list rolls the whole batch back; [`execute`] runs them in one the caller
+//! has, where lists before a failing one stay.
+
+use stoolap::api::{Database, Transaction};
+use stoolap::parser::ast::Statement;
+use stoolap::parser::Parser;
+use stoolap::ParamVec;
+
+use crate::params::check_count;
+use crate::{Error, Result};
+
+/// The first statement of `sql`.
+pub fn parse(sql: &str) -> Result<Statement> {
+    let mut parser = Parser::new(sql);
+    let program = parser
+        .parse_program()
+        .map_err(|e| Error::invalid(e.to_string()))?;
+    program
+        .statements
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::invalid("No SQL statement found"))
+}
+
+/// Check that every list holds `expected` parameters, failing with the
+/// [`Error::Row`] of the first that doesn't.
+pub fn check_arity(expected: usize, rows: &[ParamVec]) -> Result<()> {
+    for (index, row) in rows.iter().enumerate() {
+        check_count(expected, row.len()).map_err(|error| Error::Row {
+            index,
+            error: Box::new(error),
+        })?;
+    }
+    Ok(())
+}
+
+/// Run `statement` with every list in `tx`, returning the rows changed.
+pub fn execute(tx: &mut Transaction, statement: &Statement, rows: Vec<ParamVec>) -> Result<i64> {
+    let mut total = 0;
+    for params in rows {
+        total += tx.execute_prepared(statement, params)?;
+    }
+    Ok(total)
+}
+
+/// Run `statement` with every list in one new transaction and commit it.
+pub fn run(db: &Database, statement: &Statement, rows: Vec<ParamVec>) -> Result<i64> {
+    let mut tx = db.begin()?;
+    let total = execute(&mut tx, statement, rows)?;
+    tx.commit()?;
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stoolap::Value;
+
+    fn rows(values: &[&[i64]]) -> Vec<ParamVec> {
+        values
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&v| Value::Integer(v))
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect()
+    }
+
+    fn table() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, v INTEGER)", ())
+            .unwrap();
+        db
+    }
+
+    fn count(db: &Database) -> i64 {
+        let mut rows = db.query("SELECT COUNT(*) FROM t", ()).unwrap();
+        assert!(rows.advance());
+        rows.current_row()
+            .get(0)
+            .and_then(|v| v.as_int64())
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_takes_the_first_statement() {
+        let statement = parse("INSERT INTO t VALUES ($1, $2); DELETE FROM t").unwrap();
+        assert!(matches!(statement, Statement::Insert(_)));
+        assert!(parse("   ").is_err());
+        assert!(parse("INSERT INTO").is_err());
+    }
+
+    #[test]
+    fn arity_names_the_first_list_that_is_off() {
+        assert!(check_arity(2, &rows(&[&[1, 2], &[3, 4]])).is_ok());
+        let err = check_arity(2, &rows(&[&[1, 2], &[3], &[4]])).unwrap_err();
+        assert!(matches!(err, Error::Row { index: 1, .. }));
+        assert_eq!(
+            err.to_string(),
+            "row 1: Statement requires 2 parameters, got 1"
+        );
+    }
+
+    #[test]
+    fn run_commits_every_list_or_none() {
+        let db = table();
+        let insert = parse("INSERT INTO t VALUES ($1, $2)").unwrap();
+        assert_eq!(run(&db, &insert, rows(&[&[1, 10], &[2, 20]])).unwrap(), 2);
+        assert_eq!(count(&db), 2);
+        assert!(run(&db, &insert, rows(&[&[3, 30], &[1, 10]])).is_err());
+        assert_eq!(count(&db), 2);
+    }
+
+    #[test]
+    fn execute_leaves_the_transaction_to_the_caller() {
+        let db = table();
+        let insert = parse("INSERT INTO t VALUES ($1, $2)").unwrap();
+        let mut tx = db.begin().unwrap();
+        assert!(execute(&mut tx, &insert, rows(&[&[1, 10], &[1, 10]])).is_err());
+        tx.commit().unwrap();
+        assert_eq!(count(&db), 1);
+    }
+}
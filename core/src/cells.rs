@@ -0,0 +1,107 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Result values, as the host sees them.
+//!
+//! A binding makes each value of a result from its [`Cell`], which settles
+//! what the value is to the host whatever it is made into: a FLOAT that
+//! isn't finite is NULL, a JSON value is its text and a VECTOR its packed
+//! `f32`s. Extension values of other kinds are NULL too.
+
+use chrono::{DateTime, Utc};
+use stoolap::{SmartString, Value};
+
+/// Tag byte of a VECTOR extension value.
+const VECTOR_TAG: u8 = 7;
+
+/// A result value, borrowed from the row it is in.
+#[derive(Clone, Copy)]
+pub enum Cell<'a> {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    /// A finite FLOAT.
+    Float(f64),
+    Text(&'a SmartString),
+    Timestamp(&'a DateTime<Utc>),
+    /// The text of a JSON value.
+    Json(&'a str),
+    /// The little-endian `f32`s of a VECTOR.
+    Vector(&'a [u8]),
+}
+
+/// The cell of `value`.
+#[inline]
+pub fn cell(value: &Value) -> Cell<'_> {
+    match value {
+        Value::Null(_) => Cell::Null,
+        Value::Boolean(b) => Cell::Boolean(*b),
+        Value::Integer(i) => Cell::Integer(*i),
+        Value::Float(f) if f.is_finite() => Cell::Float(*f),
+        Value::Float(_) => Cell::Null,
+        Value::Text(s) => Cell::Text(s),
+        Value::Timestamp(ts) => Cell::Timestamp(ts),
+        Value::Extension(data) => match value.as_json() {
+            Some(json) => Cell::Json(json),
+            None if data.first() == Some(&VECTOR_TAG) => Cell::Vector(&data[1..]),
+            None => Cell::Null,
+        },
+    }
+}
+
+/// The `f32`s of a [`Cell::Vector`].
+pub fn vector(payload: &[u8]) -> Vec<f32> {
+    payload
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalars_map_to_their_cells() {
+        assert!(matches!(cell(&Value::null_unknown()), Cell::Null));
+        assert!(matches!(cell(&Value::Boolean(true)), Cell::Boolean(true)));
+        assert!(matches!(cell(&Value::Integer(-7)), Cell::Integer(-7)));
+        assert!(matches!(cell(&Value::Float(1.5)), Cell::Float(f) if f == 1.5));
+        let text = Value::text("héllo");
+        assert!(matches!(cell(&text), Cell::Text(s) if s.as_str() == "héllo"));
+        let ts = DateTime::from_timestamp(-86_400, 0).unwrap();
+        assert!(matches!(cell(&Value::Timestamp(ts)), Cell::Timestamp(t) if *t == ts));
+    }
+
+    #[test]
+    fn floats_that_are_not_finite_are_null() {
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(cell(&Value::Float(f)), Cell::Null));
+        }
+    }
+
+    #[test]
+    fn json_is_its_text_and_vectors_their_floats() {
+        let json = Value::json(r#"{"k":[1,2]}"#);
+        assert!(matches!(cell(&json), Cell::Json(r#"{"k":[1,2]}"#)));
+
+        let vector = Value::vector(vec![1.0, -2.5, 0.0]);
+        let Cell::Vector(payload) = cell(&vector) else {
+            panic!("expected a vector");
+        };
+        assert_eq!(payload.len(), 12);
+        assert_eq!(super::vector(payload), vec![1.0, -2.5, 0.0]);
+        assert_eq!(super::vector(&[]), Vec::<f32>::new());
+    }
+}
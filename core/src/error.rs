@@ -0,0 +1,61 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// An error of this crate's calls.
+#[derive(Debug)]
+pub enum Error {
+    /// An error of the engine, which a binding can describe in more detail.
+    Engine(stoolap::Error),
+    /// A call that can't be made as asked, such as with the wrong number of
+    /// parameters.
+    Invalid(String),
+    /// An error of one parameter list of a batch.
+    Row { index: usize, error: Box<Error> },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn invalid(reason: impl Into<String>) -> Self {
+        Error::Invalid(reason.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Engine(err) => err.fmt(f),
+            Error::Invalid(reason) => f.write_str(reason),
+            Error::Row { index, error } => write!(f, "row {index}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Engine(err) => Some(err),
+            Error::Invalid(_) => None,
+            Error::Row { error, .. } => Some(error.as_ref()),
+        }
+    }
+}
+
+impl From<stoolap::Error> for Error {
+    fn from(err: stoolap::Error) -> Self {
+        Error::Engine(err)
+    }
+}
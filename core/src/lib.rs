@@ -0,0 +1,38 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The parts of the Stoolap drivers that don't depend on the host they run
+//! in.
+//!
+//! The Node.js driver (`stoolap-node`) is built on this crate, and another
+//! binding can be too, so that it binds parameters, runs batches and writes
+//! result values the way the Node.js driver does:
+//!
+//! - [`params`]: the parameters of a call, how host numbers, dates and bytes
+//!   become values, and running a statement with them.
+//! - [`batch`]: one statement run with many parameter lists.
+//! - [`cells`]: what a result value is to the host, such as a JSON text or a
+//!   vector.
+//! - [`timestamps`]: how TIMESTAMP results are written as text.
+//!
+//! Nothing here makes host values; a binding makes them from what these
+//! return.
+
+pub mod batch;
+pub mod cells;
+mod error;
+pub mod params;
+pub mod timestamps;
+
+pub use error::{Error, Result};
@@ -0,0 +1,238 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parameters.
+//!
+//! A binding reads each host value it is given and makes it a [`Value`]
+//! with the helpers here where the host's kind of value has one: a number
+//! that is a whole one binds as an INTEGER, a date as a TIMESTAMP, bytes as
+//! UTF-8 text. The values of a call are [`Params`], by position or by name,
+//! which run a statement's SQL or cached plan on a database or transaction.
+
+use chrono::DateTime;
+use stoolap::api::{Database, NamedParams, Transaction};
+use stoolap::{CachedPlanRef, ParamVec, Rows, Value};
+
+use crate::{Error, Result};
+
+/// The parameters of a call.
+#[derive(Clone)]
+pub enum Params {
+    Positional(ParamVec),
+    Named(Vec<(String, Value)>),
+}
+
+impl Params {
+    pub fn execute_on_db(self, db: &Database, sql: &str) -> Result<i64> {
+        Ok(match self {
+            Params::Positional(p) => db.execute(sql, p)?,
+            Params::Named(n) => db.execute_named(sql, named(n))?,
+        })
+    }
+
+    pub fn query_on_db(self, db: &Database, sql: &str) -> Result<Rows> {
+        Ok(match self {
+            Params::Positional(p) => db.query(sql, p)?,
+            Params::Named(n) => db.query_named(sql, named(n))?,
+        })
+    }
+
+    pub fn execute_plan_on_db(&self, db: &Database, plan: &CachedPlanRef) -> Result<i64> {
+        Ok(match self {
+            Params::Positional(p) => db.execute_plan(plan, p.clone())?,
+            Params::Named(n) => db.execute_named_plan(plan, named(n.clone()))?,
+        })
+    }
+
+    pub fn query_plan_on_db(&self, db: &Database, plan: &CachedPlanRef) -> Result<Rows> {
+        Ok(match self {
+            Params::Positional(p) => db.query_plan(plan, p.clone())?,
+            Params::Named(n) => db.query_named_plan(plan, named(n.clone()))?,
+        })
+    }
+
+    pub fn execute_on_tx(self, tx: &mut Transaction, sql: &str) -> Result<i64> {
+        match self {
+            Params::Positional(p) => Ok(tx.execute(sql, p)?),
+            Params::Named(_) => Err(named_on_tx()),
+        }
+    }
+
+    pub fn query_on_tx(self, tx: &mut Transaction, sql: &str) -> Result<Rows> {
+        match self {
+            Params::Positional(p) => Ok(tx.query(sql, p)?),
+            Params::Named(_) => Err(named_on_tx()),
+        }
+    }
+}
+
+fn named(values: Vec<(String, Value)>) -> NamedParams {
+    let mut named = NamedParams::new();
+    for (k, v) in values {
+        named.insert(k, v);
+    }
+    named
+}
+
+fn named_on_tx() -> Error {
+    Error::invalid("Named parameters not yet supported in transactions")
+}
+
+/// A host number: an INTEGER when it is a whole number in range, else a
+/// FLOAT.
+#[inline]
+pub fn number(f: f64) -> Value {
+    if f.fract() == 0.0 && f >= i64::MIN as f64 && f <= i64::MAX as f64 {
+        Value::Integer(f as i64)
+    } else {
+        Value::Float(f)
+    }
+}
+
+/// A host date, as milliseconds since the Unix epoch: a TIMESTAMP, or NULL
+/// when it is out of range.
+pub fn date(ms: f64) -> Value {
+    let secs = (ms / 1000.0).floor() as i64;
+    let remaining_ms = ms - (secs as f64 * 1000.0);
+    let nsecs = (remaining_ms * 1_000_000.0).round() as u32;
+    match DateTime::from_timestamp(secs, nsecs) {
+        Some(dt) => Value::Timestamp(dt),
+        None => Value::null_unknown(),
+    }
+}
+
+/// Host bytes, such as a Node.js `Buffer`, which bind as their UTF-8 text.
+pub fn utf8(bytes: &[u8]) -> Result<Value> {
+    let s = std::str::from_utf8(bytes)
+        .map_err(|e| Error::invalid(format!("Invalid UTF-8 in Buffer: {e}")))?;
+    Ok(Value::text(s))
+}
+
+/// Check that a statement that takes `expected` positional parameters is
+/// given `got`.
+pub fn check_count(expected: usize, got: usize) -> Result<()> {
+    if expected == got {
+        return Ok(());
+    }
+    let noun = if expected == 1 {
+        "parameter"
+    } else {
+        "parameters"
+    };
+    Err(Error::invalid(format!(
+        "Statement requires {expected} {noun}, got {got}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(rows: Rows) -> usize {
+        let mut rows = rows;
+        let mut n = 0;
+        while rows.advance() {
+            n += 1;
+        }
+        n
+    }
+
+    #[test]
+    fn numbers_bind_as_integers_when_whole() {
+        assert_eq!(number(3.0), Value::Integer(3));
+        assert_eq!(number(-0.0), Value::Integer(0));
+        assert_eq!(number(2.5), Value::Float(2.5));
+        assert_eq!(number(1e300), Value::Float(1e300));
+        assert!(matches!(number(f64::NAN), Value::Float(f) if f.is_nan()));
+    }
+
+    #[test]
+    fn dates_keep_sub_millisecond_parts_and_dates_before_the_epoch() {
+        let Value::Timestamp(ts) = date(1.5) else {
+            panic!("expected a timestamp");
+        };
+        assert_eq!(ts.timestamp(), 0);
+        assert_eq!(ts.timestamp_subsec_nanos(), 1_500_000);
+
+        let Value::Timestamp(ts) = date(-1.0) else {
+            panic!("expected a timestamp");
+        };
+        assert_eq!(ts.timestamp(), -1);
+        assert_eq!(ts.timestamp_subsec_millis(), 999);
+
+        assert!(date(1e20).is_null());
+    }
+
+    #[test]
+    fn bytes_bind_as_utf8_text() {
+        assert_eq!(utf8("héllo".as_bytes()).unwrap(), Value::text("héllo"));
+        let err = utf8(&[0xff, 0xfe]).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid UTF-8 in Buffer"));
+    }
+
+    #[test]
+    fn counts_are_checked() {
+        assert!(check_count(2, 2).is_ok());
+        assert_eq!(
+            check_count(1, 2).unwrap_err().to_string(),
+            "Statement requires 1 parameter, got 2"
+        );
+        assert_eq!(
+            check_count(3, 0).unwrap_err().to_string(),
+            "Statement requires 3 parameters, got 0"
+        );
+    }
+
+    #[test]
+    fn params_run_by_position_and_by_name() {
+        let db = Database::open_in_memory().unwrap();
+        db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT)", ())
+            .unwrap();
+        let positional = Params::Positional(vec![Value::Integer(1), Value::text("a")].into());
+        assert_eq!(
+            positional
+                .execute_on_db(&db, "INSERT INTO t VALUES ($1, $2)")
+                .unwrap(),
+            1
+        );
+        let named = Params::Named(vec![
+            ("id".to_string(), Value::Integer(2)),
+            ("v".to_string(), Value::text("b")),
+        ]);
+        assert_eq!(
+            named
+                .execute_on_db(&db, "INSERT INTO t VALUES (:id, :v)")
+                .unwrap(),
+            1
+        );
+        let by_id = Params::Positional(vec![Value::Integer(2)].into());
+        let rows = by_id
+            .query_on_db(&db, "SELECT v FROM t WHERE id = $1")
+            .unwrap();
+        assert_eq!(count(rows), 1);
+
+        let mut tx = db.begin().unwrap();
+        let named = Params::Named(vec![("id".to_string(), Value::Integer(1))]);
+        let Err(err) = named.query_on_tx(&mut tx, "SELECT * FROM t WHERE id = :id") else {
+            panic!("expected named parameters to fail in a transaction");
+        };
+        assert!(matches!(err, Error::Invalid(_)));
+        let all = Params::Positional(ParamVec::new());
+        assert_eq!(
+            count(all.query_on_tx(&mut tx, "SELECT * FROM t").unwrap()),
+            2
+        );
+        tx.rollback().unwrap();
+    }
+}
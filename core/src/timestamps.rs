@@ -0,0 +1,218 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! How TIMESTAMP results are written as text.
+//!
+//! By default a timestamp is ISO 8601 in UTC to the second, which
+//! [`iso_seconds`] writes without going through a formatter. A
+//! [`TimestampFormat`] writes three or six digits of the second always,
+//! and in UTC (`Z`), the process's local time zone or a fixed offset
+//! (`+05:30`). Digits beyond the precision are cut, not rounded, so a value
+//! never moves into the next second.
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, SecondsFormat, Timelike, Utc};
+
+use crate::{Error, Result};
+
+/// How TIMESTAMP results are written, when not as the default.
+#[derive(Clone, Copy)]
+pub struct TimestampFormat {
+    precision: SecondsFormat,
+    zone: Zone,
+}
+
+#[derive(Clone, Copy)]
+enum Zone {
+    Utc,
+    Local,
+    Offset(FixedOffset),
+}
+
+impl TimestampFormat {
+    /// The format of a `precision` of `s`, `ms` or `us` and a `timezone` of
+    /// `utc`, `local` or an offset, or `None` for the default.
+    pub fn new(precision: Option<&str>, timezone: Option<&str>) -> Result<Option<Self>> {
+        let precision = match precision {
+            None | Some("s") => SecondsFormat::Secs,
+            Some("ms") => SecondsFormat::Millis,
+            Some("us") => SecondsFormat::Micros,
+            Some(other) => {
+                return Err(Error::invalid(format!(
+                    "Invalid timestampPrecision '{other}': expected 's', 'ms', or 'us'"
+                )))
+            }
+        };
+        let zone = match timezone {
+            None | Some("utc") => Zone::Utc,
+            Some("local") => Zone::Local,
+            Some(offset) => Zone::Offset(parse_offset(offset).ok_or_else(|| {
+                Error::invalid(format!(
+                    "Invalid timezone '{offset}': expected 'utc', 'local', or an offset like '+05:30'"
+                ))
+            })?),
+        };
+        if precision == SecondsFormat::Secs && matches!(zone, Zone::Utc) {
+            return Ok(None);
+        }
+        Ok(Some(Self { precision, zone }))
+    }
+
+    pub fn format(&self, ts: &DateTime<Utc>) -> String {
+        match self.zone {
+            Zone::Utc => ts.to_rfc3339_opts(self.precision, true),
+            Zone::Local => ts
+                .with_timezone(&Local)
+                .to_rfc3339_opts(self.precision, false),
+            Zone::Offset(offset) => ts
+                .with_timezone(&offset)
+                .to_rfc3339_opts(self.precision, false),
+        }
+    }
+}
+
+/// `ts` as `YYYY-MM-DDTHH:MM:SSZ`, the default TIMESTAMP result.
+pub fn iso_seconds(ts: &DateTime<Utc>) -> String {
+    let mut s = String::with_capacity(22);
+    let mut b = itoa::Buffer::new();
+    let y = ts.year();
+    if (0..10).contains(&y) {
+        s.push_str("000");
+    } else if (10..100).contains(&y) {
+        s.push_str("00");
+    } else if (100..1000).contains(&y) {
+        s.push('0');
+    }
+    s.push_str(b.format(y));
+    s.push('-');
+    let m = ts.month();
+    if m < 10 {
+        s.push('0');
+    }
+    s.push_str(b.format(m));
+    s.push('-');
+    let d = ts.day();
+    if d < 10 {
+        s.push('0');
+    }
+    s.push_str(b.format(d));
+    s.push('T');
+    let h = ts.hour();
+    if h < 10 {
+        s.push('0');
+    }
+    s.push_str(b.format(h));
+    s.push(':');
+    let min = ts.minute();
+    if min < 10 {
+        s.push('0');
+    }
+    s.push_str(b.format(min));
+    s.push(':');
+    let sec = ts.second();
+    if sec < 10 {
+        s.push('0');
+    }
+    s.push_str(b.format(sec));
+    s.push('Z');
+    s
+}
+
+/// `+HH:MM` or `-HH:MM`.
+fn parse_offset(text: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':')?;
+    let digits = |part: &str| part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit());
+    if !digits(hours) || !digits(minutes) {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64, nanos: u32) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, nanos).unwrap()
+    }
+
+    #[test]
+    fn iso_seconds_pads_and_drops_the_fraction() {
+        assert_eq!(iso_seconds(&at(0, 999_999_999)), "1970-01-01T00:00:00Z");
+        assert_eq!(iso_seconds(&at(-1, 500_000_000)), "1969-12-31T23:59:59Z");
+        assert_eq!(iso_seconds(&at(1_704_164_645, 0)), "2024-01-02T03:04:05Z");
+        let early = DateTime::parse_from_rfc3339("0042-03-04T05:06:07Z").unwrap();
+        assert_eq!(iso_seconds(&early.to_utc()), "0042-03-04T05:06:07Z");
+    }
+
+    #[test]
+    fn iso_seconds_matches_chrono() {
+        for secs in [-62_135_596_800, -1, 0, 59, 951_782_400, 4_102_444_799] {
+            let ts = at(secs, 123_456_789);
+            assert_eq!(
+                iso_seconds(&ts),
+                ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+            );
+        }
+    }
+
+    #[test]
+    fn the_default_format_is_none() {
+        assert!(TimestampFormat::new(None, None).unwrap().is_none());
+        assert!(TimestampFormat::new(Some("s"), Some("utc"))
+            .unwrap()
+            .is_none());
+        assert!(TimestampFormat::new(Some("ms"), None).unwrap().is_some());
+    }
+
+    #[test]
+    fn digits_beyond_the_precision_are_cut() {
+        let ts = at(-1, 999_999_999);
+        let ms = TimestampFormat::new(Some("ms"), None).unwrap().unwrap();
+        assert_eq!(ms.format(&ts), "1969-12-31T23:59:59.999Z");
+        let us = TimestampFormat::new(Some("us"), None).unwrap().unwrap();
+        assert_eq!(us.format(&ts), "1969-12-31T23:59:59.999999Z");
+        assert_eq!(us.format(&at(0, 1_500)), "1970-01-01T00:00:00.000001Z");
+    }
+
+    #[test]
+    fn offsets_shift_the_time() {
+        let plus = TimestampFormat::new(None, Some("+05:30")).unwrap().unwrap();
+        assert_eq!(plus.format(&at(0, 0)), "1970-01-01T05:30:00+05:30");
+        let minus = TimestampFormat::new(Some("ms"), Some("-01:00"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(minus.format(&at(0, 0)), "1969-12-31T23:00:00.000-01:00");
+    }
+
+    #[test]
+    fn bad_options_are_rejected() {
+        assert!(TimestampFormat::new(Some("ns"), None).is_err());
+        for offset in ["05:30", "+5:30", "+24:00", "+05:60", "+05-30", "+"] {
+            assert!(
+                TimestampFormat::new(None, Some(offset)).is_err(),
+                "{offset}"
+            );
+        }
+    }
+}
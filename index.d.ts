@@ -2,7 +2,113 @@
 /* eslint-disable */
 
 export interface RunResult {
+  /** Number of rows the statement inserted, updated or deleted. */
   changes: number
+  /**
+   * Primary key of the last row an auto-committed INSERT added, or null for
+   * other statements, inside transactions and batches, and for tables
+   * without a primary key.
+   */
+  lastInsertRowId: number | null
+  /** Wall-clock time the call took in the binding, including retries. */
+  durationMs: number
+  /** Notes about how the statement ran, such as write-conflict retries. */
+  warnings: string[]
+  /**
+   * Rows produced by a `RETURNING` clause of an auto-committed statement;
+   * absent for other statements.
+   */
+  rows?: Record<string, any>[]
+}
+
+/**
+ * One statement of an `exec()` / `queryMulti()` script: the rows it changed,
+ * or its columns and rows when it returns any (SELECT, SHOW, RETURNING, ...).
+ */
+export type StatementResult =
+  | { statement: string, changes: number }
+  | { statement: string, columns: string[], rows: any[][] }
+
+export interface EngineErrorCause extends Error {
+  /** Engine error variant, e.g. `'UniqueConstraint'` or `'TableNotFound'`. */
+  kind: string
+  table?: string
+  column?: string
+  index?: string | number
+  view?: string
+  value?: string
+  expression?: string
+  refTable?: string
+  refColumn?: string
+  rowId?: number
+  expected?: number
+  got?: number
+  max?: number
+  from?: string
+  to?: string
+  /** Free-form detail of kinds that carry only a message, e.g. `'Parse'`. */
+  detail?: string
+}
+
+/** What `db.columnStats()` returns, as of the table's last ANALYZE. */
+export interface ColumnStats {
+  /** Table and column names as the schema spells them. */
+  table: string
+  column: string
+  /** Rows in the table. */
+  rows: number
+  /**
+   * Rows the figures below were computed from: all of them, or a sample of
+   * 10,000 on larger tables.
+   */
+  sampled: number
+  /** Fraction of the sampled rows where the column is NULL. */
+  nullFraction: number
+  /** Distinct non-NULL values in the sampled rows. */
+  ndv: number
+  /** Smallest and largest non-NULL value, or null when there are none. */
+  min: any
+  max: any
+  /** Average size of the column's values, in bytes. */
+  avgWidth: number
+  /** Whether this call ran ANALYZE, because there were no statistics yet. */
+  analyzed: boolean
+}
+
+/** A logged statement, as `db.logStatements()` sinks get it. */
+export interface StatementLogEvent {
+  sql: string
+  durationMs: number
+  /** Rows returned by a query, or rows changed by DML. */
+  rows: number
+  /**
+   * The statement's parameters, with `includeParams`: an array, an object
+   * for named ones, or an array of arrays for the rows of `executeBatch()`.
+   */
+  params?: any[] | Record<string, any>
+}
+
+/** What changed in one table, from the first database to the second. */
+export interface TableDiff {
+  table: string
+  /** Rows only the second database has. */
+  inserted: Record<string, any>[]
+  /**
+   * Rows both have under the same primary key, with other values in the
+   * second. Tables without a single-column primary key have none.
+   */
+  updated: Array<{ key: any, before: Record<string, any>, after: Record<string, any> }>
+  /** Rows only the first database has. */
+  deleted: Record<string, any>[]
+}
+
+/**
+ * A statement or expression of `Database.parse()`: `type` is its kind in
+ * the engine's AST and the other keys its parts, null where absent.
+ */
+export interface SyntaxNode {
+  type: string
+  [key: string]: any
 }
 
 export declare class Database {
@@ -14,8 +120,64 @@ export declare class Database {
    * - `memory://` for in-memory database
    * - `file:///path/to/db` for file-based database
    * - Bare path like `./mydb` for file-based database
+   *
+   * `options` sets engine tunables (e.g. `{ sync: 'full' }`) so they apply
+   * from the first statement; they are equivalent to DSN query parameters.
+   * `options.retry` retries async auto-committed statements that fail with
+   * a write conflict; `options.autoAnalyze` refreshes table statistics in
+   * the background once enough rows changed. `options.dedicatedThread`
+   * runs the database's async calls in order on a thread of its own;
+   * `options.readers` runs async read-only queries in parallel;
+   * `options.serializeWrites` queues async writes to hot tables, and
+   * `options.memoryBudget` bounds the query results held in memory.
+   * `options.idleTransactionMs` warns about transactions left idle,
+   * `options.asyncStackTraces` adds the caller's stack to async errors,
+   * `options.columnCase` converts column names in results,
+   * `options.duplicateColumns` decides what colliding names become,
+   * `options.temporal` returns timestamps as `Temporal.Instant`s,
+   * `options.timestampPrecision` and `options.timezone` change how they
+   * are written as strings, and `options.maxRows` caps every result.
+   */
+  static open(path: string, options?: OpenOptions): Promise<Database>
+  /**
+   * Report what this engine and binding support: versions, SQL features,
+   * registered functions, vector support and binding-level value handling,
+   * so libraries can feature-detect instead of probing with try/catch.
+   */
+  static capabilities(): Capabilities
+  /**
+   * Report the runtime hosting the addon and how result rows are built:
+   * with direct V8 calls, or with N-API when the V8 helpers can't be used
+   * in this runtime.
+   */
+  static runtimeInfo(): RuntimeInfo
+  /**
+   * Reprint SQL canonically: keywords upper-cased, one clause per line and
+   * subqueries indented by `options.indent` (default 2 spaces; 0 prints
+   * each statement on one line). Literals, identifiers and comments are
+   * kept as written. Throws if the SQL does not parse.
    */
-  static open(path: string): Promise<Database>
+  static formatSql(sql: string, options?: FormatOptions): string
+  /**
+   * Parse SQL without running it and return its statements' syntax
+   * trees as plain objects, each node with a `type` naming it. Throws if
+   * the SQL does not parse.
+   */
+  static parse(sql: string): SyntaxNode[]
+  /**
+   * Compare the rows of two database files: what was inserted, updated
+   * or deleted from `pathA` to `pathB`, table by table, for the tables
+   * that differ. With `options.format: 'sql'` resolves to the statements
+   * that turn the first database's rows into the second's instead.
+   */
+  static diffData(pathA: string, pathB: string, options?: DiffDataOptions): Promise<TableDiff[] | string>
+  /**
+   * Append WAL segments from `db.exportWalSegments()` to the standby
+   * database at `path`, which must not be open, creating it if needed.
+   * The standby replays them when it is next opened. Returns
+   * Promise<number>: the primary LSN the standby has applied up to.
+   */
+  static applyWalSegments(path: string, segments: Array<WalSegment>): Promise<number>
   /**
    * Execute a DDL/DML statement. Returns Promise<{ changes: number }>.
    *
@@ -25,23 +187,98 @@ export declare class Database {
   execute(sql: string, params?: any[] | Record<string, any>): Promise<RunResult>
   /**
    * Execute one or more SQL statements separated by semicolons.
-   * Returns Promise<void>.
+   * Returns Promise<Array>, one `StatementResult` per statement.
+   */
+  exec(sql: string): Promise<StatementResult[]>
+  /**
+   * Run one or more SQL statements and return every result set. Same as
+   * `exec()`; returns Promise<Array>, one `StatementResult` per statement.
+   */
+  queryMulti(sql: string): Promise<StatementResult[]>
+  /**
+   * Execute the same SQL with multiple param sets. Returns Promise<RunResult>.
+   *
+   * Like `executeBatchSync()`: the SQL is parsed once and the params are
+   * converted up front, then every set runs in one transaction off the
+   * event loop.
+   */
+  executeBatch(sql: string, paramsArray: any[][]): Promise<RunResult>
+  /**
+   * Delete the rows whose key is in `keys`. Returns Promise<{ changes: number }>.
+   *
+   * Keys are deleted in `IN` lists of `options.chunkSize` (default 500),
+   * each committed in its own transaction. `options.keyColumn` defaults to
+   * the table's primary key.
+   */
+  deleteMany(table: string, keys: any[], options?: DeleteManyOptions): Promise<RunResult>
+  /**
+   * Read an adjacency-list table as nested objects. Returns Promise<Array<Object>>.
+   *
+   * Walks down from `options.rootId`, or from every row whose
+   * `options.parentColumn` is NULL, with one recursive CTE. Each node is the
+   * row plus its children (in id order) under `options.childrenKey`.
+   */
+  tree(table: string, options: TreeOptions): Promise<Record<string, any>[]>
+  /**
+   * Query the first `options.n` rows of each group. Returns Promise<Array<Object>>.
+   *
+   * Rows are ranked within each `options.group` by `options.orderBy` with
+   * a window function, and come back ordered by group, then rank.
    */
-  exec(sql: string): Promise<void>
+  topNPerGroup(table: string, options: TopNOptions): Promise<Record<string, any>[]>
+  /**
+   * Statistics of `table.column` from the engine's ANALYZE data: row
+   * count, null fraction, distinct values, min and max. Runs ANALYZE on
+   * the table first when it has none for the column. Returns
+   * Promise<ColumnStats>.
+   */
+  columnStats(table: string, column: string): Promise<ColumnStats>
   /**
    * Query rows. Returns Promise<Array<Object>>.
    *
    * Each row is an object with column names as keys.
+   * Pass `options.asOf` (Date, timestamp string, or transaction id) to read
+   * the table as it was at that point in MVCC history. With the
+   * `consistencyTokens` open option, the array has a `consistencyToken`.
    */
-  query(sql: string, params?: any[] | Record<string, any>): Promise<Record<string, any>[]>
+  query(sql: string, params?: any[] | Record<string, any>, options?: QueryOptions): Promise<Record<string, any>[]>
   /** Query a single row. Returns Promise<Object | null>. */
-  queryOne(sql: string, params?: any[] | Record<string, any>): Promise<Record<string, any> | null>
+  queryOne(sql: string, params?: any[] | Record<string, any>, options?: QueryOptions): Promise<Record<string, any> | null>
   /**
    * Query rows in raw format. Returns Promise<{ columns: string[], rows: any[][] }>.
    *
    * Faster than query() — skips per-row object creation.
    */
-  queryRaw(sql: string, params?: any[] | Record<string, any>): Promise<{ columns: string[], rows: any[][] }>
+  queryRaw(sql: string, params?: any[] | Record<string, any>, options?: QueryOptions): Promise<{ columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }>
+  /**
+   * Query rows a chunk at a time. Returns Promise<number>, the row count.
+   *
+   * Calls `onChunk` with arrays of up to `options.chunkSize` (default 1000)
+   * row objects, in order, reading the next chunk while it runs, so only
+   * two chunks are held at once. The promise settles after the last
+   * callback; if one throws, the query stops and the promise rejects with
+   * what it threw. `onChunk`'s return value is ignored.
+   */
+  queryChunks(sql: string, params: any[] | Record<string, any> | null | undefined, options: QueryChunksOptions | null | undefined, onChunk: (rows: Record<string, any>[]) => void): Promise<number>
+  /**
+   * Dump the database as a SQL script. Returns Promise<string>, or
+   * Promise<void> when `onChunk` is given.
+   *
+   * The script is CREATE TABLE, CREATE INDEX and CREATE VIEW statements
+   * followed by INSERTs, read from one snapshot, which `restore()` runs
+   * back. With `onChunk` the script is passed to it in pieces (the schema,
+   * then one INSERT of up to 500 rows at a time) instead of being built in
+   * memory; if it throws, the dump stops and the promise rejects.
+   */
+  dump(options?: DumpOptions | null, onChunk?: (sql: string) => void): Promise<string | void>
+  /**
+   * Run a script from `dump()`. Returns Promise<{ changes: number }>.
+   *
+   * Accepts only CREATE TABLE/INDEX/VIEW, INSERT, UPDATE and DELETE. The
+   * rows go in one transaction; if any statement fails it is rolled back
+   * and the tables, indexes and views the script created are dropped.
+   */
+  restore(sql: string): Promise<RunResult>
   /**
    * Execute a DML statement synchronously. Returns { changes: number }.
    *
@@ -53,70 +290,481 @@ export declare class Database {
    * Query rows synchronously. Returns Array<Object>.
    * Uses direct V8 bulk object creation — bypasses NAPI per-property overhead.
    */
-  querySync(sql: string, params?: any[] | Record<string, any>): Record<string, any>[]
+  querySync(sql: string, params?: any[] | Record<string, any>, options?: QueryOptions): Record<string, any>[]
   /**
    * Query a single row synchronously. Returns Object | null.
    * Uses direct V8 bulk object creation — optimal hidden class in one call.
    */
-  queryOneSync(sql: string, params?: any[] | Record<string, any>): Record<string, any> | null
+  queryOneSync(sql: string, params?: any[] | Record<string, any>, options?: QueryOptions): Record<string, any> | null
   /**
    * Query rows in raw format synchronously. Returns { columns: string[], rows: any[][] }.
    * Uses direct V8 bulk array creation — bypasses NAPI per-element overhead.
    */
-queryRawSync(sql: string, params?: any[] | Record<string, any>): { columns: string[], rows: any[][] }
+queryRawSync(sql: string, params?: any[] | Record<string, any>, options?: QueryOptions): { columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }
 /**
  * Execute the same SQL with multiple param sets in a single call.
  * Parses SQL once, auto-wraps in a transaction: begin, execute all, commit.
  * Returns { changes: total_rows_affected }.
  */
 executeBatchSync(sql: string, paramsArray: any[][]): RunResult
-/** Execute one or more SQL statements synchronously. */
-execSync(sql: string): void
-/** Create a prepared statement (synchronous — parses and caches the plan). */
-prepare(sql: string): JsPreparedStatement
+/**
+ * Delete the rows whose key is in `keys` synchronously, in chunked `IN`
+ * lists with one transaction per chunk. Returns { changes: number }.
+ */
+deleteManySync(table: string, keys: any[], options?: DeleteManyOptions): RunResult
+/**
+ * Read an adjacency-list table as nested objects synchronously.
+ * Returns Array<Object>.
+ */
+treeSync(table: string, options: TreeOptions): Record<string, any>[]
+/**
+ * Query the first `options.n` rows of each group synchronously.
+ * Returns Array<Object>.
+ */
+topNPerGroupSync(table: string, options: TopNOptions): Record<string, any>[]
+/**
+ * Statistics of `table.column` synchronously; see `columnStats()`.
+ * Returns ColumnStats.
+ */
+columnStatsSync(table: string, column: string): ColumnStats
+/**
+ * Execute one or more SQL statements synchronously.
+ * Returns one `StatementResult` per statement.
+ */
+execSync(sql: string): StatementResult[]
+/**
+ * Run one or more SQL statements synchronously and return every result
+ * set. Same as `execSync()`.
+ */
+queryMultiSync(sql: string): StatementResult[]
+/**
+ * Replace each `{{name}}` in `sql` with the table, view or column
+ * `identifiers` gives for it, quoted, after checking it against the
+ * schema: `'orders'`, `'orders.total'`, or `'total'` for a column of any
+ * table. The result must parse; run it like any other SQL.
+ */
+bindIdentifiers(sql: string, identifiers: Record<string, string>): string
+/**
+ * Parse `sql` without running it and report its syntax errors, each
+ * with its line and column. Tables and columns aren't checked.
+ */
+validate(sql: string): Validation
+/**
+ * Create a prepared statement (synchronous — parses and caches the plan).
+ * `options.maxSqlBytes` rejects longer SQL before it is parsed.
+ */
+prepare(sql: string, options?: PrepareOptions): JsPreparedStatement
+/**
+ * Create a prepared statement, parsing the SQL off the JS thread.
+ * Returns Promise<PreparedStatement>. `options` as for `prepare()`.
+ */
+prepareAsync(sql: string, options?: PrepareOptions): Promise<PreparedStatement>
+/**
+ * Start a pipeline: queue statements with `query()` and `execute()`,
+ * then `run()` them in order in a single async call.
+ */
+pipeline(): Pipeline
+/**
+ * Run a query and keep its rows, to read them a few at a time with the
+ * returned cursor's `fetchNext(n)`.
+ */
+openCursor(sql: string, params?: any[] | Record<string, any>, options?: CursorOptions): Cursor
+/**
+ * Prepared statement usage by SQL text, most executed first: how often
+ * each was prepared and run, and when it last ran. Pass
+ * `options.unusedForMs` to list only statements that haven't run for
+ * that long.
+ */
+statementReport(options?: StatementReportOptions): Array<StatementUsageEntry>
+/**
+ * Create a table only `callback` uses, pass it to `callback` and drop it
+ * once the promise `callback` returns settles, whether it resolves or
+ * rejects. `columns` is a string of column definitions or an object of
+ * column types by name. Returns Promise<T>, settled as `callback` was.
+ */
+withTempTable<Row extends Record<string, any> = Record<string, any>, T = unknown>(columns: string | { [K in keyof Row]: string }, callback: (table: TempTable<Row>) => T | Promise<T>): Promise<T>
 /** Begin a transaction. Returns Promise<Transaction>. */
-begin(): Promise<Transaction>
+begin(options?: TransactionOptions): Promise<Transaction>
 /** Begin a transaction synchronously. Returns Transaction. */
-beginSync(): Transaction
-/** Close the database. Returns Promise<void>. */
-close(): Promise<void>
+beginSync(options?: TransactionOptions): Transaction
+/**
+ * Create (or replace) a named checkpoint. Returns Promise<void>.
+ *
+ * Only a transaction marker and the current table list are recorded;
+ * no data is copied. Checkpoints live for the lifetime of this handle.
+ */
+createCheckpoint(name: string): Promise<void>
+/**
+ * Roll the checkpointed tables back to their contents at the checkpoint,
+ * in a single transaction. Returns Promise<void>.
+ *
+ * Schema changes are not reverted, and restoring only works while the
+ * old row versions are retained (see `deleted_row_retention`).
+ */
+restoreCheckpoint(name: string): Promise<void>
+/**
+ * Append every statement run on this handle (and its statements,
+ * transactions and pipelines) to the file at `path`, one JSON object
+ * per line with its SQL, parameters, duration and row count. Replaces
+ * the file and any recording already running.
+ */
+record(path: string): void
+/**
+ * Stop recording and close the file. Throws the error that stopped the
+ * recording early, such as a full disk, if one did.
+ */
+stopRecording(): void
+/**
+ * Call `options.sink` with a `sample` of the statements run on this
+ * handle (and its statements, transactions and pipelines) that
+ * complete, each with its SQL, duration and row count, and its
+ * parameters with `includeParams`. Statements are sampled at random
+ * when they start. Replaces the sink logged to before.
+ */
+logStatements(options: StatementLogOptions): void
+/** Stop calling the sink given to `logStatements()`. */
+stopLoggingStatements(): void
+/**
+ * Run the statements recorded in the file at `path` on this database,
+ * in order. Returns Promise<ReplayReport> with the time they took now
+ * and when recorded, overall and per SQL text.
+ *
+ * A failing statement is counted and replay goes on with the next one.
+ * Statements of a recorded transaction run on their own.
+ */
+replay(path: string): Promise<ReplayReport>
+/**
+ * The plan cache shared by this handle's statements: `stats()`,
+ * `clear()`, and settable `maxSize` and `automatic`.
+ */
+get planCache(): PlanCache
+/**
+ * Describe this database for support bundles and bug reports: the DSN
+ * with credentials redacted, the engine options in effect, and the
+ * engine/binding versions and build features.
+ */
+describe(): DatabaseDescription
+/**
+ * Read an engine setting, or change it when `value` is given. Returns the
+ * setting's current value.
+ *
+ * Keys are the `EngineOptions` names. `sync`, `snapshotInterval`,
+ * `keepSnapshots` and `walFlushTrigger` can be changed at runtime; the
+ * others are fixed once the database is open.
+ */
+config(key: keyof EngineOptions, value?: string | number | boolean): string | number | boolean
+/**
+ * Restrict the SQL this handle (and its statements and transactions) will
+ * run. Pass `null` to remove the policy.
+ *
+ * - `deny`: statement types to reject, e.g. `['DROP', 'ALTER']`
+ * - `allowTables`: the only tables and views statements may reference
+ *
+ * Statements are checked when parsed, before they reach the engine.
+ */
+setPolicy(policy: SqlPolicy | null): void
+/**
+ * A handle on this database that only runs the statement types of
+ * `allow`, such as `['SELECT']`, to hand to code that must not write.
+ * Statements of other types are rejected when parsed, before they reach
+ * the engine, on the handle and its statements, transactions and
+ * pipelines; the policy of `setPolicy()` on this handle applies to it
+ * too. The calls that would change this handle's settings, such as
+ * `setPolicy()`, `registerType()` or `close()`, throw on it.
+ */
+createRestrictedHandle(options: RestrictedHandleOptions): Database
+/**
+ * Make this handle's async calls (and those of its statements,
+ * transactions and pipelines) fail or slow down at random, to test
+ * error handling. Replaces the faults enabled before.
+ *
+ * - `errorRate`: fraction of calls that fail, from 0 to 1
+ * - `latencyMs`: most milliseconds a call is delayed by
+ */
+enableFaultInjection(options: FaultInjectionOptions): void
+/** Stop injecting faults. */
+disableFaultInjection(): void
+/**
+ * Keep the slowest SELECTs this handle (and its statements,
+ * transactions and pipelines) runs, for `applyAdvisedIndexes()`.
+ * Enabling again starts over.
+ *
+ * - `slowest`: fingerprints kept, the slowest by mean time
+ * - `windowMs`: milliseconds a fingerprint is kept after its last call
+ */
+enableIndexAdvisor(options?: IndexAdvisorOptions): void
+/** Stop keeping slow queries, and forget those kept. */
+disableIndexAdvisor(): void
+/**
+ * Create the indexes advised for the slow queries kept by the index
+ * advisor, keeping each only if its query got faster with it. With
+ * `dryRun` the indexes are listed but not created. On failure the
+ * indexes created are dropped again, as far as they can be: the error
+ * names any left in place.
+ * Returns Promise<AdvisedIndex[]>.
+ */
+applyAdvisedIndexes(options?: ApplyIndexesOptions): Promise<AdvisedIndex[]>
+/**
+ * Drop the deleted rows and old row versions no transaction can see
+ * from every table. With `into`, also write a compacted copy of the
+ * database, holding only its live rows, to that new file.
+ * `onProgress` is called after each table. Returns Promise<VacuumReport>.
+ */
+vacuum(options?: VacuumOptions): Promise<VacuumReport>
+/**
+ * The committed WAL entries written after `sinceLsn` (default 0), for
+ * `Database.applyWalSegments()` to keep a standby copy up to date.
+ * Pass the last segment's `toLsn` as `sinceLsn` next time. Not
+ * available on a restricted handle, or while a policy limits the tables
+ * or denies SELECT, since the entries hold every table's rows.
+ * Returns Promise<WalSegment[]>.
+ */
+exportWalSegments(sinceLsn?: number): Promise<WalSegment[]>
+/**
+ * Convert values of a custom type for this handle (and its statements,
+ * transactions and pipelines).
+ *
+ * - `test(value)` picks the parameters `serialize(value)` turns into a
+ *   supported value
+ * - `deserialize(value, column)` revives result values, of the
+ *   `columns` named or every column; returning `undefined` leaves the
+ *   value to the next type
+ *
+ * Types are tried in the order they were registered.
+ */
+registerType(options: TypeOptions): void
+/**
+ * Pass every value of this handle's results (and its statements',
+ * transactions' and pipelines') through `filter(table, column, value)`
+ * as the rows are made, and use what it returns. `table` and `column`
+ * are those the value comes from; `table` is null, and `column` the
+ * result's key, when they can't be worked out from the query. `null`
+ * removes the filter.
+ */
+setResultFilter(filter: ((table: string | null, column: string, value: any) => any) | null): void
+/**
+ * Register an event listener. Supported events:
+ *
+ * - `'change'`: called with `{ op, table, rowId }` for every row written
+ *   by INSERT/UPDATE/DELETE through this handle, after the change commits.
+ * - `'profile'`: called with `{ sql, durationMs, rows, plan }` after each
+ *   statement completes. `options.thresholdMs` skips faster statements and
+ *   `options.plan` attaches the statement's query plan.
+ * - `'task'`: called with `{ phase, method, queued, running, waitMs,
+ *   runMs, ok }` when an async call is enqueued, starts on a thread
+ *   (`dequeue`) and finishes its work (`complete`).
+ * - `'transactionTimeout'`: called with `{ maxDurationMs, durationMs }`
+ *   when a transaction begun with `maxDurationMs` is rolled back for
+ *   being open too long.
+ * - `'transactionIdle'`: called with `{ idleMs, openMs, stack }` when
+ *   a transaction has had no calls for `idleTransactionMs`.
+ */
+on(event: 'change', listener: (change: ChangeEvent) => void): void
+on(event: 'profile', listener: (profile: ProfileEvent) => void, options?: ProfileOptions): void
+on(event: 'task', listener: (task: TaskEvent) => void): void
+on(event: 'transactionTimeout', listener: (timeout: TransactionTimeoutEvent) => void): void
+on(event: 'transactionIdle', listener: (idle: TransactionIdleEvent) => void): void
+/**
+ * Async calls of this handle (and its statements, transactions and
+ * pipelines) waiting for a thread or running on one.
+ */
+get taskQueue(): TaskQueueStats
+/**
+ * Queue counters for each table in `serializeWrites`: whether it is
+ * held, calls waiting for it, and how long calls have waited.
+ */
+get writeQueue(): Array<WriteQueueStats>
+/**
+ * Limit the query results this handle (and its statements, transactions
+ * and pipelines) holds in memory at once to `mb` megabytes, or remove
+ * the limit with `null`. A query whose rows would exceed it fails.
+ */
+setMemoryBudget(mb: number | null): void
+/**
+ * The memory budget, the estimated size of the query results held now
+ * and the number of queries that exceeded the budget.
+ */
+get memoryBudget(): MemoryBudgetStats
+/**
+ * Whether the tables a query result's `consistencyToken` was issued for
+ * have changed since. Needs the `consistencyTokens` open option.
+ */
+isStale(token: string): boolean
+/**
+ * Memory the binding holds for this handle: the query results held now,
+ * the most held at once and by one call, and the plan cache's SQL, next
+ * to the engine's WAL buffer.
+ */
+memoryUsage(): MemoryUsage
+/**
+ * How long the `*Sync` methods of this handle (and its statements and
+ * transactions) have blocked the JS thread: calls, total, p99 and
+ * slowest, overall and per method.
+ */
+get syncBusy(): SyncBusyStats
+/**
+ * Statements this handle (and its statements, transactions and
+ * pipelines) has completed.
+ */
+get queryCount(): number
+/**
+ * Rows changed by the last completed statement that ran for its
+ * changes, such as an `execute()`.
+ */
+get changes(): number
+/**
+ * Rows changed by all the statements this handle (and its statements,
+ * transactions and pipelines) has completed.
+ */
+get totalChanges(): number
+/**
+ * False once the database has been closed, through this handle or
+ * another on the same database.
+ */
+get isOpen(): boolean
+/** Whether the handle was made by `createRestrictedHandle()`. */
+get isRestricted(): boolean
+/**
+ * Check the database can run statements, with a trivial one on a worker
+ * thread. Resolves to the round-trip time in milliseconds, and rejects
+ * with a `DatabaseClosed` error once the database is closed. Returns
+ * Promise<number>.
+ */
+ping(): Promise<number>
+/**
+ * Answer the `query()` and `execute()` calls of a `RemoteDatabase` made
+ * on the other end of `port`, usually from the thread that started this
+ * worker. The calls run as this handle's own async calls do, and their
+ * results are encoded off the JS thread. Serves until the port closes.
+ */
+serve(port: import('node:worker_threads').MessagePort): void
+/**
+ * Interrupt every async statement issued on this handle (and its
+ * statements and transactions) that hasn't completed. They reject with an
+ * `Interrupted` error; statements issued afterwards run normally.
+ *
+ * Statements stop at their next check: before they start, between rows
+ * while results are collected, or between the statements of `exec()`.
+ * Work already inside the engine, such as a sort or a write, finishes first.
+ */
+interrupt(): void
+/**
+ * Close the database, and stop recording. Async calls issued afterwards
+ * reject with a `DatabaseClosed` error. In `drain` mode (the default)
+ * the calls issued before wait to finish first; in `abort` mode they
+ * reject with the same error, those already inside the engine once they
+ * reach their next check. After `timeoutMs` a drain aborts what is left
+ * and the database closes without waiting further. Returns Promise<void>.
+ */
+close(options?: CloseOptions): Promise<void>
+/**
+ * Close the database synchronously, without waiting for pending async
+ * calls. Async calls issued afterwards reject with a `DatabaseClosed`
+ * error. Closing twice is a no-op.
+ */
+closeSync(): void
 }
 export type JsDatabase = Database
 
 export declare class PreparedStatement {
   /** Execute the statement (DML). Returns Promise<{ changes: number }>. */
   execute(params?: any[] | Record<string, any>): Promise<RunResult>
+  /**
+   * Execute the statement with multiple param sets. Returns
+   * Promise<RunResult>.
+   *
+   * Like `executeBatchSync()`: the params are converted up front, then
+   * every set runs in one transaction off the event loop.
+   */
+  executeBatch(paramsArray: any[][]): Promise<RunResult>
   /** Query rows. Returns Promise<Array<Object>>. */
-  query(params?: any[] | Record<string, any>): Promise<Record<string, any>[]>
+  query(params?: any[] | Record<string, any>, options?: StatementQueryOptions): Promise<Record<string, any>[]>
   /** Query single row. Returns Promise<Object | null>. */
-  queryOne(params?: any[] | Record<string, any>): Promise<Record<string, any> | null>
+  queryOne(params?: any[] | Record<string, any>, options?: StatementQueryOptions): Promise<Record<string, any> | null>
   /** Query rows in raw format. Returns Promise<{ columns: string[], rows: any[][] }>. */
-  queryRaw(params?: any[] | Record<string, any>): Promise<{ columns: string[], rows: any[][] }>
+  queryRaw(params?: any[] | Record<string, any>, options?: StatementQueryOptions): Promise<{ columns: string[], rows: any[][] }>
+  /** Explain the cached plan without executing it. Returns Promise<QueryPlan>. */
+  explain(params?: any[] | Record<string, any>): Promise<QueryPlan>
+  /**
+   * Execute the statement and explain it with actual row counts and timing.
+   * Returns Promise<QueryPlan>.
+   */
+  explainAnalyze(params?: any[] | Record<string, any>): Promise<QueryPlan>
   /** Execute synchronously. Returns { changes: number }. */
   executeSync(params?: any[] | Record<string, any>): RunResult
   /**
    * Query rows synchronously. Returns Array<Object>.
    * Uses direct V8 bulk object creation — bypasses NAPI per-property overhead.
    */
-  querySync(params?: any[] | Record<string, any>): Record<string, any>[]
+  querySync(params?: any[] | Record<string, any>, options?: StatementQueryOptions): Record<string, any>[]
   /**
    * Query single row synchronously. Returns Object | null.
    * Uses direct V8 bulk object creation — optimal hidden class in one call.
    */
-  queryOneSync(params?: any[] | Record<string, any>): Record<string, any> | null
+  queryOneSync(params?: any[] | Record<string, any>, options?: StatementQueryOptions): Record<string, any> | null
   /**
    * Query rows in raw format synchronously. Returns { columns: string[], rows: any[][] }.
    * Uses direct V8 bulk array creation — bypasses NAPI per-element overhead.
    */
-queryRawSync(params?: any[] | Record<string, any>): { columns: string[], rows: any[][] }
+queryRawSync(params?: any[] | Record<string, any>, options?: StatementQueryOptions): { columns: string[], rows: any[][] }
+/** Explain the cached plan synchronously without executing it. Returns QueryPlan. */
+explainSync(params?: any[] | Record<string, any>): QueryPlan
+/**
+ * Execute synchronously and explain with actual row counts and timing.
+ * Returns QueryPlan.
+ */
+explainAnalyzeSync(params?: any[] | Record<string, any>): QueryPlan
 /**
  * Execute the prepared SQL with multiple param sets in a single call.
  * Uses pre-cached AST, auto-wraps in a transaction: begin, execute all, commit.
  * Returns { changes: total_rows_affected }.
  */
 executeBatchSync(paramsArray: any[][]): RunResult
+/**
+ * Bind `params` to the statement, for the calls that run it without
+ * parameters of their own; calls given parameters use those instead.
+ * They are converted once, here, and can be bound again; call with no
+ * arguments to unbind them.
+ */
+bind(params?: any[] | Record<string, any> | null): this
+/**
+ * Return only the first column of each row from `query()` and
+ * `queryOne()` and their sync forms, or turn that off with `false`.
+ * Turns `expand()` off.
+ */
+pluck(toggle?: boolean): this
+/**
+ * Return each row from `query()` and `queryOne()` and their sync forms
+ * as one object per table, keyed by its alias or name, with columns
+ * that don't belong to a table under `$`; or turn that off with
+ * `false`. Turns `pluck()` off.
+ */
+expand(toggle?: boolean): this
+/**
+ * Case the column names of this statement's rows and `columns`:
+ * `'camel'`, `'snake'` or `'asIs'`. Starts as the database's
+ * `columnCase`.
+ */
+columnCase(columnCase: 'camel' | 'snake' | 'asIs'): this
+/**
+ * Finalize the statement. Later calls on it throw; calls already running
+ * finish normally. Finalizing twice is a no-op.
+ */
+finalize(): void
+/** Whether `finalize()` has been called. */
+get finalized(): boolean
 /** Get the SQL text of this prepared statement. */
 get sql(): string
+/**
+ * Number of parameters the statement takes: the highest `$n` or the
+ * number of `?` placeholders, or the number of distinct `:name` ones.
+ */
+get parameterCount(): number
+/**
+ * Names of the `:name` placeholders, without the colon, in order of
+ * first use. Empty for positional placeholders.
+ */
+get parameterNames(): Array<string>
 }
 export type JsPreparedStatement = PreparedStatement
 
@@ -126,6 +774,14 @@ export declare class Transaction {
    * Returns Promise<{ changes: number }>.
    */
   execute(sql: string, params?: any[] | Record<string, any>): Promise<RunResult>
+  /**
+   * Execute the same SQL with multiple param sets within the transaction.
+   * Returns Promise<{ changes: number }>.
+   *
+   * Like `executeBatchSync()`: the SQL is parsed once and the params are
+   * converted up front, then every set runs off the event loop.
+   */
+  executeBatch(sql: string, paramsArray: any[][]): Promise<RunResult>
   /**
    * Query rows within the transaction.
    * Returns Promise<Array<Object>>.
@@ -170,7 +826,1175 @@ commitSync(): void
  * Returns { changes: total_rows_affected }.
  */
 executeBatchSync(sql: string, paramsArray: any[][]): RunResult
+/**
+ * Whether the transaction can still run statements: false once it has
+ * been committed or rolled back.
+ */
+get active(): boolean
+/** `active`, named like `db.isOpen`. */
+get isActive(): boolean
+/**
+ * Results kept for repeated SELECTs, and how often one was reused.
+ * Zero unless the transaction was begun with `memoize: true`.
+ */
+get memoStats(): MemoStats
+/**
+ * The statements the transaction has run so far, as one SQL script.
+ * Needs `recordSql: true` in the `begin()` options.
+ */
+toSql(options?: ToSqlOptions): string
 /** Rollback the transaction synchronously. */
 rollbackSync(): void
 }
 export type JsTransaction = Transaction
+
+/** JS handle for a database's plan cache, returned by `db.planCache`. */
+export declare class PlanCache {
+  /** Entry count, capacity, hit/miss/eviction counters and key size. */
+  stats(): PlanCacheStats
+  /** Drop every cached plan. The counters are kept. */
+  clear(): void
+  /**
+   * Maximum number of cached plans (default 1000). Lowering it evicts the
+   * least recently used plans; 0 disables caching.
+   */
+  get maxSize(): number
+  set maxSize(maxSize: number)
+  /**
+   * Whether `execute()` / `query()` calls on the database reuse cached
+   * plans like prepared statements do (default false).
+   */
+  get automatic(): boolean
+  set automatic(automatic: boolean)
+}
+export type JsPlanCache = PlanCache
+
+export declare class Pipeline {
+  /** Queue a query. Its rows come back as an array of objects. */
+  query(sql: string, params?: any[] | Record<string, any>, options?: QueryOptions): this
+  /** Queue a DDL/DML statement. It comes back as a `RunResult`. */
+  execute(sql: string, params?: any[] | Record<string, any>): this
+  /** Number of queued calls. */
+  get length(): number
+  /**
+   * Run the queued calls in order. Returns Promise<Array>, one result per
+   * call. The queue is emptied, so the pipeline can be reused.
+   */
+  run(): Promise<Array<Record<string, any>[] | RunResult>>
+}
+export type JsPipeline = Pipeline
+
+/** JS handle for an open query, returned by `db.openCursor()`. */
+export declare class Cursor {
+  /** Keys of the result rows. */
+  get columns(): Array<string>
+  /** Rows the next `fetchNext()` without a count will read. */
+  get fetchSize(): number
+  /**
+   * The next `n` rows, or fewer once the result runs out; without `n`,
+   * `fetchSize` rows. Returns Promise<Array<Object>>.
+   */
+  fetchNext(n?: number): Promise<Record<string, any>[]>
+  /**
+   * The next `n` rows synchronously, or `fetchSize` rows without `n`.
+   * Returns Array<Object>.
+   */
+  fetchNextSync(n?: number): Record<string, any>[]
+  /**
+   * Drop the rows not fetched yet. Fetching afterwards throws; closing
+   * twice is a no-op.
+   */
+  close(): void
+}
+export type JsCursor = Cursor
+
+/** The table of one `withTempTable()` call, passed to its callback. */
+export declare class TempTable<Row extends Record<string, any> = Record<string, any>> {
+  /** Name of the table, to use it in SQL of your own. */
+  get name(): string
+  /** Names of the columns, in table order. */
+  get columns(): Array<string>
+  /**
+   * Insert one row or an array of rows in one transaction. A row is an
+   * object of values by column name, where missing columns are NULL, or
+   * an array of values in column order. Returns Promise<RunResult>.
+   */
+  insert(rows: Partial<Row> | any[] | Array<Partial<Row> | any[]>): Promise<RunResult>
+  /**
+   * Query the table's rows. `clause` follows `FROM <table>`, as in
+   * `'WHERE total > $1 ORDER BY total'`. Returns Promise<Array<Object>>.
+   */
+  query(clause?: string, params?: any[] | Record<string, any>): Promise<Row[]>
+}
+export type JsTempTable = TempTable
+
+/**
+ * Opens databases on demand and keeps at most `maxOpen` open, closing idle
+ * ones to make room.
+ */
+export declare class DatabaseManager {
+  constructor(options?: DatabaseManagerOptions)
+  /**
+   * The database at `path`, opened with the manager's options unless it
+   * is open already. Opening one closes idle databases first when
+   * `maxOpen` are open, and throws when none of them is idle.
+   * Returns Promise<Database>.
+   */
+  get(path: string): Promise<Database>
+  /** Whether the database at `path` is open in the manager. */
+  has(path: string): boolean
+  /** Number of databases open in the manager. */
+  get size(): number
+  /**
+   * Close every database the manager has open, letting their pending
+   * calls finish first. `get()` throws afterwards. Returns Promise<void>.
+   */
+  close(): Promise<void>
+}
+export type JsDatabaseManager = DatabaseManager
+
+/** Runs queries on a database a worker thread serves with `db.serve(port)`. */
+export declare class RemoteDatabase {
+  /**
+   * Send calls over `port`, one end of a `MessageChannel` whose other end
+   * a worker thread passed to `db.serve()`. The port doesn't keep the
+   * process alive while no call is pending.
+   */
+  constructor(port: import('node:worker_threads').MessagePort)
+  /** Query rows on the served database. Returns Promise<Array<Object>>. */
+  query(sql: string, params?: any[] | Record<string, any>): Promise<Record<string, any>[]>
+  /**
+   * Execute a DDL/DML statement on the served database. Returns
+   * Promise<RunResult>.
+   */
+  execute(sql: string, params?: any[] | Record<string, any>): Promise<RunResult>
+  /** Number of calls waiting for their response. */
+  get pending(): number
+  /**
+   * Close the port. Pending calls reject with a `DatabaseClosed` error, as
+   * do calls made afterwards; the served database stays open.
+   */
+  close(): void
+}
+export type JsRemoteDatabase = RemoteDatabase
+
+/** An index advised for a slow query, returned by `applyAdvisedIndexes()`. */
+export interface AdvisedIndex {
+  table: string
+  column: string
+  /** The `CREATE INDEX` statement. */
+  sql: string
+  /** Fingerprint of the query the index is for. */
+  query: string
+  /** Calls of the query in the window. */
+  calls: number
+  /** Their mean time in milliseconds. */
+  meanMs: number
+  /** Best time of its slowest call without the index; null in a dry run. */
+  beforeMs: number | null
+  /** Best time of the call with the index; null in a dry run. */
+  afterMs: number | null
+  /** Whether the index was created and kept. */
+  created: boolean
+}
+
+/** Options for `db.applyAdvisedIndexes()`. */
+export interface ApplyIndexesOptions {
+  /** List the indexes without creating them (default false). */
+  dryRun?: boolean
+}
+
+/** Options for `Database.open(path, { autoAnalyze })`. */
+export interface AutoAnalyzeOptions {
+  /**
+   * Changed rows, as a fraction of the table's rows at its last ANALYZE,
+   * that trigger a refresh (default 0.1).
+   */
+  threshold?: number
+  /** Changed rows below which a table is never refreshed (default 50). */
+  minChanges?: number
+}
+
+/** How JS values cross the binding. */
+export interface BindingFeatures {
+  /** Binary values round-trip as Buffers (Buffers currently bind as UTF-8 text) */
+  blob: boolean
+  /** BigInt parameters are accepted (within the i64 range) */
+  bigintParams: boolean
+  /** Integer results can be returned as BigInt */
+  bigintResults: boolean
+  /** Rows can be consumed incrementally instead of as a single array */
+  streaming: boolean
+  /** `:name` parameters bound from an object */
+  namedParams: boolean
+  /** Float32Array parameters bind as vectors, and vectors return as Float32Array */
+  float32ArrayVectors: boolean
+  /**
+   * Temporal.Instant, PlainDateTime and PlainDate parameters bind as
+   * timestamps, and timestamps can return as Temporal.Instant
+   */
+  temporal: boolean
+}
+
+/** Features compiled into this build of the binding. */
+export interface BuildFeatures {
+  /**
+   * Result rows are built with direct V8 calls instead of per-value N-API.
+   * False when the runtime falls back to N-API; see `Database.runtimeInfo()`.
+   */
+  v8Helpers: boolean
+  /** Parallel query execution */
+  parallel: boolean
+}
+
+/** What this engine and binding support, for feature detection. */
+export interface Capabilities {
+  engineVersion: string
+  bindingVersion: string
+  sql: SqlFeatures
+  functions: FunctionLists
+  vector: VectorSupport
+  binding: BindingFeatures
+  build: BuildFeatures
+}
+
+/** A committed row change. */
+export interface ChangeEvent {
+  op: 'insert' | 'update' | 'delete'
+  table: string
+  /** Integer primary key of the changed row, or null when the table has none. */
+  rowId: number | null
+}
+
+/** Sanitized description of an open database, safe to include in bug reports. */
+export interface DatabaseDescription {
+  /** DSN with credentials and unrecognized query parameter values redacted */
+  path: string
+  options: EngineOptions
+  engineVersion: string
+  bindingVersion: string
+  features: BuildFeatures
+}
+
+/** Options for `new DatabaseManager()`. */
+export interface DatabaseManagerOptions {
+  /** Most databases open at once (default 64). */
+  maxOpen?: number
+  /**
+   * Close idle databases not got for this many milliseconds, at the next
+   * `get()`; by default they stay open until `maxOpen` is reached.
+   */
+  idleMs?: number
+  /** Options every database is opened with, as for `Database.open()`. */
+  options?: OpenOptions
+}
+
+/** Options for `deleteMany()` / `deleteManySync()`. */
+export interface DeleteManyOptions {
+  /**
+   * Column the keys are matched against. Defaults to the table's
+   * single-column primary key.
+   */
+  keyColumn?: string
+  /** Keys per DELETE statement (default 500). */
+  chunkSize?: number
+}
+
+/** Options for `dump()`. */
+export interface DumpOptions {
+  /** Dump only these tables, without views (default every table and view). */
+  tables?: Array<string>
+  /** Leave out the rows (default false). */
+  schemaOnly?: boolean
+}
+
+/**
+ * `cause` of an error raised by the engine: the engine's message, the kind
+ * of engine error and its fields.
+ */
+/** Options for `Database.diffData()`. */
+export interface DiffDataOptions {
+  /** Tables to compare (default every table of either database). */
+  tables?: Array<string>
+  /**
+   * `'rows'` resolves to the changed rows of each table, `'sql'` to the
+   * statements that make the first database's rows the second's
+   * (default `'rows'`).
+   */
+  format?: 'rows' | 'sql'
+}
+
+/** Engine settings in effect for an open database. */
+export interface EngineOptions {
+  /** Whether data is persisted to disk (false for in-memory databases) */
+  persistence: boolean
+  sync: 'none' | 'normal' | 'full'
+  snapshotInterval: number
+  keepSnapshots: number
+  walFlushTrigger: number
+  walBufferSize: number
+  walMaxSize: number
+  commitBatchSize: number
+  syncIntervalMs: number
+  walCompression: boolean
+  snapshotCompression: boolean
+  compressionThreshold: number
+  cleanup: boolean
+  cleanupInterval: number
+  deletedRowRetention: number
+  transactionRetention: number
+}
+
+/** Options for `db.openCursor(sql, params, options)`. */
+export interface CursorOptions {
+  /** Read the next rows ahead after each `fetchNext()` (default true). */
+  prefetch?: boolean
+  /**
+   * Rows a `fetchNext()` without a count reads. Tuned from the width of
+   * the rows and how fast they are handled by default.
+   */
+  fetchSize?: number
+}
+
+/** Options for `db.close()`. */
+export interface CloseOptions {
+  /**
+   * `drain` lets pending calls finish first, `abort` rejects them
+   * (default `drain`).
+   */
+  mode?: 'drain' | 'abort'
+  /** Most milliseconds to wait for pending calls; no limit by default. */
+  timeoutMs?: number
+}
+
+/** Options for `db.enableFaultInjection()`. */
+export interface FaultInjectionOptions {
+  /** Fraction of calls that fail, from 0 to 1 (default 0). */
+  errorRate?: number
+  /**
+   * Most milliseconds a call is delayed by; each waits a random time up
+   * to it (default 0).
+   */
+  latencyMs?: number
+}
+
+/** Options for `Database.formatSql()`. */
+export interface FormatOptions {
+  /**
+   * Spaces per nesting level, or the string to indent with (default 2).
+   * 0 prints each statement on a single line.
+   */
+  indent?: number | string
+}
+
+/** Functions registered with the engine, by kind. */
+export interface FunctionLists {
+  scalar: Array<string>
+  aggregate: Array<string>
+  window: Array<string>
+}
+
+/** Options for `db.enableIndexAdvisor()`. */
+export interface IndexAdvisorOptions {
+  /** Fingerprints kept, the slowest by mean time (default 20). */
+  slowest?: number
+  /** Milliseconds a fingerprint is kept after its last call (default 600000). */
+  windowMs?: number
+}
+
+/** Time spent in sync calls, returned by `db.syncBusy`. */
+export interface SyncBusyStats {
+  /** Sync calls made. */
+  calls: number
+  /** Milliseconds they blocked the JS thread in all. */
+  totalMs: number
+  /** 99th percentile of their durations, in milliseconds. */
+  p99Ms: number
+  /** The slowest call, in milliseconds. */
+  maxMs: number
+  /** The same per method, most total time first. */
+  methods: Array<SyncMethodBusy>
+}
+
+/** Time spent in one sync method, e.g. `querySync`. */
+export interface SyncMethodBusy {
+  method: string
+  calls: number
+  totalMs: number
+  p99Ms: number
+  maxMs: number
+}
+
+/** Snapshot returned by `tx.memoStats`. */
+export interface MemoStats {
+  /** Results kept. */
+  entries: number
+  /** Reads answered from a kept result. */
+  hits: number
+  /** Reads that ran their statement. */
+  misses: number
+}
+
+/** Memory held for results, returned by `db.memoryBudget`. */
+export interface MemoryBudgetStats {
+  /** The budget in megabytes; absent when there is none. */
+  limitMb?: number
+  /** Estimated size of the results held now, in megabytes. */
+  usedMb: number
+  /** Calls that failed because they would have exceeded the budget. */
+  rejected: number
+}
+
+/**
+ * What `db.memoryUsage()` returns. Sizes are in megabytes, and those of
+ * results are estimates.
+ */
+export interface MemoryUsage {
+  /** The memory budget, or null when there is none. */
+  limitMb: number | null
+  /** Query results held now. */
+  resultsMb: number
+  /** Most query results held at once since the database was opened. */
+  peakResultsMb: number
+  /** Largest result a single call has held. */
+  largestResultMb: number
+  /** Calls that failed because they would have exceeded the budget. */
+  rejected: number
+  /** Plans in `db.planCache`. */
+  planCacheEntries: number
+  /** SQL text the plan cache holds as keys. Parsed plans are not measured. */
+  planCacheMb: number
+  /** The engine's WAL buffer; 0 for in-memory databases. */
+  walBufferMb: number
+}
+
+/**
+ * Engine settings applied from the first statement, for `Database.open(path, options)`.
+ *
+ * They are passed to the engine as DSN query parameters, so they are only
+ * supported for file-based databases. `retry`, `autoAnalyze`,
+ * `dedicatedThread`, `readers`, `serializeWrites`, `memoryBudget`,
+ * `idleTransactionMs`, `asyncStackTraces`, `columnCase` and
+ * `duplicateColumns` are handled by the binding and work for any database.
+ */
+export interface OpenOptions {
+  sync?: 'none' | 'normal' | 'full'
+  snapshotInterval?: number
+  keepSnapshots?: number
+  walFlushTrigger?: number
+  walBufferSize?: number
+  walMaxSize?: number
+  commitBatchSize?: number
+  syncIntervalMs?: number
+  walCompression?: boolean
+  snapshotCompression?: boolean
+  compressionThreshold?: number
+  cleanup?: boolean
+  cleanupInterval?: number
+  deletedRowRetention?: number
+  transactionRetention?: number
+  /** Retry async auto-committed statements that fail with a write conflict. */
+  retry?: RetryOptions
+  /** Re-ANALYZE tables in the background once enough of their rows changed. */
+  autoAnalyze?: AutoAnalyzeOptions
+  /**
+   * Run async calls in order on a thread of the database's own instead of
+   * the libuv thread pool (default false).
+   */
+  dedicatedThread?: boolean
+  /**
+   * Extra engine handles that run async read-only queries in parallel
+   * (default 0).
+   */
+  readers?: number
+  /**
+   * Tables whose async writes take turns in a queue instead of failing
+   * with write conflicts.
+   */
+  serializeWrites?: Array<string>
+  /**
+   * Megabytes of query results the binding may hold at once (default
+   * unlimited).
+   */
+  memoryBudget?: number
+  /**
+   * Warn with a `transactionIdle` event about transactions that have had
+   * no calls for this many milliseconds (default off).
+   */
+  idleTransactionMs?: number
+  /**
+   * Append the JS stack of each async call to the errors it rejects with
+   * (default false).
+   */
+  asyncStackTraces?: boolean
+  /** Case of column names in result rows (default `'asIs'`). */
+  columnCase?: 'camel' | 'snake' | 'asIs'
+  /**
+   * What happens to result columns whose names collide in row objects:
+   * later ones get `_1`, `_2`, ... suffixes (`'suffix'`, the default),
+   * the call throws (`'error'`), or rows become one object per table
+   * (`'expand'`).
+   */
+  duplicateColumns?: 'suffix' | 'error' | 'expand'
+  /**
+   * Return TIMESTAMP values as `Temporal.Instant`s instead of ISO strings
+   * (default false). Needs `globalThis.Temporal`.
+   */
+  temporal?: boolean
+  /**
+   * Digits of the second in TIMESTAMP results: whole seconds (`'s'`, the
+   * default), milliseconds or microseconds.
+   */
+  timestampPrecision?: 's' | 'ms' | 'us'
+  /**
+   * Time zone of TIMESTAMP results: `'utc'` (the default), `'local'`, or a
+   * fixed offset such as `'+05:30'`.
+   */
+  timezone?: 'utc' | 'local' | string
+  /**
+   * `maxRows` for every query without its own: fail if a result has more
+   * rows, or truncate it with `onMaxRows: 'truncate'` (default no limit).
+   */
+  maxRows?: number
+  /** What a result over `maxRows` does (default `'error'`). */
+  onMaxRows?: 'error' | 'truncate'
+  /**
+   * Commit async `execute()` calls made within this many milliseconds of
+   * each other in one transaction, with one sync (default off).
+   */
+  commitWindowMs?: number
+  /**
+   * Run the engine's parallel operators on this many threads for every
+   * query and async call (default one per core).
+   */
+  parallelism?: number
+  /**
+   * Give query results a `consistencyToken` that `db.isStale()` checks
+   * (default false).
+   */
+  consistencyTokens?: boolean
+  /**
+   * Reject SQL holding more than one statement in every call but
+   * `exec()` and `queryMulti()` (default false).
+   */
+  strictStatements?: boolean
+}
+
+/** One operator in a query plan. */
+/** Snapshot returned by `db.planCache.stats()`. */
+export interface PlanCacheStats {
+  /** Cached plans. */
+  entries: number
+  maxSize: number
+  /** Lookups served from the cache since the database was opened. */
+  hits: number
+  /** Lookups that had to parse the SQL since the database was opened. */
+  misses: number
+  /** Plans dropped to stay within `maxSize`. */
+  evictions: number
+  /** Bytes of SQL text held as cache keys. Parsed plans are not measured. */
+  sqlBytes: number
+}
+
+export interface PlanNode {
+  /** Operator description, e.g. `SELECT` or `Index Scan using idx on t`. */
+  operation: string
+  /** `Key: value` lines, e.g. `Filter`, `Index Cond`, `Join Cond`. */
+  properties: Record<string, string>
+  /** Other detail lines, e.g. the key condition of a PK lookup. */
+  details: Array<string>
+  /** Planner row estimate, when the engine reports one. */
+  estimatedRows: number | null
+  /** Planner cost estimate, when the engine reports one. */
+  estimatedCost: number | null
+  /** Rows produced (explainAnalyze only). */
+  actualRows: number | null
+  /** Execution time in milliseconds (explainAnalyze only, statement nodes). */
+  actualTimeMs: number | null
+  children: Array<PlanNode>
+}
+
+/** Options for `queryChunks()`. */
+export interface QueryChunksOptions {
+  /** Rows per callback (default 1000). */
+  chunkSize?: number
+}
+
+/** Per-call options accepted by the query methods. */
+export interface QueryOptions {
+  /**
+   * Read a historical snapshot: a Date or timestamp string (AS OF TIMESTAMP)
+   * or an integer transaction id (AS OF TRANSACTION).
+   */
+  asOf?: Date | string | number
+  /**
+   * Convert the rows with less peak memory at some cost in speed: column
+   * names aren't interned, rows are added to the result one at a time,
+   * and on async calls each row is freed as soon as it has been converted.
+   */
+  lowMemory?: boolean
+  /**
+   * Return only these result columns, in this order, as if the SQL had
+   * selected just them.
+   */
+  columns?: Array<string>
+  /** Skip this many result rows, as if the SQL ended in `OFFSET`. */
+  offset?: number
+  /** Return at most this many rows, as if the SQL ended in `LIMIT`. */
+  limit?: number
+  /**
+   * Fail if the result has more than this many rows, or truncate it with
+   * `onMaxRows: 'truncate'`.
+   */
+  maxRows?: number
+  /** What a result over `maxRows` does (default `'error'`). */
+  onMaxRows?: 'error' | 'truncate'
+  /**
+   * With `queryRaw()`, also return this many of the first rows as
+   * objects, in `preview`.
+   */
+  preview?: number
+  /**
+   * With `queryRaw()`, `buffer` packs each vector column into one Buffer,
+   * described in `vectors` (default `typedArray`, one per row).
+   */
+  vectors?: 'typedArray' | 'buffer'
+  /**
+   * Run the engine's parallel operators on this many threads instead of
+   * the database's `parallelism`.
+   */
+  parallelism?: number
+}
+
+/** One packed vector column of a raw result. */
+export interface VectorBuffer {
+  /** Every row's vector, one after the other. */
+  buffer: Buffer
+  /** Elements per vector. */
+  dim: number
+  dtype: 'float32'
+  /** Bytes from one row's vector to the next. */
+  stride: number
+  /** Indexes of the rows whose vector is NULL; their place holds zeros. */
+  nulls: Array<number>
+}
+
+/** Timing for one completed statement. */
+export interface ProfileEvent {
+  sql: string
+  durationMs: number
+  /** Rows returned by a query, or rows changed by DML. */
+  rows: number
+  /** Query plan, when the listener was registered with `{ plan: true }`. */
+  plan: QueryPlan | null
+}
+
+/** Options for `db.on('profile', listener, options)`. */
+export interface ProfileOptions {
+  /** Only report statements that took at least this long. */
+  thresholdMs?: number
+  /** Attach the statement's query plan to each reported event. */
+  plan?: boolean
+}
+
+/** Result of `stmt.explain()` / `stmt.explainAnalyze()`. */
+export interface QueryPlan {
+  /** Root node: the statement itself. */
+  plan: PlanNode
+  /** Whether the statement was executed to collect actual statistics. */
+  analyzed: boolean
+  /** The engine's EXPLAIN text, one line per row. */
+  text: Array<string>
+  /**
+   * Threads the plan's parallel scans ran on, with `explainAnalyze()`;
+   * null when none ran in parallel. Plain EXPLAIN doesn't show parallel
+   * scans, and parallel sorts, joins and aggregations never show.
+   */
+  parallelWorkers: number | null
+}
+
+/** Restrictions applied to every SQL statement run through a database handle. */
+/** What `db.replay()` resolves to. */
+export interface ReplayReport {
+  /** Statements run. */
+  statements: number
+  /** Statements that failed. Replay goes on with the next one. */
+  errors: number
+  /** Milliseconds the statements took now, in all. */
+  durationMs: number
+  /** Milliseconds they took when they were recorded. */
+  recordedMs: number
+  /** The same per SQL text, most total time first. */
+  sql: Array<ReplayedSql>
+}
+
+/** Replay times of one SQL text. */
+export interface ReplayedSql {
+  sql: string
+  statements: number
+  errors: number
+  durationMs: number
+  recordedMs: number
+  /** The first error it failed with. */
+  error: string | null
+}
+
+/** Options for `Database.open(path, { retry })`. */
+export interface RetryOptions {
+  /** Attempts after the first (default 3). */
+  retries?: number
+  /**
+   * Pause before the first retry in milliseconds, doubled for each
+   * following one (default 10).
+   */
+  backoffMs?: number
+  /**
+   * Pause a random 50-100% of the backoff, so competing callers don't
+   * retry in lockstep (default true).
+   */
+  jitter?: boolean
+}
+
+/** Report returned by `Database.runtimeInfo()`. */
+export interface RuntimeInfo {
+  /** Runtime hosting the addon. */
+  runtime: 'node' | 'electron' | 'deno' | 'bun'
+  /** Node version the runtime reports through N-API, e.g. `v22.4.0`. */
+  nodeVersion: string | null
+  /** Highest N-API version the runtime supports. */
+  napiVersion: number
+  /** How result rows are built. */
+  resultPath: 'v8' | 'napi'
+  /** Why results are built with N-API, or null when the V8 helpers are used. */
+  fallbackReason: string | null
+}
+
+export interface SqlPolicy {
+  /**
+   * Statement types to reject, e.g. `'DROP'`, `'ALTER TABLE'` or `'PRAGMA'`.
+   * A single keyword matches every statement type it starts.
+   */
+  deny?: Array<string>
+  /** When set, statements may only reference these tables and views. */
+  allowTables?: Array<string>
+}
+
+/** Options for `db.createRestrictedHandle()`. */
+export interface RestrictedHandleOptions {
+  /**
+   * The only statement types the handle runs, e.g. `['SELECT']`. A single
+   * keyword matches every statement type it starts.
+   */
+  allow: Array<string>
+}
+
+/** Options for `db.registerType()`. */
+export interface TypeOptions {
+  /** Whether an object parameter is of this type. */
+  test?: (value: any) => boolean
+  /** Convert a parameter of this type to a value the engine can store. */
+  serialize?: (value: any) => any
+  /** Revive a result value, or return `undefined` to leave it. */
+  deserialize?: (value: any, column: string) => any
+  /** The result columns `deserialize` sees (default all of them). */
+  columns?: Array<string>
+}
+
+/** SQL features supported by the engine. */
+export interface SqlFeatures {
+  transactions: boolean
+  savepoints: boolean
+  /** `AS OF TIMESTAMP` / `AS OF TRANSACTION` time-travel reads */
+  timeTravel: boolean
+  returning: boolean
+  onDuplicateKeyUpdate: boolean
+  cte: boolean
+  recursiveCte: boolean
+  windowFunctions: boolean
+  views: boolean
+  json: boolean
+  foreignKeys: boolean
+  /** Constraint checks deferred to commit (`begin({ deferConstraints: true })`) */
+  deferredConstraints: boolean
+}
+
+/** Options for `db.logStatements()`. */
+export interface StatementLogOptions {
+  /** Share of statements logged, from 0 to 1 (default 1, all of them). */
+  sample?: number
+  /** Called with each logged statement. */
+  sink: (event: StatementLogEvent) => void
+  /**
+   * Copy the parameters of logged statements into their events
+   * (default false).
+   */
+  includeParams?: boolean
+}
+
+/** Per-call options accepted by a prepared statement's query methods. */
+export interface StatementQueryOptions {
+  /**
+   * Return only these result columns, in this order, as if the SQL had
+   * selected just them.
+   */
+  columns?: Array<string>
+  /** Skip this many result rows, as if the SQL ended in `OFFSET`. */
+  offset?: number
+  /** Return at most this many rows, as if the SQL ended in `LIMIT`. */
+  limit?: number
+  /**
+   * Fail if the result has more than this many rows, or truncate it with
+   * `onMaxRows: 'truncate'`.
+   */
+  maxRows?: number
+  /** What a result over `maxRows` does (default `'error'`). */
+  onMaxRows?: 'error' | 'truncate'
+  /**
+   * Run the engine's parallel operators on this many threads instead of
+   * the database's `parallelism`.
+   */
+  parallelism?: number
+}
+
+/** Options for `db.statementReport()`. */
+export interface StatementReportOptions {
+  /**
+   * Only include statements that haven't run for this many milliseconds,
+   * counting from when they were prepared if they never have.
+   */
+  unusedForMs?: number
+}
+
+/** One prepared SQL text, returned by `db.statementReport()`. */
+export interface StatementUsageEntry {
+  sql: string
+  /** Times the SQL was prepared. */
+  prepared: number
+  /** Times its statements ran; each parameter set of `executeBatch` counts. */
+  executions: number
+  /** When the SQL was first prepared, in milliseconds since the epoch. */
+  preparedAt: number
+  /**
+   * When a statement last ran, in milliseconds since the epoch; null if
+   * none has.
+   */
+  lastUsedAt: number | null
+}
+
+/** One step in the life of an async call, for `db.on('task')`. */
+export interface TaskEvent {
+  phase: 'enqueue' | 'dequeue' | 'complete'
+  /** Method that issued the task, e.g. `query` or `commit`. */
+  method: string
+  /** Tasks waiting for a thread, after this step. */
+  queued: number
+  /** Tasks running on a thread, after this step. */
+  running: number
+  /** Time between enqueue and dequeue; 0 for `enqueue`. */
+  waitMs: number
+  /** Time between dequeue and complete; 0 before `complete`. */
+  runMs: number
+  /** False when the task's work failed; true before `complete`. */
+  ok: boolean
+}
+
+/** Async calls in flight, returned by `db.taskQueue`. */
+export interface TaskQueueStats {
+  /** Tasks waiting for a thread. */
+  queued: number
+  /** Tasks running on a thread. */
+  running: number
+}
+
+/** Options for `topNPerGroup()` / `topNPerGroupSync()`. */
+/** Options for `tx.toSql()`. */
+export interface ToSqlOptions {
+  /**
+   * `inline` writes the parameters into the SQL as literals,
+   * `placeholders` keeps the SQL as it ran and the parameters in a
+   * comment after it (default `inline`).
+   */
+  params?: 'inline' | 'placeholders'
+}
+
+export interface TopNOptions {
+  /** Column(s) that define a group. */
+  group: string | Array<string>
+  /**
+   * Column(s) that rank rows within a group, each optionally followed by
+   * `ASC` or `DESC`, e.g. `'score DESC'`.
+   */
+  orderBy: string | Array<string>
+  /** Rows to keep per group. */
+  n: number
+  /** Keep rows tied with the `n`th row too (RANK() instead of ROW_NUMBER()). */
+  ties?: boolean
+}
+
+/** Options accepted by `prepare()` / `prepareAsync()`. */
+export interface PrepareOptions {
+  /** Reject SQL longer than this many bytes instead of parsing it. */
+  maxSqlBytes?: number
+}
+
+/** Options accepted by `begin()` / `beginSync()`. */
+export interface TransactionOptions {
+  /**
+   * Defer constraint checks until commit. The engine checks foreign keys
+   * and other constraints as each statement runs, so this is rejected
+   * until it gains deferred checking; see `Database.capabilities()`.
+   */
+  deferConstraints?: boolean
+  /**
+   * Roll the transaction back if it is still open this many milliseconds
+   * after it began, emitting a `transactionTimeout` event.
+   */
+  maxDurationMs?: number
+  /**
+   * Keep the statements the transaction runs, with their parameters, for
+   * `tx.toSql()` (default false).
+   */
+  recordSql?: boolean
+  /**
+   * Run at snapshot isolation and reuse the rows of a SELECT run again
+   * with the same SQL and parameters, until the transaction executes a
+   * statement (default false).
+   */
+  memoize?: boolean
+}
+
+/**
+ * A transaction idle for `idleTransactionMs`, for
+ * `db.on('transactionIdle')`.
+ */
+export interface TransactionIdleEvent {
+  /** Time since the transaction's last call. */
+  idleMs: number
+  /** Time since the transaction began. */
+  openMs: number
+  /** JS stack trace of the `begin()` or `beginSync()` call. */
+  stack: string
+}
+
+/**
+ * A transaction rolled back by its `maxDurationMs`, for
+ * `db.on('transactionTimeout')`.
+ */
+export interface TransactionTimeoutEvent {
+  maxDurationMs: number
+  /**
+   * Time from begin to the rollback; longer than `maxDurationMs` when a
+   * statement was running.
+   */
+  durationMs: number
+}
+
+/** Options for `tree()` / `treeSync()`. */
+export interface TreeOptions {
+  /** Column holding the parent row's id. */
+  parentColumn: string
+  /**
+   * Column the parent column refers to. Defaults to the table's
+   * single-column primary key.
+   */
+  idColumn?: string
+  /** Id of the row to start from. Defaults to every row whose parent is NULL. */
+  rootId?: any
+  /** Levels below the roots to include; 0 returns the roots alone. */
+  maxDepth?: number
+  /** Property that holds each node's children (default `children`). */
+  childrenKey?: string
+}
+
+/** Options for `db.vacuum()`. */
+export interface VacuumOptions {
+  /** Also write a compacted copy of the database to this new file path. */
+  into?: string
+  /** Called after each table is vacuumed, and after each is copied. */
+  onProgress?: (progress: VacuumProgress) => void
+}
+
+/** How far `db.vacuum()` got, as `onProgress` gets it. */
+export interface VacuumProgress {
+  /**
+   * `'vacuum'` while tables are vacuumed, then `'copy'` while they are
+   * copied `into` a new file.
+   */
+  phase: 'vacuum' | 'copy'
+  /** The table just done. */
+  table: string
+  /** Tables done in this phase, this one included. */
+  done: number
+  /** Tables in all. */
+  total: number
+}
+
+/** What `db.vacuum()` resolves to. */
+export interface VacuumReport {
+  /** Tables vacuumed. */
+  tables: number
+  /** Deleted rows dropped. */
+  deletedRowsCleaned: number
+  /** Old row versions dropped. */
+  oldVersionsCleaned: number
+  /** Finished transactions forgotten. */
+  transactionsCleaned: number
+  /** Rows copied `into` the new file, if one was given. */
+  rowsCopied: number | null
+}
+
+/** Result of `db.validate()`. */
+export interface Validation {
+  /** Whether the SQL parses. */
+  ok: boolean
+  errors: Array<ValidationError>
+}
+
+/** A parse error of `db.validate()`. */
+export interface ValidationError {
+  message: string
+  /** Line of the error, from 1. */
+  line: number
+  /** Column of the error on its line, from 1. */
+  column: number
+}
+
+/** Vector column support. */
+export interface VectorSupport {
+  supported: boolean
+  /** Largest `VECTOR(n)` dimension the engine accepts */
+  maxDimensions: number
+  /** Approximate nearest-neighbor indexes (`USING HNSW`) */
+  hnswIndex: boolean
+}
+
+/** WAL entries of a primary, as `db.exportWalSegments()` returns them. */
+export interface WalSegment {
+  /**
+   * The LSN these entries follow: the `sinceLsn` of the export, or the
+   * `toLsn` of the segment before.
+   */
+  fromLsn: number
+  /** LSN of the last entry. */
+  toLsn: number
+  /** Entries in `data`. */
+  entries: number
+  /** The entries as the engine wrote them. */
+  data: Buffer
+}
+
+/** Queue counters for one serialized table, returned by `db.writeQueue`. */
+export interface WriteQueueStats {
+  table: string
+  /** Whether a write or transaction holds the table. */
+  locked: boolean
+  /** Calls waiting for the table. */
+  waiting: number
+  /** Calls that have taken the table, including those that waited. */
+  writes: number
+  /** Calls that had to wait for the table. */
+  waits: number
+  /** Total time calls spent waiting for the table. */
+  totalWaitMs: number
+  /** Longest time a call waited for the table. */
+  maxWaitMs: number
+}
+
+/** Explicit resource management: `using db = ...` closes the database. */
+export interface Database {
+  [Symbol.dispose](): void
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: rolls back unless committed. */
+export interface Transaction {
+  [Symbol.dispose](): void
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: finalizes the statement. */
+export interface PreparedStatement {
+  [Symbol.dispose](): void
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: closes every database of the manager. */
+export interface DatabaseManager {
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: closes the port. */
+export interface RemoteDatabase {
+  [Symbol.dispose](): void
+}
+
+/**
+ * SQL built by the `sql` template tag: `text` holds a `$n` placeholder for
+ * each interpolated value and `params` the values, in order.
+ */
+export declare class Sql {
+  private constructor()
+  readonly text: string
+  readonly params: readonly any[]
+}
+
+/** An identifier for `sql`, double-quoted where it is interpolated. */
+export interface SqlIdentifier {
+  readonly text: string
+}
+
+/**
+ * Tag a template so each `${value}` binds as a parameter:
+ * `` db.query(sql`SELECT * FROM t WHERE id = ${id}`) ``. A nested `Sql` is
+ * spliced in with its parameters, and `sql.identifier(name)` as a quoted
+ * name.
+ */
+export declare function sql(strings: TemplateStringsArray, ...values: any[]): Sql
+export declare namespace sql {
+  /** Splice `name` in as a double-quoted identifier rather than bind it. */
+  function identifier(name: string): SqlIdentifier
+}
+
+/** `sql` queries: the tagged SQL stands for both `sql` and `params`. */
+export interface Database {
+  execute(query: Sql): Promise<RunResult>
+  query(query: Sql, options?: QueryOptions): Promise<Record<string, any>[]>
+  queryOne(query: Sql, options?: QueryOptions): Promise<Record<string, any> | null>
+  queryRaw(query: Sql, options?: QueryOptions): Promise<{ columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }>
+  executeSync(query: Sql): RunResult
+  querySync(query: Sql, options?: QueryOptions): Record<string, any>[]
+  queryOneSync(query: Sql, options?: QueryOptions): Record<string, any> | null
+  queryRawSync(query: Sql, options?: QueryOptions): { columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }
+  openCursor(query: Sql, options?: CursorOptions): Cursor
+  queryChunks(query: Sql, options: QueryChunksOptions | null | undefined, onChunk: (rows: Record<string, any>[]) => void): Promise<number>
+  /** The query can't interpolate values: each row of `paramsArray` binds. */
+  executeBatch(query: Sql, paramsArray: any[][]): Promise<RunResult>
+  executeBatchSync(query: Sql, paramsArray: any[][]): RunResult
+}
+
+/** `sql` queries: the tagged SQL stands for both `sql` and `params`. */
+export interface Transaction {
+  execute(query: Sql): Promise<RunResult>
+  query(query: Sql): Promise<Record<string, any>[]>
+  queryOne(query: Sql): Promise<Record<string, any> | null>
+  queryRaw(query: Sql): Promise<{ columns: string[], rows: any[][] }>
+  executeSync(query: Sql): RunResult
+  querySync(query: Sql): Record<string, any>[]
+  queryOneSync(query: Sql): Record<string, any> | null
+  queryRawSync(query: Sql): { columns: string[], rows: any[][] }
+  /** The query can't interpolate values: each row of `paramsArray` binds. */
+  executeBatch(query: Sql, paramsArray: any[][]): Promise<RunResult>
+  executeBatchSync(query: Sql, paramsArray: any[][]): RunResult
+}
+
+/** `sql` queries: the tagged SQL stands for both `sql` and `params`. */
+export interface Pipeline {
+  query(query: Sql, options?: QueryOptions): this
+  execute(query: Sql): this
+}
+
+/** `sql` queries: the tagged SQL stands for both `sql` and `params`. */
+export interface RemoteDatabase {
+  query(query: Sql): Promise<Record<string, any>[]>
+  execute(query: Sql): Promise<RunResult>
+}
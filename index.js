@@ -582,3 +582,130 @@ module.exports.PreparedStatement = nativeBinding.PreparedStatement
 module.exports.JsPreparedStatement = nativeBinding.JsPreparedStatement
 module.exports.Transaction = nativeBinding.Transaction
 module.exports.JsTransaction = nativeBinding.JsTransaction
+module.exports.PlanCache = nativeBinding.PlanCache
+module.exports.JsPlanCache = nativeBinding.JsPlanCache
+module.exports.Pipeline = nativeBinding.Pipeline
+module.exports.JsPipeline = nativeBinding.JsPipeline
+module.exports.DatabaseManager = nativeBinding.DatabaseManager
+module.exports.JsDatabaseManager = nativeBinding.JsDatabaseManager
+module.exports.RemoteDatabase = nativeBinding.RemoteDatabase
+module.exports.JsRemoteDatabase = nativeBinding.JsRemoteDatabase
+
+// Explicit resource management (`using` / `await using`)
+{
+  const { Database, DatabaseManager, PreparedStatement, RemoteDatabase, Transaction } = nativeBinding
+  // A restricted handle can't close the database it was made from.
+  Database.prototype[Symbol.dispose] = function () {
+    if (!this.isRestricted) this.closeSync()
+  }
+  Database.prototype[Symbol.asyncDispose] = async function () {
+    if (!this.isRestricted) await this.close()
+  }
+  Transaction.prototype[Symbol.dispose] = function () {
+    if (this.active) this.rollbackSync()
+  }
+  Transaction.prototype[Symbol.asyncDispose] = async function () {
+    if (this.active) await this.rollback()
+  }
+  PreparedStatement.prototype[Symbol.dispose] = function () {
+    this.finalize()
+  }
+  PreparedStatement.prototype[Symbol.asyncDispose] = async function () {
+    this.finalize()
+  }
+  DatabaseManager.prototype[Symbol.asyncDispose] = function () {
+    return this.close()
+  }
+  RemoteDatabase.prototype[Symbol.dispose] = function () {
+    this.close()
+  }
+}
+
+// SQL template tag: sql`... ${value}` binds each value as a parameter
+{
+  const { Database, Pipeline, RemoteDatabase, Transaction } = nativeBinding
+
+  class SqlIdentifier {
+    constructor(name) {
+      if (typeof name !== 'string' || name === '') {
+        throw new TypeError('sql.identifier() takes a non-empty string')
+      }
+      this.text = `"${name.replace(/"/g, '""')}"`
+      Object.freeze(this)
+    }
+  }
+
+  class Sql {
+    // The text between parameters; there is one more piece than parameters.
+    #pieces
+
+    constructor(strings, values) {
+      const pieces = [strings[0]]
+      const params = []
+      values.forEach((value, i) => {
+        if (value instanceof Sql) {
+          // A nested fragment brings its own parameters along.
+          pieces[pieces.length - 1] += value.#pieces[0]
+          for (let j = 1; j < value.#pieces.length; j++) {
+            params.push(value.params[j - 1])
+            pieces.push(value.#pieces[j])
+          }
+          pieces[pieces.length - 1] += strings[i + 1]
+        } else if (value instanceof SqlIdentifier) {
+          pieces[pieces.length - 1] += value.text + strings[i + 1]
+        } else {
+          params.push(value)
+          pieces.push(strings[i + 1])
+        }
+      })
+      this.#pieces = pieces
+      this.text = pieces.reduce((text, piece, i) => `${text}$${i}${piece}`)
+      this.params = Object.freeze(params)
+      Object.freeze(this)
+    }
+  }
+
+  const sql = (strings, ...values) => {
+    if (!Array.isArray(strings) || !Array.isArray(strings.raw)) {
+      throw new TypeError('sql is a template tag: write sql`SELECT ...`')
+    }
+    return new Sql(strings, values)
+  }
+  sql.identifier = (name) => new SqlIdentifier(name)
+
+  // Methods taking (sql, params, ...rest) also take (sqlQuery, ...rest).
+  const accept = (proto, names) => {
+    for (const name of names) {
+      const run = proto[name]
+      proto[name] = function (query, ...rest) {
+        if (query instanceof Sql) return run.call(this, query.text, query.params, ...rest)
+        return run.call(this, query, ...rest)
+      }
+    }
+  }
+  // executeBatch() binds each row of paramsArray, so the query can only
+  // bring identifiers and fragments, not values of its own.
+  const acceptBatch = (proto) => {
+    for (const name of ['executeBatch', 'executeBatchSync']) {
+      const run = proto[name]
+      proto[name] = function (query, ...rest) {
+        if (!(query instanceof Sql)) return run.call(this, query, ...rest)
+        if (query.params.length > 0) {
+          throw new TypeError(`${name}() binds paramsArray; the sql query can't interpolate values`)
+        }
+        return run.call(this, query.text, ...rest)
+      }
+    }
+  }
+  const calls = ['execute', 'query', 'queryOne', 'queryRaw']
+  const syncCalls = calls.map((name) => `${name}Sync`)
+  accept(Database.prototype, [...calls, ...syncCalls, 'openCursor', 'queryChunks'])
+  accept(Transaction.prototype, [...calls, ...syncCalls])
+  acceptBatch(Database.prototype)
+  acceptBatch(Transaction.prototype)
+  accept(Pipeline.prototype, ['execute', 'query'])
+  accept(RemoteDatabase.prototype, ['execute', 'query'])
+
+  module.exports.sql = sql
+  module.exports.Sql = Sql
+}
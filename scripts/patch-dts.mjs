@@ -21,18 +21,231 @@ const DTS_PATH = 'index.d.ts';
 const HEADER = `/* eslint-disable */
 
 export interface RunResult {
+  /** Number of rows the statement inserted, updated or deleted. */
   changes: number
+  /**
+   * Primary key of the last row an auto-committed INSERT added, or null for
+   * other statements, inside transactions and batches, and for tables
+   * without a primary key.
+   */
+  lastInsertRowId: number | null
+  /** Wall-clock time the call took in the binding, including retries. */
+  durationMs: number
+  /** Notes about how the statement ran, such as write-conflict retries. */
+  warnings: string[]
+  /**
+   * Rows produced by a \`RETURNING\` clause of an auto-committed statement;
+   * absent for other statements.
+   */
+  rows?: Record<string, any>[]
+}
+
+/**
+ * One statement of an \`exec()\` / \`queryMulti()\` script: the rows it changed,
+ * or its columns and rows when it returns any (SELECT, SHOW, RETURNING, ...).
+ */
+export type StatementResult =
+  | { statement: string, changes: number }
+  | { statement: string, columns: string[], rows: any[][] }
+
+export interface EngineErrorCause extends Error {
+  /** Engine error variant, e.g. \`'UniqueConstraint'\` or \`'TableNotFound'\`. */
+  kind: string
+  table?: string
+  column?: string
+  index?: string | number
+  view?: string
+  value?: string
+  expression?: string
+  refTable?: string
+  refColumn?: string
+  rowId?: number
+  expected?: number
+  got?: number
+  max?: number
+  from?: string
+  to?: string
+  /** Free-form detail of kinds that carry only a message, e.g. \`'Parse'\`. */
+  detail?: string
+}
+
+/** What \`db.columnStats()\` returns, as of the table's last ANALYZE. */
+export interface ColumnStats {
+  /** Table and column names as the schema spells them. */
+  table: string
+  column: string
+  /** Rows in the table. */
+  rows: number
+  /**
+   * Rows the figures below were computed from: all of them, or a sample of
+   * 10,000 on larger tables.
+   */
+  sampled: number
+  /** Fraction of the sampled rows where the column is NULL. */
+  nullFraction: number
+  /** Distinct non-NULL values in the sampled rows. */
+  ndv: number
+  /** Smallest and largest non-NULL value, or null when there are none. */
+  min: any
+  max: any
+  /** Average size of the column's values, in bytes. */
+  avgWidth: number
+  /** Whether this call ran ANALYZE, because there were no statistics yet. */
+  analyzed: boolean
+}
+
+/** A logged statement, as \`db.logStatements()\` sinks get it. */
+export interface StatementLogEvent {
+  sql: string
+  durationMs: number
+  /** Rows returned by a query, or rows changed by DML. */
+  rows: number
+  /**
+   * The statement's parameters, with \`includeParams\`: an array, an object
+   * for named ones, or an array of arrays for the rows of \`executeBatch()\`.
+   */
+  params?: any[] | Record<string, any>
+}
+
+/** What changed in one table, from the first database to the second. */
+export interface TableDiff {
+  table: string
+  /** Rows only the second database has. */
+  inserted: Record<string, any>[]
+  /**
+   * Rows both have under the same primary key, with other values in the
+   * second. Tables without a single-column primary key have none.
+   */
+  updated: Array<{ key: any, before: Record<string, any>, after: Record<string, any> }>
+  /** Rows only the first database has. */
+  deleted: Record<string, any>[]
+}
+
+/**
+ * A statement or expression of \`Database.parse()\`: \`type\` is its kind in
+ * the engine's AST and the other keys its parts, null where absent.
+ */
+export interface SyntaxNode {
+  type: string
+  [key: string]: any
 }
 
 `;
 
+const FOOTER_MARKER = '/** Explicit resource management';
+const FOOTER = `${FOOTER_MARKER}: \`using db = ...\` closes the database. */
+export interface Database {
+  [Symbol.dispose](): void
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: rolls back unless committed. */
+export interface Transaction {
+  [Symbol.dispose](): void
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: finalizes the statement. */
+export interface PreparedStatement {
+  [Symbol.dispose](): void
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: closes every database of the manager. */
+export interface DatabaseManager {
+  [Symbol.asyncDispose](): Promise<void>
+}
+
+/** Explicit resource management: closes the port. */
+export interface RemoteDatabase {
+  [Symbol.dispose](): void
+}
+
+/**
+ * SQL built by the \`sql\` template tag: \`text\` holds a \`$n\` placeholder for
+ * each interpolated value and \`params\` the values, in order.
+ */
+export declare class Sql {
+  private constructor()
+  readonly text: string
+  readonly params: readonly any[]
+}
+
+/** An identifier for \`sql\`, double-quoted where it is interpolated. */
+export interface SqlIdentifier {
+  readonly text: string
+}
+
+/**
+ * Tag a template so each \`\${value}\` binds as a parameter:
+ * \`\` db.query(sql\`SELECT * FROM t WHERE id = \${id}\`) \`\`. A nested \`Sql\` is
+ * spliced in with its parameters, and \`sql.identifier(name)\` as a quoted
+ * name.
+ */
+export declare function sql(strings: TemplateStringsArray, ...values: any[]): Sql
+export declare namespace sql {
+  /** Splice \`name\` in as a double-quoted identifier rather than bind it. */
+  function identifier(name: string): SqlIdentifier
+}
+
+/** \`sql\` queries: the tagged SQL stands for both \`sql\` and \`params\`. */
+export interface Database {
+  execute(query: Sql): Promise<RunResult>
+  query(query: Sql, options?: QueryOptions): Promise<Record<string, any>[]>
+  queryOne(query: Sql, options?: QueryOptions): Promise<Record<string, any> | null>
+  queryRaw(query: Sql, options?: QueryOptions): Promise<{ columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }>
+  executeSync(query: Sql): RunResult
+  querySync(query: Sql, options?: QueryOptions): Record<string, any>[]
+  queryOneSync(query: Sql, options?: QueryOptions): Record<string, any> | null
+  queryRawSync(query: Sql, options?: QueryOptions): { columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }
+  openCursor(query: Sql, options?: CursorOptions): Cursor
+  queryChunks(query: Sql, options: QueryChunksOptions | null | undefined, onChunk: (rows: Record<string, any>[]) => void): Promise<number>
+  /** The query can't interpolate values: each row of \`paramsArray\` binds. */
+  executeBatch(query: Sql, paramsArray: any[][]): Promise<RunResult>
+  executeBatchSync(query: Sql, paramsArray: any[][]): RunResult
+}
+
+/** \`sql\` queries: the tagged SQL stands for both \`sql\` and \`params\`. */
+export interface Transaction {
+  execute(query: Sql): Promise<RunResult>
+  query(query: Sql): Promise<Record<string, any>[]>
+  queryOne(query: Sql): Promise<Record<string, any> | null>
+  queryRaw(query: Sql): Promise<{ columns: string[], rows: any[][] }>
+  executeSync(query: Sql): RunResult
+  querySync(query: Sql): Record<string, any>[]
+  queryOneSync(query: Sql): Record<string, any> | null
+  queryRawSync(query: Sql): { columns: string[], rows: any[][] }
+  /** The query can't interpolate values: each row of \`paramsArray\` binds. */
+  executeBatch(query: Sql, paramsArray: any[][]): Promise<RunResult>
+  executeBatchSync(query: Sql, paramsArray: any[][]): RunResult
+}
+
+/** \`sql\` queries: the tagged SQL stands for both \`sql\` and \`params\`. */
+export interface Pipeline {
+  query(query: Sql, options?: QueryOptions): this
+  execute(query: Sql): this
+}
+
+/** \`sql\` queries: the tagged SQL stands for both \`sql\` and \`params\`. */
+export interface RemoteDatabase {
+  query(query: Sql): Promise<Record<string, any>[]>
+  execute(query: Sql): Promise<RunResult>
+}
+`;
+
 let content = readFileSync(DTS_PATH, 'utf8');
 
 // Remove the NAPI-RS auto-generated header (if present)
 content = content.replace(/^\/\* auto-generated by NAPI-RS \*\/\n/, '');
 content = content.replace(/^\/\* eslint-disable \*\/\n/, '');
 
-// Prepend our header with RunResult type
+// Prepend our header with the types ts_return_type refers to
 content = '/* auto-generated by NAPI-RS */\n' + HEADER + content;
 
+// Append the dispose methods and the `sql` tag added by patch-js.mjs, with
+// their overloads merged into the classes
+const at = content.indexOf(FOOTER_MARKER);
+if (at !== -1) content = content.slice(0, at);
+content = content.trimEnd() + '\n\n' + FOOTER;
+
 writeFileSync(DTS_PATH, content);
@@ -0,0 +1,151 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Patch the NAPI-RS generated index.js to add explicit resource management
+// and the `sql` template tag. NAPI-RS can't define symbol-keyed methods, so
+// `Symbol.dispose` and `Symbol.asyncDispose` are attached to the class
+// prototypes here, as are the wrappers that accept `sql` queries.
+
+import { readFileSync, writeFileSync } from 'node:fs';
+
+const JS_PATH = 'index.js';
+const MARKER = '// Explicit resource management (`using` / `await using`)';
+const FOOTER = `
+${MARKER}
+{
+  const { Database, DatabaseManager, PreparedStatement, RemoteDatabase, Transaction } = nativeBinding
+  // A restricted handle can't close the database it was made from.
+  Database.prototype[Symbol.dispose] = function () {
+    if (!this.isRestricted) this.closeSync()
+  }
+  Database.prototype[Symbol.asyncDispose] = async function () {
+    if (!this.isRestricted) await this.close()
+  }
+  Transaction.prototype[Symbol.dispose] = function () {
+    if (this.active) this.rollbackSync()
+  }
+  Transaction.prototype[Symbol.asyncDispose] = async function () {
+    if (this.active) await this.rollback()
+  }
+  PreparedStatement.prototype[Symbol.dispose] = function () {
+    this.finalize()
+  }
+  PreparedStatement.prototype[Symbol.asyncDispose] = async function () {
+    this.finalize()
+  }
+  DatabaseManager.prototype[Symbol.asyncDispose] = function () {
+    return this.close()
+  }
+  RemoteDatabase.prototype[Symbol.dispose] = function () {
+    this.close()
+  }
+}
+
+// SQL template tag: sql\`... \${value}\` binds each value as a parameter
+{
+  const { Database, Pipeline, RemoteDatabase, Transaction } = nativeBinding
+
+  class SqlIdentifier {
+    constructor(name) {
+      if (typeof name !== 'string' || name === '') {
+        throw new TypeError('sql.identifier() takes a non-empty string')
+      }
+      this.text = \`"\${name.replace(/"/g, '""')}"\`
+      Object.freeze(this)
+    }
+  }
+
+  class Sql {
+    // The text between parameters; there is one more piece than parameters.
+    #pieces
+
+    constructor(strings, values) {
+      const pieces = [strings[0]]
+      const params = []
+      values.forEach((value, i) => {
+        if (value instanceof Sql) {
+          // A nested fragment brings its own parameters along.
+          pieces[pieces.length - 1] += value.#pieces[0]
+          for (let j = 1; j < value.#pieces.length; j++) {
+            params.push(value.params[j - 1])
+            pieces.push(value.#pieces[j])
+          }
+          pieces[pieces.length - 1] += strings[i + 1]
+        } else if (value instanceof SqlIdentifier) {
+          pieces[pieces.length - 1] += value.text + strings[i + 1]
+        } else {
+          params.push(value)
+          pieces.push(strings[i + 1])
+        }
+      })
+      this.#pieces = pieces
+      this.text = pieces.reduce((text, piece, i) => \`\${text}$\${i}\${piece}\`)
+      this.params = Object.freeze(params)
+      Object.freeze(this)
+    }
+  }
+
+  const sql = (strings, ...values) => {
+    if (!Array.isArray(strings) || !Array.isArray(strings.raw)) {
+      throw new TypeError('sql is a template tag: write sql\`SELECT ...\`')
+    }
+    return new Sql(strings, values)
+  }
+  sql.identifier = (name) => new SqlIdentifier(name)
+
+  // Methods taking (sql, params, ...rest) also take (sqlQuery, ...rest).
+  const accept = (proto, names) => {
+    for (const name of names) {
+      const run = proto[name]
+      proto[name] = function (query, ...rest) {
+        if (query instanceof Sql) return run.call(this, query.text, query.params, ...rest)
+        return run.call(this, query, ...rest)
+      }
+    }
+  }
+  // executeBatch() binds each row of paramsArray, so the query can only
+  // bring identifiers and fragments, not values of its own.
+  const acceptBatch = (proto) => {
+    for (const name of ['executeBatch', 'executeBatchSync']) {
+      const run = proto[name]
+      proto[name] = function (query, ...rest) {
+        if (!(query instanceof Sql)) return run.call(this, query, ...rest)
+        if (query.params.length > 0) {
+          throw new TypeError(\`\${name}() binds paramsArray; the sql query can't interpolate values\`)
+        }
+        return run.call(this, query.text, ...rest)
+      }
+    }
+  }
+  const calls = ['execute', 'query', 'queryOne', 'queryRaw']
+  const syncCalls = calls.map((name) => \`\${name}Sync\`)
+  accept(Database.prototype, [...calls, ...syncCalls, 'openCursor', 'queryChunks'])
+  accept(Transaction.prototype, [...calls, ...syncCalls])
+  acceptBatch(Database.prototype)
+  acceptBatch(Transaction.prototype)
+  accept(Pipeline.prototype, ['execute', 'query'])
+  accept(RemoteDatabase.prototype, ['execute', 'query'])
+
+  module.exports.sql = sql
+  module.exports.Sql = Sql
+}
+`;
+
+let content = readFileSync(JS_PATH, 'utf8');
+
+// Drop a previous patch so the script can run on an already patched file
+const at = content.indexOf(`\n${MARKER}`);
+if (at !== -1) content = content.slice(0, at);
+
+writeFileSync(JS_PATH, content.trimEnd() + '\n' + FOOTER);
@@ -0,0 +1,478 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.enableIndexAdvisor()` and `db.applyAdvisedIndexes()`.
+//
+// While enabled, the profiler hands the advisor every SELECT that completes
+// (see profile.rs), with a copy of its parameters, and the advisor keeps the
+// `slowest` fingerprints (see `sql::fingerprint()`) by mean time among those
+// seen in the last `windowMs`, each with the SQL and parameters of its
+// slowest call. A fingerprint is advised an index when its query reads one
+// table and compares a column of it with a value: the first column compared
+// for equality, or else for a range, unless that column is the primary key
+// or leads an index already.
+//
+// Applying times the slowest call, creates the index and times the call
+// again, best of `RUNS` each time, and drops the index again unless the call
+// got faster by more than timing noise. The engine doesn't run DDL in
+// transactions, so undoing the indexes of one call is best effort: when one
+// fails to be created or timed, those created before it are dropped by
+// hand, and any that can't be are named in the error along with why.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use stoolap::api::Database;
+use stoolap::parser::ast::{Expression, InfixOperator, SimpleTableSource, Statement};
+use stoolap::storage::Engine;
+use stoolap::ParamVec;
+
+use crate::error::{from_core, to_napi, with_reason};
+use crate::policy::{check_sql, PolicyRef};
+use crate::recording::Recorded;
+use crate::sql::{fingerprint, is_select, parse_single, quote_ident};
+use crate::tasks::TaskParams;
+
+/// `slowest` when not given.
+const DEFAULT_SLOWEST: u32 = 20;
+
+/// `windowMs` when not given: ten minutes.
+const DEFAULT_WINDOW_MS: f64 = 600_000.0;
+
+/// Runs of a call timed before and after creating its index.
+const RUNS: usize = 3;
+
+/// Share of its time without the index a call must take less than with it
+/// for the index to be kept, so timing noise doesn't keep useless ones.
+const KEEP_BELOW: f64 = 0.9;
+
+/// Options for `db.enableIndexAdvisor()`.
+#[napi(object, object_to_js = false)]
+pub struct IndexAdvisorOptions {
+    /// Fingerprints kept, the slowest by mean time (default 20).
+    pub slowest: Option<u32>,
+    /// Milliseconds a fingerprint is kept after its last call (default
+    /// 600000).
+    pub window_ms: Option<f64>,
+}
+
+/// Options for `db.applyAdvisedIndexes()`.
+#[napi(object, object_to_js = false)]
+pub struct ApplyIndexesOptions {
+    /// List the indexes without creating them (default false).
+    pub dry_run: Option<bool>,
+}
+
+/// An index advised for a slow query, returned by `applyAdvisedIndexes()`.
+#[napi(object, object_from_js = false, use_nullable = true)]
+pub struct AdvisedIndex {
+    pub table: String,
+    pub column: String,
+    /// The `CREATE INDEX` statement.
+    pub sql: String,
+    /// Fingerprint of the query the index is for.
+    pub query: String,
+    /// Calls of the query in the window.
+    pub calls: u32,
+    /// Their mean time in milliseconds.
+    pub mean_ms: f64,
+    /// Best time of its slowest call without the index; null in a dry run.
+    pub before_ms: Option<f64>,
+    /// Best time of the call with the index; null in a dry run.
+    pub after_ms: Option<f64>,
+    /// Whether the index was created and kept.
+    pub created: bool,
+}
+
+/// The slow queries of a database.
+#[derive(Default)]
+pub struct IndexAdvisor {
+    enabled: AtomicBool,
+    tracked: Mutex<Tracked>,
+}
+
+#[derive(Default)]
+struct Tracked {
+    slowest: usize,
+    window: Duration,
+    /// By fingerprint.
+    queries: HashMap<String, Query>,
+}
+
+/// Calls of one fingerprint.
+#[derive(Clone)]
+struct Query {
+    /// SQL and parameters of the slowest call.
+    sql: String,
+    params: TaskParams,
+    slowest_ms: f64,
+    calls: u32,
+    total_ms: f64,
+    last: Instant,
+}
+
+impl Query {
+    fn mean_ms(&self) -> f64 {
+        self.total_ms / self.calls as f64
+    }
+}
+
+impl IndexAdvisor {
+    /// Start keeping slow queries, forgetting those kept so far.
+    pub fn enable(&self, options: Option<IndexAdvisorOptions>) -> napi::Result<()> {
+        let (slowest, window_ms) = match options {
+            Some(o) => (o.slowest, o.window_ms),
+            None => (None, None),
+        };
+        let slowest = slowest.unwrap_or(DEFAULT_SLOWEST);
+        if slowest == 0 {
+            return Err(napi::Error::from_reason("slowest must be at least 1"));
+        }
+        let window_ms = window_ms.unwrap_or(DEFAULT_WINDOW_MS);
+        if !(window_ms > 0.0 && window_ms.is_finite()) {
+            return Err(napi::Error::from_reason(
+                "windowMs must be a positive number",
+            ));
+        }
+        *lock(&self.tracked) = Tracked {
+            slowest: slowest as usize,
+            window: Duration::from_secs_f64(window_ms / 1000.0),
+            queries: HashMap::new(),
+        };
+        self.enabled.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Stop keeping slow queries and forget them.
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Release);
+        lock(&self.tracked).queries.clear();
+    }
+
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Count a completed statement, which is kept if it is a SELECT.
+    pub fn observe(&self, sql: &str, params: Option<Recorded>, duration_ms: f64) {
+        if !self.enabled() || !is_select(sql) {
+            return;
+        }
+        let params = match params {
+            Some(Recorded::Params(params)) => params,
+            _ => TaskParams::Positional(ParamVec::new()),
+        };
+        let key = fingerprint(sql);
+        let now = Instant::now();
+        let mut tracked = lock(&self.tracked);
+        let window = tracked.window;
+        tracked
+            .queries
+            .retain(|_, query| now.duration_since(query.last) < window);
+        if let Some(query) = tracked.queries.get_mut(&key) {
+            query.calls += 1;
+            query.total_ms += duration_ms;
+            query.last = now;
+            if duration_ms >= query.slowest_ms {
+                query.sql = sql.to_string();
+                query.params = params;
+                query.slowest_ms = duration_ms;
+            }
+            return;
+        }
+        if tracked.queries.len() >= tracked.slowest {
+            // Make room by dropping the fastest, if this call is slower.
+            let fastest = tracked
+                .queries
+                .iter()
+                .min_by(|a, b| a.1.mean_ms().total_cmp(&b.1.mean_ms()))
+                .map(|(key, query)| (key.clone(), query.mean_ms()));
+            match fastest {
+                Some((fastest, mean_ms)) if mean_ms < duration_ms => {
+                    tracked.queries.remove(&fastest);
+                }
+                _ => return,
+            }
+        }
+        tracked.queries.insert(
+            key,
+            Query {
+                sql: sql.to_string(),
+                params,
+                slowest_ms: duration_ms,
+                calls: 1,
+                total_ms: duration_ms,
+                last: now,
+            },
+        );
+    }
+
+    /// The queries kept, slowest first.
+    fn slowest(&self) -> Vec<(String, Query)> {
+        let now = Instant::now();
+        let tracked = lock(&self.tracked);
+        let mut queries: Vec<(String, Query)> = tracked
+            .queries
+            .iter()
+            .filter(|(_, query)| now.duration_since(query.last) < tracked.window)
+            .map(|(key, query)| (key.clone(), query.clone()))
+            .collect();
+        queries.sort_by(|a, b| b.1.mean_ms().total_cmp(&a.1.mean_ms()));
+        queries
+    }
+}
+
+/// The indexes advised for the slow queries of `advisor`, created and
+/// verified unless `dry_run`.
+pub fn apply(
+    db: &Database,
+    policy: &PolicyRef,
+    advisor: &IndexAdvisor,
+    dry_run: bool,
+) -> napi::Result<Vec<AdvisedIndex>> {
+    let mut advised = Vec::new();
+    let mut seen = HashSet::new();
+    for (query, slow) in advisor.slowest() {
+        let Some((table, column)) = candidate(db, &slow.sql) else {
+            continue;
+        };
+        if !seen.insert((table.to_lowercase(), column.to_lowercase())) {
+            continue;
+        }
+        let name = format!("idx_{table}_{column}").to_lowercase();
+        let sql = format!(
+            "CREATE INDEX {} ON {} ({})",
+            quote_ident(&name),
+            quote_ident(&table),
+            quote_ident(&column)
+        );
+        check_sql(policy, &sql)?;
+        let index = AdvisedIndex {
+            table,
+            column,
+            sql,
+            query,
+            calls: slow.calls,
+            mean_ms: slow.mean_ms(),
+            before_ms: None,
+            after_ms: None,
+            created: false,
+        };
+        advised.push((index, name, slow));
+    }
+    if !dry_run {
+        let mut created = Vec::new();
+        if let Err(err) = create(db, &mut advised, &mut created) {
+            let left: Vec<String> = created
+                .iter()
+                .rev()
+                .filter_map(|(table, name)| {
+                    let dropped = drop_index(db, table, name);
+                    dropped.err().map(|e| format!("'{name}' ({})", e.reason))
+                })
+                .collect();
+            if left.is_empty() {
+                return Err(err);
+            }
+            let reason = format!(
+                "{}; the indexes created before it could not all be dropped again: {}",
+                err.reason,
+                left.join(", ")
+            );
+            return Err(with_reason(err, reason));
+        }
+    }
+    Ok(advised.into_iter().map(|(index, _, _)| index).collect())
+}
+
+/// Create and time each advised index, keeping those that made their query
+/// faster. `created` collects the indexes still there, to undo them on
+/// error.
+fn create(
+    db: &Database,
+    advised: &mut [(AdvisedIndex, String, Query)],
+    created: &mut Vec<(String, String)>,
+) -> napi::Result<()> {
+    for (index, name, slow) in advised {
+        let before_ms = time(db, slow)?;
+        db.execute(&index.sql, ()).map_err(to_napi)?;
+        created.push((index.table.clone(), name.clone()));
+        let after_ms = time(db, slow)?;
+        index.before_ms = Some(before_ms);
+        index.after_ms = Some(after_ms);
+        if after_ms < before_ms * KEEP_BELOW {
+            index.created = true;
+        } else {
+            drop_index(db, &index.table, name)?;
+            created.pop();
+        }
+    }
+    Ok(())
+}
+
+fn drop_index(db: &Database, table: &str, name: &str) -> napi::Result<()> {
+    let sql = format!("DROP INDEX {} ON {}", quote_ident(name), quote_ident(table));
+    db.execute(&sql, ()).map(|_| ()).map_err(to_napi)
+}
+
+/// Best time of `RUNS` runs of the slowest call of `query`, in milliseconds.
+/// The semantic cache is cleared before each, or a query without parameters
+/// would be answered from it.
+fn time(db: &Database, query: &Query) -> napi::Result<f64> {
+    let mut best = f64::INFINITY;
+    for _ in 0..RUNS {
+        db.clear_semantic_cache().map_err(to_napi)?;
+        let started = Instant::now();
+        let mut rows = query
+            .params
+            .clone()
+            .query_on_db(db, &query.sql)
+            .map_err(from_core)?;
+        while rows.advance() {}
+        best = best.min(started.elapsed().as_secs_f64() * 1000.0);
+    }
+    Ok(best)
+}
+
+/// The table and column to index for `sql`, if any.
+fn candidate(db: &Database, sql: &str) -> Option<(String, String)> {
+    let Ok(Statement::Select(select)) = parse_single(sql) else {
+        return None;
+    };
+    if !select.set_operations.is_empty() {
+        return None;
+    }
+    let Some(Expression::TableSource(source)) = select.table_expr.as_deref() else {
+        return None;
+    };
+    let (mut equal, mut range) = (None, None);
+    compared(
+        select.where_clause.as_deref()?,
+        source,
+        &mut equal,
+        &mut range,
+    );
+    let column = equal.or(range)?;
+
+    let schema = db.engine().get_table_schema(&source.name.value).ok()?;
+    let found = schema
+        .columns
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(&column))?;
+    if found.primary_key {
+        return None;
+    }
+    let indexes = db.engine().get_all_indexes(&schema.table_name).ok()?;
+    let indexed = indexes.iter().any(|index| {
+        index
+            .column_names()
+            .first()
+            .is_some_and(|c| c.eq_ignore_ascii_case(&found.name))
+    });
+    if indexed {
+        return None;
+    }
+    Some((schema.table_name.clone(), found.name.clone()))
+}
+
+/// Note the first column of `source` that the `AND`ed conditions of `expr`
+/// compare with a value for equality, and the first compared for a range.
+fn compared(
+    expr: &Expression,
+    source: &SimpleTableSource,
+    equal: &mut Option<String>,
+    range: &mut Option<String>,
+) {
+    let (column, is_equal) = match expr {
+        Expression::Infix(infix) if infix.op_type == InfixOperator::And => {
+            compared(&infix.left, source, equal, range);
+            compared(&infix.right, source, equal, range);
+            return;
+        }
+        Expression::Infix(infix) => {
+            let is_equal = match infix.op_type {
+                InfixOperator::Equal => true,
+                InfixOperator::LessThan
+                | InfixOperator::LessEqual
+                | InfixOperator::GreaterThan
+                | InfixOperator::GreaterEqual => false,
+                _ => return,
+            };
+            let column = match (
+                column_of(&infix.left, source),
+                column_of(&infix.right, source),
+            ) {
+                (Some(column), None) if is_value(&infix.right) => column,
+                (None, Some(column)) if is_value(&infix.left) => column,
+                _ => return,
+            };
+            (column, is_equal)
+        }
+        Expression::In(is_in) if !is_in.not => {
+            let values = match is_in.right.as_ref() {
+                Expression::List(list) => list.elements.iter().all(is_value),
+                Expression::ExpressionList(list) => list.expressions.iter().all(is_value),
+                _ => false,
+            };
+            match column_of(&is_in.left, source) {
+                Some(column) if values => (column, true),
+                _ => return,
+            }
+        }
+        Expression::Between(between) if !between.not => match column_of(&between.expr, source) {
+            Some(column) if is_value(&between.lower) && is_value(&between.upper) => (column, false),
+            _ => return,
+        },
+        _ => return,
+    };
+    let slot = if is_equal { equal } else { range };
+    slot.get_or_insert(column);
+}
+
+/// The column `expr` names, if it is a column of `source`.
+fn column_of(expr: &Expression, source: &SimpleTableSource) -> Option<String> {
+    match expr {
+        Expression::Identifier(ident) => Some(ident.value.to_string()),
+        Expression::QualifiedIdentifier(qualified) => {
+            let qualifier = &qualified.qualifier.value_lower;
+            let names_source = *qualifier == source.name.value_lower
+                || source
+                    .alias
+                    .as_ref()
+                    .is_some_and(|alias| *qualifier == alias.value_lower);
+            names_source.then(|| qualified.name.value.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Whether `expr` is a literal or a parameter.
+fn is_value(expr: &Expression) -> bool {
+    match expr {
+        Expression::IntegerLiteral(_)
+        | Expression::FloatLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::BooleanLiteral(_)
+        | Expression::Parameter(_) => true,
+        Expression::Prefix(prefix) => prefix.operator == "-" && is_value(&prefix.right),
+        _ => false,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,165 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `autoAnalyze` open option.
+//
+// Changed rows are counted per table from the same committed change events
+// that `on('change')` listeners receive, so enabling the option turns on
+// change capture as a listener would. Once a table has seen `minChanges`
+// changed rows, and at least `threshold` times the rows it had when it was
+// last analyzed, ANALYZE runs on a background thread and its counter starts
+// over. A table not yet analyzed by the binding counts as empty, so its first
+// refresh comes after `minChanges` changes. Failures (a table dropped in the
+// meantime, a closed database) are ignored: the next changes try again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use stoolap::Value;
+
+use crate::changes::ChangeEvent;
+use crate::sql::quote_ident;
+use crate::tasks::DbHandle;
+
+/// Fraction of a table's rows that must change when `threshold` is not given.
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// Changed rows needed before any refresh when `minChanges` is not given.
+pub const DEFAULT_MIN_CHANGES: u32 = 50;
+
+/// Row count ANALYZE recorded for a table.
+const ROW_COUNT_SQL: &str = "SELECT row_count FROM _sys_table_stats WHERE table_name = $1";
+
+/// Options for `Database.open(path, { autoAnalyze })`.
+#[napi(object, object_to_js = false)]
+#[derive(Clone)]
+pub struct AutoAnalyzeOptions {
+    /// Changed rows, as a fraction of the table's rows at its last ANALYZE,
+    /// that trigger a refresh (default 0.1).
+    pub threshold: Option<f64>,
+    /// Changed rows below which a table is never refreshed (default 50).
+    pub min_changes: Option<u32>,
+}
+
+pub type AutoAnalyzeRef = Arc<AutoAnalyze>;
+
+/// Per-table change counters that refresh statistics when they grow large.
+pub struct AutoAnalyze {
+    threshold: f64,
+    min_changes: u64,
+    tables: Mutex<HashMap<String, Counter>>,
+}
+
+#[derive(Default)]
+struct Counter {
+    /// Rows changed since the last refresh started.
+    changed: u64,
+    /// Rows in the table after the last refresh.
+    rows: u64,
+    /// A refresh is running for this table.
+    running: bool,
+}
+
+impl AutoAnalyze {
+    pub fn new(options: AutoAnalyzeOptions) -> napi::Result<Self> {
+        let threshold = options.threshold.unwrap_or(DEFAULT_THRESHOLD);
+        if !threshold.is_finite() || threshold < 0.0 {
+            return Err(napi::Error::from_reason(
+                "autoAnalyze.threshold must be a non-negative number",
+            ));
+        }
+        Ok(Self {
+            threshold,
+            min_changes: options.min_changes.unwrap_or(DEFAULT_MIN_CHANGES).into(),
+            tables: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Count committed changes and start a refresh for every table that
+    /// crossed its threshold.
+    pub fn record(self: &Arc<Self>, db: &DbHandle, events: &[ChangeEvent]) {
+        let mut due = Vec::new();
+        {
+            let mut tables = lock(&self.tables);
+            for event in events {
+                let counter = tables.entry(event.table.clone()).or_default();
+                counter.changed += 1;
+                if !counter.running && self.is_due(counter) {
+                    counter.running = true;
+                    counter.changed = 0;
+                    due.push(event.table.clone());
+                }
+            }
+        }
+        for table in due {
+            let this = Arc::clone(self);
+            let db = Arc::clone(db);
+            let name = table.clone();
+            let spawned = std::thread::Builder::new()
+                .name("stoolap-analyze".to_string())
+                .spawn(move || this.refresh(&db, &name));
+            if spawned.is_err() {
+                // No thread to run it on: leave it to the next changes.
+                if let Some(counter) = lock(&self.tables).get_mut(&table) {
+                    counter.running = false;
+                }
+            }
+        }
+    }
+
+    fn is_due(&self, counter: &Counter) -> bool {
+        counter.changed >= self.min_changes
+            && counter.changed as f64 >= self.threshold * counter.rows as f64
+    }
+
+    /// Run ANALYZE on `table` and remember the row count it recorded, which
+    /// leaves out rows committed after its scan. Changes counted while it ran
+    /// can make the table due again, and then it runs again.
+    fn refresh(&self, db: &DbHandle, table: &str) {
+        let table_ident = quote_ident(table);
+        loop {
+            let rows = db
+                .execute(&format!("ANALYZE {table_ident}"), ())
+                .and_then(|_| db.query(ROW_COUNT_SQL, (table,)))
+                .ok()
+                .and_then(|mut rows| {
+                    if !rows.advance() {
+                        return None;
+                    }
+                    match rows.current_row().get(0) {
+                        Some(Value::Integer(n)) => Some(*n as u64),
+                        _ => None,
+                    }
+                });
+            let mut tables = lock(&self.tables);
+            let Some(counter) = tables.get_mut(table) else {
+                return;
+            };
+            let Some(rows) = rows else {
+                counter.running = false;
+                return;
+            };
+            counter.rows = rows;
+            if !self.is_due(counter) {
+                counter.running = false;
+                return;
+            }
+            counter.changed = 0;
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,204 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `executeBatch()` and `executeBatchSync()`.
+//
+// The statement is parsed once, or taken from a prepared statement's plan,
+// and every parameter array is converted before anything runs, so the async
+// version touches no JS values off the main thread. On a database or
+// prepared statement the rows run in one transaction that commits at the end;
+// a failing row rolls the whole batch back. In an explicit transaction they
+// run in that transaction, and rows before a failing one stay in it.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use napi::{sys, Env};
+use stoolap::api::{Database, Transaction as ApiTransaction};
+use stoolap::parser::ast::Statement;
+use stoolap::{CachedPlanRef, ParamVec};
+use stoolap_bindings_core::{batch, Error};
+
+use crate::changes::{ChangeEvent, ChangeHubRef};
+use crate::error::{from_core, to_napi};
+use crate::interrupt::Ticket;
+use crate::policy::{check_single, check_statement, PolicyRef};
+use crate::profile::ProfilerRef;
+use crate::readers::Readers;
+use crate::retry::{retry_warning, RetryPolicy};
+use crate::tasks::{check, RunOutcome, TaskParams};
+use crate::types::Types;
+use crate::value::{parse_positional, RawParam};
+
+/// A resolved `executeBatch` call.
+pub struct Batch {
+    sql: String,
+    statement: Arc<Statement>,
+    plan: Option<CachedPlanRef>,
+    rows: Vec<ParamVec>,
+}
+
+impl Batch {
+    /// Parse `sql` once and check it against the policy.
+    pub fn parse(
+        env: &Env,
+        types: &Types,
+        sql: String,
+        policy: &PolicyRef,
+        params_array: RawParam,
+    ) -> napi::Result<Self> {
+        let rows = batch_rows(env, types, params_array)?;
+        check_single(policy, &sql)?;
+        let statement = batch::parse(&sql).map_err(from_core)?;
+        check_statement(policy, &statement)?;
+        Ok(Self {
+            sql,
+            statement: Arc::new(statement),
+            plan: None,
+            rows,
+        })
+    }
+
+    /// Run a prepared statement's cached plan. With `arity`, every row must
+    /// hold that many positional parameters.
+    pub fn prepared(
+        env: &Env,
+        types: &Types,
+        sql: String,
+        plan: CachedPlanRef,
+        params_array: RawParam,
+        arity: Option<usize>,
+    ) -> napi::Result<Self> {
+        let rows = batch_rows(env, types, params_array)?;
+        if let Some(expected) = arity {
+            batch::check_arity(expected, &rows).map_err(|e| match e {
+                Error::Row { index, error } => {
+                    napi::Error::from_reason(format!("paramsArray[{index}]: {error}"))
+                }
+                other => from_core(other),
+            })?;
+        }
+        Ok(Self {
+            sql,
+            statement: Arc::clone(&plan.statement),
+            plan: Some(plan),
+            rows,
+        })
+    }
+
+    /// Number of parameter sets.
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// One parameter list per row.
+    pub fn rows(&self) -> &[ParamVec] {
+        &self.rows
+    }
+
+    /// Run every row in one new transaction and commit it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run(
+        mut self,
+        db: &Database,
+        changes: &ChangeHubRef,
+        profiler: &ProfilerRef,
+        readers: &Readers,
+        retry: &RetryPolicy,
+        ticket: &Ticket,
+    ) -> napi::Result<RunOutcome> {
+        let timer = Instant::now();
+        let started = profiler.start_batch(&self.rows);
+        let rows = std::mem::take(&mut self.rows);
+        let ((total, events), retries) = retry.run_counted(ticket, rows, |rows| {
+            readers.write(|| {
+                let mut tx = db.begin().map_err(to_napi)?;
+                let mut events = Vec::new();
+                let total = self.execute(&mut tx, rows, changes, &mut events, ticket)?;
+                tx.commit().map_err(to_napi)?;
+                Ok((total, events))
+            })
+        })?;
+        changes.emit(events);
+        profiler.finish_write(started, &self.sql, total, self.plan.as_ref());
+        let mut outcome = RunOutcome::new(timer, total);
+        outcome.warnings.extend(retry_warning(retries));
+        Ok(outcome)
+    }
+
+    /// Run every row in an explicit transaction, adding change events to its
+    /// pending list.
+    pub fn run_on_tx(
+        mut self,
+        tx: &mut ApiTransaction,
+        changes: &ChangeHubRef,
+        pending: &mut Vec<ChangeEvent>,
+        profiler: &ProfilerRef,
+        ticket: &Ticket,
+    ) -> napi::Result<RunOutcome> {
+        let timer = Instant::now();
+        let started = profiler.start_batch(&self.rows);
+        let rows = std::mem::take(&mut self.rows);
+        let total = self.execute(tx, rows, changes, pending, ticket)?;
+        profiler.finish_write(started, &self.sql, total, self.plan.as_ref());
+        Ok(RunOutcome::new(timer, total))
+    }
+
+    fn execute(
+        &self,
+        tx: &mut ApiTransaction,
+        rows: Vec<ParamVec>,
+        changes: &ChangeHubRef,
+        events: &mut Vec<ChangeEvent>,
+        ticket: &Ticket,
+    ) -> napi::Result<i64> {
+        let mut total = 0i64;
+        for params in rows {
+            ticket.check()?;
+            total += if changes.is_active() {
+                changes.execute_on_tx(tx, TaskParams::Positional(params), &self.sql, events)?
+            } else {
+                tx.execute_prepared(&self.statement, params)
+                    .map_err(to_napi)?
+            };
+        }
+        Ok(total)
+    }
+}
+
+/// Convert `paramsArray` to one value list per row.
+fn batch_rows(env: &Env, types: &Types, params_array: RawParam) -> napi::Result<Vec<ParamVec>> {
+    let raw_env = env.raw();
+    let arr = params_array.0;
+
+    let mut is_array = false;
+    check(unsafe { sys::napi_is_array(raw_env, arr, &mut is_array) })?;
+    if !is_array {
+        return Err(napi::Error::from_reason("paramsArray must be an array"));
+    }
+
+    let mut len = 0u32;
+    check(unsafe { sys::napi_get_array_length(raw_env, arr, &mut len) })?;
+    let mut rows = Vec::with_capacity(len as usize);
+    for i in 0..len {
+        let mut elem = std::ptr::null_mut();
+        check(unsafe { sys::napi_get_element(raw_env, arr, i, &mut elem) })?;
+        rows.push(parse_positional(raw_env, elem, types)?);
+    }
+    Ok(rows)
+}
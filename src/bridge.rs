@@ -0,0 +1,755 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.serve(port)` and `RemoteDatabase`, for a database owned by a worker
+// thread.
+//
+// A `RemoteDatabase` encodes each `query()` or `execute()` call as a
+// `Uint8Array` and posts it to its port. The database serving the other end
+// decodes it and schedules the call as one of its own, so `close()`,
+// `serializeWrites` and the monitor see it; the task encodes the rows or the
+// `RunResult` on its thread, and the response is posted back. The client
+// decodes it into the value the call would have resolved to locally, so the
+// thread that made the call only pays for the two copies. Errors keep their
+// status and engine cause, its `kind` and fields included; custom types are
+// not applied on either side.
+//
+// Every number is little-endian, and a string is its u32 byte length then
+// its UTF-8 bytes.
+//
+//     request   u32 id, u8 method (0 query, 1 execute), str sql,
+//               u8 form (0 positional, 1 named), u32 count,
+//               count × ([str name] value)
+//     response  u32 id, then one of
+//               0 rows      u32 columns, columns × str, u32 rows,
+//                           rows × columns × value
+//               1 result    i64 changes, value lastInsertRowId, f64 durationMs,
+//                           u32 warnings, warnings × str, u8 has rows, [rows]
+//               2 error     i32 status, str message, str encoded cause
+//               3 closed
+//     value     u8 tag, then 0 null, 1 i64, 2 f64, 3 str, 4 u8 boolean,
+//               5 i64 seconds and u32 nanoseconds, 6 str JSON,
+//               7 u32 length and that many f32 (a vector)
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr;
+use std::rc::Rc;
+
+use chrono::DateTime;
+use napi::bindgen_prelude::*;
+use napi::{sys, Env, JsValue, Status, Task};
+use stoolap::{ParamVec, Value};
+
+use crate::closing;
+use crate::database::{convert_params, JsDatabase};
+use crate::error::restore;
+use crate::memory::MemoryBudgetRef;
+use crate::tasks::{
+    check, collected_rows_to_js, run_result, CollectedRows, ExecTask, QueryTask, RunOutcome,
+    TaskParams,
+};
+use crate::types::Types;
+use crate::value::RawParam;
+
+const QUERY: u8 = 0;
+const EXECUTE: u8 = 1;
+
+const POSITIONAL: u8 = 0;
+const NAMED: u8 = 1;
+
+const ROWS: u8 = 0;
+const RESULT: u8 = 1;
+const ERROR: u8 = 2;
+const CLOSED: u8 = 3;
+
+const NULL: u8 = 0;
+const INTEGER: u8 = 1;
+const FLOAT: u8 = 2;
+const TEXT: u8 = 3;
+const BOOLEAN: u8 = 4;
+const TIMESTAMP: u8 = 5;
+const JSON: u8 = 6;
+const VECTOR: u8 = 7;
+
+/// The first byte of a vector extension value.
+const VECTOR_TAG: u8 = 7;
+
+type PortMethod = FunctionRef<Unknown<'static>, Unknown<'static>>;
+
+pub enum Method {
+    Query,
+    Execute,
+}
+
+/// A call decoded from a request.
+pub struct Request {
+    pub id: u32,
+    pub method: Method,
+    pub sql: String,
+    pub params: TaskParams,
+}
+
+impl Request {
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(bytes);
+        let id = reader.u32()?;
+        let method = match reader.u8()? {
+            QUERY => Method::Query,
+            EXECUTE => Method::Execute,
+            _ => return None,
+        };
+        let sql = reader.str()?.to_string();
+        let form = reader.u8()?;
+        let count = reader.u32()? as usize;
+        let params = match form {
+            POSITIONAL => TaskParams::Positional(
+                (0..count)
+                    .map(|_| reader.value())
+                    .collect::<Option<ParamVec>>()?,
+            ),
+            NAMED => TaskParams::Named(
+                (0..count)
+                    .map(|_| Some((reader.str()?.to_string(), reader.value()?)))
+                    .collect::<Option<_>>()?,
+            ),
+            _ => return None,
+        };
+        reader.done().then_some(Self {
+            id,
+            method,
+            sql,
+            params,
+        })
+    }
+}
+
+// ============================================================
+// Serving — db.serve(port)
+// ============================================================
+
+/// Answer the requests that arrive on `port` with `db`.
+pub fn serve(env: &Env, db: JsDatabase, port: Object) -> napi::Result<()> {
+    let post = bound(&port, "postMessage")?;
+    let respond = env.create_function_from_closure::<Unknown, (), _>("respond", move |ctx| {
+        // Messages that are not requests are left to other listeners.
+        let Ok(request) = ctx.get::<Uint8Array>(0) else {
+            return Ok(());
+        };
+        let post = ctx.this::<Function<Unknown, Unknown>>()?;
+        let env = &*ctx.env;
+        let scheduled = match Request::decode(&request) {
+            Some(request) => db.bridge_task(request),
+            None => {
+                let Some(id) = Reader::new(&request).u32() else {
+                    return Ok(());
+                };
+                let err = napi::Error::new(Status::InvalidArg, "Malformed RemoteDatabase request");
+                let mut writer = Writer::response(id, ERROR);
+                writer.error(&err);
+                post.call(Buffer::from(writer.0).into_unknown(env)?)?;
+                return Ok(());
+            }
+        };
+        match scheduled {
+            Ok(scheduled) => {
+                let promise = unsafe { ToNapiValue::to_napi_value(env.raw(), scheduled)? };
+                let promise = Object::from_raw(env.raw(), promise);
+                let then = promise
+                    .get_named_property::<Function<Function<Unknown, Unknown>, Unknown>>("then")?;
+                then.apply(promise, post)?;
+            }
+            Err(err) => {
+                let mut writer = Writer::response(request_id(&request), ERROR);
+                writer.error(&err);
+                post.call(Buffer::from(writer.0).into_unknown(env)?)?;
+            }
+        }
+        Ok(())
+    })?;
+    // The listener gets the bound `postMessage` as `this`.
+    let listener = respond.bind(unsafe { Unknown::from_napi_value(env.raw(), post)? })?;
+    let on =
+        port.get_named_property::<Function<FnArgs<(&str, Function<Unknown, ()>)>, Unknown>>("on")?;
+    on.apply(port, FnArgs::from(("message", listener)))?;
+    Ok(())
+}
+
+fn request_id(bytes: &[u8]) -> u32 {
+    Reader::new(bytes).u32().unwrap_or_default()
+}
+
+/// `port[name]` bound to `port`.
+fn bound(port: &Object, name: &str) -> napi::Result<sys::napi_value> {
+    let method = port.get_named_property::<Function<Unknown, Unknown>>(name)?;
+    Ok(method.bind(port)?.raw())
+}
+
+/// The function `value` holds, kept past the current scope.
+fn keep(env: &Env, value: sys::napi_value) -> napi::Result<PortMethod> {
+    unsafe { PortMethod::from_napi_value(env.raw(), value) }
+}
+
+// ============================================================
+// BridgeTask — a call that came in over a port
+// ============================================================
+
+pub enum BridgeCall {
+    Query(QueryTask),
+    Execute(ExecTask),
+}
+
+pub struct BridgeTask {
+    id: u32,
+    call: BridgeCall,
+}
+
+impl BridgeTask {
+    pub fn new(id: u32, call: BridgeCall) -> Self {
+        Self { id, call }
+    }
+}
+
+impl Task for BridgeTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let written = match self.call {
+            BridgeCall::Query(ref mut task) => task.compute().and_then(|rows| {
+                let mut writer = Writer::response(self.id, ROWS);
+                writer.rows(&rows)?;
+                Ok(writer)
+            }),
+            BridgeCall::Execute(ref mut task) => task.compute().and_then(|outcome| {
+                let mut writer = Writer::response(self.id, RESULT);
+                writer.outcome(&outcome)?;
+                Ok(writer)
+            }),
+        };
+        Ok(written
+            .unwrap_or_else(|err| {
+                let mut writer = Writer::response(self.id, ERROR);
+                writer.error(&err);
+                writer
+            })
+            .0)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output.into())
+    }
+
+    fn reject(&mut self, env: Env, mut err: napi::Error) -> napi::Result<Self::JsValue> {
+        // Errors of the call are in the response already; what is left was
+        // made before it ran, as a JS error by now.
+        let status = err.status;
+        let cause = err.cause.take();
+        let error = unsafe { JsError::from(err).into_value(env.raw()) };
+        let error = Object::from_raw(env.raw(), error);
+        let writer = if error.get_named_property::<String>("code").ok().as_deref()
+            == Some("DatabaseClosed")
+        {
+            Writer::response(self.id, CLOSED)
+        } else {
+            let message = error.get_named_property::<String>("message")?;
+            let mut error = napi::Error::new(status, message);
+            error.cause = cause;
+            let mut writer = Writer::response(self.id, ERROR);
+            writer.error(&error);
+            writer
+        };
+        Ok(writer.0.into())
+    }
+}
+
+// ============================================================
+// RemoteDatabase — the other end
+// ============================================================
+
+/// Runs queries on a database a worker thread serves with `db.serve(port)`.
+#[napi(js_name = "RemoteDatabase")]
+pub struct JsRemoteDatabase {
+    client: Rc<RefCell<Client>>,
+}
+
+struct Client {
+    next_id: u32,
+    /// Promises of the calls waiting for their response, by id.
+    pending: HashMap<u32, sys::napi_deferred>,
+    post: PortMethod,
+    /// `port.ref()`, while calls are pending.
+    hold: PortMethod,
+    /// `port.unref()`, once none is.
+    release: PortMethod,
+    close: PortMethod,
+    closed: bool,
+    /// Parameters are converted without custom types.
+    types: Types,
+    /// Decoded rows are held against no budget.
+    memory: MemoryBudgetRef,
+}
+
+#[napi]
+impl JsRemoteDatabase {
+    /// Send calls over `port`, one end of a `MessageChannel` whose other end
+    /// a worker thread passed to `db.serve()`. The port doesn't keep the
+    /// process alive while no call is pending.
+    #[napi(
+        constructor,
+        ts_args_type = "port: import('node:worker_threads').MessagePort"
+    )]
+    pub fn new(env: Env, port: Object) -> napi::Result<Self> {
+        let method = |name: &str| keep(&env, bound(&port, name)?);
+        let client = Rc::new(RefCell::new(Client {
+            next_id: 0,
+            pending: HashMap::new(),
+            post: method("postMessage")?,
+            hold: method("ref")?,
+            release: method("unref")?,
+            close: method("close")?,
+            closed: false,
+            types: Types::default(),
+            memory: MemoryBudgetRef::default(),
+        }));
+        let receiving = Rc::clone(&client);
+        let receive =
+            env.create_function_from_closure::<Unknown, (), _>("receive", move |ctx| {
+                if let Ok(response) = ctx.get::<Uint8Array>(0) {
+                    receiving.borrow_mut().receive(&*ctx.env, &response)?;
+                }
+                Ok(())
+            })?;
+        let on = port
+            .get_named_property::<Function<FnArgs<(&str, Function<Unknown, ()>)>, Unknown>>("on")?;
+        on.apply(port, FnArgs::from(("message", receive)))?;
+        client
+            .borrow()
+            .release
+            .borrow_back(&env)?
+            .call(().into_unknown(&env)?)?;
+        Ok(Self { client })
+    }
+
+    /// Query rows on the served database. Returns Promise<Array<Object>>.
+    #[napi(
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
+        ts_return_type = "Promise<Record<string, any>[]>"
+    )]
+    pub fn query(
+        &self,
+        env: Env,
+        sql: String,
+        params: Option<RawParam>,
+    ) -> napi::Result<RawPromise> {
+        self.client.borrow_mut().send(&env, QUERY, sql, params)
+    }
+
+    /// Execute a DDL/DML statement on the served database. Returns
+    /// Promise<RunResult>.
+    #[napi(
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
+        ts_return_type = "Promise<RunResult>"
+    )]
+    pub fn execute(
+        &self,
+        env: Env,
+        sql: String,
+        params: Option<RawParam>,
+    ) -> napi::Result<RawPromise> {
+        self.client.borrow_mut().send(&env, EXECUTE, sql, params)
+    }
+
+    /// Number of calls waiting for their response.
+    #[napi(getter)]
+    pub fn pending(&self) -> u32 {
+        self.client.borrow().pending.len() as u32
+    }
+
+    /// Close the port. Pending calls reject with a `DatabaseClosed` error, as
+    /// do calls made afterwards; the served database stays open.
+    #[napi]
+    pub fn close(&self, env: Env) -> napi::Result<()> {
+        let mut client = self.client.borrow_mut();
+        if client.closed {
+            return Ok(());
+        }
+        client.closed = true;
+        for (_, deferred) in client.pending.drain() {
+            settle(
+                &env,
+                deferred,
+                Err(closing::to_js(&env, closing::closed_error())),
+            )?;
+        }
+        client
+            .close
+            .borrow_back(&env)?
+            .call(().into_unknown(&env)?)?;
+        Ok(())
+    }
+}
+
+impl Client {
+    fn send(
+        &mut self,
+        env: &Env,
+        method: u8,
+        sql: String,
+        params: Option<RawParam>,
+    ) -> napi::Result<RawPromise> {
+        let mut deferred = ptr::null_mut();
+        let mut promise = ptr::null_mut();
+        check(unsafe { sys::napi_create_promise(env.raw(), &mut deferred, &mut promise) })?;
+        if self.closed {
+            settle(
+                env,
+                deferred,
+                Err(closing::to_js(env, closing::closed_error())),
+            )?;
+            return Ok(RawPromise(promise));
+        }
+        let (sql, params) = convert_params(env, &self.types, sql, params)?;
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let mut writer = Writer(Vec::with_capacity(sql.len() + 16));
+        writer.u32(id);
+        writer.u8(method);
+        writer.str(&sql);
+        match params {
+            TaskParams::Positional(params) => {
+                writer.u8(POSITIONAL);
+                writer.u32(params.len() as u32);
+                params.iter().for_each(|value| writer.value(value));
+            }
+            TaskParams::Named(params) => {
+                writer.u8(NAMED);
+                writer.u32(params.len() as u32);
+                for (name, value) in &params {
+                    writer.str(name);
+                    writer.value(value);
+                }
+            }
+        }
+        self.post
+            .borrow_back(env)?
+            .call(Buffer::from(writer.0).into_unknown(env)?)?;
+        if self.pending.is_empty() {
+            self.hold.borrow_back(env)?.call(().into_unknown(env)?)?;
+        }
+        self.pending.insert(id, deferred);
+        Ok(RawPromise(promise))
+    }
+
+    fn receive(&mut self, env: &Env, bytes: &[u8]) -> napi::Result<()> {
+        let mut reader = Reader::new(bytes);
+        let Some(deferred) = reader.u32().and_then(|id| self.pending.remove(&id)) else {
+            return Ok(());
+        };
+        if self.pending.is_empty() {
+            self.release.borrow_back(env)?.call(().into_unknown(env)?)?;
+        }
+        let settled = match reader.response(&self.memory) {
+            Some(Response::Rows(rows)) => collected_rows_to_js(env.raw(), rows, false),
+            Some(Response::Result(outcome)) => Ok(run_result(env.raw(), &outcome)?),
+            Some(Response::Failed(err)) => Err(restore(env, err)),
+            Some(Response::Closed) => Err(closing::to_js(env, closing::closed_error())),
+            None => Err(napi::Error::new(
+                Status::GenericFailure,
+                "Malformed RemoteDatabase response",
+            )),
+        };
+        settle(env, deferred, settled)
+    }
+}
+
+/// Resolve or reject the promise of `deferred`.
+fn settle(
+    env: &Env,
+    deferred: sys::napi_deferred,
+    settled: napi::Result<sys::napi_value>,
+) -> napi::Result<()> {
+    match settled {
+        Ok(value) => check(unsafe { sys::napi_resolve_deferred(env.raw(), deferred, value) }),
+        Err(err) => {
+            let value = unsafe { JsError::from(err).into_value(env.raw()) };
+            check(unsafe { sys::napi_reject_deferred(env.raw(), deferred, value) })
+        }
+    }
+}
+
+/// A promise made with `napi_create_promise`.
+pub struct RawPromise(sys::napi_value);
+
+impl TypeName for RawPromise {
+    fn type_name() -> &'static str {
+        "Promise"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Object
+    }
+}
+
+impl ToNapiValue for RawPromise {
+    unsafe fn to_napi_value(_env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        Ok(val.0)
+    }
+}
+
+enum Response {
+    Rows(CollectedRows),
+    Result(RunOutcome),
+    Failed(napi::Error),
+    Closed,
+}
+
+// ============================================================
+// Encoding
+// ============================================================
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn response(id: u32, kind: u8) -> Self {
+        let mut writer = Writer(Vec::new());
+        writer.u32(id);
+        writer.u8(kind);
+        writer
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn i64(&mut self, v: i64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.0.extend_from_slice(s.as_bytes());
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Null(_) => self.u8(NULL),
+            Value::Integer(i) => {
+                self.u8(INTEGER);
+                self.i64(*i);
+            }
+            Value::Float(f) => {
+                self.u8(FLOAT);
+                self.f64(*f);
+            }
+            Value::Text(s) => {
+                self.u8(TEXT);
+                self.str(s);
+            }
+            Value::Boolean(b) => {
+                self.u8(BOOLEAN);
+                self.u8(*b as u8);
+            }
+            Value::Timestamp(ts) => {
+                self.u8(TIMESTAMP);
+                self.i64(ts.timestamp());
+                self.u32(ts.timestamp_subsec_nanos());
+            }
+            Value::Extension(data) => {
+                if let Some(text) = value.as_json() {
+                    self.u8(JSON);
+                    self.str(text);
+                } else if data.first() == Some(&VECTOR_TAG) {
+                    self.u8(VECTOR);
+                    self.u32((data.len() as u32 - 1) / 4);
+                    self.0.extend_from_slice(&data[1..]);
+                } else {
+                    self.u8(NULL);
+                }
+            }
+        }
+    }
+
+    fn rows(&mut self, rows: &CollectedRows) -> napi::Result<()> {
+        let keys = rows.keys()?;
+        self.u32(keys.len() as u32);
+        keys.iter().for_each(|key| self.str(key));
+        self.u32(rows.row_count() as u32);
+        for row in rows.rows() {
+            row.iter().for_each(|value| self.value(value));
+        }
+        Ok(())
+    }
+
+    fn outcome(&mut self, outcome: &RunOutcome) -> napi::Result<()> {
+        self.i64(outcome.changes);
+        match outcome.last_insert_row_id {
+            Some(id) => self.value(&Value::Integer(id)),
+            None => self.u8(NULL),
+        }
+        self.f64(outcome.duration_ms);
+        self.u32(outcome.warnings.len() as u32);
+        outcome.warnings.iter().for_each(|w| self.str(w));
+        match outcome.rows {
+            Some(ref rows) => {
+                self.u8(1);
+                self.rows(rows)
+            }
+            None => {
+                self.u8(0);
+                Ok(())
+            }
+        }
+    }
+
+    fn error(&mut self, err: &napi::Error) {
+        self.0
+            .extend_from_slice(&i32::from(err.status).to_le_bytes());
+        self.str(&err.reason);
+        // An engine cause's reason is its encoding, on either thread.
+        self.str(err.cause.as_ref().map_or("", |cause| cause.reason.as_str()));
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let (taken, rest) = self.bytes.split_at_checked(n)?;
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn done(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn str(&mut self) -> Option<&'a str> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).ok()
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        Some(match self.u8()? {
+            NULL => Value::null_unknown(),
+            INTEGER => Value::integer(self.i64()?),
+            FLOAT => Value::float(self.f64()?),
+            TEXT => Value::text(self.str()?),
+            BOOLEAN => Value::boolean(self.u8()? != 0),
+            TIMESTAMP => {
+                let secs = self.i64()?;
+                Value::timestamp(DateTime::from_timestamp(secs, self.u32()?)?)
+            }
+            JSON => Value::json(self.str()?),
+            VECTOR => {
+                let len = self.u32()? as usize;
+                let floats = self.take(len.checked_mul(4)?)?;
+                Value::vector(
+                    floats
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                )
+            }
+            _ => return None,
+        })
+    }
+
+    fn rows(&mut self, memory: &MemoryBudgetRef) -> Option<CollectedRows> {
+        let columns = (0..self.u32()?)
+            .map(|_| self.str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()?;
+        let count = self.u32()? as usize;
+        let mut rows = Vec::with_capacity(count.min(self.bytes.len()));
+        for _ in 0..count {
+            rows.push(
+                (0..columns.len())
+                    .map(|_| self.value())
+                    .collect::<Option<Vec<_>>>()?,
+            );
+        }
+        Some(CollectedRows::new(columns, rows, memory.charge()))
+    }
+
+    fn response(&mut self, memory: &MemoryBudgetRef) -> Option<Response> {
+        let response = match self.u8()? {
+            ROWS => Response::Rows(self.rows(memory)?),
+            RESULT => {
+                let changes = self.i64()?;
+                let last_insert_row_id = self.value()?.as_int64();
+                let duration_ms = self.f64()?;
+                let warnings = (0..self.u32()?)
+                    .map(|_| self.str().map(str::to_string))
+                    .collect::<Option<_>>()?;
+                let rows = match self.u8()? {
+                    0 => None,
+                    _ => Some(self.rows(memory)?),
+                };
+                Response::Result(RunOutcome {
+                    changes,
+                    last_insert_row_id,
+                    duration_ms,
+                    warnings,
+                    rows,
+                })
+            }
+            ERROR => {
+                let status = Status::from(self.i32()?);
+                let mut err = napi::Error::new(status, self.str()?);
+                let cause = self.str()?;
+                if !cause.is_empty() {
+                    err.cause = Some(Box::new(napi::Error::new(Status::GenericFailure, cause)));
+                }
+                Response::Failed(err)
+            }
+            CLOSED => Response::Closed,
+            _ => return None,
+        };
+        self.done().then_some(response)
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Bulk helpers that generate their SQL natively.
+//
+// `deleteMany` splits the key list into `IN` lists of at most `chunkSize`
+// parameters, so neither the statement text nor a single transaction's write
+// set grows with the input. Each chunk runs as one auto-committed statement,
+// which is its own transaction; explicit transactions can't be used because
+// their statements don't evaluate IN lists.
+
+use std::time::Instant;
+
+use stoolap::api::Database;
+use stoolap::{CachedPlanRef, ParamVec};
+
+use crate::changes::ChangeHubRef;
+use crate::interrupt::Ticket;
+use crate::plan_cache::PlanCache;
+use crate::policy::{check_statement, PolicyRef};
+use crate::profile::ProfilerRef;
+use crate::readers::Readers;
+use crate::retry::{retry_warning, RetryPolicy};
+use crate::sql::{primary_key, quote_ident};
+use crate::tasks::{RunOutcome, TaskParams};
+
+/// Default number of keys per `IN` list.
+pub const DEFAULT_CHUNK_SIZE: u32 = 500;
+
+/// Options for `deleteMany()` / `deleteManySync()`.
+#[napi(object, object_to_js = false)]
+pub struct DeleteManyOptions {
+    /// Column the keys are matched against. Defaults to the table's
+    /// single-column primary key.
+    pub key_column: Option<String>,
+    /// Keys per DELETE statement (default 500).
+    pub chunk_size: Option<u32>,
+}
+
+/// A resolved `deleteMany` call.
+pub struct DeleteMany {
+    table: String,
+    key_column: Option<String>,
+    chunk_size: usize,
+    keys: ParamVec,
+}
+
+impl DeleteMany {
+    pub fn new(
+        table: String,
+        keys: ParamVec,
+        options: Option<DeleteManyOptions>,
+    ) -> napi::Result<Self> {
+        let (key_column, chunk_size) = match options {
+            Some(o) => (o.key_column, o.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE)),
+            None => (None, DEFAULT_CHUNK_SIZE),
+        };
+        if chunk_size == 0 {
+            return Err(napi::Error::from_reason("chunkSize must be at least 1"));
+        }
+        Ok(Self {
+            table,
+            key_column,
+            chunk_size: chunk_size as usize,
+            keys,
+        })
+    }
+
+    /// Delete every row whose key is in the list. Reports the number of rows
+    /// deleted. Chunks committed before a failing chunk stay deleted.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run(
+        self,
+        db: &Database,
+        changes: &ChangeHubRef,
+        policy: &PolicyRef,
+        profiler: &ProfilerRef,
+        cache: &PlanCache,
+        readers: &Readers,
+        retry: &RetryPolicy,
+        ticket: &Ticket,
+    ) -> napi::Result<RunOutcome> {
+        let timer = Instant::now();
+        if self.keys.is_empty() {
+            return Ok(RunOutcome::new(timer, 0));
+        }
+        let key_column = match self.key_column {
+            Some(column) => column,
+            None => primary_key(db, &self.table)?
+                .map(|pk| pk.name)
+                .ok_or_else(|| {
+                    napi::Error::from_reason(format!(
+                        "Table '{}' has no single-column primary key; pass options.keyColumn",
+                        self.table
+                    ))
+                })?,
+        };
+        let prefix = format!(
+            "DELETE FROM {} WHERE {} IN (",
+            quote_ident(&self.table),
+            quote_ident(&key_column)
+        );
+
+        // Plans by IN-list length: full chunks and the remainder.
+        let mut plans: Vec<(usize, String, CachedPlanRef)> = Vec::with_capacity(2);
+        let mut total = 0i64;
+        let mut retried = 0;
+        for chunk in self.keys.chunks(self.chunk_size) {
+            ticket.check()?;
+            let index = match plans.iter().position(|(len, _, _)| *len == chunk.len()) {
+                Some(index) => index,
+                None => {
+                    let sql = in_list_sql(&prefix, chunk.len());
+                    let plan = cache.plan(db, &sql)?;
+                    check_statement(policy, &plan.statement)?;
+                    plans.push((chunk.len(), sql, plan));
+                    plans.len() - 1
+                }
+            };
+            let (_, sql, plan) = &plans[index];
+
+            let params: ParamVec = chunk.iter().cloned().collect();
+            let started = profiler.start_positional(&params);
+            let (executed, retries) = retry.run_counted(ticket, params, |params| {
+                readers.write(|| {
+                    changes.execute_on_db(TaskParams::Positional(params), sql, Some(plan))
+                })
+            })?;
+            profiler.finish_write(started, sql, executed.changes, Some(plan));
+            total += executed.changes;
+            retried += retries;
+        }
+        let mut outcome = RunOutcome::new(timer, total);
+        outcome.warnings.extend(retry_warning(retried));
+        Ok(outcome)
+    }
+}
+
+fn in_list_sql(prefix: &str, len: usize) -> String {
+    let placeholders: Vec<String> = (1..=len).map(|i| format!("${i}")).collect();
+    format!("{prefix}{})", placeholders.join(", "))
+}
@@ -0,0 +1,72 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `asyncStackTraces` open option.
+//
+// An async call's work runs on another thread, and its promise is rejected
+// from a callback with nothing of the caller on the stack, so the error's
+// stack trace ends at the binding. With the option, every async call of the
+// database captures the JS stack when it returns its promise, and the frames
+// are appended to the message of an error its work fails with; as the stack
+// starts with the message, it then reads like that of a synchronous call.
+// Capturing costs about as much as creating an `Error`, which is why it is
+// off by default. A serialized write waiting for its tables keeps the stack
+// of the call that queued it.
+
+use napi::bindgen_prelude::JsObjectValue;
+use napi::Env;
+
+use crate::error::with_reason;
+
+/// Where an async call was made, for `asyncStackTraces`.
+#[derive(Default)]
+pub enum CallSite {
+    /// Stacks aren't captured.
+    #[default]
+    Off,
+    /// To be captured when the call's promise is created.
+    Wanted,
+    Captured(String),
+}
+
+impl CallSite {
+    /// Capture the current JS stack if it is wanted and not captured yet.
+    pub fn capture(&mut self, env: &Env) -> napi::Result<()> {
+        if let CallSite::Wanted = self {
+            *self = CallSite::Captured(js_stack(env)?);
+        }
+        Ok(())
+    }
+
+    /// `err` with the call site's frames after its message.
+    pub fn append_to(&self, err: napi::Error) -> napi::Error {
+        match self {
+            CallSite::Captured(stack) if !stack.is_empty() => {
+                let reason = format!("{}\n{stack}", err.reason);
+                with_reason(err, reason)
+            }
+            _ => err,
+        }
+    }
+}
+
+/// The JS stack at the current call, without the `Error` line.
+pub fn js_stack(env: &Env) -> napi::Result<String> {
+    let error = env.create_error(napi::Error::from_reason(""))?;
+    let stack: String = error.get_named_property("stack")?;
+    Ok(match stack.split_once('\n') {
+        Some((_, frames)) => frames.to_string(),
+        None => String::new(),
+    })
+}
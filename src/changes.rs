@@ -0,0 +1,428 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Row change notifications for `db.on('change')`.
+//
+// The engine has no commit hooks, so changes are captured in the binding:
+// while a listener is registered, INSERT/UPDATE/DELETE statements run through
+// the query path with `RETURNING <primary key>` appended, and the returned
+// keys are delivered to listeners once the statement's transaction commits.
+// Auto-committed INSERTs take the same path without listeners, since the last
+// returned key is what `RunResult.lastInsertRowId` reports.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+
+use stoolap::api::Transaction as ApiTransaction;
+use stoolap::parser::ast::{Expression, Statement};
+use stoolap::storage::Engine;
+use stoolap::{CachedPlanRef, ParamVec, Value};
+
+use crate::analyze::AutoAnalyzeRef;
+use crate::consistency::TableVersionsRef;
+use crate::error::{from_core, to_napi};
+use crate::interrupt::Ticket;
+use crate::memory::MemoryBudgetRef;
+use crate::paging::Page;
+use crate::sql::{is_insert, parse_single, quote_ident, where_clause, with_returning};
+use crate::tasks::{collect_all_rows, CollectedRows, DbHandle, ExecOutput, TaskParams};
+
+/// A committed row change.
+#[napi(object, object_from_js = false, use_nullable = true)]
+#[derive(Clone)]
+pub struct ChangeEvent {
+    #[napi(ts_type = "'insert' | 'update' | 'delete'")]
+    pub op: String,
+    pub table: String,
+    /// Integer primary key of the changed row, or null when the table has none.
+    pub row_id: Option<i64>,
+}
+
+impl ChangeEvent {
+    pub fn new(op: &str, table: &str, key: Option<&Value>) -> Self {
+        let row_id = match key {
+            Some(Value::Integer(id)) => Some(*id),
+            _ => None,
+        };
+        Self {
+            op: op.to_string(),
+            table: table.to_string(),
+            row_id,
+        }
+    }
+}
+
+pub type ChangeListener =
+    ThreadsafeFunction<ChangeEvent, Unknown<'static>, ChangeEvent, Status, false, true>;
+
+/// What an auto-committed statement changed.
+pub struct Executed {
+    pub changes: i64,
+    /// Integer primary key of the last row an INSERT added.
+    pub last_insert_row_id: Option<i64>,
+}
+
+/// Events recorded inside an open transaction, delivered on commit.
+pub type PendingChanges = Arc<Mutex<Vec<ChangeEvent>>>;
+
+pub type ChangeHubRef = Arc<ChangeHub>;
+
+/// Listener registry shared by a database and everything created from it.
+pub struct ChangeHub {
+    db: DbHandle,
+    active: AtomicBool,
+    listeners: Mutex<Vec<ChangeListener>>,
+    analyze: Option<AutoAnalyzeRef>,
+    versions: Option<TableVersionsRef>,
+}
+
+/// A DML statement resolved for capture.
+struct Dml {
+    stmt: Statement,
+    op: &'static str,
+    table: String,
+    /// Schema position and name of the integer primary key.
+    key: Option<(usize, String)>,
+    /// Already has RETURNING or ON DUPLICATE KEY UPDATE: run as-is and
+    /// report `changes` rows without ids.
+    as_is: bool,
+}
+
+impl Dml {
+    /// Expression selecting the changed row's key.
+    fn key_expr(&self) -> String {
+        match self.key {
+            Some((_, ref name)) => quote_ident(name),
+            None => "NULL".to_string(),
+        }
+    }
+}
+
+impl ChangeHub {
+    pub fn new(
+        db: DbHandle,
+        analyze: Option<AutoAnalyzeRef>,
+        versions: Option<TableVersionsRef>,
+    ) -> Self {
+        Self {
+            db,
+            active: AtomicBool::new(analyze.is_some() || versions.is_some()),
+            listeners: Mutex::new(Vec::new()),
+            analyze,
+            versions,
+        }
+    }
+
+    pub fn subscribe(&self, listener: ChangeListener) {
+        lock(&self.listeners).push(listener);
+        self.active.store(true, Ordering::Release);
+    }
+
+    /// Whether any listener is registered or `autoAnalyze` or
+    /// `consistencyTokens` is on; capture is skipped otherwise.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Queue events for delivery on the JS event loop.
+    pub fn emit(&self, events: Vec<ChangeEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        if let Some(ref analyze) = self.analyze {
+            analyze.record(&self.db, &events);
+        }
+        if let Some(ref versions) = self.versions {
+            versions.record(&events);
+        }
+        for listener in lock(&self.listeners).iter() {
+            for event in &events {
+                listener.call(event.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+    }
+
+    /// The write counters behind consistency tokens, with the
+    /// `consistencyTokens` open option.
+    pub fn versions(&self) -> Option<&TableVersionsRef> {
+        self.versions.as_ref()
+    }
+
+    /// The consistency token of a query of `sql`, with `plan` when there is
+    /// one, issued now; `None` without `consistencyTokens` or when `sql`
+    /// doesn't parse.
+    pub fn token(&self, sql: &str, plan: Option<&CachedPlanRef>) -> Option<String> {
+        let versions = self.versions.as_ref()?;
+        match plan {
+            Some(plan) => Some(versions.token(&self.db, &plan.statement)),
+            None => parse_single(sql)
+                .ok()
+                .map(|stmt| versions.token(&self.db, &stmt)),
+        }
+    }
+
+    /// Execute an auto-committed statement, with `plan` when there is one,
+    /// and emit its changes.
+    pub fn execute_on_db(
+        &self,
+        params: TaskParams,
+        sql: &str,
+        plan: Option<&CachedPlanRef>,
+    ) -> napi::Result<Executed> {
+        let active = self.is_active();
+        let insert = match plan {
+            Some(plan) => matches!(*plan.statement, Statement::Insert(_)),
+            None => is_insert(sql),
+        };
+        let execute = |params: TaskParams| match plan {
+            Some(plan) => params.execute_plan_on_db(&self.db, plan).map_err(from_core),
+            None => params.execute_on_db(&self.db, sql).map_err(from_core),
+        };
+        let Some(dml) = (active || insert).then(|| self.capture(sql)).flatten() else {
+            return Ok(Executed {
+                changes: execute(params)?,
+                last_insert_row_id: None,
+            });
+        };
+        let mut events = Vec::new();
+        // RETURNING NULL yields no rows on tables without a primary key,
+        // so those fall back to counting.
+        let executed = if dml.as_is || dml.key.is_none() {
+            let changes = execute(params)?;
+            if active {
+                collect_unkeyed(changes, &dml, &mut events);
+            }
+            Executed {
+                changes,
+                last_insert_row_id: None,
+            }
+        } else {
+            let rows = params
+                .query_on_db(&self.db, &with_returning(sql, &dml.key_expr()))
+                .map_err(from_core)?;
+            let (changes, last_key) = collect_keyed(rows, &dml, active.then_some(&mut events));
+            Executed {
+                changes,
+                last_insert_row_id: last_key.filter(|_| insert),
+            }
+        };
+        self.emit(events);
+        Ok(executed)
+    }
+
+    /// Execute an auto-committed DML statement with RETURNING, with `plan`
+    /// when there is one, and emit its changes. Each returned row is one
+    /// changed row.
+    pub fn execute_returning(
+        &self,
+        params: TaskParams,
+        sql: &str,
+        plan: Option<&CachedPlanRef>,
+        ticket: &Ticket,
+        memory: &MemoryBudgetRef,
+    ) -> napi::Result<CollectedRows> {
+        let rows = match plan {
+            Some(plan) => params.query_plan_on_db(&self.db, plan).map_err(from_core)?,
+            None => params.query_on_db(&self.db, sql).map_err(from_core)?,
+        };
+        let rows = collect_all_rows(rows, ticket, memory, None, Page::default())?;
+        if let Some(dml) = self.is_active().then(|| self.capture(sql)).flatten() {
+            let mut events = Vec::new();
+            collect_unkeyed(rows.row_count() as i64, &dml, &mut events);
+            self.emit(events);
+        }
+        Ok(rows)
+    }
+
+    /// Execute one statement of a multi-statement `exec()` batch. Statements
+    /// that return columns, such as SELECT, SHOW or DML with RETURNING, return
+    /// their rows; the others report the number of rows they changed.
+    pub fn exec(
+        &self,
+        sql: &str,
+        ticket: &Ticket,
+        memory: &MemoryBudgetRef,
+    ) -> napi::Result<ExecOutput> {
+        let dml = self.is_active().then(|| self.capture(sql)).flatten();
+        if dml.as_ref().is_some_and(|dml| !dml.as_is) {
+            return self
+                .execute_on_db(TaskParams::Positional(ParamVec::new()), sql, None)
+                .map(|executed| ExecOutput::Changes(executed.changes));
+        }
+        let rows = self.db.query(sql, ()).map_err(to_napi)?;
+        let output = if rows.columns().is_empty() {
+            ExecOutput::Changes(rows.rows_affected())
+        } else {
+            ExecOutput::Rows(collect_all_rows(
+                rows,
+                ticket,
+                memory,
+                None,
+                Page::default(),
+            )?)
+        };
+        if let Some(dml) = dml {
+            let changes = match output {
+                ExecOutput::Changes(changes) => changes,
+                ExecOutput::Rows(ref rows) => rows.row_count() as i64,
+            };
+            let mut events = Vec::new();
+            collect_unkeyed(changes, &dml, &mut events);
+            self.emit(events);
+        }
+        Ok(output)
+    }
+
+    /// Execute a statement inside a transaction, recording its changes in
+    /// `pending` until the transaction commits.
+    ///
+    /// Transactions don't support RETURNING, so UPDATE/DELETE keys are read
+    /// with a SELECT over the same WHERE clause first, and INSERT keys come
+    /// from literal or parameter values in the VALUES rows.
+    pub fn execute_on_tx(
+        &self,
+        tx: &mut ApiTransaction,
+        params: TaskParams,
+        sql: &str,
+        pending: &mut Vec<ChangeEvent>,
+    ) -> napi::Result<i64> {
+        let (Some(dml), TaskParams::Positional(values)) = (self.capture(sql), &params) else {
+            return params.execute_on_tx(tx, sql).map_err(from_core);
+        };
+        if dml.as_is {
+            let changes = params.execute_on_tx(tx, sql).map_err(from_core)?;
+            collect_unkeyed(changes, &dml, pending);
+            return Ok(changes);
+        }
+
+        match dml.stmt {
+            Statement::Insert(ref insert) => {
+                let values = values.clone();
+                let changes = params.execute_on_tx(tx, sql).map_err(from_core)?;
+                // The key's index in each VALUES row: its schema position,
+                // or its place in an explicit column list.
+                let index = dml.key.as_ref().and_then(|(pos, name)| {
+                    if insert.columns.is_empty() {
+                        Some(*pos)
+                    } else {
+                        insert
+                            .columns
+                            .iter()
+                            .position(|c| c.value_lower.eq_ignore_ascii_case(name))
+                    }
+                });
+                for row in &insert.values {
+                    let key = index.and_then(|i| literal_value(row.get(i)?, &values));
+                    pending.push(ChangeEvent::new(dml.op, &dml.table, key.as_ref()));
+                }
+                Ok(changes)
+            }
+            _ => {
+                let mut select =
+                    format!("SELECT {} FROM {}", dml.key_expr(), quote_ident(&dml.table));
+                if let Some(filter) = where_clause(sql) {
+                    select.push_str(" WHERE");
+                    select.push_str(&filter);
+                }
+                let rows = tx.query(&select, values.clone()).map_err(to_napi)?;
+                let mut events = Vec::new();
+                collect_keyed(rows, &dml, Some(&mut events));
+                let changes = params.execute_on_tx(tx, sql).map_err(from_core)?;
+                pending.append(&mut events);
+                Ok(changes)
+            }
+        }
+    }
+
+    /// Resolve `sql` for capture. Anything that is not plain DML, or that
+    /// fails to parse or resolve, runs unchanged so the engine reports errors.
+    fn capture(&self, sql: &str) -> Option<Dml> {
+        let stmt = parse_single(sql).ok()?;
+        let (op, table, as_is) = match &stmt {
+            Statement::Insert(s) => (
+                "insert",
+                &s.table_name,
+                s.on_duplicate || !s.returning.is_empty(),
+            ),
+            Statement::Update(s) => ("update", &s.table_name, !s.returning.is_empty()),
+            Statement::Delete(s) => ("delete", &s.table_name, !s.returning.is_empty()),
+            _ => return None,
+        };
+        let table = table.value_lower.to_string();
+        let key = self.integer_key(&table).ok()?;
+        Some(Dml {
+            stmt,
+            op,
+            table,
+            key,
+            as_is,
+        })
+    }
+
+    /// Schema position and name of the table's single-column integer primary
+    /// key, read from the engine's schema so it is never stale after DDL.
+    fn integer_key(&self, table: &str) -> napi::Result<Option<(usize, String)>> {
+        let schema = self.db.engine().get_table_schema(table).map_err(to_napi)?;
+        if schema.primary_key_indices().len() != 1 {
+            return Ok(None);
+        }
+        Ok(schema
+            .pk_column_index()
+            .map(|i| (i, schema.columns[i].name.clone())))
+    }
+}
+
+/// Value of a literal or bound parameter expression.
+fn literal_value(expr: &Expression, params: &ParamVec) -> Option<Value> {
+    match expr {
+        Expression::IntegerLiteral(lit) => Some(Value::Integer(lit.value)),
+        Expression::Parameter(p) if p.index > 0 => params.get(p.index - 1).cloned(),
+        _ => None,
+    }
+}
+
+/// Count the returned keys, recording an event for each when `events` is
+/// given. Also returns the last integer key.
+fn collect_keyed(
+    mut rows: stoolap::Rows,
+    dml: &Dml,
+    mut events: Option<&mut Vec<ChangeEvent>>,
+) -> (i64, Option<i64>) {
+    let mut changes = 0;
+    let mut last_key = None;
+    while rows.advance() {
+        let key = rows.current_row().get(0);
+        if let Some(events) = events.as_mut() {
+            events.push(ChangeEvent::new(dml.op, &dml.table, key));
+        }
+        last_key = match key {
+            Some(Value::Integer(id)) => Some(*id),
+            _ => None,
+        };
+        changes += 1;
+    }
+    (changes, last_key)
+}
+
+fn collect_unkeyed(changes: i64, dml: &Dml, events: &mut Vec<ChangeEvent>) {
+    events.extend((0..changes).map(|_| ChangeEvent::new(dml.op, &dml.table, None)));
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
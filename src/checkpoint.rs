@@ -0,0 +1,229 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Named checkpoints.
+//
+// A checkpoint is only a transaction-id marker plus the table list at that
+// moment; no data is copied. Restoring reads each table AS OF the marker
+// from the MVCC version history and rewrites the live rows in a single
+// transaction, so it is bounded by the `deleted_row_retention` window.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use stoolap::api::{Database, Transaction};
+use stoolap::parser::ast::Statement;
+use stoolap::parser::Parser;
+use stoolap::{Rows, Value};
+
+use crate::changes::ChangeEvent;
+use crate::error::to_napi;
+use crate::sql::{primary_key, quote_ident};
+
+pub type CheckpointMap = Arc<Mutex<HashMap<String, Checkpoint>>>;
+
+#[derive(Clone)]
+pub struct Checkpoint {
+    txn_id: i64,
+    tables: Vec<String>,
+}
+
+/// Capture the current transaction-id marker and table list.
+pub fn create(db: &Database) -> napi::Result<Checkpoint> {
+    // Every transaction committed so far has an id below the next one handed out.
+    let mut tx = db.begin().map_err(to_napi)?;
+    let txn_id = tx.id();
+    tx.rollback().map_err(to_napi)?;
+
+    let mut rows = db.query("SHOW TABLES", ()).map_err(to_napi)?;
+    let mut tables = Vec::new();
+    while rows.advance() {
+        if let Some(Value::Text(name)) = rows.current_row().get(0) {
+            tables.push(name.to_string());
+        }
+    }
+    Ok(Checkpoint { txn_id, tables })
+}
+
+/// Roll every checkpointed table back to its contents at the marker.
+/// Returns the row changes made, for `db.on('change')` listeners.
+pub fn restore(db: &Database, checkpoint: &Checkpoint) -> napi::Result<Vec<ChangeEvent>> {
+    // Historical reads must run outside the restoring transaction so they
+    // are not shadowed by its own writes.
+    let mut snapshots = Vec::with_capacity(checkpoint.tables.len());
+    for table in &checkpoint.tables {
+        if !db.table_exists(table).map_err(to_napi)? {
+            return Err(napi::Error::from_reason(format!(
+                "Table '{table}' was dropped after the checkpoint was created"
+            )));
+        }
+        let sql = format!(
+            "SELECT * FROM {} AS OF TRANSACTION {}",
+            quote_ident(table),
+            checkpoint.txn_id
+        );
+        let rows = db.query(&sql, ()).map_err(to_napi)?;
+        let columns = rows.columns().to_vec();
+        let data = collect_rows(rows);
+        let pk =
+            primary_key(db, table)?.and_then(|key| columns.iter().position(|c| *c == key.name));
+        snapshots.push(TableSnapshot {
+            table,
+            columns,
+            pk,
+            rows: data,
+        });
+    }
+
+    let mut tx = db.begin().map_err(to_napi)?;
+    let mut events = Vec::new();
+    for snapshot in snapshots {
+        let column_list: Vec<String> = snapshot.columns.iter().map(|c| quote_ident(c)).collect();
+        let current = tx
+            .query(
+                &format!(
+                    "SELECT {} FROM {}",
+                    column_list.join(", "),
+                    quote_ident(snapshot.table)
+                ),
+                (),
+            )
+            .map_err(to_napi)?;
+        let current = collect_rows(current);
+
+        match snapshot.pk {
+            Some(pk) => restore_keyed(&mut tx, &snapshot, current, pk, &mut events)?,
+            None if current == snapshot.rows => {}
+            None => {
+                let deleted = tx
+                    .execute(&format!("DELETE FROM {}", quote_ident(snapshot.table)), ())
+                    .map_err(to_napi)?;
+                events
+                    .extend((0..deleted).map(|_| ChangeEvent::new("delete", snapshot.table, None)));
+                if !snapshot.rows.is_empty() {
+                    let insert = parse(&insert_sql(&snapshot))?;
+                    for row in snapshot.rows {
+                        tx.execute_prepared(&insert, row).map_err(to_napi)?;
+                        events.push(ChangeEvent::new("insert", snapshot.table, None));
+                    }
+                }
+            }
+        }
+    }
+    tx.commit().map_err(to_napi)?;
+    Ok(events)
+}
+
+struct TableSnapshot<'a> {
+    table: &'a str,
+    columns: Vec<String>,
+    pk: Option<usize>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// Restore a table with a primary key row by row: a key cannot be deleted
+/// and re-inserted within one transaction, so surviving keys are updated
+/// in place and only vanished or new keys are deleted or inserted. Rows
+/// that already match are left alone so restores don't grow version chains.
+fn restore_keyed(
+    tx: &mut Transaction,
+    snapshot: &TableSnapshot,
+    current: Vec<Vec<Value>>,
+    pk: usize,
+    events: &mut Vec<ChangeEvent>,
+) -> napi::Result<()> {
+    let table = quote_ident(snapshot.table);
+    let pk_col = quote_ident(&snapshot.columns[pk]);
+
+    let current: HashMap<Value, Vec<Value>> = current
+        .into_iter()
+        .map(|row| (row[pk].clone(), row))
+        .collect();
+    let wanted: HashSet<&Value> = snapshot.rows.iter().map(|r| &r[pk]).collect();
+
+    let delete = parse(&format!("DELETE FROM {table} WHERE {pk_col} = $1"))?;
+    for key in current.keys().filter(|k| !wanted.contains(k)) {
+        tx.execute_prepared(&delete, vec![key.clone()])
+            .map_err(to_napi)?;
+        events.push(ChangeEvent::new("delete", snapshot.table, Some(key)));
+    }
+
+    let insert = parse(&insert_sql(snapshot))?;
+    let assignments: Vec<String> = snapshot
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != pk)
+        .map(|(i, c)| format!("{} = ${}", quote_ident(c), i + 1))
+        .collect();
+    let update = if assignments.is_empty() {
+        None
+    } else {
+        Some(parse(&format!(
+            "UPDATE {table} SET {} WHERE {pk_col} = ${}",
+            assignments.join(", "),
+            pk + 1
+        ))?)
+    };
+
+    for row in &snapshot.rows {
+        match current.get(&row[pk]) {
+            None => {
+                tx.execute_prepared(&insert, row.clone()).map_err(to_napi)?;
+                events.push(ChangeEvent::new("insert", snapshot.table, Some(&row[pk])));
+            }
+            Some(existing) if existing != row => {
+                if let Some(ref update) = update {
+                    tx.execute_prepared(update, row.clone()).map_err(to_napi)?;
+                    events.push(ChangeEvent::new("update", snapshot.table, Some(&row[pk])));
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn collect_rows(mut rows: Rows) -> Vec<Vec<Value>> {
+    let mut data = Vec::new();
+    while rows.advance() {
+        data.push(rows.current_row().as_slice().to_vec());
+    }
+    data
+}
+
+fn insert_sql(snapshot: &TableSnapshot) -> String {
+    let column_list: Vec<String> = snapshot.columns.iter().map(|c| quote_ident(c)).collect();
+    let placeholders: Vec<String> = (1..=snapshot.columns.len())
+        .map(|i| format!("${i}"))
+        .collect();
+    format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote_ident(snapshot.table),
+        column_list.join(", "),
+        placeholders.join(", ")
+    )
+}
+
+fn parse(sql: &str) -> napi::Result<Statement> {
+    let mut parser = Parser::new(sql);
+    let program = parser
+        .parse_program()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    program
+        .statements
+        .into_iter()
+        .next()
+        .ok_or_else(|| napi::Error::from_reason("No SQL statement found"))
+}
@@ -0,0 +1,149 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Chunked delivery for `queryChunks` and `dump`.
+//
+// The worker thread reads `chunkSize` rows at a time and hands each chunk to
+// the JS callback through a threadsafe function; `dump` sends SQL text the
+// same way. It reads the next chunk while
+// the callback handles the current one, but doesn't send it until the
+// callback has returned, so at most two chunks are held at once however large
+// the result is. The worker only ever waits for the JS thread to run a
+// callback, never for anything the callback starts, so a callback that calls
+// back into the database can't stall it.
+
+use std::sync::mpsc::{channel, Receiver};
+
+use napi::bindgen_prelude::{Function, JsValuesTupleIntoVec, Unknown};
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::Status;
+
+use crate::result_filter;
+use crate::shape::RowShape;
+use crate::tasks::{collected_rows_to_shape, CollectedRows, DbHandle, RawJsValue};
+use crate::types::TypesRef;
+
+/// Default number of rows per chunk.
+pub const DEFAULT_CHUNK_SIZE: u32 = 1000;
+
+/// Options for `queryChunks()`.
+#[napi(object, object_to_js = false)]
+pub struct QueryChunksOptions {
+    /// Rows per callback (default 1000).
+    pub chunk_size: Option<u32>,
+}
+
+/// The `chunkSize` option, checked.
+pub fn chunk_size(options: Option<&QueryChunksOptions>) -> napi::Result<usize> {
+    match options
+        .and_then(|o| o.chunk_size)
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+    {
+        0 => Err(napi::Error::from_reason("chunkSize must be at least 1")),
+        n => Ok(n as usize),
+    }
+}
+
+/// One chunk on its way to the callback.
+pub struct Chunk {
+    rows: CollectedRows,
+    shape: RowShape,
+}
+
+impl Chunk {
+    pub fn new(rows: CollectedRows, shape: RowShape) -> Self {
+        Self { rows, shape }
+    }
+}
+
+/// A JS callback the worker thread calls with `T`, passed to JS as `V`.
+pub type Listener<T, V> = ThreadsafeFunction<T, Unknown<'static>, V, Status, false>;
+
+/// The `onChunk` callback of `queryChunks()`.
+pub type ChunkListener = Listener<Chunk, RawJsValue>;
+
+/// Wrap `onChunk` so the worker thread can call it with the rows of `sql`,
+/// revived by `types`.
+pub fn listener(
+    on_chunk: Function<Unknown<'static>, Unknown<'static>>,
+    types: TypesRef,
+    db: DbHandle,
+    sql: String,
+) -> napi::Result<ChunkListener> {
+    on_chunk
+        .build_threadsafe_function::<Chunk>()
+        .callee_handled::<false>()
+        .build_callback(move |ctx| {
+            let Chunk { rows, shape } = ctx.value;
+            let _reviving = types.reviving();
+            let _statement = result_filter::statement(&db, &sql, None);
+            Ok(RawJsValue(collected_rows_to_shape(
+                ctx.env.raw(),
+                rows,
+                &shape,
+            )?))
+        })
+}
+
+/// Chunks sent to a callback, one at a time.
+pub struct ChunkStream<T: 'static, V: 'static + JsValuesTupleIntoVec> {
+    listener: Listener<T, V>,
+    /// Reports the return of the callback for the chunk last sent, if it
+    /// hasn't been waited for.
+    pending: Option<Receiver<napi::Result<()>>>,
+}
+
+impl<T: 'static, V: 'static + JsValuesTupleIntoVec> ChunkStream<T, V> {
+    pub fn new(listener: Listener<T, V>) -> Self {
+        Self {
+            listener,
+            pending: None,
+        }
+    }
+
+    /// Send `chunk` once the previous chunk's callback has returned. Fails
+    /// with what the callback threw, if it did.
+    pub fn send(&mut self, chunk: T) -> napi::Result<()> {
+        self.wait()?;
+        let (done, returned) = channel();
+        let status = self.listener.call_with_return_value(
+            chunk,
+            ThreadsafeFunctionCallMode::Blocking,
+            move |result, _env| {
+                let _ = done.send(result.map(|_| ()));
+                Ok(())
+            },
+        );
+        if status != Status::Ok {
+            return Err(napi::Error::new(
+                status,
+                "Chunk callback is no longer callable",
+            ));
+        }
+        self.pending = Some(returned);
+        Ok(())
+    }
+
+    /// Wait for the last chunk's callback to return.
+    pub fn wait(&mut self) -> napi::Result<()> {
+        let Some(returned) = self.pending.take() else {
+            return Ok(());
+        };
+        returned.recv().unwrap_or_else(|_| {
+            Err(napi::Error::from_reason(
+                "Chunk callback was dropped before it ran",
+            ))
+        })
+    }
+}
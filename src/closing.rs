@@ -0,0 +1,230 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.close({ mode, timeoutMs })`.
+//
+// Closing marks the handle closed on the JS thread, and async calls issued
+// after that reject with a `DatabaseClosed` error without running. The close
+// task then waits, on its thread, for the calls issued before it: in `drain`
+// mode (the default) they finish, and in `abort` mode they are interrupted
+// (see interrupt.rs), so those not started yet reject before they start and
+// running ones at their next check, with the same error. Once `timeoutMs`
+// passes from the `close()` call a drain aborts what is left, from a thread
+// of its own in case the close task is still queued behind those calls, and
+// either mode closes the engine without waiting any longer. The check runs where the task monitor sees a
+// call start (see monitor.rs), and the wait reads the monitor's counters.
+// A handle with its own worker thread runs the close task after every call
+// issued before it, so it has nothing to wait for.
+
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::{JsObjectValue, JsValue};
+use napi::{Env, Status};
+
+use crate::interrupt::InterruptRef;
+use crate::monitor::TaskMonitorRef;
+
+/// Message of the error calls reject with once their database closes.
+const CLOSED: &str = "Database is closed";
+
+/// How often the close task looks at the counters while it waits.
+const POLL: Duration = Duration::from_millis(1);
+
+const OPEN: u8 = 0;
+const DRAINING: u8 = 1;
+const ABORTED: u8 = 2;
+
+pub type ClosingRef = Arc<Closing>;
+
+/// Options for `db.close()`.
+#[napi(object, object_to_js = false)]
+pub struct CloseOptions {
+    /// `drain` lets pending calls finish first, `abort` rejects them
+    /// (default `drain`).
+    #[napi(ts_type = "'drain' | 'abort'")]
+    pub mode: Option<String>,
+    /// Most milliseconds to wait for pending calls; no limit by default.
+    pub timeout_ms: Option<f64>,
+}
+
+/// Whether a handle, its statements, transactions and pipelines are closed.
+#[derive(Default)]
+pub struct Closing {
+    state: AtomicU8,
+    /// Close tasks queued or running, which the wait doesn't count.
+    closers: AtomicU32,
+}
+
+impl Closing {
+    pub fn is_closed(&self) -> bool {
+        self.state.load(Ordering::Acquire) != OPEN
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.state.load(Ordering::Acquire) == ABORTED
+    }
+
+    /// Reject calls issued from now on.
+    pub fn close(&self) {
+        let _ = self
+            .state
+            .compare_exchange(OPEN, DRAINING, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// Reject the calls issued so far too.
+    pub fn abort(&self, interrupts: &InterruptRef) {
+        self.state.store(ABORTED, Ordering::Release);
+        interrupts.interrupt();
+    }
+
+    /// Whether a call to `method` rejects before it runs; `issued_closed`
+    /// is whether the handle was closed when it was issued.
+    pub fn rejects(&self, method: &str, issued_closed: bool) -> bool {
+        method != "close" && (issued_closed || self.is_aborted())
+    }
+
+    /// Whether `err` is a running call stopped by an abort.
+    pub fn stopped(&self, err: &napi::Error) -> bool {
+        err.status == Status::Cancelled && self.is_aborted()
+    }
+
+    /// A close task for the handle, waiting for its calls unless `wait` is
+    /// false.
+    pub fn closer(
+        self: &Arc<Self>,
+        options: Option<CloseOptions>,
+        monitor: &TaskMonitorRef,
+        interrupts: &InterruptRef,
+        wait: bool,
+    ) -> napi::Result<Closer> {
+        let (mode, timeout_ms) = match options {
+            Some(o) => (o.mode, o.timeout_ms),
+            None => (None, None),
+        };
+        let abort = match mode.as_deref() {
+            None | Some("drain") => false,
+            Some("abort") => true,
+            Some(_) => return Err(napi::Error::from_reason("mode must be 'drain' or 'abort'")),
+        };
+        let timeout = match timeout_ms {
+            None => None,
+            Some(ms) if ms >= 0.0 && ms.is_finite() => Some(Duration::from_secs_f64(ms / 1000.0)),
+            Some(_) => {
+                return Err(napi::Error::from_reason(
+                    "timeoutMs must be a non-negative number",
+                ))
+            }
+        };
+        // The timeout runs from the call, not from when the close task gets
+        // a thread, which may be after the calls it waits for.
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self.close();
+        if abort {
+            self.abort(interrupts);
+        }
+        let watchdog = match deadline {
+            Some(deadline) if wait && !abort => self.watch(deadline, interrupts),
+            _ => None,
+        };
+        self.closers.fetch_add(1, Ordering::AcqRel);
+        Ok(Closer {
+            closing: Arc::clone(self),
+            monitor: Arc::clone(monitor),
+            interrupts: Arc::clone(interrupts),
+            wait,
+            deadline,
+            _watchdog: watchdog,
+        })
+    }
+
+    /// Abort at `deadline` unless the returned sender is dropped first, on a
+    /// thread of its own, so calls still queued reject then even while the
+    /// close task waits for a thread. Without one, the close task aborts
+    /// once it runs.
+    fn watch(self: &Arc<Self>, deadline: Instant, interrupts: &InterruptRef) -> Option<Sender<()>> {
+        let (done, closed) = mpsc::channel::<()>();
+        let closing = Arc::clone(self);
+        let interrupts = Arc::clone(interrupts);
+        std::thread::Builder::new()
+            .name("stoolap-close-timeout".to_string())
+            .spawn(move || {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if let Err(RecvTimeoutError::Timeout) = closed.recv_timeout(left) {
+                    closing.abort(&interrupts);
+                }
+            })
+            .ok()?;
+        Some(done)
+    }
+}
+
+/// The close task's part of closing.
+pub struct Closer {
+    closing: ClosingRef,
+    monitor: TaskMonitorRef,
+    interrupts: InterruptRef,
+    wait: bool,
+    deadline: Option<Instant>,
+    /// Dropped with the closer, which stops the timeout thread.
+    _watchdog: Option<Sender<()>>,
+}
+
+impl Closer {
+    /// Wait until the calls issued before the close are done, or abort them
+    /// once the timeout passes.
+    pub fn wait(&self) {
+        if !self.wait {
+            return;
+        }
+        let deadline = self.deadline;
+        loop {
+            let stats = self.monitor.stats();
+            let closers = self.closing.closers.load(Ordering::Acquire);
+            if stats.queued + stats.running <= closers {
+                break;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                self.closing.abort(&self.interrupts);
+                break;
+            }
+            std::thread::sleep(POLL);
+        }
+    }
+}
+
+impl Drop for Closer {
+    fn drop(&mut self) {
+        self.closing.closers.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The error a call rejects with once its database closes.
+pub fn closed_error() -> napi::Error {
+    napi::Error::from_reason(CLOSED)
+}
+
+/// `err` as a JS error with `code` set to `DatabaseClosed`, on the JS thread.
+pub fn to_js(env: &Env, mut err: napi::Error) -> napi::Error {
+    let reason = std::mem::take(&mut err.reason);
+    let made = env
+        .create_error(napi::Error::from_reason(reason.as_str()))
+        .and_then(|mut error| {
+            error.set_named_property("code", "DatabaseClosed")?;
+            Ok(napi::Error::from(error.to_unknown()))
+        });
+    made.unwrap_or_else(|_| napi::Error::from_reason(reason))
+}
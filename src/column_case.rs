@@ -0,0 +1,118 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `columnCase` open option and `stmt.columnCase()`.
+//
+// Column names are converted when their JS strings are created, for row
+// objects, `columns` arrays and the tables and keys of expanded rows, so
+// there is no mapping layer in JS. Only names made of letters, digits,
+// underscores and dots are converted, one dot-separated part at a time;
+// names of expressions like `COUNT(*)` are left as they are. The SQL itself
+// still uses the names from the schema, as do options that name columns,
+// such as the `parentColumn` of `db.tree()`.
+
+use std::borrow::Cow;
+
+/// How column names are cased in results.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnCase {
+    /// As returned by the engine.
+    #[default]
+    AsIs,
+    /// `user_id` becomes `userId`.
+    Camel,
+    /// `userId` becomes `user_id`.
+    Snake,
+}
+
+impl ColumnCase {
+    pub fn parse(name: &str) -> napi::Result<Self> {
+        match name {
+            "asIs" => Ok(ColumnCase::AsIs),
+            "camel" => Ok(ColumnCase::Camel),
+            "snake" => Ok(ColumnCase::Snake),
+            _ => Err(napi::Error::from_reason(format!(
+                "Invalid columnCase '{name}': expected 'camel', 'snake', or 'asIs'"
+            ))),
+        }
+    }
+
+    /// Column names as this case renders them.
+    pub fn columns<'a>(&self, columns: &'a [String]) -> Cow<'a, [String]> {
+        match self {
+            ColumnCase::AsIs => Cow::Borrowed(columns),
+            _ => Cow::Owned(columns.iter().map(|c| self.convert(c)).collect()),
+        }
+    }
+
+    /// One column name as this case renders it.
+    pub fn convert(&self, name: &str) -> String {
+        let identifier = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if !identifier {
+            return name.to_string();
+        }
+        let convert = match self {
+            ColumnCase::AsIs => return name.to_string(),
+            ColumnCase::Camel => camel,
+            ColumnCase::Snake => snake,
+        };
+        name.split('.').map(convert).collect::<Vec<_>>().join(".")
+    }
+}
+
+fn camel(part: &str) -> String {
+    let trimmed = part.trim_start_matches('_');
+    let mut out = String::with_capacity(part.len());
+    out.push_str(&part[..part.len() - trimmed.len()]);
+    let mut upper = false;
+    for c in trimmed.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    if upper {
+        out.push('_');
+    }
+    out
+}
+
+fn snake(part: &str) -> String {
+    let chars: Vec<char> = part.chars().collect();
+    let mut out = String::with_capacity(part.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let boundary = prev.is_some_and(|p| {
+                p.is_ascii_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_ascii_uppercase() && next.is_some_and(|n| n.is_ascii_lowercase()))
+            });
+            if boundary {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -0,0 +1,160 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// How result column names become the keys of row objects: the `columnCase`
+// and `duplicateColumns` open options.
+//
+// A join can return two columns with the same name (`SELECT a.id, b.id`
+// names both `id`), and a row object can only hold one of them. Instead of
+// keeping the last one, `duplicateColumns` decides what happens, once
+// `columnCase` has converted the names, so `user_id` and `userId` collide
+// under `'camel'`. `'suffix'`, the default, keys the later ones `id_1`,
+// `id_2` and so on; `'error'` throws; `'expand'` returns such rows as one
+// object per table, like `stmt.expand()`, for the calls that have their
+// statement at hand, and falls back to suffixes for the others. Within the
+// tables of an expanded row, duplicates are suffixed, or throw for
+// `'error'`. The `columns` of raw results hold every name, so they are only
+// cased.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::column_case::ColumnCase;
+use crate::shape::Expanded;
+
+/// What happens to result columns whose names collide in a row object.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateColumns {
+    /// `id`, `id_1`, `id_2`, ...
+    #[default]
+    Suffix,
+    /// Throw.
+    Error,
+    /// One object per table, where the statement is known.
+    Expand,
+}
+
+impl DuplicateColumns {
+    pub fn parse(name: &str) -> napi::Result<Self> {
+        match name {
+            "suffix" => Ok(DuplicateColumns::Suffix),
+            "error" => Ok(DuplicateColumns::Error),
+            "expand" => Ok(DuplicateColumns::Expand),
+            _ => Err(napi::Error::from_reason(format!(
+                "Invalid duplicateColumns '{name}': expected 'suffix', 'error', or 'expand'"
+            ))),
+        }
+    }
+
+    /// `keys` with the later of each duplicate renamed, or `None` when they
+    /// are all distinct.
+    fn resolve(&self, keys: &[String]) -> napi::Result<Option<Vec<String>>> {
+        let mut seen = HashSet::with_capacity(keys.len());
+        let Some(first) = keys.iter().position(|k| !seen.insert(k.as_str())) else {
+            return Ok(None);
+        };
+        if *self == DuplicateColumns::Error {
+            return Err(napi::Error::from_reason(format!(
+                "Duplicate column name '{}' in result: alias the columns, or set duplicateColumns \
+                 to 'suffix' or 'expand'",
+                keys[first]
+            )));
+        }
+        let mut taken: HashSet<String> = keys.iter().cloned().collect();
+        let mut kept = HashSet::with_capacity(keys.len());
+        let renamed = keys
+            .iter()
+            .map(|key| {
+                if kept.insert(key.as_str()) {
+                    return key.clone();
+                }
+                let renamed = (1..)
+                    .map(|n| format!("{key}_{n}"))
+                    .find(|candidate| !taken.contains(candidate))
+                    .expect("some suffix is free");
+                taken.insert(renamed.clone());
+                renamed
+            })
+            .collect();
+        Ok(Some(renamed))
+    }
+}
+
+/// The naming options of a handle, a statement or a call's results.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColumnNames {
+    pub case: ColumnCase,
+    pub duplicates: DuplicateColumns,
+}
+
+impl ColumnNames {
+    /// Names for a `columns` array: cased.
+    pub fn columns<'a>(&self, columns: &'a [String]) -> Cow<'a, [String]> {
+        self.case.columns(columns)
+    }
+
+    /// Keys for row objects: cased, with duplicates renamed or rejected.
+    pub fn keys<'a>(&self, columns: &'a [String]) -> napi::Result<Cow<'a, [String]>> {
+        let cased = self.case.columns(columns);
+        Ok(match self.duplicates.resolve(&cased)? {
+            Some(keys) => Cow::Owned(keys),
+            None => cased,
+        })
+    }
+
+    /// `keys()` for names `columns()` has already cased.
+    pub fn dedupe(&self, cased: Vec<String>) -> napi::Result<Vec<String>> {
+        Ok(self.duplicates.resolve(&cased)?.unwrap_or(cased))
+    }
+
+    /// `keys()` for row objects, or `columns()` for raw results.
+    pub fn render<'a>(&self, columns: &'a [String], raw: bool) -> napi::Result<Cow<'a, [String]>> {
+        if raw {
+            Ok(self.columns(columns))
+        } else {
+            self.keys(columns)
+        }
+    }
+
+    /// Whether rows with these columns become one object per table.
+    pub fn expands(&self, columns: &[String]) -> bool {
+        if self.duplicates != DuplicateColumns::Expand {
+            return false;
+        }
+        let cased = self.case.columns(columns);
+        let mut seen = HashSet::with_capacity(cased.len());
+        !cased.iter().all(|c| seen.insert(c.as_str()))
+    }
+
+    /// Case an expanded row's tables and keys, and handle duplicate keys
+    /// within each table.
+    pub fn expanded(&self, expanded: &mut Expanded) -> napi::Result<()> {
+        if self.case != ColumnCase::AsIs {
+            expanded.keys = self.case.columns(&expanded.keys).into_owned();
+            expanded.namespaces = self.case.columns(&expanded.namespaces).into_owned();
+        }
+        for namespace in 0..expanded.namespaces.len() as i32 {
+            let members: Vec<usize> = (0..expanded.keys.len())
+                .filter(|&i| expanded.column_namespaces[i] == namespace)
+                .collect();
+            let keys: Vec<String> = members.iter().map(|&i| expanded.keys[i].clone()).collect();
+            if let Some(renamed) = self.duplicates.resolve(&keys)? {
+                for (&i, key) in members.iter().zip(renamed) {
+                    expanded.keys[i] = key;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.columnStats(table, column)`.
+//
+// The figures are the ones ANALYZE keeps for the optimizer in the engine's
+// statistics tables, read without scanning the table. When there are none
+// for the column, because the table was never analyzed or the column was
+// added since, ANALYZE runs first. ANALYZE works from a sample of at most
+// `DEFAULT_SAMPLE_SIZE` rows, so on larger tables `nullFraction` is the
+// sample's and `ndv` counts the distinct values in the sample. The engine
+// keeps min and max as text; they are converted back to the column's type.
+// Everything is as of the last ANALYZE, and the engine records each ANALYZE
+// under the table name as it was written, so the newest row for the name in
+// any case wins.
+
+use napi::sys;
+use stoolap::api::Database;
+use stoolap::storage::statistics::{DEFAULT_SAMPLE_SIZE, SYS_COLUMN_STATS, SYS_TABLE_STATS};
+use stoolap::storage::Engine;
+use stoolap::Value;
+
+use crate::error::to_napi;
+use crate::policy::{check_sql, PolicyRef};
+use crate::sql::quote_ident;
+use crate::tasks::row_object;
+
+/// Keys of the object `columnStats()` returns, in `ColumnStats::values` order.
+const KEYS: [&str; 10] = [
+    "table",
+    "column",
+    "rows",
+    "sampled",
+    "nullFraction",
+    "ndv",
+    "min",
+    "max",
+    "avgWidth",
+    "analyzed",
+];
+
+/// Statistics of one column.
+pub struct ColumnStats {
+    values: Vec<Value>,
+}
+
+/// What ANALYZE recorded for a column.
+struct Analyzed {
+    null_count: i64,
+    distinct_count: i64,
+    min: Option<String>,
+    max: Option<String>,
+    avg_width: i64,
+}
+
+/// Read the statistics of `table.column`, running ANALYZE on the table
+/// first if it has none for the column. Both are checked against `policy`.
+pub fn column_stats(
+    db: &Database,
+    policy: &PolicyRef,
+    table: &str,
+    column: &str,
+) -> napi::Result<ColumnStats> {
+    let schema = db.engine().get_table_schema(table).map_err(to_napi)?;
+    let found = schema
+        .columns
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(column))
+        .ok_or_else(|| {
+            napi::Error::from_reason(format!(
+                "Column '{column}' not found in table '{}'",
+                schema.table_name
+            ))
+        })?;
+    let table_ident = quote_ident(&schema.table_name);
+    check_sql(
+        policy,
+        &format!("SELECT {} FROM {table_ident}", quote_ident(&found.name)),
+    )?;
+
+    let mut analyzed = false;
+    let stats = match read(db, &schema.table_name, &found.name)? {
+        Some(stats) => stats,
+        None => {
+            // Writes the engine's statistics tables, so it must be allowed
+            // as well; without it the call fails rather than analyze.
+            let analyze = format!("ANALYZE {table_ident}");
+            check_sql(policy, &analyze)?;
+            db.execute(&analyze, ()).map_err(to_napi)?;
+            analyzed = true;
+            read(db, &schema.table_name, &found.name)?.ok_or_else(|| {
+                napi::Error::from_reason(format!(
+                    "ANALYZE recorded no statistics for '{}.{}'",
+                    schema.table_name, found.name
+                ))
+            })?
+        }
+    };
+    let rows = row_count(db, &schema.table_name)?;
+    let sampled = rows.min(DEFAULT_SAMPLE_SIZE as i64);
+    let null_fraction = if sampled > 0 {
+        stats.null_count as f64 / sampled as f64
+    } else {
+        0.0
+    };
+    let typed = |text: Option<String>| match text {
+        Some(text) => Value::text(text.as_str()).coerce_to_type(found.data_type),
+        None => Value::null_unknown(),
+    };
+    Ok(ColumnStats {
+        values: vec![
+            Value::text(schema.table_name.as_str()),
+            Value::text(found.name.as_str()),
+            Value::integer(rows),
+            Value::integer(sampled),
+            Value::float(null_fraction),
+            Value::integer(stats.distinct_count),
+            typed(stats.min),
+            typed(stats.max),
+            Value::integer(stats.avg_width),
+            Value::boolean(analyzed),
+        ],
+    })
+}
+
+impl ColumnStats {
+    pub fn to_js(&self, env: sys::napi_env) -> napi::Result<sys::napi_value> {
+        let keys = KEYS.map(String::from);
+        row_object(env, &keys, &self.values)
+    }
+}
+
+/// The newest statistics ANALYZE recorded for `table.column`, if any.
+fn read(db: &Database, table: &str, column: &str) -> napi::Result<Option<Analyzed>> {
+    if !db.table_exists(SYS_COLUMN_STATS).map_err(to_napi)? {
+        return Ok(None);
+    }
+    let sql = format!(
+        "SELECT null_count, distinct_count, min_value, max_value, avg_width FROM {SYS_COLUMN_STATS} \
+         WHERE LOWER(table_name) = $1 AND LOWER(column_name) = $2 ORDER BY id DESC LIMIT 1"
+    );
+    let mut rows = db
+        .query(&sql, (table.to_lowercase(), column.to_lowercase()))
+        .map_err(to_napi)?;
+    if !rows.advance() {
+        return Ok(None);
+    }
+    let row = rows.current_row();
+    let integer = |i| match row.get(i) {
+        Some(Value::Integer(n)) => *n,
+        _ => 0,
+    };
+    let text = |i| match row.get(i) {
+        Some(Value::Text(s)) => Some(s.to_string()),
+        _ => None,
+    };
+    Ok(Some(Analyzed {
+        null_count: integer(0),
+        distinct_count: integer(1),
+        min: text(2),
+        max: text(3),
+        avg_width: integer(4),
+    }))
+}
+
+/// Rows in `table` at its newest ANALYZE.
+fn row_count(db: &Database, table: &str) -> napi::Result<i64> {
+    if !db.table_exists(SYS_TABLE_STATS).map_err(to_napi)? {
+        return Ok(0);
+    }
+    let sql = format!(
+        "SELECT row_count FROM {SYS_TABLE_STATS} WHERE LOWER(table_name) = $1 \
+         ORDER BY id DESC LIMIT 1"
+    );
+    let mut rows = db.query(&sql, (table.to_lowercase(),)).map_err(to_napi)?;
+    if !rows.advance() {
+        return Ok(0);
+    }
+    Ok(match rows.current_row().get(0) {
+        Some(Value::Integer(n)) => *n,
+        _ => 0,
+    })
+}
@@ -0,0 +1,279 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `commitWindowMs` open option.
+//
+// The engine syncs its WAL to disk at every commit, so small auto-committed
+// writes each wait for a sync of their own. With the option, an async
+// `execute()` of a plain INSERT, UPDATE or DELETE joins a group instead of
+// being scheduled: the first call of a group schedules a task that waits
+// `commitWindowMs` on its thread, then runs the statements of every call
+// that joined meanwhile, in call order, in one transaction, and commits them
+// with one sync. Each call's promise then settles with its own result. When
+// a statement or the commit fails, the transaction is rolled back and the
+// calls run again one by one, auto-committed as they would run without the
+// option, so each settles as it would have alone.
+//
+// Calls waiting for their group don't take a thread; the group's task is
+// monitored as one `execute` call, so `close()` waits for it. Statements with
+// RETURNING or named parameters, writes to `serializeWrites` tables and calls
+// made once the handle is closing are scheduled as usual.
+
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::ToNapiValue;
+use napi::{sys, Env, JsDeferred, JsValue, Task};
+use stoolap::api::Transaction as ApiTransaction;
+use stoolap::parser::ast::Statement;
+
+use crate::call_site::CallSite;
+use crate::changes::ChangeEvent;
+use crate::closing::{self, ClosingRef};
+use crate::error::{from_core, restore, to_napi};
+use crate::sql::parse_single;
+use crate::tasks::{returning, ExecTask, RawJsValue, RunOutcome, TaskParams};
+use crate::worker::{Scheduled, WorkerRef};
+
+pub type CommitWindowRef = Arc<CommitWindow>;
+
+type Resolver = Box<dyn FnOnce(Env) -> napi::Result<RawJsValue>>;
+
+/// The calls of the group that has not started yet.
+pub struct CommitWindow {
+    window: Duration,
+    group: Mutex<Group>,
+}
+
+#[derive(Default)]
+struct Group {
+    members: Vec<Member>,
+    /// Whether a task is scheduled to run `members`.
+    scheduled: bool,
+}
+
+/// A call waiting for its group.
+struct Member {
+    task: ExecTask,
+    call_site: CallSite,
+    deferred: JsDeferred<RawJsValue, Resolver>,
+}
+
+impl CommitWindow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            group: Mutex::new(Group::default()),
+        }
+    }
+
+    /// Whether `task` can join a group.
+    pub fn groups(task: &ExecTask) -> bool {
+        if !matches!(task.params, TaskParams::Positional(_))
+            || returning(task.plan.as_ref(), &task.sql)
+        {
+            return false;
+        }
+        match task.plan {
+            Some(ref plan) => is_dml(&plan.statement),
+            None => parse_single(&task.sql).is_ok_and(|stmt| is_dml(&stmt)),
+        }
+    }
+
+    /// Add `task` to the group, scheduling the group's task if it is the
+    /// first, and return its promise.
+    fn join(
+        self: &Arc<Self>,
+        env: sys::napi_env,
+        worker: &WorkerRef,
+        task: ExecTask,
+        mut call_site: CallSite,
+    ) -> napi::Result<sys::napi_value> {
+        let env = Env::from_raw(env);
+        call_site.capture(&env)?;
+        let (deferred, promise) = env.create_deferred::<RawJsValue, Resolver>()?;
+        let first = {
+            let mut group = lock(&self.group);
+            group.members.push(Member {
+                task,
+                call_site,
+                deferred,
+            });
+            !mem::replace(&mut group.scheduled, true)
+        };
+        if first {
+            let commit = worker.schedule(
+                "execute",
+                CommitTask {
+                    window: Arc::clone(self),
+                    closing: Arc::clone(worker.closing()),
+                },
+            );
+            // Its promise only reports to the members.
+            unsafe { Scheduled::to_napi_value(env.raw(), commit)? };
+        }
+        Ok(promise.raw())
+    }
+
+    /// The calls that joined, leaving the next call to start a new group.
+    fn take(&self) -> Vec<Member> {
+        let mut group = lock(&self.group);
+        group.scheduled = false;
+        mem::take(&mut group.members)
+    }
+}
+
+/// A call joining a group once it returns its promise.
+pub struct Joining {
+    pub window: CommitWindowRef,
+    pub worker: WorkerRef,
+    pub task: ExecTask,
+    pub call_site: CallSite,
+}
+
+impl Joining {
+    pub fn join(self, env: sys::napi_env) -> napi::Result<sys::napi_value> {
+        self.window
+            .join(env, &self.worker, self.task, self.call_site)
+    }
+}
+
+// ============================================================
+// CommitTask — a group of db.execute() calls
+// ============================================================
+
+pub struct CommitTask {
+    window: CommitWindowRef,
+    closing: ClosingRef,
+}
+
+impl Task for CommitTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        std::thread::sleep(self.window.window);
+        let mut members = self.window.take();
+        let outcomes: Vec<napi::Result<RunOutcome>> = match commit(&members) {
+            Ok(outcomes) => outcomes.into_iter().map(Ok).collect(),
+            // Each call runs alone, with its own retries.
+            Err(_) => members.iter_mut().map(|m| m.task.compute()).collect(),
+        };
+        for (member, outcome) in members.into_iter().zip(outcomes) {
+            let closed = outcome.as_ref().is_err_and(|err| self.closing.stopped(err));
+            member.settle(outcome, closed);
+        }
+        Ok(())
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(())
+    }
+
+    fn reject(&mut self, _env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+        // The task never ran, e.g. because the handle was closed first.
+        for member in self.window.take() {
+            member.deferred.reject(err.try_clone()?);
+        }
+        Ok(())
+    }
+}
+
+impl Member {
+    /// Settle the call's promise with `outcome`; `closed` is whether it
+    /// failed because the handle was closed.
+    fn settle(self, outcome: napi::Result<RunOutcome>, closed: bool) {
+        let Member {
+            mut task,
+            call_site,
+            deferred,
+        } = self;
+        deferred.resolve(Box::new(move |env| match outcome {
+            Ok(outcome) => task.resolve(env, outcome),
+            Err(_) if closed => task.reject(env, closing::to_js(&env, closing::closed_error())),
+            Err(err) => {
+                let err = restore(&env, call_site.append_to(err));
+                task.reject(env, err)
+            }
+        }));
+    }
+}
+
+/// Run the statements of `members` in one transaction and commit it.
+fn commit(members: &[Member]) -> napi::Result<Vec<RunOutcome>> {
+    let Some(first) = members.first() else {
+        return Ok(Vec::new());
+    };
+    let task = &first.task;
+    task.readers.write(|| {
+        let mut tx = task.db.begin().map_err(to_napi)?;
+        let mut events = Vec::new();
+        let mut outcomes = Vec::with_capacity(members.len());
+        for member in members {
+            match run(&member.task, &mut tx, &mut events) {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(err) => {
+                    let _ = tx.rollback();
+                    return Err(err);
+                }
+            }
+        }
+        tx.commit().map_err(to_napi)?;
+        task.changes.emit(events);
+        Ok(outcomes)
+    })
+}
+
+/// Run the statement of `task` in `tx`.
+fn run(
+    task: &ExecTask,
+    tx: &mut ApiTransaction,
+    events: &mut Vec<ChangeEvent>,
+) -> napi::Result<RunOutcome> {
+    task.ticket.check()?;
+    let timer = Instant::now();
+    let started = task.profiler.start_with(&task.params);
+    let params = task.params.clone();
+    let changes = if task.changes.is_active() {
+        task.changes.execute_on_tx(tx, params, &task.sql, events)?
+    } else {
+        params.execute_on_tx(tx, &task.sql).map_err(from_core)?
+    };
+    task.profiler
+        .finish_write(started, &task.sql, changes, task.plan.as_ref());
+    Ok(RunOutcome::new(timer, changes))
+}
+
+fn is_dml(stmt: &Statement) -> bool {
+    matches!(
+        stmt,
+        Statement::Insert(_) | Statement::Update(_) | Statement::Delete(_)
+    )
+}
+
+/// Check `commitWindowMs`.
+pub fn commit_window(ms: f64) -> napi::Result<Duration> {
+    if ms >= 0.0 && ms.is_finite() {
+        Ok(Duration::from_secs_f64(ms / 1000.0))
+    } else {
+        Err(napi::Error::from_reason(
+            "commitWindowMs must be a non-negative number",
+        ))
+    }
+}
+
+fn lock(mutex: &Mutex<Group>) -> MutexGuard<'_, Group> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,519 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use napi::bindgen_prelude::Either3;
+
+use stoolap::api::Database;
+use stoolap::{Config, SyncMode};
+
+use crate::analyze::AutoAnalyzeOptions;
+use crate::error::to_napi;
+use crate::retry::RetryOptions;
+
+/// DSN query parameters understood by the engine. Values of any other
+/// parameter are redacted, since the engine ignores them and they are the
+/// likeliest place for an application to have stashed credentials.
+const DSN_OPTIONS: &[&str] = &[
+    "sync",
+    "sync_mode",
+    "snapshot_interval",
+    "keep_snapshots",
+    "wal_flush_trigger",
+    "wal_buffer_size",
+    "wal_max_size",
+    "commit_batch_size",
+    "sync_interval_ms",
+    "sync_interval",
+    "wal_compression",
+    "snapshot_compression",
+    "compression",
+    "compression_threshold",
+    "cleanup",
+    "cleanup_interval",
+    "deleted_row_retention",
+    "transaction_retention",
+];
+
+const REDACTED: &str = "***";
+
+/// Engine settings in effect for an open database.
+#[napi(object, object_from_js = false)]
+pub struct EngineOptions {
+    /// Whether data is persisted to disk (false for in-memory databases)
+    pub persistence: bool,
+    #[napi(ts_type = "'none' | 'normal' | 'full'")]
+    pub sync: String,
+    pub snapshot_interval: u32,
+    pub keep_snapshots: u32,
+    pub wal_flush_trigger: i64,
+    pub wal_buffer_size: i64,
+    pub wal_max_size: i64,
+    pub commit_batch_size: u32,
+    pub sync_interval_ms: u32,
+    pub wal_compression: bool,
+    pub snapshot_compression: bool,
+    pub compression_threshold: i64,
+    pub cleanup: bool,
+    pub cleanup_interval: i64,
+    pub deleted_row_retention: i64,
+    pub transaction_retention: i64,
+}
+
+impl From<Config> for EngineOptions {
+    fn from(config: Config) -> Self {
+        let p = config.persistence;
+        let c = config.cleanup;
+        Self {
+            persistence: p.enabled && config.path.is_some(),
+            sync: sync_mode_name(p.sync_mode).to_string(),
+            snapshot_interval: p.snapshot_interval,
+            keep_snapshots: p.keep_snapshots,
+            wal_flush_trigger: p.wal_flush_trigger as i64,
+            wal_buffer_size: p.wal_buffer_size as i64,
+            wal_max_size: p.wal_max_size as i64,
+            commit_batch_size: p.commit_batch_size,
+            sync_interval_ms: p.sync_interval_ms,
+            wal_compression: p.wal_compression,
+            snapshot_compression: p.snapshot_compression,
+            compression_threshold: p.compression_threshold as i64,
+            cleanup: c.enabled,
+            cleanup_interval: c.interval_secs as i64,
+            deleted_row_retention: c.deleted_row_retention_secs as i64,
+            transaction_retention: c.transaction_retention_secs as i64,
+        }
+    }
+}
+
+/// Engine settings applied from the first statement, for `Database.open(path, options)`.
+///
+/// They are passed to the engine as DSN query parameters, so they are only
+/// supported for file-based databases. `retry`, `autoAnalyze`,
+/// `dedicatedThread`, `readers`, `serializeWrites`, `memoryBudget`,
+/// `idleTransactionMs`, `asyncStackTraces`, `columnCase`, `commitWindowMs`,
+/// `parallelism`, `consistencyTokens` and `strictStatements` are handled by
+/// the binding and work for any database.
+#[napi(object, object_to_js = false)]
+#[derive(Clone)]
+pub struct OpenOptions {
+    #[napi(ts_type = "'none' | 'normal' | 'full'")]
+    pub sync: Option<String>,
+    pub snapshot_interval: Option<u32>,
+    pub keep_snapshots: Option<u32>,
+    pub wal_flush_trigger: Option<u32>,
+    pub wal_buffer_size: Option<u32>,
+    pub wal_max_size: Option<i64>,
+    pub commit_batch_size: Option<u32>,
+    pub sync_interval_ms: Option<u32>,
+    pub wal_compression: Option<bool>,
+    pub snapshot_compression: Option<bool>,
+    pub compression_threshold: Option<u32>,
+    pub cleanup: Option<bool>,
+    pub cleanup_interval: Option<u32>,
+    pub deleted_row_retention: Option<u32>,
+    pub transaction_retention: Option<u32>,
+    /// Retry async auto-committed statements that fail with a write conflict.
+    pub retry: Option<RetryOptions>,
+    /// Re-ANALYZE tables in the background once enough of their rows changed.
+    pub auto_analyze: Option<AutoAnalyzeOptions>,
+    /// Run async calls in order on a thread of the database's own instead of
+    /// the libuv thread pool (default false).
+    pub dedicated_thread: Option<bool>,
+    /// Extra engine handles that run async read-only queries in parallel
+    /// (default 0).
+    pub readers: Option<u32>,
+    /// Tables whose async writes take turns in a queue instead of failing
+    /// with write conflicts.
+    pub serialize_writes: Option<Vec<String>>,
+    /// Megabytes of query results the binding may hold at once (default
+    /// unlimited).
+    pub memory_budget: Option<f64>,
+    /// Warn with a `transactionIdle` event about transactions that have had
+    /// no calls for this many milliseconds (default off).
+    pub idle_transaction_ms: Option<f64>,
+    /// Append the JS stack of each async call to the errors it rejects with
+    /// (default false).
+    pub async_stack_traces: Option<bool>,
+    /// Case of column names in result rows (default `'asIs'`).
+    #[napi(ts_type = "'camel' | 'snake' | 'asIs'")]
+    pub column_case: Option<String>,
+    /// What happens to result columns whose names collide in row objects:
+    /// later ones get `_1`, `_2`, ... suffixes (`'suffix'`, the default),
+    /// the call throws (`'error'`), or rows become one object per table
+    /// (`'expand'`).
+    #[napi(ts_type = "'suffix' | 'error' | 'expand'")]
+    pub duplicate_columns: Option<String>,
+    /// Return TIMESTAMP values as `Temporal.Instant`s instead of ISO strings
+    /// (default false). Needs `globalThis.Temporal`.
+    pub temporal: Option<bool>,
+    /// Digits of the second in TIMESTAMP results: whole seconds (`'s'`, the
+    /// default), milliseconds or microseconds.
+    #[napi(ts_type = "'s' | 'ms' | 'us'")]
+    pub timestamp_precision: Option<String>,
+    /// Time zone of TIMESTAMP results: `'utc'` (the default), `'local'`, or a
+    /// fixed offset such as `'+05:30'`.
+    #[napi(ts_type = "'utc' | 'local' | string")]
+    pub timezone: Option<String>,
+    /// `maxRows` for every query without its own: fail if a result has more
+    /// rows, or truncate it with `onMaxRows: 'truncate'` (default no limit).
+    pub max_rows: Option<f64>,
+    /// What a result over `maxRows` does (default `'error'`).
+    #[napi(ts_type = "'error' | 'truncate'")]
+    pub on_max_rows: Option<String>,
+    /// Commit async `execute()` calls made within this many milliseconds of
+    /// each other in one transaction, with one sync (default off).
+    pub commit_window_ms: Option<f64>,
+    /// Run the engine's parallel operators on this many threads for every
+    /// query and async call (default one per core).
+    pub parallelism: Option<f64>,
+    /// Give query results a `consistencyToken` that `db.isStale()` checks
+    /// (default false).
+    pub consistency_tokens: Option<bool>,
+    /// Reject SQL holding more than one statement in every call but
+    /// `exec()` and `queryMulti()` (default false).
+    pub strict_statements: Option<bool>,
+}
+
+/// Append `options` to a DSN as query parameters.
+pub fn dsn_with_options(dsn: String, options: OpenOptions) -> napi::Result<String> {
+    let on_off = |b: bool| if b { "on" } else { "off" }.to_string();
+    let mut params = Vec::new();
+    if let Some(sync) = options.sync {
+        params.push((
+            "sync",
+            parse_sync_mode(&sync).map(sync_mode_name)?.to_string(),
+        ));
+    }
+    if let Some(bytes) = options.wal_max_size {
+        if bytes < 0 {
+            return Err(napi::Error::from_reason("walMaxSize must not be negative"));
+        }
+        params.push(("wal_max_size", bytes.to_string()));
+    }
+    let numbers = [
+        ("snapshot_interval", options.snapshot_interval),
+        ("keep_snapshots", options.keep_snapshots),
+        ("wal_flush_trigger", options.wal_flush_trigger),
+        ("wal_buffer_size", options.wal_buffer_size),
+        ("commit_batch_size", options.commit_batch_size),
+        ("sync_interval_ms", options.sync_interval_ms),
+        ("compression_threshold", options.compression_threshold),
+        ("cleanup_interval", options.cleanup_interval),
+        ("deleted_row_retention", options.deleted_row_retention),
+        ("transaction_retention", options.transaction_retention),
+    ];
+    for (key, value) in numbers {
+        if let Some(value) = value {
+            params.push((key, value.to_string()));
+        }
+    }
+    let flags = [
+        ("wal_compression", options.wal_compression),
+        ("snapshot_compression", options.snapshot_compression),
+        ("cleanup", options.cleanup),
+    ];
+    for (key, value) in flags {
+        if let Some(value) = value {
+            params.push((key, on_off(value)));
+        }
+    }
+
+    if params.is_empty() {
+        return Ok(dsn);
+    }
+    if !dsn.starts_with("file://") {
+        return Err(napi::Error::from_reason(
+            "Open options are only supported for file-based databases",
+        ));
+    }
+    let query: Vec<String> = params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    let separator = if dsn.contains('?') { '&' } else { '?' };
+    Ok(format!("{dsn}{separator}{}", query.join("&")))
+}
+
+/// A single engine setting as seen from JS.
+pub type ConfigValue = Either3<String, i64, bool>;
+
+/// Read an engine setting by its `EngineOptions` name.
+pub fn get(db: &Database, key: &str) -> napi::Result<ConfigValue> {
+    let o = EngineOptions::from(db.engine().config());
+    Ok(match key {
+        "persistence" => Either3::C(o.persistence),
+        "sync" => Either3::A(o.sync),
+        "snapshotInterval" => Either3::B(o.snapshot_interval.into()),
+        "keepSnapshots" => Either3::B(o.keep_snapshots.into()),
+        "walFlushTrigger" => Either3::B(o.wal_flush_trigger),
+        "walBufferSize" => Either3::B(o.wal_buffer_size),
+        "walMaxSize" => Either3::B(o.wal_max_size),
+        "commitBatchSize" => Either3::B(o.commit_batch_size.into()),
+        "syncIntervalMs" => Either3::B(o.sync_interval_ms.into()),
+        "walCompression" => Either3::C(o.wal_compression),
+        "snapshotCompression" => Either3::C(o.snapshot_compression),
+        "compressionThreshold" => Either3::B(o.compression_threshold),
+        "cleanup" => Either3::C(o.cleanup),
+        "cleanupInterval" => Either3::B(o.cleanup_interval),
+        "deletedRowRetention" => Either3::B(o.deleted_row_retention),
+        "transactionRetention" => Either3::B(o.transaction_retention),
+        _ => {
+            return Err(napi::Error::from_reason(format!(
+                "Unknown config key '{key}'"
+            )))
+        }
+    })
+}
+
+/// Change an engine setting at runtime. Only the settings the engine re-reads
+/// while running can be changed; the rest must be given to `Database.open`.
+pub fn set(db: &Database, key: &str, value: ConfigValue) -> napi::Result<()> {
+    let engine = db.engine();
+    let mut config = engine.config();
+    let p = &mut config.persistence;
+    match (key, value) {
+        ("sync", Either3::A(mode)) => p.sync_mode = parse_sync_mode(&mode)?,
+        ("snapshotInterval", Either3::B(secs)) => p.snapshot_interval = to_u32(key, secs)?,
+        ("keepSnapshots", Either3::B(count)) => p.keep_snapshots = to_u32(key, count)?,
+        ("walFlushTrigger", Either3::B(bytes)) => {
+            p.wal_flush_trigger = to_u32(key, bytes)? as usize
+        }
+        ("sync" | "snapshotInterval" | "keepSnapshots" | "walFlushTrigger", _) => {
+            let expected = if key == "sync" {
+                "a string"
+            } else {
+                "a number"
+            };
+            return Err(napi::Error::from_reason(format!(
+                "Config '{key}' must be {expected}"
+            )));
+        }
+        _ => {
+            // Validates the key.
+            get(db, key)?;
+            return Err(napi::Error::from_reason(format!(
+                "Config '{key}' cannot be changed after opening; pass it to Database.open(path, options)"
+            )));
+        }
+    }
+    engine.update_engine_config(config).map_err(to_napi)
+}
+
+fn to_u32(key: &str, value: i64) -> napi::Result<u32> {
+    u32::try_from(value).map_err(|_| {
+        napi::Error::from_reason(format!("Config '{key}' must be a non-negative integer"))
+    })
+}
+
+fn parse_sync_mode(mode: &str) -> napi::Result<SyncMode> {
+    match mode {
+        "none" => Ok(SyncMode::None),
+        "normal" => Ok(SyncMode::Normal),
+        "full" => Ok(SyncMode::Full),
+        _ => Err(napi::Error::from_reason(format!(
+            "Invalid sync mode '{mode}': expected 'none', 'normal', or 'full'"
+        ))),
+    }
+}
+
+/// Features compiled into this build of the binding.
+#[napi(object, object_from_js = false)]
+pub struct BuildFeatures {
+    /// Result rows are built with direct V8 calls instead of per-value N-API.
+    /// False when this runtime falls back to N-API; see `Database.runtimeInfo()`.
+    pub v8_helpers: bool,
+    /// Parallel query execution
+    pub parallel: bool,
+}
+
+/// Sanitized description of an open database, safe to include in bug reports.
+#[napi(object, object_from_js = false)]
+pub struct DatabaseDescription {
+    /// DSN with credentials and unrecognized query parameter values redacted
+    pub path: String,
+    pub options: EngineOptions,
+    pub engine_version: String,
+    pub binding_version: String,
+    pub features: BuildFeatures,
+}
+
+pub fn describe(db: &Database) -> DatabaseDescription {
+    DatabaseDescription {
+        path: redact_dsn(db.dsn()),
+        options: db.engine().config().into(),
+        engine_version: stoolap::common::version().to_string(),
+        binding_version: env!("CARGO_PKG_VERSION").to_string(),
+        features: build_features(),
+    }
+}
+
+/// SQL features supported by the engine.
+#[napi(object, object_from_js = false)]
+pub struct SqlFeatures {
+    pub transactions: bool,
+    pub savepoints: bool,
+    /// `AS OF TIMESTAMP` / `AS OF TRANSACTION` time-travel reads
+    pub time_travel: bool,
+    pub returning: bool,
+    pub on_duplicate_key_update: bool,
+    pub cte: bool,
+    pub recursive_cte: bool,
+    pub window_functions: bool,
+    pub views: bool,
+    pub json: bool,
+    pub foreign_keys: bool,
+    /// Constraint checks deferred to commit (`begin({ deferConstraints: true })`)
+    pub deferred_constraints: bool,
+}
+
+/// Vector column support.
+#[napi(object, object_from_js = false)]
+pub struct VectorSupport {
+    pub supported: bool,
+    /// Largest `VECTOR(n)` dimension the engine accepts
+    pub max_dimensions: u32,
+    /// Approximate nearest-neighbor indexes (`USING HNSW`)
+    pub hnsw_index: bool,
+}
+
+/// How JS values cross the binding.
+#[napi(object, object_from_js = false)]
+pub struct BindingFeatures {
+    /// Binary values round-trip as Buffers (Buffers currently bind as UTF-8 text)
+    pub blob: bool,
+    /// BigInt parameters are accepted (within the i64 range)
+    pub bigint_params: bool,
+    /// Integer results can be returned as BigInt
+    pub bigint_results: bool,
+    /// Rows can be consumed incrementally instead of as a single array
+    pub streaming: bool,
+    /// `:name` parameters bound from an object
+    pub named_params: bool,
+    /// Float32Array parameters bind as vectors, and vectors return as Float32Array
+    pub float32_array_vectors: bool,
+    /// Temporal.Instant, PlainDateTime and PlainDate parameters bind as
+    /// timestamps, and timestamps can return as Temporal.Instant
+    pub temporal: bool,
+}
+
+/// Functions registered with the engine, by kind.
+#[napi(object, object_from_js = false)]
+pub struct FunctionLists {
+    pub scalar: Vec<String>,
+    pub aggregate: Vec<String>,
+    pub window: Vec<String>,
+}
+
+/// What this engine and binding support, for feature detection.
+#[napi(object, object_from_js = false)]
+pub struct Capabilities {
+    pub engine_version: String,
+    pub binding_version: String,
+    pub sql: SqlFeatures,
+    pub functions: FunctionLists,
+    pub vector: VectorSupport,
+    pub binding: BindingFeatures,
+    pub build: BuildFeatures,
+}
+
+pub fn capabilities() -> Capabilities {
+    let registry = stoolap::functions::global_registry();
+    let sorted = |mut names: Vec<String>| {
+        names.sort_unstable();
+        names
+    };
+    Capabilities {
+        engine_version: stoolap::common::version().to_string(),
+        binding_version: env!("CARGO_PKG_VERSION").to_string(),
+        sql: SqlFeatures {
+            transactions: true,
+            savepoints: true,
+            time_travel: true,
+            returning: true,
+            on_duplicate_key_update: true,
+            cte: true,
+            recursive_cte: true,
+            window_functions: true,
+            views: true,
+            json: true,
+            foreign_keys: true,
+            deferred_constraints: false,
+        },
+        functions: FunctionLists {
+            scalar: sorted(registry.list_scalars()),
+            aggregate: sorted(registry.list_aggregates()),
+            window: sorted(registry.list_windows()),
+        },
+        vector: VectorSupport {
+            supported: true,
+            max_dimensions: u16::MAX.into(),
+            hnsw_index: true,
+        },
+        binding: BindingFeatures {
+            blob: false,
+            bigint_params: true,
+            bigint_results: false,
+            streaming: false,
+            named_params: true,
+            float32_array_vectors: true,
+            temporal: true,
+        },
+        build: build_features(),
+    }
+}
+
+fn build_features() -> BuildFeatures {
+    BuildFeatures {
+        v8_helpers: crate::runtime::v8_helpers(),
+        parallel: true,
+    }
+}
+
+/// Strip `user:password@` from the DSN authority and redact the values of
+/// query parameters the engine does not recognize.
+fn redact_dsn(dsn: &str) -> String {
+    let (base, query) = match dsn.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (dsn, None),
+    };
+
+    let mut out = match base.split_once("://") {
+        Some((scheme, rest)) => {
+            let authority_end = rest.find('/').unwrap_or(rest.len());
+            let rest = match rest[..authority_end].rfind('@') {
+                Some(at) => &rest[at + 1..],
+                None => rest,
+            };
+            format!("{scheme}://{rest}")
+        }
+        None => base.to_string(),
+    };
+
+    if let Some(query) = query {
+        let params: Vec<String> = query
+            .split('&')
+            .map(|param| match param.split_once('=') {
+                Some((key, _)) if !DSN_OPTIONS.contains(&key) => format!("{key}={REDACTED}"),
+                _ => param.to_string(),
+            })
+            .collect();
+        out.push('?');
+        out.push_str(&params.join("&"));
+    }
+    out
+}
+
+fn sync_mode_name(mode: SyncMode) -> &'static str {
+    match mode {
+        SyncMode::None => "none",
+        SyncMode::Normal => "normal",
+        SyncMode::Full => "full",
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `consistencyTokens` open option and `db.isStale()`.
+//
+// The engine keeps no version per table the binding could read, so the
+// binding counts writes itself: each table has a counter that goes up when a
+// write to it commits, from the same committed change events `on('change')`
+// listeners receive (see changes.rs), so the option turns on change capture
+// as a listener would. A query's token lists the tables it reads, with views
+// expanded to the tables they read, and their counters when the query was
+// issued; it is stale once any of them has moved on. The counters only see
+// writes made through this handle, so the token also holds what the engine
+// tracks for every writer in the process: its schema epoch, which most DDL
+// bumps, each table's columns, which ALTER TABLE changes without bumping it,
+// each table's committed row count, which INSERT, DELETE and TRUNCATE change,
+// and the text of each view.
+//
+// The counters are read before the query runs and bumped once a write has
+// committed, so a write that commits while the query runs makes its token
+// stale whether the query saw it or not. A token also names the open it was
+// issued by, so tokens of an earlier open or of another database are stale.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use napi::sys;
+use stoolap::api::Database;
+use stoolap::parser::ast::Statement;
+use stoolap::storage::mvcc::VersionStore;
+use stoolap::storage::Engine;
+
+use crate::changes::ChangeEvent;
+use crate::policy::referenced_tables;
+use crate::sql::parse_single;
+use crate::tasks::check;
+
+/// Opens so far in this process, so two opens in the same nanosecond still
+/// get different ids.
+static OPENS: AtomicU64 = AtomicU64::new(0);
+
+pub type TableVersionsRef = Arc<TableVersions>;
+
+/// Write counters per table, shared by a database and everything created
+/// from it.
+pub struct TableVersions {
+    /// Tells this open apart from other databases and earlier opens.
+    id: u64,
+    versions: Mutex<HashMap<String, u64>>,
+}
+
+/// A table or view a token lists, as it was when the query was issued.
+#[derive(PartialEq)]
+enum Source {
+    Table {
+        name: String,
+        version: u64,
+        rows: usize,
+        /// Hash of the table's columns.
+        schema: u64,
+    },
+    View {
+        name: String,
+        hash: u64,
+    },
+}
+
+impl Default for TableVersions {
+    fn default() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self {
+            id: now ^ OPENS.fetch_add(1, Ordering::Relaxed).rotate_right(17),
+            versions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl TableVersions {
+    /// Count a commit's changes, once per table.
+    pub fn record(&self, events: &[ChangeEvent]) {
+        let tables: HashSet<&str> = events.iter().map(|e| e.table.as_str()).collect();
+        let mut versions = lock(&self.versions);
+        for table in tables {
+            *versions.entry(table.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// The token of a query running `stmt` now.
+    pub fn token(&self, db: &Database, stmt: &Statement) -> String {
+        let mut token = format!("{:x}.{}", self.id, db.engine().schema_epoch());
+        for source in self.sources(db, referenced_tables(stmt)) {
+            token.push(',');
+            match source {
+                Source::Table {
+                    name,
+                    version,
+                    rows,
+                    schema,
+                } => token.push_str(&format!("t{version}:{rows}:{schema:x}:{}", escape(&name))),
+                Source::View { name, hash } => {
+                    token.push_str(&format!("v{hash:x}:{}", escape(&name)))
+                }
+            }
+        }
+        token
+    }
+
+    /// Whether anything the query `token` was issued for reads has changed.
+    pub fn is_stale(&self, db: &Database, token: &str) -> napi::Result<bool> {
+        let invalid = || napi::Error::from_reason("Invalid consistency token");
+        let mut parts = token.split(',');
+        let head = parts.next().unwrap_or_default();
+        let (id, epoch) = head.split_once('.').ok_or_else(invalid)?;
+        let id = u64::from_str_radix(id, 16).map_err(|_| invalid())?;
+        let epoch: u64 = epoch.parse().map_err(|_| invalid())?;
+        let mut sources = Vec::new();
+        for part in parts {
+            let source = match part.split_at_checked(1) {
+                Some(("t", fields)) => {
+                    let mut fields = fields.splitn(4, ':');
+                    let (Some(version), Some(rows), Some(schema), Some(name)) =
+                        (fields.next(), fields.next(), fields.next(), fields.next())
+                    else {
+                        return Err(invalid());
+                    };
+                    Source::Table {
+                        name: unescape(name),
+                        version: version.parse().map_err(|_| invalid())?,
+                        rows: rows.parse().map_err(|_| invalid())?,
+                        schema: u64::from_str_radix(schema, 16).map_err(|_| invalid())?,
+                    }
+                }
+                Some(("v", fields)) => {
+                    let (hash, name) = fields.split_once(':').ok_or_else(invalid)?;
+                    Source::View {
+                        name: unescape(name),
+                        hash: u64::from_str_radix(hash, 16).map_err(|_| invalid())?,
+                    }
+                }
+                _ => return Err(invalid()),
+            };
+            sources.push(source);
+        }
+        if id != self.id || epoch != db.engine().schema_epoch() {
+            return Ok(true);
+        }
+        Ok(sources
+            .iter()
+            .any(|source| self.current(db, source).as_ref() != Some(source)))
+    }
+
+    /// The tables and views `names` stand for, with the tables of the views.
+    fn sources(&self, db: &Database, mut names: Vec<String>) -> Vec<Source> {
+        let engine = db.engine();
+        let mut seen = HashSet::new();
+        let mut sources = Vec::new();
+        while let Some(name) = names.pop() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            if let Ok(store) = engine.get_version_store(&name) {
+                sources.push(Source::Table {
+                    version: self.version(&name),
+                    rows: store.committed_row_count(),
+                    schema: schema_hash(&store),
+                    name,
+                });
+            } else if let Ok(Some(view)) = engine.get_view_lowercase(&name) {
+                if let Ok(query) = parse_single(&view.query) {
+                    names.extend(referenced_tables(&query));
+                }
+                sources.push(Source::View {
+                    hash: hash(&view.query),
+                    name,
+                });
+            }
+        }
+        sources
+    }
+
+    /// `source` as it is now, or `None` once it is gone.
+    fn current(&self, db: &Database, source: &Source) -> Option<Source> {
+        let engine = db.engine();
+        match source {
+            Source::Table { name, .. } => {
+                let store = engine.get_version_store(name).ok()?;
+                Some(Source::Table {
+                    name: name.clone(),
+                    version: self.version(name),
+                    rows: store.committed_row_count(),
+                    schema: schema_hash(&store),
+                })
+            }
+            Source::View { name, .. } => {
+                let view = engine.get_view_lowercase(name).ok()??;
+                Some(Source::View {
+                    name: name.clone(),
+                    hash: hash(&view.query),
+                })
+            }
+        }
+    }
+
+    fn version(&self, table: &str) -> u64 {
+        lock(&self.versions).get(table).copied().unwrap_or(0)
+    }
+}
+
+/// Set `consistencyToken` on a query's result.
+pub fn attach(env: sys::napi_env, value: sys::napi_value, token: &str) -> napi::Result<()> {
+    let mut js = ptr::null_mut();
+    check(unsafe {
+        sys::napi_create_string_utf8(env, token.as_ptr().cast(), token.len() as isize, &mut js)
+    })?;
+    check(unsafe { sys::napi_set_named_property(env, value, c"consistencyToken".as_ptr(), js) })
+}
+
+fn schema_hash(store: &VersionStore) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for column in &store.schema().columns {
+        column.name_lower.hash(&mut hasher);
+        column.data_type.hash(&mut hasher);
+        column.nullable.hash(&mut hasher);
+        column.primary_key.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Table names can hold the `,` that separates a token's parts.
+fn escape(name: &str) -> String {
+    name.replace('%', "%25").replace(',', "%2C")
+}
+
+fn unescape(name: &str) -> String {
+    name.replace("%2C", ",").replace("%25", "%")
+}
+
+fn lock(mutex: &Mutex<HashMap<String, u64>>) -> MutexGuard<'_, HashMap<String, u64>> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,61 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.queryCount`, `db.changes` and `db.totalChanges`.
+//
+// The engine keeps no such counts, so the binding does, where the profiler
+// hears of every statement that completes (see profile.rs). Every statement
+// of a database, its prepared statements, transactions and pipelines counts
+// toward `queryCount`, including each statement of an `exec()` script and
+// each chunk of `deleteMany()`. Statements that run for their changes
+// (`execute()`, `executeBatch()` and the like, and statements of a script
+// that return no rows) set `changes` and add it to `totalChanges`; queries
+// leave both alone. Statements that fail aren't counted, and changes made in
+// a transaction count when they are made, even if it is rolled back.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+
+#[derive(Default)]
+pub struct Counters {
+    statements: AtomicI64,
+    changes: AtomicI64,
+    total_changes: AtomicI64,
+}
+
+impl Counters {
+    /// A statement completed.
+    #[inline]
+    pub fn ran(&self) {
+        self.statements.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The statement that completed changed `changes` rows.
+    #[inline]
+    pub fn changed(&self, changes: i64) {
+        self.changes.store(changes, Ordering::Relaxed);
+        self.total_changes.fetch_add(changes, Ordering::Relaxed);
+    }
+
+    pub fn statements(&self) -> i64 {
+        self.statements.load(Ordering::Relaxed)
+    }
+
+    pub fn changes(&self) -> i64 {
+        self.changes.load(Ordering::Relaxed)
+    }
+
+    pub fn total_changes(&self) -> i64 {
+        self.total_changes.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.openCursor()`.
+//
+// A cursor runs its query once and keeps the engine's row iterator between
+// calls, so each `fetchNext(n)` converts only the next `n` rows. Rows come
+// back as objects keyed like `query()` rows. Once a fetch returns fewer rows
+// than it asked for the iterator is dropped, and later fetches return empty
+// arrays; `close()` drops it early. Fetches of one cursor run one at a time,
+// since they share the iterator.
+//
+// After an async fetch resolves, the cursor reads the next `n` rows ahead on
+// the worker while JS handles the ones it got, and the next fetch starts
+// from them. Rows read ahead are raw engine values, held against the memory
+// budget; a budget error while reading ahead rejects the next fetch, as it
+// would have had that fetch read them. The read-ahead of a fetch that is
+// interrupted or fails is skipped.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::ToNapiValue;
+use napi::{sys, Env};
+use stoolap::{Rows, Value};
+
+use crate::fetch_size::{self, FetchSize};
+use crate::interrupt::{InterruptRef, Ticket, CHECK_INTERVAL};
+use crate::memory::{Charge, MemoryBudgetRef};
+use crate::paging::Page;
+use crate::tasks::{
+    check, collected_rows_to_array, next_rows_to_array, CollectedRows, CursorFetchTask,
+    CursorPrefetchTask, RawJsValue,
+};
+use crate::worker::{Scheduled, WorkerRef};
+
+pub type CursorRowsRef = Arc<CursorRows>;
+
+/// Options for `db.openCursor(sql, params, options)`.
+#[napi(object, object_to_js = false)]
+pub struct CursorOptions {
+    /// Read the next rows ahead after each `fetchNext()` (default true).
+    pub prefetch: Option<bool>,
+    /// Rows a `fetchNext()` without a count reads. Tuned from the width of
+    /// the rows and how fast they are handled by default.
+    pub fetch_size: Option<f64>,
+}
+
+/// A cursor's rows, shared with its async fetches.
+pub struct CursorRows {
+    state: Mutex<State>,
+    memory: MemoryBudgetRef,
+    fetch_size: FetchSize,
+}
+
+enum State {
+    Open(Box<Open>),
+    /// Every row has been fetched.
+    Done,
+    Closed,
+}
+
+struct Open {
+    rows: Rows,
+    /// Rows read ahead of the next fetch, oldest first.
+    ahead: VecDeque<Vec<Value>>,
+    /// What `ahead` holds against the memory budget.
+    charge: Charge,
+    /// `rows` ran out while reading ahead.
+    exhausted: bool,
+    /// Reading ahead failed; the next fetch rejects with it.
+    failed: Option<napi::Error>,
+}
+
+impl Open {
+    /// Read from `rows` until `ahead` holds `count` rows or none are left.
+    fn fill(&mut self, count: usize, ticket: Option<&Ticket>) -> napi::Result<()> {
+        while self.ahead.len() < count && !self.exhausted {
+            if !self.rows.advance() {
+                self.exhausted = true;
+                break;
+            }
+            let row = self.rows.current_row().as_slice().to_vec();
+            self.charge.add_row(&row)?;
+            self.ahead.push_back(row);
+            if let (Some(ticket), 0) = (ticket, self.ahead.len() % CHECK_INTERVAL) {
+                ticket.check()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The first `count` rows read ahead, moved to a charge of their own.
+    fn take(&mut self, count: usize, memory: &MemoryBudgetRef) -> napi::Result<CollectedRows> {
+        let mut charge = memory.charge();
+        let mut rows = Vec::with_capacity(count.min(self.ahead.len()));
+        while rows.len() < count {
+            let Some(row) = self.ahead.pop_front() else {
+                break;
+            };
+            self.charge.release_row(&row);
+            charge.add_row(&row)?;
+            rows.push(row);
+        }
+        Ok(CollectedRows::new(
+            self.rows.columns().to_vec(),
+            rows,
+            charge,
+        ))
+    }
+}
+
+impl CursorRows {
+    pub fn new(rows: Rows, memory: &MemoryBudgetRef, fetch_size: FetchSize) -> Self {
+        Self {
+            state: Mutex::new(State::Open(Box::new(Open {
+                rows,
+                ahead: VecDeque::new(),
+                charge: memory.charge(),
+                exhausted: false,
+                failed: None,
+            }))),
+            memory: Arc::clone(memory),
+            fetch_size,
+        }
+    }
+
+    pub fn fetch_size(&self) -> &FetchSize {
+        &self.fetch_size
+    }
+
+    /// Read at most `count` rows. With none read ahead, `read` reads them
+    /// from the iterator; otherwise `collected` converts them. Both also
+    /// return how many they read. `None` when there are none left to read.
+    pub fn next<T>(
+        &self,
+        count: usize,
+        ticket: &Ticket,
+        read: impl FnOnce(&mut Rows, Page) -> napi::Result<(T, usize)>,
+        collected: impl FnOnce(CollectedRows) -> napi::Result<(T, usize)>,
+    ) -> napi::Result<Option<(T, usize)>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let open = match &mut *state {
+            State::Open(open) => open,
+            State::Done => return Ok(None),
+            State::Closed => return Err(napi::Error::from_reason("Cursor is closed")),
+        };
+        if let Some(error) = open.failed.take() {
+            open.ahead.clear();
+            open.charge = self.memory.charge();
+            return Err(error);
+        }
+        if count == 0 {
+            return Ok(None);
+        }
+        if open.ahead.is_empty() && open.exhausted {
+            *state = State::Done;
+            return Ok(None);
+        }
+        let (value, read) = if open.ahead.is_empty() {
+            read(&mut open.rows, Page::new(None, Some(count as f64))?)?
+        } else {
+            open.fill(count, Some(ticket))?;
+            collected(open.take(count, &self.memory)?)?
+        };
+        if read < count {
+            *state = State::Done;
+        }
+        Ok(Some((value, read)))
+    }
+
+    /// Read up to `count` rows ahead of the next fetch.
+    pub fn prefetch(&self, count: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let State::Open(open) = &mut *state {
+            if open.failed.is_none() {
+                if let Err(error) = open.fill(count, None) {
+                    open.failed = Some(error);
+                }
+            }
+        }
+    }
+
+    fn close(&self) {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = State::Closed;
+    }
+}
+
+/// `n` of `fetchNext(n)`, checked.
+fn count(n: f64) -> napi::Result<usize> {
+    if n >= 0.0 && n.fract() == 0.0 {
+        Ok(n as usize)
+    } else {
+        Err(napi::Error::from_reason(
+            "fetchNext count must be a non-negative integer",
+        ))
+    }
+}
+
+/// JS handle for an open query, returned by `db.openCursor()`.
+#[napi(js_name = "Cursor")]
+pub struct JsCursor {
+    rows: CursorRowsRef,
+    columns: Vec<String>,
+    worker: WorkerRef,
+    interrupts: InterruptRef,
+    /// Read ahead after async fetches (`prefetch`).
+    prefetch: bool,
+}
+
+impl JsCursor {
+    pub fn new(
+        rows: Rows,
+        worker: WorkerRef,
+        interrupts: InterruptRef,
+        options: Option<CursorOptions>,
+    ) -> napi::Result<Self> {
+        let columns = worker.names().keys(rows.columns())?.into_owned();
+        let (prefetch, fetch_size) = match options {
+            Some(o) => (o.prefetch, o.fetch_size),
+            None => (None, None),
+        };
+        let fetch_size = FetchSize::new(fetch_size::fixed(fetch_size)?);
+        Ok(Self {
+            rows: Arc::new(CursorRows::new(rows, worker.memory(), fetch_size)),
+            columns,
+            prefetch: prefetch.unwrap_or(true),
+            worker,
+            interrupts,
+        })
+    }
+}
+
+#[napi]
+impl JsCursor {
+    /// Keys of the result rows.
+    #[napi(getter)]
+    pub fn columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    /// Rows the next `fetchNext()` without a count will read.
+    #[napi(getter, js_name = "fetchSize")]
+    pub fn fetch_size(&self) -> u32 {
+        self.rows.fetch_size().current() as u32
+    }
+
+    /// The next `n` rows, or fewer once the result runs out; without `n`,
+    /// `fetchSize` rows. Returns Promise<Array<Object>>.
+    #[napi(
+        js_name = "fetchNext",
+        ts_args_type = "n?: number",
+        ts_return_type = "Promise<Record<string, any>[]>"
+    )]
+    pub fn fetch_next(&self, n: Option<f64>) -> napi::Result<Scheduled<CursorFetchTask>> {
+        let count = self.count(n)?;
+        Ok(self.worker.schedule(
+            "fetchNext",
+            CursorFetchTask {
+                rows: Arc::clone(&self.rows),
+                count,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.worker.names(),
+                prefetch: self.prefetch.then(|| self.worker.clone()),
+            },
+        ))
+    }
+
+    /// The next `n` rows synchronously, or `fetchSize` rows without `n`.
+    /// Returns Array<Object>.
+    #[napi(
+        js_name = "fetchNextSync",
+        ts_args_type = "n?: number",
+        ts_return_type = "Record<string, any>[]"
+    )]
+    pub fn fetch_next_sync(&self, env: Env, n: Option<f64>) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("fetchNextSync");
+        let _reviving = self.worker.types().reviving();
+        let names = self.worker.names();
+        let mut bytes = None;
+        let fetched = self.rows.next(
+            self.count(n)?,
+            &self.interrupts.ticket(),
+            |rows, page| next_rows_to_array(env.raw(), rows, false, names, None, page),
+            |rows| {
+                bytes = Some(rows.bytes());
+                let read = rows.row_count();
+                let array = collected_rows_to_array(env.raw(), &rows.column_names(names))?;
+                Ok((array, read))
+            },
+        )?;
+        match fetched {
+            Some((array, read)) => {
+                self.rows.fetch_size().delivered(read, bytes);
+                Ok(RawJsValue(array))
+            }
+            None => empty_array(env.raw()),
+        }
+    }
+
+    /// `n`, or the tuned size without it. Either way the consumer's time
+    /// since the last fetch counts toward the tuning.
+    fn count(&self, n: Option<f64>) -> napi::Result<usize> {
+        let tuned = self.rows.fetch_size().next();
+        n.map_or(Ok(tuned), count)
+    }
+
+    /// Drop the rows not fetched yet. Fetching afterwards throws; closing
+    /// twice is a no-op.
+    #[napi]
+    pub fn close(&self) {
+        self.rows.close();
+    }
+}
+
+/// Have the worker read `count` rows of `rows` ahead, after a fetch that got
+/// as many. Its promise is dropped: reading ahead never rejects.
+pub(crate) fn read_ahead(
+    env: sys::napi_env,
+    worker: &WorkerRef,
+    rows: &CursorRowsRef,
+    count: usize,
+) -> napi::Result<()> {
+    let task = CursorPrefetchTask {
+        rows: Arc::clone(rows),
+        count,
+    };
+    unsafe { Scheduled::to_napi_value(env, worker.schedule("prefetch", task))? };
+    Ok(())
+}
+
+pub(crate) fn empty_array(env: sys::napi_env) -> napi::Result<RawJsValue> {
+    let mut array = std::ptr::null_mut();
+    check(unsafe { sys::napi_create_array(env, &mut array) })?;
+    Ok(RawJsValue(array))
+}
@@ -14,27 +14,293 @@
 
 use napi::bindgen_prelude::*;
 use napi::Env;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use stoolap::api::Database;
-use stoolap::ParamVec;
+use stoolap::{CachedPlanRef, ParamVec};
+use stoolap_bindings_core::timestamps::TimestampFormat;
 
-use crate::error::to_napi;
+use crate::advisor::{ApplyIndexesOptions, IndexAdvisorOptions};
+use crate::analyze::{AutoAnalyze, AutoAnalyzeRef};
+use crate::batch::Batch;
+use crate::bridge::{self, BridgeCall, BridgeTask, Method, Request};
+use crate::bulk::{DeleteMany, DeleteManyOptions};
+use crate::call_site::js_stack;
+use crate::changes::{ChangeEvent, ChangeHub, ChangeHubRef};
+use crate::checkpoint::CheckpointMap;
+use crate::chunks::{self, ChunkStream, QueryChunksOptions};
+use crate::closing::CloseOptions;
+use crate::column_case::ColumnCase;
+use crate::column_names::{ColumnNames, DuplicateColumns};
+use crate::column_stats::column_stats;
+use crate::commit_window;
+use crate::config::{self, Capabilities, ConfigValue, DatabaseDescription, OpenOptions};
+use crate::consistency::{self, TableVersionsRef};
+use crate::cursor::{CursorOptions, JsCursor};
+use crate::diff::{self, DiffDataOptions};
+use crate::dump::{self, Dump, DumpOptions, Sink};
+use crate::error::{from_core, to_napi};
+use crate::faults::FaultInjectionOptions;
+use crate::format::{self, FormatOptions};
+use crate::identifiers;
+use crate::interrupt::InterruptRef;
+use crate::memory::{budget_bytes, MemoryBudgetStats, MemoryUsage};
+use crate::monitor::{TaskEvent, TaskQueueStats};
+use crate::options::{
+    apply_query_options, check_sql_size, check_transaction_options, low_memory, page, parallelism,
+    preview, projection, vectors, PrepareOptions, QueryOptions, TransactionOptions,
+};
+use crate::paging::{MaxRows, Page};
+use crate::parallel::{self, Parallelism};
+use crate::pipeline::JsPipeline;
+use crate::plan_cache::{JsPlanCache, PlanCacheRef};
+use crate::policy::{
+    check_single, check_single_sql, check_sql, check_statement, Policies, Policy, PolicyRef,
+    RestrictedHandleOptions, SqlPolicy,
+};
+use crate::profile::{ProfileEvent, ProfileOptions, Profiler, ProfilerRef};
+use crate::projection::pick;
+use crate::readers::{reader_count, Readers, ReadersRef};
+use crate::result_filter;
+use crate::retry::RetryPolicy;
+use crate::runtime::{self, RuntimeInfo};
+use crate::shape::RowShape;
+use crate::sql::expand_in_lists;
 use crate::statement::JsPreparedStatement;
+use crate::statement_log::{StatementLogEvent, StatementLogOptions};
+use crate::sync_busy::SyncBusyStats;
+use crate::syntax;
 use crate::tasks::*;
+use crate::temp_table::{self, JsTempTable};
+use crate::temporal;
+use crate::tree::{Tree, TreeOptions};
+use crate::tx_memo::QueryMemo;
+use crate::tx_sql::SqlLog;
+use crate::tx_timeout::{
+    idle_transaction_duration, TransactionIdleEvent, TransactionTimeoutEvent, TxTimeoutsRef, Watch,
+};
+use crate::types::{Hook, TypeOptions, Types};
+use crate::usage::{StatementReportOptions, StatementUsageEntry, StatementUsageRef};
+use crate::vacuum::{VacuumOptions, VacuumProgress};
+use crate::validate::{self, Validation};
 use crate::value::{parse_params, parse_positional, BindParams, RawParam};
+use crate::wal_shipping::{self, Segment, WalSegment};
+use crate::window::{TopN, TopNOptions};
+use crate::worker::{Scheduled, WorkerRef};
+use crate::write_queue::WriteQueueStats;
 
 #[napi(js_name = "Database")]
+#[derive(Clone)]
 pub struct JsDatabase {
     db: Arc<Database>,
+    checkpoints: CheckpointMap,
+    changes: ChangeHubRef,
+    policy: PolicyRef,
+    profiler: ProfilerRef,
+    plans: PlanCacheRef,
+    interrupts: InterruptRef,
+    retry: RetryPolicy,
+    worker: WorkerRef,
+    readers: ReadersRef,
+    usage: StatementUsageRef,
+    timeouts: TxTimeoutsRef,
 }
 
 impl JsDatabase {
-    pub fn from_db(db: Database) -> Self {
-        Self { db: Arc::new(db) }
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_db(
+        db: Database,
+        retry: RetryPolicy,
+        analyze: Option<AutoAnalyzeRef>,
+        versions: Option<TableVersionsRef>,
+        worker: WorkerRef,
+        readers: Readers,
+        timeouts: TxTimeoutsRef,
+        policy: Policies,
+    ) -> Self {
+        let db = Arc::new(db);
+        Self {
+            changes: Arc::new(ChangeHub::new(Arc::clone(&db), analyze, versions)),
+            profiler: Arc::new(Profiler::new(Arc::clone(&db))),
+            db,
+            checkpoints: CheckpointMap::default(),
+            policy: Arc::new(policy),
+            plans: PlanCacheRef::default(),
+            interrupts: InterruptRef::default(),
+            retry,
+            worker,
+            readers: Arc::new(readers),
+            usage: StatementUsageRef::default(),
+            timeouts,
+        }
+    }
+
+    /// The JS stack of a `begin()` call, when `idleTransactionMs` needs it.
+    fn begin_stack(&self, env: &Env) -> napi::Result<Option<String>> {
+        if !self.timeouts.watches_idle() {
+            return Ok(None);
+        }
+        js_stack(env).map(Some)
+    }
+
+    /// Mark the handle closed and make the task that closes the engine.
+    pub(crate) fn close_task(&self, options: Option<CloseOptions>) -> napi::Result<CloseTask> {
+        let closer = self.worker.closing().closer(
+            options,
+            self.worker.monitor(),
+            &self.interrupts,
+            !self.worker.is_dedicated(),
+        )?;
+        Ok(CloseTask {
+            db: Arc::clone(&self.db),
+            profiler: Arc::clone(&self.profiler),
+            closer,
+        })
+    }
+
+    /// Throw from `method` on a handle made by `createRestrictedHandle()`,
+    /// for the calls that change settings it shares with the handle it was
+    /// made from.
+    fn unrestricted(&self, method: &str) -> napi::Result<()> {
+        if self.policy.is_restricted() {
+            return Err(napi::Error::from_reason(format!(
+                "{method}() is not available on a restricted handle"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether no async call is pending and no transaction is open, so
+    /// closing would interrupt nothing.
+    pub(crate) fn is_idle(&self) -> bool {
+        let stats = self.worker.monitor().stats();
+        stats.queued + stats.running == 0 && !self.timeouts.any_open()
+    }
+
+    fn plan(&self, sql: &str) -> napi::Result<Option<CachedPlanRef>> {
+        automatic_plan(&self.plans, &self.policy, sql)
+    }
+
+    /// The task of an `execute()` call.
+    fn exec_task(&self, sql: String, params: TaskParams) -> napi::Result<ExecTask> {
+        Ok(ExecTask {
+            db: Arc::clone(&self.db),
+            plan: self.plan(&sql)?,
+            sql,
+            params,
+            changes: Arc::clone(&self.changes),
+            profiler: Arc::clone(&self.profiler),
+            readers: Arc::clone(&self.readers),
+            retry: self.retry,
+            ticket: self.interrupts.ticket(),
+            memory: Arc::clone(self.worker.memory()),
+            names: self.worker.names(),
+        })
+    }
+
+    /// The task of a `query()` call.
+    fn query_task(
+        &self,
+        sql: String,
+        params: TaskParams,
+        options: Option<&QueryOptions>,
+    ) -> napi::Result<QueryTask> {
+        let plan = self.plan(&sql)?;
+        Ok(QueryTask {
+            db: Arc::clone(&self.db),
+            params,
+            profiler: Arc::clone(&self.profiler),
+            readers: Arc::clone(&self.readers),
+            retry: self.retry,
+            ticket: self.interrupts.ticket(),
+            memory: Arc::clone(self.worker.memory()),
+            low_memory: low_memory(options),
+            shape: RowShape::default(),
+            names: self.worker.names(),
+            projection: projection(options)?,
+            page: page(options, self.worker.max_rows())?,
+            token: self.changes.token(&sql, plan.as_ref()),
+            sql,
+            plan,
+        })
+    }
+
+    /// Schedule a call that came in over a port given to `serve()`.
+    pub(crate) fn bridge_task(&self, request: Request) -> napi::Result<Scheduled<BridgeTask>> {
+        let Request {
+            id,
+            method,
+            sql,
+            params,
+        } = request;
+        Ok(match method {
+            Method::Query => {
+                let call = BridgeCall::Query(self.query_task(sql, params, None)?);
+                self.worker.schedule("query", BridgeTask::new(id, call))
+            }
+            Method::Execute => {
+                let tables = self.worker.writes().tables_in(&sql);
+                let call = BridgeCall::Execute(self.exec_task(sql, params)?);
+                self.worker
+                    .schedule_write("execute", tables, BridgeTask::new(id, call))
+            }
+        })
+    }
+
+    /// Schedule a multi-statement script for `exec()` / `queryMulti()`.
+    fn script(&self, method: &'static str, sql: String) -> napi::Result<Scheduled<BatchExecTask>> {
+        check_sql(&self.policy, &sql)?;
+        let tables = self.worker.writes().tables_in(&sql);
+        Ok(self.worker.schedule_write(
+            method,
+            tables,
+            BatchExecTask {
+                db: Arc::clone(&self.db),
+                sql,
+                changes: Arc::clone(&self.changes),
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.worker.names(),
+            },
+        ))
+    }
+
+    /// Run a multi-statement script for `execSync()` / `queryMultiSync()`.
+    fn script_sync(&self, env: &Env, sql: &str) -> napi::Result<RawJsValue> {
+        check_sql(&self.policy, sql)?;
+        let ticket = self.interrupts.ticket();
+        let results = run_script(sql, &self.profiler, &ticket, |sql| {
+            self.changes
+                .exec(sql, &ticket, self.worker.memory())
+                .map(|output| output.column_names(self.worker.names()))
+        })?;
+        Ok(RawJsValue(statement_results(env.raw(), &results)?))
     }
 }
 
+/// The cached plan for a single-statement call in automatic mode, checked
+/// against the policy. `None` sends the SQL to the engine as text.
+pub(crate) fn automatic_plan(
+    plans: &PlanCacheRef,
+    policy: &PolicyRef,
+    sql: &str,
+) -> napi::Result<Option<CachedPlanRef>> {
+    let plan = plans.get_automatic(sql);
+    match plan {
+        Some(ref plan) => {
+            check_single(policy, sql)?;
+            check_statement(policy, &plan.statement)?
+        }
+        None => check_single_sql(policy, sql)?,
+    }
+    Ok(plan)
+}
+
 #[napi]
 impl JsDatabase {
     /// Open a database. Returns a Promise that resolves to a Database instance.
@@ -44,10 +310,94 @@ impl JsDatabase {
     /// - `memory://` for in-memory database
     /// - `file:///path/to/db` for file-based database
     /// - Bare path like `./mydb` for file-based database
+    ///
+    /// `options` sets engine tunables (e.g. `{ sync: 'full' }`) so they apply
+    /// from the first statement; they are equivalent to DSN query parameters.
+    /// `options.retry` retries async auto-committed statements that fail with
+    /// a write conflict; `options.autoAnalyze` refreshes table statistics in
+    /// the background once enough rows changed. `options.dedicatedThread`
+    /// runs the database's async calls in order on a thread of its own;
+    /// `options.readers` runs async read-only queries in parallel;
+    /// `options.serializeWrites` queues async writes to hot tables, and
+    /// `options.memoryBudget` bounds the query results held in memory.
+    /// `options.idleTransactionMs` warns about transactions left idle,
+    /// `options.asyncStackTraces` adds the caller's stack to async errors,
+    /// `options.columnCase` converts column names in results,
+    /// `options.duplicateColumns` decides what colliding names become,
+    /// `options.temporal` returns timestamps as `Temporal.Instant`s,
+    /// `options.timestampPrecision` and `options.timezone` change how they
+    /// are written as strings, and `options.maxRows` caps every result.
     #[napi(ts_return_type = "Promise<Database>")]
-    pub fn open(path: String) -> AsyncTask<OpenTask> {
-        let dsn = translate_path(&path);
-        AsyncTask::new(OpenTask { dsn })
+    pub fn open(
+        env: Env,
+        path: String,
+        options: Option<OpenOptions>,
+    ) -> napi::Result<AsyncTask<OpenTask>> {
+        open_task(&env, &path, options).map(AsyncTask::new)
+    }
+
+    /// Report what this engine and binding support: versions, SQL features,
+    /// registered functions, vector support and binding-level value handling,
+    /// so libraries can feature-detect instead of probing with try/catch.
+    #[napi]
+    pub fn capabilities() -> Capabilities {
+        config::capabilities()
+    }
+
+    /// Report the runtime hosting the addon and how result rows are built:
+    /// with direct V8 calls, or with N-API when the V8 helpers can't be used
+    /// in this runtime.
+    #[napi]
+    pub fn runtime_info(env: Env) -> napi::Result<RuntimeInfo> {
+        runtime::runtime_info(&env)
+    }
+
+    /// Reprint SQL canonically: keywords upper-cased, one clause per line and
+    /// subqueries indented by `options.indent` (default 2 spaces; 0 prints
+    /// each statement on one line). Literals, identifiers and comments are
+    /// kept as written. Throws if the SQL does not parse.
+    #[napi]
+    pub fn format_sql(sql: String, options: Option<FormatOptions>) -> napi::Result<String> {
+        format::format_sql(&sql, options)
+    }
+
+    /// Parse SQL without running it and return its statements' syntax
+    /// trees as plain objects, each node with a `type` naming it. Throws if
+    /// the SQL does not parse.
+    #[napi(ts_return_type = "SyntaxNode[]")]
+    pub fn parse(sql: String) -> napi::Result<serde_json::Value> {
+        syntax::parse(&sql)
+    }
+
+    /// Compare the rows of two database files: what was inserted, updated
+    /// or deleted from `pathA` to `pathB`, table by table, for the tables
+    /// that differ. With `options.format: 'sql'` resolves to the statements
+    /// that turn the first database's rows into the second's instead.
+    #[napi(ts_return_type = "Promise<TableDiff[] | string>")]
+    pub fn diff_data(
+        path_a: String,
+        path_b: String,
+        options: Option<DiffDataOptions>,
+    ) -> napi::Result<AsyncTask<DiffDataTask>> {
+        let (tables, format) = options.map_or((None, None), |o| (o.tables, o.format));
+        Ok(AsyncTask::new(DiffDataTask {
+            a: path_a,
+            b: path_b,
+            tables,
+            sql: diff::wants_sql(format.as_deref())?,
+        }))
+    }
+
+    /// Append WAL segments from `db.exportWalSegments()` to the standby
+    /// database at `path`, which must not be open, creating it if needed.
+    /// The standby replays them when it is next opened. Returns
+    /// Promise<number>: the primary LSN the standby has applied up to.
+    #[napi(ts_return_type = "Promise<number>")]
+    pub fn apply_wal_segments(path: String, segments: Vec<WalSegment>) -> AsyncTask<ApplyWalTask> {
+        AsyncTask::new(ApplyWalTask {
+            path,
+            segments: segments.iter().map(Segment::from).collect(),
+        })
     }
 
     /// Execute a DDL/DML statement. Returns Promise<{ changes: number }>.
@@ -63,31 +413,178 @@ impl JsDatabase {
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<ExecTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(ExecTask {
-            db: Arc::clone(&self.db),
-            sql,
-            params: task_params,
-            plan: None,
-        }))
+    ) -> napi::Result<Scheduled<ExecTask>> {
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let tables = self.worker.writes().tables_in(&sql);
+        Ok(self
+            .worker
+            .schedule_execute(tables, self.exec_task(sql, task_params)?))
     }
 
     /// Execute one or more SQL statements separated by semicolons.
-    /// Returns Promise<void>.
-    #[napi(ts_return_type = "Promise<void>")]
-    pub fn exec(&self, sql: String) -> AsyncTask<BatchExecTask> {
-        AsyncTask::new(BatchExecTask {
-            db: Arc::clone(&self.db),
-            sql,
-        })
+    /// Returns Promise<Array>, one `StatementResult` per statement.
+    #[napi(ts_return_type = "Promise<StatementResult[]>")]
+    pub fn exec(&self, sql: String) -> napi::Result<Scheduled<BatchExecTask>> {
+        self.script("exec", sql)
+    }
+
+    /// Run one or more SQL statements and return every result set. Same as
+    /// `exec()`; returns Promise<Array>, one `StatementResult` per statement.
+    #[napi(js_name = "queryMulti", ts_return_type = "Promise<StatementResult[]>")]
+    pub fn query_multi(&self, sql: String) -> napi::Result<Scheduled<BatchExecTask>> {
+        self.script("queryMulti", sql)
+    }
+
+    /// Execute the same SQL with multiple param sets. Returns Promise<RunResult>.
+    ///
+    /// Like `executeBatchSync()`: the SQL is parsed once and the params are
+    /// converted up front, then every set runs in one transaction off the
+    /// event loop.
+    #[napi(
+        js_name = "executeBatch",
+        ts_args_type = "sql: string, paramsArray: any[][]",
+        ts_return_type = "Promise<RunResult>"
+    )]
+    pub fn execute_batch(
+        &self,
+        env: Env,
+        sql: String,
+        params_array: RawParam,
+    ) -> napi::Result<Scheduled<ExecuteBatchTask>> {
+        let tables = self.worker.writes().tables_in(&sql);
+        let batch = Batch::parse(&env, self.worker.types(), sql, &self.policy, params_array)?;
+        Ok(self.worker.schedule_write(
+            "executeBatch",
+            tables,
+            ExecuteBatchTask {
+                db: Arc::clone(&self.db),
+                batch: Some(batch),
+                changes: Arc::clone(&self.changes),
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+            },
+        ))
+    }
+
+    /// Delete the rows whose key is in `keys`. Returns Promise<{ changes: number }>.
+    ///
+    /// Keys are deleted in `IN` lists of `options.chunkSize` (default 500),
+    /// each committed in its own transaction. `options.keyColumn` defaults to
+    /// the table's primary key.
+    #[napi(
+        js_name = "deleteMany",
+        ts_args_type = "table: string, keys: any[], options?: DeleteManyOptions",
+        ts_return_type = "Promise<RunResult>"
+    )]
+    pub fn delete_many(
+        &self,
+        env: Env,
+        table: String,
+        keys: RawParam,
+        options: Option<DeleteManyOptions>,
+    ) -> napi::Result<Scheduled<DeleteManyTask>> {
+        let tables = self.worker.writes().table_named(&table);
+        let delete = delete_many_args(&env, self.worker.types(), table, keys, options)?;
+        Ok(self.worker.schedule_write(
+            "deleteMany",
+            tables,
+            DeleteManyTask {
+                db: Arc::clone(&self.db),
+                delete: Some(delete),
+                changes: Arc::clone(&self.changes),
+                policy: Arc::clone(&self.policy),
+                profiler: Arc::clone(&self.profiler),
+                plans: Arc::clone(&self.plans),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+            },
+        ))
+    }
+
+    /// Read an adjacency-list table as nested objects. Returns Promise<Array<Object>>.
+    ///
+    /// Walks down from `options.rootId`, or from every row whose
+    /// `options.parentColumn` is NULL, with one recursive CTE. Each node is the
+    /// row plus its children (in id order) under `options.childrenKey`.
+    #[napi(
+        ts_args_type = "table: string, options: TreeOptions",
+        ts_return_type = "Promise<Record<string, any>[]>"
+    )]
+    pub fn tree(
+        &self,
+        env: Env,
+        table: String,
+        options: TreeOptions,
+    ) -> napi::Result<Scheduled<TreeTask>> {
+        Ok(self.worker.schedule(
+            "tree",
+            TreeTask {
+                db: Arc::clone(&self.db),
+                tree: Some(Tree::new(&env, table, options)?),
+                policy: Arc::clone(&self.policy),
+                profiler: Arc::clone(&self.profiler),
+                ticket: self.interrupts.ticket(),
+                names: self.worker.names(),
+            },
+        ))
+    }
+
+    /// Query the first `options.n` rows of each group. Returns Promise<Array<Object>>.
+    ///
+    /// Rows are ranked within each `options.group` by `options.orderBy` with
+    /// a window function, and come back ordered by group, then rank.
+    #[napi(
+        js_name = "topNPerGroup",
+        ts_args_type = "table: string, options: TopNOptions",
+        ts_return_type = "Promise<Record<string, any>[]>"
+    )]
+    pub fn top_n_per_group(
+        &self,
+        table: String,
+        options: TopNOptions,
+    ) -> napi::Result<Scheduled<TopNTask>> {
+        Ok(self.worker.schedule(
+            "topNPerGroup",
+            TopNTask {
+                db: Arc::clone(&self.db),
+                top: TopN::new(table, options)?,
+                policy: Arc::clone(&self.policy),
+                profiler: Arc::clone(&self.profiler),
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.worker.names(),
+            },
+        ))
+    }
+
+    /// Statistics of `table.column` from the engine's ANALYZE data: row
+    /// count, null fraction, distinct values, min and max. Runs ANALYZE on
+    /// the table first when it has none for the column. Returns
+    /// Promise<ColumnStats>.
+    #[napi(js_name = "columnStats", ts_return_type = "Promise<ColumnStats>")]
+    pub fn column_stats(&self, table: String, column: String) -> Scheduled<ColumnStatsTask> {
+        self.worker.schedule(
+            "columnStats",
+            ColumnStatsTask {
+                db: Arc::clone(&self.db),
+                table,
+                column,
+                policy: Arc::clone(&self.policy),
+            },
+        )
     }
 
     /// Query rows. Returns Promise<Array<Object>>.
     ///
     /// Each row is an object with column names as keys.
+    /// Pass `options.asOf` (Date, timestamp string, or transaction id) to read
+    /// the table as it was at that point in MVCC history. With the
+    /// `consistencyTokens` open option, the array has a `consistencyToken`.
     #[napi(
-        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: QueryOptions",
         ts_return_type = "Promise<Record<string, any>[]>"
     )]
     pub fn query(
@@ -95,20 +592,21 @@ impl JsDatabase {
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<QueryTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(QueryTask {
-            db: Arc::clone(&self.db),
-            sql,
-            params: task_params,
-            plan: None,
-        }))
+        options: Option<QueryOptions>,
+    ) -> napi::Result<Scheduled<QueryTask>> {
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let sql = apply_query_options(&env, sql, options.as_ref())?;
+        Ok(self.worker.schedule_on(
+            "query",
+            self.query_task(sql, task_params, options.as_ref())?,
+            parallelism(options.as_ref())?,
+        ))
     }
 
     /// Query a single row. Returns Promise<Object | null>.
     #[napi(
         js_name = "queryOne",
-        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: QueryOptions",
         ts_return_type = "Promise<Record<string, any> | null>"
     )]
     pub fn query_one(
@@ -116,14 +614,30 @@ impl JsDatabase {
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<QueryOneTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(QueryOneTask {
-            db: Arc::clone(&self.db),
-            sql,
-            params: task_params,
-            plan: None,
-        }))
+        options: Option<QueryOptions>,
+    ) -> napi::Result<Scheduled<QueryOneTask>> {
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let sql = apply_query_options(&env, sql, options.as_ref())?;
+        let plan = self.plan(&sql)?;
+        Ok(self.worker.schedule_on(
+            "queryOne",
+            QueryOneTask {
+                db: Arc::clone(&self.db),
+                sql,
+                params: task_params,
+                plan,
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                shape: RowShape::default(),
+                names: self.worker.names(),
+                projection: projection(options.as_ref())?,
+                page: page(options.as_ref(), self.worker.max_rows())?,
+            },
+            parallelism(options.as_ref())?,
+        ))
     }
 
     /// Query rows in raw format. Returns Promise<{ columns: string[], rows: any[][] }>.
@@ -131,22 +645,145 @@ impl JsDatabase {
     /// Faster than query() — skips per-row object creation.
     #[napi(
         js_name = "queryRaw",
-        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
-        ts_return_type = "Promise<{ columns: string[], rows: any[][] }>"
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: QueryOptions",
+        ts_return_type = "Promise<{ columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }>"
     )]
     pub fn query_raw(
         &self,
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<QueryRawTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(QueryRawTask {
-            db: Arc::clone(&self.db),
-            sql,
-            params: task_params,
-            plan: None,
-        }))
+        options: Option<QueryOptions>,
+    ) -> napi::Result<Scheduled<QueryRawTask>> {
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let sql = apply_query_options(&env, sql, options.as_ref())?;
+        let plan = self.plan(&sql)?;
+        let token = self.changes.token(&sql, plan.as_ref());
+        Ok(self.worker.schedule_on(
+            "queryRaw",
+            QueryRawTask {
+                db: Arc::clone(&self.db),
+                sql,
+                params: task_params,
+                plan,
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                low_memory: low_memory(options.as_ref()),
+                names: self.worker.names(),
+                projection: projection(options.as_ref())?,
+                page: page(options.as_ref(), self.worker.max_rows())?,
+                preview: preview(options.as_ref())?,
+                vectors: vectors(options.as_ref())?,
+                token,
+            },
+            parallelism(options.as_ref())?,
+        ))
+    }
+
+    /// Query rows a chunk at a time. Returns Promise<number>, the row count.
+    ///
+    /// Calls `onChunk` with arrays of up to `options.chunkSize` (default 1000)
+    /// row objects, in order, reading the next chunk while it runs, so only
+    /// two chunks are held at once. The promise settles after the last
+    /// callback; if one throws, the query stops and the promise rejects with
+    /// what it threw. `onChunk`'s return value is ignored.
+    #[napi(
+        js_name = "queryChunks",
+        ts_args_type = "sql: string, params: any[] | Record<string, any> | null | undefined, options: QueryChunksOptions | null | undefined, onChunk: (rows: Record<string, any>[]) => void",
+        ts_return_type = "Promise<number>"
+    )]
+    pub fn query_chunks(
+        &self,
+        env: Env,
+        sql: String,
+        params: Option<RawParam>,
+        options: Option<QueryChunksOptions>,
+        on_chunk: Function<Unknown<'static>, Unknown<'static>>,
+    ) -> napi::Result<Scheduled<QueryChunksTask>> {
+        let chunk_size = chunks::chunk_size(options.as_ref())?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let plan = self.plan(&sql)?;
+        let listener = chunks::listener(
+            on_chunk,
+            Arc::clone(self.worker.types()),
+            Arc::clone(&self.db),
+            sql.clone(),
+        )?;
+        Ok(self.worker.schedule(
+            "queryChunks",
+            QueryChunksTask {
+                db: Arc::clone(&self.db),
+                sql,
+                params: task_params,
+                plan,
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.worker.names(),
+                chunk_size,
+                listener: Some(listener),
+            },
+        ))
+    }
+
+    /// Dump the database as a SQL script. Returns Promise<string>, or
+    /// Promise<void> when `onChunk` is given.
+    ///
+    /// The script is CREATE TABLE, CREATE INDEX and CREATE VIEW statements
+    /// followed by INSERTs, read from one snapshot, which `restore()` runs
+    /// back. With `onChunk` the script is passed to it in pieces (the schema,
+    /// then one INSERT of up to 500 rows at a time) instead of being built in
+    /// memory; if it throws, the dump stops and the promise rejects.
+    #[napi(
+        ts_args_type = "options?: DumpOptions | null, onChunk?: (sql: string) => void",
+        ts_return_type = "Promise<string | void>"
+    )]
+    pub fn dump(
+        &self,
+        options: Option<DumpOptions>,
+        on_chunk: Option<Function<Unknown<'static>, Unknown<'static>>>,
+    ) -> napi::Result<Scheduled<DumpTask>> {
+        let sink = match on_chunk {
+            Some(on_chunk) => Sink::Callback(ChunkStream::new(dump::listener(on_chunk)?)),
+            None => Sink::Text(String::new()),
+        };
+        Ok(self.worker.schedule(
+            "dump",
+            DumpTask {
+                db: Arc::clone(&self.db),
+                dump: Dump::new(options),
+                policy: Arc::clone(&self.policy),
+                ticket: self.interrupts.ticket(),
+                sink: Some(sink),
+            },
+        ))
+    }
+
+    /// Run a script from `dump()`. Returns Promise<{ changes: number }>.
+    ///
+    /// Accepts only CREATE TABLE/INDEX/VIEW, INSERT, UPDATE and DELETE. The
+    /// rows go in one transaction; if any statement fails it is rolled back
+    /// and the tables, indexes and views the script created are dropped.
+    #[napi(ts_args_type = "sql: string", ts_return_type = "Promise<RunResult>")]
+    pub fn restore(&self, sql: String) -> napi::Result<Scheduled<RestoreTask>> {
+        let tables = self.worker.writes().tables_in(&sql);
+        Ok(self.worker.schedule_write(
+            "restore",
+            tables,
+            RestoreTask {
+                db: Arc::clone(&self.db),
+                sql,
+                changes: Arc::clone(&self.changes),
+                policy: Arc::clone(&self.policy),
+                readers: Arc::clone(&self.readers),
+                ticket: self.interrupts.ticket(),
+            },
+        ))
     }
 
     // ================================================================
@@ -168,63 +805,217 @@ impl JsDatabase {
         sql: String,
         params: Option<RawParam>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&env, params)?;
-        let changes = task_params.execute_on_db(&self.db, &sql)?;
-        Ok(RawJsValue(v8_run_result(changes)))
+        let _busy = self.worker.busy().time("executeSync");
+        let _reviving = self.worker.types().reviving();
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let plan = self.plan(&sql)?;
+        let timer = Instant::now();
+        let started = self.profiler.start_with(&task_params);
+        let outcome = if returning(plan.as_ref(), &sql) {
+            let rows = self.changes.execute_returning(
+                task_params,
+                &sql,
+                plan.as_ref(),
+                &self.interrupts.ticket(),
+                self.worker.memory(),
+            )?;
+            RunOutcome::returned(timer, rows.column_names(self.worker.names()), 0)
+        } else {
+            let executed = self
+                .changes
+                .execute_on_db(task_params, &sql, plan.as_ref())?;
+            RunOutcome::executed(timer, executed, 0)
+        };
+        self.profiler
+            .finish_write(started, &sql, outcome.changes, plan.as_ref());
+        Ok(RawJsValue(run_result(env.raw(), &outcome)?))
     }
 
     /// Query rows synchronously. Returns Array<Object>.
     /// Uses direct V8 bulk object creation — bypasses NAPI per-property overhead.
     #[napi(
         js_name = "querySync",
-        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: QueryOptions",
         ts_return_type = "Record<string, any>[]"
     )]
     pub fn query_sync(
         &self,
-        _env: Env,
+        env: Env,
         sql: String,
         params: Option<RawParam>,
+        options: Option<QueryOptions>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = task_params.query_on_db(&self.db, &sql)?;
-        Ok(RawJsValue(v8_streaming_rows_to_array(rows)))
+        let _busy = self.worker.busy().time("querySync");
+        let _reviving = self.worker.types().reviving();
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let sql = apply_query_options(&env, sql, options.as_ref())?;
+        let plan = self.plan(&sql)?;
+        let token = self.changes.token(&sql, plan.as_ref());
+        let started = self.profiler.start_with(&task_params);
+        let threads = self.worker.threads(parallelism(options.as_ref())?);
+        let rows = parallel::run(threads, || match plan {
+            Some(ref plan) => task_params
+                .query_plan_on_db(&self.db, plan)
+                .map_err(from_core),
+            None => task_params.query_on_db(&self.db, &sql).map_err(from_core),
+        })?;
+        let projection = projection(options.as_ref())?;
+        let picked = pick(rows.columns(), projection.as_ref())?;
+        let _statement = result_filter::statement(&self.db, &sql, picked.as_deref());
+        let page = page(options.as_ref(), self.worker.max_rows())?;
+        let names = self.worker.names();
+        let projected = projection.is_some();
+        let columns = rows.columns();
+        let shape = result_shape(
+            &RowShape::Object,
+            names,
+            &self.db,
+            plan.as_ref(),
+            &sql,
+            columns,
+            projected,
+        );
+        let (value, count) = match shape {
+            RowShape::Object => streaming_rows_to_array(
+                env.raw(),
+                rows,
+                low_memory(options.as_ref()),
+                names,
+                projection.as_ref(),
+                page,
+            )?,
+            shape => {
+                streaming_rows_to_shape(env.raw(), rows, &shape, names, projection.as_ref(), page)?
+            }
+        };
+        self.profiler
+            .finish(started, &sql, count as i64, plan.as_ref());
+        if let Some(ref token) = token {
+            consistency::attach(env.raw(), value, token)?;
+        }
+        Ok(RawJsValue(value))
     }
 
     /// Query a single row synchronously. Returns Object | null.
     /// Uses direct V8 bulk object creation — optimal hidden class in one call.
     #[napi(
         js_name = "queryOneSync",
-        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: QueryOptions",
         ts_return_type = "Record<string, any> | null"
     )]
     pub fn query_one_sync(
         &self,
-        _env: Env,
+        env: Env,
         sql: String,
         params: Option<RawParam>,
+        options: Option<QueryOptions>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = task_params.query_on_db(&self.db, &sql)?;
-        Ok(RawJsValue(v8_single_row_or_null(rows)))
+        let _busy = self.worker.busy().time("queryOneSync");
+        let _reviving = self.worker.types().reviving();
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let sql = apply_query_options(&env, sql, options.as_ref())?;
+        let plan = self.plan(&sql)?;
+        let started = self.profiler.start_with(&task_params);
+        let threads = self.worker.threads(parallelism(options.as_ref())?);
+        let rows = parallel::run(threads, || match plan {
+            Some(ref plan) => task_params
+                .query_plan_on_db(&self.db, plan)
+                .map_err(from_core),
+            None => task_params.query_on_db(&self.db, &sql).map_err(from_core),
+        })?;
+        let projection = projection(options.as_ref())?;
+        let picked = pick(rows.columns(), projection.as_ref())?;
+        let _statement = result_filter::statement(&self.db, &sql, picked.as_deref());
+        let page = page(options.as_ref(), self.worker.max_rows())?;
+        let names = self.worker.names();
+        let projected = projection.is_some();
+        let columns = rows.columns();
+        let shape = result_shape(
+            &RowShape::Object,
+            names,
+            &self.db,
+            plan.as_ref(),
+            &sql,
+            columns,
+            projected,
+        );
+        let (value, count) = match shape {
+            RowShape::Object => {
+                single_row_or_null(env.raw(), rows, names, projection.as_ref(), page)?
+            }
+            shape => single_row_shaped(env.raw(), rows, &shape, names, projection.as_ref(), page)?,
+        };
+        self.profiler
+            .finish(started, &sql, count as i64, plan.as_ref());
+        Ok(RawJsValue(value))
     }
 
     /// Query rows in raw format synchronously. Returns { columns: string[], rows: any[][] }.
     /// Uses direct V8 bulk array creation — bypasses NAPI per-element overhead.
     #[napi(
         js_name = "queryRawSync",
-        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
-        ts_return_type = "{ columns: string[], rows: any[][] }"
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: QueryOptions",
+        ts_return_type = "{ columns: string[], rows: any[][], preview?: Record<string, any>[], vectors?: Record<string, VectorBuffer> }"
     )]
     pub fn query_raw_sync(
         &self,
-        _env: Env,
+        env: Env,
         sql: String,
         params: Option<RawParam>,
+        options: Option<QueryOptions>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = task_params.query_on_db(&self.db, &sql)?;
-        Ok(RawJsValue(v8_streaming_rows_to_raw(rows)))
+        let _busy = self.worker.busy().time("queryRawSync");
+        let _reviving = self.worker.types().reviving();
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let sql = apply_query_options(&env, sql, options.as_ref())?;
+        let plan = self.plan(&sql)?;
+        let vectors = vectors(options.as_ref())?;
+        let token = self.changes.token(&sql, plan.as_ref());
+        let started = self.profiler.start_with(&task_params);
+        let threads = self.worker.threads(parallelism(options.as_ref())?);
+        let rows = parallel::run(threads, || match plan {
+            Some(ref plan) => task_params
+                .query_plan_on_db(&self.db, plan)
+                .map_err(from_core),
+            None => task_params.query_on_db(&self.db, &sql).map_err(from_core),
+        })?;
+        let projection = projection(options.as_ref())?;
+        let picked = pick(rows.columns(), projection.as_ref())?;
+        let _statement = result_filter::statement(&self.db, &sql, picked.as_deref());
+        let page = page(options.as_ref(), self.worker.max_rows())?;
+        let (value, count) = match vectors {
+            // Packing needs the whole column, so the rows are collected first.
+            Some(mut vectors) => {
+                let ticket = self.interrupts.ticket();
+                let mut collected =
+                    collect_all_rows(rows, &ticket, self.worker.memory(), picked.as_deref(), page)?
+                        .low_memory(low_memory(options.as_ref()))
+                        .column_names(self.worker.names());
+                vectors.pack(&mut collected)?;
+                let columns = collected.columns().to_vec();
+                let count = collected.row_count();
+                let value = collected_rows_to_js(env.raw(), collected, true)?;
+                vectors.attach(env.raw(), value, &columns, self.worker.names())?;
+                (value, count)
+            }
+            None => streaming_rows_to_raw(
+                env.raw(),
+                rows,
+                low_memory(options.as_ref()),
+                self.worker.names(),
+                projection.as_ref(),
+                page,
+            )?,
+        };
+        if let Some(preview) = preview(options.as_ref())? {
+            crate::preview::attach(env.raw(), value, preview, self.worker.names())?;
+        }
+        self.profiler
+            .finish(started, &sql, count as i64, plan.as_ref());
+        if let Some(ref token) = token {
+            consistency::attach(env.raw(), value, token)?;
+        }
+        Ok(RawJsValue(value))
     }
 
     // ================================================================
@@ -245,89 +1036,1038 @@ impl JsDatabase {
         sql: String,
         params_array: RawParam,
     ) -> napi::Result<RawJsValue> {
-        use napi::sys;
-        use stoolap::parser::Parser;
-        let raw_env = env.raw();
-        let arr = params_array.0;
-
-        let mut is_array = false;
-        check(unsafe { sys::napi_is_array(raw_env, arr, &mut is_array) })?;
-        if !is_array {
-            return Err(napi::Error::from_reason("paramsArray must be an array"));
-        }
+        let _busy = self.worker.busy().time("executeBatchSync");
+        let batch = Batch::parse(&env, self.worker.types(), sql, &self.policy, params_array)?;
+        let outcome = batch.run(
+            &self.db,
+            &self.changes,
+            &self.profiler,
+            // Sync calls stay off the reader gate, like the other sync methods.
+            &Readers::default(),
+            // Sync calls don't retry: sleeping would block the event loop.
+            &RetryPolicy::default(),
+            &self.interrupts.ticket(),
+        )?;
+        Ok(RawJsValue(run_result(env.raw(), &outcome)?))
+    }
 
-        let mut len = 0u32;
-        check(unsafe { sys::napi_get_array_length(raw_env, arr, &mut len) })?;
-
-        // Parse SQL once for all executions
-        let mut parser = Parser::new(&sql);
-        let program = parser
-            .parse_program()
-            .map_err(|e| napi::Error::from_reason(e.to_string()))?;
-        let stmt = program
-            .statements
-            .first()
-            .ok_or_else(|| napi::Error::from_reason("No SQL statement found"))?;
-
-        let mut tx = self.db.begin().map_err(to_napi)?;
-        let mut total_changes = 0i64;
-
-        for i in 0..len {
-            let mut elem = std::ptr::null_mut();
-            check(unsafe { sys::napi_get_element(raw_env, arr, i, &mut elem) })?;
-            let params = parse_positional(raw_env, elem)?;
-            total_changes += tx.execute_prepared(stmt, params).map_err(to_napi)?;
-        }
+    /// Delete the rows whose key is in `keys` synchronously, in chunked `IN`
+    /// lists with one transaction per chunk. Returns { changes: number }.
+    #[napi(
+        js_name = "deleteManySync",
+        ts_args_type = "table: string, keys: any[], options?: DeleteManyOptions",
+        ts_return_type = "RunResult"
+    )]
+    pub fn delete_many_sync(
+        &self,
+        env: Env,
+        table: String,
+        keys: RawParam,
+        options: Option<DeleteManyOptions>,
+    ) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("deleteManySync");
+        let delete = delete_many_args(&env, self.worker.types(), table, keys, options)?;
+        let outcome = delete.run(
+            &self.db,
+            &self.changes,
+            &self.policy,
+            &self.profiler,
+            &self.plans,
+            // Sync calls stay off the reader gate, like the other sync methods.
+            &Readers::default(),
+            // Sync calls don't retry: sleeping would block the event loop.
+            &RetryPolicy::default(),
+            &self.interrupts.ticket(),
+        )?;
+        Ok(RawJsValue(run_result(env.raw(), &outcome)?))
+    }
 
-        tx.commit().map_err(to_napi)?;
-        Ok(RawJsValue(v8_run_result(total_changes)))
+    /// Read an adjacency-list table as nested objects synchronously.
+    /// Returns Array<Object>.
+    #[napi(
+        js_name = "treeSync",
+        ts_args_type = "table: string, options: TreeOptions",
+        ts_return_type = "Record<string, any>[]"
+    )]
+    pub fn tree_sync(
+        &self,
+        env: Env,
+        table: String,
+        options: TreeOptions,
+    ) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("treeSync");
+        let _reviving = self.worker.types().reviving();
+        let tree = Tree::new(&env, table, options)?.run(
+            &self.db,
+            &self.policy,
+            &self.profiler,
+            &self.interrupts.ticket(),
+        )?;
+        Ok(RawJsValue(tree.to_js(env.raw(), self.worker.names())?))
+    }
+
+    /// Query the first `options.n` rows of each group synchronously.
+    /// Returns Array<Object>.
+    #[napi(
+        js_name = "topNPerGroupSync",
+        ts_args_type = "table: string, options: TopNOptions",
+        ts_return_type = "Record<string, any>[]"
+    )]
+    pub fn top_n_per_group_sync(
+        &self,
+        env: Env,
+        table: String,
+        options: TopNOptions,
+    ) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("topNPerGroupSync");
+        let _reviving = self.worker.types().reviving();
+        let (sql, params) = TopN::new(table, options)?.sql(&self.db, &self.policy)?;
+        let started = self.profiler.start_positional(&params);
+        let rows = self.db.query(&sql, params).map_err(to_napi)?;
+        let (value, count) = streaming_rows_to_array(
+            env.raw(),
+            rows,
+            false,
+            self.worker.names(),
+            None,
+            Page::default(),
+        )?;
+        self.profiler.finish(started, &sql, count as i64, None);
+        Ok(RawJsValue(value))
+    }
+
+    /// Statistics of `table.column` synchronously; see `columnStats()`.
+    /// Returns ColumnStats.
+    #[napi(js_name = "columnStatsSync", ts_return_type = "ColumnStats")]
+    pub fn column_stats_sync(
+        &self,
+        env: Env,
+        table: String,
+        column: String,
+    ) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("columnStatsSync");
+        let _reviving = self.worker.types().reviving();
+        let stats = column_stats(&self.db, &self.policy, &table, &column)?;
+        Ok(RawJsValue(stats.to_js(env.raw())?))
     }
 
     /// Execute one or more SQL statements synchronously.
-    #[napi(js_name = "execSync")]
-    pub fn exec_sync(&self, sql: String) -> napi::Result<()> {
-        for stmt in crate::tasks::split_sql_statements(&sql) {
-            let trimmed = stmt.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-            self.db.execute(trimmed, ()).map_err(to_napi)?;
-        }
-        Ok(())
+    /// Returns one `StatementResult` per statement.
+    #[napi(js_name = "execSync", ts_return_type = "StatementResult[]")]
+    pub fn exec_sync(&self, env: Env, sql: String) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("execSync");
+        let _reviving = self.worker.types().reviving();
+        self.script_sync(&env, &sql)
+    }
+
+    /// Run one or more SQL statements synchronously and return every result
+    /// set. Same as `execSync()`.
+    #[napi(js_name = "queryMultiSync", ts_return_type = "StatementResult[]")]
+    pub fn query_multi_sync(&self, env: Env, sql: String) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("queryMultiSync");
+        let _reviving = self.worker.types().reviving();
+        self.script_sync(&env, &sql)
+    }
+
+    /// Replace each `{{name}}` in `sql` with the table, view or column
+    /// `identifiers` gives for it, quoted, after checking it against the
+    /// schema: `'orders'`, `'orders.total'`, or `'total'` for a column of any
+    /// table. The result must parse; run it like any other SQL.
+    #[napi(
+        js_name = "bindIdentifiers",
+        ts_args_type = "sql: string, identifiers: Record<string, string>"
+    )]
+    pub fn bind_identifiers(
+        &self,
+        sql: String,
+        identifiers: HashMap<String, String>,
+    ) -> napi::Result<String> {
+        identifiers::bind(&self.db, &sql, &identifiers)
+    }
+
+    /// Parse `sql` without running it and report its syntax errors, each
+    /// with its line and column. Tables and columns aren't checked.
+    #[napi]
+    pub fn validate(&self, sql: String) -> Validation {
+        validate::validate(&sql)
     }
 
     /// Create a prepared statement (synchronous — parses and caches the plan).
+    /// `options.maxSqlBytes` rejects longer SQL before it is parsed.
+    #[napi]
+    pub fn prepare(
+        &self,
+        sql: String,
+        options: Option<PrepareOptions>,
+    ) -> napi::Result<JsPreparedStatement> {
+        check_sql_size(&sql, options.as_ref())?;
+        let plan = self.plans.plan(&self.db, &sql)?;
+        JsPreparedStatement::new(
+            Arc::clone(&self.db),
+            sql,
+            plan,
+            Arc::clone(&self.changes),
+            Arc::clone(&self.policy),
+            Arc::clone(&self.profiler),
+            Arc::clone(&self.plans),
+            Arc::clone(&self.interrupts),
+            self.retry,
+            self.worker.clone(),
+            Arc::clone(&self.readers),
+            &self.usage,
+        )
+    }
+
+    /// Create a prepared statement, parsing the SQL off the JS thread.
+    /// Returns Promise<PreparedStatement>. `options` as for `prepare()`.
+    #[napi(
+        js_name = "prepareAsync",
+        ts_return_type = "Promise<PreparedStatement>"
+    )]
+    pub fn prepare_async(
+        &self,
+        sql: String,
+        options: Option<PrepareOptions>,
+    ) -> napi::Result<Scheduled<PrepareTask>> {
+        check_sql_size(&sql, options.as_ref())?;
+        Ok(self.worker.schedule(
+            "prepareAsync",
+            PrepareTask {
+                db: Arc::clone(&self.db),
+                sql,
+                changes: Arc::clone(&self.changes),
+                policy: Arc::clone(&self.policy),
+                profiler: Arc::clone(&self.profiler),
+                plans: Arc::clone(&self.plans),
+                interrupts: Arc::clone(&self.interrupts),
+                retry: self.retry,
+                worker: self.worker.clone(),
+                readers: Arc::clone(&self.readers),
+                usage: Arc::clone(&self.usage),
+                ticket: self.interrupts.ticket(),
+            },
+        ))
+    }
+
+    /// Prepared statement usage by SQL text, most executed first: how often
+    /// each was prepared and run, and when it last ran. Pass
+    /// `options.unusedForMs` to list only statements that haven't run for
+    /// that long.
+    #[napi(js_name = "statementReport")]
+    pub fn statement_report(
+        &self,
+        options: Option<StatementReportOptions>,
+    ) -> Vec<StatementUsageEntry> {
+        self.usage.report(options.as_ref())
+    }
+
+    /// Start a pipeline: queue statements with `query()` and `execute()`,
+    /// then `run()` them in order in a single async call.
     #[napi]
-    pub fn prepare(&self, sql: String) -> napi::Result<JsPreparedStatement> {
-        JsPreparedStatement::new(Arc::clone(&self.db), sql)
+    pub fn pipeline(&self) -> JsPipeline {
+        JsPipeline::new(
+            Arc::clone(&self.db),
+            Arc::clone(&self.changes),
+            Arc::clone(&self.policy),
+            Arc::clone(&self.profiler),
+            Arc::clone(&self.plans),
+            Arc::clone(&self.interrupts),
+            self.retry,
+            self.worker.clone(),
+            Arc::clone(&self.readers),
+        )
+    }
+
+    /// Run a query and keep its rows, to read them a few at a time with the
+    /// returned cursor's `fetchNext(n)`.
+    #[napi(
+        js_name = "openCursor",
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: CursorOptions"
+    )]
+    pub fn open_cursor(
+        &self,
+        env: Env,
+        sql: String,
+        params: Option<RawParam>,
+        options: Option<CursorOptions>,
+    ) -> napi::Result<JsCursor> {
+        let _busy = self.worker.busy().time("openCursor");
+        let _reviving = self.worker.types().reviving();
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let plan = self.plan(&sql)?;
+        let rows = parallel::run(self.worker.threads(None), || match plan {
+            Some(ref plan) => task_params
+                .query_plan_on_db(&self.db, plan)
+                .map_err(from_core),
+            None => task_params.query_on_db(&self.db, &sql).map_err(from_core),
+        })?;
+        self.profiler.counters().ran();
+        JsCursor::new(
+            rows,
+            self.worker.clone(),
+            Arc::clone(&self.interrupts),
+            options,
+        )
+    }
+
+    /// Create a table only `callback` uses, pass it to `callback` and drop it
+    /// once the promise `callback` returns settles, whether it resolves or
+    /// rejects. `columns` is a string of column definitions or an object of
+    /// column types by name. Returns Promise<T>, settled as `callback` was.
+    #[napi(
+        js_name = "withTempTable",
+        ts_args_type = "columns: string | Record<string, string>, callback: (table: TempTable) => any",
+        ts_return_type = "Promise<any>"
+    )]
+    pub fn with_temp_table(
+        &self,
+        env: Env,
+        columns: Either<String, Object>,
+        callback: Function<'_, JsTempTable, Unknown>,
+    ) -> napi::Result<RawJsValue> {
+        temp_table::run(&env, self, columns, callback.raw())
     }
 
     /// Begin a transaction. Returns Promise<Transaction>.
     #[napi(ts_return_type = "Promise<Transaction>")]
-    pub fn begin(&self) -> AsyncTask<BeginTask> {
-        AsyncTask::new(BeginTask {
-            db: Arc::clone(&self.db),
-        })
+    pub fn begin(
+        &self,
+        env: Env,
+        options: Option<TransactionOptions>,
+    ) -> napi::Result<Scheduled<BeginTask>> {
+        let max_duration = check_transaction_options(options.as_ref())?;
+        let log = SqlLog::new(options.as_ref().and_then(|o| o.record_sql).unwrap_or(false));
+        let memo = QueryMemo::new(options.and_then(|o| o.memoize).unwrap_or(false));
+        let stack = self.begin_stack(&env)?;
+        Ok(self.worker.schedule(
+            "begin",
+            BeginTask {
+                db: Arc::clone(&self.db),
+                changes: Arc::clone(&self.changes),
+                policy: Arc::clone(&self.policy),
+                profiler: Arc::clone(&self.profiler),
+                interrupts: Arc::clone(&self.interrupts),
+                worker: self.worker.clone(),
+                timeouts: Arc::clone(&self.timeouts),
+                max_duration,
+                log,
+                stack,
+                memo,
+            },
+        ))
     }
 
     /// Begin a transaction synchronously. Returns Transaction.
     #[napi(js_name = "beginSync", ts_return_type = "Transaction")]
-    pub fn begin_sync(&self) -> napi::Result<crate::transaction::JsTransaction> {
-        let tx = self.db.begin().map_err(to_napi)?;
-        Ok(crate::transaction::JsTransaction::from_tx(tx))
+    pub fn begin_sync(
+        &self,
+        env: Env,
+        options: Option<TransactionOptions>,
+    ) -> napi::Result<crate::transaction::JsTransaction> {
+        let _busy = self.worker.busy().time("beginSync");
+        let max_duration = check_transaction_options(options.as_ref())?;
+        let log = SqlLog::new(options.as_ref().and_then(|o| o.record_sql).unwrap_or(false));
+        let memo = QueryMemo::new(options.and_then(|o| o.memoize).unwrap_or(false));
+        let stack = self.begin_stack(&env)?;
+        let began = Instant::now();
+        let tx = memo.begin(&self.db).map_err(to_napi)?;
+        crate::transaction::JsTransaction::from_tx(
+            tx,
+            Arc::clone(&self.db),
+            began,
+            Arc::clone(&self.changes),
+            Arc::clone(&self.policy),
+            Arc::clone(&self.profiler),
+            Arc::clone(&self.interrupts),
+            self.worker.clone(),
+            log,
+            memo,
+        )
+        .watch(
+            &self.timeouts,
+            Watch {
+                max_duration,
+                stack,
+            },
+        )
     }
 
-    /// Close the database. Returns Promise<void>.
+    /// Create (or replace) a named checkpoint. Returns Promise<void>.
+    ///
+    /// Only a transaction marker and the current table list are recorded;
+    /// no data is copied. Checkpoints live for the lifetime of this handle.
     #[napi(ts_return_type = "Promise<void>")]
-    pub fn close(&self) -> AsyncTask<CloseTask> {
-        AsyncTask::new(CloseTask {
-            db: Arc::clone(&self.db),
+    pub fn create_checkpoint(&self, name: String) -> napi::Result<Scheduled<CreateCheckpointTask>> {
+        self.unrestricted("createCheckpoint")?;
+        Ok(self.worker.schedule(
+            "createCheckpoint",
+            CreateCheckpointTask {
+                db: Arc::clone(&self.db),
+                checkpoints: Arc::clone(&self.checkpoints),
+                name,
+            },
+        ))
+    }
+
+    /// Roll the checkpointed tables back to their contents at the checkpoint,
+    /// in a single transaction. Returns Promise<void>.
+    ///
+    /// Schema changes are not reverted, and restoring only works while the
+    /// old row versions are retained (see `deleted_row_retention`).
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn restore_checkpoint(
+        &self,
+        name: String,
+    ) -> napi::Result<Scheduled<RestoreCheckpointTask>> {
+        self.unrestricted("restoreCheckpoint")?;
+        Ok(self.worker.schedule(
+            "restoreCheckpoint",
+            RestoreCheckpointTask {
+                db: Arc::clone(&self.db),
+                checkpoints: Arc::clone(&self.checkpoints),
+                name,
+                changes: Arc::clone(&self.changes),
+            },
+        ))
+    }
+
+    /// Append every statement run on this handle (and its statements,
+    /// transactions and pipelines) to the file at `path`, one JSON object
+    /// per line with its SQL, parameters, duration and row count. Replaces
+    /// the file and any recording already running.
+    #[napi]
+    pub fn record(&self, path: String) -> napi::Result<()> {
+        self.unrestricted("record")?;
+        self.profiler.record(&path)
+    }
+
+    /// Stop recording and close the file. Throws the error that stopped the
+    /// recording early, such as a full disk, if one did.
+    #[napi(js_name = "stopRecording")]
+    pub fn stop_recording(&self) -> napi::Result<()> {
+        self.unrestricted("stopRecording")?;
+        self.profiler.stop_recording()
+    }
+
+    /// Call `options.sink` with a `sample` of the statements run on this
+    /// handle (and its statements, transactions and pipelines) that
+    /// complete, each with its SQL, duration and row count, and its
+    /// parameters with `includeParams`. Statements are sampled at random
+    /// when they start. Replaces the sink logged to before.
+    #[napi(js_name = "logStatements")]
+    pub fn log_statements(&self, env: Env, options: StatementLogOptions) -> napi::Result<()> {
+        self.unrestricted("logStatements")?;
+        let sink = options
+            .sink
+            .borrow_back(&env)?
+            .build_threadsafe_function::<StatementLogEvent>()
+            .callee_handled::<false>()
+            .weak::<true>()
+            .build_callback(|ctx| Ok(ctx.value))?;
+        self.profiler.log_statements(
+            sink,
+            options.sample,
+            options.include_params.unwrap_or(false),
+        )
+    }
+
+    /// Stop calling the sink given to `logStatements()`.
+    #[napi(js_name = "stopLoggingStatements")]
+    pub fn stop_logging_statements(&self) -> napi::Result<()> {
+        self.unrestricted("stopLoggingStatements")?;
+        self.profiler.stop_logging_statements();
+        Ok(())
+    }
+
+    /// Run the statements recorded in the file at `path` on this database,
+    /// in order. Returns Promise<ReplayReport> with the time they took now
+    /// and when recorded, overall and per SQL text.
+    ///
+    /// A failing statement is counted and replay goes on with the next one.
+    /// Statements of a recorded transaction run on their own.
+    #[napi(ts_return_type = "Promise<ReplayReport>")]
+    pub fn replay(&self, path: String) -> Scheduled<ReplayTask> {
+        self.worker.schedule(
+            "replay",
+            ReplayTask {
+                db: Arc::clone(&self.db),
+                policy: Arc::clone(&self.policy),
+                readers: Arc::clone(&self.readers),
+                ticket: self.interrupts.ticket(),
+                path,
+            },
+        )
+    }
+
+    /// The plan cache shared by this handle's statements: `stats()`,
+    /// `clear()`, and settable `maxSize` and `automatic`.
+    #[napi(getter)]
+    pub fn plan_cache(&self) -> napi::Result<JsPlanCache> {
+        self.unrestricted("planCache")?;
+        Ok(JsPlanCache::new(Arc::clone(&self.plans)))
+    }
+
+    /// Describe this database for support bundles and bug reports: the DSN
+    /// with credentials redacted, the engine options in effect, and the
+    /// engine/binding versions and build features.
+    #[napi]
+    pub fn describe(&self) -> DatabaseDescription {
+        config::describe(&self.db)
+    }
+
+    /// Read an engine setting, or change it when `value` is given. Returns the
+    /// setting's current value.
+    ///
+    /// Keys are the `EngineOptions` names. `sync`, `snapshotInterval`,
+    /// `keepSnapshots` and `walFlushTrigger` can be changed at runtime; the
+    /// others are fixed once the database is open.
+    #[napi(
+        ts_args_type = "key: keyof EngineOptions, value?: string | number | boolean",
+        ts_return_type = "string | number | boolean"
+    )]
+    pub fn config(&self, key: String, value: Option<ConfigValue>) -> napi::Result<ConfigValue> {
+        if let Some(value) = value {
+            self.unrestricted("config")?;
+            config::set(&self.db, &key, value)?;
+        }
+        config::get(&self.db, &key)
+    }
+
+    /// Restrict the SQL this handle (and its statements and transactions) will
+    /// run. Pass `null` to remove the policy.
+    ///
+    /// - `deny`: statement types to reject, e.g. `['DROP', 'ALTER']`
+    /// - `allowTables`: the only tables and views statements may reference
+    ///
+    /// Statements are checked when parsed, before they reach the engine.
+    #[napi(ts_args_type = "policy: SqlPolicy | null")]
+    pub fn set_policy(&self, policy: Option<SqlPolicy>) -> napi::Result<()> {
+        self.unrestricted("setPolicy")?;
+        let policy = policy.map(Policy::new).transpose()?;
+        self.policy.set(policy);
+        Ok(())
+    }
+
+    /// A handle on this database that only runs the statement types of
+    /// `allow`, such as `['SELECT']`, to hand to code that must not write.
+    /// Statements of other types are rejected when parsed, before they reach
+    /// the engine, on the handle and its statements, transactions and
+    /// pipelines; the policy of `setPolicy()` on this handle applies to it
+    /// too. The calls that would change this handle's settings, such as
+    /// `setPolicy()`, `registerType()` or `close()`, throw on it.
+    #[napi(js_name = "createRestrictedHandle")]
+    pub fn create_restricted_handle(
+        &self,
+        options: RestrictedHandleOptions,
+    ) -> napi::Result<JsDatabase> {
+        Ok(Self {
+            policy: Arc::new(self.policy.restricted(options)?),
+            ..self.clone()
         })
     }
+
+    /// Make this handle's async calls (and those of its statements,
+    /// transactions and pipelines) fail or slow down at random, to test
+    /// error handling. Replaces the faults enabled before.
+    ///
+    /// - `errorRate`: fraction of calls that fail, from 0 to 1
+    /// - `latencyMs`: most milliseconds a call is delayed by
+    #[napi(js_name = "enableFaultInjection")]
+    pub fn enable_fault_injection(&self, options: FaultInjectionOptions) -> napi::Result<()> {
+        self.unrestricted("enableFaultInjection")?;
+        self.worker.faults().enable(options)
+    }
+
+    /// Stop injecting faults.
+    #[napi(js_name = "disableFaultInjection")]
+    pub fn disable_fault_injection(&self) -> napi::Result<()> {
+        self.unrestricted("disableFaultInjection")?;
+        self.worker.faults().disable();
+        Ok(())
+    }
+
+    /// Keep the slowest SELECTs this handle (and its statements,
+    /// transactions and pipelines) runs, for `applyAdvisedIndexes()`.
+    /// Enabling again starts over.
+    ///
+    /// - `slowest`: fingerprints kept, the slowest by mean time
+    /// - `windowMs`: milliseconds a fingerprint is kept after its last call
+    #[napi(js_name = "enableIndexAdvisor")]
+    pub fn enable_index_advisor(&self, options: Option<IndexAdvisorOptions>) -> napi::Result<()> {
+        self.unrestricted("enableIndexAdvisor")?;
+        self.profiler.enable_advisor(options)
+    }
+
+    /// Stop keeping slow queries, and forget those kept.
+    #[napi(js_name = "disableIndexAdvisor")]
+    pub fn disable_index_advisor(&self) -> napi::Result<()> {
+        self.unrestricted("disableIndexAdvisor")?;
+        self.profiler.disable_advisor();
+        Ok(())
+    }
+
+    /// Create the indexes advised for the slow queries kept by the index
+    /// advisor, keeping each only if its query got faster with it. With
+    /// `dryRun` the indexes are listed but not created. On failure the
+    /// indexes created are dropped again, as far as they can be: the error
+    /// names any left in place.
+    /// Returns Promise<AdvisedIndex[]>.
+    #[napi(ts_return_type = "Promise<AdvisedIndex[]>")]
+    pub fn apply_advised_indexes(
+        &self,
+        options: Option<ApplyIndexesOptions>,
+    ) -> Scheduled<ApplyIndexesTask> {
+        self.worker.schedule(
+            "applyAdvisedIndexes",
+            ApplyIndexesTask {
+                db: Arc::clone(&self.db),
+                policy: Arc::clone(&self.policy),
+                profiler: Arc::clone(&self.profiler),
+                dry_run: options.and_then(|o| o.dry_run).unwrap_or(false),
+            },
+        )
+    }
+
+    /// Drop the deleted rows and old row versions no transaction can see
+    /// from every table. With `into`, also write a compacted copy of the
+    /// database, holding only its live rows, to that new file.
+    /// `onProgress` is called after each table. Returns Promise<VacuumReport>.
+    #[napi(ts_return_type = "Promise<VacuumReport>")]
+    pub fn vacuum(
+        &self,
+        env: Env,
+        options: Option<VacuumOptions>,
+    ) -> napi::Result<Scheduled<VacuumTask>> {
+        let (into, on_progress) = options.map_or((None, None), |o| (o.into, o.on_progress));
+        let progress = on_progress
+            .map(|hook| {
+                hook.borrow_back(&env)?
+                    .build_threadsafe_function::<VacuumProgress>()
+                    .callee_handled::<false>()
+                    .weak::<true>()
+                    .build_callback(|ctx| Ok(ctx.value))
+            })
+            .transpose()?;
+        Ok(self.worker.schedule(
+            "vacuum",
+            VacuumTask {
+                db: Arc::clone(&self.db),
+                policy: Arc::clone(&self.policy),
+                readers: Arc::clone(&self.readers),
+                ticket: self.interrupts.ticket(),
+                into,
+                progress,
+            },
+        ))
+    }
+
+    /// The committed WAL entries written after `sinceLsn` (default 0), for
+    /// `Database.applyWalSegments()` to keep a standby copy up to date.
+    /// Pass the last segment's `toLsn` as `sinceLsn` next time. Not
+    /// available on a restricted handle, or while a policy limits the tables
+    /// or denies SELECT, since the entries hold every table's rows.
+    /// Returns Promise<WalSegment[]>.
+    #[napi(ts_return_type = "Promise<WalSegment[]>")]
+    pub fn export_wal_segments(
+        &self,
+        since_lsn: Option<i64>,
+    ) -> napi::Result<Scheduled<ExportWalTask>> {
+        self.unrestricted("exportWalSegments")?;
+        self.policy.check_read_all("exportWalSegments")?;
+        let since = wal_shipping::check_lsn(since_lsn)?;
+        let dir = wal_shipping::database_dir(&self.db)?;
+        Ok(self
+            .worker
+            .schedule("exportWalSegments", ExportWalTask { dir, since }))
+    }
+
+    /// Convert values of a custom type for this handle (and its statements,
+    /// transactions and pipelines).
+    ///
+    /// - `test(value)` picks the parameters `serialize(value)` turns into a
+    ///   supported value
+    /// - `deserialize(value, column)` revives result values, of the
+    ///   `columns` named or every column; returning `undefined` leaves the
+    ///   value to the next type
+    ///
+    /// Types are tried in the order they were registered.
+    #[napi(js_name = "registerType")]
+    pub fn register_type(&self, options: TypeOptions) -> napi::Result<()> {
+        self.unrestricted("registerType")?;
+        self.worker.types().register(options)
+    }
+
+    /// Pass every value of this handle's results (and its statements',
+    /// transactions' and pipelines') through `filter(table, column, value)`
+    /// as the rows are made, and use what it returns. `table` and `column`
+    /// are those the value comes from; `table` is null, and `column` the
+    /// result's key, when they can't be worked out from the query. `null`
+    /// removes the filter.
+    #[napi(
+        js_name = "setResultFilter",
+        ts_args_type = "filter: ((table: string | null, column: string, value: any) => any) | null"
+    )]
+    pub fn set_result_filter(&self, filter: Option<Hook>) -> napi::Result<()> {
+        self.unrestricted("setResultFilter")?;
+        self.worker.types().set_filter(filter);
+        Ok(())
+    }
+
+    /// Register an event listener. Supported events:
+    ///
+    /// - `'change'`: called with `{ op, table, rowId }` for every row written
+    ///   by INSERT/UPDATE/DELETE through this handle, after the change commits.
+    /// - `'profile'`: called with `{ sql, durationMs, rows, plan }` after each
+    ///   statement completes. `options.thresholdMs` skips faster statements and
+    ///   `options.plan` attaches the statement's query plan.
+    /// - `'task'`: called with `{ phase, method, queued, running, waitMs,
+    ///   runMs, ok }` when an async call is enqueued, starts on a thread
+    ///   (`dequeue`) and finishes its work (`complete`).
+    /// - `'transactionTimeout'`: called with `{ maxDurationMs, durationMs }`
+    ///   when a transaction begun with `maxDurationMs` is rolled back for
+    ///   being open too long.
+    /// - `'transactionIdle'`: called with `{ idleMs, openMs, stack }` when
+    ///   a transaction has had no calls for `idleTransactionMs`.
+    #[napi(
+        ts_args_type = "event: 'change' | 'profile' | 'task' | 'transactionTimeout' | 'transactionIdle', listener: (event: any) => void, options?: ProfileOptions"
+    )]
+    pub fn on(
+        &self,
+        event: String,
+        listener: Function<Unknown<'static>, Unknown<'static>>,
+        options: Option<ProfileOptions>,
+    ) -> napi::Result<()> {
+        self.unrestricted("on")?;
+        if options.is_some() && event != "profile" {
+            return Err(napi::Error::from_reason(
+                "Options are only supported for 'profile' listeners",
+            ));
+        }
+        match event.as_str() {
+            "change" => {
+                let listener = listener
+                    .build_threadsafe_function::<ChangeEvent>()
+                    .callee_handled::<false>()
+                    .weak::<true>()
+                    .build_callback(|ctx| Ok(ctx.value))?;
+                self.changes.subscribe(listener);
+            }
+            "profile" => {
+                let listener = listener
+                    .build_threadsafe_function::<ProfileEvent>()
+                    .callee_handled::<false>()
+                    .weak::<true>()
+                    .build_callback(|ctx| Ok(ctx.value))?;
+                self.profiler.subscribe(listener, options);
+            }
+            "task" => {
+                let listener = listener
+                    .build_threadsafe_function::<TaskEvent>()
+                    .callee_handled::<false>()
+                    .weak::<true>()
+                    .build_callback(|ctx| Ok(ctx.value))?;
+                self.worker.monitor().subscribe(listener);
+            }
+            "transactionTimeout" => {
+                let listener = listener
+                    .build_threadsafe_function::<TransactionTimeoutEvent>()
+                    .callee_handled::<false>()
+                    .weak::<true>()
+                    .build_callback(|ctx| Ok(ctx.value))?;
+                self.timeouts.subscribe_timeout(listener);
+            }
+            "transactionIdle" => {
+                let listener = listener
+                    .build_threadsafe_function::<TransactionIdleEvent>()
+                    .callee_handled::<false>()
+                    .weak::<true>()
+                    .build_callback(|ctx| Ok(ctx.value))?;
+                self.timeouts.subscribe_idle(listener);
+            }
+            _ => {
+                return Err(napi::Error::from_reason(format!(
+                    "Unsupported event '{event}'"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Async calls of this handle (and its statements, transactions and
+    /// pipelines) waiting for a thread or running on one.
+    #[napi(getter)]
+    pub fn task_queue(&self) -> TaskQueueStats {
+        self.worker.monitor().stats()
+    }
+
+    /// Queue counters for each table in `serializeWrites`: whether it is
+    /// held, calls waiting for it, and how long calls have waited.
+    #[napi(getter)]
+    pub fn write_queue(&self) -> Vec<WriteQueueStats> {
+        self.worker.writes().stats()
+    }
+
+    /// Limit the query results this handle (and its statements, transactions
+    /// and pipelines) holds in memory at once to `mb` megabytes, or remove
+    /// the limit with `null`. A query whose rows would exceed it fails.
+    #[napi(ts_args_type = "mb: number | null")]
+    pub fn set_memory_budget(&self, mb: Option<f64>) -> napi::Result<()> {
+        self.unrestricted("setMemoryBudget")?;
+        let bytes = mb.map(budget_bytes).transpose()?;
+        self.worker.memory().set_limit(bytes);
+        Ok(())
+    }
+
+    /// The memory budget, the estimated size of the query results held now
+    /// and the number of queries that exceeded the budget.
+    #[napi(getter)]
+    pub fn memory_budget(&self) -> MemoryBudgetStats {
+        self.worker.memory().stats()
+    }
+
+    /// Whether the tables a query result's `consistencyToken` was issued for
+    /// have changed since. Needs the `consistencyTokens` open option.
+    #[napi(js_name = "isStale")]
+    pub fn is_stale(&self, token: String) -> napi::Result<bool> {
+        let Some(versions) = self.changes.versions() else {
+            return Err(napi::Error::from_reason(
+                "isStale needs the consistencyTokens open option",
+            ));
+        };
+        versions.is_stale(&self.db, &token)
+    }
+
+    /// Memory the binding holds for this handle: the query results held now,
+    /// the most held at once and by one call, and the plan cache's SQL, next
+    /// to the engine's WAL buffer.
+    #[napi]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let config = self.db.engine().config();
+        let wal_buffer = if config.persistence.enabled && config.path.is_some() {
+            config.persistence.wal_buffer_size as u64
+        } else {
+            0
+        };
+        self.worker.memory().usage(&self.plans.stats(), wal_buffer)
+    }
+
+    /// How long the `*Sync` methods of this handle (and its statements and
+    /// transactions) have blocked the JS thread: calls, total, p99 and
+    /// slowest, overall and per method.
+    #[napi(getter)]
+    pub fn sync_busy(&self) -> SyncBusyStats {
+        self.worker.busy().stats()
+    }
+
+    /// Statements this handle (and its statements, transactions and
+    /// pipelines) has completed.
+    #[napi(getter)]
+    pub fn query_count(&self) -> i64 {
+        self.profiler.counters().statements()
+    }
+
+    /// Rows changed by the last completed statement that ran for its
+    /// changes, such as an `execute()`.
+    #[napi(getter)]
+    pub fn changes(&self) -> i64 {
+        self.profiler.counters().changes()
+    }
+
+    /// Rows changed by all the statements this handle (and its statements,
+    /// transactions and pipelines) has completed.
+    #[napi(getter)]
+    pub fn total_changes(&self) -> i64 {
+        self.profiler.counters().total_changes()
+    }
+
+    /// False once the database has been closed, through this handle or
+    /// another on the same database.
+    #[napi(getter, js_name = "isOpen")]
+    pub fn is_open(&self) -> bool {
+        !self.worker.closing().is_closed() && self.db.engine().is_open()
+    }
+
+    /// Whether the handle was made by `createRestrictedHandle()`.
+    #[napi(getter, js_name = "isRestricted")]
+    pub fn is_restricted(&self) -> bool {
+        self.policy.is_restricted()
+    }
+
+    /// Check the database can run statements, with a trivial one on a worker
+    /// thread. Resolves to the round-trip time in milliseconds, and rejects
+    /// with a `DatabaseClosed` error once the database is closed. Returns
+    /// Promise<number>.
+    #[napi(ts_return_type = "Promise<number>")]
+    pub fn ping(&self) -> Scheduled<PingTask> {
+        self.worker.schedule(
+            "ping",
+            PingTask {
+                db: Arc::clone(&self.db),
+                started: Instant::now(),
+                closed: false,
+            },
+        )
+    }
+
+    /// Answer the `query()` and `execute()` calls of a `RemoteDatabase` made
+    /// on the other end of `port`, usually from the thread that started this
+    /// worker. The calls run as this handle's own async calls do, and their
+    /// results are encoded off the JS thread. Serves until the port closes.
+    #[napi(ts_args_type = "port: import('node:worker_threads').MessagePort")]
+    pub fn serve(&self, env: Env, port: Object) -> napi::Result<()> {
+        bridge::serve(&env, self.clone(), port)
+    }
+
+    /// Interrupt every async statement issued on this handle (and its
+    /// statements and transactions) that hasn't completed. They reject with an
+    /// `Interrupted` error; statements issued afterwards run normally.
+    ///
+    /// Statements stop at their next check: before they start, between rows
+    /// while results are collected, or between the statements of `exec()`.
+    /// Work already inside the engine, such as a sort or a write, finishes first.
+    #[napi]
+    pub fn interrupt(&self) -> napi::Result<()> {
+        self.unrestricted("interrupt")?;
+        self.interrupts.interrupt();
+        Ok(())
+    }
+
+    /// Close the database, and stop recording. Async calls issued afterwards
+    /// reject with a `DatabaseClosed` error. In `drain` mode (the default)
+    /// the calls issued before wait to finish first; in `abort` mode they
+    /// reject with the same error, those already inside the engine once they
+    /// reach their next check. After `timeoutMs` a drain aborts what is left
+    /// and the database closes without waiting further. Returns Promise<void>.
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn close(&self, options: Option<CloseOptions>) -> napi::Result<Scheduled<CloseTask>> {
+        self.unrestricted("close")?;
+        let task = self.close_task(options)?;
+        Ok(self.worker.schedule("close", task))
+    }
+
+    /// Close the database synchronously, without waiting for pending async
+    /// calls. Async calls issued afterwards reject with a `DatabaseClosed`
+    /// error. Closing twice is a no-op.
+    #[napi(js_name = "closeSync")]
+    pub fn close_sync(&self) -> napi::Result<()> {
+        self.unrestricted("closeSync")?;
+        let _busy = self.worker.busy().time("closeSync");
+        self.worker.closing().close();
+        let recorded = self.profiler.stop_recording();
+        self.db.close().map_err(to_napi)?;
+        recorded
+    }
+}
+
+fn delete_many_args(
+    env: &Env,
+    types: &Types,
+    table: String,
+    keys: RawParam,
+    options: Option<DeleteManyOptions>,
+) -> napi::Result<DeleteMany> {
+    let mut is_array = false;
+    check(unsafe { napi::sys::napi_is_array(env.raw(), keys.0, &mut is_array) })?;
+    if !is_array {
+        return Err(napi::Error::from_reason("keys must be an array"));
+    }
+    let keys = parse_positional(env.raw(), keys.0, types)?;
+    DeleteMany::new(table, keys, options)
+}
+
+/// The task `Database.open()` runs, with its options checked.
+pub(crate) fn open_task(
+    env: &Env,
+    path: &str,
+    options: Option<OpenOptions>,
+) -> napi::Result<OpenTask> {
+    let mut dsn = translate_path(path);
+    let mut retry = RetryPolicy::default();
+    let mut analyze = None;
+    let mut dedicated_thread = false;
+    let mut readers = 0;
+    let mut serialize_writes = Vec::new();
+    let mut memory_budget = None;
+    let mut idle_transaction = None;
+    let mut async_stack_traces = false;
+    let mut column_names = ColumnNames::default();
+    let mut temporal = false;
+    let mut timestamps = None;
+    let mut max_rows = None;
+    let mut commit_window = None;
+    let mut parallelism = None;
+    let mut consistency_tokens = false;
+    let mut strict_statements = false;
+    if let Some(mut options) = options {
+        dedicated_thread = options.dedicated_thread.take().unwrap_or(false);
+        readers = reader_count(options.readers.take())?;
+        serialize_writes = options.serialize_writes.take().unwrap_or_default();
+        memory_budget = options.memory_budget.take().map(budget_bytes).transpose()?;
+        idle_transaction = options
+            .idle_transaction_ms
+            .take()
+            .map(idle_transaction_duration)
+            .transpose()?;
+        async_stack_traces = options.async_stack_traces.take().unwrap_or(false);
+        if let Some(case) = options.column_case.take() {
+            column_names.case = ColumnCase::parse(&case)?;
+        }
+        if let Some(duplicates) = options.duplicate_columns.take() {
+            column_names.duplicates = DuplicateColumns::parse(&duplicates)?;
+        }
+        temporal = options.temporal.take().unwrap_or(false);
+        if temporal && !temporal::available(env.raw())? {
+            return Err(napi::Error::from_reason(
+                "temporal needs the Temporal API: globalThis.Temporal is not defined",
+            ));
+        }
+        timestamps = TimestampFormat::new(
+            options.timestamp_precision.take().as_deref(),
+            options.timezone.take().as_deref(),
+        )
+        .map_err(from_core)?;
+        max_rows = MaxRows::new(
+            options.max_rows.take(),
+            options.on_max_rows.take().as_deref(),
+            None,
+        )?;
+        commit_window = options
+            .commit_window_ms
+            .take()
+            .map(commit_window::commit_window)
+            .transpose()?;
+        parallelism = Parallelism::new(options.parallelism.take())?;
+        consistency_tokens = options.consistency_tokens.take().unwrap_or(false);
+        strict_statements = options.strict_statements.take().unwrap_or(false);
+        retry = RetryPolicy::new(options.retry.take());
+        if let Some(auto_analyze) = options.auto_analyze.take() {
+            analyze = Some(Arc::new(AutoAnalyze::new(auto_analyze)?));
+        }
+        dsn = config::dsn_with_options(dsn, options)?;
+    }
+    Ok(OpenTask {
+        dsn,
+        retry,
+        analyze,
+        dedicated_thread,
+        readers,
+        serialize_writes,
+        memory_budget,
+        idle_transaction,
+        async_stack_traces,
+        column_names,
+        temporal,
+        timestamps,
+        max_rows,
+        commit_window,
+        parallelism,
+        consistency_tokens,
+        strict_statements,
+    })
 }
 
 /// Translate user-friendly paths to Stoolap DSN format.
-fn translate_path(path: &str) -> String {
+pub(crate) fn translate_path(path: &str) -> String {
     let trimmed = path.trim();
     if trimmed.is_empty() || trimmed == ":memory:" {
         "memory://".to_string()
@@ -339,13 +2079,21 @@ fn translate_path(path: &str) -> String {
     }
 }
 
-/// Convert JS params to TaskParams.
-fn convert_params(env: &Env, params: Option<RawParam>) -> napi::Result<TaskParams> {
-    match params {
-        None => Ok(TaskParams::Positional(ParamVec::new())),
-        Some(p) => match parse_params(env.raw(), p.0)? {
-            BindParams::Positional(pos) => Ok(TaskParams::Positional(pos)),
-            BindParams::Named(n) => Ok(TaskParams::Named(n)),
-        },
-    }
+/// Convert JS params to TaskParams, expanding arrays bound to `IN (?)` lists
+/// into the SQL.
+pub(crate) fn convert_params(
+    env: &Env,
+    types: &Types,
+    sql: String,
+    params: Option<RawParam>,
+) -> napi::Result<(String, TaskParams)> {
+    let Some(p) = params else {
+        return Ok((sql, TaskParams::Positional(ParamVec::new())));
+    };
+    let (params, arrays) = parse_params(env.raw(), p.0, types)?;
+    let params = match params {
+        BindParams::Positional(pos) => TaskParams::Positional(pos),
+        BindParams::Named(n) => TaskParams::Named(n),
+    };
+    Ok(expand_in_lists(&sql, &params, &arrays).unwrap_or((sql, params)))
 }
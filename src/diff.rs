@@ -0,0 +1,391 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `Database.diffData()`.
+//
+// Both databases are opened and each table is read in full from both, in
+// primary key order, so the diff holds both copies of a table in memory at
+// once. Rows of a table with a single-column primary key are matched by key:
+// a key only the second database has is inserted, one only the first has is
+// deleted, and one whose other columns differ is updated. A table without
+// such a key has no way to tell an update from a delete and an insert, so
+// its rows are matched by their whole contents, duplicates counted. A table
+// only one database has counts as empty in the other; one whose columns
+// differ between the two is an error, as the rows can't be compared.
+//
+// The SQL form is the statements that turn the first database's rows into
+// the second's, with `sql::literal()` values. A keyless table that lost rows
+// can't delete just some of a set of identical ones, so it is emptied and
+// all of its rows are inserted again.
+//
+// The engine shares one instance per path within a process, so a database
+// the application has open is read as it is now, and it is never closed
+// here: the handles are only dropped.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::ptr;
+
+use napi::sys;
+use stoolap::api::Database;
+use stoolap::Value;
+
+use crate::database::translate_path;
+use crate::error::to_napi;
+use crate::sql::{literal, primary_key, quote_ident, table_columns};
+use crate::tasks::{check, value_to_js};
+
+/// Options for `Database.diffData()`.
+#[napi(object, object_to_js = false)]
+pub struct DiffDataOptions {
+    /// Tables to compare (default every table of either database).
+    pub tables: Option<Vec<String>>,
+    /// `'rows'` resolves to the changed rows of each table, `'sql'` to the
+    /// statements that make the first database's rows the second's
+    /// (default `'rows'`).
+    #[napi(ts_type = "'rows' | 'sql'")]
+    pub format: Option<String>,
+}
+
+/// What changed in one table, from the first database to the second.
+pub struct TableDiff {
+    table: String,
+    columns: Vec<String>,
+    /// Position of the primary key in `columns`.
+    key: Option<usize>,
+    inserted: Vec<Vec<Value>>,
+    /// Each row as the first database has it, then as the second does.
+    updated: Vec<(Vec<Value>, Vec<Value>)>,
+    deleted: Vec<Vec<Value>>,
+    /// Every row of a keyless table that lost rows, for the SQL form.
+    rows: Vec<Vec<Value>>,
+}
+
+/// Whether `format` asks for SQL.
+pub fn wants_sql(format: Option<&str>) -> napi::Result<bool> {
+    match format {
+        None | Some("rows") => Ok(false),
+        Some("sql") => Ok(true),
+        Some(_) => Err(napi::Error::from_reason("format must be 'rows' or 'sql'")),
+    }
+}
+
+/// The tables whose rows differ between the databases at `a` and `b`.
+pub fn diff(a: &str, b: &str, tables: Option<&[String]>) -> napi::Result<Vec<TableDiff>> {
+    let a = open(a)?;
+    let b = open(b)?;
+    let tables = match tables {
+        Some(tables) => {
+            for table in tables {
+                if !exists(&a, table)? && !exists(&b, table)? {
+                    return Err(napi::Error::from_reason(format!(
+                        "Table '{table}' is in neither database"
+                    )));
+                }
+            }
+            tables.to_vec()
+        }
+        None => {
+            let mut names: BTreeSet<String> = table_names(&a)?.into_iter().collect();
+            names.extend(table_names(&b)?);
+            names.into_iter().collect()
+        }
+    };
+
+    let mut diffs = Vec::new();
+    for table in tables {
+        let diff = diff_table(&a, &b, &table)?;
+        if !(diff.inserted.is_empty() && diff.updated.is_empty() && diff.deleted.is_empty()) {
+            diffs.push(diff);
+        }
+    }
+    Ok(diffs)
+}
+
+fn diff_table(a: &Database, b: &Database, table: &str) -> napi::Result<TableDiff> {
+    let (in_a, in_b) = (exists(a, table)?, exists(b, table)?);
+    // The schema comes from the second database unless only the first has
+    // the table.
+    let schema = if in_b { b } else { a };
+    let columns = table_columns(schema, table)?;
+    if in_a && in_b {
+        let mut theirs = table_columns(a, table)?;
+        let mut ours = columns.clone();
+        theirs.sort();
+        ours.sort();
+        if theirs != ours {
+            return Err(napi::Error::from_reason(format!(
+                "Table '{table}' has different columns in the two databases"
+            )));
+        }
+    }
+    let key =
+        primary_key(schema, table)?.and_then(|key| columns.iter().position(|c| *c == key.name));
+    let read = |db: &Database, present: bool| {
+        if present {
+            read_rows(db, table, &columns, key)
+        } else {
+            Ok(Vec::new())
+        }
+    };
+    let before = read(a, in_a)?;
+    let after = read(b, in_b)?;
+
+    let mut diff = TableDiff {
+        table: table.to_string(),
+        columns: columns.clone(),
+        key,
+        inserted: Vec::new(),
+        updated: Vec::new(),
+        deleted: Vec::new(),
+        rows: Vec::new(),
+    };
+    match key {
+        Some(key) => {
+            let kept: HashSet<&Value> = after.iter().map(|row| &row[key]).collect();
+            diff.deleted = before
+                .iter()
+                .filter(|row| !kept.contains(&row[key]))
+                .cloned()
+                .collect();
+            let mut old: HashMap<Value, Vec<Value>> = before
+                .into_iter()
+                .map(|row| (row[key].clone(), row))
+                .collect();
+            for row in after {
+                match old.remove(&row[key]) {
+                    None => diff.inserted.push(row),
+                    Some(previous) if previous != row => diff.updated.push((previous, row)),
+                    Some(_) => {}
+                }
+            }
+        }
+        None => {
+            let mut old: HashMap<Vec<Value>, usize> = HashMap::new();
+            for row in &before {
+                *old.entry(row.clone()).or_insert(0) += 1;
+            }
+            for row in &after {
+                match old.get_mut(row) {
+                    Some(n) if *n > 0 => *n -= 1,
+                    _ => diff.inserted.push(row.clone()),
+                }
+            }
+            for row in before {
+                if let Some(n) = old.get_mut(&row) {
+                    if *n > 0 {
+                        *n -= 1;
+                        diff.deleted.push(row);
+                    }
+                }
+            }
+            if !diff.deleted.is_empty() {
+                diff.rows = after;
+            }
+        }
+    }
+    Ok(diff)
+}
+
+/// The statements that apply `diffs`, each ended by `;`, one per line.
+pub fn to_sql(diffs: &[TableDiff]) -> String {
+    let mut statements = Vec::new();
+    for diff in diffs {
+        let table = quote_ident(&diff.table);
+        let column_list: Vec<String> = diff.columns.iter().map(|c| quote_ident(c)).collect();
+        let insert = |row: &[Value]| {
+            let values: Vec<String> = row.iter().map(literal).collect();
+            format!(
+                "INSERT INTO {table} ({}) VALUES ({});",
+                column_list.join(", "),
+                values.join(", ")
+            )
+        };
+        let Some(key) = diff.key else {
+            if diff.deleted.is_empty() {
+                statements.extend(diff.inserted.iter().map(|row| insert(row)));
+            } else {
+                statements.push(format!("DELETE FROM {table};"));
+                statements.extend(diff.rows.iter().map(|row| insert(row)));
+            }
+            continue;
+        };
+        let key_column = &column_list[key];
+        for row in &diff.deleted {
+            statements.push(format!(
+                "DELETE FROM {table} WHERE {key_column} = {};",
+                literal(&row[key])
+            ));
+        }
+        for (before, after) in &diff.updated {
+            let assignments: Vec<String> = column_list
+                .iter()
+                .zip(before.iter().zip(after))
+                .filter(|(_, (old, new))| old != new)
+                .map(|(column, (_, new))| format!("{column} = {}", literal(new)))
+                .collect();
+            statements.push(format!(
+                "UPDATE {table} SET {} WHERE {key_column} = {};",
+                assignments.join(", "),
+                literal(&after[key])
+            ));
+        }
+        statements.extend(diff.inserted.iter().map(|row| insert(row)));
+    }
+    statements.join("\n")
+}
+
+/// `diffs` as `TableDiff` objects.
+pub fn to_js(env: sys::napi_env, diffs: &[TableDiff]) -> napi::Result<sys::napi_value> {
+    let mut elements = Vec::with_capacity(diffs.len());
+    for diff in diffs {
+        let table = object(env)?;
+        set(env, table, c"table", string(env, &diff.table)?)?;
+        let rows_of = |rows: &mut dyn Iterator<Item = &Vec<Value>>| {
+            let mut objects = Vec::new();
+            for row in rows {
+                objects.push(row_object(env, &diff.columns, row)?);
+            }
+            array(env, &objects)
+        };
+        set(env, table, c"inserted", rows_of(&mut diff.inserted.iter())?)?;
+        let mut updated = Vec::with_capacity(diff.updated.len());
+        for (before, after) in &diff.updated {
+            let update = object(env)?;
+            if let Some(key) = diff.key {
+                set(env, update, c"key", value_to_js(env, &after[key])?)?;
+            }
+            set(
+                env,
+                update,
+                c"before",
+                row_object(env, &diff.columns, before)?,
+            )?;
+            set(
+                env,
+                update,
+                c"after",
+                row_object(env, &diff.columns, after)?,
+            )?;
+            updated.push(update);
+        }
+        set(env, table, c"updated", array(env, &updated)?)?;
+        set(env, table, c"deleted", rows_of(&mut diff.deleted.iter())?)?;
+        elements.push(table);
+    }
+    array(env, &elements)
+}
+
+/// The database at `path`, which must be an existing file database.
+fn open(path: &str) -> napi::Result<Database> {
+    let dsn = translate_path(path);
+    let file = dsn
+        .strip_prefix("file://")
+        .map(|rest| rest.split('?').next().unwrap_or(rest))
+        .filter(|file| !file.is_empty());
+    let Some(file) = file else {
+        return Err(napi::Error::from_reason("diffData needs file paths"));
+    };
+    if !Path::new(file).exists() {
+        return Err(napi::Error::from_reason(format!(
+            "Cannot diff '{path}': it does not exist"
+        )));
+    }
+    Database::open(&dsn).map_err(to_napi)
+}
+
+fn exists(db: &Database, table: &str) -> napi::Result<bool> {
+    db.table_exists(table).map_err(to_napi)
+}
+
+fn table_names(db: &Database) -> napi::Result<Vec<String>> {
+    let mut rows = db.query("SHOW TABLES", ()).map_err(to_napi)?;
+    let mut names = Vec::new();
+    while rows.advance() {
+        if let Some(Value::Text(name)) = rows.current_row().get(0) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Every row of `table`, with `columns` in order, by primary key if any.
+fn read_rows(
+    db: &Database,
+    table: &str,
+    columns: &[String],
+    key: Option<usize>,
+) -> napi::Result<Vec<Vec<Value>>> {
+    let column_list: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+    let mut sql = format!(
+        "SELECT {} FROM {}",
+        column_list.join(", "),
+        quote_ident(table)
+    );
+    if let Some(key) = key {
+        sql.push_str(&format!(" ORDER BY {}", column_list[key]));
+    }
+    let mut rows = db.query(&sql, ()).map_err(to_napi)?;
+    let mut data = Vec::new();
+    while rows.advance() {
+        data.push(rows.current_row().as_slice().to_vec());
+    }
+    Ok(data)
+}
+
+fn row_object(
+    env: sys::napi_env,
+    columns: &[String],
+    row: &[Value],
+) -> napi::Result<sys::napi_value> {
+    let object = object(env)?;
+    for (column, value) in columns.iter().zip(row) {
+        let key = string(env, column)?;
+        let value = value_to_js(env, value)?;
+        check(unsafe { sys::napi_set_property(env, object, key, value) })?;
+    }
+    Ok(object)
+}
+
+fn object(env: sys::napi_env) -> napi::Result<sys::napi_value> {
+    let mut object = ptr::null_mut();
+    check(unsafe { sys::napi_create_object(env, &mut object) })?;
+    Ok(object)
+}
+
+fn array(env: sys::napi_env, elements: &[sys::napi_value]) -> napi::Result<sys::napi_value> {
+    let mut array = ptr::null_mut();
+    check(unsafe { sys::napi_create_array_with_length(env, elements.len(), &mut array) })?;
+    for (i, element) in elements.iter().enumerate() {
+        check(unsafe { sys::napi_set_element(env, array, i as u32, *element) })?;
+    }
+    Ok(array)
+}
+
+fn string(env: sys::napi_env, text: &str) -> napi::Result<sys::napi_value> {
+    let mut value = ptr::null_mut();
+    check(unsafe {
+        sys::napi_create_string_utf8(env, text.as_ptr().cast(), text.len() as isize, &mut value)
+    })?;
+    Ok(value)
+}
+
+fn set(
+    env: sys::napi_env,
+    object: sys::napi_value,
+    name: &std::ffi::CStr,
+    value: sys::napi_value,
+) -> napi::Result<()> {
+    check(unsafe { sys::napi_set_named_property(env, object, name.as_ptr(), value) })
+}
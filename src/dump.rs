@@ -0,0 +1,562 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.dump()` and `db.restore()`.
+//
+// A dump is a SQL script: CREATE TABLE statements built from the engine's
+// schema, with tables after the tables their foreign keys reference, then
+// CREATE INDEX for every index but the primary key's, then the views, then
+// the rows as multi-row INSERTs in table order. The engine's `_sys_` tables
+// are left out, and the names in view bodies that need quotes are quoted
+// again, since the engine keeps the bodies with identifiers unquoted. Every row is read from one
+// snapshot, so the data is consistent across tables. Values are written with
+// `sql::literal()`, except timestamps, which are cast: statements in a
+// transaction don't convert text to TIMESTAMP as auto-committed ones do. An
+// INSERT lists every column, in schema order, since that is all an INSERT in
+// a transaction accepts.
+//
+// `restore` runs the CREATE statements of a script first, in order, then
+// its INSERT, UPDATE and DELETE statements in one transaction. The engine
+// doesn't run DDL in transactions, so the schema is created as it goes, and
+// if any statement fails the data is rolled back and what the restore
+// created is dropped again.
+
+use std::collections::HashSet;
+use std::time::Instant;
+
+use napi::bindgen_prelude::{Function, Unknown};
+use stoolap::api::Database;
+use stoolap::parser::ast::Statement;
+use stoolap::storage::Engine;
+use stoolap::{DataType, IndexType, IsolationLevel, ParamVec, Schema, Value};
+
+use crate::changes::ChangeHubRef;
+use crate::chunks::{ChunkStream, Listener};
+use crate::error::{from_core, to_napi, with_reason};
+use crate::interrupt::Ticket;
+use crate::policy::{check_sql, check_statement, statement_type, PolicyRef};
+use crate::sql::{is_blank, literal, parse_single, quote_ident};
+use crate::tasks::{split_sql_statements, RunOutcome, TaskParams};
+
+/// Prefix of the engine's own tables, left out of a dump.
+const SYSTEM_PREFIX: &str = "_sys_";
+
+/// Rows per INSERT statement of a dump.
+pub const ROWS_PER_INSERT: usize = 500;
+
+/// Options for `dump()`.
+#[napi(object, object_to_js = false)]
+pub struct DumpOptions {
+    /// Dump only these tables, without views (default every table and view).
+    pub tables: Option<Vec<String>>,
+    /// Leave out the rows (default false).
+    pub schema_only: Option<bool>,
+}
+
+/// The `onChunk` callback of `dump()`.
+pub type DumpListener = Listener<String, String>;
+
+/// Wrap `onChunk` so the worker thread can call it with SQL text.
+pub fn listener(
+    on_chunk: Function<Unknown<'static>, Unknown<'static>>,
+) -> napi::Result<DumpListener> {
+    on_chunk
+        .build_threadsafe_function::<String>()
+        .callee_handled::<false>()
+        .build_callback(|ctx| Ok(ctx.value))
+}
+
+/// Where a dump goes: to a callback piece by piece, or into one string.
+pub enum Sink {
+    Callback(ChunkStream<String, String>),
+    Text(String),
+}
+
+impl Sink {
+    fn write(&mut self, sql: String) -> napi::Result<()> {
+        match self {
+            Sink::Callback(stream) => stream.send(sql),
+            Sink::Text(text) => {
+                text.push_str(&sql);
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait for the callback to handle the last piece. Returns the script
+    /// when there is no callback.
+    pub fn finish(self) -> napi::Result<Option<String>> {
+        match self {
+            Sink::Callback(mut stream) => stream.wait().map(|()| None),
+            Sink::Text(text) => Ok(Some(text)),
+        }
+    }
+}
+
+/// A resolved `dump()` call.
+pub struct Dump {
+    tables: Option<Vec<String>>,
+    schema_only: bool,
+}
+
+impl Dump {
+    pub fn new(options: Option<DumpOptions>) -> Self {
+        let options = options.unwrap_or(DumpOptions {
+            tables: None,
+            schema_only: None,
+        });
+        Self {
+            tables: options.tables,
+            schema_only: options.schema_only.unwrap_or(false),
+        }
+    }
+
+    /// Write the script to `sink`: the schema as one piece, then one piece
+    /// per INSERT. Fails before writing anything if `policy` rejects reading
+    /// one of the tables or views.
+    pub fn run(
+        &self,
+        db: &Database,
+        policy: &PolicyRef,
+        ticket: &Ticket,
+        sink: &mut Sink,
+    ) -> napi::Result<()> {
+        // Begun first, so the rows are as of the schema that was read.
+        let mut tx = db
+            .begin_with_isolation(IsolationLevel::SnapshotIsolation)
+            .map_err(to_napi)?;
+        let names = match self.tables {
+            Some(ref tables) => tables.clone(),
+            None => table_names(db)?,
+        };
+        let mut schemas = Vec::with_capacity(names.len());
+        for name in &names {
+            check_sql(policy, &format!("SELECT * FROM {}", quote_ident(name)))?;
+            schemas.push(db.engine().get_table_schema(name).map_err(to_napi)?);
+        }
+        let schemas = referenced_first(schemas);
+
+        let mut schema = String::new();
+        for table in &schemas {
+            schema.push_str(&create_table(table));
+        }
+        for table in &schemas {
+            schema.push_str(&create_indexes(db, table)?);
+        }
+        if self.tables.is_none() {
+            schema.push_str(&create_views(db, policy, &schemas)?);
+        }
+        sink.write(schema)?;
+
+        if self.schema_only {
+            return Ok(());
+        }
+        for table in &schemas {
+            ticket.check()?;
+            let name = quote_ident(&table.table_name);
+            let columns: Vec<String> = table.columns.iter().map(|c| quote_ident(&c.name)).collect();
+            let prefix = format!("INSERT INTO {name} ({}) VALUES\n", columns.join(", "));
+            let mut rows = tx
+                .query(&format!("SELECT * FROM {name}"), ())
+                .map_err(to_napi)?;
+            let mut insert = String::new();
+            let mut count = 0;
+            while rows.advance() {
+                insert.push_str(if count == 0 { &prefix } else { ",\n" });
+                insert.push('(');
+                for (i, value) in rows.current_row().as_slice().iter().enumerate() {
+                    if i > 0 {
+                        insert.push_str(", ");
+                    }
+                    insert.push_str(&dump_literal(value));
+                }
+                insert.push(')');
+                count += 1;
+                if count == ROWS_PER_INSERT {
+                    insert.push_str(";\n");
+                    sink.write(std::mem::take(&mut insert))?;
+                    count = 0;
+                    ticket.check()?;
+                }
+            }
+            if count > 0 {
+                insert.push_str(";\n");
+                sink.write(insert)?;
+            }
+        }
+        tx.rollback().map_err(to_napi)
+    }
+}
+
+/// Every table, in the order the engine lists them, but the engine's own
+/// `_sys_` statistics tables, which ANALYZE creates in the target too.
+fn table_names(db: &Database) -> napi::Result<Vec<String>> {
+    let mut rows = db.query("SHOW TABLES", ()).map_err(to_napi)?;
+    let mut tables = Vec::new();
+    while rows.advance() {
+        if let Some(Value::Text(name)) = rows.current_row().get(0) {
+            let is_system = name
+                .get(..SYSTEM_PREFIX.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(SYSTEM_PREFIX));
+            if !is_system {
+                tables.push(name.to_string());
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/// `tables` reordered so each table comes after the tables its foreign keys
+/// reference, and otherwise in the order given. Tables in a reference cycle
+/// keep their order.
+fn referenced_first<T: std::ops::Deref<Target = Schema>>(mut tables: Vec<T>) -> Vec<T> {
+    let mut ordered = Vec::with_capacity(tables.len());
+    let mut placed = HashSet::new();
+    while !tables.is_empty() {
+        let names: HashSet<String> = tables.iter().map(|t| t.table_name_lower.clone()).collect();
+        let ready = tables.iter().position(|t| {
+            t.foreign_keys.iter().all(|fk| {
+                fk.referenced_table == t.table_name_lower
+                    || placed.contains(&fk.referenced_table)
+                    || !names.contains(&fk.referenced_table)
+            })
+        });
+        let table = tables.remove(ready.unwrap_or(0));
+        placed.insert(table.table_name_lower.clone());
+        ordered.push(table);
+    }
+    ordered
+}
+
+/// CREATE TABLE for `table`. Unique constraints are left to the indexes
+/// that enforce them.
+fn create_table(table: &Schema) -> String {
+    let mut defs: Vec<String> = table
+        .columns
+        .iter()
+        .map(|col| {
+            let mut def = format!("{} {}", quote_ident(&col.name), type_name(col.data_type));
+            if col.data_type == DataType::Vector {
+                def.push_str(&format!("({})", col.vector_dimensions));
+            }
+            if col.primary_key {
+                def.push_str(" PRIMARY KEY");
+                if col.auto_increment {
+                    def.push_str(" AUTO_INCREMENT");
+                }
+            } else if !col.nullable {
+                def.push_str(" NOT NULL");
+            }
+            if let Some(ref default) = col.default_expr {
+                def.push_str(&format!(" DEFAULT {default}"));
+            }
+            if let Some(ref check) = col.check_expr {
+                def.push_str(&format!(" CHECK ({check})"));
+            }
+            def
+        })
+        .collect();
+    for fk in &table.foreign_keys {
+        defs.push(format!(
+            "FOREIGN KEY ({}) REFERENCES {}({}) ON DELETE {} ON UPDATE {}",
+            quote_ident(&fk.column_name),
+            quote_ident(&fk.referenced_table),
+            quote_ident(&fk.referenced_column),
+            fk.on_delete,
+            fk.on_update
+        ));
+    }
+    format!(
+        "CREATE TABLE {} (\n  {}\n);\n",
+        quote_ident(&table.table_name),
+        defs.join(",\n  ")
+    )
+}
+
+fn type_name(data_type: DataType) -> &'static str {
+    match data_type {
+        DataType::Integer => "INTEGER",
+        DataType::Float => "FLOAT",
+        DataType::Boolean => "BOOLEAN",
+        DataType::Timestamp => "TIMESTAMP",
+        DataType::Json => "JSON",
+        DataType::Vector => "VECTOR",
+        DataType::Text | DataType::Null => "TEXT",
+    }
+}
+
+/// CREATE INDEX for each index of `table` but the primary key's and the ones
+/// CREATE TABLE makes for its foreign keys, by name. HNSW tuning parameters
+/// are not kept.
+fn create_indexes(db: &Database, table: &Schema) -> napi::Result<String> {
+    let mut indexes = db
+        .engine()
+        .get_all_indexes(&table.table_name)
+        .map_err(to_napi)?;
+    indexes.sort_by(|a, b| a.name().cmp(b.name()));
+    let foreign_key_indexes: Vec<String> = table
+        .foreign_keys
+        .iter()
+        .map(|fk| format!("fk_{}_{}", table.table_name, fk.column_name).to_lowercase())
+        .collect();
+    let mut sql = String::new();
+    for index in indexes {
+        if foreign_key_indexes.contains(&index.name().to_lowercase()) {
+            continue;
+        }
+        let method = match index.index_type() {
+            IndexType::PrimaryKey => continue,
+            IndexType::BTree => " USING BTREE",
+            IndexType::Hash => " USING HASH",
+            IndexType::Bitmap => " USING BITMAP",
+            IndexType::Hnsw => " USING HNSW",
+            IndexType::MultiColumn => "",
+        };
+        let columns: Vec<String> = index
+            .column_names()
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect();
+        sql.push_str(&format!(
+            "CREATE {}INDEX {} ON {} ({}){method};\n",
+            if index.is_unique() { "UNIQUE " } else { "" },
+            quote_ident(index.name()),
+            quote_ident(&table.table_name),
+            columns.join(", ")
+        ));
+    }
+    Ok(sql)
+}
+
+/// CREATE VIEW for every view, by name.
+fn create_views<T: std::ops::Deref<Target = Schema>>(
+    db: &Database,
+    policy: &PolicyRef,
+    tables: &[T],
+) -> napi::Result<String> {
+    let mut names = db.engine().list_views().map_err(to_napi)?;
+    names.sort();
+    let mut views = Vec::with_capacity(names.len());
+    for name in &names {
+        if let Some(view) = db.engine().get_view(name).map_err(to_napi)? {
+            views.push(view);
+        }
+    }
+    let mut quoted: Vec<&str> = tables
+        .iter()
+        .flat_map(|t| std::iter::once(&t.table_name).chain(t.columns.iter().map(|c| &c.name)))
+        .map(String::as_str)
+        .chain(views.iter().map(|v| v.original_name.as_str()))
+        .filter(|name| !is_plain_ident(name))
+        .collect();
+    // Longest first, so a name isn't quoted in part for a shorter one.
+    quoted.sort_by_key(|name| std::cmp::Reverse(name.len()));
+
+    let mut sql = String::new();
+    for view in &views {
+        check_sql(
+            policy,
+            &format!("SELECT * FROM {}", quote_ident(&view.original_name)),
+        )?;
+        let query = requote(&view.query, &quoted);
+        if parse_single(&query).is_err() {
+            return Err(napi::Error::from_reason(format!(
+                "Cannot dump view '{}': the engine keeps its query as `{}`, which does not parse",
+                view.original_name, view.query
+            )));
+        }
+        sql.push_str(&format!(
+            "CREATE VIEW {} AS {query};\n",
+            quote_ident(&view.original_name)
+        ));
+    }
+    Ok(sql)
+}
+
+/// Whether `name` reads as itself unquoted: letters, digits, `_` and `$`,
+/// not starting with a digit.
+fn is_plain_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(is_ident_char)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// `query`, a view body as the engine keeps it, with each of `names` that
+/// stands alone outside string literals double-quoted again. The engine
+/// writes a view's identifiers back unquoted, so a name with spaces or
+/// quotes in it would no longer parse, or parse as something else.
+fn requote(query: &str, names: &[&str]) -> String {
+    if names.is_empty() {
+        return query.to_string();
+    }
+    let mut out = String::with_capacity(query.len());
+    let mut rest = query;
+    let mut after_ident = false;
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' {
+            // A string literal, with '' for a quote.
+            let end = rest[1..]
+                .match_indices('\'')
+                .map(|(i, _)| i + 2)
+                .find(|&i| !rest[i..].starts_with('\''))
+                .unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            after_ident = false;
+            continue;
+        }
+        let name = names.iter().find(|name| {
+            !after_ident
+                && rest
+                    .get(..name.len())
+                    .is_some_and(|head| head.eq_ignore_ascii_case(name))
+                && !rest[name.len()..].chars().next().is_some_and(is_ident_char)
+        });
+        if let Some(name) = name {
+            out.push_str(&quote_ident(name));
+            rest = &rest[name.len()..];
+            after_ident = false;
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+        after_ident = is_ident_char(c) || c == '"';
+    }
+    out
+}
+
+/// `value` as a literal an INSERT in a transaction stores as the same value.
+fn dump_literal(value: &Value) -> String {
+    match value {
+        Value::Timestamp(_) => format!("CAST({} AS TIMESTAMP)", literal(value)),
+        _ => literal(value),
+    }
+}
+
+/// Something a restore created, to drop if it fails.
+enum Created {
+    Table(String),
+    Index(String, String),
+    View(String),
+}
+
+/// Run a dump script: its CREATE statements, then its data statements in
+/// one transaction. Reports the rows the data statements changed.
+pub fn restore(
+    db: &Database,
+    changes: &ChangeHubRef,
+    policy: &PolicyRef,
+    ticket: &Ticket,
+    sql: &str,
+) -> napi::Result<RunOutcome> {
+    let timer = Instant::now();
+    let mut schema = Vec::new();
+    let mut data = Vec::new();
+    for (i, text) in split_sql_statements(sql).into_iter().enumerate() {
+        let text = text.trim();
+        if is_blank(text) {
+            continue;
+        }
+        let stmt = parse_single(text).map_err(|e| failed_at(i, e))?;
+        check_statement(policy, &stmt).map_err(|e| failed_at(i, e))?;
+        match stmt {
+            Statement::CreateTable(_) | Statement::CreateIndex(_) | Statement::CreateView(_) => {
+                schema.push((i, text, stmt))
+            }
+            Statement::Insert(_) | Statement::Update(_) | Statement::Delete(_) => {
+                data.push((i, text))
+            }
+            _ => {
+                return Err(napi::Error::from_reason(format!(
+                    "Restore statement {}: restore() runs CREATE TABLE, CREATE INDEX, CREATE VIEW, INSERT, UPDATE and DELETE statements, not {}",
+                    i + 1,
+                    statement_type(&stmt)
+                )))
+            }
+        }
+    }
+
+    let mut created = Vec::new();
+    let restored = (|| {
+        for (i, text, stmt) in &schema {
+            ticket.check()?;
+            db.execute(text, ())
+                .map_err(|e| failed_at(*i, to_napi(e)))?;
+            created.push(match stmt {
+                Statement::CreateTable(s) => Created::Table(s.table_name.value.to_string()),
+                Statement::CreateIndex(s) => Created::Index(
+                    s.index_name.value.to_string(),
+                    s.table_name.value.to_string(),
+                ),
+                Statement::CreateView(s) => Created::View(s.view_name.value.to_string()),
+                _ => unreachable!("only CREATE statements are schema statements"),
+            });
+        }
+        let mut tx = db.begin().map_err(to_napi)?;
+        let mut pending = Vec::new();
+        let mut total = 0;
+        for (i, text) in &data {
+            let ran = ticket.check().and_then(|()| {
+                let params = TaskParams::Positional(ParamVec::new());
+                if changes.is_active() {
+                    changes.execute_on_tx(&mut tx, params, text, &mut pending)
+                } else {
+                    params.execute_on_tx(&mut tx, text).map_err(from_core)
+                }
+            });
+            match ran {
+                Ok(changed) => total += changed,
+                Err(e) => {
+                    let _ = tx.rollback();
+                    return Err(failed_at(*i, e));
+                }
+            }
+        }
+        tx.commit().map_err(to_napi)?;
+        changes.emit(pending);
+        Ok(total)
+    })();
+    match restored {
+        Ok(total) => Ok(RunOutcome::new(timer, total)),
+        Err(e) => {
+            drop_created(db, created);
+            Err(e)
+        }
+    }
+}
+
+fn failed_at(i: usize, e: napi::Error) -> napi::Error {
+    let reason = format!("Restore statement {}: {}", i + 1, e.reason);
+    with_reason(e, reason)
+}
+
+/// Drop what a failed restore created, newest first. Dropping a table drops
+/// its indexes.
+fn drop_created(db: &Database, created: Vec<Created>) {
+    for created in created.into_iter().rev() {
+        let sql = match created {
+            Created::Table(table) => format!("DROP TABLE IF EXISTS {}", quote_ident(&table)),
+            Created::Index(index, table) => format!(
+                "DROP INDEX IF EXISTS {} ON {}",
+                quote_ident(&index),
+                quote_ident(&table)
+            ),
+            Created::View(view) => format!("DROP VIEW IF EXISTS {}", quote_ident(&view)),
+        };
+        let _ = db.execute(&sql, ());
+    }
+}
@@ -12,9 +12,216 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use napi::Status;
+// Engine errors and their `cause`.
+//
+// An error from the engine keeps its message, and its `cause` is an `Error`
+// with the engine's own message, the `kind` of engine error (the variant
+// name, e.g. `UniqueConstraint`) and that kind's fields, such as `column` or
+// `rowId`. JS values can only be made on the JS thread: a sync call's cause
+// is made where the error is, and an async call's travels back encoded in
+// the reason of a plain `napi::Error` cause, which `restore()` turns into the
+// JS object when the promise is rejected. A JS cause keeps the encoding in
+// its reason too, so the error can still be sent to another thread whole.
+
+use std::cell::Cell;
+
+use napi::bindgen_prelude::{JsObjectValue, JsValue};
+use napi::{sys, Env, Status};
+
+/// Starts the reason of a cause that `restore()` has yet to decode.
+const ENCODED: &str = "\0stoolap-engine-error\0";
+
+thread_local! {
+    /// The env of the JS thread, set on the JS thread only.
+    static JS_ENV: Cell<Option<sys::napi_env>> = const { Cell::new(None) };
+}
+
+/// Note the thread's env, so errors made on it get a JS cause right away.
+pub fn bind_js_thread(env: &Env) {
+    JS_ENV.with(|cell| cell.set(Some(env.raw())));
+}
 
 /// Convert a stoolap::Error into a napi::Error
 pub fn to_napi(err: stoolap::Error) -> napi::Error {
-    napi::Error::new(Status::GenericFailure, err.to_string())
+    let cause = EngineCause::of(&err);
+    let mut error = napi::Error::new(Status::GenericFailure, cause.message.clone());
+    let encoded = cause.encode();
+    let js = JS_ENV
+        .with(Cell::get)
+        .and_then(|env| cause.to_js(&Env::from_raw(env)).ok())
+        .map(|mut js| {
+            // Thrown as the JS object; the reason is only read on this side.
+            js.reason = encoded.reason.clone();
+            js
+        });
+    error.cause = Some(Box::new(js.unwrap_or(encoded)));
+    error
+}
+
+/// Convert an error of the bindings core into a napi::Error, engine errors
+/// as `to_napi` does.
+pub fn from_core(err: stoolap_bindings_core::Error) -> napi::Error {
+    match err {
+        stoolap_bindings_core::Error::Engine(err) => to_napi(err),
+        other => napi::Error::from_reason(other.to_string()),
+    }
+}
+
+/// `err` with a JS cause in place of an encoded one, on the JS thread.
+pub fn restore(env: &Env, mut err: napi::Error) -> napi::Error {
+    let decoded = err
+        .cause
+        .as_ref()
+        .and_then(|cause| EngineCause::decode(&cause.reason));
+    if let Some(js) = decoded.and_then(|cause| cause.to_js(env).ok()) {
+        err.cause = Some(Box::new(js));
+    }
+    err
+}
+
+/// `err` with another reason, keeping its status and cause.
+pub fn with_reason(mut err: napi::Error, reason: String) -> napi::Error {
+    let mut wrapped = napi::Error::new(err.status, reason);
+    wrapped.cause = err.cause.take();
+    wrapped
+}
+
+/// What `error.cause` holds for an engine error.
+struct EngineCause {
+    message: String,
+    kind: String,
+    fields: Vec<(String, Field)>,
+}
+
+enum Field {
+    Text(String),
+    Number(f64),
+}
+
+impl EngineCause {
+    fn of(err: &stoolap::Error) -> Self {
+        use stoolap::Error as E;
+        use Field::{Number, Text};
+
+        let debug = format!("{err:?}");
+        let kind = debug
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let fields: Vec<(&str, Field)> = match err.clone() {
+            E::TableNotFound(table)
+            | E::TableAlreadyExists(table)
+            | E::TableOrViewNotFound(table) => vec![("table", Text(table))],
+            E::TableColumnsNotMatch { expected, got } => vec![
+                ("expected", Number(expected as f64)),
+                ("got", Number(got as f64)),
+            ],
+            E::ColumnNotFound(column) | E::NotNullConstraint { column } => {
+                vec![("column", Text(column))]
+            }
+            E::VectorDimensionMismatch { expected, got } => vec![
+                ("expected", Number(expected as f64)),
+                ("got", Number(got as f64)),
+            ],
+            E::ValueTooLong { column, max, got } => vec![
+                ("column", Text(column)),
+                ("max", Number(max as f64)),
+                ("got", Number(got as f64)),
+            ],
+            E::PrimaryKeyConstraint { row_id } => vec![("rowId", Number(row_id as f64))],
+            E::UniqueConstraint {
+                index,
+                column,
+                value,
+            } => vec![
+                ("index", Text(index)),
+                ("column", Text(column)),
+                ("value", Text(value)),
+            ],
+            E::CheckConstraintViolation { column, expression } => {
+                vec![("column", Text(column)), ("expression", Text(expression))]
+            }
+            E::ForeignKeyViolation {
+                table,
+                column,
+                ref_table,
+                ref_column,
+                detail,
+            } => vec![
+                ("table", Text(table)),
+                ("column", Text(column)),
+                ("refTable", Text(ref_table)),
+                ("refColumn", Text(ref_column)),
+                ("detail", Text(detail)),
+            ],
+            E::IndexNotFound(index) | E::IndexAlreadyExists(index) => {
+                vec![("index", Text(index))]
+            }
+            E::ViewAlreadyExists(view) | E::ViewNotFound(view) => vec![("view", Text(view))],
+            E::InvalidArgument(detail)
+            | E::LockAcquisitionFailed(detail)
+            | E::NotSupported(detail)
+            | E::Parse(detail)
+            | E::Type(detail)
+            | E::ExpressionEvaluationWithMessage { message: detail }
+            | E::Io { message: detail }
+            | E::Internal { message: detail } => vec![("detail", Text(detail))],
+            E::ColumnIndexOutOfBounds { index } => vec![("index", Number(index as f64))],
+            E::TypeConversion { from, to } => vec![("from", Text(from)), ("to", Text(to))],
+            _ => Vec::new(),
+        };
+        Self {
+            message: err.to_string(),
+            kind,
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        }
+    }
+
+    /// The JS `Error`, as a napi::Error that throws it as is.
+    fn to_js(&self, env: &Env) -> napi::Result<napi::Error> {
+        let mut error = env.create_error(napi::Error::from_reason(self.message.as_str()))?;
+        error.set_named_property("kind", self.kind.as_str())?;
+        for (name, value) in &self.fields {
+            match value {
+                Field::Text(text) => error.set_named_property(name, text.as_str())?,
+                Field::Number(number) => error.set_named_property(name, *number)?,
+            }
+        }
+        Ok(napi::Error::from(error.to_unknown()))
+    }
+
+    /// A cause carrying the details as text, for another thread.
+    fn encode(&self) -> napi::Error {
+        let mut reason = format!("{ENCODED}{}\0{}", self.message, self.kind);
+        for (name, value) in &self.fields {
+            match value {
+                Field::Text(text) => reason.push_str(&format!("\0{name}\0s{text}")),
+                Field::Number(number) => reason.push_str(&format!("\0{name}\0n{number}")),
+            }
+        }
+        napi::Error::new(Status::GenericFailure, reason)
+    }
+
+    fn decode(reason: &str) -> Option<Self> {
+        let mut parts = reason.strip_prefix(ENCODED)?.split('\0');
+        let message = parts.next()?.to_string();
+        let kind = parts.next()?.to_string();
+        let mut fields = Vec::new();
+        while let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            let value = match value.split_at_checked(1)? {
+                ("n", number) => Field::Number(number.parse().ok()?),
+                (_, text) => Field::Text(text.to_string()),
+            };
+            fields.push((name.to_string(), value));
+        }
+        Some(Self {
+            message,
+            kind,
+            fields,
+        })
+    }
 }
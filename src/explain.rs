@@ -0,0 +1,258 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Structured EXPLAIN output for prepared statements.
+//
+// The engine renders plans as indented text rows. The prepared statement's
+// cached AST is wrapped in an EXPLAIN node and run through the same plan
+// path, and the rows are parsed back into a tree: `-> ...` lines and nested
+// statement headers are nodes, `Key: value` lines are properties of the
+// nearest shallower node, and `(cost=.. rows=..)` / `(actual ...)` suffixes
+// become numeric fields.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use stoolap::api::Database;
+use stoolap::parser::ast::{ExplainStatement, Statement};
+use stoolap::parser::{Position, Token, TokenType};
+use stoolap::{CachedPlanRef, Value};
+
+use crate::error::from_core;
+use crate::tasks::TaskParams;
+
+/// One operator in a query plan.
+#[napi(object, object_from_js = false, use_nullable = true)]
+#[derive(Clone)]
+pub struct PlanNode {
+    /// Operator description, e.g. `SELECT` or `Index Scan using idx on t`.
+    pub operation: String,
+    /// `Key: value` lines, e.g. `Filter`, `Index Cond`, `Join Cond`.
+    pub properties: HashMap<String, String>,
+    /// Other detail lines, e.g. the key condition of a PK lookup.
+    pub details: Vec<String>,
+    /// Planner row estimate, when the engine reports one.
+    pub estimated_rows: Option<f64>,
+    /// Planner cost estimate, when the engine reports one.
+    pub estimated_cost: Option<f64>,
+    /// Rows produced (explainAnalyze only).
+    pub actual_rows: Option<f64>,
+    /// Execution time in milliseconds (explainAnalyze only, statement nodes).
+    pub actual_time_ms: Option<f64>,
+    pub children: Vec<PlanNode>,
+}
+
+/// Result of `stmt.explain()` / `stmt.explainAnalyze()`.
+#[napi(object, object_from_js = false, use_nullable = true)]
+#[derive(Clone)]
+pub struct QueryPlan {
+    /// Root node: the statement itself.
+    pub plan: PlanNode,
+    /// Whether the statement was executed to collect actual statistics.
+    pub analyzed: bool,
+    /// The engine's EXPLAIN text, one line per row.
+    pub text: Vec<String>,
+    /// Threads the plan's parallel scans ran on, with `explainAnalyze()`;
+    /// null when none ran in parallel. Plain EXPLAIN doesn't show parallel
+    /// scans, and parallel sorts, joins and aggregations never show.
+    pub parallel_workers: Option<u32>,
+}
+
+/// Explain the statement behind `plan`. With `analyze`, the statement is
+/// executed (including any writes it makes) to collect actual statistics.
+pub fn explain(
+    db: &Database,
+    plan: &CachedPlanRef,
+    params: &TaskParams,
+    analyze: bool,
+) -> napi::Result<QueryPlan> {
+    let statement = Statement::Explain(ExplainStatement {
+        token: Token::new(TokenType::Keyword, "EXPLAIN", Position::new(0, 1, 1)),
+        statement: Box::new((*plan.statement).clone()),
+        analyze,
+    });
+    // Plain EXPLAIN never evaluates parameters, so they are optional there.
+    let explain_plan = CachedPlanRef {
+        statement: Arc::new(statement),
+        has_params: analyze && plan.has_params,
+        param_count: plan.param_count,
+        compiled: Default::default(),
+    };
+    let mut rows = params
+        .query_plan_on_db(db, &explain_plan)
+        .map_err(from_core)?;
+    let mut text = Vec::new();
+    while rows.advance() {
+        if let Some(Value::Text(line)) = rows.current_row().get(0) {
+            text.push(line.to_string());
+        }
+    }
+    let plan =
+        parse_plan(&text).ok_or_else(|| napi::Error::from_reason("EXPLAIN returned no plan"))?;
+    let parallel_workers = text.iter().filter_map(|line| workers(line)).max();
+    Ok(QueryPlan {
+        plan,
+        analyzed: analyze,
+        text,
+        parallel_workers,
+    })
+}
+
+/// The `workers=n` of a `Parallel Seq Scan on t (workers=n)` line.
+fn workers(line: &str) -> Option<u32> {
+    let rest = &line[line.find("Parallel ")?..];
+    let rest = &rest[rest.find("(workers=")? + "(workers=".len()..];
+    rest[..rest.find(')')?].parse().ok()
+}
+
+/// Statement headers that start a node without a `->` marker.
+const STATEMENT_HEADERS: &[&str] = &[
+    "SELECT",
+    "INSERT INTO",
+    "UPDATE",
+    "DELETE FROM",
+    "WITH",
+    "Statement:",
+];
+
+fn parse_plan(lines: &[String]) -> Option<PlanNode> {
+    // Open nodes from the root down, with the column each one starts at.
+    let mut stack: Vec<(usize, PlanNode)> = Vec::new();
+    for line in lines {
+        let content = line.trim_start();
+        if content.is_empty() {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        let node = match content.strip_prefix("-> ") {
+            Some(op) => Some(op),
+            None if stack.is_empty() || is_statement_header(content) => Some(content),
+            None => None,
+        };
+        match node {
+            Some(op) => {
+                while stack.len() > 1 && stack.last().is_some_and(|(col, _)| *col >= indent) {
+                    pop_into_parent(&mut stack);
+                }
+                stack.push((indent, new_node(op)));
+            }
+            None => {
+                while stack.len() > 1 && stack.last().is_some_and(|(col, _)| *col >= indent) {
+                    pop_into_parent(&mut stack);
+                }
+                let Some((_, parent)) = stack.last_mut() else {
+                    continue;
+                };
+                match content.split_once(": ") {
+                    Some((key, value)) if is_property_key(key) => {
+                        parent.properties.insert(key.to_string(), value.to_string());
+                    }
+                    _ => parent.details.push(content.to_string()),
+                }
+            }
+        }
+    }
+    while stack.len() > 1 {
+        pop_into_parent(&mut stack);
+    }
+    stack.pop().map(|(_, node)| node)
+}
+
+fn pop_into_parent(stack: &mut Vec<(usize, PlanNode)>) {
+    if let Some((_, node)) = stack.pop() {
+        if let Some((_, parent)) = stack.last_mut() {
+            parent.children.push(node);
+        }
+    }
+}
+
+fn is_statement_header(content: &str) -> bool {
+    STATEMENT_HEADERS.iter().any(|h| {
+        content
+            .strip_prefix(h)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '(']))
+    })
+}
+
+/// `Index Cond`, `Group By`, ...: capitalized words only.
+fn is_property_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_uppercase())
+        && key.chars().all(|c| c.is_ascii_alphabetic() || c == ' ')
+}
+
+/// Build a node from an operator line, lifting out `(cost=.. rows=..)` and
+/// `(actual time=.., rows=..)` annotations.
+fn new_node(text: &str) -> PlanNode {
+    let mut node = PlanNode {
+        operation: String::new(),
+        properties: HashMap::new(),
+        details: Vec::new(),
+        estimated_rows: None,
+        estimated_cost: None,
+        actual_rows: None,
+        actual_time_ms: None,
+        children: Vec::new(),
+    };
+    let mut operation = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('(') {
+        let Some(len) = rest[start..].find(')') else {
+            break;
+        };
+        let inner = &rest[start + 1..start + len];
+        operation.push_str(&rest[..start]);
+        if !apply_stats(&mut node, inner) {
+            operation.push_str(&rest[start..=start + len]);
+        }
+        rest = &rest[start + len + 1..];
+    }
+    operation.push_str(rest);
+    node.operation = operation.split_whitespace().collect::<Vec<_>>().join(" ");
+    node
+}
+
+/// Apply a parenthesized stats annotation; false if `inner` is not one.
+fn apply_stats(node: &mut PlanNode, inner: &str) -> bool {
+    let (actual, fields) = match inner.strip_prefix("actual ") {
+        Some(fields) => (true, fields),
+        None if inner.starts_with("cost=") => (false, inner),
+        None => return false,
+    };
+    for field in fields.split([',', ' ']).filter(|f| !f.is_empty()) {
+        let Some((key, value)) = field.split_once('=') else {
+            continue;
+        };
+        match (actual, key) {
+            (true, "rows") => node.actual_rows = value.parse().ok(),
+            (true, "time") => node.actual_time_ms = parse_duration_ms(value),
+            (false, "rows") => node.estimated_rows = value.parse().ok(),
+            (false, "cost") => node.estimated_cost = value.parse().ok(),
+            _ => {}
+        }
+    }
+    true
+}
+
+/// `339.00µs`, `4.38ms`, `1.20s` -> milliseconds.
+fn parse_duration_ms(value: &str) -> Option<f64> {
+    if let Some(us) = value.strip_suffix("µs") {
+        us.parse::<f64>().ok().map(|v| v / 1000.0)
+    } else if let Some(ms) = value.strip_suffix("ms") {
+        ms.parse().ok()
+    } else if let Some(s) = value.strip_suffix('s') {
+        s.parse::<f64>().ok().map(|v| v * 1000.0)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.enableFaultInjection()`, for testing how an application copes with a
+// failing database.
+//
+// While enabled, every async call of the database, its statements,
+// transactions and pipelines first waits a random time up to `latencyMs` on
+// its thread, then fails with `errorRate` probability before reaching the
+// engine, so a failed call changes nothing. The check runs where the task
+// monitor sees the call start (see monitor.rs), so queue metrics and task
+// events include injected failures. Sync calls are left alone, and so is
+// `close()`, so a test can always clean up.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub type FaultsRef = Arc<Faults>;
+
+/// Options for `db.enableFaultInjection()`.
+#[napi(object, object_to_js = false)]
+pub struct FaultInjectionOptions {
+    /// Fraction of calls that fail, from 0 to 1 (default 0).
+    pub error_rate: Option<f64>,
+    /// Most milliseconds a call is delayed by; each waits a random time up
+    /// to it (default 0).
+    pub latency_ms: Option<f64>,
+}
+
+#[derive(Clone, Copy)]
+struct Config {
+    error_rate: f64,
+    latency_ms: f64,
+}
+
+/// The faults a handle injects, shared with what is created from it.
+#[derive(Default)]
+pub struct Faults {
+    enabled: AtomicBool,
+    config: Mutex<Option<Config>>,
+}
+
+impl Faults {
+    pub fn enable(&self, options: FaultInjectionOptions) -> napi::Result<()> {
+        let error_rate = options.error_rate.unwrap_or(0.0);
+        if !(0.0..=1.0).contains(&error_rate) {
+            return Err(napi::Error::from_reason(
+                "errorRate must be a number from 0 to 1",
+            ));
+        }
+        let latency_ms = options.latency_ms.unwrap_or(0.0);
+        if !(latency_ms >= 0.0 && latency_ms.is_finite()) {
+            return Err(napi::Error::from_reason(
+                "latencyMs must be a non-negative number",
+            ));
+        }
+        *lock(&self.config) = Some(Config {
+            error_rate,
+            latency_ms,
+        });
+        self.enabled.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Release);
+        *lock(&self.config) = None;
+    }
+
+    /// Delay or fail the call to `method`, on the thread about to run it.
+    pub fn inject(&self, method: &str) -> napi::Result<()> {
+        if !self.enabled.load(Ordering::Acquire) || method == "close" {
+            return Ok(());
+        }
+        let Some(config) = *lock(&self.config) else {
+            return Ok(());
+        };
+        if config.latency_ms > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(
+                random() * config.latency_ms / 1000.0,
+            ));
+        }
+        if config.error_rate > 0.0 && random() < config.error_rate {
+            return Err(napi::Error::from_reason(format!(
+                "Injected fault in {method}()"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A random number in `[0, 1)`.
+fn random() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,125 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// How many rows `cursor.fetchNext()` reads when not told.
+//
+// Small batches pay the cost of a call, and of a trip to the worker, for few
+// rows; large ones hold more memory and make the consumer wait longer for
+// the first row. The cursor starts at `FIRST` rows and then sizes each batch
+// from two averages: the estimated width of the rows it has collected, so a
+// batch holds about `TARGET_BYTES`, and the time the consumer took per row
+// between getting a batch and asking for the next, so a batch is about
+// `TARGET_MS` of its work, which is also the time the worker has to read the
+// next one ahead. The smaller wins, within `MIN` and `MAX`. The `fetchSize`
+// cursor option turns this off.
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// The first batch, before anything is known.
+const FIRST: usize = 256;
+const MIN: usize = 16;
+const MAX: usize = 10_000;
+const TARGET_BYTES: f64 = 1024.0 * 1024.0;
+const TARGET_MS: f64 = 50.0;
+/// Weight of the newest sample in the averages.
+const SMOOTHING: f64 = 0.5;
+
+/// Validate the `fetchSize` cursor option.
+pub fn fixed(fetch_size: Option<f64>) -> napi::Result<Option<usize>> {
+    match fetch_size {
+        None => Ok(None),
+        Some(n) if n >= 1.0 && n.fract() == 0.0 => Ok(Some(n as usize)),
+        Some(_) => Err(napi::Error::from_reason(
+            "fetchSize must be a positive integer",
+        )),
+    }
+}
+
+/// A cursor's batch size, fixed or tuned as it goes.
+pub struct FetchSize {
+    fixed: Option<usize>,
+    tuning: Mutex<Tuning>,
+}
+
+#[derive(Default)]
+struct Tuning {
+    /// Average bytes per row collected.
+    row_bytes: Option<f64>,
+    /// Average consumer milliseconds per row.
+    row_ms: Option<f64>,
+    /// When the last batch was handed over, and its row count.
+    delivered: Option<(Instant, usize)>,
+}
+
+impl FetchSize {
+    pub fn new(fixed: Option<usize>) -> Self {
+        Self {
+            fixed,
+            tuning: Mutex::default(),
+        }
+    }
+
+    /// The size of the next batch, as of now.
+    pub fn current(&self) -> usize {
+        self.fixed.unwrap_or_else(|| lock(&self.tuning).size())
+    }
+
+    /// A fetch is being made: time the consumer's handling of the last
+    /// batch, and return the size of this one.
+    pub fn next(&self) -> usize {
+        let mut tuning = lock(&self.tuning);
+        if let Some((at, rows)) = tuning.delivered.take() {
+            if rows > 0 {
+                let ms = at.elapsed().as_secs_f64() * 1000.0 / rows as f64;
+                tuning.row_ms = Some(average(tuning.row_ms, ms));
+            }
+        }
+        self.fixed.unwrap_or_else(|| tuning.size())
+    }
+
+    /// `rows` rows of `bytes` in all (if known) were handed to JS.
+    pub fn delivered(&self, rows: usize, bytes: Option<u64>) {
+        let mut tuning = lock(&self.tuning);
+        if let (Some(bytes), true) = (bytes, rows > 0) {
+            let width = bytes as f64 / rows as f64;
+            tuning.row_bytes = Some(average(tuning.row_bytes, width));
+        }
+        tuning.delivered = Some((Instant::now(), rows));
+    }
+}
+
+impl Tuning {
+    fn size(&self) -> usize {
+        let by_bytes = self.row_bytes.map(|width| TARGET_BYTES / width);
+        let by_time = self.row_ms.filter(|&ms| ms > 0.0).map(|ms| TARGET_MS / ms);
+        let size = match (by_bytes, by_time) {
+            (None, None) => return FIRST,
+            (Some(a), Some(b)) => a.min(b),
+            (Some(a), None) | (None, Some(a)) => a,
+        };
+        (size as usize).clamp(MIN, MAX)
+    }
+}
+
+fn average(old: Option<f64>, sample: f64) -> f64 {
+    match old {
+        Some(old) => old + (sample - old) * SMOOTHING,
+        None => sample,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,309 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `Database.formatSql()`: canonical reprinting of SQL text.
+//
+// The SQL is parsed first, so only valid statements are formatted, and then
+// reprinted from the lexer's tokens. Keywords are upper-cased and whitespace
+// is replaced by a fixed layout; every other token, including literals,
+// quoted identifiers and comments, is copied from the input byte for byte.
+// Rendering the AST instead would lose quoting and add parentheses around
+// every operator.
+
+use napi::bindgen_prelude::Either;
+use stoolap::parser::{Lexer, Parser, Token, TokenType};
+
+/// Spaces per nesting level when `indent` is not given.
+pub const DEFAULT_INDENT: u32 = 2;
+
+/// Options for `Database.formatSql()`.
+#[napi(object, object_to_js = false)]
+pub struct FormatOptions {
+    /// Spaces per nesting level, or the string to indent with (default 2).
+    /// 0 prints each statement on a single line.
+    pub indent: Option<Either<u32, String>>,
+}
+
+/// Keywords that take a parenthesized argument list like a function.
+const CALL_KEYWORDS: &[&str] = &[
+    "CAST", "EXTRACT", "IF", "LEFT", "RIGHT", "VARCHAR", "CHAR", "DECIMAL", "NUMERIC", "VECTOR",
+];
+
+/// Keywords that end an operand, so a following `-` or `+` is binary.
+const VALUE_KEYWORDS: &[&str] = &["NULL", "TRUE", "FALSE", "END"];
+
+/// Keywords that can start a join.
+const JOIN_KEYWORDS: &[&str] = &["JOIN", "INNER", "CROSS", "NATURAL", "FULL", "LEFT", "RIGHT"];
+
+/// Keywords that may precede JOIN within one join clause.
+const JOIN_PREFIXES: &[&str] = &[
+    "INNER", "CROSS", "NATURAL", "FULL", "LEFT", "RIGHT", "OUTER",
+];
+
+/// Reformat every statement in `sql`. Statements end with `;` and are
+/// separated by a blank line, or by a line break with `indent: 0`.
+pub fn format_sql(sql: &str, options: Option<FormatOptions>) -> napi::Result<String> {
+    Parser::new(sql)
+        .parse_program()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    let indent = match options.and_then(|o| o.indent) {
+        None => " ".repeat(DEFAULT_INDENT as usize),
+        Some(Either::A(spaces)) => " ".repeat(spaces as usize),
+        Some(Either::B(indent)) => indent,
+    };
+
+    let words = words(sql);
+    let mut statements = Vec::new();
+    for statement in words.split(|w| is_punctuator(&w.token, ";")) {
+        if statement.is_empty() {
+            continue;
+        }
+        let mut printer = Printer::new(&indent);
+        printer.statement(statement);
+        let mut text = printer.out.trim_end().to_string();
+        if statement
+            .iter()
+            .any(|w| w.token.token_type != TokenType::Comment)
+        {
+            text.push(';');
+        }
+        statements.push(text);
+    }
+    let separator = if indent.is_empty() { "\n" } else { "\n\n" };
+    Ok(statements.join(separator))
+}
+
+/// A token and its source text, without the whitespace after it.
+struct Word<'a> {
+    token: Token,
+    text: &'a str,
+}
+
+/// The tokens of `sql` with their source text.
+fn words(sql: &str) -> Vec<Word<'_>> {
+    let mut lexer = Lexer::new(sql);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        if token.token_type == TokenType::Eof {
+            break;
+        }
+        tokens.push(token);
+    }
+    let ends: Vec<usize> = tokens
+        .iter()
+        .skip(1)
+        .map(|t| t.position.offset)
+        .chain([sql.len()])
+        .collect();
+    let mut words = Vec::with_capacity(tokens.len());
+    for (token, end) in tokens.into_iter().zip(ends) {
+        let text = sql[token.position.offset..end].trim_end();
+        words.push(Word { token, text });
+    }
+    words
+}
+
+/// How an open parenthesis is laid out.
+#[derive(Clone, Copy, PartialEq)]
+enum Paren {
+    Inline,
+    /// A subquery, indented on its own lines.
+    Query,
+    /// The column list of CREATE TABLE, one definition per line.
+    Columns,
+}
+
+struct Printer<'a> {
+    indent: &'a str,
+    out: String,
+    level: usize,
+    parens: Vec<Paren>,
+    /// Nothing printed on the current line yet.
+    fresh: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(indent: &'a str) -> Self {
+        Self {
+            indent,
+            out: String::new(),
+            level: 0,
+            parens: Vec::new(),
+            fresh: true,
+        }
+    }
+
+    fn statement(&mut self, words: &[Word]) {
+        let create = words
+            .iter()
+            .find(|w| w.token.token_type != TokenType::Comment)
+            .is_some_and(|w| is_keyword(&w.token, "CREATE"));
+        let significant: Vec<&Word> = words
+            .iter()
+            .filter(|w| w.token.token_type != TokenType::Comment)
+            .collect();
+        let mut index = 0usize;
+        // Whether the previous significant token was a unary operator.
+        let mut unary = false;
+
+        for word in words {
+            let token = &word.token;
+            if token.token_type == TokenType::Comment {
+                self.push(word.text, true);
+                if !word.text.starts_with("/*") {
+                    // Line comments run to the end of the line.
+                    self.out.push('\n');
+                    self.out.push_str(&self.indent.repeat(self.level));
+                    self.fresh = true;
+                }
+                continue;
+            }
+            let prev = index.checked_sub(1).map(|i| &significant[i].token);
+            let before_prev = index.checked_sub(2).map(|i| &significant[i].token);
+            let next = significant.get(index + 1).map(|w| &w.token);
+            index += 1;
+
+            // Keywords are printed as the lexer upper-cased them.
+            let text = if token.token_type == TokenType::Keyword {
+                token.literal.as_str()
+            } else {
+                word.text
+            };
+
+            if token.token_type == TokenType::Punctuator {
+                match text {
+                    "(" => {
+                        let call = prev.is_some_and(|p| {
+                            (p.token_type == TokenType::Identifier
+                                && !before_prev.is_some_and(|b| {
+                                    ["INTO", "TABLE", "EXISTS", "VIEW"]
+                                        .iter()
+                                        .any(|k| is_keyword(b, k))
+                                }))
+                                || CALL_KEYWORDS.iter().any(|k| is_keyword(p, k))
+                        });
+                        let kind = if next.is_some_and(|n| {
+                            ["SELECT", "WITH", "VALUES"]
+                                .iter()
+                                .any(|k| is_keyword(n, k))
+                        }) {
+                            Paren::Query
+                        } else if create
+                            && self.parens.is_empty()
+                            && prev.is_some_and(|p| p.token_type == TokenType::Identifier)
+                            && before_prev
+                                .is_some_and(|b| is_keyword(b, "TABLE") || is_keyword(b, "EXISTS"))
+                        {
+                            Paren::Columns
+                        } else {
+                            Paren::Inline
+                        };
+                        self.push("(", !call && !unary);
+                        self.parens.push(kind);
+                        if kind != Paren::Inline {
+                            self.level += 1;
+                            self.newline();
+                        }
+                    }
+                    ")" => {
+                        if self.parens.pop().is_some_and(|kind| kind != Paren::Inline) {
+                            self.level = self.level.saturating_sub(1);
+                            self.newline();
+                        }
+                        self.push(")", false);
+                    }
+                    "," => {
+                        self.push(",", false);
+                        if self.parens.last() == Some(&Paren::Columns) {
+                            self.newline();
+                        }
+                    }
+                    "[" => self.push("[", !unary),
+                    _ => self.push(text, false),
+                }
+                unary = false;
+                continue;
+            }
+
+            if self.breaks_before(token, prev, next) {
+                self.newline();
+            }
+            let after_open = prev.is_some_and(|p| {
+                ["(", "[", ".", ":"]
+                    .iter()
+                    .any(|punct| is_punctuator(p, punct))
+            });
+            self.push(text, !after_open && !unary);
+
+            unary = token.token_type == TokenType::Operator
+                && ["-", "+", "~"].contains(&text)
+                && prev.is_none_or(|p| match p.token_type {
+                    TokenType::Operator => true,
+                    TokenType::Punctuator => p.literal != ")" && p.literal != "]",
+                    TokenType::Keyword => !VALUE_KEYWORDS.iter().any(|k| is_keyword(p, k)),
+                    _ => false,
+                });
+        }
+    }
+
+    /// Whether `token` starts a clause on a new line.
+    fn breaks_before(&self, token: &Token, prev: Option<&Token>, next: Option<&Token>) -> bool {
+        if self.fresh
+            || token.token_type != TokenType::Keyword
+            || self.parens.last().is_some_and(|&p| p == Paren::Inline)
+        {
+            return false;
+        }
+        let prev_is =
+            |keywords: &[&str]| prev.is_some_and(|p| keywords.iter().any(|k| is_keyword(p, k)));
+        match token.literal.as_str() {
+            "SELECT" => !prev_is(&["EXPLAIN", "ANALYZE"]),
+            "FROM" => !prev_is(&["DELETE", "DISTINCT"]),
+            "VALUES" => !prev_is(&["DEFAULT"]),
+            "WHERE" | "HAVING" | "LIMIT" | "OFFSET" | "WINDOW" | "RETURNING" | "SET" | "UNION"
+            | "INTERSECT" | "EXCEPT" => true,
+            "GROUP" | "ORDER" => next.is_some_and(|n| is_keyword(n, "BY")),
+            "LEFT" | "RIGHT" if next.is_some_and(|n| is_punctuator(n, "(")) => false,
+            keyword if JOIN_KEYWORDS.contains(&keyword) => !prev_is(JOIN_PREFIXES),
+            _ => false,
+        }
+    }
+
+    /// Start a new line at the current level. A no-op on one-line output.
+    fn newline(&mut self) {
+        if self.indent.is_empty() || self.fresh {
+            return;
+        }
+        self.out.push('\n');
+        self.out.push_str(&self.indent.repeat(self.level));
+        self.fresh = true;
+    }
+
+    fn push(&mut self, text: &str, space: bool) {
+        if space && !self.fresh {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.fresh = false;
+    }
+}
+
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    token.token_type == TokenType::Keyword && token.literal.eq_ignore_ascii_case(keyword)
+}
+
+fn is_punctuator(token: &Token, punctuator: &str) -> bool {
+    token.token_type == TokenType::Punctuator && token.literal == punctuator
+}
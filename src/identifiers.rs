@@ -0,0 +1,204 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.bindIdentifiers()`: table and column names spliced into SQL.
+//
+// Placeholders bind values, not names, so SQL over a table picked at runtime
+// tends to be built by concatenation. Here `{{name}}` stands for an
+// identifier instead. The placeholders are found with the stoolap lexer, so
+// braces in literals, quoted identifiers and comments are left alone, and
+// each is replaced by its identifier, quoted and spelled as in the schema,
+// once the schema confirms it names a table or view (`'orders'`), a column
+// of one (`'orders.total'`), or a column of any of them (`'total'`). The
+// result has to parse.
+
+use std::collections::HashMap;
+
+use stoolap::api::Database;
+use stoolap::parser::{Lexer, Parser, TokenType};
+use stoolap::Value;
+
+use crate::error::to_napi;
+use crate::sql::quote_ident;
+
+/// `sql` with each `{{name}}` replaced by the identifier `identifiers` gives
+/// for it.
+pub fn bind(
+    db: &Database,
+    sql: &str,
+    identifiers: &HashMap<String, String>,
+) -> napi::Result<String> {
+    let placeholders = placeholders(sql)?;
+    if let Some(unused) = identifiers
+        .keys()
+        .find(|key| !placeholders.iter().any(|p| p.name == key.as_str()))
+    {
+        return Err(napi::Error::from_reason(format!(
+            "Identifier '{unused}' has no {{{{{unused}}}}} placeholder in the SQL"
+        )));
+    }
+    let mut schema = Schema::new(db)?;
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0;
+    for p in &placeholders {
+        let Some(value) = identifiers.get(p.name) else {
+            return Err(napi::Error::from_reason(format!(
+                "No identifier given for {{{{{}}}}}",
+                p.name
+            )));
+        };
+        out.push_str(&sql[last..p.start]);
+        out.push_str(&schema.resolve(p.name, value)?);
+        last = p.end;
+    }
+    out.push_str(&sql[last..]);
+    Parser::new(&out)
+        .parse_program()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    Ok(out)
+}
+
+/// A `{{name}}` at `sql[start..end]`.
+struct Placeholder<'a> {
+    name: &'a str,
+    start: usize,
+    end: usize,
+}
+
+fn placeholders(sql: &str) -> napi::Result<Vec<Placeholder<'_>>> {
+    let mut lexer = Lexer::new(sql);
+    let mut out = Vec::new();
+    let mut end = 0;
+    loop {
+        let token = lexer.next_token();
+        if token.token_type == TokenType::Eof {
+            break;
+        }
+        let start = token.position.offset;
+        if start < end
+            || matches!(token.token_type, TokenType::String | TokenType::Comment)
+            || !sql[start..].starts_with("{{")
+        {
+            continue;
+        }
+        let placeholder = placeholder_at(sql, start).ok_or_else(|| {
+            napi::Error::from_reason(format!(
+                "Invalid identifier placeholder at offset {start}: expected {{{{name}}}}"
+            ))
+        })?;
+        end = placeholder.end;
+        out.push(placeholder);
+    }
+    Ok(out)
+}
+
+/// `{{ name }}` starting at `start`, with optional spaces around the name.
+fn placeholder_at(sql: &str, start: usize) -> Option<Placeholder<'_>> {
+    let rest = &sql[start + 2..];
+    let inner = rest.trim_start();
+    let name_len = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(inner.len());
+    let name = &inner[..name_len];
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let after = inner[name_len..].trim_start();
+    after.strip_prefix("}}")?;
+    let end = sql.len() - after.len() + 2;
+    Some(Placeholder { name, start, end })
+}
+
+/// The tables and views of the database, and their columns once needed.
+struct Schema<'a> {
+    db: &'a Database,
+    relations: Vec<String>,
+    columns: HashMap<String, Vec<String>>,
+}
+
+impl<'a> Schema<'a> {
+    fn new(db: &'a Database) -> napi::Result<Self> {
+        let mut relations = names(db, "SHOW TABLES")?;
+        relations.extend(names(db, "SHOW VIEWS")?);
+        Ok(Self {
+            db,
+            relations,
+            columns: HashMap::new(),
+        })
+    }
+
+    /// The quoted identifier for `value`, bound to `{{name}}`.
+    fn resolve(&mut self, name: &str, value: &str) -> napi::Result<String> {
+        let resolved = match value.split_once('.') {
+            Some((relation, column)) => self.relation(relation).and_then(|relation| {
+                let column = self.column(&relation, column)?;
+                Some(format!(
+                    "{}.{}",
+                    quote_ident(&relation),
+                    quote_ident(&column)
+                ))
+            }),
+            None => self
+                .relation(value)
+                .or_else(|| self.any_column(value))
+                .map(|identifier| quote_ident(&identifier)),
+        };
+        resolved.ok_or_else(|| {
+            napi::Error::from_reason(format!(
+                "Identifier '{value}' for {{{{{name}}}}} is not a table, view or column"
+            ))
+        })
+    }
+
+    fn relation(&self, name: &str) -> Option<String> {
+        self.relations
+            .iter()
+            .find(|r| r.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    fn column(&mut self, relation: &str, name: &str) -> Option<String> {
+        if !self.columns.contains_key(relation) {
+            // A view can't be described, but an empty read of either has
+            // the column names.
+            let sql = format!("SELECT * FROM {} LIMIT 0", quote_ident(relation));
+            let rows = self.db.query(&sql, ()).ok()?;
+            self.columns
+                .insert(relation.to_string(), rows.columns().to_vec());
+        }
+        self.columns[relation]
+            .iter()
+            .find(|c| c.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    fn any_column(&mut self, name: &str) -> Option<String> {
+        let relations = self.relations.clone();
+        relations
+            .iter()
+            .find_map(|relation| self.column(relation, name))
+    }
+}
+
+/// The first column of each row `sql` returns.
+fn names(db: &Database, sql: &str) -> napi::Result<Vec<String>> {
+    let mut rows = db.query(sql, ()).map_err(to_napi)?;
+    let mut names = Vec::new();
+    while rows.advance() {
+        if let Some(Value::Text(name)) = rows.current_row().get(0) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
@@ -0,0 +1,72 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.interrupt()`.
+//
+// Every async statement takes a ticket when it is issued, recording the
+// database's interrupt generation. `interrupt()` bumps the generation, and a
+// ticket from an older generation fails its next check. Tasks check before
+// they start, between the statements of `exec()` and the chunks of
+// `deleteMany()`, and while rows are collected. The engine offers no hook into
+// a statement it is already evaluating, so joins, sorts and aggregates finish
+// their engine-side work first, and DML is never stopped part way.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use napi::Status;
+
+/// Rows collected between checks.
+pub const CHECK_INTERVAL: usize = 1024;
+
+pub type InterruptRef = Arc<Interrupt>;
+
+/// Interrupt generation shared by a database and everything created from it.
+#[derive(Default)]
+pub struct Interrupt {
+    generation: AtomicU64,
+}
+
+impl Interrupt {
+    /// Ticket for a statement issued now.
+    pub fn ticket(self: &Arc<Self>) -> Ticket {
+        Ticket {
+            generation: self.generation.load(Ordering::Acquire),
+            interrupt: Arc::clone(self),
+        }
+    }
+
+    /// Interrupt every statement issued so far.
+    pub fn interrupt(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+pub struct Ticket {
+    interrupt: InterruptRef,
+    generation: u64,
+}
+
+impl Ticket {
+    /// Fail with an `Interrupted` error if `interrupt()` was called since the
+    /// statement was issued.
+    #[inline]
+    pub fn check(&self) -> napi::Result<()> {
+        if self.interrupt.generation.load(Ordering::Acquire) == self.generation {
+            Ok(())
+        } else {
+            Err(napi::Error::new(Status::Cancelled, "Interrupted"))
+        }
+    }
+}
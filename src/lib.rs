@@ -15,9 +15,80 @@
 #[macro_use]
 extern crate napi_derive;
 
+use napi::Env;
+
+mod advisor;
+mod analyze;
+mod batch;
+mod bridge;
+mod bulk;
+mod call_site;
+mod changes;
+mod checkpoint;
+mod chunks;
+mod closing;
+mod column_case;
+mod column_names;
+mod column_stats;
+mod commit_window;
+mod config;
+mod consistency;
+mod counters;
+mod cursor;
 mod database;
+mod diff;
+mod dump;
 mod error;
+mod explain;
+mod faults;
+mod fetch_size;
+mod format;
+mod identifiers;
+mod interrupt;
+mod manager;
+mod memory;
+mod monitor;
+mod options;
+mod paging;
+mod parallel;
+mod pipeline;
+mod plan_cache;
+mod policy;
+mod preview;
+mod profile;
+mod projection;
+mod readers;
+mod recording;
+mod result_filter;
+mod retry;
+mod runtime;
+mod shape;
+mod sql;
 mod statement;
+mod statement_log;
+mod sync_busy;
+mod syntax;
 mod tasks;
+mod temp_table;
+mod temporal;
 mod transaction;
+mod tree;
+mod tx_memo;
+mod tx_sql;
+mod tx_timeout;
+mod types;
+mod usage;
+mod vacuum;
+mod validate;
 mod value;
+mod vectors;
+mod wal_shipping;
+mod window;
+mod worker;
+mod write_queue;
+
+/// Choose how results are built before any database is opened.
+#[napi(module_exports)]
+pub fn init(env: Env) {
+    runtime::init(&env);
+}
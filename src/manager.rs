@@ -0,0 +1,316 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `DatabaseManager`, for applications with a database per tenant.
+//
+// A manager opens databases on demand and keeps them open: `get(path)` on a
+// path it has open returns a handle sharing the open one's engine, worker,
+// settings and listeners, so what is set through one handle holds for all.
+// At most `maxOpen` are open at once. A `get()` that needs one more closes
+// the least recently got idle databases first, idle meaning no async call
+// pending and no transaction open, and throws if none is idle; with
+// `idleMs` it also closes the idle databases not got for that long. Closed
+// databases close in the background, as `close()` would, and their handles
+// reject later calls with a `DatabaseClosed` error; the next `get()` opens
+// the database again, as it does after a handle is closed directly.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::Env;
+use stoolap::api::Database;
+
+use crate::config::OpenOptions;
+use crate::database::{open_task, translate_path, JsDatabase};
+use crate::tasks::{CloseTask, OpenTask};
+
+/// `maxOpen` when not given.
+const DEFAULT_MAX_OPEN: u32 = 64;
+
+/// Options for `new DatabaseManager()`.
+#[napi(object, object_to_js = false)]
+pub struct DatabaseManagerOptions {
+    /// Most databases open at once (default 64).
+    pub max_open: Option<u32>,
+    /// Close idle databases not got for this many milliseconds, at the next
+    /// `get()`; by default they stay open until `maxOpen` is reached.
+    pub idle_ms: Option<f64>,
+    /// Options every database is opened with, as for `Database.open()`.
+    pub options: Option<OpenOptions>,
+}
+
+/// An open database and when it was last got.
+struct Managed {
+    db: JsDatabase,
+    got: Instant,
+}
+
+#[derive(Default)]
+struct Handles {
+    /// Open databases by DSN.
+    open: HashMap<String, Managed>,
+    /// DSNs being opened, which count towards `maxOpen`.
+    opening: HashSet<String>,
+    closed: bool,
+}
+
+type HandlesRef = Arc<Mutex<Handles>>;
+
+/// Opens databases on demand and closes idle ones to stay under `maxOpen`.
+#[napi(js_name = "DatabaseManager")]
+pub struct JsDatabaseManager {
+    max_open: usize,
+    idle: Option<Duration>,
+    options: Option<OpenOptions>,
+    handles: HandlesRef,
+}
+
+#[napi]
+impl JsDatabaseManager {
+    #[napi(constructor)]
+    pub fn new(options: Option<DatabaseManagerOptions>) -> napi::Result<Self> {
+        let (max_open, idle_ms, options) = match options {
+            Some(o) => (o.max_open, o.idle_ms, o.options),
+            None => (None, None, None),
+        };
+        let max_open = max_open.unwrap_or(DEFAULT_MAX_OPEN);
+        if max_open == 0 {
+            return Err(napi::Error::from_reason("maxOpen must be at least 1"));
+        }
+        let idle = match idle_ms {
+            None => None,
+            Some(ms) if ms >= 0.0 && ms.is_finite() => Some(Duration::from_secs_f64(ms / 1000.0)),
+            Some(_) => {
+                return Err(napi::Error::from_reason(
+                    "idleMs must be a non-negative number",
+                ))
+            }
+        };
+        Ok(Self {
+            max_open: max_open as usize,
+            idle,
+            options,
+            handles: HandlesRef::default(),
+        })
+    }
+
+    /// The database at `path`, opened with the manager's options unless it
+    /// is open already. Opening one closes idle databases first when
+    /// `maxOpen` are open, and throws when none of them is idle.
+    /// Returns Promise<Database>.
+    #[napi(ts_return_type = "Promise<Database>")]
+    pub fn get(&self, env: Env, path: String) -> napi::Result<AsyncTask<GetTask>> {
+        let mut handles = lock(&self.handles);
+        if handles.closed {
+            return Err(napi::Error::from_reason("DatabaseManager is closed"));
+        }
+        let dsn = translate_path(&path);
+        let now = Instant::now();
+        handles.open.retain(|_, managed| managed.db.is_open());
+
+        let mut victims: Vec<String> = match self.idle {
+            Some(idle) => handles
+                .open
+                .iter()
+                .filter(|(key, managed)| {
+                    **key != dsn && now - managed.got >= idle && managed.db.is_idle()
+                })
+                .map(|(key, _)| key.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        let cached = handles.open.get(&dsn).map(|managed| managed.db.clone());
+        let opens = cached.is_none() && !handles.opening.contains(&dsn);
+        let wanted = handles.open.len() - victims.len() + handles.opening.len() + opens as usize;
+        if wanted > self.max_open {
+            let mut idle: Vec<(&String, Instant)> = handles
+                .open
+                .iter()
+                .filter(|(key, managed)| {
+                    **key != dsn && !victims.contains(key) && managed.db.is_idle()
+                })
+                .map(|(key, managed)| (key, managed.got))
+                .collect();
+            let needed = wanted - self.max_open;
+            if idle.len() < needed {
+                return Err(napi::Error::from_reason(format!(
+                    "DatabaseManager has {} databases open and not enough of them are idle",
+                    self.max_open
+                )));
+            }
+            idle.sort_by_key(|(_, got)| *got);
+            let oldest: Vec<String> = idle[..needed]
+                .iter()
+                .map(|(key, _)| (*key).clone())
+                .collect();
+            victims.extend(oldest);
+        }
+
+        let open = match cached {
+            Some(_) => None,
+            None => Some(open_task(&env, &path, self.options.clone())?),
+        };
+        let mut closing = Vec::with_capacity(victims.len());
+        for key in victims {
+            if let Some(managed) = handles.open.remove(&key) {
+                closing.push(managed.db.close_task(None)?);
+            }
+        }
+        match handles.open.get_mut(&dsn) {
+            Some(managed) => managed.got = now,
+            None => {
+                handles.opening.insert(dsn.clone());
+            }
+        }
+        Ok(AsyncTask::new(GetTask {
+            dsn,
+            handles: Arc::clone(&self.handles),
+            cached,
+            open,
+            closing,
+        }))
+    }
+
+    /// Whether the database at `path` is open in the manager.
+    #[napi]
+    pub fn has(&self, path: String) -> bool {
+        lock(&self.handles)
+            .open
+            .get(&translate_path(&path))
+            .is_some_and(|managed| managed.db.is_open())
+    }
+
+    /// Number of databases open in the manager.
+    #[napi(getter)]
+    pub fn size(&self) -> u32 {
+        let handles = lock(&self.handles);
+        handles
+            .open
+            .values()
+            .filter(|managed| managed.db.is_open())
+            .count() as u32
+    }
+
+    /// Close every database the manager has open, letting their pending
+    /// calls finish first. `get()` throws afterwards. Returns Promise<void>.
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn close(&self) -> napi::Result<AsyncTask<CloseAllTask>> {
+        let mut handles = lock(&self.handles);
+        handles.closed = true;
+        let closing = handles
+            .open
+            .drain()
+            .map(|(_, managed)| managed.db.close_task(None))
+            .collect::<napi::Result<Vec<_>>>()?;
+        Ok(AsyncTask::new(CloseAllTask { closing }))
+    }
+}
+
+// ============================================================
+// GetTask — manager.get(path)
+// ============================================================
+
+pub struct GetTask {
+    dsn: String,
+    handles: HandlesRef,
+    /// The open database, when there is one.
+    cached: Option<JsDatabase>,
+    open: Option<OpenTask>,
+    /// Databases closed to make room.
+    closing: Vec<CloseTask>,
+}
+
+impl Task for GetTask {
+    type Output = Option<Database>;
+    type JsValue = JsDatabase;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        for task in &mut self.closing {
+            // A database that fails to close has nothing left to give this
+            // call, or any other.
+            let _ = task.compute();
+        }
+        self.open.as_mut().map(|open| open.compute()).transpose()
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        if let Some(db) = self.cached.take() {
+            return Ok(db);
+        }
+        let mut handles = lock(&self.handles);
+        handles.opening.remove(&self.dsn);
+        if let Some(managed) = handles.open.get_mut(&self.dsn) {
+            // Another `get()` opened it meanwhile; the engine is the same.
+            managed.got = Instant::now();
+            return Ok(managed.db.clone());
+        }
+        let (Some(open), Some(output)) = (self.open.as_mut(), output) else {
+            return Err(napi::Error::from_reason("DatabaseManager opened nothing"));
+        };
+        let db = open.resolve(env, output)?;
+        if handles.closed {
+            db.close_sync()?;
+            return Err(napi::Error::from_reason("DatabaseManager is closed"));
+        }
+        handles.open.insert(
+            self.dsn.clone(),
+            Managed {
+                db: db.clone(),
+                got: Instant::now(),
+            },
+        );
+        Ok(db)
+    }
+
+    fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+        lock(&self.handles).opening.remove(&self.dsn);
+        match self.open.as_mut() {
+            Some(open) => open.reject(env, err),
+            None => Err(err),
+        }
+    }
+}
+
+// ============================================================
+// CloseAllTask — manager.close()
+// ============================================================
+
+pub struct CloseAllTask {
+    closing: Vec<CloseTask>,
+}
+
+impl Task for CloseAllTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let mut result = Ok(());
+        for task in &mut self.closing {
+            if let Err(err) = task.compute() {
+                result = result.and(Err(err));
+            }
+        }
+        result
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(())
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,223 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `memoryBudget` open option and `db.setMemoryBudget()`.
+//
+// The engine keeps its tables in memory and has no buffer pool or memtable
+// whose size could be set; its only buffer sizes are the WAL options. What
+// the binding can bound is the memory it holds itself: query results are
+// collected into Rust values before they become JS values, which for an
+// async call happens on a worker thread while the event loop runs on. The
+// budget covers the results held by all of a database's calls at once. Rows
+// are charged as they are collected, by an estimate of their size, and a
+// call that would exceed the budget fails instead of holding more; its rows
+// are freed, and other calls are unaffected. Results are released once they
+// have been converted to JS values, or row by row with the `lowMemory`
+// query option. Async calls that exceed it reject with `code` set to
+// `OutOfMemoryBudget`, made on the JS thread like `DatabaseClosed` errors
+// (see closing.rs).
+//
+// `db.memoryUsage()` reports the same counts with their high-water marks,
+// next to the other memory the binding and the engine size themselves: the
+// SQL text held by the plan cache and the WAL buffer.
+
+use std::mem::{size_of, size_of_val};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use napi::bindgen_prelude::{JsObjectValue, JsValue};
+use napi::Env;
+use stoolap::Value;
+
+use crate::plan_cache::PlanCacheStats;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+/// Starts the message of the error a call over the budget fails with.
+const EXCEEDED: &str = "Query results exceed the memory budget";
+
+/// Memory held for results, returned by `db.memoryBudget`.
+#[napi(object, object_from_js = false)]
+pub struct MemoryBudgetStats {
+    /// The budget in megabytes; absent when there is none.
+    pub limit_mb: Option<f64>,
+    /// Estimated size of the results held now, in megabytes.
+    pub used_mb: f64,
+    /// Calls that failed because they would have exceeded the budget.
+    pub rejected: u32,
+}
+
+/// What `db.memoryUsage()` returns. Sizes are in megabytes, and those of
+/// results are estimates.
+#[napi(object, object_from_js = false, use_nullable = true)]
+pub struct MemoryUsage {
+    /// The memory budget, or null when there is none.
+    pub limit_mb: Option<f64>,
+    /// Query results held now.
+    pub results_mb: f64,
+    /// Most query results held at once since the database was opened.
+    pub peak_results_mb: f64,
+    /// Largest result a single call has held.
+    pub largest_result_mb: f64,
+    /// Calls that failed because they would have exceeded the budget.
+    pub rejected: u32,
+    /// Plans in `db.planCache`.
+    pub plan_cache_entries: u32,
+    /// SQL text the plan cache holds as keys. Parsed plans are not measured.
+    pub plan_cache_mb: f64,
+    /// The engine's WAL buffer; 0 for in-memory databases.
+    pub wal_buffer_mb: f64,
+}
+
+pub type MemoryBudgetRef = Arc<MemoryBudget>;
+
+/// Result memory shared by a database and everything created from it. The
+/// default has no limit and only counts.
+#[derive(Default)]
+pub struct MemoryBudget {
+    /// Limit in bytes; 0 means none.
+    limit: AtomicU64,
+    used: AtomicU64,
+    /// Most `used` has been.
+    peak: AtomicU64,
+    /// Most one charge has held.
+    largest: AtomicU64,
+    rejected: AtomicU32,
+}
+
+/// Validate a budget in megabytes and convert it to bytes.
+pub fn budget_bytes(mb: f64) -> napi::Result<u64> {
+    if !mb.is_finite() || mb <= 0.0 {
+        return Err(napi::Error::from_reason(
+            "memoryBudget must be a positive number of megabytes",
+        ));
+    }
+    Ok(((mb * BYTES_PER_MB).round() as u64).max(1))
+}
+
+impl MemoryBudget {
+    /// Set the limit in bytes, or remove it with `None`. Results already
+    /// held are kept.
+    pub fn set_limit(&self, bytes: Option<u64>) {
+        self.limit.store(bytes.unwrap_or(0), Ordering::Release);
+    }
+
+    pub fn stats(&self) -> MemoryBudgetStats {
+        let limit = self.limit.load(Ordering::Acquire);
+        MemoryBudgetStats {
+            limit_mb: (limit > 0).then(|| limit as f64 / BYTES_PER_MB),
+            used_mb: self.used.load(Ordering::Acquire) as f64 / BYTES_PER_MB,
+            rejected: self.rejected.load(Ordering::Acquire),
+        }
+    }
+
+    /// `stats()` with the high-water marks, the plan cache's `plans` and a
+    /// WAL buffer of `wal_buffer` bytes.
+    pub fn usage(&self, plans: &PlanCacheStats, wal_buffer: u64) -> MemoryUsage {
+        let stats = self.stats();
+        let mb = |bytes: u64| bytes as f64 / BYTES_PER_MB;
+        MemoryUsage {
+            limit_mb: stats.limit_mb,
+            results_mb: stats.used_mb,
+            peak_results_mb: mb(self.peak.load(Ordering::Acquire)),
+            largest_result_mb: mb(self.largest.load(Ordering::Acquire)),
+            rejected: stats.rejected,
+            plan_cache_entries: plans.entries,
+            plan_cache_mb: mb(plans.sql_bytes as u64),
+            wal_buffer_mb: mb(wal_buffer),
+        }
+    }
+
+    /// An empty charge against this budget.
+    pub fn charge(self: &Arc<Self>) -> Charge {
+        Charge {
+            budget: Arc::clone(self),
+            bytes: 0,
+        }
+    }
+}
+
+/// Memory one result holds against the budget, released when dropped.
+pub struct Charge {
+    budget: MemoryBudgetRef,
+    bytes: u64,
+}
+
+impl Charge {
+    /// Charge one more row, failing if the budget can't hold it.
+    pub fn add_row(&mut self, row: &[Value]) -> napi::Result<()> {
+        let bytes = row_bytes(row);
+        let budget = &self.budget;
+        let limit = budget.limit.load(Ordering::Acquire);
+        let used = budget.used.fetch_add(bytes, Ordering::AcqRel) + bytes;
+        self.bytes += bytes;
+        if limit > 0 && used > limit {
+            budget.rejected.fetch_add(1, Ordering::AcqRel);
+            let mb = (limit as f64 / BYTES_PER_MB * 100.0).round() / 100.0;
+            return Err(napi::Error::from_reason(format!("{EXCEEDED} of {mb} MB")));
+        }
+        budget.peak.fetch_max(used, Ordering::AcqRel);
+        budget.largest.fetch_max(self.bytes, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Bytes charged so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Release one row charged with `add_row`, once it has been freed.
+    pub fn release_row(&mut self, row: &[Value]) {
+        let bytes = row_bytes(row).min(self.bytes);
+        self.bytes -= bytes;
+        self.budget.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+}
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        self.budget.used.fetch_sub(self.bytes, Ordering::AcqRel);
+    }
+}
+
+/// Whether `err` is the error of a call that exceeded its budget.
+pub fn exceeded(err: &napi::Error) -> bool {
+    err.reason.starts_with(EXCEEDED)
+}
+
+/// `err` as a JS error with `code` set to `OutOfMemoryBudget`, on the JS
+/// thread.
+pub fn to_js(env: &Env, mut err: napi::Error) -> napi::Error {
+    let reason = std::mem::take(&mut err.reason);
+    let made = env
+        .create_error(napi::Error::from_reason(reason.as_str()))
+        .and_then(|mut error| {
+            error.set_named_property("code", "OutOfMemoryBudget")?;
+            Ok(napi::Error::from(error.to_unknown()))
+        });
+    made.unwrap_or_else(|_| napi::Error::from_reason(reason))
+}
+
+/// Estimated size of a collected row.
+fn row_bytes(row: &[Value]) -> u64 {
+    let heap: usize = row
+        .iter()
+        .map(|value| match value {
+            Value::Text(s) => s.len(),
+            Value::Extension(bytes) => bytes.len(),
+            _ => 0,
+        })
+        .sum();
+    (size_of::<Vec<Value>>() + size_of_val(row) + heap) as u64
+}
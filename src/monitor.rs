@@ -0,0 +1,274 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Task queue instrumentation for `db.on('task')` and `db.taskQueue`.
+//
+// Every async call of a database, its statements, transactions and pipelines
+// is one task. A task is enqueued when the call returns its promise, dequeued
+// when a thread starts its work and complete when that work is done, before
+// the promise settles on the JS thread. The counters are kept whether or not
+// anyone listens; events are built only while a listener is registered, and
+// reach it through a threadsafe function like the other events, so they
+// arrive after the call that caused them.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::Task;
+
+use crate::call_site::CallSite;
+use crate::closing::{self, ClosingRef};
+use crate::error::restore;
+use crate::faults::FaultsRef;
+use crate::memory;
+use crate::parallel::{self, Parallelism};
+use crate::types::TypesRef;
+use crate::write_queue::WriteLock;
+
+/// One step in the life of an async call, for `db.on('task')`.
+#[napi(object, object_from_js = false)]
+#[derive(Clone)]
+pub struct TaskEvent {
+    #[napi(ts_type = "'enqueue' | 'dequeue' | 'complete'")]
+    pub phase: String,
+    /// Method that issued the task, e.g. `query` or `commit`.
+    pub method: String,
+    /// Tasks waiting for a thread, after this step.
+    pub queued: u32,
+    /// Tasks running on a thread, after this step.
+    pub running: u32,
+    /// Time between enqueue and dequeue; 0 for `enqueue`.
+    pub wait_ms: f64,
+    /// Time between dequeue and complete; 0 before `complete`.
+    pub run_ms: f64,
+    /// False when the task's work failed; true before `complete`.
+    pub ok: bool,
+}
+
+/// Async calls in flight, returned by `db.taskQueue`.
+#[napi(object, object_from_js = false)]
+pub struct TaskQueueStats {
+    /// Tasks waiting for a thread.
+    pub queued: u32,
+    /// Tasks running on a thread.
+    pub running: u32,
+}
+
+pub type TaskListener =
+    ThreadsafeFunction<TaskEvent, Unknown<'static>, TaskEvent, Status, false, true>;
+
+pub type TaskMonitorRef = Arc<TaskMonitor>;
+
+/// Queue counters and task listeners shared by a database and everything
+/// created from it.
+#[derive(Default)]
+pub struct TaskMonitor {
+    queued: AtomicU32,
+    running: AtomicU32,
+    active: AtomicBool,
+    listeners: Mutex<Vec<TaskListener>>,
+}
+
+impl TaskMonitor {
+    pub fn subscribe(&self, listener: TaskListener) {
+        lock(&self.listeners).push(listener);
+        self.active.store(true, Ordering::Release);
+    }
+
+    pub fn stats(&self) -> TaskQueueStats {
+        TaskQueueStats {
+            queued: self.queued.load(Ordering::Acquire),
+            running: self.running.load(Ordering::Acquire),
+        }
+    }
+
+    /// Count `task` as queued and wrap it to report the rest of its life.
+    pub fn enqueue<T: Task>(self: &Arc<Self>, method: &'static str, task: T) -> Monitored<T> {
+        self.queued.fetch_add(1, Ordering::AcqRel);
+        let task = Monitored {
+            task,
+            method,
+            enqueued: Instant::now(),
+            slot: Slot {
+                monitor: Arc::clone(self),
+                dequeued: None,
+            },
+            lock: None,
+            call_site: CallSite::Off,
+            types: None,
+            faults: None,
+            parallelism: None,
+            closing: None,
+            closed: false,
+        };
+        self.emit(&task, "enqueue", 0.0, true);
+        task
+    }
+
+    fn emit<T>(&self, task: &Monitored<T>, phase: &str, run_ms: f64, ok: bool) {
+        if !self.active.load(Ordering::Acquire) {
+            return;
+        }
+        let wait_ms = task.slot.dequeued.map_or(0.0, |dequeued| {
+            dequeued.duration_since(task.enqueued).as_secs_f64() * 1000.0
+        });
+        let stats = self.stats();
+        let event = TaskEvent {
+            phase: phase.to_string(),
+            method: task.method.to_string(),
+            queued: stats.queued,
+            running: stats.running,
+            wait_ms,
+            run_ms,
+            ok,
+        };
+        for listener in lock(&self.listeners).iter() {
+            listener.call(event.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+}
+
+/// A task that reports when it starts and finishes.
+pub struct Monitored<T> {
+    task: T,
+    method: &'static str,
+    enqueued: Instant,
+    slot: Slot,
+    /// Serialized tables the task holds until it is dropped.
+    lock: Option<WriteLock>,
+    /// Where the call was made, for errors of its work.
+    call_site: CallSite,
+    /// Custom types that revive the task's results.
+    types: Option<TypesRef>,
+    /// Faults injected before the task runs.
+    faults: Option<FaultsRef>,
+    /// `parallelism`, for the engine work of the task.
+    parallelism: Option<Parallelism>,
+    /// `close()`, and whether the handle was closed when the task was
+    /// placed.
+    closing: Option<(ClosingRef, bool)>,
+    /// The task failed because the handle closed.
+    closed: bool,
+}
+
+impl<T> Monitored<T> {
+    pub fn hold(&mut self, lock: WriteLock) {
+        self.lock = Some(lock);
+    }
+
+    pub fn set_call_site(&mut self, call_site: CallSite) {
+        self.call_site = call_site;
+    }
+
+    pub fn set_types(&mut self, types: TypesRef) {
+        self.types = Some(types);
+    }
+
+    pub fn set_faults(&mut self, faults: FaultsRef) {
+        self.faults = Some(faults);
+    }
+
+    pub fn set_parallelism(&mut self, parallelism: Option<Parallelism>) {
+        self.parallelism = parallelism;
+    }
+
+    pub fn set_closing(&mut self, closing: ClosingRef) {
+        let issued_closed = closing.is_closed();
+        self.closing = Some((closing, issued_closed));
+    }
+
+    pub fn capture_call_site(&mut self, env: &Env) -> napi::Result<()> {
+        self.call_site.capture(env)
+    }
+}
+
+/// A task's place in the queue counters.
+struct Slot {
+    monitor: TaskMonitorRef,
+    dequeued: Option<Instant>,
+}
+
+impl<T: Task> Task for Monitored<T> {
+    type Output = T::Output;
+    type JsValue = T::JsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let monitor = Arc::clone(&self.slot.monitor);
+        let dequeued = Instant::now();
+        self.slot.dequeued = Some(dequeued);
+        monitor.queued.fetch_sub(1, Ordering::AcqRel);
+        monitor.running.fetch_add(1, Ordering::AcqRel);
+        monitor.emit(self, "dequeue", 0.0, true);
+
+        let closing = self.closing.as_ref();
+        let output = if closing.is_some_and(|(c, issued)| c.rejects(self.method, *issued)) {
+            self.closed = true;
+            Err(closing::closed_error())
+        } else {
+            let injected = match self.faults {
+                Some(ref faults) => faults.inject(self.method),
+                None => Ok(()),
+            };
+            let task = &mut self.task;
+            let computed =
+                injected.and_then(|()| parallel::run(self.parallelism, || task.compute()));
+            match computed {
+                Err(e) if closing.is_some_and(|(c, _)| c.stopped(&e)) => {
+                    self.closed = true;
+                    Err(closing::closed_error())
+                }
+                output => output,
+            }
+        };
+        monitor.running.fetch_sub(1, Ordering::AcqRel);
+        let run_ms = dequeued.elapsed().as_secs_f64() * 1000.0;
+        monitor.emit(self, "complete", run_ms, output.is_ok());
+        output
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let _reviving = self.types.as_ref().map(|types| types.reviving());
+        self.task.resolve(env, output)
+    }
+
+    fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+        let mut err = restore(&env, self.call_site.append_to(err));
+        if self.closed {
+            err = closing::to_js(&env, err);
+        } else if memory::exceeded(&err) {
+            err = memory::to_js(&env, err);
+        }
+        self.task.reject(env, err)
+    }
+
+    fn finally(self, env: Env) -> napi::Result<()> {
+        self.task.finally(env)
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        // Never started, e.g. on a worker thread that has stopped.
+        if self.dequeued.is_none() {
+            self.monitor.queued.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
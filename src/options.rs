@@ -0,0 +1,250 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use napi::Env;
+
+use crate::paging::{MaxRows, Page};
+use crate::parallel::Parallelism;
+use crate::projection::Projection;
+use crate::sql::{with_as_of, AsOf};
+use crate::value::{js_to_value, RawParam};
+use crate::vectors::VectorColumns;
+
+/// Per-call options accepted by the query methods.
+#[napi(object, object_to_js = false)]
+pub struct QueryOptions {
+    /// Read a historical snapshot: a Date or timestamp string (AS OF TIMESTAMP)
+    /// or an integer transaction id (AS OF TRANSACTION).
+    #[napi(ts_type = "Date | string | number")]
+    pub as_of: Option<RawParam>,
+    /// Convert the rows with less peak memory at some cost in speed: column
+    /// names aren't interned, rows are added to the result one at a time,
+    /// and on async calls each row is freed as soon as it has been converted.
+    pub low_memory: Option<bool>,
+    /// Return only these result columns, in this order, as if the SQL had
+    /// selected just them.
+    pub columns: Option<Vec<String>>,
+    /// Skip this many result rows, as if the SQL ended in `OFFSET`.
+    pub offset: Option<f64>,
+    /// Return at most this many rows, as if the SQL ended in `LIMIT`.
+    pub limit: Option<f64>,
+    /// Fail if the result has more than this many rows, or truncate it with
+    /// `onMaxRows: 'truncate'`.
+    pub max_rows: Option<f64>,
+    /// What a result over `maxRows` does (default `'error'`).
+    #[napi(ts_type = "'error' | 'truncate'")]
+    pub on_max_rows: Option<String>,
+    /// With `queryRaw()`, also return this many of the first rows as
+    /// objects, in `preview`.
+    pub preview: Option<f64>,
+    /// With `queryRaw()`, `buffer` packs each vector column into one Buffer,
+    /// described in `vectors` (default `typedArray`, one per row).
+    #[napi(ts_type = "'typedArray' | 'buffer'")]
+    pub vectors: Option<String>,
+    /// Run the engine's parallel operators on this many threads instead of
+    /// the database's `parallelism`.
+    pub parallelism: Option<f64>,
+}
+
+/// Per-call options accepted by a prepared statement's query methods.
+#[napi(object, object_to_js = false)]
+pub struct StatementQueryOptions {
+    /// Return only these result columns, in this order, as if the SQL had
+    /// selected just them.
+    pub columns: Option<Vec<String>>,
+    /// Skip this many result rows, as if the SQL ended in `OFFSET`.
+    pub offset: Option<f64>,
+    /// Return at most this many rows, as if the SQL ended in `LIMIT`.
+    pub limit: Option<f64>,
+    /// Fail if the result has more than this many rows, or truncate it with
+    /// `onMaxRows: 'truncate'`.
+    pub max_rows: Option<f64>,
+    /// What a result over `maxRows` does (default `'error'`).
+    #[napi(ts_type = "'error' | 'truncate'")]
+    pub on_max_rows: Option<String>,
+    /// Run the engine's parallel operators on this many threads instead of
+    /// the database's `parallelism`.
+    pub parallelism: Option<f64>,
+}
+
+/// Apply SQL-level query options (e.g. `asOf`) to the statement text.
+pub fn apply_query_options(
+    env: &Env,
+    sql: String,
+    options: Option<&QueryOptions>,
+) -> napi::Result<String> {
+    let Some(opts) = options else {
+        return Ok(sql);
+    };
+    match opts.as_of {
+        Some(ref raw) => {
+            let as_of = AsOf::from_value(js_to_value(env.raw(), raw.0)?)?;
+            with_as_of(&sql, &as_of)
+        }
+        None => Ok(sql),
+    }
+}
+
+/// Reject `asOf` on a transaction's query, which reads the transaction's
+/// snapshot: a transaction takes no query options, but one passed the way
+/// the database methods take them would otherwise be ignored.
+pub fn reject_as_of(options: Option<&QueryOptions>) -> napi::Result<()> {
+    if options.is_some_and(|o| o.as_of.is_some()) {
+        return Err(napi::Error::from_reason(
+            "asOf is not supported in a transaction, which reads its own snapshot",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the `lowMemory` query option is set.
+pub fn low_memory(options: Option<&QueryOptions>) -> bool {
+    options.and_then(|o| o.low_memory).unwrap_or(false)
+}
+
+/// The `parallelism` query option, checked.
+pub fn parallelism(options: Option<&QueryOptions>) -> napi::Result<Option<Parallelism>> {
+    Parallelism::new(options.and_then(|o| o.parallelism))
+}
+
+/// The `parallelism` option of a prepared statement's query, checked.
+pub fn statement_parallelism(
+    options: Option<&StatementQueryOptions>,
+) -> napi::Result<Option<Parallelism>> {
+    Parallelism::new(options.and_then(|o| o.parallelism))
+}
+
+/// The `columns` query option, checked.
+pub fn projection(options: Option<&QueryOptions>) -> napi::Result<Option<Projection>> {
+    options
+        .and_then(|o| o.columns.clone())
+        .map(Projection::new)
+        .transpose()
+}
+
+/// The `columns` option of a prepared statement's query, checked.
+pub fn statement_projection(
+    options: Option<&StatementQueryOptions>,
+) -> napi::Result<Option<Projection>> {
+    options
+        .and_then(|o| o.columns.clone())
+        .map(Projection::new)
+        .transpose()
+}
+
+/// The `offset`, `limit` and `maxRows` query options, checked, with the
+/// database's `maxRows` when the call has none.
+pub fn page(options: Option<&QueryOptions>, max_rows: Option<MaxRows>) -> napi::Result<Page> {
+    match options {
+        Some(o) => Ok(Page::new(o.offset, o.limit)?.max_rows(MaxRows::new(
+            o.max_rows,
+            o.on_max_rows.as_deref(),
+            max_rows,
+        )?)),
+        None => Ok(Page::default().max_rows(max_rows)),
+    }
+}
+
+/// The `preview` query option, checked.
+pub fn preview(options: Option<&QueryOptions>) -> napi::Result<Option<usize>> {
+    crate::preview::count(options.and_then(|o| o.preview))
+}
+
+/// The `vectors` query option, checked: somewhere to pack the vector
+/// columns when they go in buffers.
+pub fn vectors(options: Option<&QueryOptions>) -> napi::Result<Option<VectorColumns>> {
+    let buffered = crate::vectors::buffered(options.and_then(|o| o.vectors.as_deref()))?;
+    Ok(buffered.then(VectorColumns::default))
+}
+
+/// The `offset`, `limit` and `maxRows` options of a prepared statement's
+/// query, checked, with the database's `maxRows` when the call has none.
+pub fn statement_page(
+    options: Option<&StatementQueryOptions>,
+    max_rows: Option<MaxRows>,
+) -> napi::Result<Page> {
+    match options {
+        Some(o) => Ok(Page::new(o.offset, o.limit)?.max_rows(MaxRows::new(
+            o.max_rows,
+            o.on_max_rows.as_deref(),
+            max_rows,
+        )?)),
+        None => Ok(Page::default().max_rows(max_rows)),
+    }
+}
+
+/// Options accepted by `prepare()` / `prepareAsync()`.
+#[napi(object, object_to_js = false)]
+pub struct PrepareOptions {
+    /// Reject SQL longer than this many bytes instead of parsing it.
+    pub max_sql_bytes: Option<f64>,
+}
+
+/// Reject `sql` if it is longer than the `maxSqlBytes` prepare option.
+pub fn check_sql_size(sql: &str, options: Option<&PrepareOptions>) -> napi::Result<()> {
+    let Some(max) = options.and_then(|o| o.max_sql_bytes) else {
+        return Ok(());
+    };
+    if max < 0.0 || max.fract() != 0.0 {
+        return Err(napi::Error::from_reason(
+            "maxSqlBytes must be a non-negative integer",
+        ));
+    }
+    if sql.len() as f64 > max {
+        return Err(napi::Error::from_reason(format!(
+            "SQL is {} bytes, more than maxSqlBytes ({max})",
+            sql.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Options accepted by `begin()` / `beginSync()`.
+#[napi(object, object_to_js = false)]
+pub struct TransactionOptions {
+    /// Defer constraint checks until commit. The engine checks foreign keys
+    /// and other constraints as each statement runs, so this is rejected
+    /// until it gains deferred checking; see `Database.capabilities()`.
+    pub defer_constraints: Option<bool>,
+    /// Roll the transaction back if it is still open this many milliseconds
+    /// after it began, emitting a `transactionTimeout` event.
+    pub max_duration_ms: Option<f64>,
+    /// Keep the statements the transaction runs, with their parameters, for
+    /// `tx.toSql()` (default false).
+    pub record_sql: Option<bool>,
+    /// Run at snapshot isolation and reuse the rows of a SELECT run again
+    /// with the same SQL and parameters, until the transaction executes a
+    /// statement (default false).
+    pub memoize: Option<bool>,
+}
+
+/// Validate transaction options before beginning. Returns the
+/// `maxDurationMs` limit, if any.
+pub fn check_transaction_options(
+    options: Option<&TransactionOptions>,
+) -> napi::Result<Option<Duration>> {
+    if options.and_then(|o| o.defer_constraints).unwrap_or(false) {
+        return Err(napi::Error::from_reason(
+            "deferConstraints is not supported: the engine checks constraints immediately",
+        ));
+    }
+    match options.and_then(|o| o.max_duration_ms) {
+        Some(ms) if !ms.is_finite() || ms <= 0.0 => Err(napi::Error::from_reason(
+            "maxDurationMs must be a positive number of milliseconds",
+        )),
+        ms => Ok(ms.map(|ms| Duration::from_secs_f64(ms / 1000.0))),
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `offset` and `limit` query options.
+//
+// They page through the results of SQL a caller can't add `LIMIT` and
+// `OFFSET` to, such as a view's or a prepared statement's. The rows before
+// `offset` are read from the engine and dropped: they are never converted
+// to JS, and async calls don't keep them or count them against the memory
+// budget. Reading stops after `limit` rows, so the rest of the result is
+// never produced. `queryOne()` returns the row at `offset`.
+//
+// `maxRows` guards against results too large to hold: reading stops after
+// that many rows too, and if one more is left, the call fails or, with
+// `onMaxRows: 'truncate'`, returns the rows it has with `truncated: true`.
+// A database can set both for every query of its own, its statements,
+// transactions and pipelines.
+
+use stoolap::Rows;
+
+use crate::interrupt::{Ticket, CHECK_INTERVAL};
+
+/// The `maxRows` and `onMaxRows` options.
+#[derive(Clone, Copy)]
+pub struct MaxRows {
+    rows: usize,
+    truncate: bool,
+}
+
+impl MaxRows {
+    /// The options given, falling back to `default` for those that aren't.
+    pub fn new(
+        rows: Option<f64>,
+        on_max_rows: Option<&str>,
+        default: Option<MaxRows>,
+    ) -> napi::Result<Option<Self>> {
+        let truncate = match on_max_rows {
+            None => default.is_some_and(|d| d.truncate),
+            Some("error") => false,
+            Some("truncate") => true,
+            Some(other) => {
+                return Err(napi::Error::from_reason(format!(
+                    "Invalid onMaxRows '{other}': expected 'error' or 'truncate'"
+                )))
+            }
+        };
+        let rows = match rows {
+            None => match default {
+                Some(default) => default.rows,
+                None => return Ok(None),
+            },
+            Some(rows) if rows >= 1.0 && rows.fract() == 0.0 => rows as usize,
+            Some(_) => {
+                return Err(napi::Error::from_reason(
+                    "maxRows must be a positive integer",
+                ))
+            }
+        };
+        Ok(Some(Self { rows, truncate }))
+    }
+}
+
+/// The rows a call keeps: `limit` of them, after the first `offset`, and no
+/// more than `maxRows`.
+#[derive(Clone, Copy)]
+pub struct Page {
+    offset: usize,
+    limit: usize,
+    max_rows: Option<MaxRows>,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: usize::MAX,
+            max_rows: None,
+        }
+    }
+}
+
+impl Page {
+    pub fn new(offset: Option<f64>, limit: Option<f64>) -> napi::Result<Self> {
+        let count = |name: &str, value: f64| {
+            if value >= 0.0 && value.fract() == 0.0 {
+                Ok(value as usize)
+            } else {
+                Err(napi::Error::from_reason(format!(
+                    "{name} must be a non-negative integer"
+                )))
+            }
+        };
+        let mut page = Self::default();
+        if let Some(offset) = offset {
+            page.offset = count("offset", offset)?;
+        }
+        if let Some(limit) = limit {
+            page.limit = count("limit", limit)?;
+        }
+        Ok(page)
+    }
+
+    pub fn max_rows(mut self, max_rows: Option<MaxRows>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// The most rows the call keeps.
+    pub fn limit(&self) -> usize {
+        match self.max_rows {
+            Some(max) => self.limit.min(max.rows),
+            None => self.limit,
+        }
+    }
+
+    /// Once `kept` rows have been read: whether `maxRows` cut the result
+    /// short, which fails the call unless it truncates.
+    pub fn overflow(&self, rows: &mut Rows, kept: usize) -> napi::Result<bool> {
+        let Some(max) = self.max_rows else {
+            return Ok(false);
+        };
+        if kept < max.rows || self.limit <= max.rows || !rows.advance() {
+            return Ok(false);
+        }
+        if max.truncate {
+            Ok(true)
+        } else {
+            Err(napi::Error::from_reason(format!(
+                "Query returned more than maxRows ({}) rows",
+                max.rows
+            )))
+        }
+    }
+
+    /// Read past the rows before the page, checking `ticket` now and then.
+    /// `rows` is closed when the page keeps none of them.
+    pub fn skip(&self, rows: &mut Rows, ticket: Option<&Ticket>) -> napi::Result<()> {
+        for skipped in 0..self.offset {
+            if skipped % CHECK_INTERVAL == 0 {
+                if let Some(ticket) = ticket {
+                    ticket.check()?;
+                }
+            }
+            if !rows.advance() {
+                break;
+            }
+        }
+        if self.limit == 0 {
+            rows.close();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `parallelism` open and query option.
+//
+// The engine runs filtered scans, sorts, hash joins and aggregations over
+// large inputs on rayon's thread pool, one thread per core by default. It
+// decides when to go parallel from the input size alone and has no setting
+// for how many threads to use, but rayon runs the work of a closure passed to
+// `ThreadPool::install` on that pool instead of the global one. With the
+// option, an async call runs its engine work inside a pool of that many
+// threads: 1 keeps a query on one core, more lets an analytical query use
+// more of them than the other calls on the machine.
+//
+// A pool runs one installed closure per thread at a time, so a call checks a
+// pool out for itself, like a reader handle (see readers.rs), and calls made
+// together each get their own. Pools are kept for later calls of the same
+// size once they are done.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Largest accepted `parallelism` value.
+pub const MAX_PARALLELISM: u32 = 256;
+
+/// How many threads a call's engine work runs on.
+#[derive(Clone, Copy)]
+pub struct Parallelism(usize);
+
+/// Idle pools, by size.
+static IDLE: OnceLock<Mutex<HashMap<usize, Vec<ThreadPool>>>> = OnceLock::new();
+
+impl Parallelism {
+    /// Check `parallelism`.
+    pub fn new(threads: Option<f64>) -> napi::Result<Option<Self>> {
+        match threads {
+            None => Ok(None),
+            Some(n) if n.fract() == 0.0 && (1.0..=MAX_PARALLELISM as f64).contains(&n) => {
+                Ok(Some(Self(n as usize)))
+            }
+            Some(_) => Err(napi::Error::from_reason(format!(
+                "parallelism must be an integer from 1 to {MAX_PARALLELISM}"
+            ))),
+        }
+    }
+}
+
+/// Run `f`, blocking until it is done, with the engine's parallel operators
+/// on a pool of `parallelism` threads, or on rayon's global pool without.
+pub fn run<R: Send>(
+    parallelism: Option<Parallelism>,
+    f: impl FnOnce() -> napi::Result<R> + Send,
+) -> napi::Result<R> {
+    let Some(Parallelism(threads)) = parallelism else {
+        return f();
+    };
+    let pool = match lock().get_mut(&threads).and_then(Vec::pop) {
+        Some(pool) => pool,
+        None => ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(move |i| format!("stoolap-parallel-{threads}-{i}"))
+            .build()
+            .map_err(|e| napi::Error::from_reason(format!("Cannot start threads: {e}")))?,
+    };
+    let result = pool.install(f);
+    lock().entry(threads).or_default().push(pool);
+    result
+}
+
+fn lock() -> MutexGuard<'static, HashMap<usize, Vec<ThreadPool>>> {
+    IDLE.get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
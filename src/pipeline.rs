@@ -0,0 +1,229 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.pipeline()`.
+//
+// Every async call pays for a task, a trip through the thread pool and a
+// promise, which dominates for statements that take microseconds. A pipeline
+// converts parameters and checks the policy as calls are queued, then runs
+// them all in one async call. Each step is the same task a direct call would
+// schedule, so retries, the reader gate, profiling and interrupts behave the
+// same; steps are auto-committed one by one, and the first failure stops the
+// run with earlier steps kept. A run holds every serialized table its steps
+// write to until it is done.
+
+use std::sync::Arc;
+
+use napi::bindgen_prelude::{Object, This};
+use napi::Env;
+use stoolap::api::Database;
+use stoolap::CachedPlanRef;
+
+use crate::changes::ChangeHubRef;
+use crate::database::{automatic_plan, convert_params};
+use crate::interrupt::InterruptRef;
+use crate::options::{apply_query_options, low_memory, page, projection, QueryOptions};
+use crate::paging::Page;
+use crate::plan_cache::PlanCacheRef;
+use crate::policy::PolicyRef;
+use crate::profile::ProfilerRef;
+use crate::projection::Projection;
+use crate::readers::ReadersRef;
+use crate::retry::RetryPolicy;
+use crate::shape::RowShape;
+use crate::tasks::*;
+use crate::value::RawParam;
+use crate::worker::{Scheduled, WorkerRef};
+
+/// A queued call, ready to become a task when the pipeline runs.
+struct Queued {
+    query: bool,
+    low_memory: bool,
+    projection: Option<Projection>,
+    page: Page,
+    sql: String,
+    params: TaskParams,
+    plan: Option<CachedPlanRef>,
+}
+
+#[napi(js_name = "Pipeline")]
+pub struct JsPipeline {
+    db: Arc<Database>,
+    changes: ChangeHubRef,
+    policy: PolicyRef,
+    profiler: ProfilerRef,
+    plans: PlanCacheRef,
+    interrupts: InterruptRef,
+    retry: RetryPolicy,
+    worker: WorkerRef,
+    readers: ReadersRef,
+    queued: Vec<Queued>,
+}
+
+impl JsPipeline {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        db: Arc<Database>,
+        changes: ChangeHubRef,
+        policy: PolicyRef,
+        profiler: ProfilerRef,
+        plans: PlanCacheRef,
+        interrupts: InterruptRef,
+        retry: RetryPolicy,
+        worker: WorkerRef,
+        readers: ReadersRef,
+    ) -> Self {
+        Self {
+            db,
+            changes,
+            policy,
+            profiler,
+            plans,
+            interrupts,
+            retry,
+            worker,
+            readers,
+            queued: Vec::new(),
+        }
+    }
+
+    fn push(
+        &mut self,
+        query: bool,
+        low_memory: bool,
+        projection: Option<Projection>,
+        page: Page,
+        sql: String,
+        params: TaskParams,
+    ) -> napi::Result<()> {
+        let plan = automatic_plan(&self.plans, &self.policy, &sql)?;
+        self.queued.push(Queued {
+            query,
+            low_memory,
+            projection,
+            page,
+            sql,
+            params,
+            plan,
+        });
+        Ok(())
+    }
+}
+
+#[napi]
+impl JsPipeline {
+    /// Queue a query. Its rows come back as an array of objects.
+    #[napi(
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>, options?: QueryOptions",
+        ts_return_type = "this"
+    )]
+    pub fn query<'a>(
+        &mut self,
+        env: Env,
+        this: This<'a>,
+        sql: String,
+        params: Option<RawParam>,
+        options: Option<QueryOptions>,
+    ) -> napi::Result<Object<'a>> {
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let sql = apply_query_options(&env, sql, options.as_ref())?;
+        self.push(
+            true,
+            low_memory(options.as_ref()),
+            projection(options.as_ref())?,
+            page(options.as_ref(), self.worker.max_rows())?,
+            sql,
+            task_params,
+        )?;
+        Ok(this.object)
+    }
+
+    /// Queue a DDL/DML statement. It comes back as a `RunResult`.
+    #[napi(
+        ts_args_type = "sql: string, params?: any[] | Record<string, any>",
+        ts_return_type = "this"
+    )]
+    pub fn execute<'a>(
+        &mut self,
+        env: Env,
+        this: This<'a>,
+        sql: String,
+        params: Option<RawParam>,
+    ) -> napi::Result<Object<'a>> {
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        self.push(false, false, None, Page::default(), sql, task_params)?;
+        Ok(this.object)
+    }
+
+    /// Number of queued calls.
+    #[napi(getter)]
+    pub fn length(&self) -> u32 {
+        self.queued.len() as u32
+    }
+
+    /// Run the queued calls in order. Returns Promise<Array>, one result per
+    /// call. The queue is emptied, so the pipeline can be reused.
+    #[napi(ts_return_type = "Promise<Array<Record<string, any>[] | RunResult>>")]
+    pub fn run(&mut self) -> Scheduled<PipelineTask> {
+        let mut tables: Vec<usize> = self
+            .queued
+            .iter()
+            .filter(|queued| !queued.query)
+            .flat_map(|queued| self.worker.writes().tables_in(&queued.sql))
+            .collect();
+        tables.sort_unstable();
+        tables.dedup();
+        let steps = self
+            .queued
+            .drain(..)
+            .map(|queued| {
+                if queued.query {
+                    PipelineStep::Query(QueryTask {
+                        db: Arc::clone(&self.db),
+                        sql: queued.sql,
+                        params: queued.params,
+                        plan: queued.plan,
+                        profiler: Arc::clone(&self.profiler),
+                        readers: Arc::clone(&self.readers),
+                        retry: self.retry,
+                        ticket: self.interrupts.ticket(),
+                        memory: Arc::clone(self.worker.memory()),
+                        low_memory: queued.low_memory,
+                        shape: RowShape::default(),
+                        names: self.worker.names(),
+                        projection: queued.projection,
+                        page: queued.page,
+                        token: None,
+                    })
+                } else {
+                    PipelineStep::Execute(ExecTask {
+                        db: Arc::clone(&self.db),
+                        sql: queued.sql,
+                        params: queued.params,
+                        plan: queued.plan,
+                        changes: Arc::clone(&self.changes),
+                        profiler: Arc::clone(&self.profiler),
+                        readers: Arc::clone(&self.readers),
+                        retry: self.retry,
+                        ticket: self.interrupts.ticket(),
+                        memory: Arc::clone(self.worker.memory()),
+                        names: self.worker.names(),
+                    })
+                }
+            })
+            .collect();
+        self.worker
+            .schedule_write("pipeline", tables, PipelineTask { steps })
+    }
+}
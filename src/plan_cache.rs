@@ -0,0 +1,262 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Binding-side plan cache behind `db.planCache`.
+//
+// The engine keeps its own plan cache but offers no statistics or controls
+// for it. Plans the binding holds itself (prepared statements, expanded IN
+// lists of prepared statements and deleteMany chunks) are parsed once here
+// instead and run with `execute_plan` / `query_plan`, which skip the engine's
+// cache. In automatic mode, single-statement `execute()` / `query()` calls
+// on the database look up their plan here too, so ad-hoc SQL gets the same
+// reuse without `prepare()`. Multi-statement `exec()` and transaction
+// statements always go through the engine.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use stoolap::api::Database;
+use stoolap::parser::Parser;
+use stoolap::CachedPlanRef;
+
+use crate::error::to_napi;
+use crate::sql::parameter_count;
+
+/// Plans kept before the least recently used one is evicted, matching the
+/// engine's own default.
+pub const DEFAULT_MAX_SIZE: u32 = 1000;
+
+/// Snapshot returned by `db.planCache.stats()`.
+#[napi(object, object_from_js = false)]
+pub struct PlanCacheStats {
+    /// Cached plans.
+    pub entries: u32,
+    pub max_size: u32,
+    /// Lookups served from the cache since the database was opened.
+    pub hits: i64,
+    /// Lookups that had to parse the SQL since the database was opened.
+    pub misses: i64,
+    /// Plans dropped to stay within `maxSize`.
+    pub evictions: i64,
+    /// Bytes of SQL text held as cache keys. Parsed plans are not measured.
+    pub sql_bytes: i64,
+}
+
+struct Entry {
+    plan: CachedPlanRef,
+    last_used: AtomicU64,
+}
+
+pub type PlanCacheRef = Arc<PlanCache>;
+
+pub struct PlanCache {
+    plans: RwLock<HashMap<String, Entry>>,
+    max_size: AtomicUsize,
+    /// Whether ad-hoc `execute()` / `query()` calls use the cache.
+    automatic: AtomicBool,
+    /// Logical clock for least-recently-used eviction.
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Default for PlanCache {
+    fn default() -> Self {
+        Self {
+            plans: RwLock::new(HashMap::new()),
+            max_size: AtomicUsize::new(DEFAULT_MAX_SIZE as usize),
+            automatic: AtomicBool::new(false),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+}
+
+impl PlanCache {
+    /// The plan for `sql`, parsing and caching it on a miss. `None` when the
+    /// SQL is not exactly one valid statement; callers then hand the text to
+    /// the engine, which runs it or reports the error itself.
+    pub fn get(&self, sql: &str) -> Option<CachedPlanRef> {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        if let Some(entry) = read(&self.plans).get(sql) {
+            entry.last_used.store(tick, Ordering::Relaxed);
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(entry.plan.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let mut program = Parser::new(sql).parse_program().ok()?;
+        if program.statements.len() != 1 {
+            return None;
+        }
+        let statement = program.statements.pop()?;
+        let (has_params, param_count) = parameter_count(sql);
+        let plan = CachedPlanRef {
+            statement: Arc::new(statement),
+            has_params,
+            param_count,
+            compiled: Default::default(),
+        };
+
+        let max_size = self.max_size.load(Ordering::Relaxed);
+        if max_size == 0 {
+            return Some(plan);
+        }
+        let mut plans = write(&self.plans);
+        if !plans.contains_key(sql) {
+            self.evict(&mut plans, max_size - 1);
+        }
+        plans.insert(
+            sql.to_string(),
+            Entry {
+                plan: plan.clone(),
+                last_used: AtomicU64::new(tick),
+            },
+        );
+        Some(plan)
+    }
+
+    /// Like `get()`, but an error for SQL that can't be cached, as the engine
+    /// reports it.
+    pub fn plan(&self, db: &Database, sql: &str) -> napi::Result<CachedPlanRef> {
+        match self.get(sql) {
+            Some(plan) => Ok(plan),
+            None => db.cached_plan(sql).map_err(to_napi),
+        }
+    }
+
+    pub fn stats(&self) -> PlanCacheStats {
+        let plans = read(&self.plans);
+        PlanCacheStats {
+            entries: plans.len() as u32,
+            max_size: self.max_size() as u32,
+            hits: self.hits.load(Ordering::Relaxed) as i64,
+            misses: self.misses.load(Ordering::Relaxed) as i64,
+            evictions: self.evictions.load(Ordering::Relaxed) as i64,
+            sql_bytes: plans.keys().map(|sql| sql.len() as i64).sum(),
+        }
+    }
+
+    /// Drop every cached plan. Prepared statements keep the plans they hold.
+    pub fn clear(&self) {
+        write(&self.plans).clear();
+    }
+
+    /// `get()` in automatic mode, otherwise `None` without touching the
+    /// cache or its counters.
+    #[inline]
+    pub fn get_automatic(&self, sql: &str) -> Option<CachedPlanRef> {
+        if self.automatic.load(Ordering::Relaxed) {
+            self.get(sql)
+        } else {
+            None
+        }
+    }
+
+    pub fn automatic(&self) -> bool {
+        self.automatic.load(Ordering::Relaxed)
+    }
+
+    pub fn set_automatic(&self, automatic: bool) {
+        self.automatic.store(automatic, Ordering::Relaxed);
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size.load(Ordering::Relaxed)
+    }
+
+    /// Change the capacity, evicting plans if the cache is now over it.
+    pub fn set_max_size(&self, max_size: usize) {
+        self.max_size.store(max_size, Ordering::Relaxed);
+        self.evict(&mut write(&self.plans), max_size);
+    }
+
+    /// Evict least recently used plans until at most `keep` remain.
+    fn evict(&self, plans: &mut HashMap<String, Entry>, keep: usize) {
+        while plans.len() > keep {
+            let oldest = plans
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(sql, _)| sql.clone());
+            let Some(oldest) = oldest else {
+                return;
+            };
+            plans.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// JS handle for a database's plan cache, returned by `db.planCache`.
+#[napi(js_name = "PlanCache")]
+pub struct JsPlanCache {
+    cache: PlanCacheRef,
+}
+
+impl JsPlanCache {
+    pub fn new(cache: PlanCacheRef) -> Self {
+        Self { cache }
+    }
+}
+
+#[napi]
+impl JsPlanCache {
+    /// Entry count, capacity, hit/miss/eviction counters and key size.
+    #[napi]
+    pub fn stats(&self) -> PlanCacheStats {
+        self.cache.stats()
+    }
+
+    /// Drop every cached plan. The counters are kept.
+    #[napi]
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+
+    /// Maximum number of cached plans (default 1000). Lowering it evicts the
+    /// least recently used plans; 0 disables caching.
+    #[napi(getter)]
+    pub fn max_size(&self) -> u32 {
+        self.cache.max_size() as u32
+    }
+
+    #[napi(setter)]
+    pub fn set_max_size(&self, max_size: u32) {
+        self.cache.set_max_size(max_size as usize);
+    }
+
+    /// Whether `execute()` / `query()` calls on the database reuse cached
+    /// plans like prepared statements do (default false).
+    #[napi(getter)]
+    pub fn automatic(&self) -> bool {
+        self.cache.automatic()
+    }
+
+    #[napi(setter)]
+    pub fn set_automatic(&self, automatic: bool) {
+        self.cache.set_automatic(automatic);
+    }
+}
+
+fn read<T>(lock: &RwLock<T>) -> std::sync::RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> std::sync::RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
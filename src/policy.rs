@@ -0,0 +1,557 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Statement policies for `db.setPolicy()`.
+//
+// Every statement is parsed and checked against the policy before it reaches
+// the engine: its kind against the deny list, and every table it names
+// (including those in subqueries, joins and CTE bodies) against the allow list.
+//
+// A handle made by `db.createRestrictedHandle()` also has the statement types
+// it allows, on top of the policy of the handle it was made from, which it
+// shares: a statement of another type is rejected the same way, and an
+// `EXPLAIN` only runs if the statement it explains is allowed too.
+//
+// With the `strictStatements` open option, the calls that run one statement
+// reject SQL that holds more, as split by `exec()`, so a value pasted into
+// the SQL can't end it and piggyback another; only `exec()` and
+// `queryMulti()` run scripts.
+
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+use stoolap::parser::ast::{Expression, SelectStatement, Statement};
+use stoolap::parser::Parser;
+
+use crate::sql::is_blank;
+use crate::tasks::split_sql_statements;
+
+/// Restrictions applied to every SQL statement run through a database handle.
+#[napi(object, object_to_js = false)]
+pub struct SqlPolicy {
+    /// Statement types to reject, e.g. `'DROP'`, `'ALTER TABLE'` or `'PRAGMA'`.
+    /// A single keyword matches every statement type it starts.
+    pub deny: Option<Vec<String>>,
+    /// When set, statements may only reference these tables and views.
+    pub allow_tables: Option<Vec<String>>,
+}
+
+/// Options for `db.createRestrictedHandle()`.
+#[napi(object, object_to_js = false)]
+pub struct RestrictedHandleOptions {
+    /// The only statement types the handle runs, e.g. `['SELECT']`. A single
+    /// keyword matches every statement type it starts.
+    pub allow: Vec<String>,
+}
+
+/// The policies of a database and everything created from it.
+pub type PolicyRef = Arc<Policies>;
+
+#[derive(Default)]
+pub struct Policies {
+    /// The `setPolicy()` policy, shared with the restricted handles made from
+    /// the database it was set on.
+    active: Arc<RwLock<Option<Policy>>>,
+    /// The statement types allowed by each `createRestrictedHandle()` the
+    /// handle was made with, innermost last.
+    allowed: Vec<Vec<String>>,
+    /// Whether single-statement calls reject several (`strictStatements`).
+    strict: bool,
+}
+
+impl Policies {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            ..Self::default()
+        }
+    }
+
+    /// The policies of a handle made from this one that only runs the
+    /// statement types of `options.allow`.
+    pub fn restricted(&self, options: RestrictedHandleOptions) -> napi::Result<Self> {
+        let mut allowed = self.allowed.clone();
+        allowed.push(
+            options
+                .allow
+                .iter()
+                .map(|entry| statement_type_entry(entry, "allow"))
+                .collect::<napi::Result<_>>()?,
+        );
+        Ok(Self {
+            active: Arc::clone(&self.active),
+            allowed,
+            strict: self.strict,
+        })
+    }
+
+    fn has_active(&self) -> bool {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Whether the handle was made by `createRestrictedHandle()`.
+    pub fn is_restricted(&self) -> bool {
+        !self.allowed.is_empty()
+    }
+
+    /// Fail from `method`, which copies every table's rows, unless the
+    /// `setPolicy()` policy lets statements read any table.
+    pub fn check_read_all(&self, method: &str) -> napi::Result<()> {
+        let guard = self.active.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(ref policy) = *guard {
+            if policy.allow_tables.is_some() {
+                return Err(violation(format!(
+                    "{method}() reads every table, and the policy limits the tables"
+                )));
+            }
+            if policy.deny.iter().any(|entry| matches("SELECT", entry)) {
+                return Err(violation(format!(
+                    "{method}() reads every table, and SELECT statements are not allowed"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set(&self, policy: Option<Policy>) {
+        *self.active.write().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    fn check(&self, stmt: &Statement) -> napi::Result<()> {
+        self.check_allowed(stmt)?;
+        let guard = self.active.read().unwrap_or_else(|e| e.into_inner());
+        match *guard {
+            Some(ref policy) => policy.check(stmt),
+            None => Ok(()),
+        }
+    }
+
+    fn check_allowed(&self, stmt: &Statement) -> napi::Result<()> {
+        let ty = statement_type(stmt);
+        let allowed = |entries: &Vec<String>| entries.iter().any(|entry| matches(ty, entry));
+        if !self.allowed.iter().all(allowed) {
+            return Err(violation(format!(
+                "{ty} statements are not allowed on a restricted handle"
+            )));
+        }
+        if let Statement::Explain(explain) = stmt {
+            return self.check_allowed(&explain.statement);
+        }
+        Ok(())
+    }
+}
+
+pub struct Policy {
+    deny: Vec<String>,
+    allow_tables: Option<HashSet<String>>,
+}
+
+/// Statement types, as matched by `deny` entries.
+const STATEMENT_TYPES: &[&str] = &[
+    "SELECT",
+    "INSERT",
+    "UPDATE",
+    "DELETE",
+    "TRUNCATE",
+    "CREATE TABLE",
+    "DROP TABLE",
+    "ALTER TABLE",
+    "CREATE INDEX",
+    "DROP INDEX",
+    "CREATE VIEW",
+    "DROP VIEW",
+    "BEGIN",
+    "COMMIT",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "RELEASE SAVEPOINT",
+    "SET",
+    "PRAGMA",
+    "SHOW TABLES",
+    "SHOW VIEWS",
+    "SHOW CREATE TABLE",
+    "SHOW CREATE VIEW",
+    "SHOW INDEXES",
+    "DESCRIBE",
+    "EXPLAIN",
+    "ANALYZE",
+    "VACUUM",
+];
+
+impl Policy {
+    pub fn new(options: SqlPolicy) -> napi::Result<Self> {
+        let deny = options
+            .deny
+            .unwrap_or_default()
+            .iter()
+            .map(|entry| statement_type_entry(entry, "policy deny"))
+            .collect::<napi::Result<_>>()?;
+        let allow_tables = options
+            .allow_tables
+            .map(|tables| tables.iter().map(|t| t.to_lowercase()).collect());
+        Ok(Self { deny, allow_tables })
+    }
+
+    fn check(&self, stmt: &Statement) -> napi::Result<()> {
+        let ty = statement_type(stmt);
+        if self.deny.iter().any(|entry| matches(ty, entry)) {
+            return Err(violation(format!("{ty} statements are not allowed")));
+        }
+        if let Statement::Explain(explain) = stmt {
+            return self.check(&explain.statement);
+        }
+        if let Some(ref allowed) = self.allow_tables {
+            let mut tables = Tables::default();
+            tables.statement(stmt);
+            if let Some(table) = tables.names.iter().find(|t| !allowed.contains(*t)) {
+                return Err(violation(format!("table '{table}' is not allowed")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Normalize a `deny` or `allow` entry, which must match a statement type.
+fn statement_type_entry(entry: &str, list: &str) -> napi::Result<String> {
+    let entry = entry.split_whitespace().collect::<Vec<_>>().join(" ");
+    let entry = entry.to_ascii_uppercase();
+    if !STATEMENT_TYPES.iter().any(|ty| matches(ty, &entry)) {
+        return Err(napi::Error::from_reason(format!(
+            "Unknown statement type '{entry}' in {list} list"
+        )));
+    }
+    Ok(entry)
+}
+
+/// Check every statement in `sql` against the active policies, if any.
+pub fn check_sql(policy: &PolicyRef, sql: &str) -> napi::Result<()> {
+    if !policy.is_restricted() && !policy.has_active() {
+        return Ok(());
+    }
+    let mut parser = Parser::new(sql);
+    let program = parser
+        .parse_program()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    for stmt in &program.statements {
+        policy.check(stmt)?;
+    }
+    Ok(())
+}
+
+/// Check that `sql`, which a single-statement call runs, holds one statement,
+/// with `strictStatements`.
+pub fn check_single(policy: &PolicyRef, sql: &str) -> napi::Result<()> {
+    if !policy.strict {
+        return Ok(());
+    }
+    let statements = split_sql_statements(sql)
+        .into_iter()
+        .filter(|stmt| !is_blank(stmt.trim()))
+        .count();
+    if statements > 1 {
+        return Err(violation(format!(
+            "{statements} statements in a call that runs one; use exec() for scripts"
+        )));
+    }
+    Ok(())
+}
+
+/// `check_single()`, then `check_sql()`.
+pub fn check_single_sql(policy: &PolicyRef, sql: &str) -> napi::Result<()> {
+    check_single(policy, sql)?;
+    check_sql(policy, sql)
+}
+
+/// Check an already-parsed statement against the active policies, if any.
+pub fn check_statement(policy: &PolicyRef, stmt: &Statement) -> napi::Result<()> {
+    policy.check(stmt)
+}
+
+fn violation(message: String) -> napi::Error {
+    napi::Error::from_reason(format!("SQL policy violation: {message}"))
+}
+
+/// Whether deny `entry` covers statement type `ty`: an exact match, or a
+/// leading keyword such as `DROP` for `DROP TABLE`.
+fn matches(ty: &str, entry: &str) -> bool {
+    ty == entry
+        || ty
+            .strip_prefix(entry)
+            .is_some_and(|rest| rest.starts_with(' '))
+}
+
+pub(crate) fn statement_type(stmt: &Statement) -> &'static str {
+    match stmt {
+        Statement::Select(_) | Statement::Expression(_) => "SELECT",
+        Statement::Insert(_) => "INSERT",
+        Statement::Update(_) => "UPDATE",
+        Statement::Delete(_) => "DELETE",
+        Statement::Truncate(_) => "TRUNCATE",
+        Statement::CreateTable(_) => "CREATE TABLE",
+        Statement::DropTable(_) => "DROP TABLE",
+        Statement::AlterTable(_) => "ALTER TABLE",
+        Statement::CreateIndex(_) => "CREATE INDEX",
+        Statement::DropIndex(_) => "DROP INDEX",
+        Statement::CreateView(_) => "CREATE VIEW",
+        Statement::DropView(_) => "DROP VIEW",
+        Statement::Begin(_) => "BEGIN",
+        Statement::Commit(_) => "COMMIT",
+        Statement::Rollback(_) => "ROLLBACK",
+        Statement::Savepoint(_) => "SAVEPOINT",
+        Statement::ReleaseSavepoint(_) => "RELEASE SAVEPOINT",
+        Statement::Set(_) => "SET",
+        Statement::Pragma(_) => "PRAGMA",
+        Statement::ShowTables(_) => "SHOW TABLES",
+        Statement::ShowViews(_) => "SHOW VIEWS",
+        Statement::ShowCreateTable(_) => "SHOW CREATE TABLE",
+        Statement::ShowCreateView(_) => "SHOW CREATE VIEW",
+        Statement::ShowIndexes(_) => "SHOW INDEXES",
+        Statement::Describe(_) => "DESCRIBE",
+        Statement::Explain(_) => "EXPLAIN",
+        Statement::Analyze(_) => "ANALYZE",
+        Statement::Vacuum(_) => "VACUUM",
+    }
+}
+
+/// Lowercased names of the tables and views `stmt` references, without
+/// CTE names, each once.
+pub fn referenced_tables(stmt: &Statement) -> Vec<String> {
+    let mut tables = Tables::default();
+    tables.statement(stmt);
+    let mut names = tables.names;
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Table and view names referenced by a statement, lowercased.
+///
+/// A CTE name shadows a table only in the FROM and JOIN sources of the query
+/// whose WITH defines it: its main query, the queries of its set operations
+/// and the bodies of the CTEs after it (and its own body, if it is
+/// recursive). Anywhere else, such as in a subquery or in the body of a CTE
+/// that selects from a table of its own name, the name is the table's.
+#[derive(Default)]
+struct Tables {
+    names: Vec<String>,
+    /// The CTE names that shadow tables in the query being walked.
+    ctes: HashSet<String>,
+}
+
+impl Tables {
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Select(s) => self.select(s),
+            Statement::Insert(s) => {
+                self.name(&s.table_name.value_lower);
+                s.values.iter().flatten().for_each(|e| self.expr(e));
+                if let Some(ref select) = s.select {
+                    self.select(select);
+                }
+                s.update_expressions.iter().for_each(|e| self.expr(e));
+                s.returning.iter().for_each(|e| self.expr(e));
+            }
+            Statement::Update(s) => {
+                self.name(&s.table_name.value_lower);
+                s.updates.values().for_each(|e| self.expr(e));
+                self.opt(&s.where_clause);
+                s.returning.iter().for_each(|e| self.expr(e));
+            }
+            Statement::Delete(s) => {
+                self.name(&s.table_name.value_lower);
+                self.opt(&s.where_clause);
+                s.returning.iter().for_each(|e| self.expr(e));
+            }
+            Statement::CreateTable(s) => {
+                self.name(&s.table_name.value_lower);
+                if let Some(ref select) = s.as_select {
+                    self.select(select);
+                }
+            }
+            Statement::AlterTable(s) => {
+                self.name(&s.table_name.value_lower);
+                if let Some(ref renamed) = s.new_table_name {
+                    self.name(&renamed.value_lower);
+                }
+            }
+            Statement::CreateView(s) => {
+                self.name(&s.view_name.value_lower);
+                self.select(&s.query);
+            }
+            Statement::Truncate(s) => self.name(&s.table_name.value_lower),
+            Statement::DropTable(s) => self.name(&s.table_name.value_lower),
+            Statement::CreateIndex(s) => self.name(&s.table_name.value_lower),
+            Statement::DropIndex(s) => {
+                if let Some(ref table) = s.table_name {
+                    self.name(&table.value_lower);
+                }
+            }
+            Statement::DropView(s) => self.name(&s.view_name.value_lower),
+            Statement::ShowCreateTable(s) => self.name(&s.table_name.value_lower),
+            Statement::ShowCreateView(s) => self.name(&s.view_name.value_lower),
+            Statement::ShowIndexes(s) => self.name(&s.table_name.value_lower),
+            Statement::Describe(s) => self.name(&s.table_name.value_lower),
+            Statement::Analyze(s) => {
+                if let Some(ref table) = s.table_name {
+                    self.name(&table.to_lowercase());
+                }
+            }
+            Statement::Vacuum(s) => {
+                if let Some(ref table) = s.table_name {
+                    self.name(&table.value_lower);
+                }
+            }
+            Statement::Explain(s) => self.statement(&s.statement),
+            Statement::Expression(s) => self.expr(&s.expression),
+            Statement::Begin(_)
+            | Statement::Commit(_)
+            | Statement::Rollback(_)
+            | Statement::Savepoint(_)
+            | Statement::ReleaseSavepoint(_)
+            | Statement::Set(_)
+            | Statement::Pragma(_)
+            | Statement::ShowTables(_)
+            | Statement::ShowViews(_) => {}
+        }
+    }
+
+    fn select(&mut self, s: &SelectStatement) {
+        self.query(s, HashSet::new());
+    }
+
+    /// Walk `s`, whose FROM and JOIN sources see the CTEs named in `ctes`
+    /// besides its own.
+    fn query(&mut self, s: &SelectStatement, mut ctes: HashSet<String>) {
+        if let Some(ref with) = s.with {
+            for cte in &with.ctes {
+                let name = cte.name.value_lower.to_string();
+                let mut seen = ctes.clone();
+                if with.is_recursive || cte.is_recursive {
+                    seen.insert(name.clone());
+                }
+                self.query(&cte.query, seen);
+                ctes.insert(name);
+            }
+        }
+        let outer = std::mem::replace(&mut self.ctes, ctes);
+        self.opt(&s.table_expr);
+        s.columns.iter().for_each(|e| self.expr(e));
+        self.opt(&s.where_clause);
+        s.group_by.columns.iter().for_each(|e| self.expr(e));
+        self.opt(&s.having);
+        for def in &s.window_defs {
+            def.partition_by.iter().for_each(|e| self.expr(e));
+            def.order_by.iter().for_each(|o| self.expr(&o.expression));
+        }
+        s.order_by.iter().for_each(|o| self.expr(&o.expression));
+        self.opt(&s.limit);
+        self.opt(&s.offset);
+        for op in &s.set_operations {
+            self.query(&op.right, self.ctes.clone());
+        }
+        self.ctes = outer;
+    }
+
+    fn opt(&mut self, expr: &Option<Box<Expression>>) {
+        if let Some(ref e) = expr {
+            self.expr(e);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::TableSource(src) => {
+                if !self.ctes.contains(src.name.value_lower.as_str()) {
+                    self.name(&src.name.value_lower);
+                }
+            }
+            Expression::JoinSource(join) => {
+                self.expr(&join.left);
+                self.expr(&join.right);
+                self.opt(&join.condition);
+            }
+            Expression::SubquerySource(src) => self.select(&src.subquery),
+            Expression::ValuesSource(src) => src.rows.iter().flatten().for_each(|e| self.expr(e)),
+            Expression::ScalarSubquery(q) => self.select(&q.subquery),
+            Expression::Exists(e) => self.select(&e.subquery),
+            Expression::AllAny(e) => {
+                self.expr(&e.left);
+                self.select(&e.subquery);
+            }
+            Expression::Prefix(e) => self.expr(&e.right),
+            Expression::Infix(e) => {
+                self.expr(&e.left);
+                self.expr(&e.right);
+            }
+            Expression::List(list) => list.elements.iter().for_each(|e| self.expr(e)),
+            Expression::ExpressionList(list) => list.expressions.iter().for_each(|e| self.expr(e)),
+            Expression::Distinct(e) => self.expr(&e.expr),
+            Expression::In(e) => {
+                self.expr(&e.left);
+                self.expr(&e.right);
+            }
+            Expression::InHashSet(e) => self.expr(&e.column),
+            Expression::Between(e) => {
+                self.expr(&e.expr);
+                self.expr(&e.lower);
+                self.expr(&e.upper);
+            }
+            Expression::Like(e) => {
+                self.expr(&e.left);
+                self.expr(&e.pattern);
+                self.opt(&e.escape);
+            }
+            Expression::Case(case) => {
+                self.opt(&case.value);
+                for when in &case.when_clauses {
+                    self.expr(&when.condition);
+                    self.expr(&when.then_result);
+                }
+                self.opt(&case.else_value);
+            }
+            Expression::Cast(e) => self.expr(&e.expr),
+            Expression::FunctionCall(call) => {
+                call.arguments.iter().for_each(|e| self.expr(e));
+                call.order_by.iter().for_each(|o| self.expr(&o.expression));
+                self.opt(&call.filter);
+            }
+            Expression::Aliased(e) => self.expr(&e.expression),
+            Expression::Window(w) => {
+                w.function.arguments.iter().for_each(|e| self.expr(e));
+                w.partition_by.iter().for_each(|e| self.expr(e));
+                w.order_by.iter().for_each(|o| self.expr(&o.expression));
+            }
+            Expression::Identifier(_)
+            | Expression::QualifiedIdentifier(_)
+            | Expression::IntegerLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::NullLiteral(_)
+            | Expression::IntervalLiteral(_)
+            | Expression::Parameter(_)
+            | Expression::CteReference(_)
+            | Expression::Star(_)
+            | Expression::QualifiedStar(_)
+            | Expression::Default(_) => {}
+        }
+    }
+
+    fn name(&mut self, name: &str) {
+        self.names.push(name.to_string());
+    }
+}
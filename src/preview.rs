@@ -0,0 +1,132 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `preview` query option of `queryRaw()`.
+//
+// A UI that shows the first rows of a result as objects and passes the
+// whole of it on in columnar form would otherwise run the query twice. With
+// `preview: n`, the raw result also has a `preview` array of its first `n`
+// rows as objects, keyed as `query()` keys them, except that
+// `duplicateColumns: 'expand'` falls back to suffixes. They are built from
+// the raw rows once those exist, so both come from one execution and share
+// their values, custom types and JSON objects included.
+
+use std::ptr;
+
+use napi::sys;
+
+use crate::column_names::ColumnNames;
+use crate::tasks::check;
+
+/// The `preview` query option, checked.
+pub fn count(preview: Option<f64>) -> napi::Result<Option<usize>> {
+    match preview {
+        Some(n) if n < 0.0 || n.fract() != 0.0 => Err(napi::Error::from_reason(
+            "preview must be a non-negative integer",
+        )),
+        n => Ok(n.map(|n| n as usize)),
+    }
+}
+
+/// Set `preview` on the raw result `raw` to its first `count` rows as
+/// objects.
+pub fn attach(
+    env: sys::napi_env,
+    raw: sys::napi_value,
+    count: usize,
+    names: ColumnNames,
+) -> napi::Result<()> {
+    let columns = property(env, raw, c"columns")?;
+    let cased = elements(env, columns, u32::MAX)?
+        .into_iter()
+        .map(|column| string(env, column))
+        .collect::<napi::Result<Vec<_>>>()?;
+    let keys = names
+        .dedupe(cased)?
+        .iter()
+        .map(|key| js_string(env, key))
+        .collect::<napi::Result<Vec<_>>>()?;
+    let rows = elements(
+        env,
+        property(env, raw, c"rows")?,
+        count.min(u32::MAX as usize) as u32,
+    )?;
+
+    let mut preview = ptr::null_mut();
+    check(unsafe { sys::napi_create_array_with_length(env, rows.len(), &mut preview) })?;
+    for (i, row) in rows.into_iter().enumerate() {
+        let mut object = ptr::null_mut();
+        check(unsafe { sys::napi_create_object(env, &mut object) })?;
+        for (j, &key) in keys.iter().enumerate() {
+            let mut cell = ptr::null_mut();
+            check(unsafe { sys::napi_get_element(env, row, j as u32, &mut cell) })?;
+            check(unsafe { sys::napi_set_property(env, object, key, cell) })?;
+        }
+        check(unsafe { sys::napi_set_element(env, preview, i as u32, object) })?;
+    }
+    check(unsafe { sys::napi_set_named_property(env, raw, c"preview".as_ptr(), preview) })
+}
+
+fn property(
+    env: sys::napi_env,
+    object: sys::napi_value,
+    name: &std::ffi::CStr,
+) -> napi::Result<sys::napi_value> {
+    let mut value = ptr::null_mut();
+    check(unsafe { sys::napi_get_named_property(env, object, name.as_ptr(), &mut value) })?;
+    Ok(value)
+}
+
+/// The first `max` elements of `array`.
+fn elements(
+    env: sys::napi_env,
+    array: sys::napi_value,
+    max: u32,
+) -> napi::Result<Vec<sys::napi_value>> {
+    let mut len = 0u32;
+    check(unsafe { sys::napi_get_array_length(env, array, &mut len) })?;
+    (0..len.min(max))
+        .map(|i| {
+            let mut element = ptr::null_mut();
+            check(unsafe { sys::napi_get_element(env, array, i, &mut element) })?;
+            Ok(element)
+        })
+        .collect()
+}
+
+fn string(env: sys::napi_env, value: sys::napi_value) -> napi::Result<String> {
+    let mut len = 0;
+    check(unsafe { sys::napi_get_value_string_utf8(env, value, ptr::null_mut(), 0, &mut len) })?;
+    let mut buf = vec![0u8; len + 1];
+    let mut written = 0;
+    check(unsafe {
+        sys::napi_get_value_string_utf8(
+            env,
+            value,
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+            &mut written,
+        )
+    })?;
+    buf.truncate(written);
+    String::from_utf8(buf).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+fn js_string(env: sys::napi_env, s: &str) -> napi::Result<sys::napi_value> {
+    let mut value = ptr::null_mut();
+    check(unsafe {
+        sys::napi_create_string_utf8(env, s.as_ptr().cast(), s.len() as isize, &mut value)
+    })?;
+    Ok(value)
+}
@@ -0,0 +1,295 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Statement timing for `db.on('profile')` and `db.record()`, and the
+// statement counters (see counters.rs), index advisor (see advisor.rs) and
+// statement log (see statement_log.rs).
+//
+// Every statement entry point brackets its work with `start()` / `finish()`,
+// or `start_with()` to hand over its parameters for the recording.
+// `start()` is a single atomic load while no listener is registered, nothing
+// records or logs and the advisor is off, so profiling costs nothing until
+// it is used. Plans are EXPLAINed after the statement completes, and only when a
+// listener asked for them and the statement crossed its threshold.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+
+use stoolap::{CachedPlanRef, ParamVec};
+
+use crate::advisor::{IndexAdvisor, IndexAdvisorOptions};
+use crate::counters::Counters;
+use crate::explain::{self, QueryPlan};
+use crate::recording::{Recorded, Recorder};
+use crate::statement_log::{StatementLog, StatementSink};
+use crate::tasks::{DbHandle, TaskParams};
+
+/// Timing for one completed statement.
+#[napi(object, object_from_js = false, use_nullable = true)]
+#[derive(Clone)]
+pub struct ProfileEvent {
+    pub sql: String,
+    pub duration_ms: f64,
+    /// Rows returned by a query, or rows changed by DML.
+    pub rows: i64,
+    /// Query plan, when the listener was registered with `{ plan: true }`.
+    pub plan: Option<QueryPlan>,
+}
+
+/// Options for `db.on('profile', listener, options)`.
+#[napi(object, object_to_js = false)]
+pub struct ProfileOptions {
+    /// Only report statements that took at least this long.
+    pub threshold_ms: Option<f64>,
+    /// Attach the statement's query plan to each reported event.
+    pub plan: Option<bool>,
+}
+
+pub type ProfileListener =
+    ThreadsafeFunction<ProfileEvent, Unknown<'static>, ProfileEvent, Status, false, true>;
+
+pub type ProfilerRef = Arc<Profiler>;
+
+struct Subscriber {
+    listener: ProfileListener,
+    threshold_ms: f64,
+    plan: bool,
+}
+
+/// A statement being timed, with its parameters while recording.
+pub struct Started {
+    at: Instant,
+    params: Option<Recorded>,
+    /// Sampled for the statement log.
+    logged: bool,
+}
+
+/// Profile listener registry shared by a database and everything created from it.
+pub struct Profiler {
+    db: DbHandle,
+    active: AtomicBool,
+    subscribers: Mutex<Vec<Subscriber>>,
+    recorder: Recorder,
+    counters: Counters,
+    advisor: IndexAdvisor,
+    log: StatementLog,
+}
+
+impl Profiler {
+    pub fn new(db: DbHandle) -> Self {
+        Self {
+            db,
+            active: AtomicBool::new(false),
+            subscribers: Mutex::new(Vec::new()),
+            recorder: Recorder::default(),
+            counters: Counters::default(),
+            advisor: IndexAdvisor::default(),
+            log: StatementLog::default(),
+        }
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    pub fn advisor(&self) -> &IndexAdvisor {
+        &self.advisor
+    }
+
+    /// Hand completed SELECTs to the index advisor from now on.
+    pub fn enable_advisor(&self, options: Option<IndexAdvisorOptions>) -> napi::Result<()> {
+        self.advisor.enable(options)?;
+        self.active.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn disable_advisor(&self) {
+        self.advisor.disable();
+        self.refresh();
+    }
+
+    /// Time statements while anything needs them.
+    fn refresh(&self) {
+        let listening = !lock(&self.subscribers).is_empty();
+        let active =
+            listening || self.recorder.active() || self.advisor.enabled() || self.log.active();
+        self.active.store(active, Ordering::Release);
+    }
+
+    /// Record every statement to `path` from now on.
+    pub fn record(&self, path: &str) -> napi::Result<()> {
+        self.recorder.start(path)?;
+        self.active.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Stop recording and close the file.
+    pub fn stop_recording(&self) -> napi::Result<()> {
+        let stopped = self.recorder.stop();
+        self.refresh();
+        stopped
+    }
+
+    /// Send a `sample` of the statements that complete to `sink` from now
+    /// on, with their parameters if `include_params`.
+    pub fn log_statements(
+        &self,
+        sink: StatementSink,
+        sample: Option<f64>,
+        include_params: bool,
+    ) -> napi::Result<()> {
+        self.log.start(sink, sample, include_params)?;
+        self.refresh();
+        Ok(())
+    }
+
+    pub fn stop_logging_statements(&self) {
+        self.log.stop();
+        self.refresh();
+    }
+
+    pub fn subscribe(&self, listener: ProfileListener, options: Option<ProfileOptions>) {
+        let (threshold_ms, plan) = options.map_or((0.0, false), |o| {
+            (o.threshold_ms.unwrap_or(0.0), o.plan.unwrap_or(false))
+        });
+        lock(&self.subscribers).push(Subscriber {
+            listener,
+            threshold_ms,
+            plan,
+        });
+        self.active.store(true, Ordering::Release);
+    }
+
+    /// Start timing a statement; `None` while no listener is registered,
+    /// nothing records or logs and the advisor is off.
+    #[inline]
+    pub fn start(&self) -> Option<Started> {
+        self.start_recording(|| None)
+    }
+
+    /// `start()` for a statement run with `params`, which are copied while
+    /// recording or advising, and for a statement logged with its params.
+    #[inline]
+    pub fn start_with(&self, params: &TaskParams) -> Option<Started> {
+        self.start_recording(|| Some(Recorded::Params(params.clone())))
+    }
+
+    /// `start_with()` for positional parameters.
+    #[inline]
+    pub fn start_positional(&self, params: &ParamVec) -> Option<Started> {
+        self.start_recording(|| Some(Recorded::Params(TaskParams::Positional(params.clone()))))
+    }
+
+    /// `start()` for the rows of `executeBatch()`.
+    #[inline]
+    pub fn start_batch(&self, rows: &[ParamVec]) -> Option<Started> {
+        self.start_recording(|| Some(Recorded::Batch(rows.to_vec())))
+    }
+
+    #[inline]
+    fn start_recording(&self, params: impl FnOnce() -> Option<Recorded>) -> Option<Started> {
+        if !self.active.load(Ordering::Acquire) {
+            return None;
+        }
+        let logged = self.log.sampled();
+        let copies = self.recorder.active()
+            || self.advisor.enabled()
+            || (logged && self.log.includes_params());
+        Some(Started {
+            at: Instant::now(),
+            params: if copies { params() } else { None },
+            logged,
+        })
+    }
+
+    /// Report a statement started with `start()`. Prepared statements pass
+    /// their cached plan so `{ plan: true }` listeners see exactly what ran.
+    pub fn finish(
+        &self,
+        started: Option<Started>,
+        sql: &str,
+        rows: i64,
+        cached: Option<&CachedPlanRef>,
+    ) {
+        self.counters.ran();
+        let Some(started) = started else {
+            return;
+        };
+        let duration_ms = started.at.elapsed().as_secs_f64() * 1000.0;
+        if self.recorder.active() {
+            self.recorder
+                .write(sql, started.params.as_ref(), duration_ms, rows);
+        }
+        if started.logged {
+            self.log
+                .write(sql, started.params.as_ref(), duration_ms, rows);
+        }
+        self.advisor.observe(sql, started.params, duration_ms);
+        let subscribers = lock(&self.subscribers);
+        let due: Vec<&Subscriber> = subscribers
+            .iter()
+            .filter(|s| duration_ms >= s.threshold_ms)
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+        let plan = if due.iter().any(|s| s.plan) {
+            self.explain(sql, cached)
+        } else {
+            None
+        };
+        for subscriber in due {
+            let event = ProfileEvent {
+                sql: sql.to_string(),
+                duration_ms,
+                rows,
+                plan: if subscriber.plan { plan.clone() } else { None },
+            };
+            subscriber
+                .listener
+                .call(event, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+
+    /// `finish()` for a statement run for its changes.
+    pub fn finish_write(
+        &self,
+        started: Option<Started>,
+        sql: &str,
+        changes: i64,
+        cached: Option<&CachedPlanRef>,
+    ) {
+        self.counters.changed(changes);
+        self.finish(started, sql, changes, cached);
+    }
+
+    /// Plain EXPLAIN of the statement. Plans that can't be produced (e.g. for
+    /// a table created inside a still-open transaction) are reported as null.
+    fn explain(&self, sql: &str, cached: Option<&CachedPlanRef>) -> Option<QueryPlan> {
+        let plan = match cached {
+            Some(plan) => plan.clone(),
+            None => self.db.cached_plan(sql).ok()?,
+        };
+        let params = TaskParams::Positional(ParamVec::new());
+        explain::explain(&self.db, &plan, &params, false).ok()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
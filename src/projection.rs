@@ -0,0 +1,107 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `columns` query option.
+//
+// A call with `columns` returns only those result columns, in that order, as
+// if its SQL had selected just them. Rows are projected as they are read from
+// the engine: the other values are never converted to JS, and async calls
+// don't keep them or count them against the memory budget. Names match the
+// result's columns as the engine names them, first exactly and then ignoring
+// ASCII case, before `columnCase` converts them.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use stoolap::Value;
+
+/// The columns a call keeps, in order.
+#[derive(Clone)]
+pub struct Projection(Arc<[String]>);
+
+impl Projection {
+    pub fn new(columns: Vec<String>) -> napi::Result<Self> {
+        if columns.is_empty() {
+            return Err(napi::Error::from_reason(
+                "columns must name at least one column",
+            ));
+        }
+        for (i, column) in columns.iter().enumerate() {
+            if columns[..i].iter().any(|c| c.eq_ignore_ascii_case(column)) {
+                return Err(napi::Error::from_reason(format!(
+                    "Column '{column}' is named twice in columns"
+                )));
+            }
+        }
+        Ok(Self(columns.into()))
+    }
+
+    /// The index of each kept column among a result's `columns`.
+    pub fn pick(&self, columns: &[String]) -> napi::Result<Vec<usize>> {
+        self.0
+            .iter()
+            .map(|name| {
+                columns
+                    .iter()
+                    .position(|c| c == name)
+                    .or_else(|| columns.iter().position(|c| c.eq_ignore_ascii_case(name)))
+                    .ok_or_else(|| {
+                        napi::Error::from_reason(format!(
+                            "Unknown column '{name}' in columns: the result has {}",
+                            columns.join(", ")
+                        ))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// The picks of `projection` among `columns`, when there is one.
+pub fn pick(
+    columns: &[String],
+    projection: Option<&Projection>,
+) -> napi::Result<Option<Vec<usize>>> {
+    projection.map(|p| p.pick(columns)).transpose()
+}
+
+/// A result's `columns` as `projection` keeps them, with its picks.
+pub fn project(
+    columns: &[String],
+    projection: Option<&Projection>,
+) -> napi::Result<(Vec<String>, Option<Vec<usize>>)> {
+    let pick = pick(columns, projection)?;
+    Ok((picked_columns(columns, pick.as_deref()), pick))
+}
+
+/// The names of the picked columns, or of all of them.
+pub fn picked_columns(columns: &[String], pick: Option<&[usize]>) -> Vec<String> {
+    match pick {
+        Some(pick) => pick.iter().map(|&i| columns[i].clone()).collect(),
+        None => columns.to_vec(),
+    }
+}
+
+/// The values of the picked columns of one row, or all of them. A row stored
+/// before `ALTER TABLE ADD COLUMN` has no value for the added columns, which
+/// are NULL.
+pub fn picked_values<'a>(row: &'a [Value], pick: Option<&[usize]>) -> Cow<'a, [Value]> {
+    match pick {
+        Some(pick) => Cow::Owned(
+            pick.iter()
+                .map(|&i| row.get(i).cloned().unwrap_or_else(Value::null_unknown))
+                .collect(),
+        ),
+        None => Cow::Borrowed(row),
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `readers` open option.
+//
+// The engine runs every statement of one handle under that handle's executor
+// lock, so async queries on a database take turns even when the pool runs
+// them on different threads. With `readers: n` the database keeps n more
+// handles on the same engine. An async auto-committed query that is a single
+// SELECT checks one out and runs next to other reads; any other async
+// auto-committed statement is a write and takes the gate exclusively, waiting
+// for running reads and holding back new ones until it is done. A read that
+// finds every reader busy runs on the main handle. Explicit transactions and
+// sync methods stay on the main handle and don't take the gate.
+//
+// The engine caches SELECT results per handle and only invalidates them on
+// writes made through that handle, so a reader drops its cache before every
+// read.
+
+use std::sync::{Mutex, RwLock};
+use std::sync::{MutexGuard, RwLockReadGuard, RwLockWriteGuard};
+
+use stoolap::api::Database;
+use stoolap::parser::ast::Statement;
+use stoolap::CachedPlanRef;
+
+use crate::error::to_napi;
+use crate::sql::is_select;
+
+/// Largest accepted `readers` value.
+pub const MAX_READERS: u32 = 64;
+
+pub type ReadersRef = std::sync::Arc<Readers>;
+
+/// Reader handles and the gate between reads and writes. The default has no
+/// readers and runs everything directly.
+#[derive(Default)]
+pub struct Readers {
+    enabled: bool,
+    gate: RwLock<()>,
+    idle: Mutex<Vec<Database>>,
+}
+
+/// Validate the `readers` open option.
+pub fn reader_count(readers: Option<u32>) -> napi::Result<u32> {
+    match readers.unwrap_or(0) {
+        n if n > MAX_READERS => Err(napi::Error::from_reason(format!(
+            "readers must be at most {MAX_READERS}"
+        ))),
+        n => Ok(n),
+    }
+}
+
+impl Readers {
+    /// `count` reader handles on `db`'s engine.
+    pub fn new(db: &Database, count: u32) -> Self {
+        Self {
+            enabled: count > 0,
+            gate: RwLock::new(()),
+            idle: Mutex::new((0..count).map(|_| db.clone()).collect()),
+        }
+    }
+
+    /// Run a query: on an idle reader when it only reads, otherwise on `db`
+    /// as a write.
+    pub fn query<T>(
+        &self,
+        db: &Database,
+        sql: &str,
+        plan: Option<&CachedPlanRef>,
+        f: impl FnOnce(&Database) -> napi::Result<T>,
+    ) -> napi::Result<T> {
+        if !self.enabled {
+            return f(db);
+        }
+        let reads = match plan {
+            Some(plan) => matches!(*plan.statement, Statement::Select(_)),
+            None => is_select(sql),
+        };
+        if !reads {
+            return self.write(|| f(db));
+        }
+        let _shared = read_lock(&self.gate);
+        let Some(reader) = lock(&self.idle).pop() else {
+            return f(db);
+        };
+        let result = reader
+            .clear_semantic_cache()
+            .map_err(to_napi)
+            .and_then(|()| f(&reader));
+        lock(&self.idle).push(reader);
+        result
+    }
+
+    /// Run a write once running reads are done, holding back new ones.
+    pub fn write<T>(&self, f: impl FnOnce() -> T) -> T {
+        if !self.enabled {
+            return f();
+        }
+        let _exclusive = write_lock(&self.gate);
+        f()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn read_lock(gate: &RwLock<()>) -> RwLockReadGuard<'_, ()> {
+    gate.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write_lock(gate: &RwLock<()>) -> RwLockWriteGuard<'_, ()> {
+    gate.write().unwrap_or_else(|e| e.into_inner())
+}
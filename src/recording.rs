@@ -0,0 +1,355 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Statement recording and replay, behind `db.record()` and `db.replay()`.
+//
+// While a database records, every statement the profiler times (see
+// profile.rs) is appended to the file as one JSON object per line, with the
+// SQL that ran, its parameters, how long it took and how many rows it
+// returned or changed. Parameters keep their types: integers and floats are
+// told apart by the decimal point, and timestamps, JSON, vectors and
+// non-finite floats are wrapped in a one-key object (`{"timestamp": ...}`).
+// Replay runs the lines in order against another database, one after the
+// other as fast as it can, and reports the time per SQL text next to the
+// recorded time.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use chrono::{DateTime, SecondsFormat};
+use serde_json::{json, Map, Number, Value as Json};
+use stoolap::api::Database;
+use stoolap::{ParamVec, Value};
+use stoolap_bindings_core::cells;
+
+use crate::error::{from_core, to_napi};
+use crate::interrupt::Ticket;
+use crate::policy::{check_sql, PolicyRef};
+use crate::readers::Readers;
+use crate::tasks::TaskParams;
+
+/// The parameters of a recorded statement.
+#[derive(Clone)]
+pub enum Recorded {
+    Params(TaskParams),
+    /// One parameter list per row of `executeBatch()`.
+    Batch(Vec<ParamVec>),
+}
+
+/// The file a database is recording to, if any.
+#[derive(Default)]
+pub struct Recorder {
+    active: AtomicBool,
+    file: Mutex<Option<BufWriter<File>>>,
+    /// The first write that failed, which stopped the recording.
+    error: Mutex<Option<String>>,
+}
+
+impl Recorder {
+    #[inline]
+    pub fn active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Record to `path`, replacing its contents and any recording running.
+    pub fn start(&self, path: &str) -> napi::Result<()> {
+        let file = File::create(path)
+            .map_err(|e| napi::Error::from_reason(format!("Cannot record to '{path}': {e}")))?;
+        self.stop()?;
+        *lock(&self.file) = Some(BufWriter::new(file));
+        self.active.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Flush and close the file. Fails with the error that stopped the
+    /// recording early, if one did.
+    pub fn stop(&self) -> napi::Result<()> {
+        self.active.store(false, Ordering::Release);
+        if let Some(mut file) = lock(&self.file).take() {
+            file.flush()
+                .map_err(|e| napi::Error::from_reason(format!("Recording failed: {e}")))?;
+        }
+        match lock(&self.error).take() {
+            Some(error) => Err(napi::Error::from_reason(format!(
+                "Recording failed: {error}"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write(&self, sql: &str, params: Option<&Recorded>, duration_ms: f64, rows: i64) {
+        let mut line = Map::new();
+        line.insert("sql".into(), Json::from(sql));
+        if let Some((key, params)) = params.and_then(encode_params) {
+            line.insert(key.into(), params);
+        }
+        line.insert("durationMs".into(), Json::from(duration_ms));
+        line.insert("rows".into(), Json::from(rows));
+
+        let mut file = lock(&self.file);
+        let Some(writer) = file.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *writer, &line)
+            .map_err(|e| e.to_string())
+            .and_then(|()| writer.write_all(b"\n").map_err(|e| e.to_string()));
+        if let Err(error) = written {
+            *file = None;
+            self.active.store(false, Ordering::Release);
+            *lock(&self.error) = Some(error);
+        }
+    }
+}
+
+/// `params` as a line stores them: under `params`, or `batch` for the rows
+/// of a batch. `None` when there are none.
+pub fn encode_params(params: &Recorded) -> Option<(&'static str, Json)> {
+    match params {
+        Recorded::Params(TaskParams::Positional(params)) if !params.is_empty() => {
+            Some(("params", positional(params)))
+        }
+        Recorded::Params(TaskParams::Named(params)) => {
+            let named = params
+                .iter()
+                .map(|(name, value)| (name.clone(), encode(value)))
+                .collect();
+            Some(("params", Json::Object(named)))
+        }
+        Recorded::Batch(rows) => Some(("batch", rows.iter().map(positional).collect())),
+        _ => None,
+    }
+}
+
+fn positional(params: &ParamVec) -> Json {
+    params.iter().map(encode).collect()
+}
+
+fn encode(value: &Value) -> Json {
+    match value {
+        Value::Null(_) => Json::Null,
+        Value::Integer(i) => Json::from(*i),
+        Value::Float(f) => match Number::from_f64(*f) {
+            Some(n) => Json::Number(n),
+            None => json!({ "float": f.to_string() }),
+        },
+        Value::Text(s) => Json::from(s.as_str()),
+        Value::Boolean(b) => Json::from(*b),
+        Value::Timestamp(ts) => {
+            json!({ "timestamp": ts.to_rfc3339_opts(SecondsFormat::AutoSi, true) })
+        }
+        Value::Extension(data) => {
+            if let Some(text) = value.as_json() {
+                json!({ "json": text })
+            } else if data.first() == Some(&7) {
+                json!({ "vector": cells::vector(&data[1..]) })
+            } else {
+                Json::Null
+            }
+        }
+    }
+}
+
+fn decode(json: &Json) -> Option<Value> {
+    Some(match json {
+        Json::Null => Value::null_unknown(),
+        Json::Bool(b) => Value::boolean(*b),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Value::integer(i),
+            None => Value::float(n.as_f64()?),
+        },
+        Json::String(s) => Value::text(s.as_str()),
+        Json::Object(wrapped) if wrapped.len() == 1 => {
+            let (kind, inner) = wrapped.iter().next()?;
+            match (kind.as_str(), inner) {
+                ("float", Json::String(f)) => Value::float(f.parse().ok()?),
+                ("timestamp", Json::String(ts)) => {
+                    Value::timestamp(DateTime::parse_from_rfc3339(ts).ok()?.to_utc())
+                }
+                ("json", Json::String(text)) => Value::json(text.as_str()),
+                ("vector", Json::Array(floats)) => Value::vector(
+                    floats
+                        .iter()
+                        .map(|f| f.as_f64().map(|f| f as f32))
+                        .collect::<Option<_>>()?,
+                ),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    })
+}
+
+fn decode_positional(json: &Json) -> Option<ParamVec> {
+    json.as_array()?.iter().map(decode).collect()
+}
+
+/// A parsed line of a recording.
+struct Line {
+    sql: String,
+    params: Option<Recorded>,
+    duration_ms: f64,
+}
+
+fn parse_line(text: &str) -> Option<Line> {
+    let json: Json = serde_json::from_str(text).ok()?;
+    let sql = json.get("sql")?.as_str()?.to_string();
+    let params = if let Some(batch) = json.get("batch") {
+        let rows = batch.as_array()?.iter().map(decode_positional);
+        Some(Recorded::Batch(rows.collect::<Option<_>>()?))
+    } else {
+        match json.get("params") {
+            None => None,
+            Some(Json::Object(named)) => Some(Recorded::Params(TaskParams::Named(
+                named
+                    .iter()
+                    .map(|(name, value)| Some((name.clone(), decode(value)?)))
+                    .collect::<Option<_>>()?,
+            ))),
+            Some(params) => Some(Recorded::Params(TaskParams::Positional(decode_positional(
+                params,
+            )?))),
+        }
+    };
+    let duration_ms = json.get("durationMs").and_then(Json::as_f64).unwrap_or(0.0);
+    Some(Line {
+        sql,
+        params,
+        duration_ms,
+    })
+}
+
+/// What `db.replay()` resolves to.
+#[napi(object, object_from_js = false)]
+pub struct ReplayReport {
+    /// Statements run.
+    pub statements: i64,
+    /// Statements that failed. Replay goes on with the next one.
+    pub errors: i64,
+    /// Milliseconds the statements took now, in all.
+    pub duration_ms: f64,
+    /// Milliseconds they took when they were recorded.
+    pub recorded_ms: f64,
+    /// The same per SQL text, most total time first.
+    pub sql: Vec<ReplayedSql>,
+}
+
+/// Replay times of one SQL text.
+#[napi(object, object_from_js = false, use_nullable = true)]
+pub struct ReplayedSql {
+    pub sql: String,
+    pub statements: i64,
+    pub errors: i64,
+    pub duration_ms: f64,
+    pub recorded_ms: f64,
+    /// The first error it failed with.
+    pub error: Option<String>,
+}
+
+/// Run the statements recorded in `path` against `db`, in order. The whole
+/// file is read first, so a malformed line fails the call before anything
+/// runs.
+pub fn replay(
+    db: &Database,
+    policy: &PolicyRef,
+    readers: &Readers,
+    path: &str,
+    ticket: &Ticket,
+) -> napi::Result<ReplayReport> {
+    let file = File::open(path)
+        .map_err(|e| napi::Error::from_reason(format!("Cannot replay '{path}': {e}")))?;
+    let mut lines = Vec::new();
+    for (i, text) in BufReader::new(file).lines().enumerate() {
+        let text =
+            text.map_err(|e| napi::Error::from_reason(format!("Cannot replay '{path}': {e}")))?;
+        if text.trim().is_empty() {
+            continue;
+        }
+        lines.push(parse_line(&text).ok_or_else(|| {
+            napi::Error::from_reason(format!("Invalid recording: line {} of '{path}'", i + 1))
+        })?);
+    }
+
+    let mut report = ReplayReport {
+        statements: 0,
+        errors: 0,
+        duration_ms: 0.0,
+        recorded_ms: 0.0,
+        sql: Vec::new(),
+    };
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for line in lines {
+        ticket.check()?;
+        let started = Instant::now();
+        let result = check_sql(policy, &line.sql).and_then(|()| readers.write(|| run(db, &line)));
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let i = *index.entry(line.sql.clone()).or_insert_with(|| {
+            report.sql.push(ReplayedSql {
+                sql: line.sql.clone(),
+                statements: 0,
+                errors: 0,
+                duration_ms: 0.0,
+                recorded_ms: 0.0,
+                error: None,
+            });
+            report.sql.len() - 1
+        });
+        let entry = &mut report.sql[i];
+        entry.statements += 1;
+        entry.duration_ms += duration_ms;
+        entry.recorded_ms += line.duration_ms;
+        report.statements += 1;
+        report.duration_ms += duration_ms;
+        report.recorded_ms += line.duration_ms;
+        if let Err(e) = result {
+            entry.errors += 1;
+            entry.error.get_or_insert(e.reason.clone());
+            report.errors += 1;
+        }
+    }
+    report
+        .sql
+        .sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+    Ok(report)
+}
+
+/// Run one recorded statement, reading every row it returns.
+fn run(db: &Database, line: &Line) -> napi::Result<()> {
+    match &line.params {
+        Some(Recorded::Batch(rows)) => {
+            let mut tx = db.begin().map_err(to_napi)?;
+            for params in rows {
+                tx.execute(&line.sql, params.clone()).map_err(to_napi)?;
+            }
+            tx.commit().map_err(to_napi)
+        }
+        params => {
+            let params = match params {
+                Some(Recorded::Params(params)) => params.clone(),
+                _ => TaskParams::Positional(ParamVec::new()),
+            };
+            let mut rows = params.query_on_db(db, &line.sql).map_err(from_core)?;
+            while rows.advance() {}
+            Ok(())
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
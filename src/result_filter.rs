@@ -0,0 +1,140 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Result filters: `db.setResultFilter()`.
+//
+// A filter sees every value of every result as its cell is made, with the
+// table and column it belongs to, and returns the value the result gets
+// instead, so sensitive columns can be masked in one place. It runs inside
+// the loops that make rows (see `filtered()` in tasks.rs): each row's cells
+// go through it before the row is created, so a filtered result is still
+// made in one pass. Like `registerType()` hooks it is a JS function, so it
+// rides on the same mark of the call whose results are being made.
+//
+// The table and column are those the value comes straight from, worked out
+// from the statement's SELECT list like `expand()` namespaces, so an alias
+// doesn't hide a column from the filter. The query calls mark their
+// statement, and the columns the `columns` option picks of it, while their
+// results are made. For a value that doesn't come straight from a table,
+// such as an expression's, or of a result of anything else, the table is
+// null and the column is the result's key, as `columnCase` makes it (or the
+// key within its table for `expand()`).
+
+use std::cell::RefCell;
+use std::ptr;
+use std::sync::Arc;
+
+use napi::sys;
+use stoolap::api::Database;
+
+use crate::shape::{self, Source};
+use crate::sql::parse_single;
+use crate::tasks::check;
+use crate::types::{self, Hook};
+
+type Sources = Arc<[Option<Source>]>;
+
+thread_local! {
+    /// The sources of the result columns of the statement whose results are
+    /// being made on the JS thread.
+    static SOURCES: RefCell<Option<Sources>> = const { RefCell::new(None) };
+}
+
+/// Marks a call's statement as the one whose results are being made until
+/// it is dropped.
+pub struct Marked(Option<Option<Sources>>);
+
+impl Drop for Marked {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            SOURCES.with(|sources| *sources.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Mark `sql` as the statement whose results are made on this thread, for
+/// the sources a result filter sees, with the columns `pick` keeps of it
+/// (the `columns` option).
+pub fn statement(db: &Database, sql: &str, pick: Option<&[usize]>) -> Marked {
+    if types::filter().is_none() {
+        return Marked(None);
+    }
+    let sources = parse_single(sql)
+        .ok()
+        .and_then(|statement| shape::sources(db, &statement))
+        .and_then(|mut sources| match pick {
+            Some(pick) => pick
+                .iter()
+                .map(|&i| sources.get_mut(i).map(Option::take))
+                .collect(),
+            None => Some(sources),
+        });
+    let previous = SOURCES.with(|marked| marked.replace(sources.map(Into::into)));
+    Marked(Some(previous))
+}
+
+/// The filter of the results made on this thread, for one result's columns.
+pub struct Filter {
+    hook: Arc<Hook>,
+    /// Each column's source column, or key.
+    columns: Vec<sys::napi_value>,
+    /// Each column's table, or null.
+    tables: Vec<sys::napi_value>,
+}
+
+impl Filter {
+    /// The filter for a result with these column `keys`, if there is one.
+    pub fn active(env: sys::napi_env, keys: &[String]) -> napi::Result<Option<Self>> {
+        let Some(hook) = types::filter() else {
+            return Ok(None);
+        };
+        let marked = SOURCES.with(|sources| sources.borrow().clone());
+        let marked = marked.filter(|sources| sources.len() == keys.len());
+        let mut null = ptr::null_mut();
+        check(unsafe { sys::napi_get_null(env, &mut null) })?;
+        let mut columns = Vec::with_capacity(keys.len());
+        let mut tables = Vec::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            match marked.as_ref().and_then(|sources| sources[i].as_ref()) {
+                Some(source) => {
+                    columns.push(types::string(env, &source.column)?);
+                    tables.push(types::string(env, &source.table)?);
+                }
+                None => {
+                    columns.push(types::string(env, key)?);
+                    tables.push(null);
+                }
+            }
+        }
+        Ok(Some(Self {
+            hook,
+            columns,
+            tables,
+        }))
+    }
+
+    /// The value of column `index` the result gets instead of `value`.
+    pub fn apply(
+        &self,
+        env: sys::napi_env,
+        index: usize,
+        value: sys::napi_value,
+    ) -> napi::Result<sys::napi_value> {
+        types::call(
+            env,
+            &self.hook,
+            &[self.tables[index], self.columns[index], value],
+        )
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `retry` open option.
+//
+// Auto-committed statements that fail because another transaction holds or
+// has just changed one of their rows are run again on the worker thread,
+// after a pause that doubles with every attempt. A failed auto-committed
+// statement leaves nothing behind, so running it again is safe. Statements
+// inside an explicit transaction are never retried: after a conflict only the
+// whole transaction can be, and that is up to the caller. Sync calls aren't
+// retried either, since sleeping would block the event loop.
+//
+// The engine reports these conflicts as internal errors, so they are
+// recognized by their message.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::interrupt::Ticket;
+
+/// Attempts after the first when `retries` is not given.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// Pause before the first retry when `backoffMs` is not given.
+pub const DEFAULT_BACKOFF_MS: u32 = 10;
+
+/// Engine errors worth retrying.
+const CONFLICT_MESSAGES: &[&str] = &[
+    "write conflict:",
+    "has uncommitted changes from transaction",
+    "database is locked by another process",
+];
+
+/// Options for `Database.open(path, { retry })`.
+#[napi(object, object_to_js = false)]
+#[derive(Clone)]
+pub struct RetryOptions {
+    /// Attempts after the first (default 3).
+    pub retries: Option<u32>,
+    /// Pause before the first retry in milliseconds, doubled for each
+    /// following one (default 10).
+    pub backoff_ms: Option<u32>,
+    /// Pause a random 50-100% of the backoff, so competing callers don't
+    /// retry in lockstep (default true).
+    pub jitter: Option<bool>,
+}
+
+/// How a database retries conflicting statements. The default never retries.
+#[derive(Clone, Copy, Default)]
+pub struct RetryPolicy {
+    retries: u32,
+    backoff_ms: u32,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    pub fn new(options: Option<RetryOptions>) -> Self {
+        match options {
+            Some(o) => Self {
+                retries: o.retries.unwrap_or(DEFAULT_RETRIES),
+                backoff_ms: o.backoff_ms.unwrap_or(DEFAULT_BACKOFF_MS),
+                jitter: o.jitter.unwrap_or(true),
+            },
+            None => Self::default(),
+        }
+    }
+
+    /// Run `f` until it succeeds, fails with an error that isn't a conflict,
+    /// or runs out of retries. Each attempt gets its own copy of `input`;
+    /// without retries the input is passed on as is.
+    pub fn run_with<I: Clone, T>(
+        &self,
+        ticket: &Ticket,
+        input: I,
+        f: impl FnMut(I) -> napi::Result<T>,
+    ) -> napi::Result<T> {
+        self.run_counted(ticket, input, f).map(|(value, _)| value)
+    }
+
+    /// [`run_with`](Self::run_with), also returning how many retries it took.
+    pub fn run_counted<I: Clone, T>(
+        &self,
+        ticket: &Ticket,
+        input: I,
+        mut f: impl FnMut(I) -> napi::Result<T>,
+    ) -> napi::Result<(T, u32)> {
+        if self.retries == 0 {
+            return f(input).map(|value| (value, 0));
+        }
+        let mut attempt = 0;
+        loop {
+            match f(input.clone()) {
+                Err(e) if attempt < self.retries && is_conflict(&e) => {
+                    std::thread::sleep(self.backoff(attempt));
+                    ticket.check()?;
+                    attempt += 1;
+                }
+                result => return result.map(|value| (value, attempt)),
+            }
+        }
+    }
+
+    /// [`run_with`](Self::run_with) for calls without input.
+    pub fn run<T>(
+        &self,
+        ticket: &Ticket,
+        mut f: impl FnMut() -> napi::Result<T>,
+    ) -> napi::Result<T> {
+        self.run_with(ticket, (), |()| f())
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ms = (self.backoff_ms as u64).saturating_mul(1 << attempt.min(20));
+        let ms = if self.jitter && ms > 0 {
+            let random = RandomState::new().build_hasher().finish();
+            ms / 2 + random % (ms - ms / 2 + 1)
+        } else {
+            ms
+        };
+        Duration::from_millis(ms)
+    }
+}
+
+/// The `RunResult` warning for a statement that took `retries` retries.
+pub fn retry_warning(retries: u32) -> Option<String> {
+    match retries {
+        0 => None,
+        1 => Some("Retried once after a write conflict".to_string()),
+        n => Some(format!("Retried {n} times after write conflicts")),
+    }
+}
+
+fn is_conflict(err: &napi::Error) -> bool {
+    CONFLICT_MESSAGES.iter().any(|m| err.reason.contains(m))
+}
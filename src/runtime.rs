@@ -0,0 +1,140 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Choice between the V8 helpers and plain N-API for building results.
+//
+// v8_helpers.cpp calls the V8 API directly and hands V8 handles back as
+// N-API values. Node guarantees both, but other runtimes that load N-API
+// addons may not export V8 or may represent values differently, and the
+// first query would crash. On load the module checks that the V8 entry
+// point resolves, that there is a current isolate, and that a value built by
+// the helpers reads back through N-API. If any check fails, or
+// STOOLAP_DISABLE_V8_HELPERS is set, results are built with N-API calls
+// instead: slower, but the same values. Builds with the `pure-napi` feature
+// leave the helpers out and always take the N-API path.
+
+use std::ffi::CStr;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use napi::{sys, Env};
+
+use crate::tasks::{check, probe_v8_helpers};
+
+/// Set to anything but `0` to build results with N-API only.
+const DISABLE_VAR: &str = "STOOLAP_DISABLE_V8_HELPERS";
+
+static V8_HELPERS: AtomicBool = AtomicBool::new(false);
+
+/// Why the N-API path was chosen, `None` on the V8 path. Decided by the
+/// first environment that loads the module.
+static FALLBACK_REASON: OnceLock<Option<String>> = OnceLock::new();
+
+/// Report returned by `Database.runtimeInfo()`.
+#[napi(object, object_from_js = false, use_nullable = true)]
+pub struct RuntimeInfo {
+    /// Runtime hosting the addon.
+    #[napi(ts_type = "'node' | 'electron' | 'deno' | 'bun'")]
+    pub runtime: String,
+    /// Node version the runtime reports through N-API, e.g. `v22.4.0`.
+    pub node_version: Option<String>,
+    /// Highest N-API version the runtime supports.
+    pub napi_version: u32,
+    /// How result rows are built.
+    #[napi(ts_type = "'v8' | 'napi'")]
+    pub result_path: String,
+    /// Why results are built with N-API, or null when the V8 helpers are used.
+    pub fallback_reason: Option<String>,
+}
+
+/// Pick the result path. Called from the module initializer of every
+/// environment; only the first one runs the checks.
+pub fn init(env: &Env) {
+    let reason = FALLBACK_REASON.get_or_init(|| {
+        let disabled = std::env::var_os(DISABLE_VAR).is_some_and(|v| !v.is_empty() && v != "0");
+        if disabled {
+            return Some(format!("{DISABLE_VAR} is set"));
+        }
+        probe_v8_helpers(env.raw()).err().map(str::to_string)
+    });
+    V8_HELPERS.store(reason.is_none(), Ordering::Relaxed);
+}
+
+/// Whether results are built with the V8 helpers. Always false when built
+/// with the `pure-napi` feature.
+#[inline]
+pub fn v8_helpers() -> bool {
+    !cfg!(feature = "pure-napi") && V8_HELPERS.load(Ordering::Relaxed)
+}
+
+pub fn runtime_info(env: &Env) -> napi::Result<RuntimeInfo> {
+    let raw = env.raw();
+    let mut napi_version = 0;
+    check(unsafe { sys::napi_get_version(raw, &mut napi_version) })?;
+    let mut version = ptr::null();
+    let node_version =
+        if unsafe { sys::napi_get_node_version(raw, &mut version) } == sys::Status::napi_ok {
+            let version = unsafe { &*version };
+            Some(format!(
+                "v{}.{}.{}",
+                version.major, version.minor, version.patch
+            ))
+        } else {
+            None
+        };
+    Ok(RuntimeInfo {
+        runtime: runtime_name(raw)?.to_string(),
+        node_version,
+        napi_version,
+        result_path: if v8_helpers() { "v8" } else { "napi" }.to_string(),
+        fallback_reason: FALLBACK_REASON.get().cloned().flatten(),
+    })
+}
+
+/// Identify the runtime from its globals: `Deno`, `Bun`, or
+/// `process.versions.electron`.
+fn runtime_name(env: sys::napi_env) -> napi::Result<&'static str> {
+    let mut global = ptr::null_mut();
+    check(unsafe { sys::napi_get_global(env, &mut global) })?;
+    if has_property(env, global, c"Deno")? {
+        return Ok("deno");
+    }
+    if has_property(env, global, c"Bun")? {
+        return Ok("bun");
+    }
+    let mut object = global;
+    for name in [c"process", c"versions"] {
+        if !has_property(env, object, name)? {
+            return Ok("node");
+        }
+        check(unsafe { sys::napi_get_named_property(env, object, name.as_ptr(), &mut object) })?;
+    }
+    if has_property(env, object, c"electron")? {
+        Ok("electron")
+    } else {
+        Ok("node")
+    }
+}
+
+fn has_property(env: sys::napi_env, object: sys::napi_value, name: &CStr) -> napi::Result<bool> {
+    let mut value_type = 0;
+    check(unsafe { sys::napi_typeof(env, object, &mut value_type) })?;
+    if value_type != sys::ValueType::napi_object && value_type != sys::ValueType::napi_function {
+        return Ok(false);
+    }
+    let mut result = false;
+    check(unsafe { sys::napi_has_named_property(env, object, name.as_ptr(), &mut result) })?;
+    Ok(result)
+}
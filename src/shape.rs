@@ -0,0 +1,352 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `stmt.pluck()` and `stmt.expand()`.
+//
+// Both change how a prepared statement's rows are built, in the same
+// materialization layer as plain row objects. Expanded rows hold one object
+// per table, keyed by the table's alias or, without one, its name; columns
+// that don't come straight from a table, like expressions and aggregates,
+// go under `$`. Which table a column comes from is worked out from the
+// SELECT list when `expand()` is called, looking up table columns for `*`
+// and for unqualified names in joins. When that isn't possible, e.g. for
+// subqueries in FROM, a result column named `alias.column` (as the engine
+// names `*` columns of a join) goes under `alias`, and any other under `$`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use stoolap::api::Database;
+use stoolap::parser::ast::{Expression, SelectStatement, Statement};
+
+use crate::column_names::ColumnNames;
+use crate::sql::{parse_single, returns_rows, table_columns};
+
+/// Namespace of columns that don't come from a table.
+const EXPRESSIONS: &str = "$";
+
+/// How a prepared statement's rows become JS values.
+#[derive(Clone, Default)]
+pub enum RowShape {
+    /// One object per row, keyed by column name.
+    #[default]
+    Object,
+    /// The first column's value (`pluck()`).
+    Pluck,
+    /// One object per table (`expand()`), with the namespace of each
+    /// column when it could be worked out from the statement.
+    Expand(Option<Arc<Vec<String>>>),
+}
+
+impl RowShape {
+    /// This shape for the columns `pick` keeps of a result (the `columns`
+    /// query option).
+    pub fn picked(&self, pick: Option<&[usize]>) -> RowShape {
+        match (self, pick) {
+            (RowShape::Expand(Some(layout)), Some(pick)) => {
+                let layout = pick.iter().filter_map(|&i| layout.get(i).cloned());
+                RowShape::Expand(Some(Arc::new(layout.collect())))
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// This shape for a result with `columns`: plain objects become one
+    /// object per table when their names collide under `duplicateColumns:
+    /// 'expand'`. The layout comes from `statement`, or from `sql` when the
+    /// call has no plan.
+    pub fn for_columns(
+        &self,
+        names: ColumnNames,
+        columns: &[String],
+        db: &Database,
+        statement: Option<&Statement>,
+        sql: &str,
+    ) -> RowShape {
+        match self {
+            RowShape::Object if names.expands(columns) => {
+                let layout = match statement {
+                    Some(statement) => layout(db, statement),
+                    None => parse_single(sql)
+                        .ok()
+                        .and_then(|statement| layout(db, &statement)),
+                };
+                RowShape::Expand(layout.map(Arc::new))
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Columns grouped for an expanded row.
+pub struct Expanded {
+    /// Namespaces in order of first column.
+    pub namespaces: Vec<String>,
+    /// Each column's key within its namespace.
+    pub keys: Vec<String>,
+    /// Each column's index into `namespaces`.
+    pub column_namespaces: Vec<i32>,
+}
+
+/// Whether a statement's calls return rows, which `pluck()` and `expand()`
+/// need.
+pub fn returns_data(statement: &Statement) -> bool {
+    returns_rows(statement)
+        || matches!(
+            statement,
+            Statement::Select(_)
+                | Statement::Pragma(_)
+                | Statement::ShowTables(_)
+                | Statement::ShowViews(_)
+                | Statement::ShowCreateTable(_)
+                | Statement::ShowCreateView(_)
+                | Statement::ShowIndexes(_)
+                | Statement::Describe(_)
+                | Statement::Expression(_)
+                | Statement::Explain(_)
+        )
+}
+
+/// The namespace of each result column of a SELECT, or `None` when it can't
+/// be worked out from the statement.
+pub fn layout(db: &Database, statement: &Statement) -> Option<Vec<String>> {
+    let Statement::Select(select) = statement else {
+        return None;
+    };
+    Layout::new(db, select)?.columns(select)
+}
+
+/// A table column a result column comes straight from.
+pub struct Source {
+    pub table: String,
+    pub column: String,
+}
+
+/// The source of each result column of a SELECT, `None` for those that
+/// don't come straight from a table, or `None` when it can't be worked out
+/// from the statement.
+pub fn sources(db: &Database, statement: &Statement) -> Option<Vec<Option<Source>>> {
+    let Statement::Select(select) = statement else {
+        return None;
+    };
+    Layout::new(db, select)?.sources(select)
+}
+
+/// Tables in a SELECT's FROM clause.
+struct Layout<'a> {
+    db: &'a Database,
+    /// `(qualifier, table)` per source: the alias or name, and the table.
+    sources: Vec<(String, String)>,
+    columns: HashMap<String, Vec<String>>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(db: &'a Database, select: &SelectStatement) -> Option<Self> {
+        let mut sources = Vec::new();
+        if let Some(ref table) = select.table_expr {
+            collect_sources(table, &mut sources)?;
+        }
+        Some(Self {
+            db,
+            sources,
+            columns: HashMap::new(),
+        })
+    }
+
+    fn columns(&mut self, select: &SelectStatement) -> Option<Vec<String>> {
+        let mut out = Vec::with_capacity(select.columns.len());
+        for column in &select.columns {
+            let expr = match column {
+                Expression::Aliased(aliased) => aliased.expression.as_ref(),
+                expr => expr,
+            };
+            match expr {
+                Expression::Star(_) => {
+                    for i in 0..self.sources.len() {
+                        let count = self.table_columns(i)?.len();
+                        let qualifier = self.sources[i].0.clone();
+                        out.extend(std::iter::repeat_n(qualifier, count));
+                    }
+                }
+                Expression::QualifiedStar(star) => {
+                    let i = self.source(&star.qualifier)?;
+                    let count = self.table_columns(i)?.len();
+                    let qualifier = self.sources[i].0.clone();
+                    out.extend(std::iter::repeat_n(qualifier, count));
+                }
+                Expression::QualifiedIdentifier(ident) => {
+                    let qualifier = match self.source(&ident.qualifier.value) {
+                        Some(i) => self.sources[i].0.clone(),
+                        None => ident.qualifier.value.to_string(),
+                    };
+                    out.push(qualifier);
+                }
+                Expression::Identifier(ident) => out.push(self.owner(&ident.value_lower)),
+                _ => out.push(EXPRESSIONS.to_string()),
+            }
+        }
+        Some(out)
+    }
+
+    fn sources(&mut self, select: &SelectStatement) -> Option<Vec<Option<Source>>> {
+        let mut out = Vec::with_capacity(select.columns.len());
+        for column in &select.columns {
+            let expr = match column {
+                Expression::Aliased(aliased) => aliased.expression.as_ref(),
+                expr => expr,
+            };
+            match expr {
+                Expression::Star(_) => {
+                    for i in 0..self.sources.len() {
+                        out.extend(self.all_of(i)?);
+                    }
+                }
+                Expression::QualifiedStar(star) => {
+                    let i = self.source(&star.qualifier)?;
+                    out.extend(self.all_of(i)?);
+                }
+                Expression::QualifiedIdentifier(ident) => {
+                    let source = self.source(&ident.qualifier.value);
+                    out.push(source.map(|i| self.column_of(i, &ident.name.value)));
+                }
+                Expression::Identifier(ident) => {
+                    let source = self.owner_of(&ident.value_lower);
+                    out.push(source.map(|i| self.column_of(i, &ident.value)));
+                }
+                _ => out.push(None),
+            }
+        }
+        Some(out)
+    }
+
+    /// The sources of every column of source `i`, as `*` selects them.
+    fn all_of(&mut self, i: usize) -> Option<Vec<Option<Source>>> {
+        let table = self.sources[i].1.clone();
+        let columns = self.table_columns(i)?;
+        Some(
+            columns
+                .iter()
+                .map(|column| {
+                    Some(Source {
+                        table: table.clone(),
+                        column: column.clone(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    fn column_of(&self, i: usize, column: &str) -> Source {
+        Source {
+            table: self.sources[i].1.clone(),
+            column: column.to_string(),
+        }
+    }
+
+    /// The source a qualifier refers to.
+    fn source(&self, qualifier: &str) -> Option<usize> {
+        self.sources
+            .iter()
+            .position(|(q, _)| q.eq_ignore_ascii_case(qualifier))
+    }
+
+    /// Namespace of an unqualified column: the one source that has it.
+    fn owner(&mut self, column: &str) -> String {
+        match self.owner_of(column) {
+            Some(i) => self.sources[i].0.clone(),
+            None => EXPRESSIONS.to_string(),
+        }
+    }
+
+    /// The one source that has an unqualified column.
+    fn owner_of(&mut self, column: &str) -> Option<usize> {
+        if self.sources.len() == 1 {
+            return Some(0);
+        }
+        let mut owners = Vec::new();
+        for i in 0..self.sources.len() {
+            let has = self
+                .table_columns(i)
+                .is_some_and(|cols| cols.iter().any(|c| c.eq_ignore_ascii_case(column)));
+            if has {
+                owners.push(i);
+            }
+        }
+        match owners[..] {
+            [i] => Some(i),
+            _ => None,
+        }
+    }
+
+    fn table_columns(&mut self, i: usize) -> Option<&Vec<String>> {
+        let table = self.sources[i].1.clone();
+        if !self.columns.contains_key(&table) {
+            let columns = table_columns(self.db, &table).ok()?;
+            self.columns.insert(table.clone(), columns);
+        }
+        self.columns.get(&table)
+    }
+}
+
+/// Tables of a FROM clause, in order. `None` for sources that aren't tables.
+fn collect_sources(expr: &Expression, out: &mut Vec<(String, String)>) -> Option<()> {
+    match expr {
+        Expression::TableSource(src) => {
+            let qualifier = src.alias.as_ref().unwrap_or(&src.name);
+            out.push((qualifier.value.to_string(), src.name.value.to_string()));
+            Some(())
+        }
+        Expression::JoinSource(join) => {
+            collect_sources(&join.left, out)?;
+            collect_sources(&join.right, out)
+        }
+        _ => None,
+    }
+}
+
+/// Group result columns by namespace, using the statement's layout when it
+/// matches the result.
+pub fn expand_columns(columns: &[String], layout: Option<&[String]>) -> Expanded {
+    let layout = layout.filter(|l| l.len() == columns.len());
+    let mut expanded = Expanded {
+        namespaces: Vec::new(),
+        keys: Vec::with_capacity(columns.len()),
+        column_namespaces: Vec::with_capacity(columns.len()),
+    };
+    for (i, column) in columns.iter().enumerate() {
+        let (namespace, key) = match layout {
+            Some(layout) => {
+                let namespace = layout[i].as_str();
+                let key = column
+                    .strip_prefix(namespace)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    .unwrap_or(column);
+                (namespace, key)
+            }
+            None => column
+                .split_once('.')
+                .unwrap_or((EXPRESSIONS, column.as_str())),
+        };
+        let index = match expanded.namespaces.iter().position(|n| n == namespace) {
+            Some(index) => index,
+            None => {
+                expanded.namespaces.push(namespace.to_string());
+                expanded.namespaces.len() - 1
+            }
+        };
+        expanded.keys.push(key.to_string());
+        expanded.column_namespaces.push(index as i32);
+    }
+    expanded
+}
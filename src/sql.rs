@@ -0,0 +1,788 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Parser-backed SQL helpers.
+//
+// Rewrites splice text into the original SQL at token positions reported by
+// the stoolap parser instead of re-rendering the AST, so literals, comments
+// and formatting in the user's SQL are preserved byte for byte.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::SecondsFormat;
+use stoolap::api::Database;
+use stoolap::parser::ast::{Expression, SelectStatement, Statement};
+use stoolap::parser::{Lexer, Parser, Token, TokenType};
+use stoolap::{ParamVec, Value};
+use stoolap_bindings_core::cells::{self, Cell};
+
+use crate::error::to_napi;
+use crate::tasks::TaskParams;
+use crate::value::{ArrayParam, ParamSlot};
+
+/// Point in MVCC history for a time-travel read.
+pub enum AsOf {
+    /// `AS OF TIMESTAMP '<rfc3339>'`
+    Timestamp(String),
+    /// `AS OF TRANSACTION <id>`
+    Transaction(i64),
+}
+
+impl AsOf {
+    /// Build from a bind value: Date/string -> TIMESTAMP, integer -> TRANSACTION.
+    pub fn from_value(value: Value) -> napi::Result<Self> {
+        match value {
+            Value::Timestamp(ts) => Ok(AsOf::Timestamp(
+                ts.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+            )),
+            Value::Text(s) => Ok(AsOf::Timestamp(s.to_string())),
+            Value::Integer(id) => Ok(AsOf::Transaction(id)),
+            _ => Err(napi::Error::from_reason(
+                "asOf must be a Date, a timestamp string, or an integer transaction id",
+            )),
+        }
+    }
+
+    fn clause(&self) -> String {
+        match self {
+            AsOf::Timestamp(ts) => format!(" AS OF TIMESTAMP '{}'", ts.replace('\'', "''")),
+            AsOf::Transaction(id) => format!(" AS OF TRANSACTION {id}"),
+        }
+    }
+}
+
+/// Parse `sql`, requiring exactly one statement.
+pub fn parse_single(sql: &str) -> napi::Result<Statement> {
+    let mut parser = Parser::new(sql);
+    let program = parser
+        .parse_program()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    let mut statements = program.statements.into_iter();
+    match (statements.next(), statements.next()) {
+        (Some(stmt), None) => Ok(stmt),
+        (None, _) => Err(napi::Error::from_reason("No SQL statement found")),
+        (Some(_), Some(_)) => Err(napi::Error::from_reason("Expected a single SQL statement")),
+    }
+}
+
+/// Lower-cased names of the tables the INSERT, UPDATE and DELETE statements
+/// of `sql` write to; empty when it doesn't parse.
+pub fn dml_tables(sql: &str) -> Vec<String> {
+    let Ok(program) = Parser::new(sql).parse_program() else {
+        return Vec::new();
+    };
+    program
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Statement::Insert(s) => Some(&s.table_name),
+            Statement::Update(s) => Some(&s.table_name),
+            Statement::Delete(s) => Some(&s.table_name),
+            _ => None,
+        })
+        .map(|table| table.value_lower.to_string())
+        .collect()
+}
+
+/// Rewrite a SELECT so every table it reads without an explicit AS OF clause
+/// is read at the given point in history: those of its FROM and JOIN
+/// clauses, of subqueries in FROM, of CTE bodies and of set operations such
+/// as UNION. Subqueries in expressions (IN, EXISTS, ANY/ALL and scalar
+/// subqueries) are rejected, since the engine doesn't evaluate them against
+/// the snapshot of the query around them.
+pub fn with_as_of(sql: &str, as_of: &AsOf) -> napi::Result<String> {
+    let stmt = parse_single(sql)?;
+    let Statement::Select(select) = stmt else {
+        return Err(napi::Error::from_reason(
+            "asOf is only supported for SELECT statements",
+        ));
+    };
+
+    let mut starts = Vec::new();
+    collect_as_of_targets(&select, &HashSet::new(), &mut starts)?;
+    if starts.is_empty() {
+        return Ok(sql.to_string());
+    }
+
+    let clause = as_of.clause();
+    let mut out = String::with_capacity(sql.len() + starts.len() * clause.len());
+    let mut last = 0;
+    starts.sort_unstable();
+    for start in starts {
+        let end = token_end(sql, start)?;
+        out.push_str(sql.get(last..end).ok_or_else(splice_failed)?);
+        out.push_str(&clause);
+        last = end;
+    }
+    out.push_str(&sql[last..]);
+    Ok(out)
+}
+
+/// Byte offset past the token at `start`, taken as where the lexer finds
+/// the next one, since a quoted name's literal is shorter than its source
+/// when it has doubled quotes.
+fn token_end(sql: &str, start: usize) -> napi::Result<usize> {
+    let rest = sql.get(start..).ok_or_else(splice_failed)?;
+    let mut lexer = Lexer::new(rest);
+    lexer.next_token();
+    Ok(start + lexer.next_token().position.offset)
+}
+
+fn splice_failed() -> napi::Error {
+    napi::Error::from_reason("asOf could not find the end of a table name in the SQL")
+}
+
+/// Byte offsets of each table name `select` reads without an AS OF
+/// clause. `ctes` are the CTE names in scope, which name no table.
+fn collect_as_of_targets(
+    select: &SelectStatement,
+    ctes: &HashSet<String>,
+    out: &mut Vec<usize>,
+) -> napi::Result<()> {
+    let mut ctes = ctes.clone();
+    if let Some(ref with) = select.with {
+        for cte in &with.ctes {
+            let name = cte.name.value_lower.to_string();
+            let mut scope = ctes.clone();
+            if with.is_recursive || cte.is_recursive {
+                scope.insert(name.clone());
+            }
+            collect_as_of_targets(&cte.query, &scope, out)?;
+            ctes.insert(name);
+        }
+    }
+    if let Some(ref table_expr) = select.table_expr {
+        collect_table_name_starts(table_expr, &ctes, out)?;
+    }
+    let windows = select.window_defs.iter().flat_map(|def| {
+        def.partition_by
+            .iter()
+            .chain(def.order_by.iter().map(|o| &o.expression))
+    });
+    let mut exprs = select
+        .columns
+        .iter()
+        .chain(select.where_clause.as_deref())
+        .chain(select.group_by.columns.iter())
+        .chain(select.having.as_deref())
+        .chain(windows)
+        .chain(select.order_by.iter().map(|o| &o.expression));
+    if exprs.any(has_subquery) {
+        return Err(subquery_with_as_of());
+    }
+    for op in &select.set_operations {
+        collect_as_of_targets(&op.right, &ctes, out)?;
+    }
+    Ok(())
+}
+
+/// Byte offsets of each table name lacking an AS OF clause.
+fn collect_table_name_starts(
+    expr: &Expression,
+    ctes: &HashSet<String>,
+    out: &mut Vec<usize>,
+) -> napi::Result<()> {
+    match expr {
+        Expression::TableSource(src)
+            if src.as_of.is_none() && !ctes.contains(src.name.value_lower.as_str()) =>
+        {
+            out.push(src.name.token.position.offset);
+        }
+        Expression::JoinSource(join) => {
+            collect_table_name_starts(&join.left, ctes, out)?;
+            collect_table_name_starts(&join.right, ctes, out)?;
+            if join.condition.as_deref().is_some_and(has_subquery) {
+                return Err(subquery_with_as_of());
+            }
+        }
+        Expression::SubquerySource(src) => collect_as_of_targets(&src.subquery, ctes, out)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn subquery_with_as_of() -> napi::Error {
+    napi::Error::from_reason(
+        "asOf is not supported for queries with subqueries in expressions (IN, EXISTS, ANY/ALL or scalar subqueries)",
+    )
+}
+
+/// Whether `expr` contains a subquery.
+fn has_subquery(expr: &Expression) -> bool {
+    let any = |exprs: &[Expression]| exprs.iter().any(has_subquery);
+    let opt = |expr: &Option<Box<Expression>>| expr.as_deref().is_some_and(has_subquery);
+    match expr {
+        Expression::ScalarSubquery(_)
+        | Expression::Exists(_)
+        | Expression::AllAny(_)
+        | Expression::SubquerySource(_) => true,
+        Expression::Prefix(e) => has_subquery(&e.right),
+        Expression::Infix(e) => has_subquery(&e.left) || has_subquery(&e.right),
+        Expression::List(list) => any(&list.elements),
+        Expression::ExpressionList(list) => any(&list.expressions),
+        Expression::Distinct(e) => has_subquery(&e.expr),
+        Expression::In(e) => has_subquery(&e.left) || has_subquery(&e.right),
+        Expression::InHashSet(e) => has_subquery(&e.column),
+        Expression::Between(e) => {
+            has_subquery(&e.expr) || has_subquery(&e.lower) || has_subquery(&e.upper)
+        }
+        Expression::Like(e) => has_subquery(&e.left) || has_subquery(&e.pattern) || opt(&e.escape),
+        Expression::Case(case) => {
+            opt(&case.value)
+                || case
+                    .when_clauses
+                    .iter()
+                    .any(|when| has_subquery(&when.condition) || has_subquery(&when.then_result))
+                || opt(&case.else_value)
+        }
+        Expression::Cast(e) => has_subquery(&e.expr),
+        Expression::FunctionCall(call) => {
+            any(&call.arguments)
+                || call.order_by.iter().any(|o| has_subquery(&o.expression))
+                || opt(&call.filter)
+        }
+        Expression::Aliased(e) => has_subquery(&e.expression),
+        Expression::Window(w) => {
+            any(&w.function.arguments)
+                || any(&w.partition_by)
+                || w.order_by.iter().any(|o| has_subquery(&o.expression))
+        }
+        _ => false,
+    }
+}
+
+/// Append a `RETURNING` clause to a single DML statement, ahead of any
+/// trailing semicolon or comments.
+pub fn with_returning(sql: &str, expr: &str) -> String {
+    let head = sql[..statement_end(sql)].trim_end();
+    format!("{head} RETURNING {expr}{}", &sql[head.len()..])
+}
+
+/// Byte offset just past the last significant token.
+fn statement_end(sql: &str) -> usize {
+    let mut lexer = Lexer::new(sql);
+    let mut trailing = None;
+    loop {
+        let token = lexer.next_token();
+        match token.token_type {
+            TokenType::Eof => return trailing.unwrap_or(sql.len()),
+            TokenType::Comment => {
+                trailing.get_or_insert(token.position.offset);
+            }
+            TokenType::Punctuator if token.literal == ";" => {
+                trailing.get_or_insert(token.position.offset);
+            }
+            _ => trailing = None,
+        }
+    }
+}
+
+/// The text after a top-level WHERE keyword, up to the end of the statement,
+/// with `?` placeholders renumbered to `$n` so the clause binds the same
+/// parameters on its own. `None` when there is no WHERE clause.
+pub fn where_clause(sql: &str) -> Option<String> {
+    let end = statement_end(sql);
+    let mut lexer = Lexer::new(sql);
+    let mut depth = 0usize;
+    let mut placeholders = 0usize;
+    let mut start = None;
+    let mut out = String::new();
+    loop {
+        let token = lexer.next_token();
+        let offset = token.position.offset;
+        if token.token_type == TokenType::Eof || offset >= end {
+            break;
+        }
+        match token.token_type {
+            TokenType::Punctuator if token.literal == "(" => depth += 1,
+            TokenType::Punctuator if token.literal == ")" => depth = depth.saturating_sub(1),
+            TokenType::Keyword
+                if start.is_none() && depth == 0 && token.literal.eq_ignore_ascii_case("WHERE") =>
+            {
+                start = Some(offset + token.literal.len());
+            }
+            TokenType::Parameter if token.literal == "?" => {
+                placeholders += 1;
+                if let Some(from) = start {
+                    out.push_str(&sql[from..offset]);
+                    out.push_str(&format!("${placeholders}"));
+                    start = Some(offset + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    // Text spliced so far plus the remainder up to the statement end.
+    let from = start?;
+    out.push_str(&sql[from..end]);
+    Some(out)
+}
+
+/// Stands in for an empty array so `IN` matches no rows and `NOT IN` all rows.
+const EMPTY_IN_LIST: &str = "SELECT NULL WHERE FALSE";
+
+/// A placeholder token and the parameter it binds.
+struct Placeholder {
+    offset: usize,
+    len: usize,
+    slot: ParamSlot,
+    /// Whether it is the only item of an `IN (...)` list.
+    in_list: bool,
+}
+
+/// Expand array-valued parameters whose placeholder is the only item of an
+/// `IN (...)` list into one placeholder per element. Positional placeholders
+/// are renumbered to `$n` around the expanded lists; expanded named
+/// placeholders become `:name__1, :name__2, ...`. The rewritten SQL depends
+/// only on the list lengths, so the plan cache keeps one plan per length.
+/// `None` when nothing expands.
+pub fn expand_in_lists(
+    sql: &str,
+    params: &TaskParams,
+    arrays: &[ArrayParam],
+) -> Option<(String, TaskParams)> {
+    if arrays.is_empty() {
+        return None;
+    }
+    let placeholders = placeholders(sql)?;
+    let array_for = |p: &Placeholder| {
+        p.in_list
+            .then(|| arrays.iter().find(|a| a.slot == p.slot))
+            .flatten()
+    };
+    if !placeholders.iter().any(|p| array_for(p).is_some()) {
+        return None;
+    }
+
+    let mut out = String::with_capacity(sql.len() + 16);
+    let mut last = 0;
+    let params = match params {
+        TaskParams::Positional(values) => {
+            let mut bound = ParamVec::new();
+            // Original position -> new `$n`, so repeated placeholders still share a value.
+            let mut scalars: HashMap<usize, usize> = HashMap::new();
+            for p in &placeholders {
+                out.push_str(&sql[last..p.offset]);
+                last = p.offset + p.len;
+                if let Some(array) = array_for(p) {
+                    let start = bound.len() + 1;
+                    bound.extend(array.values.iter().cloned());
+                    push_list(&mut out, array.values.len(), |i| format!("${}", start + i));
+                    continue;
+                }
+                match p.slot {
+                    ParamSlot::Position(slot) => {
+                        let index = *scalars.entry(slot).or_insert_with(|| {
+                            // Unbound positions read as NULL, as they do unexpanded.
+                            bound.push(
+                                values
+                                    .get(slot)
+                                    .cloned()
+                                    .unwrap_or_else(Value::null_unknown),
+                            );
+                            bound.len()
+                        });
+                        out.push_str(&format!("${index}"));
+                    }
+                    ParamSlot::Name(_) => out.push_str(&sql[p.offset..last]),
+                }
+            }
+            TaskParams::Positional(bound)
+        }
+        TaskParams::Named(values) => {
+            let mut bound = values.clone();
+            let mut expanded: Vec<&str> = Vec::new();
+            for p in &placeholders {
+                let (Some(array), ParamSlot::Name(name)) = (array_for(p), &p.slot) else {
+                    continue;
+                };
+                out.push_str(&sql[last..p.offset]);
+                last = p.offset + p.len;
+                if !expanded.contains(&name.as_str()) {
+                    expanded.push(name);
+                    for (i, value) in array.values.iter().enumerate() {
+                        bound.push((format!("{name}__{}", i + 1), value.clone()));
+                    }
+                }
+                push_list(&mut out, array.values.len(), |i| {
+                    format!(":{name}__{}", i + 1)
+                });
+            }
+            TaskParams::Named(bound)
+        }
+    };
+    out.push_str(&sql[last..]);
+    Some((out, params))
+}
+
+/// Every placeholder in `sql`, in order. `None` if one is malformed; the
+/// engine reports that when the SQL runs unexpanded.
+fn placeholders(sql: &str) -> Option<Vec<Placeholder>> {
+    // Comments are dropped so `IN /* ids */ (?)` still counts as an IN list.
+    let mut lexer = Lexer::new(sql);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        match token.token_type {
+            TokenType::Eof => break,
+            TokenType::Comment => {}
+            _ => tokens.push(token),
+        }
+    }
+
+    let mut questions = 0;
+    let mut out = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.token_type != TokenType::Parameter {
+            continue;
+        }
+        let literal = token.literal.as_str();
+        let slot = if literal == "?" {
+            questions += 1;
+            ParamSlot::Position(questions - 1)
+        } else if let Some(n) = literal.strip_prefix('$') {
+            ParamSlot::Position(n.parse::<usize>().ok()?.checked_sub(1)?)
+        } else {
+            ParamSlot::Name(literal.trim_start_matches(':').to_string())
+        };
+        let in_list = i >= 2
+            && is_keyword(&tokens[i - 2], "IN")
+            && is_punctuator(&tokens[i - 1], "(")
+            && tokens.get(i + 1).is_some_and(|t| is_punctuator(t, ")"));
+        out.push(Placeholder {
+            offset: token.position.offset,
+            len: literal.len(),
+            slot,
+            in_list,
+        });
+    }
+    Some(out)
+}
+
+/// `(has_params, param_count)` for a plan, counted the way the engine does:
+/// the highest positional index, or 0 (unchecked) when named placeholders
+/// are present.
+pub fn parameter_count(sql: &str) -> (bool, usize) {
+    let Some(placeholders) = placeholders(sql) else {
+        return (true, 0);
+    };
+    let mut count = 0;
+    for p in &placeholders {
+        match p.slot {
+            ParamSlot::Position(slot) => count = count.max(slot + 1),
+            ParamSlot::Name(_) => return (true, 0),
+        }
+    }
+    (!placeholders.is_empty(), count)
+}
+
+/// Names of the named placeholders in `sql`, without the `:`, in order of
+/// first use. Empty when the placeholders are positional.
+pub fn parameter_names(sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for p in placeholders(sql).unwrap_or_default() {
+        if let ParamSlot::Name(name) = p.slot {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// `sql` with each placeholder replaced by the literal of the value it
+/// binds (see `literal()`). Unbound placeholders read as NULL, as they do
+/// when the SQL runs; SQL with a malformed placeholder comes back as is.
+pub fn inline_params(sql: &str, params: &TaskParams) -> String {
+    let Some(placeholders) = placeholders(sql) else {
+        return sql.to_string();
+    };
+    let mut out = String::with_capacity(sql.len() + placeholders.len() * 8);
+    let mut last = 0;
+    for p in &placeholders {
+        out.push_str(&sql[last..p.offset]);
+        last = p.offset + p.len;
+        let value = match (&p.slot, params) {
+            (ParamSlot::Position(slot), TaskParams::Positional(values)) => values.get(*slot),
+            (ParamSlot::Name(name), TaskParams::Named(values)) => values
+                .iter()
+                .find(|(bound, _)| bound == name)
+                .map(|(_, value)| value),
+            _ => None,
+        };
+        match value {
+            Some(value) => out.push_str(&literal(value)),
+            None => out.push_str("NULL"),
+        }
+    }
+    out.push_str(&sql[last..]);
+    out
+}
+
+/// Whether `sql` ends in a `--` comment, which would swallow anything
+/// appended on the same line.
+pub fn ends_in_line_comment(sql: &str) -> bool {
+    let mut lexer = Lexer::new(sql);
+    let mut last = None;
+    loop {
+        let token = lexer.next_token();
+        if token.token_type == TokenType::Eof {
+            break;
+        }
+        last = Some(token);
+    }
+    last.is_some_and(|t| t.token_type == TokenType::Comment && t.literal.starts_with("--"))
+}
+
+/// `sql` with its literals and placeholders replaced by `?`, lists of them
+/// collapsed to one, keywords upper-cased and comments dropped, so calls of
+/// the same query with different values read the same.
+pub fn fingerprint(sql: &str) -> String {
+    let mut lexer = Lexer::new(sql);
+    let mut tokens: Vec<String> = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        let text = match token.token_type {
+            TokenType::Eof => break,
+            TokenType::Comment => continue,
+            TokenType::String
+            | TokenType::Integer
+            | TokenType::Float
+            | TokenType::Date
+            | TokenType::Time
+            | TokenType::Timestamp
+            | TokenType::Parameter => {
+                if tokens.len() >= 2
+                    && tokens[tokens.len() - 2] == "?"
+                    && tokens[tokens.len() - 1] == ","
+                {
+                    tokens.pop();
+                    continue;
+                }
+                "?".to_string()
+            }
+            TokenType::Keyword => token.literal.to_uppercase().to_string(),
+            TokenType::Punctuator if token.literal == ";" => continue,
+            _ => token.literal.to_string(),
+        };
+        tokens.push(text);
+    }
+    let mut out = String::new();
+    let mut glued = true;
+    for text in &tokens {
+        if !glued && !matches!(text.as_str(), "," | ")" | ".") {
+            out.push(' ');
+        }
+        out.push_str(text);
+        glued = matches!(text.as_str(), "(" | ".");
+    }
+    out
+}
+
+/// A value as a SQL literal the engine reads back as the same value.
+/// Timestamps, JSON and vectors are quoted text, which the engine converts
+/// when it stores them; NaN and infinities, which have no literal, are NULL.
+pub fn literal(value: &Value) -> String {
+    match cells::cell(value) {
+        Cell::Null => "NULL".to_string(),
+        Cell::Integer(i) => i.to_string(),
+        Cell::Float(f) => format!("{f:?}"),
+        Cell::Text(s) => quote_text(s),
+        Cell::Boolean(b) => if b { "TRUE" } else { "FALSE" }.to_string(),
+        Cell::Timestamp(ts) => quote_text(&ts.to_rfc3339_opts(SecondsFormat::AutoSi, false)),
+        Cell::Json(text) => quote_text(text),
+        Cell::Vector(payload) => {
+            let floats: Vec<String> = cells::vector(payload)
+                .iter()
+                .map(|f| format!("{f:?}"))
+                .collect();
+            format!("'[{}]'", floats.join(", "))
+        }
+    }
+}
+
+/// Single-quote a string literal.
+fn quote_text(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Whether the first token of `sql`, after any comments, is INSERT. Cheaper
+/// than parsing when only the statement kind matters.
+pub fn is_insert(sql: &str) -> bool {
+    let mut lexer = Lexer::new(sql);
+    loop {
+        let token = lexer.next_token();
+        if token.token_type != TokenType::Comment {
+            return is_keyword(&token, "INSERT");
+        }
+    }
+}
+
+/// Whether `sql` has a RETURNING clause. Only DML takes one, so any
+/// RETURNING keyword outside literals and comments is one.
+pub fn has_returning(sql: &str) -> bool {
+    let mut lexer = Lexer::new(sql);
+    loop {
+        let token = lexer.next_token();
+        match token.token_type {
+            TokenType::Eof => return false,
+            _ if is_keyword(&token, "RETURNING") => return true,
+            _ => {}
+        }
+    }
+}
+
+/// Whether `stmt` is DML with a RETURNING clause.
+pub fn returns_rows(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Insert(s) => !s.returning.is_empty(),
+        Statement::Update(s) => !s.returning.is_empty(),
+        Statement::Delete(s) => !s.returning.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether `sql` holds nothing but comments.
+pub fn is_blank(sql: &str) -> bool {
+    let mut lexer = Lexer::new(sql);
+    loop {
+        match lexer.next_token().token_type {
+            TokenType::Eof => return true,
+            TokenType::Comment => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Whether `sql` is a single SELECT statement: its first token is SELECT and
+/// nothing but comments follows a semicolon.
+pub fn is_select(sql: &str) -> bool {
+    let mut lexer = Lexer::new(sql);
+    let mut first = true;
+    let mut ended = false;
+    loop {
+        let token = lexer.next_token();
+        match token.token_type {
+            TokenType::Eof => return !first,
+            TokenType::Comment => {}
+            _ if first => {
+                if !is_keyword(&token, "SELECT") {
+                    return false;
+                }
+                first = false;
+            }
+            _ if ended => return false,
+            _ => ended = is_punctuator(&token, ";"),
+        }
+    }
+}
+
+/// Functions whose result changes from one call to the next.
+const VOLATILE_FUNCTIONS: &[&str] = &[
+    "NOW",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "RANDOM",
+    "UUID",
+    "SLEEP",
+];
+
+/// Whether `sql` names a function that can return something else each time
+/// it runs, such as NOW() or RANDOM().
+pub fn calls_volatile(sql: &str) -> bool {
+    let mut lexer = Lexer::new(sql);
+    loop {
+        let token = lexer.next_token();
+        match token.token_type {
+            TokenType::Eof => return false,
+            TokenType::Keyword | TokenType::Identifier
+                if VOLATILE_FUNCTIONS
+                    .iter()
+                    .any(|f| token.literal.eq_ignore_ascii_case(f)) =>
+            {
+                return true
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    token.token_type == TokenType::Keyword && token.literal.eq_ignore_ascii_case(keyword)
+}
+
+fn is_punctuator(token: &Token, punctuator: &str) -> bool {
+    token.token_type == TokenType::Punctuator && token.literal == punctuator
+}
+
+fn push_list(out: &mut String, len: usize, placeholder: impl Fn(usize) -> String) {
+    if len == 0 {
+        out.push_str(EMPTY_IN_LIST);
+        return;
+    }
+    for i in 0..len {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        out.push_str(&placeholder(i));
+    }
+}
+
+/// A table's single-column primary key.
+pub struct PrimaryKey {
+    pub name: String,
+}
+
+/// Look up a table's single-column primary key, if it has one.
+pub fn primary_key(db: &Database, table: &str) -> napi::Result<Option<PrimaryKey>> {
+    let mut rows = db
+        .query(&format!("DESCRIBE {}", quote_ident(table)), ())
+        .map_err(to_napi)?;
+    let mut keys = Vec::new();
+    while rows.advance() {
+        let row = rows.current_row();
+        if let (Some(Value::Text(field)), Some(Value::Text(key))) = (row.get(0), row.get(3)) {
+            if key.as_ref() == "PRI" {
+                keys.push(PrimaryKey {
+                    name: field.to_string(),
+                });
+            }
+        }
+    }
+    if keys.len() == 1 {
+        Ok(keys.pop())
+    } else {
+        Ok(None)
+    }
+}
+
+/// A table's column names, in schema order.
+pub fn table_columns(db: &Database, table: &str) -> napi::Result<Vec<String>> {
+    let mut rows = db
+        .query(&format!("DESCRIBE {}", quote_ident(table)), ())
+        .map_err(to_napi)?;
+    let mut columns = Vec::new();
+    while rows.advance() {
+        if let Some(Value::Text(field)) = rows.current_row().get(0) {
+            columns.push(field.to_string());
+        }
+    }
+    Ok(columns)
+}
+
+/// Double-quote an identifier.
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -12,33 +12,239 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use napi::bindgen_prelude::*;
+use napi::bindgen_prelude::{Object, This};
 use napi::Env;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use stoolap::api::Database;
 use stoolap::{CachedPlanRef, ParamVec};
+use stoolap_bindings_core::params::check_count as check_param_count;
 
-use crate::error::to_napi;
+use crate::batch::Batch;
+use crate::changes::ChangeHubRef;
+use crate::column_case::ColumnCase;
+use crate::column_names::ColumnNames;
+use crate::consistency;
+use crate::error::from_core;
+use crate::explain::{self, QueryPlan};
+use crate::interrupt::InterruptRef;
+use crate::options::{
+    statement_page, statement_parallelism, statement_projection, StatementQueryOptions,
+};
+use crate::parallel;
+use crate::plan_cache::PlanCacheRef;
+use crate::policy::{check_statement, PolicyRef};
+use crate::profile::{ProfilerRef, Started};
+use crate::projection::{pick, Projection};
+use crate::readers::{Readers, ReadersRef};
+use crate::result_filter;
+use crate::retry::RetryPolicy;
+use crate::shape::{self, RowShape};
+use crate::sql::{expand_in_lists, parameter_names, returns_rows};
 use crate::tasks::*;
-use crate::value::{parse_params, parse_positional, BindParams, RawParam};
+use crate::types::Types;
+use crate::usage::{StatementUsage, UsageRef};
+use crate::value::{parse_params, ArrayParam, BindParams, RawParam};
+use crate::worker::{Scheduled, WorkerRef};
 
 #[napi(js_name = "PreparedStatement")]
 pub struct JsPreparedStatement {
     db: Arc<Database>,
     sql_text: String,
     plan: CachedPlanRef,
+    /// Named placeholders, in order of first use.
+    parameter_names: Vec<String>,
+    usage: UsageRef,
+    changes: ChangeHubRef,
+    policy: PolicyRef,
+    profiler: ProfilerRef,
+    plans: PlanCacheRef,
+    interrupts: InterruptRef,
+    retry: RetryPolicy,
+    worker: WorkerRef,
+    readers: ReadersRef,
+    finalized: AtomicBool,
+    /// Set by `pluck()` and `expand()`.
+    shape: RowShape,
+    /// The case is set by `columnCase()`; the database's `columnCase` and
+    /// `duplicateColumns` by default.
+    names: ColumnNames,
+    /// Column names of the last sync call's rows, for the next one.
+    keys: RefCell<ColumnKeys>,
+    /// Set by `bind()`, for calls made without parameters.
+    bound: Option<Bound<'static>>,
+}
+
+/// Parameters for one call, with the SQL and plan they run against.
+struct Bound<'a> {
+    params: TaskParams,
+    sql: Cow<'a, str>,
+    plan: Cow<'a, CachedPlanRef>,
 }
 
 impl JsPreparedStatement {
-    pub fn new(db: Arc<Database>, sql: String) -> napi::Result<Self> {
-        let plan = db.cached_plan(&sql).map_err(to_napi)?;
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        db: Arc<Database>,
+        sql: String,
+        plan: CachedPlanRef,
+        changes: ChangeHubRef,
+        policy: PolicyRef,
+        profiler: ProfilerRef,
+        plans: PlanCacheRef,
+        interrupts: InterruptRef,
+        retry: RetryPolicy,
+        worker: WorkerRef,
+        readers: ReadersRef,
+        usage: &StatementUsage,
+    ) -> napi::Result<Self> {
+        check_statement(&policy, &plan.statement)?;
+        let parameter_names = parameter_names(&sql);
+        let usage = usage.prepared(&sql);
+        let names = worker.names();
         Ok(Self {
             db,
             sql_text: sql,
             plan,
+            parameter_names,
+            usage,
+            changes,
+            policy,
+            profiler,
+            plans,
+            interrupts,
+            retry,
+            worker,
+            readers,
+            finalized: AtomicBool::new(false),
+            shape: RowShape::default(),
+            names,
+            keys: RefCell::default(),
+            bound: None,
+        })
+    }
+
+    /// The shape of this call's `rows`, given its `projection`.
+    fn result_shape(
+        &self,
+        rows: &stoolap::Rows,
+        plan: &CachedPlanRef,
+        sql: &str,
+        projection: Option<&Projection>,
+    ) -> RowShape {
+        let columns = rows.columns();
+        let projected = projection.is_some();
+        result_shape(
+            &self.shape,
+            self.names,
+            &self.db,
+            Some(plan),
+            sql,
+            columns,
+            projected,
+        )
+    }
+
+    /// Reject finalized statements, and re-check the cached statement in case
+    /// the policy changed since prepare.
+    fn check_policy(&self) -> napi::Result<()> {
+        if self.finalized.load(Ordering::Relaxed) {
+            return Err(napi::Error::from_reason("Statement is finalized"));
+        }
+        check_statement(&self.policy, &self.plan.statement)
+    }
+
+    fn check_returns_data(&self, method: &str) -> napi::Result<()> {
+        if shape::returns_data(&self.plan.statement) {
+            return Ok(());
+        }
+        Err(napi::Error::from_reason(format!(
+            "The {method}() method is only for statements that return data"
+        )))
+    }
+
+    /// Positional parameters the statement takes, or `None` when its
+    /// placeholders are named.
+    fn positional_count(&self) -> Option<usize> {
+        self.parameter_names
+            .is_empty()
+            .then_some(self.plan.param_count)
+    }
+
+    /// Convert JS params for a call that runs the statement, checking a
+    /// positional list's length and counting the execution. Arrays bound to
+    /// `IN (?)` lists switch to the plan for the expanded SQL, which the plan
+    /// cache keeps per list length.
+    fn bind(&self, env: &Env, params: Option<RawParam>) -> napi::Result<Bound<'_>> {
+        let bound = self.bind_params(env, params, true)?;
+        self.usage.record(1);
+        Ok(bound)
+    }
+
+    /// `bind` without counting an execution, and with the length check
+    /// optional: explaining without running the statement needs no
+    /// parameters. Without params, the ones set by `bind()` are used.
+    fn bind_params(
+        &self,
+        env: &Env,
+        params: Option<RawParam>,
+        check_count: bool,
+    ) -> napi::Result<Bound<'_>> {
+        if let (None, Some(bound)) = (&params, &self.bound) {
+            return Ok(Bound {
+                params: bound.params.clone(),
+                sql: Cow::Borrowed(&bound.sql),
+                plan: Cow::Borrowed(&bound.plan),
+            });
+        }
+        let (params, arrays) = convert_params(env, self.worker.types(), params)?;
+        if let (TaskParams::Positional(values), Some(expected), true) =
+            (&params, self.positional_count(), check_count)
+        {
+            check_param_count(expected, values.len()).map_err(from_core)?;
+        }
+        if let Some((sql, params)) = expand_in_lists(&self.sql_text, &params, &arrays) {
+            let plan = self.plans.plan(&self.db, &sql)?;
+            return Ok(Bound {
+                params,
+                sql: Cow::Owned(sql),
+                plan: Cow::Owned(plan),
+            });
+        }
+        Ok(Bound {
+            params,
+            sql: Cow::Borrowed(&self.sql_text),
+            plan: Cow::Borrowed(&self.plan),
         })
     }
+
+    fn profile(&self, started: Option<Started>, sql: &str, plan: &CachedPlanRef, rows: i64) {
+        self.profiler.finish(started, sql, rows, Some(plan));
+    }
+
+    fn explain_task(
+        &self,
+        env: &Env,
+        params: Option<RawParam>,
+        analyze: bool,
+    ) -> napi::Result<Scheduled<ExplainTask>> {
+        self.check_policy()?;
+        let bound = self.bind_params(env, params, analyze)?;
+        Ok(self.worker.schedule(
+            if analyze { "explainAnalyze" } else { "explain" },
+            ExplainTask {
+                db: Arc::clone(&self.db),
+                params: bound.params,
+                plan: bound.plan.into_owned(),
+                analyze,
+                ticket: self.interrupts.ticket(),
+            },
+        ))
+    }
 }
 
 #[napi]
@@ -48,69 +254,206 @@ impl JsPreparedStatement {
         ts_args_type = "params?: any[] | Record<string, any>",
         ts_return_type = "Promise<RunResult>"
     )]
-    pub fn execute(&self, env: Env, params: Option<RawParam>) -> napi::Result<AsyncTask<ExecTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(ExecTask {
-            db: Arc::clone(&self.db),
-            sql: self.sql_text.clone(),
-            params: task_params,
-            plan: Some(self.plan.clone()),
-        }))
+    pub fn execute(&self, env: Env, params: Option<RawParam>) -> napi::Result<Scheduled<ExecTask>> {
+        self.check_policy()?;
+        let bound = self.bind(&env, params)?;
+        let tables = self.worker.writes().tables_in(&self.sql_text);
+        Ok(self.worker.schedule_execute(
+            tables,
+            ExecTask {
+                db: Arc::clone(&self.db),
+                sql: bound.sql.into_owned(),
+                params: bound.params,
+                plan: Some(bound.plan.into_owned()),
+                changes: Arc::clone(&self.changes),
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.names,
+            },
+        ))
+    }
+
+    /// Execute the statement with multiple param sets. Returns
+    /// Promise<RunResult>.
+    ///
+    /// Like `executeBatchSync()`: the params are converted up front, then
+    /// every set runs in one transaction off the event loop.
+    #[napi(
+        js_name = "executeBatch",
+        ts_args_type = "paramsArray: any[][]",
+        ts_return_type = "Promise<RunResult>"
+    )]
+    pub fn execute_batch(
+        &self,
+        env: Env,
+        params_array: RawParam,
+    ) -> napi::Result<Scheduled<ExecuteBatchTask>> {
+        self.check_policy()?;
+        let batch = Batch::prepared(
+            &env,
+            self.worker.types(),
+            self.sql_text.clone(),
+            self.plan.clone(),
+            params_array,
+            self.positional_count(),
+        )?;
+        self.usage.record(batch.row_count());
+        let tables = self.worker.writes().tables_in(&self.sql_text);
+        Ok(self.worker.schedule_write(
+            "executeBatch",
+            tables,
+            ExecuteBatchTask {
+                db: Arc::clone(&self.db),
+                batch: Some(batch),
+                changes: Arc::clone(&self.changes),
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+            },
+        ))
     }
 
     /// Query rows. Returns Promise<Array<Object>>.
     #[napi(
-        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_args_type = "params?: any[] | Record<string, any>, options?: StatementQueryOptions",
         ts_return_type = "Promise<Record<string, any>[]>"
     )]
-    pub fn query(&self, env: Env, params: Option<RawParam>) -> napi::Result<AsyncTask<QueryTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(QueryTask {
-            db: Arc::clone(&self.db),
-            sql: self.sql_text.clone(),
-            params: task_params,
-            plan: Some(self.plan.clone()),
-        }))
+    pub fn query(
+        &self,
+        env: Env,
+        params: Option<RawParam>,
+        options: Option<StatementQueryOptions>,
+    ) -> napi::Result<Scheduled<QueryTask>> {
+        self.check_policy()?;
+        let bound = self.bind(&env, params)?;
+        let token = self.changes.token(&bound.sql, Some(&bound.plan));
+        Ok(self.worker.schedule_on(
+            "query",
+            QueryTask {
+                db: Arc::clone(&self.db),
+                sql: bound.sql.into_owned(),
+                params: bound.params,
+                plan: Some(bound.plan.into_owned()),
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                low_memory: false,
+                shape: self.shape.clone(),
+                names: self.names,
+                projection: statement_projection(options.as_ref())?,
+                page: statement_page(options.as_ref(), self.worker.max_rows())?,
+                token,
+            },
+            statement_parallelism(options.as_ref())?,
+        ))
     }
 
     /// Query single row. Returns Promise<Object | null>.
     #[napi(
         js_name = "queryOne",
-        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_args_type = "params?: any[] | Record<string, any>, options?: StatementQueryOptions",
         ts_return_type = "Promise<Record<string, any> | null>"
     )]
     pub fn query_one(
         &self,
         env: Env,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<QueryOneTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(QueryOneTask {
-            db: Arc::clone(&self.db),
-            sql: self.sql_text.clone(),
-            params: task_params,
-            plan: Some(self.plan.clone()),
-        }))
+        options: Option<StatementQueryOptions>,
+    ) -> napi::Result<Scheduled<QueryOneTask>> {
+        self.check_policy()?;
+        let bound = self.bind(&env, params)?;
+        Ok(self.worker.schedule_on(
+            "queryOne",
+            QueryOneTask {
+                db: Arc::clone(&self.db),
+                sql: bound.sql.into_owned(),
+                params: bound.params,
+                plan: Some(bound.plan.into_owned()),
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                shape: self.shape.clone(),
+                names: self.names,
+                projection: statement_projection(options.as_ref())?,
+                page: statement_page(options.as_ref(), self.worker.max_rows())?,
+            },
+            statement_parallelism(options.as_ref())?,
+        ))
     }
 
     /// Query rows in raw format. Returns Promise<{ columns: string[], rows: any[][] }>.
     #[napi(
         js_name = "queryRaw",
-        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_args_type = "params?: any[] | Record<string, any>, options?: StatementQueryOptions",
         ts_return_type = "Promise<{ columns: string[], rows: any[][] }>"
     )]
     pub fn query_raw(
         &self,
         env: Env,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<QueryRawTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(QueryRawTask {
-            db: Arc::clone(&self.db),
-            sql: self.sql_text.clone(),
-            params: task_params,
-            plan: Some(self.plan.clone()),
-        }))
+        options: Option<StatementQueryOptions>,
+    ) -> napi::Result<Scheduled<QueryRawTask>> {
+        self.check_policy()?;
+        let bound = self.bind(&env, params)?;
+        let token = self.changes.token(&bound.sql, Some(&bound.plan));
+        Ok(self.worker.schedule_on(
+            "queryRaw",
+            QueryRawTask {
+                db: Arc::clone(&self.db),
+                sql: bound.sql.into_owned(),
+                params: bound.params,
+                plan: Some(bound.plan.into_owned()),
+                profiler: Arc::clone(&self.profiler),
+                readers: Arc::clone(&self.readers),
+                retry: self.retry,
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                low_memory: false,
+                names: self.names,
+                projection: statement_projection(options.as_ref())?,
+                page: statement_page(options.as_ref(), self.worker.max_rows())?,
+                preview: None,
+                vectors: None,
+                token,
+            },
+            statement_parallelism(options.as_ref())?,
+        ))
+    }
+
+    /// Explain the cached plan without executing it. Returns Promise<QueryPlan>.
+    #[napi(
+        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_return_type = "Promise<QueryPlan>"
+    )]
+    pub fn explain(
+        &self,
+        env: Env,
+        params: Option<RawParam>,
+    ) -> napi::Result<Scheduled<ExplainTask>> {
+        self.explain_task(&env, params, false)
+    }
+
+    /// Execute the statement and explain it with actual row counts and timing.
+    /// Returns Promise<QueryPlan>.
+    #[napi(
+        js_name = "explainAnalyze",
+        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_return_type = "Promise<QueryPlan>"
+    )]
+    pub fn explain_analyze(
+        &self,
+        env: Env,
+        params: Option<RawParam>,
+    ) -> napi::Result<Scheduled<ExplainTask>> {
+        self.explain_task(&env, params, true)
     }
 
     // ================================================================
@@ -124,48 +467,210 @@ impl JsPreparedStatement {
         ts_return_type = "RunResult"
     )]
     pub fn execute_sync(&self, env: Env, params: Option<RawParam>) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&env, params)?;
-        let changes = task_params.execute_plan_on_db(&self.db, &self.plan)?;
-        Ok(RawJsValue(v8_run_result(changes)))
+        let _busy = self.worker.busy().time("executeSync");
+        let _reviving = self.worker.types().reviving();
+        self.check_policy()?;
+        let Bound { params, sql, plan } = self.bind(&env, params)?;
+        let timer = Instant::now();
+        let started = self.profiler.start_with(&params);
+        let outcome = if returns_rows(&plan.statement) {
+            let rows = self.changes.execute_returning(
+                params,
+                &sql,
+                Some(&plan),
+                &self.interrupts.ticket(),
+                self.worker.memory(),
+            )?;
+            RunOutcome::returned(timer, rows.column_names(self.names), 0)
+        } else {
+            let executed = self.changes.execute_on_db(params, &sql, Some(&plan))?;
+            RunOutcome::executed(timer, executed, 0)
+        };
+        self.profiler
+            .finish_write(started, &sql, outcome.changes, Some(&plan));
+        Ok(RawJsValue(run_result(env.raw(), &outcome)?))
     }
 
     /// Query rows synchronously. Returns Array<Object>.
     /// Uses direct V8 bulk object creation — bypasses NAPI per-property overhead.
     #[napi(
         js_name = "querySync",
-        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_args_type = "params?: any[] | Record<string, any>, options?: StatementQueryOptions",
         ts_return_type = "Record<string, any>[]"
     )]
-    pub fn query_sync(&self, _env: Env, params: Option<RawParam>) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = task_params.query_plan_on_db(&self.db, &self.plan)?;
-        Ok(RawJsValue(v8_streaming_rows_to_array(rows)))
+    pub fn query_sync(
+        &self,
+        env: Env,
+        params: Option<RawParam>,
+        options: Option<StatementQueryOptions>,
+    ) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("querySync");
+        let _reviving = self.worker.types().reviving();
+        self.check_policy()?;
+        let projection = statement_projection(options.as_ref())?;
+        let page = statement_page(options.as_ref(), self.worker.max_rows())?;
+        let Bound { params, sql, plan } = self.bind(&env, params)?;
+        let token = self.changes.token(&sql, Some(&plan));
+        let started = self.profiler.start_with(&params);
+        let threads = self
+            .worker
+            .threads(statement_parallelism(options.as_ref())?);
+        let db = &self.db;
+        let rows = parallel::run(threads, || {
+            params.query_plan_on_db(db, &plan).map_err(from_core)
+        })?;
+        let picked = pick(rows.columns(), projection.as_ref())?;
+        let _statement = result_filter::statement(&self.db, &sql, picked.as_deref());
+        let shape = self.result_shape(&rows, &plan, &sql, projection.as_ref());
+        let (value, count) = match shape {
+            RowShape::Object => streaming_rows_keyed(
+                env.raw(),
+                rows,
+                false,
+                &mut self.keys.borrow_mut(),
+                self.names,
+                projection.as_ref(),
+                page,
+            )?,
+            _ => {
+                let projection = projection.as_ref();
+                streaming_rows_to_shape(env.raw(), rows, &shape, self.names, projection, page)?
+            }
+        };
+        self.profile(started, &sql, &plan, count as i64);
+        if let Some(ref token) = token {
+            consistency::attach(env.raw(), value, token)?;
+        }
+        Ok(RawJsValue(value))
     }
 
     /// Query single row synchronously. Returns Object | null.
     /// Uses direct V8 bulk object creation — optimal hidden class in one call.
     #[napi(
         js_name = "queryOneSync",
-        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_args_type = "params?: any[] | Record<string, any>, options?: StatementQueryOptions",
         ts_return_type = "Record<string, any> | null"
     )]
-    pub fn query_one_sync(&self, _env: Env, params: Option<RawParam>) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = task_params.query_plan_on_db(&self.db, &self.plan)?;
-        Ok(RawJsValue(v8_single_row_or_null(rows)))
+    pub fn query_one_sync(
+        &self,
+        env: Env,
+        params: Option<RawParam>,
+        options: Option<StatementQueryOptions>,
+    ) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("queryOneSync");
+        let _reviving = self.worker.types().reviving();
+        self.check_policy()?;
+        let projection = statement_projection(options.as_ref())?;
+        let page = statement_page(options.as_ref(), self.worker.max_rows())?;
+        let Bound { params, sql, plan } = self.bind(&env, params)?;
+        let started = self.profiler.start_with(&params);
+        let threads = self
+            .worker
+            .threads(statement_parallelism(options.as_ref())?);
+        let db = &self.db;
+        let rows = parallel::run(threads, || {
+            params.query_plan_on_db(db, &plan).map_err(from_core)
+        })?;
+        let picked = pick(rows.columns(), projection.as_ref())?;
+        let _statement = result_filter::statement(&self.db, &sql, picked.as_deref());
+        let shape = self.result_shape(&rows, &plan, &sql, projection.as_ref());
+        let (value, count) = match shape {
+            RowShape::Object => single_row_keyed(
+                env.raw(),
+                rows,
+                &mut self.keys.borrow_mut(),
+                self.names,
+                projection.as_ref(),
+                page,
+            )?,
+            _ => {
+                let projection = projection.as_ref();
+                single_row_shaped(env.raw(), rows, &shape, self.names, projection, page)?
+            }
+        };
+        self.profile(started, &sql, &plan, count as i64);
+        Ok(RawJsValue(value))
     }
 
     /// Query rows in raw format synchronously. Returns { columns: string[], rows: any[][] }.
     /// Uses direct V8 bulk array creation — bypasses NAPI per-element overhead.
     #[napi(
         js_name = "queryRawSync",
-        ts_args_type = "params?: any[] | Record<string, any>",
+        ts_args_type = "params?: any[] | Record<string, any>, options?: StatementQueryOptions",
         ts_return_type = "{ columns: string[], rows: any[][] }"
     )]
-    pub fn query_raw_sync(&self, _env: Env, params: Option<RawParam>) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = task_params.query_plan_on_db(&self.db, &self.plan)?;
-        Ok(RawJsValue(v8_streaming_rows_to_raw(rows)))
+    pub fn query_raw_sync(
+        &self,
+        env: Env,
+        params: Option<RawParam>,
+        options: Option<StatementQueryOptions>,
+    ) -> napi::Result<RawJsValue> {
+        let _busy = self.worker.busy().time("queryRawSync");
+        let _reviving = self.worker.types().reviving();
+        self.check_policy()?;
+        let projection = statement_projection(options.as_ref())?;
+        let page = statement_page(options.as_ref(), self.worker.max_rows())?;
+        let Bound { params, sql, plan } = self.bind(&env, params)?;
+        let token = self.changes.token(&sql, Some(&plan));
+        let started = self.profiler.start_with(&params);
+        let threads = self
+            .worker
+            .threads(statement_parallelism(options.as_ref())?);
+        let db = &self.db;
+        let rows = parallel::run(threads, || {
+            params.query_plan_on_db(db, &plan).map_err(from_core)
+        })?;
+        let picked = pick(rows.columns(), projection.as_ref())?;
+        let _statement = result_filter::statement(&self.db, &sql, picked.as_deref());
+        let (value, count) = streaming_rows_keyed(
+            env.raw(),
+            rows,
+            true,
+            &mut self.keys.borrow_mut(),
+            self.names,
+            projection.as_ref(),
+            page,
+        )?;
+        self.profile(started, &sql, &plan, count as i64);
+        if let Some(ref token) = token {
+            consistency::attach(env.raw(), value, token)?;
+        }
+        Ok(RawJsValue(value))
+    }
+
+    /// Explain the cached plan synchronously without executing it. Returns QueryPlan.
+    #[napi(
+        js_name = "explainSync",
+        ts_args_type = "params?: any[] | Record<string, any>"
+    )]
+    pub fn explain_sync(&self, env: Env, params: Option<RawParam>) -> napi::Result<QueryPlan> {
+        let _busy = self.worker.busy().time("explainSync");
+        self.check_policy()?;
+        let bound = self.bind_params(&env, params, false)?;
+        let db = &self.db;
+        parallel::run(self.worker.threads(None), || {
+            explain::explain(db, &bound.plan, &bound.params, false)
+        })
+    }
+
+    /// Execute synchronously and explain with actual row counts and timing.
+    /// Returns QueryPlan.
+    #[napi(
+        js_name = "explainAnalyzeSync",
+        ts_args_type = "params?: any[] | Record<string, any>"
+    )]
+    pub fn explain_analyze_sync(
+        &self,
+        env: Env,
+        params: Option<RawParam>,
+    ) -> napi::Result<QueryPlan> {
+        let _busy = self.worker.busy().time("explainAnalyzeSync");
+        self.check_policy()?;
+        let bound = self.bind(&env, params)?;
+        let db = &self.db;
+        parallel::run(self.worker.threads(None), || {
+            explain::explain(db, &bound.plan, &bound.params, true)
+        })
     }
 
     /// Execute the prepared SQL with multiple param sets in a single call.
@@ -177,34 +682,114 @@ impl JsPreparedStatement {
         ts_return_type = "RunResult"
     )]
     pub fn execute_batch_sync(&self, env: Env, params_array: RawParam) -> napi::Result<RawJsValue> {
-        use napi::sys;
-        let raw_env = env.raw();
-        let arr = params_array.0;
-
-        let mut is_array = false;
-        check(unsafe { sys::napi_is_array(raw_env, arr, &mut is_array) })?;
-        if !is_array {
-            return Err(napi::Error::from_reason("paramsArray must be an array"));
+        let _busy = self.worker.busy().time("executeBatchSync");
+        self.check_policy()?;
+        // Use pre-cached AST from the plan (no re-parsing)
+        let batch = Batch::prepared(
+            &env,
+            self.worker.types(),
+            self.sql_text.clone(),
+            self.plan.clone(),
+            params_array,
+            self.positional_count(),
+        )?;
+        self.usage.record(batch.row_count());
+        let outcome = batch.run(
+            &self.db,
+            &self.changes,
+            &self.profiler,
+            // Sync calls stay off the reader gate, like the other sync methods.
+            &Readers::default(),
+            // Sync calls don't retry: sleeping would block the event loop.
+            &RetryPolicy::default(),
+            &self.interrupts.ticket(),
+        )?;
+        Ok(RawJsValue(run_result(env.raw(), &outcome)?))
+    }
+
+    /// Bind `params` to the statement, for the calls that run it without
+    /// parameters of their own; calls given parameters use those instead.
+    /// They are converted once, here, and can be bound again; call with no
+    /// arguments to unbind them.
+    #[napi(
+        js_name = "bind",
+        ts_args_type = "params?: any[] | Record<string, any> | null",
+        ts_return_type = "this"
+    )]
+    pub fn bind_defaults<'a>(
+        &mut self,
+        env: Env,
+        this: This<'a>,
+        params: Option<RawParam>,
+    ) -> napi::Result<Object<'a>> {
+        self.check_policy()?;
+        self.bound = None;
+        if params.is_some() {
+            let Bound { params, sql, plan } = self.bind_params(&env, params, true)?;
+            let bound = Bound {
+                params,
+                sql: Cow::Owned(sql.into_owned()),
+                plan: Cow::Owned(plan.into_owned()),
+            };
+            self.bound = Some(bound);
         }
+        Ok(this.object)
+    }
 
-        let mut len = 0u32;
-        check(unsafe { sys::napi_get_array_length(raw_env, arr, &mut len) })?;
+    /// Return only the first column of each row from `query()` and
+    /// `queryOne()` and their sync forms, or turn that off with `false`.
+    /// Turns `expand()` off.
+    #[napi(ts_args_type = "toggle?: boolean", ts_return_type = "this")]
+    pub fn pluck<'a>(&mut self, this: This<'a>, toggle: Option<bool>) -> napi::Result<Object<'a>> {
+        self.check_returns_data("pluck")?;
+        if toggle.unwrap_or(true) {
+            self.shape = RowShape::Pluck;
+        } else if let RowShape::Pluck = self.shape {
+            self.shape = RowShape::Object;
+        }
+        Ok(this.object)
+    }
 
-        // Use pre-cached AST from the plan (no re-parsing)
-        let stmt = self.plan.statement.as_ref();
+    /// Return each row from `query()` and `queryOne()` and their sync forms
+    /// as one object per table, keyed by its alias or name, with columns
+    /// that don't belong to a table under `$`; or turn that off with
+    /// `false`. Turns `pluck()` off.
+    #[napi(ts_args_type = "toggle?: boolean", ts_return_type = "this")]
+    pub fn expand<'a>(&mut self, this: This<'a>, toggle: Option<bool>) -> napi::Result<Object<'a>> {
+        self.check_returns_data("expand")?;
+        if toggle.unwrap_or(true) {
+            let layout = shape::layout(&self.db, &self.plan.statement);
+            self.shape = RowShape::Expand(layout.map(Arc::new));
+        } else if let RowShape::Expand(_) = self.shape {
+            self.shape = RowShape::Object;
+        }
+        Ok(this.object)
+    }
 
-        let mut tx = self.db.begin().map_err(to_napi)?;
-        let mut total_changes = 0i64;
+    /// Case the column names of this statement's rows and `columns`:
+    /// `'camel'`, `'snake'` or `'asIs'`. Starts as the database's
+    /// `columnCase`.
+    #[napi(
+        js_name = "columnCase",
+        ts_args_type = "columnCase: 'camel' | 'snake' | 'asIs'",
+        ts_return_type = "this"
+    )]
+    pub fn column_case<'a>(&mut self, this: This<'a>, case: String) -> napi::Result<Object<'a>> {
+        self.names.case = ColumnCase::parse(&case)?;
+        Ok(this.object)
+    }
 
-        for i in 0..len {
-            let mut elem = std::ptr::null_mut();
-            check(unsafe { sys::napi_get_element(raw_env, arr, i, &mut elem) })?;
-            let params = parse_positional(raw_env, elem)?;
-            total_changes += tx.execute_prepared(stmt, params).map_err(to_napi)?;
-        }
+    /// Finalize the statement. Later calls on it throw; calls already running
+    /// finish normally. Finalizing twice is a no-op.
+    #[napi]
+    pub fn finalize(&self) {
+        self.finalized.store(true, Ordering::Relaxed);
+    }
 
-        tx.commit().map_err(to_napi)?;
-        Ok(RawJsValue(v8_run_result(total_changes)))
+    /// Whether `finalize()` has been called.
+    #[napi(getter)]
+    pub fn finalized(&self) -> bool {
+        self.finalized.load(Ordering::Relaxed)
     }
 
     /// Get the SQL text of this prepared statement.
@@ -212,14 +797,35 @@ impl JsPreparedStatement {
     pub fn sql(&self) -> String {
         self.sql_text.clone()
     }
-}
 
-fn convert_params(env: &Env, params: Option<RawParam>) -> napi::Result<TaskParams> {
-    match params {
-        None => Ok(TaskParams::Positional(ParamVec::new())),
-        Some(p) => match parse_params(env.raw(), p.0)? {
-            BindParams::Positional(pos) => Ok(TaskParams::Positional(pos)),
-            BindParams::Named(n) => Ok(TaskParams::Named(n)),
-        },
+    /// Number of parameters the statement takes: the highest `$n` or the
+    /// number of `?` placeholders, or the number of distinct `:name` ones.
+    #[napi(getter)]
+    pub fn parameter_count(&self) -> u32 {
+        self.positional_count()
+            .unwrap_or(self.parameter_names.len()) as u32
     }
+
+    /// Names of the `:name` placeholders, without the colon, in order of
+    /// first use. Empty for positional placeholders.
+    #[napi(getter)]
+    pub fn parameter_names(&self) -> Vec<String> {
+        self.parameter_names.clone()
+    }
+}
+
+fn convert_params(
+    env: &Env,
+    types: &Types,
+    params: Option<RawParam>,
+) -> napi::Result<(TaskParams, Vec<ArrayParam>)> {
+    let Some(p) = params else {
+        return Ok((TaskParams::Positional(ParamVec::new()), Vec::new()));
+    };
+    let (params, arrays) = parse_params(env.raw(), p.0, types)?;
+    let params = match params {
+        BindParams::Positional(pos) => TaskParams::Positional(pos),
+        BindParams::Named(n) => TaskParams::Named(n),
+    };
+    Ok((params, arrays))
 }
@@ -0,0 +1,220 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Sampled statement logging, behind `db.logStatements()`.
+//
+// The profiler (see profile.rs) asks the log whether to sample each
+// statement when it starts timing it, so a statement left out costs one
+// random number and its parameters are copied only for the ones sampled,
+// and only with `includeParams`. Sampled statements that complete are sent
+// to the sink through a threadsafe function, like `profile` events, with
+// the SQL that ran, how long it took and how many rows it returned or
+// changed.
+
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{sys, Env};
+use stoolap::ParamVec;
+
+use crate::recording::Recorded;
+use crate::tasks::{check, value_to_js, RawJsValue, TaskParams};
+
+type Hook = FunctionRef<Unknown<'static>, Unknown<'static>>;
+
+/// Options for `db.logStatements()`.
+#[napi(object, object_to_js = false)]
+pub struct StatementLogOptions {
+    /// Share of statements logged, from 0 to 1 (default 1, all of them).
+    pub sample: Option<f64>,
+    /// Called with each logged statement.
+    #[napi(ts_type = "(event: StatementLogEvent) => void")]
+    pub sink: Hook,
+    /// Copy the parameters of logged statements into their events
+    /// (default false).
+    pub include_params: Option<bool>,
+}
+
+/// A logged statement, as `db.logStatements()` sinks get it.
+pub struct StatementLogEvent {
+    sql: String,
+    duration_ms: f64,
+    rows: i64,
+    params: Option<Recorded>,
+}
+
+pub type StatementSink =
+    ThreadsafeFunction<StatementLogEvent, Unknown<'static>, StatementLogEvent, Status, false, true>;
+
+/// The sink a database logs to, if any.
+pub struct StatementLog {
+    sink: Mutex<Option<StatementSink>>,
+    /// Statements are sampled when a random u64 is below this; 0 while
+    /// nothing logs.
+    threshold: AtomicU64,
+    include_params: AtomicBool,
+    /// State of the random numbers deciding which statements are sampled.
+    seed: AtomicU64,
+}
+
+impl Default for StatementLog {
+    fn default() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self {
+            sink: Mutex::new(None),
+            threshold: AtomicU64::new(0),
+            include_params: AtomicBool::new(false),
+            seed: AtomicU64::new(now),
+        }
+    }
+}
+
+impl StatementLog {
+    #[inline]
+    pub fn active(&self) -> bool {
+        self.threshold.load(Ordering::Acquire) > 0
+    }
+
+    /// Log to `sink` from now on, replacing the sink logged to before.
+    pub fn start(
+        &self,
+        sink: StatementSink,
+        sample: Option<f64>,
+        include_params: bool,
+    ) -> napi::Result<()> {
+        let sample = sample.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&sample) {
+            return Err(napi::Error::from_reason(
+                "sample must be a number from 0 to 1",
+            ));
+        }
+        let threshold = if sample >= 1.0 {
+            u64::MAX
+        } else {
+            (sample * u64::MAX as f64) as u64
+        };
+        *lock(&self.sink) = Some(sink);
+        self.include_params.store(include_params, Ordering::Release);
+        self.threshold.store(threshold, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        self.threshold.store(0, Ordering::Release);
+        lock(&self.sink).take();
+    }
+
+    /// Whether to log the statement starting now.
+    #[inline]
+    pub fn sampled(&self) -> bool {
+        match self.threshold.load(Ordering::Acquire) {
+            0 => false,
+            u64::MAX => true,
+            threshold => self.random() < threshold,
+        }
+    }
+
+    pub fn includes_params(&self) -> bool {
+        self.include_params.load(Ordering::Acquire)
+    }
+
+    /// Send a completed statement to the sink.
+    pub fn write(&self, sql: &str, params: Option<&Recorded>, duration_ms: f64, rows: i64) {
+        let sink = lock(&self.sink);
+        let Some(sink) = sink.as_ref() else {
+            return;
+        };
+        let event = StatementLogEvent {
+            sql: sql.to_string(),
+            duration_ms,
+            rows,
+            params: params.filter(|_| self.includes_params()).cloned(),
+        };
+        sink.call(event, ThreadsafeFunctionCallMode::NonBlocking);
+    }
+
+    /// The next number of a SplitMix64 sequence.
+    fn random(&self) -> u64 {
+        let mut z = self
+            .seed
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl ToNapiValue for StatementLogEvent {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        let js = Env::from_raw(env);
+        let mut event = Object::new(&js)?;
+        event.set_named_property("sql", val.sql)?;
+        event.set_named_property("durationMs", val.duration_ms)?;
+        event.set_named_property("rows", val.rows)?;
+        let params = match val.params {
+            Some(Recorded::Params(TaskParams::Positional(ref params))) => {
+                values_to_js(env, params)?
+            }
+            Some(Recorded::Params(TaskParams::Named(ref params))) => {
+                let mut named = Object::new(&js)?;
+                for (name, value) in params {
+                    named.set_named_property(name, RawJsValue(value_to_js(env, value)?))?;
+                }
+                named.raw()
+            }
+            // The rows of `executeBatch()`, one array each.
+            Some(Recorded::Batch(ref rows)) => {
+                let mut batch = ptr::null_mut();
+                check(sys::napi_create_array_with_length(
+                    env,
+                    rows.len(),
+                    &mut batch,
+                ))?;
+                for (i, row) in rows.iter().enumerate() {
+                    check(sys::napi_set_element(
+                        env,
+                        batch,
+                        i as u32,
+                        values_to_js(env, row)?,
+                    ))?;
+                }
+                batch
+            }
+            None => return Ok(event.raw()),
+        };
+        event.set_named_property("params", RawJsValue(params))?;
+        Ok(event.raw())
+    }
+}
+
+fn values_to_js(env: sys::napi_env, values: &ParamVec) -> napi::Result<sys::napi_value> {
+    let mut array = ptr::null_mut();
+    check(unsafe { sys::napi_create_array_with_length(env, values.len(), &mut array) })?;
+    for (i, value) in values.iter().enumerate() {
+        let value = value_to_js(env, value)?;
+        check(unsafe { sys::napi_set_element(env, array, i as u32, value) })?;
+    }
+    Ok(array)
+}
+
+fn lock(mutex: &Mutex<Option<StatementSink>>) -> MutexGuard<'_, Option<StatementSink>> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
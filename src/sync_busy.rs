@@ -0,0 +1,199 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Time the sync calls block the JS thread, behind `db.syncBusy`.
+//
+// Every `*Sync` method of a database, its statements and transactions is
+// timed from the call until it returns, results included, whether it
+// succeeds or throws. Times are kept per method name in a log-scale
+// histogram with eight buckets per power of two, so memory stays fixed
+// however many calls are made and a p99 is at most an eighth above the
+// true value (and never above the slowest call).
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Time spent in sync calls, returned by `db.syncBusy`.
+#[napi(object, object_from_js = false)]
+pub struct SyncBusyStats {
+    /// Sync calls made.
+    pub calls: i64,
+    /// Milliseconds they blocked the JS thread in all.
+    pub total_ms: f64,
+    /// 99th percentile of their durations, in milliseconds.
+    pub p99_ms: f64,
+    /// The slowest call, in milliseconds.
+    pub max_ms: f64,
+    /// The same per method, most total time first.
+    pub methods: Vec<SyncMethodBusy>,
+}
+
+/// Time spent in one sync method, e.g. `querySync`.
+#[napi(object, object_from_js = false)]
+pub struct SyncMethodBusy {
+    pub method: String,
+    pub calls: i64,
+    pub total_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Buckets per power of two.
+const SUB_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+const BUCKETS: usize = 64 * SUB_BUCKETS;
+
+struct Histogram {
+    counts: Box<[u64; BUCKETS]>,
+    calls: u64,
+    total_ns: u64,
+    max_ns: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: Box::new([0; BUCKETS]),
+            calls: 0,
+            total_ns: 0,
+            max_ns: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn record(&mut self, ns: u64) {
+        self.counts[bucket(ns)] += 1;
+        self.calls += 1;
+        self.total_ns = self.total_ns.saturating_add(ns);
+        self.max_ns = self.max_ns.max(ns);
+    }
+
+    fn merge(&mut self, other: &Histogram) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+        self.calls += other.calls;
+        self.total_ns = self.total_ns.saturating_add(other.total_ns);
+        self.max_ns = self.max_ns.max(other.max_ns);
+    }
+
+    /// The upper bound of the bucket holding the 99th percentile.
+    fn p99_ns(&self) -> u64 {
+        let rank = self.calls - self.calls / 100;
+        let mut seen = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank && seen > 0 {
+                return upper_bound(i).min(self.max_ns);
+            }
+        }
+        0
+    }
+}
+
+/// The low buckets hold 0..7 ns exactly; above, each power of two is split
+/// into `SUB_BUCKETS`.
+fn bucket(ns: u64) -> usize {
+    if ns < SUB_BUCKETS as u64 {
+        return ns as usize;
+    }
+    let exp = 63 - ns.leading_zeros();
+    let sub = (ns >> (exp - SUB_BITS)) as usize & (SUB_BUCKETS - 1);
+    exp as usize * SUB_BUCKETS + sub
+}
+
+fn upper_bound(bucket: usize) -> u64 {
+    if bucket < SUB_BUCKETS {
+        return bucket as u64;
+    }
+    let exp = (bucket / SUB_BUCKETS) as u32;
+    let sub = (bucket % SUB_BUCKETS) as u64;
+    (SUB_BUCKETS as u64 + sub + 1).saturating_mul(1 << (exp - SUB_BITS))
+}
+
+pub type SyncBusyRef = Arc<SyncBusy>;
+
+/// Sync call times of a database and everything created from it.
+#[derive(Default)]
+pub struct SyncBusy {
+    methods: Mutex<Vec<(&'static str, Histogram)>>,
+}
+
+impl SyncBusy {
+    /// Time a call of `method` until the returned guard is dropped.
+    pub fn time(&self, method: &'static str) -> Timing<'_> {
+        Timing {
+            busy: self,
+            method,
+            started: Instant::now(),
+        }
+    }
+
+    fn record(&self, method: &'static str, ns: u64) {
+        let mut methods = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+        match methods.iter_mut().find(|(m, _)| *m == method) {
+            Some((_, histogram)) => histogram.record(ns),
+            None => {
+                let mut histogram = Histogram::default();
+                histogram.record(ns);
+                methods.push((method, histogram));
+            }
+        }
+    }
+
+    pub fn stats(&self) -> SyncBusyStats {
+        let methods = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = Histogram::default();
+        let mut per_method: Vec<SyncMethodBusy> = methods
+            .iter()
+            .map(|(method, histogram)| {
+                all.merge(histogram);
+                SyncMethodBusy {
+                    method: method.to_string(),
+                    calls: histogram.calls as i64,
+                    total_ms: ms(histogram.total_ns),
+                    p99_ms: ms(histogram.p99_ns()),
+                    max_ms: ms(histogram.max_ns),
+                }
+            })
+            .collect();
+        per_method.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        SyncBusyStats {
+            calls: all.calls as i64,
+            total_ms: ms(all.total_ns),
+            p99_ms: ms(all.p99_ns()),
+            max_ms: ms(all.max_ns),
+            methods: per_method,
+        }
+    }
+}
+
+/// Records the time since it was made when dropped.
+pub struct Timing<'a> {
+    busy: &'a SyncBusy,
+    method: &'static str,
+    started: Instant,
+}
+
+impl Drop for Timing<'_> {
+    fn drop(&mut self) {
+        let ns = self.started.elapsed().as_nanos().min(u64::MAX as u128) as u64;
+        self.busy.record(self.method, ns);
+    }
+}
+
+fn ms(ns: u64) -> f64 {
+    ns as f64 / 1_000_000.0
+}
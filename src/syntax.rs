@@ -0,0 +1,532 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `Database.parse()`: the parser's syntax tree, as JSON.
+//
+// Each statement and expression becomes an object whose `type` is the name
+// of its variant in the engine's AST (`Select`, `Infix`, `TableSource`, ...)
+// and whose other keys are the node's fields, camel-cased. The lexer tokens
+// the nodes keep are left out, identifiers are their names, absent parts are
+// null, and keyword-like enums (join types, set operations, referential
+// actions and so on) are spelled as in SQL. A nested SELECT is a `Select`
+// node like a top-level one.
+
+use serde_json::{json, Value as Json};
+use stoolap::parser::ast::{
+    AlterTableOperation, AlterTableStatement, ColumnConstraint, ColumnDefinition,
+    CommonTableExpression, Expression, FunctionCall, GroupByModifier, Identifier,
+    OrderByExpression, SelectStatement, Statement, TableConstraint, WindowDefinition, WindowFrame,
+    WindowFrameBound, WindowFrameUnit,
+};
+use stoolap::parser::Parser;
+
+/// The statements of `sql`. Throws the parser's first error if it doesn't
+/// parse.
+pub fn parse(sql: &str) -> napi::Result<Json> {
+    let program = Parser::new(sql)
+        .parse_program()
+        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    Ok(Json::Array(
+        program.statements.iter().map(statement).collect(),
+    ))
+}
+
+fn statement(stmt: &Statement) -> Json {
+    match stmt {
+        Statement::Select(s) => select(s),
+        Statement::Insert(s) => json!({
+            "type": "Insert",
+            "tableName": ident(&s.table_name),
+            "columns": idents(&s.columns),
+            "values": rows(&s.values),
+            "select": s.select.as_deref().map(select),
+            "onDuplicate": s.on_duplicate,
+            "updateColumns": idents(&s.update_columns),
+            "updateExpressions": expressions(&s.update_expressions),
+            "returning": expressions(&s.returning),
+        }),
+        Statement::Update(s) => json!({
+            "type": "Update",
+            "tableName": ident(&s.table_name),
+            "updates": s
+                .updates
+                .iter()
+                .map(|(column, value)| (column.to_string(), expression(value)))
+                .collect::<serde_json::Map<_, _>>(),
+            "whereClause": optional(s.where_clause.as_deref()),
+            "returning": expressions(&s.returning),
+        }),
+        Statement::Delete(s) => json!({
+            "type": "Delete",
+            "tableName": ident(&s.table_name),
+            "alias": s.alias.as_ref().map(ident),
+            "whereClause": optional(s.where_clause.as_deref()),
+            "returning": expressions(&s.returning),
+        }),
+        Statement::Truncate(s) => json!({
+            "type": "Truncate",
+            "tableName": ident(&s.table_name),
+        }),
+        Statement::CreateTable(s) => json!({
+            "type": "CreateTable",
+            "tableName": ident(&s.table_name),
+            "ifNotExists": s.if_not_exists,
+            "columns": s.columns.iter().map(column_definition).collect::<Vec<_>>(),
+            "tableConstraints": s.table_constraints.iter().map(table_constraint).collect::<Vec<_>>(),
+            "asSelect": s.as_select.as_deref().map(select),
+        }),
+        Statement::DropTable(s) => json!({
+            "type": "DropTable",
+            "tableName": ident(&s.table_name),
+            "ifExists": s.if_exists,
+        }),
+        Statement::AlterTable(s) => alter_table(s),
+        Statement::CreateIndex(s) => json!({
+            "type": "CreateIndex",
+            "indexName": ident(&s.index_name),
+            "tableName": ident(&s.table_name),
+            "columns": idents(&s.columns),
+            "isUnique": s.is_unique,
+            "ifNotExists": s.if_not_exists,
+            "indexMethod": s.index_method.map(|m| m.to_string()),
+            "options": s
+                .options
+                .iter()
+                .map(|(key, value)| (key.clone(), json!(value)))
+                .collect::<serde_json::Map<_, _>>(),
+        }),
+        Statement::DropIndex(s) => json!({
+            "type": "DropIndex",
+            "indexName": ident(&s.index_name),
+            "tableName": s.table_name.as_ref().map(ident),
+            "ifExists": s.if_exists,
+        }),
+        Statement::CreateView(s) => json!({
+            "type": "CreateView",
+            "viewName": ident(&s.view_name),
+            "query": select(&s.query),
+            "ifNotExists": s.if_not_exists,
+        }),
+        Statement::DropView(s) => json!({
+            "type": "DropView",
+            "viewName": ident(&s.view_name),
+            "ifExists": s.if_exists,
+        }),
+        Statement::Begin(s) => json!({
+            "type": "Begin",
+            "isolationLevel": s.isolation_level.as_ref().map(|l| l.to_string()),
+        }),
+        Statement::Commit(_) => json!({ "type": "Commit" }),
+        Statement::Rollback(s) => json!({
+            "type": "Rollback",
+            "savepointName": s.savepoint_name.as_ref().map(ident),
+        }),
+        Statement::Savepoint(s) => json!({
+            "type": "Savepoint",
+            "savepointName": ident(&s.savepoint_name),
+        }),
+        Statement::ReleaseSavepoint(s) => json!({
+            "type": "ReleaseSavepoint",
+            "savepointName": ident(&s.savepoint_name),
+        }),
+        Statement::Set(s) => json!({
+            "type": "Set",
+            "name": ident(&s.name),
+            "value": expression(&s.value),
+        }),
+        Statement::Pragma(s) => json!({
+            "type": "Pragma",
+            "name": ident(&s.name),
+            "value": s.value.as_ref().map(expression),
+        }),
+        Statement::ShowTables(_) => json!({ "type": "ShowTables" }),
+        Statement::ShowViews(_) => json!({ "type": "ShowViews" }),
+        Statement::ShowCreateTable(s) => json!({
+            "type": "ShowCreateTable",
+            "tableName": ident(&s.table_name),
+        }),
+        Statement::ShowCreateView(s) => json!({
+            "type": "ShowCreateView",
+            "viewName": ident(&s.view_name),
+        }),
+        Statement::ShowIndexes(s) => json!({
+            "type": "ShowIndexes",
+            "tableName": ident(&s.table_name),
+        }),
+        Statement::Describe(s) => json!({
+            "type": "Describe",
+            "tableName": ident(&s.table_name),
+        }),
+        Statement::Expression(s) => json!({
+            "type": "Expression",
+            "expression": expression(&s.expression),
+        }),
+        Statement::Explain(s) => json!({
+            "type": "Explain",
+            "statement": statement(&s.statement),
+            "analyze": s.analyze,
+        }),
+        Statement::Analyze(s) => json!({
+            "type": "Analyze",
+            "tableName": s.table_name.as_ref().map(|t| t.to_string()),
+        }),
+        Statement::Vacuum(s) => json!({
+            "type": "Vacuum",
+            "tableName": s.table_name.as_ref().map(ident),
+        }),
+    }
+}
+
+fn select(s: &SelectStatement) -> Json {
+    let group_by = &s.group_by;
+    json!({
+        "type": "Select",
+        "distinct": s.distinct,
+        "columns": expressions(&s.columns),
+        "with": s.with.as_ref().map(|w| json!({
+            "ctes": w.ctes.iter().map(cte).collect::<Vec<_>>(),
+            "isRecursive": w.is_recursive,
+        })),
+        "tableExpr": optional(s.table_expr.as_deref()),
+        "whereClause": optional(s.where_clause.as_deref()),
+        "groupBy": json!({
+            "columns": expressions(&group_by.columns),
+            "modifier": match &group_by.modifier {
+                GroupByModifier::None => Json::Null,
+                GroupByModifier::Rollup => json!("ROLLUP"),
+                GroupByModifier::Cube => json!("CUBE"),
+                GroupByModifier::GroupingSets(_) => json!("GROUPING SETS"),
+            },
+            "groupingSets": match &group_by.modifier {
+                GroupByModifier::GroupingSets(sets) => rows(sets),
+                _ => Json::Null,
+            },
+        }),
+        "having": optional(s.having.as_deref()),
+        "windowDefs": s.window_defs.iter().map(window_definition).collect::<Vec<_>>(),
+        "orderBy": order_by(&s.order_by),
+        "limit": optional(s.limit.as_deref()),
+        "offset": optional(s.offset.as_deref()),
+        "setOperations": s.set_operations.iter().map(|op| json!({
+            "operation": op.operation.to_string(),
+            "right": select(&op.right),
+        })).collect::<Vec<_>>(),
+    })
+}
+
+fn cte(c: &CommonTableExpression) -> Json {
+    json!({
+        "name": ident(&c.name),
+        "columnNames": idents(&c.column_names),
+        "query": select(&c.query),
+        "isRecursive": c.is_recursive,
+    })
+}
+
+fn alter_table(s: &AlterTableStatement) -> Json {
+    json!({
+        "type": "AlterTable",
+        "tableName": ident(&s.table_name),
+        "operation": match s.operation {
+            AlterTableOperation::AddColumn => "ADD COLUMN",
+            AlterTableOperation::DropColumn => "DROP COLUMN",
+            AlterTableOperation::RenameColumn => "RENAME COLUMN",
+            AlterTableOperation::ModifyColumn => "MODIFY COLUMN",
+            AlterTableOperation::RenameTable => "RENAME TO",
+        },
+        "columnDef": s.column_def.as_ref().map(column_definition),
+        "columnName": s.column_name.as_ref().map(ident),
+        "newColumnName": s.new_column_name.as_ref().map(ident),
+        "newTableName": s.new_table_name.as_ref().map(ident),
+    })
+}
+
+fn column_definition(c: &ColumnDefinition) -> Json {
+    json!({
+        "name": ident(&c.name),
+        "dataType": c.data_type.to_string(),
+        "constraints": c.constraints.iter().map(column_constraint).collect::<Vec<_>>(),
+    })
+}
+
+fn column_constraint(c: &ColumnConstraint) -> Json {
+    match c {
+        ColumnConstraint::NotNull => json!({ "type": "NotNull" }),
+        ColumnConstraint::PrimaryKey => json!({ "type": "PrimaryKey" }),
+        ColumnConstraint::Unique => json!({ "type": "Unique" }),
+        ColumnConstraint::AutoIncrement => json!({ "type": "AutoIncrement" }),
+        ColumnConstraint::Default(e) => json!({ "type": "Default", "value": expression(e) }),
+        ColumnConstraint::Check(e) => json!({ "type": "Check", "expression": expression(e) }),
+        ColumnConstraint::References {
+            table,
+            column,
+            on_delete,
+            on_update,
+        } => json!({
+            "type": "References",
+            "table": ident(table),
+            "column": column.as_ref().map(ident),
+            "onDelete": on_delete.to_string(),
+            "onUpdate": on_update.to_string(),
+        }),
+    }
+}
+
+fn table_constraint(c: &TableConstraint) -> Json {
+    match c {
+        TableConstraint::Unique(columns) => json!({ "type": "Unique", "columns": idents(columns) }),
+        TableConstraint::Check(e) => json!({ "type": "Check", "expression": expression(e) }),
+        TableConstraint::PrimaryKey(columns) => {
+            json!({ "type": "PrimaryKey", "columns": idents(columns) })
+        }
+        TableConstraint::ForeignKey(fk) => json!({
+            "type": "ForeignKey",
+            "column": ident(&fk.column),
+            "refTable": ident(&fk.ref_table),
+            "refColumn": fk.ref_column.as_ref().map(ident),
+            "onDelete": fk.on_delete.to_string(),
+            "onUpdate": fk.on_update.to_string(),
+        }),
+    }
+}
+
+fn expression(expr: &Expression) -> Json {
+    match expr {
+        Expression::Identifier(e) => json!({ "type": "Identifier", "value": ident(e) }),
+        Expression::QualifiedIdentifier(e) => json!({
+            "type": "QualifiedIdentifier",
+            "qualifier": ident(&e.qualifier),
+            "name": ident(&e.name),
+        }),
+        // A number, as INTEGER results are, rather than a BigInt past 2^53.
+        Expression::IntegerLiteral(e) => {
+            json!({ "type": "IntegerLiteral", "value": e.value as f64 })
+        }
+        Expression::FloatLiteral(e) => json!({ "type": "FloatLiteral", "value": e.value }),
+        Expression::StringLiteral(e) => json!({
+            "type": "StringLiteral",
+            "value": e.value.to_string(),
+            "typeHint": e.type_hint.as_ref().map(|t| t.to_string()),
+        }),
+        Expression::BooleanLiteral(e) => json!({ "type": "BooleanLiteral", "value": e.value }),
+        Expression::NullLiteral(_) => json!({ "type": "NullLiteral" }),
+        Expression::IntervalLiteral(e) => json!({
+            "type": "IntervalLiteral",
+            "value": e.value.to_string(),
+            "quantity": e.quantity,
+            "unit": e.unit.to_string(),
+        }),
+        Expression::Parameter(e) => json!({
+            "type": "Parameter",
+            "name": e.name.to_string(),
+            "index": e.index,
+        }),
+        Expression::Prefix(e) => json!({
+            "type": "Prefix",
+            "operator": e.operator.to_string(),
+            "right": expression(&e.right),
+        }),
+        Expression::Infix(e) => json!({
+            "type": "Infix",
+            "left": expression(&e.left),
+            "operator": e.operator.to_string(),
+            "right": expression(&e.right),
+        }),
+        Expression::List(e) => json!({ "type": "List", "elements": expressions(&e.elements) }),
+        Expression::Distinct(e) => json!({ "type": "Distinct", "expr": expression(&e.expr) }),
+        Expression::Exists(e) => json!({ "type": "Exists", "subquery": select(&e.subquery) }),
+        Expression::AllAny(e) => json!({
+            "type": "AllAny",
+            "left": expression(&e.left),
+            "operator": e.operator.to_string(),
+            "allAnyType": e.all_any_type.to_string(),
+            "subquery": select(&e.subquery),
+        }),
+        Expression::In(e) => json!({
+            "type": "In",
+            "left": expression(&e.left),
+            "right": expression(&e.right),
+            "not": e.not,
+        }),
+        // Made by the executor for IN over a subquery's rows, never parsed.
+        Expression::InHashSet(e) => json!({
+            "type": "InHashSet",
+            "column": expression(&e.column),
+            "values": e.values.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+            "not": e.not,
+        }),
+        Expression::Between(e) => json!({
+            "type": "Between",
+            "expr": expression(&e.expr),
+            "lower": expression(&e.lower),
+            "upper": expression(&e.upper),
+            "not": e.not,
+        }),
+        Expression::Like(e) => json!({
+            "type": "Like",
+            "left": expression(&e.left),
+            "pattern": expression(&e.pattern),
+            "operator": e.operator.to_string(),
+            "escape": optional(e.escape.as_deref()),
+        }),
+        Expression::ScalarSubquery(e) => {
+            json!({ "type": "ScalarSubquery", "subquery": select(&e.subquery) })
+        }
+        Expression::ExpressionList(e) => json!({
+            "type": "ExpressionList",
+            "expressions": expressions(&e.expressions),
+        }),
+        Expression::Case(e) => json!({
+            "type": "Case",
+            "value": optional(e.value.as_deref()),
+            "whenClauses": e.when_clauses.iter().map(|w| json!({
+                "condition": expression(&w.condition),
+                "thenResult": expression(&w.then_result),
+            })).collect::<Vec<_>>(),
+            "elseValue": optional(e.else_value.as_deref()),
+        }),
+        Expression::Cast(e) => json!({
+            "type": "Cast",
+            "expr": expression(&e.expr),
+            "typeName": e.type_name.to_string(),
+        }),
+        Expression::FunctionCall(e) => function_call(e),
+        Expression::Aliased(e) => json!({
+            "type": "Aliased",
+            "expression": expression(&e.expression),
+            "alias": ident(&e.alias),
+        }),
+        Expression::Window(e) => json!({
+            "type": "Window",
+            "function": function_call(&e.function),
+            "windowRef": e.window_ref.as_ref().map(|w| w.to_string()),
+            "partitionBy": expressions(&e.partition_by),
+            "orderBy": order_by(&e.order_by),
+            "frame": e.frame.as_ref().map(window_frame),
+        }),
+        Expression::TableSource(e) => json!({
+            "type": "TableSource",
+            "name": ident(&e.name),
+            "alias": e.alias.as_ref().map(ident),
+            "asOf": e.as_of.as_ref().map(|a| json!({
+                "asOfType": a.as_of_type.to_string(),
+                "value": expression(&a.value),
+            })),
+        }),
+        Expression::JoinSource(e) => json!({
+            "type": "JoinSource",
+            "left": expression(&e.left),
+            "joinType": e.join_type.to_string(),
+            "right": expression(&e.right),
+            "condition": optional(e.condition.as_deref()),
+            "usingColumns": idents(&e.using_columns),
+        }),
+        Expression::SubquerySource(e) => json!({
+            "type": "SubquerySource",
+            "subquery": select(&e.subquery),
+            "alias": e.alias.as_ref().map(ident),
+        }),
+        Expression::ValuesSource(e) => json!({
+            "type": "ValuesSource",
+            "rows": rows(&e.rows),
+            "alias": e.alias.as_ref().map(ident),
+            "columnAliases": idents(&e.column_aliases),
+        }),
+        Expression::CteReference(e) => json!({
+            "type": "CteReference",
+            "name": ident(&e.name),
+            "alias": e.alias.as_ref().map(ident),
+        }),
+        Expression::Star(_) => json!({ "type": "Star" }),
+        Expression::QualifiedStar(e) => json!({
+            "type": "QualifiedStar",
+            "qualifier": e.qualifier.to_string(),
+        }),
+        Expression::Default(_) => json!({ "type": "Default" }),
+    }
+}
+
+fn function_call(f: &FunctionCall) -> Json {
+    json!({
+        "type": "FunctionCall",
+        "function": f.function.to_string(),
+        "arguments": expressions(&f.arguments),
+        "isDistinct": f.is_distinct,
+        "orderBy": order_by(&f.order_by),
+        "filter": optional(f.filter.as_deref()),
+    })
+}
+
+fn window_definition(w: &WindowDefinition) -> Json {
+    json!({
+        "name": w.name.to_string(),
+        "partitionBy": expressions(&w.partition_by),
+        "orderBy": order_by(&w.order_by),
+        "frame": w.frame.as_ref().map(window_frame),
+    })
+}
+
+fn window_frame(f: &WindowFrame) -> Json {
+    json!({
+        "unit": match f.unit {
+            WindowFrameUnit::Rows => "ROWS",
+            WindowFrameUnit::Range => "RANGE",
+        },
+        "start": frame_bound(&f.start),
+        "end": f.end.as_ref().map(frame_bound),
+    })
+}
+
+fn frame_bound(b: &WindowFrameBound) -> Json {
+    match b {
+        WindowFrameBound::CurrentRow => json!({ "type": "CURRENT ROW" }),
+        WindowFrameBound::UnboundedPreceding => json!({ "type": "UNBOUNDED PRECEDING" }),
+        WindowFrameBound::UnboundedFollowing => json!({ "type": "UNBOUNDED FOLLOWING" }),
+        WindowFrameBound::Preceding(e) => json!({ "type": "PRECEDING", "offset": expression(e) }),
+        WindowFrameBound::Following(e) => json!({ "type": "FOLLOWING", "offset": expression(e) }),
+    }
+}
+
+fn order_by(items: &[OrderByExpression]) -> Json {
+    items
+        .iter()
+        .map(|o| {
+            json!({
+                "expression": expression(&o.expression),
+                "ascending": o.ascending,
+                "nullsFirst": o.nulls_first,
+            })
+        })
+        .collect()
+}
+
+fn optional(expr: Option<&Expression>) -> Json {
+    expr.map_or(Json::Null, expression)
+}
+
+fn expressions(exprs: &[Expression]) -> Json {
+    exprs.iter().map(expression).collect()
+}
+
+fn rows(rows: &[Vec<Expression>]) -> Json {
+    rows.iter().map(|row| expressions(row)).collect()
+}
+
+fn ident(id: &Identifier) -> Json {
+    Json::String(id.value.to_string())
+}
+
+fn idents(ids: &[Identifier]) -> Json {
+    ids.iter().map(ident).collect()
+}
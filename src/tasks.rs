@@ -14,13 +14,17 @@
 
 use napi::bindgen_prelude::*;
 use napi::{sys, Env, Task};
+use std::borrow::Cow;
+use std::path::PathBuf;
 use std::ptr;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use stoolap::api::Database;
-use stoolap::api::NamedParams;
 use stoolap::api::Transaction as ApiTransaction;
-use stoolap::{CachedPlanRef, ParamVec, Value};
+use stoolap::{CachedPlanRef, ParamVec, SmartString, Value};
+use stoolap_bindings_core::cells::{self, Cell};
+use stoolap_bindings_core::timestamps::{iso_seconds, TimestampFormat};
 
 // ============================================================
 // V8 bulk object creation via C++ FFI
@@ -35,10 +39,27 @@ const TAG_DOUBLE: u8 = 4;
 const TAG_STRING: u8 = 5;
 const TAG_INT64: u8 = 6;
 const TAG_FLOAT32_ARRAY: u8 = 7;
+/// ASCII text, which V8 copies without decoding UTF-8.
+const TAG_ASCII_STRING: u8 = 8;
+/// ASCII text of at least `EXTERNAL_TEXT_MIN` bytes, which V8 references
+/// instead of copying; `int_val` points to the `SmartString` that owns it.
+const TAG_EXTERNAL_ASCII: u8 = 9;
+/// A TIMESTAMP as RFC 3339 text, made into a `Temporal.Instant` (the
+/// `temporal` open option).
+const TAG_TIMESTAMP: u8 = 10;
+/// A JS value made already, such as one a result filter returned;
+/// `int_val` is its `napi_value`.
+const TAG_JS_VALUE: u8 = 11;
+
+/// Size from which ASCII text becomes an external V8 string. Below it,
+/// copying is cheaper than the resource and finalizer an external string
+/// needs.
+const EXTERNAL_TEXT_MIN: usize = 32 * 1024;
 
 /// C-compatible cell data — must match C++ CellData layout exactly.
 /// Passed to V8 helper for direct value creation (bypasses NAPI).
 #[repr(C)]
+#[derive(Clone, Copy)]
 struct CellData {
     tag: u8,
     // 7 bytes padding (automatic with repr(C))
@@ -53,12 +74,14 @@ struct CellData {
 /// C++ calls this per row; returns 1 if row available, 0 when done.
 type RowCallback = extern "C" fn(ctx: *mut std::ffi::c_void, cells: *mut CellData) -> i32;
 
+#[cfg(not(feature = "pure-napi"))]
 extern "C" {
     fn v8_create_single_object(
         col_count: i32,
         col_ptrs: *const *const u8,
         col_lens: *const i32,
         cells: *const CellData,
+        cached_keys: *const std::ffi::c_void,
     ) -> sys::napi_value;
 
     fn v8_create_null() -> sys::napi_value;
@@ -69,6 +92,8 @@ extern "C" {
         col_lens: *const i32,
         next_row: RowCallback,
         ctx: *mut std::ffi::c_void,
+        low_memory: i32,
+        cached_keys: *const std::ffi::c_void,
     ) -> sys::napi_value;
 
     fn v8_create_raw_streaming(
@@ -77,9 +102,243 @@ extern "C" {
         col_lens: *const i32,
         next_row: RowCallback,
         ctx: *mut std::ffi::c_void,
+        low_memory: i32,
+        cached_keys: *const std::ffi::c_void,
     ) -> sys::napi_value;
 
-    fn v8_create_run_result(changes: i64) -> sys::napi_value;
+    fn v8_create_pluck_streaming(
+        col_count: i32,
+        next_row: RowCallback,
+        ctx: *mut std::ffi::c_void,
+    ) -> sys::napi_value;
+
+    #[allow(clippy::too_many_arguments)]
+    fn v8_create_expanded_streaming(
+        col_count: i32,
+        key_ptrs: *const *const u8,
+        key_lens: *const i32,
+        ns_count: i32,
+        ns_ptrs: *const *const u8,
+        ns_lens: *const i32,
+        col_ns: *const i32,
+        next_row: RowCallback,
+        ctx: *mut std::ffi::c_void,
+    ) -> sys::napi_value;
+
+    fn v8_create_run_result(
+        changes: i64,
+        last_id: *const CellData,
+        duration_ms: f64,
+        warnings: *const CellData,
+        warning_count: i32,
+    ) -> sys::napi_value;
+
+    fn v8_column_keys_new(
+        col_count: i32,
+        col_ptrs: *const *const u8,
+        col_lens: *const i32,
+    ) -> *mut std::ffi::c_void;
+
+    fn v8_column_keys_free(keys: *mut std::ffi::c_void);
+
+    fn v8_helpers_probe() -> i32;
+}
+
+/// Stand-ins for the V8 helpers when built with the `pure-napi` feature.
+/// `runtime::v8_helpers()` is always false then, so none of them is called.
+#[cfg(feature = "pure-napi")]
+mod v8_unavailable {
+    use super::{sys, CellData, RowCallback};
+
+    pub unsafe fn v8_create_single_object(
+        _col_count: i32,
+        _col_ptrs: *const *const u8,
+        _col_lens: *const i32,
+        _cells: *const CellData,
+        _cached_keys: *const std::ffi::c_void,
+    ) -> sys::napi_value {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_create_null() -> sys::napi_value {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_create_rows_streaming(
+        _col_count: i32,
+        _col_ptrs: *const *const u8,
+        _col_lens: *const i32,
+        _next_row: RowCallback,
+        _ctx: *mut std::ffi::c_void,
+        _low_memory: i32,
+        _cached_keys: *const std::ffi::c_void,
+    ) -> sys::napi_value {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_create_raw_streaming(
+        _col_count: i32,
+        _col_ptrs: *const *const u8,
+        _col_lens: *const i32,
+        _next_row: RowCallback,
+        _ctx: *mut std::ffi::c_void,
+        _low_memory: i32,
+        _cached_keys: *const std::ffi::c_void,
+    ) -> sys::napi_value {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_create_pluck_streaming(
+        _col_count: i32,
+        _next_row: RowCallback,
+        _ctx: *mut std::ffi::c_void,
+    ) -> sys::napi_value {
+        unreachable!("built without V8 helpers")
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn v8_create_expanded_streaming(
+        _col_count: i32,
+        _key_ptrs: *const *const u8,
+        _key_lens: *const i32,
+        _ns_count: i32,
+        _ns_ptrs: *const *const u8,
+        _ns_lens: *const i32,
+        _col_ns: *const i32,
+        _next_row: RowCallback,
+        _ctx: *mut std::ffi::c_void,
+    ) -> sys::napi_value {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_create_run_result(
+        _changes: i64,
+        _last_id: *const CellData,
+        _duration_ms: f64,
+        _warnings: *const CellData,
+        _warning_count: i32,
+    ) -> sys::napi_value {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_column_keys_new(
+        _col_count: i32,
+        _col_ptrs: *const *const u8,
+        _col_lens: *const i32,
+    ) -> *mut std::ffi::c_void {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_column_keys_free(_keys: *mut std::ffi::c_void) {
+        unreachable!("built without V8 helpers")
+    }
+
+    pub unsafe fn v8_helpers_probe() -> i32 {
+        0
+    }
+}
+
+#[cfg(feature = "pure-napi")]
+use v8_unavailable::*;
+
+/// Check that the V8 helpers work in this runtime: the V8 API is present,
+/// and a value they build reads back correctly through N-API.
+pub(crate) fn probe_v8_helpers(env: sys::napi_env) -> std::result::Result<(), &'static str> {
+    if cfg!(feature = "pure-napi") {
+        return Err("built with the pure-napi feature");
+    }
+    if unsafe { v8_helpers_probe() } == 0 {
+        return Err("V8 API not available");
+    }
+    let read_back = || -> napi::Result<i64> {
+        let result = unsafe { v8_create_run_result(7, &NULL_CELL, 0.0, ptr::null(), 0) };
+        let mut changes = ptr::null_mut();
+        check(unsafe {
+            sys::napi_get_named_property(env, result, c"changes".as_ptr(), &mut changes)
+        })?;
+        let mut value = 0;
+        check(unsafe { sys::napi_get_value_int64(env, changes, &mut value) })?;
+        Ok(value)
+    };
+    match read_back() {
+        Ok(7) => Ok(()),
+        _ => Err("V8 values are not N-API values in this runtime"),
+    }
+}
+
+/// Fill the cells of one row. A row stored before `ALTER TABLE ADD COLUMN`
+/// can be shorter than the result's columns; its missing values are NULL.
+///
+/// # Safety
+///
+/// `cells` must have room for `col_count` cells.
+unsafe fn fill_cells(
+    values: &[Value],
+    col_count: usize,
+    cells: *mut CellData,
+    temp_strings: &mut Vec<String>,
+) {
+    for i in 0..col_count {
+        let cell = match values.get(i) {
+            Some(val) => value_to_cell(val, temp_strings),
+            None => NULL_CELL,
+        };
+        unsafe { *cells.add(i) = cell };
+    }
+}
+
+/// Fill the cells of one row's picked columns, in `pick` order.
+///
+/// # Safety
+///
+/// `cells` must have room for `pick.len()` cells.
+unsafe fn fill_picked(
+    values: &[Value],
+    pick: &[usize],
+    cells: *mut CellData,
+    temp_strings: &mut Vec<String>,
+) {
+    for (slot, &i) in pick.iter().enumerate() {
+        let cell = match values.get(i) {
+            Some(val) => value_to_cell(val, temp_strings),
+            None => NULL_CELL,
+        };
+        unsafe { *cells.add(slot) = cell };
+    }
+}
+
+/// The cells of one row, as `fill_cells` fills them.
+fn row_cells(values: &[Value], col_count: usize, temp_strings: &mut Vec<String>) -> Vec<CellData> {
+    let mut cells = vec![NULL_CELL; col_count];
+    unsafe { fill_cells(values, col_count, cells.as_mut_ptr(), temp_strings) };
+    cells
+}
+
+/// Keep the text of a `TAG_EXTERNAL_ASCII` cell alive for an external V8
+/// string: a new reference to its shared buffer, so the bytes stay where
+/// they are until `stoolap_text_release`.
+///
+/// # Safety
+///
+/// `text` must be the `int_val` of a `TAG_EXTERNAL_ASCII` cell whose row is
+/// still alive.
+#[no_mangle]
+pub unsafe extern "C" fn stoolap_text_retain(
+    text: *const std::ffi::c_void,
+) -> *mut std::ffi::c_void {
+    let text = unsafe { &*(text as *const SmartString) };
+    Box::into_raw(Box::new(text.clone())) as *mut std::ffi::c_void
+}
+
+/// Drop a reference taken by `stoolap_text_retain`, once V8 has collected
+/// the string.
+///
+/// # Safety
+///
+/// `owner` must come from `stoolap_text_retain` and not be released yet.
+#[no_mangle]
+pub unsafe extern "C" fn stoolap_text_release(owner: *mut std::ffi::c_void) {
+    drop(unsafe { Box::from_raw(owner as *mut SmartString) });
 }
 
 /// Context passed to the streaming callback.
@@ -88,6 +347,12 @@ struct StreamContext {
     rows: *mut stoolap::Rows,
     temp_strings: Vec<String>,
     col_count: usize,
+    /// The columns the `columns` option keeps, if set.
+    pick: Option<Vec<usize>>,
+    /// Rows the `limit` option still lets through.
+    remaining: usize,
+    /// Rows handed to V8 so far (reported to profile listeners).
+    row_count: usize,
 }
 
 /// Streaming callback: advance Rows, fill CellData directly from current_row().
@@ -98,15 +363,16 @@ extern "C" fn stream_next_row(ctx: *mut std::ffi::c_void, cells: *mut CellData)
     ctx.temp_strings.clear();
 
     let rows = unsafe { &mut *ctx.rows };
-    if !rows.advance() {
+    if ctx.remaining == 0 || !rows.advance() {
         return 0;
     }
+    ctx.remaining -= 1;
+    ctx.row_count += 1;
 
     let values = rows.current_row().as_slice();
-    for (i, val) in values.iter().enumerate().take(ctx.col_count) {
-        unsafe {
-            *cells.add(i) = value_to_cell(val, &mut ctx.temp_strings);
-        }
+    match ctx.pick {
+        Some(ref pick) => unsafe { fill_picked(values, pick, cells, &mut ctx.temp_strings) },
+        None => unsafe { fill_cells(values, ctx.col_count, cells, &mut ctx.temp_strings) },
     }
     1
 }
@@ -116,30 +382,29 @@ extern "C" fn stream_next_row(ctx: *mut std::ffi::c_void, cells: *mut CellData)
 /// (the caller must keep temp_strings alive until the C++ call completes).
 #[inline]
 fn value_to_cell(val: &Value, temp_strings: &mut Vec<String>) -> CellData {
-    match val {
-        Value::Null(_) => CellData {
+    match cells::cell(val) {
+        Cell::Null => CellData {
             tag: TAG_NULL,
             int_val: 0,
             float_val: 0.0,
             str_ptr: ptr::null(),
             str_len: 0,
         },
-        Value::Boolean(false) => CellData {
+        Cell::Boolean(false) => CellData {
             tag: TAG_BOOL_FALSE,
             int_val: 0,
             float_val: 0.0,
             str_ptr: ptr::null(),
             str_len: 0,
         },
-        Value::Boolean(true) => CellData {
+        Cell::Boolean(true) => CellData {
             tag: TAG_BOOL_TRUE,
             int_val: 0,
             float_val: 0.0,
             str_ptr: ptr::null(),
             str_len: 0,
         },
-        Value::Integer(i) => {
-            let i = *i;
+        Cell::Integer(i) => {
             if i >= i32::MIN as i64 && i <= i32::MAX as i64 {
                 CellData {
                     tag: TAG_INT32,
@@ -158,79 +423,46 @@ fn value_to_cell(val: &Value, temp_strings: &mut Vec<String>) -> CellData {
                 }
             }
         }
-        Value::Float(f) => {
-            if f.is_nan() || f.is_infinite() {
-                CellData {
-                    tag: TAG_NULL,
-                    int_val: 0,
-                    float_val: 0.0,
-                    str_ptr: ptr::null(),
-                    str_len: 0,
-                }
-            } else {
-                CellData {
-                    tag: TAG_DOUBLE,
-                    int_val: 0,
-                    float_val: *f,
-                    str_ptr: ptr::null(),
-                    str_len: 0,
-                }
-            }
-        }
-        Value::Text(s) => {
+        Cell::Float(f) => CellData {
+            tag: TAG_DOUBLE,
+            int_val: 0,
+            float_val: f,
+            str_ptr: ptr::null(),
+            str_len: 0,
+        },
+        Cell::Text(s) => {
             let s_ref: &str = s;
+            let (tag, int_val) = if !s_ref.is_ascii() {
+                (TAG_STRING, 0)
+            } else if s_ref.len() >= EXTERNAL_TEXT_MIN && s.is_heap() {
+                (TAG_EXTERNAL_ASCII, s as *const SmartString as i64)
+            } else {
+                (TAG_ASCII_STRING, 0)
+            };
             CellData {
-                tag: TAG_STRING,
-                int_val: 0,
+                tag,
+                int_val,
                 float_val: 0.0,
                 str_ptr: s_ref.as_ptr(),
                 str_len: s_ref.len() as i32,
             }
         }
-        Value::Timestamp(ts) => {
-            use chrono::{Datelike, Timelike};
-            let mut s = String::with_capacity(22);
-            let mut b = itoa::Buffer::new();
-            let y = ts.year();
-            if (0..10).contains(&y) {
-                s.push_str("000");
-            } else if (10..100).contains(&y) {
-                s.push_str("00");
-            } else if (100..1000).contains(&y) {
-                s.push('0');
-            }
-            s.push_str(b.format(y));
-            s.push('-');
-            let m = ts.month();
-            if m < 10 {
-                s.push('0');
-            }
-            s.push_str(b.format(m));
-            s.push('-');
-            let d = ts.day();
-            if d < 10 {
-                s.push('0');
-            }
-            s.push_str(b.format(d));
-            s.push('T');
-            let h = ts.hour();
-            if h < 10 {
-                s.push('0');
-            }
-            s.push_str(b.format(h));
-            s.push(':');
-            let min = ts.minute();
-            if min < 10 {
-                s.push('0');
-            }
-            s.push_str(b.format(min));
-            s.push(':');
-            let sec = ts.second();
-            if sec < 10 {
-                s.push('0');
+        Cell::Timestamp(ts) if types::temporal() => {
+            temp_strings.push(ts.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true));
+            let last = temp_strings.last().unwrap();
+            CellData {
+                tag: TAG_TIMESTAMP,
+                int_val: 0,
+                float_val: 0.0,
+                str_ptr: last.as_ptr(),
+                str_len: last.len() as i32,
             }
-            s.push_str(b.format(sec));
-            s.push('Z');
+        }
+        Cell::Timestamp(ts) => {
+            let s = match types::timestamp_format() {
+                Some(format) => format.format(ts),
+                None => iso_seconds(ts),
+            };
             // Push to temp_strings; String's heap buffer won't move on Vec realloc
             temp_strings.push(s);
             let last = temp_strings.last().unwrap();
@@ -242,582 +474,2853 @@ fn value_to_cell(val: &Value, temp_strings: &mut Vec<String>) -> CellData {
                 str_len: last.len() as i32,
             }
         }
-        Value::Extension(data) => {
-            if let Some(s_ref) = val.as_json() {
-                CellData {
-                    tag: TAG_STRING,
-                    int_val: 0,
-                    float_val: 0.0,
-                    str_ptr: s_ref.as_ptr(),
-                    str_len: s_ref.len() as i32,
-                }
-            } else if data.first() == Some(&7) {
-                // Vector: tag byte (7) + packed little-endian f32 payload
-                let payload = &data[1..];
-                CellData {
-                    tag: TAG_FLOAT32_ARRAY,
-                    int_val: 0,
-                    float_val: 0.0,
-                    str_ptr: payload.as_ptr(),
-                    str_len: payload.len() as i32,
-                }
-            } else {
-                CellData {
-                    tag: TAG_NULL,
-                    int_val: 0,
-                    float_val: 0.0,
-                    str_ptr: ptr::null(),
-                    str_len: 0,
-                }
+        Cell::Json(s_ref) => CellData {
+            tag: TAG_STRING,
+            int_val: 0,
+            float_val: 0.0,
+            str_ptr: s_ref.as_ptr(),
+            str_len: s_ref.len() as i32,
+        },
+        // Packed little-endian f32 payload, without the tag byte
+        Cell::Vector(payload) => CellData {
+            tag: TAG_FLOAT32_ARRAY,
+            int_val: 0,
+            float_val: 0.0,
+            str_ptr: payload.as_ptr(),
+            str_len: payload.len() as i32,
+        },
+    }
+}
+
+// ============================================================
+// N-API fallback when the V8 helpers can't be used (see runtime.rs)
+// ============================================================
+
+const NULL_CELL: CellData = CellData {
+    tag: TAG_NULL,
+    int_val: 0,
+    float_val: 0.0,
+    str_ptr: ptr::null(),
+    str_len: 0,
+};
+
+/// Create the JS value of `value` with N-API calls, as a result cell.
+pub(crate) fn value_to_js(env: sys::napi_env, value: &Value) -> napi::Result<sys::napi_value> {
+    let mut temp_strings = Vec::new();
+    napi_cell(env, &value_to_cell(value, &mut temp_strings))
+}
+
+/// Create the JS value for a cell with N-API calls, matching `cell_to_v8`.
+fn napi_cell(env: sys::napi_env, cell: &CellData) -> napi::Result<sys::napi_value> {
+    if cell.tag == TAG_JS_VALUE {
+        return Ok(cell.int_val as sys::napi_value);
+    }
+    if cell.tag == TAG_TIMESTAMP {
+        let text = unsafe { std::slice::from_raw_parts(cell.str_ptr, cell.str_len as usize) };
+        return temporal::instant(env, std::str::from_utf8(text).unwrap_or_default());
+    }
+    let mut value = ptr::null_mut();
+    let status = unsafe {
+        match cell.tag {
+            TAG_BOOL_FALSE | TAG_BOOL_TRUE => {
+                sys::napi_get_boolean(env, cell.tag == TAG_BOOL_TRUE, &mut value)
+            }
+            TAG_INT32 => sys::napi_create_int32(env, cell.int_val as i32, &mut value),
+            TAG_DOUBLE => sys::napi_create_double(env, cell.float_val, &mut value),
+            TAG_STRING => sys::napi_create_string_utf8(
+                env,
+                cell.str_ptr as *const std::ffi::c_char,
+                cell.str_len as isize,
+                &mut value,
+            ),
+            TAG_ASCII_STRING | TAG_EXTERNAL_ASCII => sys::napi_create_string_latin1(
+                env,
+                cell.str_ptr as *const std::ffi::c_char,
+                cell.str_len as isize,
+                &mut value,
+            ),
+            TAG_INT64 => sys::napi_create_int64(env, cell.int_val, &mut value),
+            TAG_FLOAT32_ARRAY => {
+                let byte_len = cell.str_len as usize;
+                let mut data = ptr::null_mut();
+                let mut buffer = ptr::null_mut();
+                check(sys::napi_create_arraybuffer(
+                    env,
+                    byte_len,
+                    &mut data,
+                    &mut buffer,
+                ))?;
+                ptr::copy_nonoverlapping(cell.str_ptr, data as *mut u8, byte_len);
+                sys::napi_create_typedarray(
+                    env,
+                    sys::TypedarrayType::float32_array,
+                    byte_len / 4,
+                    buffer,
+                    0,
+                    &mut value,
+                )
             }
+            _ => sys::napi_get_null(env, &mut value),
         }
-    }
+    };
+    check(status)?;
+    Ok(value)
 }
 
-/// Collected rows for async path — transfer from compute() to resolve().
-pub struct CollectedRows {
-    columns: Vec<String>,
-    rows: Vec<Vec<Value>>,
+fn napi_string(env: sys::napi_env, s: &str) -> napi::Result<sys::napi_value> {
+    let mut value = ptr::null_mut();
+    check(unsafe {
+        sys::napi_create_string_utf8(
+            env,
+            s.as_ptr() as *const std::ffi::c_char,
+            s.len() as isize,
+            &mut value,
+        )
+    })?;
+    Ok(value)
 }
 
-/// Context for streaming over already-collected rows (async resolve path).
-struct CollectedStreamContext<'a> {
-    data: &'a CollectedRows,
-    row_idx: usize,
-    temp_strings: Vec<String>,
+fn napi_object(
+    env: sys::napi_env,
+    keys: &[sys::napi_value],
+    cells: &[CellData],
+) -> napi::Result<sys::napi_value> {
+    let mut object = ptr::null_mut();
+    check(unsafe { sys::napi_create_object(env, &mut object) })?;
+    for (key, cell) in keys.iter().zip(cells) {
+        let value = napi_cell(env, cell)?;
+        check(unsafe { sys::napi_set_property(env, object, *key, value) })?;
+    }
+    Ok(object)
 }
 
-/// Streaming callback for collected rows: iterates over Vec<Vec<Value>> row by row.
-/// Avoids allocating a flat Vec<CellData> for all rows — reuses C++ per-row buffer.
-extern "C" fn collected_next_row(ctx: *mut std::ffi::c_void, cells: *mut CellData) -> i32 {
-    let ctx = unsafe { &mut *(ctx as *mut CollectedStreamContext) };
-    if ctx.row_idx >= ctx.data.rows.len() {
-        return 0;
+fn napi_array(env: sys::napi_env, elements: &[sys::napi_value]) -> napi::Result<sys::napi_value> {
+    let mut array = ptr::null_mut();
+    check(unsafe { sys::napi_create_array_with_length(env, elements.len(), &mut array) })?;
+    for (i, element) in elements.iter().enumerate() {
+        check(unsafe { sys::napi_set_element(env, array, i as u32, *element) })?;
     }
-    ctx.temp_strings.clear();
-    let row = &ctx.data.rows[ctx.row_idx];
-    for (i, val) in row.iter().enumerate() {
-        unsafe {
-            *cells.add(i) = value_to_cell(val, &mut ctx.temp_strings);
+    Ok(array)
+}
+
+/// N-API counterpart of `v8_create_rows_streaming` (`raw: false`) and
+/// `v8_create_raw_streaming` (`raw: true`), driven by the same callbacks.
+fn napi_create_streaming(
+    env: sys::napi_env,
+    columns: &[String],
+    raw: bool,
+    low_memory: bool,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    if columns.is_empty() && !raw {
+        return napi_array(env, &[]);
+    }
+    let keys = columns
+        .iter()
+        .map(|c| napi_string(env, c))
+        .collect::<napi::Result<Vec<_>>>()?;
+    let mut cells = vec![NULL_CELL; columns.len()];
+    let rows = if low_memory {
+        napi_append_rows(env, &keys, raw, &mut cells, next_row, ctx)?
+    } else {
+        let mut rows = Vec::new();
+        while next_row(ctx, cells.as_mut_ptr()) != 0 {
+            rows.push(napi_row(env, &keys, raw, &cells)?);
         }
+        napi_array(env, &rows)?
+    };
+    if !raw {
+        return Ok(rows);
     }
-    ctx.row_idx += 1;
-    1
+    let mut result = ptr::null_mut();
+    check(unsafe { sys::napi_create_object(env, &mut result) })?;
+    let columns = napi_array(env, &keys)?;
+    check(unsafe { sys::napi_set_named_property(env, result, c"columns".as_ptr(), columns) })?;
+    check(unsafe { sys::napi_set_named_property(env, result, c"rows".as_ptr(), rows) })?;
+    Ok(result)
 }
 
-/// Convert collected rows to a JS array using V8 streaming callback.
-/// Iterates row-by-row over the collected data — no flat CellData allocation.
-fn collected_rows_to_v8_array(data: &CollectedRows) -> sys::napi_value {
-    let col_count = data.columns.len();
+/// One row as an array (`raw: true`) or an object.
+fn napi_row(
+    env: sys::napi_env,
+    keys: &[sys::napi_value],
+    raw: bool,
+    cells: &[CellData],
+) -> napi::Result<sys::napi_value> {
+    if !raw {
+        return napi_object(env, keys, cells);
+    }
+    let values = cells
+        .iter()
+        .map(|cell| napi_cell(env, cell))
+        .collect::<napi::Result<Vec<_>>>()?;
+    napi_array(env, &values)
+}
 
-    let col_ptrs: Vec<*const u8> = data.columns.iter().map(|c| c.as_ptr()).collect();
-    let col_lens: Vec<i32> = data.columns.iter().map(|c| c.len() as i32).collect();
+/// Low-memory mode: append each row to the array as it is made, in its own
+/// handle scope, instead of holding every row's handle first.
+fn napi_append_rows(
+    env: sys::napi_env,
+    keys: &[sys::napi_value],
+    raw: bool,
+    cells: &mut [CellData],
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    let mut array = ptr::null_mut();
+    check(unsafe { sys::napi_create_array(env, &mut array) })?;
+    let mut index = 0u32;
+    loop {
+        let mut scope = ptr::null_mut();
+        check(unsafe { sys::napi_open_handle_scope(env, &mut scope) })?;
+        let appended: napi::Result<bool> = (|| {
+            if next_row(ctx, cells.as_mut_ptr()) == 0 {
+                return Ok(false);
+            }
+            let row = napi_row(env, keys, raw, cells)?;
+            check(unsafe { sys::napi_set_element(env, array, index, row) })?;
+            Ok(true)
+        })();
+        check(unsafe { sys::napi_close_handle_scope(env, scope) })?;
+        if !appended? {
+            return Ok(array);
+        }
+        index += 1;
+    }
+}
 
-    let mut ctx = CollectedStreamContext {
-        data,
-        row_idx: 0,
-        temp_strings: Vec::new(),
-    };
+/// N-API counterpart of `v8_create_pluck_streaming`.
+fn napi_create_pluck(
+    env: sys::napi_env,
+    col_count: usize,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    if col_count == 0 {
+        return napi_array(env, &[]);
+    }
+    let mut cells = vec![NULL_CELL; col_count];
+    let mut values = Vec::new();
+    while next_row(ctx, cells.as_mut_ptr()) != 0 {
+        values.push(napi_cell(env, &cells[0])?);
+    }
+    napi_array(env, &values)
+}
 
-    unsafe {
-        v8_create_rows_streaming(
-            col_count as i32,
-            col_ptrs.as_ptr(),
-            col_lens.as_ptr(),
-            collected_next_row,
-            &mut ctx as *mut CollectedStreamContext as *mut std::ffi::c_void,
-        )
+/// N-API counterpart of `v8_create_expanded_streaming`.
+fn napi_create_expanded(
+    env: sys::napi_env,
+    expanded: &Expanded,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    let col_count = expanded.keys.len();
+    if col_count == 0 {
+        return napi_array(env, &[]);
+    }
+    let namespaces = expanded
+        .namespaces
+        .iter()
+        .map(|n| napi_string(env, n))
+        .collect::<napi::Result<Vec<_>>>()?;
+    let keys = expanded
+        .keys
+        .iter()
+        .map(|k| napi_string(env, k))
+        .collect::<napi::Result<Vec<_>>>()?;
+    let mut cells = vec![NULL_CELL; col_count];
+    let mut rows = Vec::new();
+    while next_row(ctx, cells.as_mut_ptr()) != 0 {
+        let inner = namespaces
+            .iter()
+            .map(|_| {
+                let mut object = ptr::null_mut();
+                check(unsafe { sys::napi_create_object(env, &mut object) })?;
+                Ok(object)
+            })
+            .collect::<napi::Result<Vec<_>>>()?;
+        for (c, cell) in cells.iter().enumerate() {
+            let object = inner[expanded.column_namespaces[c] as usize];
+            let value = napi_cell(env, cell)?;
+            check(unsafe { sys::napi_set_property(env, object, keys[c], value) })?;
+        }
+        rows.push(napi_object_of(env, &namespaces, &inner)?);
     }
+    napi_array(env, &rows)
 }
 
-/// Create a JS array of row objects from streaming Rows using V8 callback API.
-/// Zero-copy: C++ calls back into Rust per row, reading directly from current_row().
-/// No Vec<Vec<Value>> collection, no Value cloning.
-pub(crate) fn v8_streaming_rows_to_array(mut rows: stoolap::Rows) -> sys::napi_value {
-    let columns = rows.columns().to_vec();
-    let col_count = columns.len();
+/// An object from keys and values that are already JS values.
+fn napi_object_of(
+    env: sys::napi_env,
+    keys: &[sys::napi_value],
+    values: &[sys::napi_value],
+) -> napi::Result<sys::napi_value> {
+    let mut object = ptr::null_mut();
+    check(unsafe { sys::napi_create_object(env, &mut object) })?;
+    for (key, value) in keys.iter().zip(values) {
+        check(unsafe { sys::napi_set_property(env, object, *key, *value) })?;
+    }
+    Ok(object)
+}
 
-    let col_ptrs: Vec<*const u8> = columns.iter().map(|c| c.as_ptr()).collect();
-    let col_lens: Vec<i32> = columns.iter().map(|c| c.len() as i32).collect();
+/// Context of `filtered_next_row`: the callback it wraps, and the filter
+/// its cells go through.
+struct FilteredContext<'a> {
+    env: sys::napi_env,
+    filter: &'a Filter,
+    /// The cells the result uses, from the first.
+    col_count: usize,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+    /// What the filter threw, which ends the rows.
+    failed: Option<napi::Error>,
+}
 
-    let mut ctx = StreamContext {
-        rows: &mut rows as *mut _,
-        temp_strings: Vec::new(),
-        col_count,
-    };
+/// Streaming callback that fills a row with the callback it wraps, then
+/// replaces each cell with what the result filter makes of its value.
+extern "C" fn filtered_next_row(ctx: *mut std::ffi::c_void, cells: *mut CellData) -> i32 {
+    let ctx = unsafe { &mut *(ctx as *mut FilteredContext) };
+    if (ctx.next_row)(ctx.ctx, cells) == 0 {
+        return 0;
+    }
+    let cells = unsafe { std::slice::from_raw_parts_mut(cells, ctx.col_count) };
+    for (i, cell) in cells.iter_mut().enumerate() {
+        let filtered =
+            napi_cell(ctx.env, cell).and_then(|value| ctx.filter.apply(ctx.env, i, value));
+        match filtered {
+            Ok(value) => *cell = js_cell(value),
+            Err(e) => {
+                ctx.failed = Some(e);
+                return 0;
+            }
+        }
+    }
+    1
+}
 
-    unsafe {
-        v8_create_rows_streaming(
-            col_count as i32,
-            col_ptrs.as_ptr(),
-            col_lens.as_ptr(),
-            stream_next_row,
-            &mut ctx as *mut StreamContext as *mut std::ffi::c_void,
-        )
+fn js_cell(value: sys::napi_value) -> CellData {
+    CellData {
+        tag: TAG_JS_VALUE,
+        int_val: value as i64,
+        float_val: 0.0,
+        str_ptr: ptr::null(),
+        str_len: 0,
     }
 }
 
-/// Create a single JS object or null from streaming Rows using V8 bulk API.
-pub(crate) fn v8_single_row_or_null(mut rows: stoolap::Rows) -> sys::napi_value {
-    if !rows.advance() {
-        return unsafe { v8_create_null() };
+/// Make a result with `create` from the rows of `next_row`, through the
+/// result filter when there is one. `keys` are the result's column keys, of
+/// which the result uses the first `col_count` cells.
+fn filtered(
+    env: sys::napi_env,
+    keys: &[String],
+    col_count: usize,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+    create: impl FnOnce(RowCallback, *mut std::ffi::c_void) -> napi::Result<sys::napi_value>,
+) -> napi::Result<sys::napi_value> {
+    let Some(filter) = Filter::active(env, keys)? else {
+        return create(next_row, ctx);
+    };
+    let mut filtered = FilteredContext {
+        env,
+        filter: &filter,
+        col_count,
+        next_row,
+        ctx,
+        failed: None,
+    };
+    let result = create(
+        filtered_next_row,
+        &mut filtered as *mut FilteredContext as *mut std::ffi::c_void,
+    )?;
+    match filtered.failed {
+        Some(e) => Err(e),
+        None => Ok(result),
     }
+}
 
-    let columns = rows.columns().to_vec();
-    let col_count = columns.len();
-    let values = rows.current_row().as_slice();
+/// Rows as an array of objects (`raw: false`) or `{ columns, rows }`, with
+/// whichever path the runtime supports. `low_memory` is the `lowMemory`
+/// query option.
+fn create_streaming(
+    env: sys::napi_env,
+    columns: &[String],
+    raw: bool,
+    low_memory: bool,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    create_streaming_keyed(env, columns, raw, low_memory, ptr::null(), next_row, ctx)
+}
 
-    let col_ptrs: Vec<*const u8> = columns.iter().map(|c| c.as_ptr()).collect();
-    let col_lens: Vec<i32> = columns.iter().map(|c| c.len() as i32).collect();
+/// `create_streaming` with the column names as `ColumnKeys` have them
+/// cached, unless `keys` is null.
+fn create_streaming_keyed(
+    env: sys::napi_env,
+    columns: &[String],
+    raw: bool,
+    low_memory: bool,
+    keys: *const std::ffi::c_void,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    let result = filtered(
+        env,
+        columns,
+        columns.len(),
+        next_row,
+        ctx,
+        |next_row, ctx| {
+            if !runtime::v8_helpers() {
+                return napi_create_streaming(env, columns, raw, low_memory, next_row, ctx);
+            }
+            let col_ptrs: Vec<*const u8> = columns.iter().map(|c| c.as_ptr()).collect();
+            let col_lens: Vec<i32> = columns.iter().map(|c| c.len() as i32).collect();
+            let create = if raw {
+                v8_create_raw_streaming
+            } else {
+                v8_create_rows_streaming
+            };
+            Ok(unsafe {
+                create(
+                    columns.len() as i32,
+                    col_ptrs.as_ptr(),
+                    col_lens.as_ptr(),
+                    next_row,
+                    ctx,
+                    low_memory as i32,
+                    keys,
+                )
+            })
+        },
+    )?;
+    let form = if raw {
+        Form::Raw(columns)
+    } else {
+        Form::Objects {
+            keys: columns,
+            many: true,
+        }
+    };
+    types::revive(env, result, form)?;
+    Ok(result)
+}
 
-    let mut temp_strings: Vec<String> = Vec::new();
-    let cells: Vec<CellData> = values
-        .iter()
-        .map(|v| value_to_cell(v, &mut temp_strings))
-        .collect();
+/// Rows of a prepared statement as its `pluck()` or `expand()` mode shapes
+/// them, with whichever path the runtime supports.
+fn create_shaped(
+    env: sys::napi_env,
+    columns: &[String],
+    shape: &RowShape,
+    names: ColumnNames,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    match shape {
+        RowShape::Object => {
+            create_streaming(env, &names.keys(columns)?, false, false, next_row, ctx)
+        }
+        RowShape::Pluck => {
+            let plucked = names.columns(columns);
+            let keys = &plucked[..plucked.len().min(1)];
+            let result = filtered(env, keys, keys.len(), next_row, ctx, |next_row, ctx| {
+                if !runtime::v8_helpers() {
+                    return napi_create_pluck(env, columns.len(), next_row, ctx);
+                }
+                Ok(unsafe { v8_create_pluck_streaming(columns.len() as i32, next_row, ctx) })
+            })?;
+            if let Some(name) = plucked.first() {
+                types::revive(env, result, Form::Pluck(name))?;
+            }
+            Ok(result)
+        }
+        RowShape::Expand(layout) => {
+            let mut expanded = expand_columns(columns, layout.as_deref().map(Vec::as_slice));
+            names.expanded(&mut expanded)?;
+            let keys = &expanded.keys;
+            let result = filtered(env, keys, keys.len(), next_row, ctx, |next_row, ctx| {
+                if !runtime::v8_helpers() {
+                    return napi_create_expanded(env, &expanded, next_row, ctx);
+                }
+                Ok(v8_expanded(&expanded, columns.len(), next_row, ctx))
+            })?;
+            types::revive(env, result, Form::Expanded(&expanded))?;
+            Ok(result)
+        }
+    }
+}
 
+/// `v8_create_expanded_streaming` for an expanded layout.
+fn v8_expanded(
+    expanded: &Expanded,
+    col_count: usize,
+    next_row: RowCallback,
+    ctx: *mut std::ffi::c_void,
+) -> sys::napi_value {
+    let key_ptrs: Vec<*const u8> = expanded.keys.iter().map(|k| k.as_ptr()).collect();
+    let key_lens: Vec<i32> = expanded.keys.iter().map(|k| k.len() as i32).collect();
+    let ns_ptrs: Vec<*const u8> = expanded.namespaces.iter().map(|n| n.as_ptr()).collect();
+    let ns_lens: Vec<i32> = expanded.namespaces.iter().map(|n| n.len() as i32).collect();
     unsafe {
-        v8_create_single_object(
+        v8_create_expanded_streaming(
             col_count as i32,
-            col_ptrs.as_ptr(),
-            col_lens.as_ptr(),
-            cells.as_ptr(),
+            key_ptrs.as_ptr(),
+            key_lens.as_ptr(),
+            expanded.namespaces.len() as i32,
+            ns_ptrs.as_ptr(),
+            ns_lens.as_ptr(),
+            expanded.column_namespaces.as_ptr(),
+            next_row,
+            ctx,
         )
     }
 }
 
-/// Create a raw-format JS object { columns: string[], rows: any[][] } from streaming Rows.
-/// Zero-copy sync path using V8 callback API.
-pub(crate) fn v8_streaming_rows_to_raw(mut rows: stoolap::Rows) -> sys::napi_value {
-    let columns = rows.columns().to_vec();
-    let col_count = columns.len();
-
-    let col_ptrs: Vec<*const u8> = columns.iter().map(|c| c.as_ptr()).collect();
-    let col_lens: Vec<i32> = columns.iter().map(|c| c.len() as i32).collect();
+/// One row object from column names and cells.
+fn create_object(
+    env: sys::napi_env,
+    columns: &[String],
+    cells: &[CellData],
+) -> napi::Result<sys::napi_value> {
+    create_object_keyed(env, columns, cells, ptr::null())
+}
 
-    let mut ctx = StreamContext {
-        rows: &mut rows as *mut _,
-        temp_strings: Vec::new(),
-        col_count,
+/// `create_object` with the column names of `ColumnKeys`, unless `keys` is
+/// null.
+fn create_object_keyed(
+    env: sys::napi_env,
+    columns: &[String],
+    cells: &[CellData],
+    keys: *const std::ffi::c_void,
+) -> napi::Result<sys::napi_value> {
+    let mut filtered_cells = Vec::new();
+    if let Some(filter) = Filter::active(env, columns)? {
+        for (i, cell) in cells.iter().enumerate() {
+            let value = filter.apply(env, i, napi_cell(env, cell)?)?;
+            filtered_cells.push(js_cell(value));
+        }
+    }
+    let cells = if filtered_cells.is_empty() {
+        cells
+    } else {
+        &filtered_cells
+    };
+    let object = if !runtime::v8_helpers() {
+        let keys = columns
+            .iter()
+            .map(|c| napi_string(env, c))
+            .collect::<napi::Result<Vec<_>>>()?;
+        napi_object(env, &keys, cells)?
+    } else {
+        let col_ptrs: Vec<*const u8> = columns.iter().map(|c| c.as_ptr()).collect();
+        let col_lens: Vec<i32> = columns.iter().map(|c| c.len() as i32).collect();
+        unsafe {
+            v8_create_single_object(
+                columns.len() as i32,
+                col_ptrs.as_ptr(),
+                col_lens.as_ptr(),
+                cells.as_ptr(),
+                keys,
+            )
+        }
     };
+    types::revive(
+        env,
+        object,
+        Form::Objects {
+            keys: columns,
+            many: false,
+        },
+    )?;
+    Ok(object)
+}
 
-    unsafe {
-        v8_create_raw_streaming(
-            col_count as i32,
-            col_ptrs.as_ptr(),
-            col_lens.as_ptr(),
-            stream_next_row,
-            &mut ctx as *mut StreamContext as *mut std::ffi::c_void,
-        )
+fn create_null(env: sys::napi_env) -> napi::Result<sys::napi_value> {
+    if runtime::v8_helpers() {
+        return Ok(unsafe { v8_create_null() });
     }
+    let mut value = ptr::null_mut();
+    check(unsafe { sys::napi_get_null(env, &mut value) })?;
+    Ok(value)
 }
 
-/// Convert collected rows to a raw-format JS object using V8 streaming callback.
-/// Used by async QueryRawTask resolve path.
-fn collected_rows_to_v8_raw(data: &CollectedRows) -> sys::napi_value {
-    let col_count = data.columns.len();
+/// Column names of a prepared statement's last sync call, kept as persistent
+/// V8 strings (with `Object.prototype`) so the next call with the same
+/// names needn't create and hash them again. Made again when the columns,
+/// their case or their duplicates change; with the N-API path there is
+/// nothing to keep.
+pub struct ColumnKeys {
+    /// The names as rendered.
+    names: Vec<String>,
+    handle: *mut std::ffi::c_void,
+}
 
-    let col_ptrs: Vec<*const u8> = data.columns.iter().map(|c| c.as_ptr()).collect();
-    let col_lens: Vec<i32> = data.columns.iter().map(|c| c.len() as i32).collect();
+impl Default for ColumnKeys {
+    fn default() -> Self {
+        Self {
+            names: Vec::new(),
+            handle: ptr::null_mut(),
+        }
+    }
+}
 
-    let mut ctx = CollectedStreamContext {
-        data,
-        row_idx: 0,
+impl ColumnKeys {
+    /// The names of `columns` for a raw result's `columns` or for row
+    /// objects, and their V8 strings, null when the runtime has no V8
+    /// helpers. Must be called on the JS thread.
+    fn get(
+        &mut self,
+        columns: &[String],
+        names: ColumnNames,
+        raw: bool,
+    ) -> napi::Result<(&[String], *const std::ffi::c_void)> {
+        let wanted = names.render(columns, raw)?;
+        if self.names[..] != wanted[..] || self.names.is_empty() {
+            self.free();
+            self.names = wanted.into_owned();
+        }
+        if self.handle.is_null() && runtime::v8_helpers() && !self.names.is_empty() {
+            let ptrs: Vec<*const u8> = self.names.iter().map(|c| c.as_ptr()).collect();
+            let lens: Vec<i32> = self.names.iter().map(|c| c.len() as i32).collect();
+            self.handle =
+                unsafe { v8_column_keys_new(ptrs.len() as i32, ptrs.as_ptr(), lens.as_ptr()) };
+        }
+        Ok((&self.names, self.handle))
+    }
+
+    fn free(&mut self) {
+        if !self.handle.is_null() {
+            unsafe { v8_column_keys_free(self.handle) };
+            self.handle = ptr::null_mut();
+        }
+    }
+}
+
+impl Drop for ColumnKeys {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+/// Collected rows for async path — transfer from compute() to resolve().
+pub struct CollectedRows {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    /// Held against the memory budget until the rows are dropped.
+    charge: Charge,
+    /// Free each row as soon as it has been converted (`lowMemory`).
+    low_memory: bool,
+    /// How the column names become keys when the rows are converted.
+    names: ColumnNames,
+    /// `maxRows` cut the result short (`onMaxRows: 'truncate'`).
+    truncated: bool,
+    /// The columns of the statement's result these are, when the `columns`
+    /// option picked them.
+    pick: Option<Vec<usize>>,
+}
+
+impl CollectedRows {
+    /// Another copy of the rows, held against `memory`.
+    pub(crate) fn copy(&self, memory: &MemoryBudgetRef) -> napi::Result<Self> {
+        let mut charge = memory.charge();
+        for row in &self.rows {
+            charge.add_row(row)?;
+        }
+        Ok(Self {
+            columns: self.columns.clone(),
+            rows: self.rows.clone(),
+            charge,
+            low_memory: self.low_memory,
+            names: self.names,
+            truncated: self.truncated,
+            pick: self.pick.clone(),
+        })
+    }
+
+    /// `rows` read elsewhere, held against `charge`.
+    pub(crate) fn new(columns: Vec<String>, rows: Vec<Vec<Value>>, charge: Charge) -> Self {
+        Self {
+            columns,
+            rows,
+            charge,
+            low_memory: false,
+            names: ColumnNames::default(),
+            truncated: false,
+            pick: None,
+        }
+    }
+
+    pub(crate) fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Estimated size of the rows, as charged to the memory budget.
+    pub(crate) fn bytes(&self) -> u64 {
+        self.charge.bytes()
+    }
+
+    /// Apply the `lowMemory` query option: spare capacity is freed now, and
+    /// rows are freed one by one as they are converted.
+    pub(crate) fn low_memory(mut self, low_memory: bool) -> Self {
+        if low_memory {
+            self.rows.shrink_to_fit();
+            self.low_memory = true;
+        }
+        self
+    }
+
+    /// Render the column names with `names` when the rows are converted.
+    pub(crate) fn column_names(mut self, names: ColumnNames) -> Self {
+        self.names = names;
+        self
+    }
+
+    pub(crate) fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub(crate) fn pick(&self) -> Option<&[usize]> {
+        self.pick.as_deref()
+    }
+
+    /// The keys of the row objects the rows become.
+    pub(crate) fn keys(&self) -> napi::Result<Cow<'_, [String]>> {
+        self.names.render(&self.columns, false)
+    }
+
+    pub(crate) fn rows(&self) -> &[Vec<Value>] {
+        &self.rows
+    }
+
+    /// The column names, and the rows to change in place.
+    pub(crate) fn parts_mut(&mut self) -> (&[String], &mut [Vec<Value>]) {
+        (&self.columns, &mut self.rows)
+    }
+}
+
+/// Context for streaming over already-collected rows (async resolve path).
+struct CollectedStreamContext<'a> {
+    data: &'a CollectedRows,
+    row_idx: usize,
+    temp_strings: Vec<String>,
+}
+
+/// Streaming callback for collected rows: iterates over Vec<Vec<Value>> row by row.
+/// Avoids allocating a flat Vec<CellData> for all rows — reuses C++ per-row buffer.
+extern "C" fn collected_next_row(ctx: *mut std::ffi::c_void, cells: *mut CellData) -> i32 {
+    let ctx = unsafe { &mut *(ctx as *mut CollectedStreamContext) };
+    if ctx.row_idx >= ctx.data.rows.len() {
+        return 0;
+    }
+    ctx.temp_strings.clear();
+    let row = &ctx.data.rows[ctx.row_idx];
+    let col_count = ctx.data.columns.len();
+    unsafe { fill_cells(row, col_count, cells, &mut ctx.temp_strings) };
+    ctx.row_idx += 1;
+    1
+}
+
+/// Context for streaming over collected rows in low-memory mode. It owns the
+/// rows and frees each one, releasing it from the memory budget, once the
+/// next is asked for.
+struct ReleasingStreamContext {
+    rows: std::vec::IntoIter<Vec<Value>>,
+    /// The row whose cells were handed out last.
+    current: Vec<Value>,
+    col_count: usize,
+    charge: Charge,
+    temp_strings: Vec<String>,
+}
+
+/// Streaming callback for low-memory mode: frees the previous row, then
+/// fills the cells from the next.
+extern "C" fn releasing_next_row(ctx: *mut std::ffi::c_void, cells: *mut CellData) -> i32 {
+    let ctx = unsafe { &mut *(ctx as *mut ReleasingStreamContext) };
+    ctx.temp_strings.clear();
+    let done = std::mem::take(&mut ctx.current);
+    if !done.is_empty() {
+        ctx.charge.release_row(&done);
+    }
+    drop(done);
+    let Some(row) = ctx.rows.next() else {
+        return 0;
+    };
+    unsafe { fill_cells(&row, ctx.col_count, cells, &mut ctx.temp_strings) };
+    ctx.current = row;
+    1
+}
+
+/// Convert a query's collected rows to a JS array (`raw: false`) or
+/// `{ columns, rows }`, freeing them as they go in low-memory mode.
+pub(crate) fn collected_rows_to_js(
+    env: sys::napi_env,
+    data: CollectedRows,
+    raw: bool,
+) -> napi::Result<sys::napi_value> {
+    if !data.low_memory {
+        return if raw {
+            collected_rows_to_raw(env, &data)
+        } else {
+            collected_rows_to_array(env, &data)
+        };
+    }
+    let CollectedRows {
+        columns,
+        rows,
+        charge,
+        names,
+        truncated,
+        ..
+    } = data;
+    let mut ctx = ReleasingStreamContext {
+        rows: rows.into_iter(),
+        current: Vec::new(),
+        col_count: columns.len(),
+        charge,
+        temp_strings: Vec::new(),
+    };
+    let value = create_streaming(
+        env,
+        &names.render(&columns, raw)?,
+        raw,
+        true,
+        releasing_next_row,
+        &mut ctx as *mut ReleasingStreamContext as *mut std::ffi::c_void,
+    )?;
+    mark_truncated(env, value, truncated)
+}
+
+/// Set `truncated: true` on a result that `maxRows` cut short.
+fn mark_truncated(
+    env: sys::napi_env,
+    value: sys::napi_value,
+    truncated: bool,
+) -> napi::Result<sys::napi_value> {
+    if truncated {
+        let mut flag = ptr::null_mut();
+        check(unsafe { sys::napi_get_boolean(env, true, &mut flag) })?;
+        check(unsafe { sys::napi_set_named_property(env, value, c"truncated".as_ptr(), flag) })?;
+    }
+    Ok(value)
+}
+
+/// Convert collected rows to a JS array using the streaming callback.
+/// Iterates row-by-row over the collected data — no flat CellData allocation.
+pub(crate) fn collected_rows_to_array(
+    env: sys::napi_env,
+    data: &CollectedRows,
+) -> napi::Result<sys::napi_value> {
+    let mut ctx = CollectedStreamContext {
+        data,
+        row_idx: 0,
+        temp_strings: Vec::new(),
+    };
+    let value = create_streaming(
+        env,
+        &data.names.keys(&data.columns)?,
+        false,
+        false,
+        collected_next_row,
+        &mut ctx as *mut CollectedStreamContext as *mut std::ffi::c_void,
+    )?;
+    mark_truncated(env, value, data.truncated)
+}
+
+/// Create a JS array of row objects from streaming Rows using the callback API.
+/// Zero-copy: each row is read directly from current_row().
+/// No Vec<Vec<Value>> collection, no Value cloning.
+/// Returns the array and its length.
+pub(crate) fn streaming_rows_to_array(
+    env: sys::napi_env,
+    mut rows: stoolap::Rows,
+    low_memory: bool,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    next_rows_to_array(env, &mut rows, low_memory, names, projection, page)
+}
+
+/// `streaming_rows_to_array` that leaves the rest of `rows` to be read
+/// later, for cursors.
+pub(crate) fn next_rows_to_array(
+    env: sys::napi_env,
+    rows: &mut stoolap::Rows,
+    low_memory: bool,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    page.skip(rows, None)?;
+    let (columns, pick) = project(rows.columns(), projection)?;
+    let mut ctx = StreamContext {
+        rows: rows as *mut _,
+        temp_strings: Vec::new(),
+        col_count: columns.len(),
+        pick,
+        remaining: page.limit(),
+        row_count: 0,
+    };
+    let value = create_streaming(
+        env,
+        &names.keys(&columns)?,
+        false,
+        low_memory,
+        stream_next_row,
+        &mut ctx as *mut StreamContext as *mut std::ffi::c_void,
+    )?;
+    let truncated = page.overflow(rows, ctx.row_count)?;
+    Ok((mark_truncated(env, value, truncated)?, ctx.row_count))
+}
+
+/// Create a single JS object or null from streaming Rows.
+/// Returns the value and the number of rows it holds (0 or 1).
+pub(crate) fn single_row_or_null(
+    env: sys::napi_env,
+    mut rows: stoolap::Rows,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    page.skip(&mut rows, None)?;
+    if !rows.advance() {
+        return Ok((create_null(env)?, 0));
+    }
+
+    let (columns, pick) = project(rows.columns(), projection)?;
+    let values = picked_values(rows.current_row().as_slice(), pick.as_deref());
+
+    let mut temp_strings: Vec<String> = Vec::new();
+    let cells = row_cells(&values, columns.len(), &mut temp_strings);
+
+    Ok((create_object(env, &names.keys(&columns)?, &cells)?, 1))
+}
+
+/// Create a raw-format JS object { columns: string[], rows: any[][] } from streaming Rows.
+/// Zero-copy sync path using the callback API.
+/// Returns the object and its row count.
+pub(crate) fn streaming_rows_to_raw(
+    env: sys::napi_env,
+    mut rows: stoolap::Rows,
+    low_memory: bool,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    page.skip(&mut rows, None)?;
+    let (columns, pick) = project(rows.columns(), projection)?;
+    let mut ctx = StreamContext {
+        rows: &mut rows as *mut _,
+        temp_strings: Vec::new(),
+        col_count: columns.len(),
+        pick,
+        remaining: page.limit(),
+        row_count: 0,
+    };
+    let value = create_streaming(
+        env,
+        &names.columns(&columns),
+        true,
+        low_memory,
+        stream_next_row,
+        &mut ctx as *mut StreamContext as *mut std::ffi::c_void,
+    )?;
+    let truncated = page.overflow(&mut rows, ctx.row_count)?;
+    Ok((mark_truncated(env, value, truncated)?, ctx.row_count))
+}
+
+/// `streaming_rows_to_array` (`raw: false`) or `streaming_rows_to_raw` for a
+/// prepared statement's sync calls, with the column names from `keys`.
+pub(crate) fn streaming_rows_keyed(
+    env: sys::napi_env,
+    mut rows: stoolap::Rows,
+    raw: bool,
+    keys: &mut ColumnKeys,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    page.skip(&mut rows, None)?;
+    let (columns, pick) = project(rows.columns(), projection)?;
+    let (columns, handle) = keys.get(&columns, names, raw)?;
+    let mut ctx = StreamContext {
+        rows: &mut rows as *mut _,
+        temp_strings: Vec::new(),
+        col_count: columns.len(),
+        pick,
+        remaining: page.limit(),
+        row_count: 0,
+    };
+    let value = create_streaming_keyed(
+        env,
+        columns,
+        raw,
+        false,
+        handle,
+        stream_next_row,
+        &mut ctx as *mut StreamContext as *mut std::ffi::c_void,
+    )?;
+    let truncated = page.overflow(&mut rows, ctx.row_count)?;
+    Ok((mark_truncated(env, value, truncated)?, ctx.row_count))
+}
+
+/// `single_row_or_null` for a prepared statement's sync calls, with the
+/// column names from `keys`.
+pub(crate) fn single_row_keyed(
+    env: sys::napi_env,
+    mut rows: stoolap::Rows,
+    keys: &mut ColumnKeys,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    page.skip(&mut rows, None)?;
+    if !rows.advance() {
+        return Ok((create_null(env)?, 0));
+    }
+    let (columns, pick) = project(rows.columns(), projection)?;
+    let values = picked_values(rows.current_row().as_slice(), pick.as_deref());
+    let (columns, handle) = keys.get(&columns, names, false)?;
+    let mut temp_strings: Vec<String> = Vec::new();
+    let cells = row_cells(&values, columns.len(), &mut temp_strings);
+    Ok((create_object_keyed(env, columns, &cells, handle)?, 1))
+}
+
+/// Convert collected rows to a raw-format JS object using the streaming callback.
+/// Used by async QueryRawTask resolve path.
+pub(crate) fn collected_rows_to_raw(
+    env: sys::napi_env,
+    data: &CollectedRows,
+) -> napi::Result<sys::napi_value> {
+    let mut ctx = CollectedStreamContext {
+        data,
+        row_idx: 0,
+        temp_strings: Vec::new(),
+    };
+    let value = create_streaming(
+        env,
+        &data.names.columns(&data.columns),
+        true,
+        false,
+        collected_next_row,
+        &mut ctx as *mut CollectedStreamContext as *mut std::ffi::c_void,
+    )?;
+    mark_truncated(env, value, data.truncated)
+}
+
+/// Collect all rows into CollectedRows for async transfer, stopping early if
+/// the statement is interrupted or the rows exceed the memory budget.
+pub(crate) fn collect_all_rows(
+    mut rows: stoolap::Rows,
+    ticket: &Ticket,
+    memory: &MemoryBudgetRef,
+    pick: Option<&[usize]>,
+    page: Page,
+) -> napi::Result<CollectedRows> {
+    collect_next_rows(&mut rows, ticket, memory, pick, page)
+}
+
+/// `collect_all_rows` that leaves the rest of `rows` to be read later, for
+/// cursors.
+pub(crate) fn collect_next_rows(
+    rows: &mut stoolap::Rows,
+    ticket: &Ticket,
+    memory: &MemoryBudgetRef,
+    pick: Option<&[usize]>,
+    page: Page,
+) -> napi::Result<CollectedRows> {
+    let columns = picked_columns(rows.columns(), pick);
+    let mut collected = Vec::new();
+    let mut charge = memory.charge();
+    ticket.check()?;
+    page.skip(rows, Some(ticket))?;
+    while collected.len() < page.limit() && rows.advance() {
+        let row = picked_values(rows.current_row().as_slice(), pick);
+        charge.add_row(&row)?;
+        collected.push(row.into_owned());
+        if collected.len() % CHECK_INTERVAL == 0 {
+            ticket.check()?;
+        }
+    }
+    let truncated = page.overflow(rows, collected.len())?;
+    Ok(CollectedRows {
+        columns,
+        rows: collected,
+        charge,
+        low_memory: false,
+        names: ColumnNames::default(),
+        truncated,
+        pick: pick.map(<[usize]>::to_vec),
+    })
+}
+
+/// Collect the next `size` rows (fewer at the end of the result) into one
+/// chunk, stopping early if the statement is interrupted or the chunk
+/// exceeds the memory budget.
+pub(crate) fn collect_chunk(
+    rows: &mut stoolap::Rows,
+    ticket: &Ticket,
+    memory: &MemoryBudgetRef,
+    size: usize,
+) -> napi::Result<CollectedRows> {
+    let mut collected = Vec::with_capacity(size.min(CHECK_INTERVAL));
+    let mut charge = memory.charge();
+    ticket.check()?;
+    while collected.len() < size && rows.advance() {
+        let row = rows.current_row().as_slice().to_vec();
+        charge.add_row(&row)?;
+        collected.push(row);
+        if collected.len() % CHECK_INTERVAL == 0 {
+            ticket.check()?;
+        }
+    }
+    Ok(CollectedRows {
+        columns: rows.columns().to_vec(),
+        rows: collected,
+        charge,
+        low_memory: false,
+        names: ColumnNames::default(),
+        truncated: false,
+        pick: None,
+    })
+}
+
+/// Collect single row data for async transfer.
+pub(crate) fn collect_single_row_data(
+    mut rows: stoolap::Rows,
+    ticket: &Ticket,
+    memory: &MemoryBudgetRef,
+    pick: Option<&[usize]>,
+    page: Page,
+) -> napi::Result<Option<CollectedRows>> {
+    ticket.check()?;
+    page.skip(&mut rows, Some(ticket))?;
+    if !rows.advance() {
+        return Ok(None);
+    }
+    let columns = picked_columns(rows.columns(), pick);
+    let values = picked_values(rows.current_row().as_slice(), pick);
+    let mut charge = memory.charge();
+    charge.add_row(&values)?;
+    Ok(Some(CollectedRows {
+        columns,
+        rows: vec![values.into_owned()],
+        charge,
+        low_memory: false,
+        names: ColumnNames::default(),
+        truncated: false,
+        pick: pick.map(<[usize]>::to_vec),
+    }))
+}
+
+/// Convert a single CollectedRows (with one row) to a JS object, or null if None.
+/// Shared by QueryOneTask and TxQueryOneTask resolve paths.
+pub(crate) fn collected_single_row_to_js(
+    env: sys::napi_env,
+    data: Option<CollectedRows>,
+    shape: &RowShape,
+) -> napi::Result<sys::napi_value> {
+    match data {
+        Some(data) => shaped_row(env, &data.columns, &data.rows[0], shape, data.names),
+        None => create_null(env),
+    }
+}
+
+/// Context for streaming a single row that is already at hand.
+struct OneRowContext<'a> {
+    row: Option<&'a [Value]>,
+    col_count: usize,
+    temp_strings: Vec<String>,
+}
+
+extern "C" fn one_row_next(ctx: *mut std::ffi::c_void, cells: *mut CellData) -> i32 {
+    let ctx = unsafe { &mut *(ctx as *mut OneRowContext) };
+    let Some(row) = ctx.row.take() else {
+        return 0;
+    };
+    unsafe { fill_cells(row, ctx.col_count, cells, &mut ctx.temp_strings) };
+    1
+}
+
+/// One row as a prepared statement's mode shapes it.
+fn shaped_row(
+    env: sys::napi_env,
+    columns: &[String],
+    values: &[Value],
+    shape: &RowShape,
+    names: ColumnNames,
+) -> napi::Result<sys::napi_value> {
+    if let RowShape::Object = shape {
+        return row_object(env, &names.keys(columns)?, values);
+    }
+    let mut ctx = OneRowContext {
+        row: Some(values),
+        col_count: columns.len(),
+        temp_strings: Vec::new(),
+    };
+    let array = create_shaped(
+        env,
+        columns,
+        shape,
+        names,
+        one_row_next,
+        &mut ctx as *mut OneRowContext as *mut std::ffi::c_void,
+    )?;
+    let mut row = ptr::null_mut();
+    check(unsafe { sys::napi_get_element(env, array, 0, &mut row) })?;
+    Ok(row)
+}
+
+/// Create a JS array of shaped rows from streaming Rows.
+/// Returns the array and its length.
+pub(crate) fn streaming_rows_to_shape(
+    env: sys::napi_env,
+    mut rows: stoolap::Rows,
+    shape: &RowShape,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    page.skip(&mut rows, None)?;
+    let (columns, pick) = project(rows.columns(), projection)?;
+    let shape = shape.picked(pick.as_deref());
+    let mut ctx = StreamContext {
+        rows: &mut rows as *mut _,
+        temp_strings: Vec::new(),
+        col_count: columns.len(),
+        pick,
+        remaining: page.limit(),
+        row_count: 0,
+    };
+    let value = create_shaped(
+        env,
+        &columns,
+        &shape,
+        names,
+        stream_next_row,
+        &mut ctx as *mut StreamContext as *mut std::ffi::c_void,
+    )?;
+    let truncated = page.overflow(&mut rows, ctx.row_count)?;
+    Ok((mark_truncated(env, value, truncated)?, ctx.row_count))
+}
+
+/// Create a single shaped row or null from streaming Rows.
+/// Returns the value and the number of rows it holds (0 or 1).
+pub(crate) fn single_row_shaped(
+    env: sys::napi_env,
+    mut rows: stoolap::Rows,
+    shape: &RowShape,
+    names: ColumnNames,
+    projection: Option<&Projection>,
+    page: Page,
+) -> napi::Result<(sys::napi_value, usize)> {
+    page.skip(&mut rows, None)?;
+    if !rows.advance() {
+        return Ok((create_null(env)?, 0));
+    }
+    let (columns, pick) = project(rows.columns(), projection)?;
+    let values = picked_values(rows.current_row().as_slice(), pick.as_deref());
+    let shape = shape.picked(pick.as_deref());
+    let row = shaped_row(env, &columns, &values, &shape, names)?;
+    Ok((row, 1))
+}
+
+/// Convert collected rows to a JS array of shaped rows.
+pub(crate) fn collected_rows_to_shape(
+    env: sys::napi_env,
+    data: CollectedRows,
+    shape: &RowShape,
+) -> napi::Result<sys::napi_value> {
+    if let RowShape::Object = shape {
+        return collected_rows_to_js(env, data, false);
+    }
+    let mut ctx = CollectedStreamContext {
+        data: &data,
+        row_idx: 0,
         temp_strings: Vec::new(),
     };
+    let value = create_shaped(
+        env,
+        &data.columns,
+        shape,
+        data.names,
+        collected_next_row,
+        &mut ctx as *mut CollectedStreamContext as *mut std::ffi::c_void,
+    )?;
+    mark_truncated(env, value, data.truncated)
+}
+
+/// `shape` for a result with `columns`: one object per table when their
+/// names collide under `duplicateColumns: 'expand'`. The names a call keeps
+/// with the `columns` option can't collide, so a `projected` result keeps
+/// `shape`.
+pub(crate) fn result_shape(
+    shape: &RowShape,
+    names: ColumnNames,
+    db: &Database,
+    plan: Option<&CachedPlanRef>,
+    sql: &str,
+    columns: &[String],
+    projected: bool,
+) -> RowShape {
+    if projected {
+        return shape.clone();
+    }
+    shape.for_columns(names, columns, db, plan.map(|p| &*p.statement), sql)
+}
+
+/// Create one JS row object from column names and values.
+pub(crate) fn row_object(
+    env: sys::napi_env,
+    columns: &[String],
+    values: &[Value],
+) -> napi::Result<sys::napi_value> {
+    let mut temp_strings: Vec<String> = Vec::new();
+    let cells = row_cells(values, columns.len(), &mut temp_strings);
+    create_object(env, columns, &cells)
+}
+
+/// Shared database handle — Arc::clone (not Database::clone) to share executor & cache.
+pub type DbHandle = Arc<Database>;
+
+use crate::advisor::{self, AdvisedIndex};
+use crate::analyze::AutoAnalyzeRef;
+use crate::batch::Batch;
+use crate::bulk::DeleteMany;
+use crate::changes::{ChangeEvent, ChangeHubRef, Executed, PendingChanges};
+use crate::checkpoint::{self, CheckpointMap};
+use crate::chunks::{Chunk, ChunkListener, ChunkStream};
+use crate::closing::{self, Closer};
+use crate::column_names::ColumnNames;
+use crate::column_stats::{self, ColumnStats};
+use crate::consistency::{self, TableVersionsRef};
+use crate::cursor::{empty_array, read_ahead, CursorRowsRef};
+use crate::diff::{self, TableDiff};
+use crate::dump::{self, Dump, Sink};
+use crate::error::{bind_js_thread, from_core, restore, to_napi, with_reason};
+use crate::explain::{self, QueryPlan};
+use crate::interrupt::{InterruptRef, Ticket, CHECK_INTERVAL};
+use crate::memory::{Charge, MemoryBudgetRef};
+use crate::paging::{MaxRows, Page};
+use crate::parallel::Parallelism;
+use crate::plan_cache::PlanCacheRef;
+use crate::policy::{Policies, PolicyRef};
+use crate::preview;
+use crate::profile::ProfilerRef;
+use crate::projection::{pick, picked_columns, picked_values, project, Projection};
+use crate::readers::{Readers, ReadersRef};
+use crate::recording::{self, ReplayReport};
+use crate::result_filter::{self, Filter};
+use crate::retry::{retry_warning, RetryPolicy};
+use crate::runtime;
+use crate::shape::{expand_columns, Expanded, RowShape};
+use crate::sql::{has_returning, is_blank, returns_rows};
+use crate::temporal;
+use crate::tree::{Tree, TreeRows};
+use crate::tx_memo::QueryMemo;
+use crate::tx_sql::SqlLog;
+use crate::tx_timeout::{TxTimeouts, TxTimeoutsRef, Watch};
+use crate::types::{self, Form};
+use crate::usage::StatementUsageRef;
+use crate::vacuum::{self, VacuumProgressSink, VacuumReport};
+use crate::vectors::VectorColumns;
+use crate::wal_shipping::{self, Segment, WalSegment};
+use crate::window::TopN;
+use crate::worker::WorkerRef;
+use crate::write_queue::TxLocksRef;
+
+// ============================================================
+// RawJsValue — newtype for Task::JsValue (heterogeneous JS values)
+// ============================================================
+
+pub struct RawJsValue(pub sys::napi_value);
+
+impl TypeName for RawJsValue {
+    fn type_name() -> &'static str {
+        "Unknown"
+    }
+
+    fn value_type() -> napi::ValueType {
+        napi::ValueType::Unknown
+    }
+}
+
+impl ToNapiValue for RawJsValue {
+    unsafe fn to_napi_value(_env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        Ok(val.0)
+    }
+}
+
+// ============================================================
+// RunResult — { changes, lastInsertRowId, durationMs, warnings } via V8 bulk API
+// ============================================================
+
+/// What a DML call reports in its `RunResult`.
+pub struct RunOutcome {
+    pub changes: i64,
+    /// Integer primary key of the last row an auto-committed INSERT added.
+    pub last_insert_row_id: Option<i64>,
+    pub duration_ms: f64,
+    /// Notes from the binding about how the call ran.
+    pub warnings: Vec<String>,
+    /// Rows an auto-committed statement with RETURNING produced.
+    pub rows: Option<CollectedRows>,
+}
+
+impl RunOutcome {
+    /// Outcome of a call started at `started` that changed `changes` rows.
+    pub fn new(started: Instant, changes: i64) -> Self {
+        Self {
+            changes,
+            last_insert_row_id: None,
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+            warnings: Vec::new(),
+            rows: None,
+        }
+    }
+
+    /// Outcome of an auto-committed statement with RETURNING, retried
+    /// `retries` times.
+    pub fn returned(started: Instant, rows: CollectedRows, retries: u32) -> Self {
+        let mut outcome = Self::new(started, rows.row_count() as i64);
+        outcome.rows = Some(rows);
+        outcome.warnings.extend(retry_warning(retries));
+        outcome
+    }
+
+    /// Outcome of an auto-committed statement, retried `retries` times.
+    pub fn executed(started: Instant, executed: Executed, retries: u32) -> Self {
+        let mut outcome = Self::new(started, executed.changes);
+        outcome.last_insert_row_id = executed.last_insert_row_id;
+        outcome.warnings.extend(retry_warning(retries));
+        outcome
+    }
+}
+
+/// Create the `RunResult` object, with the RETURNING rows when there are any.
+pub(crate) fn run_result(
+    env: sys::napi_env,
+    outcome: &RunOutcome,
+) -> napi::Result<sys::napi_value> {
+    let result = run_result_object(env, outcome)?;
+    if let Some(ref rows) = outcome.rows {
+        let value = collected_rows_to_array(env, rows)?;
+        check(unsafe { sys::napi_set_named_property(env, result, c"rows".as_ptr(), value) })?;
+    }
+    Ok(result)
+}
+
+/// Create the `RunResult` fields using the V8 bulk API (1 call vs ~10 NAPI calls).
+fn run_result_object(env: sys::napi_env, outcome: &RunOutcome) -> napi::Result<sys::napi_value> {
+    let mut temp_strings = Vec::new();
+    let last_id = match outcome.last_insert_row_id {
+        Some(id) => value_to_cell(&Value::Integer(id), &mut temp_strings),
+        None => NULL_CELL,
+    };
+    let warnings: Vec<CellData> = outcome
+        .warnings
+        .iter()
+        .map(|w| CellData {
+            tag: TAG_STRING,
+            str_ptr: w.as_ptr(),
+            str_len: w.len() as i32,
+            ..NULL_CELL
+        })
+        .collect();
+    if runtime::v8_helpers() {
+        return Ok(unsafe {
+            v8_create_run_result(
+                outcome.changes,
+                &last_id,
+                outcome.duration_ms,
+                warnings.as_ptr(),
+                warnings.len() as i32,
+            )
+        });
+    }
+    let mut result = ptr::null_mut();
+    let mut value = ptr::null_mut();
+    check(unsafe { sys::napi_create_object(env, &mut result) })?;
+    check(unsafe { sys::napi_create_int64(env, outcome.changes, &mut value) })?;
+    check(unsafe { sys::napi_set_named_property(env, result, c"changes".as_ptr(), value) })?;
+    let value = napi_cell(env, &last_id)?;
+    check(unsafe {
+        sys::napi_set_named_property(env, result, c"lastInsertRowId".as_ptr(), value)
+    })?;
+    let mut value = ptr::null_mut();
+    check(unsafe { sys::napi_create_double(env, outcome.duration_ms, &mut value) })?;
+    check(unsafe { sys::napi_set_named_property(env, result, c"durationMs".as_ptr(), value) })?;
+    let messages = warnings
+        .iter()
+        .map(|cell| napi_cell(env, cell))
+        .collect::<napi::Result<Vec<_>>>()?;
+    let value = napi_array(env, &messages)?;
+    check(unsafe { sys::napi_set_named_property(env, result, c"warnings".as_ptr(), value) })?;
+    Ok(result)
+}
+
+// ============================================================
+// Raw NAPI helpers — zero overhead
+// ============================================================
+
+#[inline(always)]
+pub(crate) fn check(status: sys::napi_status) -> napi::Result<()> {
+    if status == sys::Status::napi_ok {
+        Ok(())
+    } else {
+        Err(napi::Error::new(
+            napi::Status::GenericFailure,
+            format!("napi call failed: {status}"),
+        ))
+    }
+}
+
+// ============================================================
+// Execute parameters enum
+// ============================================================
+
+pub use stoolap_bindings_core::params::Params as TaskParams;
+
+// ============================================================
+// OpenTask — Database.open()
+// ============================================================
+
+pub struct OpenTask {
+    pub dsn: String,
+    pub retry: RetryPolicy,
+    pub analyze: Option<AutoAnalyzeRef>,
+    pub dedicated_thread: bool,
+    pub readers: u32,
+    pub serialize_writes: Vec<String>,
+    pub memory_budget: Option<u64>,
+    pub idle_transaction: Option<Duration>,
+    pub async_stack_traces: bool,
+    pub column_names: ColumnNames,
+    pub temporal: bool,
+    pub timestamps: Option<TimestampFormat>,
+    pub max_rows: Option<MaxRows>,
+    pub commit_window: Option<Duration>,
+    pub parallelism: Option<Parallelism>,
+    pub consistency_tokens: bool,
+    pub strict_statements: bool,
+}
+
+impl Task for OpenTask {
+    type Output = Database;
+    type JsValue = crate::database::JsDatabase;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        // Nothing can interrupt an open; the ticket only satisfies the retry loop.
+        let ticket = InterruptRef::default().ticket();
+        self.retry
+            .run(&ticket, || Database::open(&self.dsn).map_err(to_napi))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        bind_js_thread(&env);
+        let worker = if self.dedicated_thread {
+            WorkerRef::dedicated()?
+        } else {
+            WorkerRef::default()
+        };
+        let worker = worker
+            .serialize_writes(std::mem::take(&mut self.serialize_writes))
+            .capture_call_sites(self.async_stack_traces)
+            .column_names(self.column_names)
+            .limit_rows(self.max_rows)
+            .commit_window(self.commit_window)
+            .parallelism(self.parallelism);
+        worker.memory().set_limit(self.memory_budget);
+        worker.types().set_temporal(self.temporal);
+        if let Some(format) = self.timestamps {
+            worker.types().set_timestamps(format);
+        }
+        let readers = Readers::new(&output, self.readers);
+        Ok(crate::database::JsDatabase::from_db(
+            output,
+            self.retry,
+            self.analyze.take(),
+            self.consistency_tokens.then(TableVersionsRef::default),
+            worker,
+            readers,
+            Arc::new(TxTimeouts::new(self.idle_transaction)),
+            Policies::new(self.strict_statements),
+        ))
+    }
+
+    fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+        Err(restore(&env, err))
+    }
+}
+
+// ============================================================
+
+// ExecTask — db.execute(sql, params)
+// ============================================================
+
+pub struct ExecTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub params: TaskParams,
+    pub plan: Option<CachedPlanRef>,
+    pub changes: ChangeHubRef,
+    pub profiler: ProfilerRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+}
+
+impl Task for ExecTask {
+    type Output = RunOutcome;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
+        let timer = Instant::now();
+        let started = self.profiler.start_with(&params);
+        if returning(self.plan.as_ref(), &self.sql) {
+            let (rows, retries) = self.retry.run_counted(&self.ticket, params, |params| {
+                self.readers.write(|| {
+                    self.changes.execute_returning(
+                        params,
+                        &self.sql,
+                        self.plan.as_ref(),
+                        &self.ticket,
+                        &self.memory,
+                    )
+                })
+            })?;
+            let outcome = RunOutcome::returned(timer, rows.column_names(self.names), retries);
+            self.profiler
+                .finish_write(started, &self.sql, outcome.changes, self.plan.as_ref());
+            return Ok(outcome);
+        }
+        let (executed, retries) = self.retry.run_counted(&self.ticket, params, |params| {
+            self.readers.write(|| {
+                self.changes
+                    .execute_on_db(params, &self.sql, self.plan.as_ref())
+            })
+        })?;
+        self.profiler
+            .finish_write(started, &self.sql, executed.changes, self.plan.as_ref());
+        Ok(RunOutcome::executed(timer, executed, retries))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(run_result(env.raw(), &output)?))
+    }
+}
+
+// ============================================================
+// BatchExecTask — db.exec(sql)
+// ============================================================
+
+pub struct BatchExecTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub changes: ChangeHubRef,
+    pub profiler: ProfilerRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+}
+
+impl Task for BatchExecTask {
+    type Output = Vec<StatementResult>;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        run_script(&self.sql, &self.profiler, &self.ticket, |sql| {
+            self.retry
+                .run(&self.ticket, || {
+                    self.readers
+                        .write(|| self.changes.exec(sql, &self.ticket, &self.memory))
+                })
+                .map(|output| output.column_names(self.names))
+        })
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(statement_results(env.raw(), &output)?))
+    }
+}
+
+/// What one statement of an `exec()` script produced.
+pub enum ExecOutput {
+    Changes(i64),
+    Rows(CollectedRows),
+}
+
+impl ExecOutput {
+    /// Render the column names of returned rows with `names`.
+    pub(crate) fn column_names(self, names: ColumnNames) -> Self {
+        match self {
+            ExecOutput::Rows(rows) => ExecOutput::Rows(rows.column_names(names)),
+            changes => changes,
+        }
+    }
+
+    /// Rows changed or returned, as reported to the profiler.
+    fn count(&self) -> i64 {
+        match self {
+            ExecOutput::Changes(changes) => *changes,
+            ExecOutput::Rows(rows) => rows.row_count() as i64,
+        }
+    }
+}
+
+/// One statement of an `exec()` script and what it produced.
+pub struct StatementResult {
+    statement: String,
+    output: ExecOutput,
+}
+
+/// Split `sql` into statements and run them in order with `run`, profiling
+/// each one. The first failure stops the script; earlier statements stay
+/// committed.
+pub(crate) fn run_script(
+    sql: &str,
+    profiler: &ProfilerRef,
+    ticket: &Ticket,
+    mut run: impl FnMut(&str) -> napi::Result<ExecOutput>,
+) -> napi::Result<Vec<StatementResult>> {
+    let mut results = Vec::new();
+    for stmt in split_sql_statements(sql) {
+        let trimmed = stmt.trim();
+        if is_blank(trimmed) {
+            continue;
+        }
+        ticket.check()?;
+        let started = profiler.start();
+        let output = run(trimmed)?;
+        match output {
+            ExecOutput::Changes(changes) => profiler.finish_write(started, trimmed, changes, None),
+            ExecOutput::Rows(_) => profiler.finish(started, trimmed, output.count(), None),
+        }
+        results.push(StatementResult {
+            statement: trimmed.to_string(),
+            output,
+        });
+    }
+    Ok(results)
+}
+
+/// Build `[{ statement, changes } | { statement, columns, rows }]`.
+pub(crate) fn statement_results(
+    env: sys::napi_env,
+    results: &[StatementResult],
+) -> napi::Result<sys::napi_value> {
+    let values = results
+        .iter()
+        .map(|result| {
+            let mut object = ptr::null_mut();
+            check(unsafe { sys::napi_create_object(env, &mut object) })?;
+            let value = napi_string(env, &result.statement)?;
+            check(unsafe {
+                sys::napi_set_named_property(env, object, c"statement".as_ptr(), value)
+            })?;
+            match result.output {
+                ExecOutput::Changes(changes) => {
+                    let mut value = ptr::null_mut();
+                    check(unsafe { sys::napi_create_int64(env, changes, &mut value) })?;
+                    check(unsafe {
+                        sys::napi_set_named_property(env, object, c"changes".as_ptr(), value)
+                    })?;
+                }
+                ExecOutput::Rows(ref rows) => {
+                    let raw = collected_rows_to_raw(env, rows)?;
+                    for name in [c"columns", c"rows"] {
+                        let mut value = ptr::null_mut();
+                        check(unsafe {
+                            sys::napi_get_named_property(env, raw, name.as_ptr(), &mut value)
+                        })?;
+                        check(unsafe {
+                            sys::napi_set_named_property(env, object, name.as_ptr(), value)
+                        })?;
+                    }
+                }
+            }
+            Ok(object)
+        })
+        .collect::<napi::Result<Vec<_>>>()?;
+    napi_array(env, &values)
+}
+
+// ============================================================
+// QueryTask — db.query(sql, params) -> array of objects
+// ============================================================
+
+pub struct QueryTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub params: TaskParams,
+    pub plan: Option<CachedPlanRef>,
+    pub profiler: ProfilerRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub low_memory: bool,
+    pub shape: RowShape,
+    pub names: ColumnNames,
+    /// The `columns` query option.
+    pub projection: Option<Projection>,
+    /// The `offset` and `limit` query options.
+    pub page: Page,
+    /// Set as the result's `consistencyToken`.
+    pub token: Option<String>,
+}
+
+impl QueryTask {
+    /// The shape of a result with `columns`, of which the call keeps `pick`.
+    fn result_shape(&self, db: &Database, columns: &[String], pick: Option<&[usize]>) -> RowShape {
+        let plan = self.plan.as_ref();
+        result_shape(
+            &self.shape,
+            self.names,
+            db,
+            plan,
+            &self.sql,
+            columns,
+            pick.is_some(),
+        )
+        .picked(pick)
+    }
+}
+
+impl Task for QueryTask {
+    type Output = CollectedRows;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
+        let started = self.profiler.start_with(&params);
+        let (output, shape) =
+            self.readers
+                .query(&self.db, &self.sql, self.plan.as_ref(), |db| {
+                    let rows = self.retry.run_with(&self.ticket, params, |params| {
+                        if let Some(ref plan) = self.plan {
+                            params.query_plan_on_db(db, plan).map_err(from_core)
+                        } else {
+                            params.query_on_db(db, &self.sql).map_err(from_core)
+                        }
+                    })?;
+                    let pick = pick(rows.columns(), self.projection.as_ref())?;
+                    let shape = self.result_shape(db, rows.columns(), pick.as_deref());
+                    let output = collect_all_rows(
+                        rows,
+                        &self.ticket,
+                        &self.memory,
+                        pick.as_deref(),
+                        self.page,
+                    )?;
+                    Ok((output, shape))
+                })?;
+        self.shape = shape;
+        let output = output.low_memory(self.low_memory).column_names(self.names);
+        self.profiler.finish(
+            started,
+            &self.sql,
+            output.rows.len() as i64,
+            self.plan.as_ref(),
+        );
+        Ok(output)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let _statement = result_filter::statement(&self.db, &self.sql, output.pick());
+        let value = collected_rows_to_shape(env.raw(), output, &self.shape)?;
+        if let Some(ref token) = self.token {
+            consistency::attach(env.raw(), value, token)?;
+        }
+        Ok(RawJsValue(value))
+    }
+}
+
+// ============================================================
+// QueryRawTask — db.queryRaw(sql, params) -> { columns, rows }
+// ============================================================
+
+pub struct QueryRawTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub params: TaskParams,
+    pub plan: Option<CachedPlanRef>,
+    pub profiler: ProfilerRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub low_memory: bool,
+    pub names: ColumnNames,
+    /// The `columns` query option.
+    pub projection: Option<Projection>,
+    /// The `offset` and `limit` query options.
+    pub page: Page,
+    /// The `preview` query option.
+    pub preview: Option<usize>,
+    /// The `vectors` query option.
+    pub vectors: Option<VectorColumns>,
+    /// Set as the result's `consistencyToken`.
+    pub token: Option<String>,
+}
+
+impl Task for QueryRawTask {
+    type Output = CollectedRows;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
+        let started = self.profiler.start_with(&params);
+        let mut output = self
+            .readers
+            .query(&self.db, &self.sql, self.plan.as_ref(), |db| {
+                let rows = self.retry.run_with(&self.ticket, params, |params| {
+                    if let Some(ref plan) = self.plan {
+                        params.query_plan_on_db(db, plan).map_err(from_core)
+                    } else {
+                        params.query_on_db(db, &self.sql).map_err(from_core)
+                    }
+                })?;
+                let pick = pick(rows.columns(), self.projection.as_ref())?;
+                collect_all_rows(rows, &self.ticket, &self.memory, pick.as_deref(), self.page)
+            })?
+            .low_memory(self.low_memory)
+            .column_names(self.names);
+        self.profiler.finish(
+            started,
+            &self.sql,
+            output.rows.len() as i64,
+            self.plan.as_ref(),
+        );
+        if let Some(ref mut vectors) = self.vectors {
+            vectors.pack(&mut output)?;
+        }
+        Ok(output)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let _statement = result_filter::statement(&self.db, &self.sql, output.pick());
+        let columns = self.vectors.is_some().then(|| output.columns.clone());
+        let value = collected_rows_to_js(env.raw(), output, true)?;
+        if let (Some(vectors), Some(columns)) = (self.vectors.take(), columns) {
+            vectors.attach(env.raw(), value, &columns, self.names)?;
+        }
+        if let Some(count) = self.preview {
+            preview::attach(env.raw(), value, count, self.names)?;
+        }
+        if let Some(ref token) = self.token {
+            consistency::attach(env.raw(), value, token)?;
+        }
+        Ok(RawJsValue(value))
+    }
+}
+
+// ============================================================
+// QueryOneTask — db.queryOne(sql, params) -> object | null
+// ============================================================
+
+pub struct QueryOneTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub params: TaskParams,
+    pub plan: Option<CachedPlanRef>,
+    pub profiler: ProfilerRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub shape: RowShape,
+    pub names: ColumnNames,
+    /// The `columns` query option.
+    pub projection: Option<Projection>,
+    /// The `offset` and `limit` query options.
+    pub page: Page,
+}
+
+impl QueryOneTask {
+    /// The shape of a result with `columns`, of which the call keeps `pick`.
+    fn result_shape(&self, db: &Database, columns: &[String], pick: Option<&[usize]>) -> RowShape {
+        let plan = self.plan.as_ref();
+        result_shape(
+            &self.shape,
+            self.names,
+            db,
+            plan,
+            &self.sql,
+            columns,
+            pick.is_some(),
+        )
+        .picked(pick)
+    }
+}
+
+impl Task for QueryOneTask {
+    type Output = Option<CollectedRows>;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
+        let started = self.profiler.start_with(&params);
+        let (output, shape) =
+            self.readers
+                .query(&self.db, &self.sql, self.plan.as_ref(), |db| {
+                    let rows = self.retry.run_with(&self.ticket, params, |params| {
+                        if let Some(ref plan) = self.plan {
+                            params.query_plan_on_db(db, plan).map_err(from_core)
+                        } else {
+                            params.query_on_db(db, &self.sql).map_err(from_core)
+                        }
+                    })?;
+                    let pick = pick(rows.columns(), self.projection.as_ref())?;
+                    let shape = self.result_shape(db, rows.columns(), pick.as_deref());
+                    let output = collect_single_row_data(
+                        rows,
+                        &self.ticket,
+                        &self.memory,
+                        pick.as_deref(),
+                        self.page,
+                    )?;
+                    Ok((output, shape))
+                })?;
+        self.shape = shape;
+        let output = output.map(|rows| rows.column_names(self.names));
+        self.profiler.finish(
+            started,
+            &self.sql,
+            output.is_some() as i64,
+            self.plan.as_ref(),
+        );
+        Ok(output)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let pick = output.as_ref().and_then(CollectedRows::pick);
+        let _statement = result_filter::statement(&self.db, &self.sql, pick);
+        Ok(RawJsValue(collected_single_row_to_js(
+            env.raw(),
+            output,
+            &self.shape,
+        )?))
+    }
+}
+
+// ============================================================
+// QueryChunksTask — db.queryChunks(sql, params, options, onChunk) -> count
+// ============================================================
+
+pub struct QueryChunksTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub params: TaskParams,
+    pub plan: Option<CachedPlanRef>,
+    pub profiler: ProfilerRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+    pub chunk_size: usize,
+    /// Taken by the stream when the task runs.
+    pub listener: Option<ChunkListener>,
+}
+
+impl Task for QueryChunksTask {
+    type Output = usize;
+    type JsValue = i64;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        let listener = self
+            .listener
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("queryChunks task already ran"))?;
+        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
+        let started = self.profiler.start();
+        let count = self
+            .readers
+            .query(&self.db, &self.sql, self.plan.as_ref(), |db| {
+                let mut rows = self.retry.run_with(&self.ticket, params, |params| {
+                    if let Some(ref plan) = self.plan {
+                        params.query_plan_on_db(db, plan).map_err(from_core)
+                    } else {
+                        params.query_on_db(db, &self.sql).map_err(from_core)
+                    }
+                })?;
+                let shape = result_shape(
+                    &RowShape::default(),
+                    self.names,
+                    db,
+                    self.plan.as_ref(),
+                    &self.sql,
+                    rows.columns(),
+                    false,
+                );
+                let mut stream = ChunkStream::new(listener);
+                let mut count = 0;
+                let streamed = loop {
+                    let chunk =
+                        match collect_chunk(&mut rows, &self.ticket, &self.memory, self.chunk_size)
+                        {
+                            Ok(chunk) => chunk,
+                            Err(e) => break Err(e),
+                        };
+                    let size = chunk.row_count();
+                    if size == 0 {
+                        break Ok(());
+                    }
+                    count += size;
+                    let chunk = Chunk::new(chunk.column_names(self.names), shape.clone());
+                    if let Err(e) = stream.send(chunk) {
+                        break Err(e);
+                    }
+                    if size < self.chunk_size {
+                        break Ok(());
+                    }
+                };
+                let returned = stream.wait();
+                streamed.and(returned).map(|()| count)
+            })?;
+        self.profiler
+            .finish(started, &self.sql, count as i64, self.plan.as_ref());
+        Ok(count)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output as i64)
+    }
+}
+
+// ============================================================
+// DeleteManyTask — db.deleteMany(table, keys, options)
+// ============================================================
+
+pub struct DeleteManyTask {
+    pub db: DbHandle,
+    pub delete: Option<DeleteMany>,
+    pub changes: ChangeHubRef,
+    pub policy: PolicyRef,
+    pub profiler: ProfilerRef,
+    pub plans: PlanCacheRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+}
+
+impl Task for DeleteManyTask {
+    type Output = RunOutcome;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let delete = self
+            .delete
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("deleteMany task already ran"))?;
+        delete.run(
+            &self.db,
+            &self.changes,
+            &self.policy,
+            &self.profiler,
+            &self.plans,
+            &self.readers,
+            &self.retry,
+            &self.ticket,
+        )
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(run_result(env.raw(), &output)?))
+    }
+}
+
+// ============================================================
+// ExecuteBatchTask — db.executeBatch(sql, paramsArray)
+// ============================================================
+
+pub struct ExecuteBatchTask {
+    pub db: DbHandle,
+    pub batch: Option<Batch>,
+    pub changes: ChangeHubRef,
+    pub profiler: ProfilerRef,
+    pub readers: ReadersRef,
+    pub retry: RetryPolicy,
+    pub ticket: Ticket,
+}
+
+impl Task for ExecuteBatchTask {
+    type Output = RunOutcome;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let batch = self
+            .batch
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("executeBatch task already ran"))?;
+        batch.run(
+            &self.db,
+            &self.changes,
+            &self.profiler,
+            &self.readers,
+            &self.retry,
+            &self.ticket,
+        )
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(run_result(env.raw(), &output)?))
+    }
+}
+
+// ============================================================
+// PipelineTask — db.pipeline().run()
+// ============================================================
+
+/// A call queued on a pipeline.
+pub enum PipelineStep {
+    Query(QueryTask),
+    Execute(ExecTask),
+}
+
+pub enum StepOutput {
+    Rows(CollectedRows),
+    Run(RunOutcome),
+}
+
+pub struct PipelineTask {
+    pub steps: Vec<PipelineStep>,
+}
+
+impl Task for PipelineTask {
+    type Output = Vec<StepOutput>;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let mut outputs = Vec::with_capacity(self.steps.len());
+        for (i, step) in self.steps.iter_mut().enumerate() {
+            let output = match step {
+                PipelineStep::Query(task) => task.compute().map(StepOutput::Rows),
+                PipelineStep::Execute(task) => task.compute().map(StepOutput::Run),
+            };
+            outputs.push(output.map_err(|e| {
+                let reason = format!("Pipeline step {}: {}", i + 1, e.reason);
+                with_reason(e, reason)
+            })?);
+        }
+        Ok(outputs)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let env = env.raw();
+        let values = output
+            .into_iter()
+            .map(|output| match output {
+                StepOutput::Rows(rows) => collected_rows_to_js(env, rows, false),
+                StepOutput::Run(run) => run_result(env, &run),
+            })
+            .collect::<napi::Result<Vec<_>>>()?;
+        Ok(RawJsValue(napi_array(env, &values)?))
+    }
+}
+
+// ============================================================
+// TreeTask — db.tree(table, options)
+// ============================================================
+
+pub struct TreeTask {
+    pub db: DbHandle,
+    pub tree: Option<Tree>,
+    pub policy: PolicyRef,
+    pub profiler: ProfilerRef,
+    pub ticket: Ticket,
+    pub names: ColumnNames,
+}
+
+impl Task for TreeTask {
+    type Output = TreeRows;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let tree = self
+            .tree
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("tree task already ran"))?;
+        tree.run(&self.db, &self.policy, &self.profiler, &self.ticket)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(output.to_js(env.raw(), self.names)?))
+    }
+}
+
+// ============================================================
+// ColumnStatsTask — db.columnStats(table, column)
+// ============================================================
+
+pub struct ColumnStatsTask {
+    pub db: DbHandle,
+    pub table: String,
+    pub column: String,
+    pub policy: PolicyRef,
+}
+
+impl Task for ColumnStatsTask {
+    type Output = ColumnStats;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        column_stats::column_stats(&self.db, &self.policy, &self.table, &self.column)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(output.to_js(env.raw())?))
+    }
+}
+
+// ============================================================
+// TopNTask — db.topNPerGroup(table, options)
+// ============================================================
+
+pub struct TopNTask {
+    pub db: DbHandle,
+    pub top: TopN,
+    pub policy: PolicyRef,
+    pub profiler: ProfilerRef,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+}
+
+impl Task for TopNTask {
+    type Output = CollectedRows;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        let (sql, params) = self.top.sql(&self.db, &self.policy)?;
+        let started = self.profiler.start_positional(&params);
+        let rows = self.db.query(&sql, params).map_err(to_napi)?;
+        let output = collect_all_rows(rows, &self.ticket, &self.memory, None, Page::default())?
+            .column_names(self.names);
+        self.profiler
+            .finish(started, &sql, output.rows.len() as i64, None);
+        Ok(output)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(collected_rows_to_array(env.raw(), &output)?))
+    }
+}
+
+// ============================================================
+// ExplainTask — stmt.explain(params) / stmt.explainAnalyze(params)
+// ============================================================
+
+pub struct ExplainTask {
+    pub db: DbHandle,
+    pub params: TaskParams,
+    pub plan: CachedPlanRef,
+    pub analyze: bool,
+    pub ticket: Ticket,
+}
+
+impl Task for ExplainTask {
+    type Output = QueryPlan;
+    type JsValue = QueryPlan;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        explain::explain(&self.db, &self.plan, &self.params, self.analyze)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+// ============================================================
+// PrepareTask — db.prepareAsync(sql)
+// ============================================================
+
+pub struct PrepareTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub changes: ChangeHubRef,
+    pub policy: PolicyRef,
+    pub profiler: ProfilerRef,
+    pub plans: PlanCacheRef,
+    pub interrupts: InterruptRef,
+    pub retry: RetryPolicy,
+    pub worker: WorkerRef,
+    pub readers: ReadersRef,
+    pub usage: StatementUsageRef,
+    pub ticket: Ticket,
+}
 
-    unsafe {
-        v8_create_raw_streaming(
-            col_count as i32,
-            col_ptrs.as_ptr(),
-            col_lens.as_ptr(),
-            collected_next_row,
-            &mut ctx as *mut CollectedStreamContext as *mut std::ffi::c_void,
+impl Task for PrepareTask {
+    type Output = CachedPlanRef;
+    type JsValue = crate::statement::JsPreparedStatement;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        let plan = self.plans.plan(&self.db, &self.sql)?;
+        // Parsing can't be stopped part way; an interrupt during it still
+        // rejects the call.
+        self.ticket.check()?;
+        Ok(plan)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        crate::statement::JsPreparedStatement::new(
+            Arc::clone(&self.db),
+            std::mem::take(&mut self.sql),
+            output,
+            Arc::clone(&self.changes),
+            Arc::clone(&self.policy),
+            Arc::clone(&self.profiler),
+            Arc::clone(&self.plans),
+            Arc::clone(&self.interrupts),
+            self.retry,
+            self.worker.clone(),
+            Arc::clone(&self.readers),
+            &self.usage,
         )
     }
 }
 
-/// Collect all rows into CollectedRows for async transfer.
-fn collect_all_rows(mut rows: stoolap::Rows) -> CollectedRows {
-    let columns = rows.columns().to_vec();
-    let mut collected = Vec::new();
-    while rows.advance() {
-        collected.push(rows.current_row().as_slice().to_vec());
-    }
-    CollectedRows {
-        columns,
-        rows: collected,
-    }
+// ============================================================
+// CursorFetchTask — cursor.fetchNext(n)
+// ============================================================
+
+pub struct CursorFetchTask {
+    pub rows: CursorRowsRef,
+    pub count: usize,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+    /// Read the next `count` rows ahead once this fetch resolves.
+    pub prefetch: Option<WorkerRef>,
 }
 
-/// Collect single row data for async transfer.
-fn collect_single_row_data(mut rows: stoolap::Rows) -> Option<CollectedRows> {
-    if !rows.advance() {
-        return None;
+impl Task for CursorFetchTask {
+    type Output = Option<CollectedRows>;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
+        self.rows
+            .next(
+                self.count,
+                &self.ticket,
+                |rows, page| {
+                    let output = collect_next_rows(rows, &self.ticket, &self.memory, None, page)?;
+                    let count = output.row_count();
+                    Ok((output.column_names(self.names), count))
+                },
+                |rows| {
+                    let count = rows.row_count();
+                    Ok((rows.column_names(self.names), count))
+                },
+            )
+            .map(|fetched| fetched.map(|(output, _)| output))
     }
-    let columns = rows.columns().to_vec();
-    let values = rows.current_row().as_slice().to_vec();
-    Some(CollectedRows {
-        columns,
-        rows: vec![values],
-    })
-}
 
-/// Convert a single CollectedRows (with one row) to a V8 object, or null if None.
-/// Shared by QueryOneTask and TxQueryOneTask resolve paths.
-fn collected_single_row_to_v8(data: Option<CollectedRows>) -> sys::napi_value {
-    match data {
-        Some(data) => {
-            let col_count = data.columns.len();
-            let col_ptrs: Vec<*const u8> = data.columns.iter().map(|c| c.as_ptr()).collect();
-            let col_lens: Vec<i32> = data.columns.iter().map(|c| c.len() as i32).collect();
-            let mut temp_strings: Vec<String> = Vec::new();
-            let cells: Vec<CellData> = data.rows[0]
-                .iter()
-                .map(|v| value_to_cell(v, &mut temp_strings))
-                .collect();
-            unsafe {
-                v8_create_single_object(
-                    col_count as i32,
-                    col_ptrs.as_ptr(),
-                    col_lens.as_ptr(),
-                    cells.as_ptr(),
-                )
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let Some(output) = output else {
+            return empty_array(env.raw());
+        };
+        let array = collected_rows_to_array(env.raw(), &output)?;
+        self.rows
+            .fetch_size()
+            .delivered(output.row_count(), Some(output.bytes()));
+        if let Some(ref worker) = self.prefetch {
+            if output.row_count() == self.count {
+                read_ahead(env.raw(), worker, &self.rows, self.count)?;
             }
         }
-        None => unsafe { v8_create_null() },
+        Ok(RawJsValue(array))
     }
 }
 
-/// Shared database handle — Arc::clone (not Database::clone) to share executor & cache.
-pub type DbHandle = Arc<Database>;
-
-use crate::error::to_napi;
-
 // ============================================================
-// RawJsValue — newtype for Task::JsValue (heterogeneous JS values)
+// CursorPrefetchTask — reading a cursor's next rows ahead
 // ============================================================
 
-pub struct RawJsValue(pub sys::napi_value);
+pub struct CursorPrefetchTask {
+    pub rows: CursorRowsRef,
+    pub count: usize,
+}
 
-impl TypeName for RawJsValue {
-    fn type_name() -> &'static str {
-        "Unknown"
+impl Task for CursorPrefetchTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.rows.prefetch(self.count);
+        Ok(())
     }
 
-    fn value_type() -> napi::ValueType {
-        napi::ValueType::Unknown
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(())
     }
-}
 
-impl ToNapiValue for RawJsValue {
-    unsafe fn to_napi_value(_env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
-        Ok(val.0)
+    /// Nothing waits for the promise, and the rows read ahead are only an
+    /// optimization: a fault injected here is dropped.
+    fn reject(&mut self, _env: Env, _err: napi::Error) -> napi::Result<Self::JsValue> {
+        Ok(())
     }
 }
 
 // ============================================================
-// RunResult — { changes: number } via V8 bulk API
+// CloseTask — db.close()
 // ============================================================
 
-/// Create a `{ changes: N }` JS object using V8 bulk API (1 call vs 3 NAPI calls).
-pub(crate) fn v8_run_result(changes: i64) -> sys::napi_value {
-    unsafe { v8_create_run_result(changes) }
+pub struct CloseTask {
+    pub db: DbHandle,
+    pub profiler: ProfilerRef,
+    pub closer: Closer,
 }
 
-// ============================================================
-// Raw NAPI helpers — zero overhead
-// ============================================================
+impl Task for CloseTask {
+    type Output = ();
+    type JsValue = ();
 
-#[inline(always)]
-pub(crate) fn check(status: sys::napi_status) -> napi::Result<()> {
-    if status == sys::Status::napi_ok {
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.closer.wait();
+        let recorded = self.profiler.stop_recording();
+        self.db.close().map_err(to_napi)?;
+        recorded
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
         Ok(())
-    } else {
-        Err(napi::Error::new(
-            napi::Status::GenericFailure,
-            format!("napi call failed: {status}"),
-        ))
     }
 }
 
 // ============================================================
-// Execute parameters enum
+// PingTask — db.ping()
 // ============================================================
 
-pub enum TaskParams {
-    Positional(ParamVec),
-    Named(Vec<(String, Value)>),
+pub struct PingTask {
+    pub db: DbHandle,
+    /// When `ping()` was called.
+    pub started: Instant,
+    /// The engine was found closed.
+    pub closed: bool,
 }
 
-impl TaskParams {
-    pub(crate) fn execute_on_db(self, db: &Database, sql: &str) -> napi::Result<i64> {
-        match self {
-            TaskParams::Positional(p) => db.execute(sql, p).map_err(to_napi),
-            TaskParams::Named(n) => {
-                let mut named = NamedParams::new();
-                for (k, v) in n {
-                    named.insert(k, v);
-                }
-                db.execute_named(sql, named).map_err(to_napi)
-            }
-        }
-    }
+impl Task for PingTask {
+    type Output = ();
+    type JsValue = f64;
 
-    pub(crate) fn query_on_db(self, db: &Database, sql: &str) -> napi::Result<stoolap::Rows> {
-        match self {
-            TaskParams::Positional(p) => db.query(sql, p).map_err(to_napi),
-            TaskParams::Named(n) => {
-                let mut named = NamedParams::new();
-                for (k, v) in n {
-                    named.insert(k, v);
-                }
-                db.query_named(sql, named).map_err(to_napi)
-            }
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        if !self.db.engine().is_open() {
+            self.closed = true;
+            return Err(closing::closed_error());
         }
+        let mut rows = self.db.query("SELECT 1", ()).map_err(to_napi)?;
+        while rows.advance() {}
+        Ok(())
     }
 
-    pub(crate) fn execute_plan_on_db(
-        &self,
-        db: &Database,
-        plan: &CachedPlanRef,
-    ) -> napi::Result<i64> {
-        match self {
-            TaskParams::Positional(p) => db.execute_plan(plan, p.clone()).map_err(to_napi),
-            TaskParams::Named(n) => {
-                let mut named = NamedParams::new();
-                for (k, v) in n {
-                    named.insert(k.clone(), v.clone());
-                }
-                db.execute_named_plan(plan, named).map_err(to_napi)
-            }
-        }
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(self.started.elapsed().as_secs_f64() * 1000.0)
     }
 
-    pub(crate) fn query_plan_on_db(
-        &self,
-        db: &Database,
-        plan: &CachedPlanRef,
-    ) -> napi::Result<stoolap::Rows> {
-        match self {
-            TaskParams::Positional(p) => db.query_plan(plan, p.clone()).map_err(to_napi),
-            TaskParams::Named(n) => {
-                let mut named = NamedParams::new();
-                for (k, v) in n {
-                    named.insert(k.clone(), v.clone());
-                }
-                db.query_named_plan(plan, named).map_err(to_napi)
-            }
-        }
+    fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+        Err(if self.closed {
+            closing::to_js(&env, err)
+        } else {
+            err
+        })
     }
+}
 
-    pub(crate) fn execute_on_tx(self, tx: &mut ApiTransaction, sql: &str) -> napi::Result<i64> {
-        match self {
-            TaskParams::Positional(p) => tx.execute(sql, p).map_err(to_napi),
-            TaskParams::Named(_) => Err(napi::Error::from_reason(
-                "Named parameters not yet supported in transactions",
-            )),
-        }
+// ============================================================
+// ReplayTask — db.replay(path)
+// ============================================================
+
+pub struct ReplayTask {
+    pub db: DbHandle,
+    pub policy: PolicyRef,
+    pub readers: ReadersRef,
+    pub ticket: Ticket,
+    pub path: String,
+}
+
+impl Task for ReplayTask {
+    type Output = ReplayReport;
+    type JsValue = ReplayReport;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        recording::replay(
+            &self.db,
+            &self.policy,
+            &self.readers,
+            &self.path,
+            &self.ticket,
+        )
     }
 
-    pub(crate) fn query_on_tx(
-        self,
-        tx: &mut ApiTransaction,
-        sql: &str,
-    ) -> napi::Result<stoolap::Rows> {
-        match self {
-            TaskParams::Positional(p) => tx.query(sql, p).map_err(to_napi),
-            TaskParams::Named(_) => Err(napi::Error::from_reason(
-                "Named parameters not yet supported in transactions",
-            )),
-        }
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
     }
 }
 
 // ============================================================
-// OpenTask — Database.open()
+// ApplyIndexesTask — db.applyAdvisedIndexes(options)
 // ============================================================
 
-pub struct OpenTask {
-    pub dsn: String,
+pub struct ApplyIndexesTask {
+    pub db: DbHandle,
+    pub policy: PolicyRef,
+    pub profiler: ProfilerRef,
+    pub dry_run: bool,
 }
 
-impl Task for OpenTask {
-    type Output = Database;
-    type JsValue = crate::database::JsDatabase;
+impl Task for ApplyIndexesTask {
+    type Output = Vec<AdvisedIndex>;
+    type JsValue = Vec<AdvisedIndex>;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        Database::open(&self.dsn).map_err(to_napi)
+        advisor::apply(
+            &self.db,
+            &self.policy,
+            self.profiler.advisor(),
+            self.dry_run,
+        )
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(crate::database::JsDatabase::from_db(output))
+        Ok(output)
     }
 }
 
 // ============================================================
-
-// ExecTask — db.execute(sql, params)
+// VacuumTask — db.vacuum(options)
 // ============================================================
 
-pub struct ExecTask {
+pub struct VacuumTask {
     pub db: DbHandle,
-    pub sql: String,
-    pub params: TaskParams,
-    pub plan: Option<CachedPlanRef>,
+    pub policy: PolicyRef,
+    pub readers: ReadersRef,
+    pub ticket: Ticket,
+    pub into: Option<String>,
+    pub progress: Option<VacuumProgressSink>,
 }
 
-impl Task for ExecTask {
-    type Output = i64;
-    type JsValue = RawJsValue;
+impl Task for VacuumTask {
+    type Output = VacuumReport;
+    type JsValue = VacuumReport;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        if let Some(ref plan) = self.plan {
-            params.execute_plan_on_db(&self.db, plan)
-        } else {
-            params.execute_on_db(&self.db, &self.sql)
-        }
+        let report = vacuum::vacuum(
+            &self.db,
+            &self.policy,
+            &self.readers,
+            &self.ticket,
+            self.into.as_deref(),
+            self.progress.as_ref(),
+        );
+        // Let the listener go once the progress events are queued.
+        self.progress.take();
+        report
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(v8_run_result(output)))
+        Ok(output)
     }
 }
 
 // ============================================================
-// BatchExecTask — db.exec(sql)
+// DiffDataTask — Database.diffData(pathA, pathB, options)
 // ============================================================
 
-pub struct BatchExecTask {
-    pub db: DbHandle,
-    pub sql: String,
+pub struct DiffDataTask {
+    pub a: String,
+    pub b: String,
+    pub tables: Option<Vec<String>>,
+    /// Resolve to SQL instead of `TableDiff` objects.
+    pub sql: bool,
 }
 
-impl Task for BatchExecTask {
-    type Output = ();
-    type JsValue = ();
+impl Task for DiffDataTask {
+    type Output = Vec<TableDiff>;
+    type JsValue = RawJsValue;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        for stmt in split_sql_statements(&self.sql) {
-            let trimmed = stmt.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-            self.db.execute(trimmed, ()).map_err(to_napi)?;
+        diff::diff(&self.a, &self.b, self.tables.as_deref())
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        if self.sql {
+            return Ok(RawJsValue(napi_string(env.raw(), &diff::to_sql(&output))?));
         }
-        Ok(())
+        diff::to_js(env.raw(), &output).map(RawJsValue)
     }
+}
 
-    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(())
+// ============================================================
+// ExportWalTask — db.exportWalSegments(sinceLsn)
+// ============================================================
+
+pub struct ExportWalTask {
+    pub dir: PathBuf,
+    pub since: u64,
+}
+
+impl Task for ExportWalTask {
+    type Output = Vec<Segment>;
+    type JsValue = Vec<WalSegment>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        wal_shipping::export(&self.dir, self.since)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output.into_iter().map(WalSegment::from).collect())
     }
 }
 
 // ============================================================
-// QueryTask — db.query(sql, params) -> array of objects
+// ApplyWalTask — Database.applyWalSegments(path, segments)
 // ============================================================
 
-pub struct QueryTask {
-    pub db: DbHandle,
-    pub sql: String,
-    pub params: TaskParams,
-    pub plan: Option<CachedPlanRef>,
+pub struct ApplyWalTask {
+    pub path: String,
+    pub segments: Vec<Segment>,
 }
 
-impl Task for QueryTask {
-    type Output = CollectedRows;
-    type JsValue = RawJsValue;
+impl Task for ApplyWalTask {
+    type Output = u64;
+    type JsValue = i64;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        let rows = if let Some(ref plan) = self.plan {
-            params.query_plan_on_db(&self.db, plan)?
-        } else {
-            params.query_on_db(&self.db, &self.sql)?
-        };
-        Ok(collect_all_rows(rows))
+        wal_shipping::apply(&self.path, &self.segments)
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(collected_rows_to_v8_array(&output)))
+        Ok(output as i64)
     }
 }
 
 // ============================================================
-// QueryRawTask — db.queryRaw(sql, params) -> { columns, rows }
+// BeginTask — db.begin()
 // ============================================================
 
-pub struct QueryRawTask {
+pub struct BeginTask {
     pub db: DbHandle,
-    pub sql: String,
-    pub params: TaskParams,
-    pub plan: Option<CachedPlanRef>,
+    pub changes: ChangeHubRef,
+    pub policy: PolicyRef,
+    pub profiler: ProfilerRef,
+    pub interrupts: InterruptRef,
+    pub worker: WorkerRef,
+    pub timeouts: TxTimeoutsRef,
+    pub max_duration: Option<Duration>,
+    pub log: SqlLog,
+    /// JS stack of the `begin()` call, for `idleTransactionMs`.
+    pub stack: Option<String>,
+    pub memo: QueryMemo,
 }
 
-impl Task for QueryRawTask {
-    type Output = CollectedRows;
-    type JsValue = RawJsValue;
+impl Task for BeginTask {
+    type Output = (ApiTransaction, Instant);
+    type JsValue = crate::transaction::JsTransaction;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        let rows = if let Some(ref plan) = self.plan {
-            params.query_plan_on_db(&self.db, plan)?
-        } else {
-            params.query_on_db(&self.db, &self.sql)?
-        };
-        Ok(collect_all_rows(rows))
+        let began = Instant::now();
+        Ok((self.memo.begin(&self.db).map_err(to_napi)?, began))
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(collected_rows_to_v8_raw(&output)))
+        let (tx, began) = output;
+        crate::transaction::JsTransaction::from_tx(
+            tx,
+            Arc::clone(&self.db),
+            began,
+            Arc::clone(&self.changes),
+            Arc::clone(&self.policy),
+            Arc::clone(&self.profiler),
+            Arc::clone(&self.interrupts),
+            self.worker.clone(),
+            self.log.clone(),
+            self.memo.clone(),
+        )
+        .watch(
+            &self.timeouts,
+            Watch {
+                max_duration: self.max_duration,
+                stack: self.stack.take(),
+            },
+        )
     }
 }
 
 // ============================================================
-// QueryOneTask — db.queryOne(sql, params) -> object | null
+// CreateCheckpointTask — db.createCheckpoint(name)
 // ============================================================
 
-pub struct QueryOneTask {
+pub struct CreateCheckpointTask {
     pub db: DbHandle,
-    pub sql: String,
-    pub params: TaskParams,
-    pub plan: Option<CachedPlanRef>,
+    pub checkpoints: CheckpointMap,
+    pub name: String,
 }
 
-impl Task for QueryOneTask {
-    type Output = Option<CollectedRows>;
-    type JsValue = RawJsValue;
+impl Task for CreateCheckpointTask {
+    type Output = ();
+    type JsValue = ();
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        let rows = if let Some(ref plan) = self.plan {
-            params.query_plan_on_db(&self.db, plan)?
-        } else {
-            params.query_on_db(&self.db, &self.sql)?
-        };
-        Ok(collect_single_row_data(rows))
+        let checkpoint = checkpoint::create(&self.db)?;
+        self.checkpoints
+            .lock()
+            .map_err(|_| napi::Error::from_reason("Checkpoint lock poisoned"))?
+            .insert(std::mem::take(&mut self.name), checkpoint);
+        Ok(())
     }
 
-    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(collected_single_row_to_v8(output)))
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(())
     }
 }
 
 // ============================================================
-// CloseTask — db.close()
+// RestoreCheckpointTask — db.restoreCheckpoint(name)
 // ============================================================
 
-pub struct CloseTask {
+pub struct RestoreCheckpointTask {
     pub db: DbHandle,
+    pub checkpoints: CheckpointMap,
+    pub name: String,
+    pub changes: ChangeHubRef,
 }
 
-impl Task for CloseTask {
+impl Task for RestoreCheckpointTask {
     type Output = ();
     type JsValue = ();
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        self.db.close().map_err(to_napi)
+        let checkpoint = self
+            .checkpoints
+            .lock()
+            .map_err(|_| napi::Error::from_reason("Checkpoint lock poisoned"))?
+            .get(&self.name)
+            .cloned()
+            .ok_or_else(|| {
+                napi::Error::from_reason(format!("Checkpoint '{}' not found", self.name))
+            })?;
+        let events = checkpoint::restore(&self.db, &checkpoint)?;
+        self.changes.emit(events);
+        Ok(())
     }
 
     fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
@@ -826,23 +3329,73 @@ impl Task for CloseTask {
 }
 
 // ============================================================
-// BeginTask — db.begin()
+// DumpTask — db.dump(options, onChunk) -> SQL script
 // ============================================================
 
-pub struct BeginTask {
+pub struct DumpTask {
     pub db: DbHandle,
+    pub dump: Dump,
+    pub policy: PolicyRef,
+    pub ticket: Ticket,
+    /// Taken by the task when it runs.
+    pub sink: Option<Sink>,
 }
 
-impl Task for BeginTask {
-    type Output = ApiTransaction;
-    type JsValue = crate::transaction::JsTransaction;
+impl Task for DumpTask {
+    type Output = Option<String>;
+    type JsValue = Either<String, ()>;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        self.db.begin().map_err(to_napi)
+        let mut sink = self
+            .sink
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("dump task already ran"))?;
+        let dumped = self
+            .dump
+            .run(&self.db, &self.policy, &self.ticket, &mut sink);
+        let finished = sink.finish();
+        dumped.and(finished)
     }
 
     fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(crate::transaction::JsTransaction::from_tx(output))
+        Ok(match output {
+            Some(sql) => Either::A(sql),
+            None => Either::B(()),
+        })
+    }
+}
+
+// ============================================================
+// RestoreTask — db.restore(sql)
+// ============================================================
+
+pub struct RestoreTask {
+    pub db: DbHandle,
+    pub sql: String,
+    pub changes: ChangeHubRef,
+    pub policy: PolicyRef,
+    pub readers: ReadersRef,
+    pub ticket: Ticket,
+}
+
+impl Task for RestoreTask {
+    type Output = RunOutcome;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.readers.write(|| {
+            dump::restore(
+                &self.db,
+                &self.changes,
+                &self.policy,
+                &self.ticket,
+                &self.sql,
+            )
+        })
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(run_result(env.raw(), &output)?))
     }
 }
 
@@ -852,7 +3405,7 @@ impl Task for BeginTask {
 
 pub type TxHandle = Arc<Mutex<Option<ApiTransaction>>>;
 
-fn with_tx<F, R>(handle: &TxHandle, f: F) -> napi::Result<R>
+pub(crate) fn with_tx<F, R>(handle: &TxHandle, f: F) -> napi::Result<R>
 where
     F: FnOnce(&mut ApiTransaction) -> napi::Result<R>,
 {
@@ -865,6 +3418,14 @@ where
     f(tx)
 }
 
+pub(crate) fn lock_pending(
+    pending: &PendingChanges,
+) -> napi::Result<std::sync::MutexGuard<'_, Vec<ChangeEvent>>> {
+    pending
+        .lock()
+        .map_err(|_| napi::Error::from_reason("Transaction lock poisoned"))
+}
+
 fn take_tx(handle: &TxHandle) -> napi::Result<ApiTransaction> {
     let mut guard = handle
         .lock()
@@ -880,19 +3441,87 @@ pub struct TxExecTask {
     pub tx: TxHandle,
     pub sql: String,
     pub params: TaskParams,
+    pub changes: ChangeHubRef,
+    pub pending: PendingChanges,
+    pub profiler: ProfilerRef,
+    pub log: SqlLog,
+    pub ticket: Ticket,
+    pub memo: QueryMemo,
 }
 
 impl Task for TxExecTask {
-    type Output = i64;
+    type Output = RunOutcome;
     type JsValue = RawJsValue;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
         let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        with_tx(&self.tx, |tx| params.execute_on_tx(tx, &self.sql))
+        let timer = Instant::now();
+        let started = self.profiler.start_with(&params);
+        let logged = self.log.keep(&self.sql, &params);
+        let changes = with_tx(&self.tx, |tx| {
+            if self.changes.is_active() {
+                let mut pending = lock_pending(&self.pending)?;
+                self.changes
+                    .execute_on_tx(tx, params, &self.sql, &mut pending)
+            } else {
+                params.execute_on_tx(tx, &self.sql).map_err(from_core)
+            }
+        });
+        self.memo.clear();
+        let changes = changes?;
+        self.profiler
+            .finish_write(started, &self.sql, changes, None);
+        self.log.push(logged);
+        Ok(RunOutcome::new(timer, changes))
     }
 
-    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(v8_run_result(output)))
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(run_result(env.raw(), &output)?))
+    }
+}
+
+// TxExecuteBatchTask
+
+pub struct TxExecuteBatchTask {
+    pub tx: TxHandle,
+    pub batch: Option<Batch>,
+    pub changes: ChangeHubRef,
+    pub pending: PendingChanges,
+    pub profiler: ProfilerRef,
+    pub log: SqlLog,
+    pub ticket: Ticket,
+    pub memo: QueryMemo,
+}
+
+impl Task for TxExecuteBatchTask {
+    type Output = RunOutcome;
+    type JsValue = RawJsValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let batch = self
+            .batch
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("executeBatch task already ran"))?;
+        let logged = self.log.keep_batch(&batch);
+        let outcome = with_tx(&self.tx, |tx| {
+            let mut pending = lock_pending(&self.pending)?;
+            batch.run_on_tx(
+                tx,
+                &self.changes,
+                &mut pending,
+                &self.profiler,
+                &self.ticket,
+            )
+        });
+        self.memo.clear();
+        let outcome = outcome?;
+        self.log.push(logged);
+        Ok(outcome)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(RawJsValue(run_result(env.raw(), &output)?))
     }
 }
 
@@ -900,8 +3529,16 @@ impl Task for TxExecTask {
 
 pub struct TxQueryTask {
     pub tx: TxHandle,
+    pub db: DbHandle,
     pub sql: String,
     pub params: TaskParams,
+    pub profiler: ProfilerRef,
+    pub log: SqlLog,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+    pub max_rows: Option<MaxRows>,
+    pub memo: QueryMemo,
 }
 
 impl Task for TxQueryTask {
@@ -909,13 +3546,32 @@ impl Task for TxQueryTask {
     type JsValue = RawJsValue;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
         let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        let rows = with_tx(&self.tx, |tx| params.query_on_tx(tx, &self.sql))?;
-        Ok(collect_all_rows(rows))
+        let started = self.profiler.start_with(&params);
+        let logged = self.log.keep(&self.sql, &params);
+        let output = self.memo.rows(&self.sql, params, &self.memory, |params| {
+            let rows = with_tx(&self.tx, |tx| {
+                params.query_on_tx(tx, &self.sql).map_err(from_core)
+            })?;
+            Ok(collect_all_rows(
+                rows,
+                &self.ticket,
+                &self.memory,
+                None,
+                Page::default().max_rows(self.max_rows),
+            )?
+            .column_names(self.names))
+        })?;
+        self.profiler
+            .finish(started, &self.sql, output.rows.len() as i64, None);
+        self.log.push(logged);
+        Ok(output)
     }
 
-    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(collected_rows_to_v8_array(&output)))
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let _statement = result_filter::statement(&self.db, &self.sql, None);
+        Ok(RawJsValue(collected_rows_to_array(env.raw(), &output)?))
     }
 }
 
@@ -923,8 +3579,15 @@ impl Task for TxQueryTask {
 
 pub struct TxQueryOneTask {
     pub tx: TxHandle,
+    pub db: DbHandle,
     pub sql: String,
     pub params: TaskParams,
+    pub profiler: ProfilerRef,
+    pub log: SqlLog,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+    pub memo: QueryMemo,
 }
 
 impl Task for TxQueryOneTask {
@@ -932,13 +3595,32 @@ impl Task for TxQueryOneTask {
     type JsValue = RawJsValue;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
         let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        let rows = with_tx(&self.tx, |tx| params.query_on_tx(tx, &self.sql))?;
-        Ok(collect_single_row_data(rows))
+        let started = self.profiler.start_with(&params);
+        let logged = self.log.keep(&self.sql, &params);
+        let output = self.memo.row(&self.sql, params, &self.memory, |params| {
+            let rows = with_tx(&self.tx, |tx| {
+                params.query_on_tx(tx, &self.sql).map_err(from_core)
+            })?;
+            Ok(
+                collect_single_row_data(rows, &self.ticket, &self.memory, None, Page::default())?
+                    .map(|rows| rows.column_names(self.names)),
+            )
+        })?;
+        self.profiler
+            .finish(started, &self.sql, output.is_some() as i64, None);
+        self.log.push(logged);
+        Ok(output)
     }
 
-    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(collected_single_row_to_v8(output)))
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let _statement = result_filter::statement(&self.db, &self.sql, None);
+        Ok(RawJsValue(collected_single_row_to_js(
+            env.raw(),
+            output,
+            &RowShape::Object,
+        )?))
     }
 }
 
@@ -946,8 +3628,16 @@ impl Task for TxQueryOneTask {
 
 pub struct TxQueryRawTask {
     pub tx: TxHandle,
+    pub db: DbHandle,
     pub sql: String,
     pub params: TaskParams,
+    pub profiler: ProfilerRef,
+    pub log: SqlLog,
+    pub ticket: Ticket,
+    pub memory: MemoryBudgetRef,
+    pub names: ColumnNames,
+    pub max_rows: Option<MaxRows>,
+    pub memo: QueryMemo,
 }
 
 impl Task for TxQueryRawTask {
@@ -955,13 +3645,32 @@ impl Task for TxQueryRawTask {
     type JsValue = RawJsValue;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
+        self.ticket.check()?;
         let params = std::mem::replace(&mut self.params, TaskParams::Positional(ParamVec::new()));
-        let rows = with_tx(&self.tx, |tx| params.query_on_tx(tx, &self.sql))?;
-        Ok(collect_all_rows(rows))
+        let started = self.profiler.start_with(&params);
+        let logged = self.log.keep(&self.sql, &params);
+        let output = self.memo.rows(&self.sql, params, &self.memory, |params| {
+            let rows = with_tx(&self.tx, |tx| {
+                params.query_on_tx(tx, &self.sql).map_err(from_core)
+            })?;
+            Ok(collect_all_rows(
+                rows,
+                &self.ticket,
+                &self.memory,
+                None,
+                Page::default().max_rows(self.max_rows),
+            )?
+            .column_names(self.names))
+        })?;
+        self.profiler
+            .finish(started, &self.sql, output.rows.len() as i64, None);
+        self.log.push(logged);
+        Ok(output)
     }
 
-    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
-        Ok(RawJsValue(collected_rows_to_v8_raw(&output)))
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        let _statement = result_filter::statement(&self.db, &self.sql, None);
+        Ok(RawJsValue(collected_rows_to_raw(env.raw(), &output)?))
     }
 }
 
@@ -969,6 +3678,9 @@ impl Task for TxQueryRawTask {
 
 pub struct CommitTask {
     pub tx: TxHandle,
+    pub changes: ChangeHubRef,
+    pub pending: PendingChanges,
+    pub writes: TxLocksRef,
 }
 
 impl Task for CommitTask {
@@ -977,7 +3689,13 @@ impl Task for CommitTask {
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
         let mut tx = take_tx(&self.tx)?;
-        tx.commit().map_err(to_napi)
+        let committed = tx.commit().map_err(to_napi);
+        drop(tx);
+        self.writes.release();
+        committed?;
+        let events = std::mem::take(&mut *lock_pending(&self.pending)?);
+        self.changes.emit(events);
+        Ok(())
     }
 
     fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
@@ -989,6 +3707,8 @@ impl Task for CommitTask {
 
 pub struct RollbackTask {
     pub tx: TxHandle,
+    pub pending: PendingChanges,
+    pub writes: TxLocksRef,
 }
 
 impl Task for RollbackTask {
@@ -997,7 +3717,11 @@ impl Task for RollbackTask {
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
         let mut tx = take_tx(&self.tx)?;
-        tx.rollback().map_err(to_napi)
+        lock_pending(&self.pending)?.clear();
+        let rolled_back = tx.rollback().map_err(to_napi);
+        drop(tx);
+        self.writes.release();
+        rolled_back
     }
 
     fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
@@ -1009,6 +3733,15 @@ impl Task for RollbackTask {
 // Helpers
 // ============================================================
 
+/// Whether an auto-committed statement returns rows from RETURNING, read
+/// from its plan when there is one.
+pub(crate) fn returning(plan: Option<&CachedPlanRef>, sql: &str) -> bool {
+    match plan {
+        Some(plan) => returns_rows(&plan.statement),
+        None => has_returning(sql),
+    }
+}
+
 /// Split SQL statements by semicolons, respecting quotes and comments.
 pub(crate) fn split_sql_statements(input: &str) -> Vec<&str> {
     let mut stmts = Vec::new();
@@ -0,0 +1,345 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.withTempTable(columns, fn)` and the `TempTable` it passes to `fn`.
+//
+// The engine has no temporary tables, so the table is an ordinary one named
+// `tmp_` and a suffix no other call has used, created before `fn` runs and
+// dropped once the promise it returns settles. The steps are chained on the
+// promises of the CREATE and DROP statements, which run as any other
+// `execute()` does. `fn` itself is handed to `then()`, so throwing and
+// rejecting both end in the DROP. The call then settles as `fn` did, unless
+// the DROP fails after `fn` resolved, which rejects it with that error. A
+// process that exits while `fn` runs leaves the table in a file database.
+
+use std::ffi::CStr;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use napi::bindgen_prelude::*;
+use napi::{sys, Env};
+use stoolap::parser::ast::Statement;
+
+use crate::database::JsDatabase;
+use crate::sql::{parse_single, quote_ident};
+use crate::tasks::{check, ExecuteBatchTask, QueryTask, RawJsValue};
+use crate::value::RawParam;
+use crate::worker::Scheduled;
+
+/// Temp tables created so far in this process, so two calls in the same
+/// nanosecond still get different names.
+static CREATED: AtomicU64 = AtomicU64::new(0);
+
+/// The table of one `withTempTable()` call, passed to its callback.
+#[napi(js_name = "TempTable")]
+#[derive(Clone)]
+pub struct JsTempTable {
+    db: JsDatabase,
+    name: String,
+    columns: Vec<String>,
+    /// Set once the callback settled and the DROP was issued.
+    dropped: Arc<AtomicBool>,
+}
+
+impl JsTempTable {
+    /// A table with the columns `columns` describes, as a string of column
+    /// definitions or an object of column types by name.
+    fn new(db: JsDatabase, columns: Either<String, Object>) -> napi::Result<(Self, String)> {
+        let definitions = match columns {
+            Either::A(definitions) => definitions,
+            Either::B(types) => Object::keys(&types)?
+                .iter()
+                .map(|name| {
+                    let ty = types.get_named_property::<String>(name).map_err(|_| {
+                        napi::Error::from_reason(format!(
+                            "The type of column '{name}' must be a string"
+                        ))
+                    })?;
+                    Ok(format!("{} {ty}", quote_ident(name)))
+                })
+                .collect::<napi::Result<Vec<_>>>()?
+                .join(", "),
+        };
+        let empty = || napi::Error::from_reason("withTempTable needs at least one column");
+        if definitions.trim().is_empty() {
+            return Err(empty());
+        }
+        let name = unique_name();
+        let sql = format!("CREATE TABLE {} ({definitions})", quote_ident(&name));
+        let parsed = parse_single(&sql).map_err(|e| {
+            napi::Error::from_reason(format!("Invalid temp table columns: {}", e.reason))
+        })?;
+        let columns = match parsed {
+            Statement::CreateTable(create) if !create.columns.is_empty() => create
+                .columns
+                .iter()
+                .map(|column| column.name.value.to_string())
+                .collect(),
+            _ => return Err(empty()),
+        };
+        let table = Self {
+            db,
+            name,
+            columns,
+            dropped: Arc::default(),
+        };
+        Ok((table, sql))
+    }
+
+    /// Issue the DROP; the table's helpers throw from now on.
+    fn drop_table(&self, env: &Env) -> napi::Result<sys::napi_value> {
+        self.dropped.store(true, Ordering::Release);
+        let sql = format!("DROP TABLE IF EXISTS {}", quote_ident(&self.name));
+        issue(env, &self.db, sql)
+    }
+
+    fn check_open(&self) -> napi::Result<()> {
+        if self.dropped.load(Ordering::Acquire) {
+            return Err(napi::Error::from_reason(format!(
+                "Temp table '{}' was dropped when its withTempTable() callback settled",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[napi]
+impl JsTempTable {
+    /// Name of the table, to use it in SQL of your own.
+    #[napi(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Names of the columns, in table order.
+    #[napi(getter)]
+    pub fn columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    /// Insert one row or an array of rows in one transaction. A row is an
+    /// object of values by column name, where missing columns are NULL, or
+    /// an array of values in column order. Returns Promise<RunResult>.
+    #[napi(
+        ts_args_type = "rows: Record<string, any> | any[] | Array<Record<string, any> | any[]>",
+        ts_return_type = "Promise<RunResult>"
+    )]
+    pub fn insert(&self, env: Env, rows: RawParam) -> napi::Result<Scheduled<ExecuteBatchTask>> {
+        self.check_open()?;
+        let env_raw = env.raw();
+        let mut is_array = false;
+        check(unsafe { sys::napi_is_array(env_raw, rows.0, &mut is_array) })?;
+        // An array of rows, rather than one row as an array of values, is
+        // empty or starts with an object or array.
+        let mut len = 0;
+        let mut many = false;
+        if is_array {
+            check(unsafe { sys::napi_get_array_length(env_raw, rows.0, &mut len) })?;
+            let mut first = ptr::null_mut();
+            check(unsafe { sys::napi_get_element(env_raw, rows.0, 0, &mut first) })?;
+            let mut kind = sys::ValueType::napi_undefined;
+            check(unsafe { sys::napi_typeof(env_raw, first, &mut kind) })?;
+            many = len == 0 || kind == sys::ValueType::napi_object;
+        }
+        let mut params = ptr::null_mut();
+        check(unsafe { sys::napi_create_array(env_raw, &mut params) })?;
+        if many {
+            for i in 0..len {
+                let mut row = ptr::null_mut();
+                check(unsafe { sys::napi_get_element(env_raw, rows.0, i, &mut row) })?;
+                let values = self.row_values(env_raw, row)?;
+                check(unsafe { sys::napi_set_element(env_raw, params, i, values) })?;
+            }
+        } else {
+            let values = self.row_values(env_raw, rows.0)?;
+            check(unsafe { sys::napi_set_element(env_raw, params, 0, values) })?;
+        }
+        let placeholders: Vec<String> = (1..=self.columns.len()).map(|i| format!("${i}")).collect();
+        let names: Vec<String> = self.columns.iter().map(|c| quote_ident(c)).collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_ident(&self.name),
+            names.join(", "),
+            placeholders.join(", ")
+        );
+        self.db.execute_batch(env, sql, RawParam(params))
+    }
+
+    /// Query the table's rows. `clause` follows `FROM <table>`, as in
+    /// `'WHERE total > $1 ORDER BY total'`. Returns Promise<Array<Object>>.
+    #[napi(
+        ts_args_type = "clause?: string, params?: any[] | Record<string, any>",
+        ts_return_type = "Promise<Record<string, any>[]>"
+    )]
+    pub fn query(
+        &self,
+        env: Env,
+        clause: Option<String>,
+        params: Option<RawParam>,
+    ) -> napi::Result<Scheduled<QueryTask>> {
+        self.check_open()?;
+        let mut sql = format!("SELECT * FROM {}", quote_ident(&self.name));
+        if let Some(clause) = clause.filter(|c| !c.trim().is_empty()) {
+            sql.push(' ');
+            sql.push_str(&clause);
+        }
+        self.db.query(env, sql, params, None)
+    }
+
+    /// The values of `row` in column order: an array as is, an object by
+    /// column name.
+    fn row_values(
+        &self,
+        env: sys::napi_env,
+        row: sys::napi_value,
+    ) -> napi::Result<sys::napi_value> {
+        let mut is_array = false;
+        check(unsafe { sys::napi_is_array(env, row, &mut is_array) })?;
+        if is_array {
+            return Ok(row);
+        }
+        let mut kind = sys::ValueType::napi_undefined;
+        check(unsafe { sys::napi_typeof(env, row, &mut kind) })?;
+        if kind != sys::ValueType::napi_object {
+            return Err(napi::Error::new(
+                Status::InvalidArg,
+                "A temp table row must be an object or an array",
+            ));
+        }
+        let mut values = ptr::null_mut();
+        check(unsafe { sys::napi_create_array(env, &mut values) })?;
+        for (i, column) in self.columns.iter().enumerate() {
+            let mut key = ptr::null_mut();
+            check(unsafe {
+                sys::napi_create_string_utf8(
+                    env,
+                    column.as_ptr().cast(),
+                    column.len() as isize,
+                    &mut key,
+                )
+            })?;
+            let mut value = ptr::null_mut();
+            check(unsafe { sys::napi_get_property(env, row, key, &mut value) })?;
+            check(unsafe { sys::napi_set_element(env, values, i as u32, value) })?;
+        }
+        Ok(values)
+    }
+}
+
+/// Create a temp table, run `callback` with it and drop it.
+pub fn run(
+    env: &Env,
+    db: &JsDatabase,
+    columns: Either<String, Object>,
+    callback: sys::napi_value,
+) -> napi::Result<RawJsValue> {
+    let (table, sql) = JsTempTable::new(db.clone(), columns)?;
+    let created = issue(env, db, sql)?;
+
+    let handed = table.clone();
+    let hand = env
+        .create_function_from_closure::<(), JsTempTable, _>("withTempTable", move |_| {
+            Ok(handed.clone())
+        })?;
+    let opened = then(env.raw(), created, hand.raw(), None)?;
+    let ran = then(env.raw(), opened, callback, None)?;
+
+    let on_ok = {
+        let table = table.clone();
+        env.create_function_from_closure::<Unknown, RawJsValue, _>("withTempTable", move |ctx| {
+            let value = ctx.get::<Unknown>(0)?.raw();
+            let env = &*ctx.env;
+            let dropped = table.drop_table(env)?;
+            let settle = settle(env, value, false)?;
+            then(env.raw(), dropped, settle, None).map(RawJsValue)
+        })?
+    };
+    let on_err =
+        env.create_function_from_closure::<Unknown, RawJsValue, _>("withTempTable", move |ctx| {
+            let error = ctx.get::<Unknown>(0)?;
+            let env = &*ctx.env;
+            // The callback's error wins over one from dropping the table.
+            let Ok(dropped) = table.drop_table(env) else {
+                return Err(error.into());
+            };
+            let settle = settle(env, error.raw(), true)?;
+            then(env.raw(), dropped, settle, Some(settle)).map(RawJsValue)
+        })?;
+    then(env.raw(), ran, on_ok.raw(), Some(on_err.raw())).map(RawJsValue)
+}
+
+/// The promise of `db.execute(sql)`.
+fn issue(env: &Env, db: &JsDatabase, sql: String) -> napi::Result<sys::napi_value> {
+    let scheduled = db.execute(*env, sql, None)?;
+    unsafe { ToNapiValue::to_napi_value(env.raw(), scheduled) }
+}
+
+/// A function that returns `value`, or throws it with `throws`, to end the
+/// chain with.
+fn settle(env: &Env, value: sys::napi_value, throws: bool) -> napi::Result<sys::napi_value> {
+    let mut holder = ptr::null_mut();
+    check(unsafe { sys::napi_create_object(env.raw(), &mut holder) })?;
+    check(unsafe { sys::napi_set_named_property(env.raw(), holder, c"held".as_ptr(), value) })?;
+    let settle =
+        env.create_function_from_closure::<(), RawJsValue, _>("withTempTable", move |ctx| {
+            let held = ctx
+                .this::<Object>()?
+                .get_named_property::<Unknown>("held")?;
+            if throws {
+                Err(held.into())
+            } else {
+                Ok(RawJsValue(held.raw()))
+            }
+        })?;
+    call(env.raw(), settle.raw(), c"bind", &[holder])
+}
+
+/// `promise.then(on_ok, on_err)`.
+fn then(
+    env: sys::napi_env,
+    promise: sys::napi_value,
+    on_ok: sys::napi_value,
+    on_err: Option<sys::napi_value>,
+) -> napi::Result<sys::napi_value> {
+    let mut args = vec![on_ok];
+    args.extend(on_err);
+    call(env, promise, c"then", &args)
+}
+
+/// `this[method](...args)`.
+fn call(
+    env: sys::napi_env,
+    this: sys::napi_value,
+    method: &CStr,
+    args: &[sys::napi_value],
+) -> napi::Result<sys::napi_value> {
+    let mut function = ptr::null_mut();
+    check(unsafe { sys::napi_get_named_property(env, this, method.as_ptr(), &mut function) })?;
+    let mut result = ptr::null_mut();
+    check(unsafe {
+        sys::napi_call_function(env, this, function, args.len(), args.as_ptr(), &mut result)
+    })?;
+    Ok(result)
+}
+
+fn unique_name() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let count = CREATED.fetch_add(1, Ordering::Relaxed);
+    format!("tmp_{:x}_{:x}_{count}", std::process::id(), now)
+}
@@ -0,0 +1,153 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Temporal values.
+//
+// `Temporal.Instant`, `Temporal.PlainDateTime` and `Temporal.PlainDate`
+// parameters bind as TIMESTAMPs like Dates do, but keep their nanoseconds.
+// The plain ones have no time zone and are read as UTC, a date as its
+// midnight. With the `temporal` open option, TIMESTAMP results come back as
+// `Temporal.Instant`s instead of ISO strings (see `TAG_TIMESTAMP` in
+// tasks.rs). Both go through `globalThis.Temporal`, so a polyfill installed
+// there works as well as the runtime's own.
+
+use std::ptr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use napi::sys;
+
+use crate::tasks::check;
+
+/// The Temporal classes that bind as timestamps.
+#[derive(Clone, Copy)]
+enum Kind {
+    Instant,
+    PlainDateTime,
+    PlainDate,
+}
+
+impl Kind {
+    const ALL: [Kind; 3] = [Kind::Instant, Kind::PlainDateTime, Kind::PlainDate];
+
+    fn name(self) -> &'static std::ffi::CStr {
+        match self {
+            Kind::Instant => c"Instant",
+            Kind::PlainDateTime => c"PlainDateTime",
+            Kind::PlainDate => c"PlainDate",
+        }
+    }
+
+    /// Parse what `toString()` returns, without a calendar annotation.
+    fn parse(self, text: &str) -> Option<DateTime<Utc>> {
+        let text = text.split('[').next().unwrap_or(text);
+        match self {
+            Kind::Instant => DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|dt| dt.to_utc()),
+            Kind::PlainDateTime => NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+                .ok()
+                .map(|dt| dt.and_utc()),
+            Kind::PlainDate => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc()),
+        }
+    }
+}
+
+/// Whether `globalThis.Temporal` exists.
+pub fn available(env: sys::napi_env) -> napi::Result<bool> {
+    Ok(global_temporal(env)?.is_some())
+}
+
+/// The timestamp of an object parameter that is a Temporal value.
+pub fn param(env: sys::napi_env, value: sys::napi_value) -> napi::Result<Option<DateTime<Utc>>> {
+    let Some(temporal) = global_temporal(env)? else {
+        return Ok(None);
+    };
+    for kind in Kind::ALL {
+        let class = property(env, temporal, kind.name())?;
+        if value_type(env, class)? != sys::ValueType::napi_function {
+            continue;
+        }
+        let mut is = false;
+        check(unsafe { sys::napi_instanceof(env, value, class, &mut is) })?;
+        if !is {
+            continue;
+        }
+        let text = to_string(env, value)?;
+        return kind.parse(&text).map(Some).ok_or_else(|| {
+            napi::Error::from_reason(format!(
+                "Cannot bind Temporal.{} '{text}' as a TIMESTAMP",
+                kind.name().to_string_lossy()
+            ))
+        });
+    }
+    Ok(None)
+}
+
+/// `Temporal.Instant.from(text)`, for a TIMESTAMP result.
+pub fn instant(env: sys::napi_env, text: &str) -> napi::Result<sys::napi_value> {
+    let Some(temporal) = global_temporal(env)? else {
+        return Err(napi::Error::from_reason(
+            "temporal results need globalThis.Temporal",
+        ));
+    };
+    let class = property(env, temporal, c"Instant")?;
+    let from = property(env, class, c"from")?;
+    let mut arg = ptr::null_mut();
+    check(unsafe {
+        sys::napi_create_string_latin1(env, text.as_ptr().cast(), text.len() as isize, &mut arg)
+    })?;
+    let mut result = ptr::null_mut();
+    check(unsafe { sys::napi_call_function(env, class, from, 1, &arg, &mut result) })?;
+    Ok(result)
+}
+
+fn global_temporal(env: sys::napi_env) -> napi::Result<Option<sys::napi_value>> {
+    let mut global = ptr::null_mut();
+    check(unsafe { sys::napi_get_global(env, &mut global) })?;
+    let temporal = property(env, global, c"Temporal")?;
+    Ok((value_type(env, temporal)? == sys::ValueType::napi_object).then_some(temporal))
+}
+
+fn property(
+    env: sys::napi_env,
+    object: sys::napi_value,
+    name: &std::ffi::CStr,
+) -> napi::Result<sys::napi_value> {
+    let mut value = ptr::null_mut();
+    check(unsafe { sys::napi_get_named_property(env, object, name.as_ptr(), &mut value) })?;
+    Ok(value)
+}
+
+fn value_type(env: sys::napi_env, value: sys::napi_value) -> napi::Result<sys::napi_valuetype> {
+    let mut ty = 0;
+    check(unsafe { sys::napi_typeof(env, value, &mut ty) })?;
+    Ok(ty)
+}
+
+fn to_string(env: sys::napi_env, value: sys::napi_value) -> napi::Result<String> {
+    let mut text = ptr::null_mut();
+    check(unsafe { sys::napi_coerce_to_string(env, value, &mut text) })?;
+    let mut len = 0;
+    check(unsafe { sys::napi_get_value_string_utf8(env, text, ptr::null_mut(), 0, &mut len) })?;
+    let mut buf = vec![0u8; len + 1];
+    let mut written = 0;
+    check(unsafe {
+        sys::napi_get_value_string_utf8(env, text, buf.as_mut_ptr().cast(), buf.len(), &mut written)
+    })?;
+    buf.truncate(written);
+    String::from_utf8(buf).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
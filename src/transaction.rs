@@ -12,28 +12,111 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use napi::bindgen_prelude::*;
 use napi::Env;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use stoolap::api::Transaction as ApiTransaction;
 use stoolap::ParamVec;
 
-use crate::error::to_napi;
+use crate::batch::Batch;
+use crate::changes::{ChangeHubRef, PendingChanges};
+use crate::error::{from_core, to_napi};
+use crate::interrupt::InterruptRef;
+use crate::options::{reject_as_of, QueryOptions};
+use crate::paging::Page;
+use crate::policy::{check_single_sql, PolicyRef};
+use crate::profile::ProfilerRef;
+use crate::result_filter;
+use crate::shape::RowShape;
+use crate::sql::expand_in_lists;
 use crate::tasks::*;
-use crate::value::{parse_params, parse_positional, BindParams, RawParam};
+use crate::tx_memo::{MemoStats, QueryMemo};
+use crate::tx_sql::{SqlLog, ToSqlOptions};
+use crate::tx_timeout::{Activity, ActivityRef, TxTimeoutsRef, Watch};
+use crate::types::Types;
+use crate::value::{parse_params, BindParams, RawParam};
+use crate::worker::{Scheduled, WorkerRef};
+use crate::write_queue::TxLocksRef;
 
 #[napi(js_name = "Transaction")]
 pub struct JsTransaction {
+    /// Shared with running tasks. When the last holder drops it, e.g. after
+    /// the JS object is garbage collected, an uncommitted transaction rolls
+    /// back.
     tx: TxHandle,
+    /// The database it runs on, for the tables a result filter sees.
+    db: DbHandle,
+    /// Serialized tables this transaction has written to.
+    writes: TxLocksRef,
+    changes: ChangeHubRef,
+    pending: PendingChanges,
+    policy: PolicyRef,
+    profiler: ProfilerRef,
+    interrupts: InterruptRef,
+    worker: WorkerRef,
+    /// When the transaction last had a call, for `idleTransactionMs`.
+    activity: ActivityRef,
+    /// The statements it ran, for `toSql()` (`recordSql`).
+    log: SqlLog,
+    /// Results of repeated SELECTs (`memoize`).
+    memo: QueryMemo,
 }
 
 impl JsTransaction {
-    pub fn from_tx(tx: ApiTransaction) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_tx(
+        tx: ApiTransaction,
+        db: DbHandle,
+        began: Instant,
+        changes: ChangeHubRef,
+        policy: PolicyRef,
+        profiler: ProfilerRef,
+        interrupts: InterruptRef,
+        worker: WorkerRef,
+        log: SqlLog,
+        memo: QueryMemo,
+    ) -> Self {
         Self {
             tx: Arc::new(Mutex::new(Some(tx))),
+            db,
+            writes: TxLocksRef::default(),
+            changes,
+            pending: PendingChanges::default(),
+            policy,
+            profiler,
+            interrupts,
+            worker,
+            activity: Arc::new(Activity::new(began)),
+            log,
+            memo,
         }
     }
+
+    /// Apply `maxDurationMs` and `idleTransactionMs`.
+    pub fn watch(self, timeouts: &TxTimeoutsRef, watch: Watch) -> napi::Result<Self> {
+        timeouts.watch(&self.tx, &self.writes, &self.pending, &self.activity, watch)?;
+        Ok(self)
+    }
+
+    /// The rows of a sync query in a memoizing transaction, collected so
+    /// they can be kept.
+    fn memoized_rows(&self, sql: &str, params: TaskParams) -> napi::Result<CollectedRows> {
+        let memory = self.worker.memory();
+        self.memo.rows(sql, params, memory, |params| {
+            let rows = with_tx(&self.tx, |tx| {
+                params.query_on_tx(tx, sql).map_err(from_core)
+            })?;
+            Ok(collect_all_rows(
+                rows,
+                &self.interrupts.ticket(),
+                memory,
+                None,
+                Page::default().max_rows(self.worker.max_rows()),
+            )?
+            .column_names(self.worker.names()))
+        })
+    }
 }
 
 #[napi]
@@ -53,13 +136,63 @@ impl JsTransaction {
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<TxExecTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(TxExecTask {
-            tx: self.tx.clone(),
-            sql,
-            params: task_params,
-        }))
+    ) -> napi::Result<Scheduled<TxExecTask>> {
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let tables = self.worker.writes().tables_in(&sql);
+        Ok(self.worker.schedule_tx_write(
+            "execute",
+            tables,
+            &self.writes,
+            TxExecTask {
+                tx: self.tx.clone(),
+                sql,
+                params: task_params,
+                changes: Arc::clone(&self.changes),
+                pending: Arc::clone(&self.pending),
+                profiler: Arc::clone(&self.profiler),
+                log: self.log.clone(),
+                ticket: self.interrupts.ticket(),
+                memo: self.memo.clone(),
+            },
+        ))
+    }
+
+    /// Execute the same SQL with multiple param sets within the transaction.
+    /// Returns Promise<{ changes: number }>.
+    ///
+    /// Like `executeBatchSync()`: the SQL is parsed once and the params are
+    /// converted up front, then every set runs off the event loop.
+    #[napi(
+        js_name = "executeBatch",
+        ts_args_type = "sql: string, paramsArray: any[][]",
+        ts_return_type = "Promise<RunResult>"
+    )]
+    pub fn execute_batch(
+        &self,
+        env: Env,
+        sql: String,
+        params_array: RawParam,
+    ) -> napi::Result<Scheduled<TxExecuteBatchTask>> {
+        self.activity.touch();
+        let tables = self.worker.writes().tables_in(&sql);
+        let batch = Batch::parse(&env, self.worker.types(), sql, &self.policy, params_array)?;
+        Ok(self.worker.schedule_tx_write(
+            "executeBatch",
+            tables,
+            &self.writes,
+            TxExecuteBatchTask {
+                tx: self.tx.clone(),
+                batch: Some(batch),
+                changes: Arc::clone(&self.changes),
+                pending: Arc::clone(&self.pending),
+                profiler: Arc::clone(&self.profiler),
+                log: self.log.clone(),
+                ticket: self.interrupts.ticket(),
+                memo: self.memo.clone(),
+            },
+        ))
     }
 
     /// Query rows within the transaction.
@@ -73,13 +206,28 @@ impl JsTransaction {
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<TxQueryTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(TxQueryTask {
-            tx: self.tx.clone(),
-            sql,
-            params: task_params,
-        }))
+        options: Option<QueryOptions>,
+    ) -> napi::Result<Scheduled<TxQueryTask>> {
+        reject_as_of(options.as_ref())?;
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        Ok(self.worker.schedule(
+            "query",
+            TxQueryTask {
+                tx: self.tx.clone(),
+                db: Arc::clone(&self.db),
+                sql,
+                params: task_params,
+                profiler: Arc::clone(&self.profiler),
+                log: self.log.clone(),
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.worker.names(),
+                max_rows: self.worker.max_rows(),
+                memo: self.memo.clone(),
+            },
+        ))
     }
 
     /// Query a single row within the transaction.
@@ -94,13 +242,27 @@ impl JsTransaction {
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<TxQueryOneTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(TxQueryOneTask {
-            tx: self.tx.clone(),
-            sql,
-            params: task_params,
-        }))
+        options: Option<QueryOptions>,
+    ) -> napi::Result<Scheduled<TxQueryOneTask>> {
+        reject_as_of(options.as_ref())?;
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        Ok(self.worker.schedule(
+            "queryOne",
+            TxQueryOneTask {
+                tx: self.tx.clone(),
+                db: Arc::clone(&self.db),
+                sql,
+                params: task_params,
+                profiler: Arc::clone(&self.profiler),
+                log: self.log.clone(),
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.worker.names(),
+                memo: self.memo.clone(),
+            },
+        ))
     }
 
     /// Query rows in raw format within the transaction.
@@ -115,29 +277,57 @@ impl JsTransaction {
         env: Env,
         sql: String,
         params: Option<RawParam>,
-    ) -> napi::Result<AsyncTask<TxQueryRawTask>> {
-        let task_params = convert_params(&env, params)?;
-        Ok(AsyncTask::new(TxQueryRawTask {
-            tx: self.tx.clone(),
-            sql,
-            params: task_params,
-        }))
+        options: Option<QueryOptions>,
+    ) -> napi::Result<Scheduled<TxQueryRawTask>> {
+        reject_as_of(options.as_ref())?;
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        Ok(self.worker.schedule(
+            "queryRaw",
+            TxQueryRawTask {
+                tx: self.tx.clone(),
+                db: Arc::clone(&self.db),
+                sql,
+                params: task_params,
+                profiler: Arc::clone(&self.profiler),
+                log: self.log.clone(),
+                ticket: self.interrupts.ticket(),
+                memory: Arc::clone(self.worker.memory()),
+                names: self.worker.names(),
+                max_rows: self.worker.max_rows(),
+                memo: self.memo.clone(),
+            },
+        ))
     }
 
     /// Commit the transaction. Returns Promise<void>.
     #[napi(ts_return_type = "Promise<void>")]
-    pub fn commit(&self) -> AsyncTask<CommitTask> {
-        AsyncTask::new(CommitTask {
-            tx: self.tx.clone(),
-        })
+    pub fn commit(&self) -> Scheduled<CommitTask> {
+        self.memo.clear();
+        self.worker.schedule(
+            "commit",
+            CommitTask {
+                tx: self.tx.clone(),
+                changes: Arc::clone(&self.changes),
+                pending: Arc::clone(&self.pending),
+                writes: Arc::clone(&self.writes),
+            },
+        )
     }
 
     /// Rollback the transaction. Returns Promise<void>.
     #[napi(ts_return_type = "Promise<void>")]
-    pub fn rollback(&self) -> AsyncTask<RollbackTask> {
-        AsyncTask::new(RollbackTask {
-            tx: self.tx.clone(),
-        })
+    pub fn rollback(&self) -> Scheduled<RollbackTask> {
+        self.memo.clear();
+        self.worker.schedule(
+            "rollback",
+            RollbackTask {
+                tx: self.tx.clone(),
+                pending: Arc::clone(&self.pending),
+                writes: Arc::clone(&self.writes),
+            },
+        )
     }
 
     // ================================================================
@@ -156,7 +346,14 @@ impl JsTransaction {
         sql: String,
         params: Option<RawParam>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&env, params)?;
+        let _busy = self.worker.busy().time("executeSync");
+        let _reviving = self.worker.types().reviving();
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let timer = Instant::now();
+        let started = self.profiler.start_with(&task_params);
+        let logged = self.log.keep(&sql, &task_params);
         let changes = {
             let mut guard = self
                 .tx
@@ -165,9 +362,20 @@ impl JsTransaction {
             let tx = guard
                 .as_mut()
                 .ok_or_else(|| napi::Error::from_reason("Transaction is no longer active"))?;
-            task_params.execute_on_tx(tx, &sql)?
+            let executed = if self.changes.is_active() {
+                let mut pending = lock_pending(&self.pending)?;
+                self.changes
+                    .execute_on_tx(tx, task_params, &sql, &mut pending)
+            } else {
+                task_params.execute_on_tx(tx, &sql).map_err(from_core)
+            };
+            self.memo.clear();
+            executed?
         };
-        Ok(RawJsValue(v8_run_result(changes)))
+        self.profiler.finish_write(started, &sql, changes, None);
+        self.log.push(logged);
+        let outcome = RunOutcome::new(timer, changes);
+        Ok(RawJsValue(run_result(env.raw(), &outcome)?))
     }
 
     /// Query rows synchronously. Returns Array<Object>.
@@ -179,22 +387,39 @@ impl JsTransaction {
     )]
     pub fn query_sync(
         &self,
-        _env: Env,
+        env: Env,
         sql: String,
         params: Option<RawParam>,
+        options: Option<QueryOptions>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = {
-            let mut guard = self
-                .tx
-                .lock()
-                .map_err(|_| napi::Error::from_reason("Transaction lock poisoned"))?;
-            let tx = guard
-                .as_mut()
-                .ok_or_else(|| napi::Error::from_reason("Transaction is no longer active"))?;
-            task_params.query_on_tx(tx, &sql)?
+        reject_as_of(options.as_ref())?;
+        let _busy = self.worker.busy().time("querySync");
+        let _reviving = self.worker.types().reviving();
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let _statement = result_filter::statement(&self.db, &sql, None);
+        let started = self.profiler.start_with(&task_params);
+        let logged = self.log.keep(&sql, &task_params);
+        let (value, count) = if self.memo.is_enabled() {
+            let rows = self.memoized_rows(&sql, task_params)?;
+            (collected_rows_to_array(env.raw(), &rows)?, rows.row_count())
+        } else {
+            let rows = with_tx(&self.tx, |tx| {
+                task_params.query_on_tx(tx, &sql).map_err(from_core)
+            })?;
+            streaming_rows_to_array(
+                env.raw(),
+                rows,
+                false,
+                self.worker.names(),
+                None,
+                Page::default().max_rows(self.worker.max_rows()),
+            )?
         };
-        Ok(RawJsValue(v8_streaming_rows_to_array(rows)))
+        self.profiler.finish(started, &sql, count as i64, None);
+        self.log.push(logged);
+        Ok(RawJsValue(value))
     }
 
     /// Query a single row synchronously. Returns Object | null.
@@ -206,22 +431,50 @@ impl JsTransaction {
     )]
     pub fn query_one_sync(
         &self,
-        _env: Env,
+        env: Env,
         sql: String,
         params: Option<RawParam>,
+        options: Option<QueryOptions>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = {
-            let mut guard = self
-                .tx
-                .lock()
-                .map_err(|_| napi::Error::from_reason("Transaction lock poisoned"))?;
-            let tx = guard
-                .as_mut()
-                .ok_or_else(|| napi::Error::from_reason("Transaction is no longer active"))?;
-            task_params.query_on_tx(tx, &sql)?
+        reject_as_of(options.as_ref())?;
+        let _busy = self.worker.busy().time("queryOneSync");
+        let _reviving = self.worker.types().reviving();
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let _statement = result_filter::statement(&self.db, &sql, None);
+        let started = self.profiler.start_with(&task_params);
+        let logged = self.log.keep(&sql, &task_params);
+        let (value, count) = if self.memo.is_enabled() {
+            let memory = self.worker.memory();
+            let names = self.worker.names();
+            let row = self.memo.row(&sql, task_params, memory, |params| {
+                let rows = with_tx(&self.tx, |tx| {
+                    params.query_on_tx(tx, &sql).map_err(from_core)
+                })?;
+                Ok(collect_single_row_data(
+                    rows,
+                    &self.interrupts.ticket(),
+                    memory,
+                    None,
+                    Page::default(),
+                )?
+                .map(|rows| rows.column_names(names)))
+            })?;
+            let count = row.is_some() as usize;
+            (
+                collected_single_row_to_js(env.raw(), row, &RowShape::Object)?,
+                count,
+            )
+        } else {
+            let rows = with_tx(&self.tx, |tx| {
+                task_params.query_on_tx(tx, &sql).map_err(from_core)
+            })?;
+            single_row_or_null(env.raw(), rows, self.worker.names(), None, Page::default())?
         };
-        Ok(RawJsValue(v8_single_row_or_null(rows)))
+        self.profiler.finish(started, &sql, count as i64, None);
+        self.log.push(logged);
+        Ok(RawJsValue(value))
     }
 
     /// Query rows in raw format synchronously. Returns { columns: string[], rows: any[][] }.
@@ -233,27 +486,46 @@ impl JsTransaction {
     )]
     pub fn query_raw_sync(
         &self,
-        _env: Env,
+        env: Env,
         sql: String,
         params: Option<RawParam>,
+        options: Option<QueryOptions>,
     ) -> napi::Result<RawJsValue> {
-        let task_params = convert_params(&_env, params)?;
-        let rows = {
-            let mut guard = self
-                .tx
-                .lock()
-                .map_err(|_| napi::Error::from_reason("Transaction lock poisoned"))?;
-            let tx = guard
-                .as_mut()
-                .ok_or_else(|| napi::Error::from_reason("Transaction is no longer active"))?;
-            task_params.query_on_tx(tx, &sql)?
+        reject_as_of(options.as_ref())?;
+        let _busy = self.worker.busy().time("queryRawSync");
+        let _reviving = self.worker.types().reviving();
+        self.activity.touch();
+        check_single_sql(&self.policy, &sql)?;
+        let (sql, task_params) = convert_params(&env, self.worker.types(), sql, params)?;
+        let _statement = result_filter::statement(&self.db, &sql, None);
+        let started = self.profiler.start_with(&task_params);
+        let logged = self.log.keep(&sql, &task_params);
+        let (value, count) = if self.memo.is_enabled() {
+            let rows = self.memoized_rows(&sql, task_params)?;
+            (collected_rows_to_raw(env.raw(), &rows)?, rows.row_count())
+        } else {
+            let rows = with_tx(&self.tx, |tx| {
+                task_params.query_on_tx(tx, &sql).map_err(from_core)
+            })?;
+            streaming_rows_to_raw(
+                env.raw(),
+                rows,
+                false,
+                self.worker.names(),
+                None,
+                Page::default().max_rows(self.worker.max_rows()),
+            )?
         };
-        Ok(RawJsValue(v8_streaming_rows_to_raw(rows)))
+        self.profiler.finish(started, &sql, count as i64, None);
+        self.log.push(logged);
+        Ok(RawJsValue(value))
     }
 
     /// Commit the transaction synchronously.
     #[napi(js_name = "commitSync")]
     pub fn commit_sync(&self) -> napi::Result<()> {
+        let _busy = self.worker.busy().time("commitSync");
+        self.memo.clear();
         let mut guard = self
             .tx
             .lock()
@@ -261,7 +533,14 @@ impl JsTransaction {
         let mut tx = guard
             .take()
             .ok_or_else(|| napi::Error::from_reason("Transaction is no longer active"))?;
-        tx.commit().map_err(to_napi)
+        let committed = tx.commit().map_err(to_napi);
+        drop(tx);
+        drop(guard);
+        self.writes.release();
+        committed?;
+        let events = std::mem::take(&mut *lock_pending(&self.pending)?);
+        self.changes.emit(events);
+        Ok(())
     }
 
     /// Execute the same SQL with multiple param sets in a single call.
@@ -278,30 +557,10 @@ impl JsTransaction {
         sql: String,
         params_array: RawParam,
     ) -> napi::Result<RawJsValue> {
-        use napi::sys;
-        use stoolap::parser::Parser;
-        let raw_env = env.raw();
-        let arr = params_array.0;
-
-        let mut is_array = false;
-        check(unsafe { sys::napi_is_array(raw_env, arr, &mut is_array) })?;
-        if !is_array {
-            return Err(napi::Error::from_reason("paramsArray must be an array"));
-        }
-
-        let mut len = 0u32;
-        check(unsafe { sys::napi_get_array_length(raw_env, arr, &mut len) })?;
-
-        // Parse SQL once for all executions
-        let mut parser = Parser::new(&sql);
-        let program = parser
-            .parse_program()
-            .map_err(|e| napi::Error::from_reason(e.to_string()))?;
-        let stmt = program
-            .statements
-            .first()
-            .ok_or_else(|| napi::Error::from_reason("No SQL statement found"))?;
-
+        let _busy = self.worker.busy().time("executeBatchSync");
+        self.activity.touch();
+        let batch = Batch::parse(&env, self.worker.types(), sql, &self.policy, params_array)?;
+        let logged = self.log.keep_batch(&batch);
         let mut guard = self
             .tx
             .lock()
@@ -309,21 +568,54 @@ impl JsTransaction {
         let tx = guard
             .as_mut()
             .ok_or_else(|| napi::Error::from_reason("Transaction is no longer active"))?;
+        let mut pending = lock_pending(&self.pending)?;
+        let outcome = batch.run_on_tx(
+            tx,
+            &self.changes,
+            &mut pending,
+            &self.profiler,
+            &self.interrupts.ticket(),
+        );
+        drop(pending);
+        drop(guard);
+        self.memo.clear();
+        let outcome = outcome?;
+        self.log.push(logged);
+        Ok(RawJsValue(run_result(env.raw(), &outcome)?))
+    }
 
-        let mut total_changes = 0i64;
-        for i in 0..len {
-            let mut elem = std::ptr::null_mut();
-            check(unsafe { sys::napi_get_element(raw_env, arr, i, &mut elem) })?;
-            let params = parse_positional(raw_env, elem)?;
-            total_changes += tx.execute_prepared(stmt, params).map_err(to_napi)?;
-        }
+    /// Whether the transaction can still run statements: false once it has
+    /// been committed or rolled back.
+    #[napi(getter)]
+    pub fn active(&self) -> bool {
+        self.tx.lock().map(|tx| tx.is_some()).unwrap_or(false)
+    }
+
+    /// `active`, named like `db.isOpen`.
+    #[napi(getter, js_name = "isActive")]
+    pub fn is_active(&self) -> bool {
+        self.active()
+    }
 
-        Ok(RawJsValue(v8_run_result(total_changes)))
+    /// Results kept for repeated SELECTs, and how often one was reused.
+    /// Zero unless the transaction was begun with `memoize: true`.
+    #[napi(getter, js_name = "memoStats")]
+    pub fn memo_stats(&self) -> MemoStats {
+        self.memo.stats()
+    }
+
+    /// The statements the transaction has run so far, as one SQL script.
+    /// Needs `recordSql: true` in the `begin()` options.
+    #[napi(js_name = "toSql")]
+    pub fn to_sql(&self, options: Option<ToSqlOptions>) -> napi::Result<String> {
+        self.log.to_sql(options)
     }
 
     /// Rollback the transaction synchronously.
     #[napi(js_name = "rollbackSync")]
     pub fn rollback_sync(&self) -> napi::Result<()> {
+        let _busy = self.worker.busy().time("rollbackSync");
+        self.memo.clear();
         let mut guard = self
             .tx
             .lock()
@@ -331,16 +623,30 @@ impl JsTransaction {
         let mut tx = guard
             .take()
             .ok_or_else(|| napi::Error::from_reason("Transaction is no longer active"))?;
-        tx.rollback().map_err(to_napi)
+        lock_pending(&self.pending)?.clear();
+        let rolled_back = tx.rollback().map_err(to_napi);
+        drop(tx);
+        drop(guard);
+        self.writes.release();
+        rolled_back
     }
 }
 
-fn convert_params(env: &Env, params: Option<RawParam>) -> napi::Result<TaskParams> {
-    match params {
-        None => Ok(TaskParams::Positional(ParamVec::new())),
-        Some(p) => match parse_params(env.raw(), p.0)? {
-            BindParams::Positional(pos) => Ok(TaskParams::Positional(pos)),
-            BindParams::Named(n) => Ok(TaskParams::Named(n)),
-        },
-    }
+/// Convert JS params to TaskParams, expanding arrays bound to `IN (?)` lists
+/// into the SQL.
+fn convert_params(
+    env: &Env,
+    types: &Types,
+    sql: String,
+    params: Option<RawParam>,
+) -> napi::Result<(String, TaskParams)> {
+    let Some(p) = params else {
+        return Ok((sql, TaskParams::Positional(ParamVec::new())));
+    };
+    let (params, arrays) = parse_params(env.raw(), p.0, types)?;
+    let params = match params {
+        BindParams::Positional(pos) => TaskParams::Positional(pos),
+        BindParams::Named(n) => TaskParams::Named(n),
+    };
+    Ok(expand_in_lists(&sql, &params, &arrays).unwrap_or((sql, params)))
 }
@@ -0,0 +1,247 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.tree()`: adjacency-list tables read back as nested objects.
+//
+// One recursive CTE walks down from the roots, carrying each row's depth in
+// an extra column. The recursive step is written as a comma join: the
+// engine's JOIN ... ON form stops after one level when the key is TEXT. The rows come back ordered by depth then id, so every
+// parent is seen before its children and siblings stay in id order. Nodes
+// are keyed by id and only their first occurrence is kept, so a cycle below
+// a root adds no duplicates; the engine stops the recursion after its
+// iteration limit, or sooner with `maxDepth`.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+
+use napi::Env;
+use stoolap::api::Database;
+use stoolap::{ParamVec, Value};
+
+use crate::column_names::ColumnNames;
+use crate::error::to_napi;
+use crate::interrupt::{Ticket, CHECK_INTERVAL};
+use crate::policy::{check_sql, PolicyRef};
+use crate::profile::ProfilerRef;
+use crate::sql::{primary_key, quote_ident};
+use crate::tasks::{check, row_object};
+use crate::value::{js_to_value, RawParam};
+
+/// Name of the depth column added by the CTE. Not included in the nodes.
+const DEPTH_COLUMN: &str = "$depth";
+
+/// Options for `tree()` / `treeSync()`.
+#[napi(object, object_to_js = false)]
+pub struct TreeOptions {
+    /// Column holding the parent row's id.
+    pub parent_column: String,
+    /// Column the parent column refers to. Defaults to the table's
+    /// single-column primary key.
+    pub id_column: Option<String>,
+    /// Id of the row to start from. Defaults to every row whose parent is NULL.
+    #[napi(ts_type = "any")]
+    pub root_id: Option<RawParam>,
+    /// Levels below the roots to include; 0 returns the roots alone.
+    pub max_depth: Option<u32>,
+    /// Property that holds each node's children (default `children`).
+    pub children_key: Option<String>,
+}
+
+/// A resolved `tree` call.
+pub struct Tree {
+    table: String,
+    parent_column: String,
+    id_column: Option<String>,
+    root_id: Option<Value>,
+    max_depth: Option<u32>,
+    children_key: String,
+}
+
+impl Tree {
+    pub fn new(env: &Env, table: String, options: TreeOptions) -> napi::Result<Self> {
+        let root_id = match options.root_id {
+            Some(raw) => Some(js_to_value(env.raw(), raw.0)?).filter(|v| !v.is_null()),
+            None => None,
+        };
+        Ok(Self {
+            table,
+            parent_column: options.parent_column,
+            id_column: options.id_column,
+            root_id,
+            max_depth: options.max_depth,
+            children_key: options
+                .children_key
+                .unwrap_or_else(|| "children".to_string()),
+        })
+    }
+
+    /// Run the recursive query and link the rows into trees.
+    pub fn run(
+        self,
+        db: &Database,
+        policy: &PolicyRef,
+        profiler: &ProfilerRef,
+        ticket: &Ticket,
+    ) -> napi::Result<TreeRows> {
+        let id_column = match self.id_column {
+            Some(column) => column,
+            None => primary_key(db, &self.table)?
+                .map(|pk| pk.name)
+                .ok_or_else(|| {
+                    napi::Error::from_reason(format!(
+                        "Table '{}' has no single-column primary key; pass options.idColumn",
+                        self.table
+                    ))
+                })?,
+        };
+        let table = quote_ident(&self.table);
+        let id = quote_ident(&id_column);
+        let parent = quote_ident(&self.parent_column);
+        let depth = quote_ident(DEPTH_COLUMN);
+
+        let mut params = ParamVec::new();
+        let anchor = match self.root_id {
+            Some(root_id) => {
+                params.push(root_id);
+                format!("{id} = $1")
+            }
+            None => format!("{parent} IS NULL"),
+        };
+        let limit = match self.max_depth {
+            Some(max_depth) => {
+                params.push(Value::Integer(max_depth as i64));
+                format!(" AND p.{depth} < ${}", params.len())
+            }
+            None => String::new(),
+        };
+        let sql = format!(
+            "WITH RECURSIVE \"tree\" AS (\
+             SELECT *, 0 AS {depth} FROM {table} WHERE {anchor} \
+             UNION ALL \
+             SELECT c.*, p.{depth} + 1 FROM {table} c, \"tree\" p WHERE c.{parent} = p.{id}{limit}\
+             ) SELECT * FROM \"tree\" ORDER BY {depth}, {id}"
+        );
+        check_sql(policy, &sql)?;
+
+        let started = profiler.start_positional(&params);
+        let rows = db.query(&sql, params).map_err(to_napi)?;
+        let tree = TreeRows::link(
+            rows,
+            &id_column,
+            &self.parent_column,
+            self.children_key,
+            ticket,
+        )?;
+        profiler.finish(started, &sql, tree.nodes.len() as i64, None);
+        Ok(tree)
+    }
+}
+
+/// Rows of a `tree` call, linked by index.
+pub struct TreeRows {
+    columns: Vec<String>,
+    children_key: String,
+    nodes: Vec<Vec<Value>>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+impl TreeRows {
+    fn link(
+        mut rows: stoolap::Rows,
+        id_column: &str,
+        parent_column: &str,
+        children_key: String,
+        ticket: &Ticket,
+    ) -> napi::Result<Self> {
+        let mut columns = rows.columns().to_vec();
+        let position = |name: &str| {
+            columns
+                .iter()
+                .position(|c| c.eq_ignore_ascii_case(name))
+                .ok_or_else(|| napi::Error::from_reason(format!("Column '{name}' not found")))
+        };
+        let id_pos = position(id_column)?;
+        let parent_pos = position(parent_column)?;
+        let depth_pos = position(DEPTH_COLUMN)?;
+        columns.remove(depth_pos);
+
+        let mut tree = Self {
+            columns,
+            children_key,
+            nodes: Vec::new(),
+            children: Vec::new(),
+            roots: Vec::new(),
+        };
+        let mut by_id: HashMap<Value, usize> = HashMap::new();
+        let mut count = 0;
+        ticket.check()?;
+        while rows.advance() {
+            count += 1;
+            if count % CHECK_INTERVAL == 0 {
+                ticket.check()?;
+            }
+            let mut values = rows.current_row().as_slice().to_vec();
+            let depth = values.remove(depth_pos);
+            let id = values[id_pos].clone();
+            if by_id.contains_key(&id) {
+                continue;
+            }
+            let index = tree.nodes.len();
+            if depth == Value::Integer(0) {
+                tree.roots.push(index);
+            } else {
+                match by_id.get(&values[parent_pos]) {
+                    Some(&parent) => tree.children[parent].push(index),
+                    None => continue,
+                }
+            }
+            by_id.insert(id, index);
+            tree.nodes.push(values);
+            tree.children.push(Vec::new());
+        }
+        Ok(tree)
+    }
+
+    /// Build the JS array of root nodes, each with its children nested under
+    /// `childrenKey`.
+    pub fn to_js(
+        &self,
+        env: napi::sys::napi_env,
+        names: ColumnNames,
+    ) -> napi::Result<napi::sys::napi_value> {
+        use napi::sys;
+        let key = CString::new(self.children_key.as_str())
+            .map_err(|_| napi::Error::from_reason("childrenKey must not contain NUL"))?;
+        let columns = names.keys(&self.columns)?;
+        let objects = self
+            .nodes
+            .iter()
+            .map(|values| row_object(env, &columns, values))
+            .collect::<napi::Result<Vec<_>>>()?;
+        let array = |indexes: &[usize]| -> napi::Result<sys::napi_value> {
+            let mut array = std::ptr::null_mut();
+            check(unsafe { sys::napi_create_array_with_length(env, indexes.len(), &mut array) })?;
+            for (i, &index) in indexes.iter().enumerate() {
+                check(unsafe { sys::napi_set_element(env, array, i as u32, objects[index]) })?;
+            }
+            Ok(array)
+        };
+        for (object, children) in objects.iter().zip(&self.children) {
+            let children = array(children)?;
+            check(unsafe { sys::napi_set_named_property(env, *object, key.as_ptr(), children) })?;
+        }
+        array(&self.roots)
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `memoize` transaction option.
+//
+// A transaction begun with `memoize: true` runs at snapshot isolation, so its
+// reads see the data as it was when it began, plus its own writes. Until it
+// writes, a SELECT run again with the same SQL text and parameters returns
+// the same rows, so the rows of the first run are kept and copied out for
+// the later ones without running the statement. `query()` and `queryRaw()`
+// share their results, as do their sync versions; `queryOne()` keeps its own,
+// since it reads only the first row. Every statement the transaction
+// executes, and every query that isn't a SELECT, clears what it kept, and a
+// SELECT that calls NOW(), RANDOM() or the like is never kept. Kept rows
+// count against the memory budget until they are cleared, at the latest
+// when the transaction commits or rolls back.
+//
+// The clear comes after the statement ran, and bumps a generation: rows are
+// only kept when it hasn't moved since their statement started, so a read
+// that overlapped a write isn't kept.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use stoolap::api::{Database, Transaction as ApiTransaction};
+use stoolap::IsolationLevel;
+
+use crate::memory::MemoryBudgetRef;
+use crate::sql::{calls_volatile, is_select};
+use crate::tasks::{CollectedRows, TaskParams};
+
+/// Snapshot returned by `tx.memoStats`.
+#[napi(object, object_from_js = false)]
+pub struct MemoStats {
+    /// Results kept.
+    pub entries: u32,
+    /// Reads answered from a kept result.
+    pub hits: i64,
+    /// Reads that ran their statement.
+    pub misses: i64,
+}
+
+/// A read a memoizing transaction can keep: `queryOne()` or the others, its
+/// SQL and its parameters.
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+    one: bool,
+    sql: String,
+    params: String,
+}
+
+#[derive(Default)]
+struct Memo {
+    entries: Mutex<HashMap<Key, CollectedRows>>,
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// The results a transaction keeps, if it memoizes. Clones share them.
+#[derive(Clone, Default)]
+pub struct QueryMemo(Option<Arc<Memo>>);
+
+impl QueryMemo {
+    pub fn new(memoize: bool) -> Self {
+        Self(memoize.then(Arc::default))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Begin the transaction the memo is for on `db`.
+    pub fn begin(&self, db: &Database) -> stoolap::Result<ApiTransaction> {
+        match self.0 {
+            Some(_) => db.begin_with_isolation(IsolationLevel::SnapshotIsolation),
+            None => db.begin(),
+        }
+    }
+
+    /// The rows of `sql` with `params`: kept ones when there are, else those
+    /// `run` reads, kept for the next time.
+    pub fn rows(
+        &self,
+        sql: &str,
+        params: TaskParams,
+        memory: &MemoryBudgetRef,
+        run: impl FnOnce(TaskParams) -> napi::Result<CollectedRows>,
+    ) -> napi::Result<CollectedRows> {
+        self.kept(false, sql, params, memory, run)
+    }
+
+    /// `rows()` for `queryOne()`, whose `run` reads the first row if any.
+    pub fn row(
+        &self,
+        sql: &str,
+        params: TaskParams,
+        memory: &MemoryBudgetRef,
+        run: impl FnOnce(TaskParams) -> napi::Result<Option<CollectedRows>>,
+    ) -> napi::Result<Option<CollectedRows>> {
+        // No row is kept as no rows.
+        let rows = self.kept(true, sql, params, memory, |params| {
+            Ok(run(params)?
+                .unwrap_or_else(|| CollectedRows::new(Vec::new(), Vec::new(), memory.charge())))
+        })?;
+        Ok((rows.row_count() > 0).then_some(rows))
+    }
+
+    fn kept(
+        &self,
+        one: bool,
+        sql: &str,
+        params: TaskParams,
+        memory: &MemoryBudgetRef,
+        run: impl FnOnce(TaskParams) -> napi::Result<CollectedRows>,
+    ) -> napi::Result<CollectedRows> {
+        let Some(memo) = &self.0 else {
+            return run(params);
+        };
+        if !is_select(sql) {
+            // Such as an INSERT with RETURNING.
+            let rows = run(params);
+            self.clear();
+            return rows;
+        }
+        if calls_volatile(sql) {
+            return run(params);
+        }
+        let key = Key {
+            one,
+            sql: sql.to_string(),
+            params: match &params {
+                TaskParams::Positional(values) => format!("{values:?}"),
+                TaskParams::Named(values) => format!("{values:?}"),
+            },
+        };
+        if let Some(kept) = lock(&memo.entries).get(&key) {
+            memo.hits.fetch_add(1, Ordering::Relaxed);
+            return kept.copy(memory);
+        }
+        memo.misses.fetch_add(1, Ordering::Relaxed);
+        let generation = memo.generation.load(Ordering::Acquire);
+        let rows = run(params)?;
+        let kept = rows.copy(memory)?;
+        let mut entries = lock(&memo.entries);
+        if memo.generation.load(Ordering::Acquire) == generation {
+            entries.insert(key, kept);
+        }
+        Ok(rows)
+    }
+
+    /// Drop the kept results, once the transaction has executed a statement
+    /// or as it ends.
+    pub fn clear(&self) {
+        if let Some(memo) = &self.0 {
+            let mut entries = lock(&memo.entries);
+            memo.generation.fetch_add(1, Ordering::AcqRel);
+            entries.clear();
+        }
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        match &self.0 {
+            Some(memo) => MemoStats {
+                entries: lock(&memo.entries).len() as u32,
+                hits: memo.hits.load(Ordering::Relaxed) as i64,
+                misses: memo.misses.load(Ordering::Relaxed) as i64,
+            },
+            None => MemoStats {
+                entries: 0,
+                hits: 0,
+                misses: 0,
+            },
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,141 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `tx.toSql()`.
+//
+// A transaction begun with `recordSql: true` keeps every statement it runs
+// successfully, with a copy of its parameters, and `toSql()` renders them in
+// the order they ran as one script, each statement ended by `;`. By default
+// the parameters are inlined as SQL literals (see `sql::literal()`), so the
+// script reads, and runs, as it is, and a batch becomes one statement per
+// row. With `params: 'placeholders'` the SQL is kept as it ran and a comment
+// after it holds the parameters, encoded as recordings encode them (see
+// recording.rs). Copying the parameters costs, so a transaction keeps
+// nothing unless asked. The log outlives commit and rollback.
+
+use std::sync::{Arc, Mutex};
+
+use crate::batch::Batch;
+use crate::recording::{encode_params, Recorded};
+use crate::sql::{ends_in_line_comment, inline_params};
+use crate::tasks::TaskParams;
+
+/// Options for `tx.toSql()`.
+#[napi(object, object_to_js = false)]
+pub struct ToSqlOptions {
+    /// `inline` writes the parameters into the SQL as literals,
+    /// `placeholders` keeps the SQL as it ran and the parameters in a
+    /// comment after it (default `inline`).
+    #[napi(ts_type = "'inline' | 'placeholders'")]
+    pub params: Option<String>,
+}
+
+/// A statement a transaction ran, and its parameters.
+pub struct Logged {
+    sql: String,
+    params: Recorded,
+}
+
+/// The statements a transaction ran, if it keeps them. Clones share the log.
+#[derive(Clone, Default)]
+pub struct SqlLog(Option<Arc<Mutex<Vec<Logged>>>>);
+
+impl SqlLog {
+    pub fn new(record: bool) -> Self {
+        Self(record.then(Arc::default))
+    }
+
+    /// `sql` and a copy of `params`, to log once the statement succeeds;
+    /// `None` when the transaction keeps no log.
+    pub fn keep(&self, sql: &str, params: &TaskParams) -> Option<Logged> {
+        self.0.as_ref().map(|_| Logged {
+            sql: sql.to_string(),
+            params: Recorded::Params(params.clone()),
+        })
+    }
+
+    /// `keep()` for `executeBatch()`.
+    pub fn keep_batch(&self, batch: &Batch) -> Option<Logged> {
+        self.0.as_ref().map(|_| Logged {
+            sql: batch.sql().to_string(),
+            params: Recorded::Batch(batch.rows().to_vec()),
+        })
+    }
+
+    pub fn push(&self, logged: Option<Logged>) {
+        if let (Some(log), Some(logged)) = (&self.0, logged) {
+            lock(log).push(logged);
+        }
+    }
+
+    /// The script `toSql()` returns.
+    pub fn to_sql(&self, options: Option<ToSqlOptions>) -> napi::Result<String> {
+        let inline = match options.and_then(|o| o.params).as_deref() {
+            None | Some("inline") => true,
+            Some("placeholders") => false,
+            Some(_) => {
+                return Err(napi::Error::from_reason(
+                    "params must be 'inline' or 'placeholders'",
+                ))
+            }
+        };
+        let Some(log) = &self.0 else {
+            return Err(napi::Error::from_reason(
+                "toSql() needs a transaction begun with recordSql: true",
+            ));
+        };
+        let mut script = String::new();
+        for logged in lock(log).iter() {
+            let sql = statement(&logged.sql);
+            match (&logged.params, inline) {
+                (Recorded::Params(params), true) => {
+                    push_statement(&mut script, &inline_params(sql, params))
+                }
+                (Recorded::Batch(rows), true) => {
+                    for row in rows {
+                        let params = TaskParams::Positional(row.clone());
+                        push_statement(&mut script, &inline_params(sql, &params));
+                    }
+                }
+                (params, false) => {
+                    push_statement(&mut script, sql);
+                    if let Some((key, params)) = encode_params(params) {
+                        script.push_str(&format!("-- {key}: {params}\n"));
+                    }
+                }
+            }
+        }
+        Ok(script)
+    }
+}
+
+/// `sql` without surrounding whitespace or a trailing `;`.
+fn statement(sql: &str) -> &str {
+    let sql = sql.trim();
+    sql.strip_suffix(';').map_or(sql, str::trim_end)
+}
+
+/// Append `sql` and its `;`, which goes on a line of its own after a `--`
+/// comment.
+fn push_statement(script: &mut String, sql: &str) {
+    script.push_str(sql);
+    if ends_in_line_comment(sql) {
+        script.push('\n');
+    }
+    script.push_str(";\n");
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
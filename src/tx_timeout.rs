@@ -0,0 +1,386 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `maxDurationMs` transaction option, the `idleTransactionMs` open option
+// and their events, `db.on('transactionTimeout')` and
+// `db.on('transactionIdle')`.
+//
+// A transaction left open, e.g. by a request handler that threw before
+// committing, holds its snapshot and any serialized tables until it is
+// garbage collected, which may be never. A guarded transaction that is still
+// open when its time is up is rolled back on a background thread, in the
+// same way as `rollback()`: its tables are released and its pending change
+// events dropped, and later calls on it throw as after any rollback. A
+// statement running at that moment finishes first, and the rollback follows
+// it.
+//
+// With `idleTransactionMs`, every transaction of the database is watched for
+// calls instead: one that has had none for that long gets one warning, with
+// the JS stack of its `begin()`, and another after each later stretch of
+// idleness. A call counts as activity when it is made, and a statement still
+// running is seen as activity when the transaction is checked, so a long
+// statement delays the warning by up to `idleTransactionMs` after it ends.
+//
+// The thread runs only while watched transactions are open.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, TryLockError, Weak};
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use stoolap::api::Transaction as ApiTransaction;
+
+use crate::changes::PendingChanges;
+use crate::write_queue::TxLocksRef;
+
+/// How soon to try a rollback again when a statement holds the transaction.
+const BUSY_RETRY: Duration = Duration::from_millis(10);
+
+/// A transaction rolled back by its `maxDurationMs`, for
+/// `db.on('transactionTimeout')`.
+#[napi(object, object_from_js = false)]
+#[derive(Clone)]
+pub struct TransactionTimeoutEvent {
+    pub max_duration_ms: f64,
+    /// Time from begin to the rollback; longer than `maxDurationMs` when a
+    /// statement was running.
+    pub duration_ms: f64,
+}
+
+/// A transaction idle for `idleTransactionMs`, for
+/// `db.on('transactionIdle')`.
+#[napi(object, object_from_js = false)]
+#[derive(Clone)]
+pub struct TransactionIdleEvent {
+    /// Time since the transaction's last call.
+    pub idle_ms: f64,
+    /// Time since the transaction began.
+    pub open_ms: f64,
+    /// JS stack trace of the `begin()` or `beginSync()` call.
+    pub stack: String,
+}
+
+pub type TimeoutListener = ThreadsafeFunction<
+    TransactionTimeoutEvent,
+    Unknown<'static>,
+    TransactionTimeoutEvent,
+    Status,
+    false,
+    true,
+>;
+
+pub type IdleListener = ThreadsafeFunction<
+    TransactionIdleEvent,
+    Unknown<'static>,
+    TransactionIdleEvent,
+    Status,
+    false,
+    true,
+>;
+
+pub type TxTimeoutsRef = Arc<TxTimeouts>;
+
+/// Watched transactions of a database and their listeners.
+#[derive(Default)]
+pub struct TxTimeouts {
+    /// `idleTransactionMs`; `None` watches no transaction for idleness.
+    idle_after: Option<Duration>,
+    state: Mutex<State>,
+    /// Every transaction begun, watched or not, for `any_open()`.
+    begun: Mutex<Vec<Weak<Mutex<Option<ApiTransaction>>>>>,
+    wake: Condvar,
+    timeout_listeners: Mutex<Vec<TimeoutListener>>,
+    idle_listeners: Mutex<Vec<IdleListener>>,
+}
+
+#[derive(Default)]
+struct State {
+    watched: Vec<Watched>,
+    /// The watchdog thread is running.
+    running: bool,
+}
+
+pub type ActivityRef = Arc<Activity>;
+
+/// When a transaction last had a call.
+pub struct Activity {
+    began: Instant,
+    /// Microseconds after `began`.
+    last: AtomicU64,
+}
+
+impl Activity {
+    pub fn new(began: Instant) -> Self {
+        Self {
+            began,
+            last: AtomicU64::new(0),
+        }
+    }
+
+    pub fn touch(&self) {
+        let us = self.began.elapsed().as_micros() as u64;
+        self.last.fetch_max(us, Ordering::Relaxed);
+    }
+
+    fn last(&self) -> Instant {
+        self.began + Duration::from_micros(self.last.load(Ordering::Relaxed))
+    }
+}
+
+/// What the watchdog needs to handle one transaction. It doesn't keep the
+/// transaction alive: one that has been collected is gone already.
+struct Watched {
+    tx: Weak<Mutex<Option<ApiTransaction>>>,
+    writes: TxLocksRef,
+    pending: PendingChanges,
+    activity: ActivityRef,
+    /// `maxDurationMs`.
+    max_duration: Option<Duration>,
+    idle: Option<Idle>,
+    due: Instant,
+}
+
+struct Idle {
+    after: Duration,
+    /// JS stack of `begin()`.
+    stack: String,
+    /// The activity last warned about, so each idle stretch warns once.
+    warned: Option<Instant>,
+}
+
+/// What a transaction is watched for, decided at `begin()`.
+pub struct Watch {
+    pub max_duration: Option<Duration>,
+    /// JS stack of `begin()`, when `idleTransactionMs` is set.
+    pub stack: Option<String>,
+}
+
+impl TxTimeouts {
+    pub fn new(idle_after: Option<Duration>) -> Self {
+        Self {
+            idle_after,
+            ..Self::default()
+        }
+    }
+
+    /// Whether transactions are watched for idleness, so `begin()` needs to
+    /// capture its stack.
+    pub fn watches_idle(&self) -> bool {
+        self.idle_after.is_some()
+    }
+
+    pub fn subscribe_timeout(&self, listener: TimeoutListener) {
+        lock(&self.timeout_listeners).push(listener);
+    }
+
+    pub fn subscribe_idle(&self, listener: IdleListener) {
+        lock(&self.idle_listeners).push(listener);
+    }
+
+    /// Whether a transaction begun on the database is still open. One that
+    /// is running a statement counts as open.
+    pub fn any_open(&self) -> bool {
+        lock(&self.begun).iter().any(|tx| {
+            tx.upgrade().is_some_and(|tx| match tx.try_lock() {
+                Ok(tx) => tx.is_some(),
+                Err(TryLockError::Poisoned(e)) => e.into_inner().is_some(),
+                Err(TryLockError::WouldBlock) => true,
+            })
+        })
+    }
+
+    /// Watch `tx` for what `watch` asks for, if anything.
+    pub fn watch(
+        self: &Arc<Self>,
+        tx: &Arc<Mutex<Option<ApiTransaction>>>,
+        writes: &TxLocksRef,
+        pending: &PendingChanges,
+        activity: &ActivityRef,
+        watch: Watch,
+    ) -> napi::Result<()> {
+        {
+            let mut begun = lock(&self.begun);
+            begun.retain(|tx| tx.strong_count() > 0);
+            begun.push(Arc::downgrade(tx));
+        }
+        let idle = self.idle_after.zip(watch.stack).map(|(after, stack)| Idle {
+            after,
+            stack,
+            warned: None,
+        });
+        let mut watched = Watched {
+            tx: Arc::downgrade(tx),
+            writes: Arc::clone(writes),
+            pending: Arc::clone(pending),
+            activity: Arc::clone(activity),
+            max_duration: watch.max_duration,
+            idle,
+            due: activity.began,
+        };
+        let Some(due) = next_due(&watched, Instant::now()) else {
+            return Ok(());
+        };
+        watched.due = due;
+        let mut state = lock(&self.state);
+        state.watched.retain(|w| w.tx.strong_count() > 0);
+        state.watched.push(watched);
+        if state.running {
+            self.wake.notify_one();
+            return Ok(());
+        }
+        let this = Arc::clone(self);
+        std::thread::Builder::new()
+            .name("stoolap-tx-timeout".to_string())
+            .spawn(move || this.run())
+            .map_err(|e| {
+                state.watched.pop();
+                napi::Error::from_reason(format!("Cannot start transaction timeout thread: {e}"))
+            })?;
+        state.running = true;
+        Ok(())
+    }
+
+    /// Watchdog loop: sleep until the next check is due, then make the
+    /// checks. Exits once nothing is watched.
+    fn run(&self) {
+        let mut state = lock(&self.state);
+        loop {
+            let now = Instant::now();
+            let mut i = 0;
+            while i < state.watched.len() {
+                if state.watched[i].due > now {
+                    i += 1;
+                    continue;
+                }
+                let watched = state.watched.swap_remove(i);
+                if let Some(watched) = self.check(watched, now) {
+                    state.watched.push(watched);
+                }
+            }
+            let Some(next) = state.watched.iter().map(|w| w.due).min() else {
+                state.running = false;
+                return;
+            };
+            let wait = next.saturating_duration_since(Instant::now());
+            state = self
+                .wake
+                .wait_timeout(state, wait)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Check a transaction that is due. Returns it again, with its next
+    /// check, unless it has ended.
+    fn check(&self, mut watched: Watched, now: Instant) -> Option<Watched> {
+        let handle = watched.tx.upgrade()?;
+        let slot = match handle.try_lock() {
+            Ok(slot) => slot,
+            Err(TryLockError::WouldBlock) => {
+                // A statement is running: that is activity, and a rollback
+                // has to wait for it.
+                watched.activity.touch();
+                let overdue = watched
+                    .max_duration
+                    .is_some_and(|max| watched.activity.began + max <= now);
+                watched.due = if overdue {
+                    now + BUSY_RETRY
+                } else {
+                    next_due(&watched, now)?
+                };
+                return Some(watched);
+            }
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        };
+        slot.as_ref()?;
+        let open = now.duration_since(watched.activity.began);
+        if watched.max_duration.is_some_and(|max| open >= max) {
+            self.roll_back(&watched, slot);
+            return None;
+        }
+        drop(slot);
+        let last = watched.activity.last();
+        let idle_for = now.saturating_duration_since(last);
+        if let Some(ref mut idle) = watched.idle {
+            if idle_for >= idle.after && idle.warned != Some(last) {
+                idle.warned = Some(last);
+                let event = TransactionIdleEvent {
+                    idle_ms: idle_for.as_secs_f64() * 1000.0,
+                    open_ms: open.as_secs_f64() * 1000.0,
+                    stack: idle.stack.clone(),
+                };
+                for listener in lock(&self.idle_listeners).iter() {
+                    listener.call(event.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+                }
+            }
+        }
+        watched.due = next_due(&watched, now)?;
+        Some(watched)
+    }
+
+    /// Roll back a transaction whose `maxDurationMs` is up.
+    fn roll_back(&self, watched: &Watched, mut slot: MutexGuard<'_, Option<ApiTransaction>>) {
+        let Some(mut tx) = slot.take() else {
+            return;
+        };
+        watched
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        // Rolled back or not, the transaction is gone once dropped.
+        let _ = tx.rollback();
+        drop(tx);
+        drop(slot);
+        watched.writes.release();
+        let event = TransactionTimeoutEvent {
+            max_duration_ms: watched.max_duration.unwrap_or_default().as_secs_f64() * 1000.0,
+            duration_ms: watched.activity.began.elapsed().as_secs_f64() * 1000.0,
+        };
+        for listener in lock(&self.timeout_listeners).iter() {
+            listener.call(event.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+}
+
+/// Validate `idleTransactionMs`.
+pub fn idle_transaction_duration(ms: f64) -> napi::Result<Duration> {
+    if !ms.is_finite() || ms <= 0.0 {
+        return Err(napi::Error::from_reason(
+            "idleTransactionMs must be a positive number of milliseconds",
+        ));
+    }
+    Ok(Duration::from_secs_f64(ms / 1000.0))
+}
+
+/// When a transaction needs checking next; `None` when it isn't watched for
+/// anything.
+fn next_due(watched: &Watched, now: Instant) -> Option<Instant> {
+    let rollback = watched.max_duration.map(|max| watched.activity.began + max);
+    let idle = watched.idle.as_ref().map(|idle| {
+        let last = watched.activity.last();
+        if idle.warned == Some(last) {
+            // Warned already: look for new calls a stretch later.
+            now + idle.after
+        } else {
+            last + idle.after
+        }
+    });
+    rollback.into_iter().chain(idle).min()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
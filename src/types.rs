@@ -0,0 +1,481 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Custom types: `db.registerType()`.
+//
+// A registered type lets an app bind its own classes, such as decimals,
+// Temporal values or ID objects, as parameters and get them back from
+// results. `test` picks the object parameters of the type and `serialize`
+// converts them to a value the engine can store, which is then converted as
+// usual. `deserialize` sees every non-NULL value of the result columns it
+// applies to (all of them, or `columns`) once the rows have been made, and
+// returns the revived value, or `undefined` to leave it to the next type.
+// Types are tried in the order they were registered.
+//
+// The hooks are JS functions, so they run on the JS thread: parameters are
+// converted when a call is made, results when a sync call returns or an
+// async call's promise settles. The code that makes rows doesn't know the
+// database they belong to, so a call marks its types as the ones reviving
+// on the thread while its results are made. The `temporal`,
+// `timestampPrecision` and `timezone` open options ride on the same mark,
+// since they change how TIMESTAMP cells are made, and so does the filter of
+// `db.setResultFilter()` (see result_filter.rs), which changes every cell.
+
+use std::cell::RefCell;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use napi::bindgen_prelude::{FromNapiValue, FunctionRef, JsValue, Unknown};
+use napi::{sys, Env};
+use stoolap_bindings_core::timestamps::TimestampFormat;
+
+use crate::shape::Expanded;
+use crate::tasks::check;
+
+pub(crate) type Hook = FunctionRef<Unknown<'static>, Unknown<'static>>;
+
+/// Options for `db.registerType()`.
+#[napi(object, object_to_js = false)]
+pub struct TypeOptions {
+    /// Whether an object parameter is of this type.
+    #[napi(ts_type = "(value: any) => boolean")]
+    pub test: Option<Hook>,
+    /// Convert a parameter of this type to a value the engine can store.
+    #[napi(ts_type = "(value: any) => any")]
+    pub serialize: Option<Hook>,
+    /// Revive a result value, or return `undefined` to leave it.
+    #[napi(ts_type = "(value: any, column: string) => any")]
+    pub deserialize: Option<Hook>,
+    /// The result columns `deserialize` sees (default all of them).
+    pub columns: Option<Vec<String>>,
+}
+
+struct Type {
+    test: Option<Hook>,
+    serialize: Option<Hook>,
+    deserialize: Option<Hook>,
+    columns: Option<Vec<String>>,
+}
+
+impl Type {
+    fn revives(&self, column: &str) -> bool {
+        self.deserialize.is_some()
+            && self
+                .columns
+                .as_ref()
+                .is_none_or(|columns| columns.iter().any(|c| c == column))
+    }
+}
+
+pub type TypesRef = Arc<Types>;
+
+/// The types registered on a database, shared with everything created from
+/// it.
+#[derive(Default)]
+pub struct Types {
+    types: RwLock<Vec<Arc<Type>>>,
+    serializes: AtomicBool,
+    deserializes: AtomicBool,
+    /// `temporal`: TIMESTAMP results as `Temporal.Instant`s.
+    temporal: AtomicBool,
+    /// `timestampPrecision` and `timezone`, set when the database opens.
+    timestamps: OnceLock<TimestampFormat>,
+    /// `setResultFilter()`.
+    filter: RwLock<Option<Arc<Hook>>>,
+    filters: AtomicBool,
+}
+
+thread_local! {
+    /// The types reviving the results being made on the JS thread.
+    static REVIVING: RefCell<Option<TypesRef>> = const { RefCell::new(None) };
+}
+
+/// Marks a call's types as reviving until it is dropped.
+pub struct Reviving(Option<Option<TypesRef>>);
+
+impl Drop for Reviving {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            REVIVING.with(|reviving| *reviving.borrow_mut() = previous);
+        }
+    }
+}
+
+impl Types {
+    pub fn register(&self, options: TypeOptions) -> napi::Result<()> {
+        if options.test.is_some() != options.serialize.is_some() {
+            return Err(napi::Error::from_reason(
+                "registerType needs both test and serialize to convert parameters",
+            ));
+        }
+        if options.serialize.is_none() && options.deserialize.is_none() {
+            return Err(napi::Error::from_reason(
+                "registerType needs test and serialize, deserialize, or both",
+            ));
+        }
+        if options.columns.is_some() && options.deserialize.is_none() {
+            return Err(napi::Error::from_reason(
+                "columns only applies to deserialize",
+            ));
+        }
+        let mut types = self.types.write().unwrap_or_else(|e| e.into_inner());
+        self.serializes
+            .fetch_or(options.serialize.is_some(), Ordering::AcqRel);
+        self.deserializes
+            .fetch_or(options.deserialize.is_some(), Ordering::AcqRel);
+        types.push(Arc::new(Type {
+            test: options.test,
+            serialize: options.serialize,
+            deserialize: options.deserialize,
+            columns: options.columns,
+        }));
+        Ok(())
+    }
+
+    /// `value` as its type serializes it, or as it is.
+    pub fn serialize(
+        &self,
+        env: sys::napi_env,
+        value: sys::napi_value,
+    ) -> napi::Result<sys::napi_value> {
+        if !self.serializes.load(Ordering::Acquire) || !is_object(env, value)? {
+            return Ok(value);
+        }
+        for ty in self.snapshot() {
+            let (Some(test), Some(serialize)) = (&ty.test, &ty.serialize) else {
+                continue;
+            };
+            let matched = call(env, test, &[value])?;
+            let mut is = false;
+            let mut truthy = ptr::null_mut();
+            check(unsafe { sys::napi_coerce_to_bool(env, matched, &mut truthy) })?;
+            check(unsafe { sys::napi_get_value_bool(env, truthy, &mut is) })?;
+            if is {
+                return call(env, serialize, &[value]);
+            }
+        }
+        Ok(value)
+    }
+
+    pub fn set_temporal(&self, on: bool) {
+        self.temporal.store(on, Ordering::Release);
+    }
+
+    pub fn set_timestamps(&self, format: TimestampFormat) {
+        let _ = self.timestamps.set(format);
+    }
+
+    /// Filter the values of every result with `filter`, or stop with `None`.
+    pub fn set_filter(&self, filter: Option<Hook>) {
+        let mut current = self.filter.write().unwrap_or_else(|e| e.into_inner());
+        self.filters.store(filter.is_some(), Ordering::Release);
+        *current = filter.map(Arc::new);
+    }
+
+    /// Mark these types as reviving the results made on this thread until
+    /// the guard is dropped.
+    pub fn reviving(self: &Arc<Self>) -> Reviving {
+        if !self.deserializes.load(Ordering::Acquire)
+            && !self.temporal.load(Ordering::Acquire)
+            && self.timestamps.get().is_none()
+            && !self.filters.load(Ordering::Acquire)
+        {
+            return Reviving(None);
+        }
+        let previous = REVIVING.with(|reviving| reviving.replace(Some(Arc::clone(self))));
+        Reviving(Some(previous))
+    }
+
+    /// The types registered so far. The hooks run without the lock, so they
+    /// can register types themselves.
+    fn snapshot(&self) -> Vec<Arc<Type>> {
+        self.types.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// How a result just made holds its values.
+pub enum Form<'a> {
+    /// Row objects with these keys: an array of them, or one (`many: false`).
+    Objects { keys: &'a [String], many: bool },
+    /// `{ columns, rows }`, with each row an array.
+    Raw(&'a [String]),
+    /// An array of one column's values.
+    Pluck(&'a str),
+    /// An array of rows with an object per table.
+    Expanded(&'a Expanded),
+}
+
+/// Revive the values of `result` with the types reviving on this thread, if
+/// any.
+pub fn revive(env: sys::napi_env, result: sys::napi_value, form: Form) -> napi::Result<()> {
+    let Some(types) = REVIVING.with(|reviving| reviving.borrow().clone()) else {
+        return Ok(());
+    };
+    if !types.deserializes.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    let types = types.snapshot();
+    if is_null(env, result)? {
+        return Ok(());
+    }
+    match form {
+        Form::Objects { keys, many } => {
+            let columns = Columns::new(env, &types, keys)?;
+            if columns.is_empty() {
+                return Ok(());
+            }
+            if !many {
+                return columns.revive_object(env, result);
+            }
+            for row in elements(env, result)? {
+                columns.revive_object(env, row)?;
+            }
+            Ok(())
+        }
+        Form::Raw(names) => {
+            let columns = Columns::new(env, &types, names)?;
+            if columns.is_empty() {
+                return Ok(());
+            }
+            let mut rows = ptr::null_mut();
+            check(unsafe {
+                sys::napi_get_named_property(env, result, c"rows".as_ptr(), &mut rows)
+            })?;
+            for row in elements(env, rows)? {
+                for column in &columns.0 {
+                    let mut value = ptr::null_mut();
+                    check(unsafe { sys::napi_get_element(env, row, column.index, &mut value) })?;
+                    if let Some(revived) = column.revive(env, value)? {
+                        check(unsafe { sys::napi_set_element(env, row, column.index, revived) })?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        Form::Pluck(name) => {
+            let columns = Columns::new(env, &types, &[name.to_string()])?;
+            let Some(column) = columns.0.first() else {
+                return Ok(());
+            };
+            for (i, value) in elements(env, result)?.into_iter().enumerate() {
+                if let Some(revived) = column.revive(env, value)? {
+                    check(unsafe { sys::napi_set_element(env, result, i as u32, revived) })?;
+                }
+            }
+            Ok(())
+        }
+        Form::Expanded(expanded) => {
+            let columns = Columns::new(env, &types, &expanded.keys)?;
+            if columns.is_empty() {
+                return Ok(());
+            }
+            let namespaces = expanded
+                .namespaces
+                .iter()
+                .map(|n| string(env, n))
+                .collect::<napi::Result<Vec<_>>>()?;
+            for row in elements(env, result)? {
+                for column in &columns.0 {
+                    let namespace = expanded.column_namespaces[column.index as usize];
+                    let mut object = ptr::null_mut();
+                    check(unsafe {
+                        sys::napi_get_property(
+                            env,
+                            row,
+                            namespaces[namespace as usize],
+                            &mut object,
+                        )
+                    })?;
+                    column.revive_property(env, object)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Whether the results made on this thread return TIMESTAMPs as
+/// `Temporal.Instant`s.
+pub fn temporal() -> bool {
+    REVIVING.with(|reviving| {
+        reviving
+            .borrow()
+            .as_ref()
+            .is_some_and(|types| types.temporal.load(Ordering::Acquire))
+    })
+}
+
+/// How the results made on this thread write TIMESTAMPs, unless as the
+/// default.
+pub fn timestamp_format() -> Option<TimestampFormat> {
+    REVIVING.with(|reviving| {
+        reviving
+            .borrow()
+            .as_ref()
+            .and_then(|types| types.timestamps.get().copied())
+    })
+}
+
+/// The result filter of the types reviving on this thread, if any.
+pub fn filter() -> Option<Arc<Hook>> {
+    let types = REVIVING.with(|reviving| reviving.borrow().clone())?;
+    if !types.filters.load(Ordering::Acquire) {
+        return None;
+    }
+    let filter = types.filter.read().unwrap_or_else(|e| e.into_inner());
+    filter.clone()
+}
+
+/// The result columns some type revives.
+struct Columns(Vec<Column>);
+
+struct Column {
+    index: u32,
+    /// The name as a JS string, the property key of row objects.
+    key: sys::napi_value,
+    /// The types that revive it, in order.
+    types: Vec<Arc<Type>>,
+}
+
+impl Columns {
+    fn new(env: sys::napi_env, types: &[Arc<Type>], names: &[String]) -> napi::Result<Self> {
+        let mut columns = Vec::new();
+        for (index, name) in names.iter().enumerate() {
+            let reviving: Vec<Arc<Type>> = types
+                .iter()
+                .filter(|ty| ty.revives(name))
+                .cloned()
+                .collect();
+            if !reviving.is_empty() {
+                columns.push(Column {
+                    index: index as u32,
+                    key: string(env, name)?,
+                    types: reviving,
+                });
+            }
+        }
+        Ok(Self(columns))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn revive_object(&self, env: sys::napi_env, object: sys::napi_value) -> napi::Result<()> {
+        for column in &self.0 {
+            column.revive_property(env, object)?;
+        }
+        Ok(())
+    }
+}
+
+impl Column {
+    /// The revived `value`, or `None` to keep it.
+    fn revive(
+        &self,
+        env: sys::napi_env,
+        value: sys::napi_value,
+    ) -> napi::Result<Option<sys::napi_value>> {
+        if is_null(env, value)? {
+            return Ok(None);
+        }
+        for ty in &self.types {
+            let deserialize = ty.deserialize.as_ref().expect("revives() checked it");
+            let revived = call(env, deserialize, &[value, self.key])?;
+            if !is_undefined(env, revived)? {
+                return Ok(Some(revived));
+            }
+        }
+        Ok(None)
+    }
+
+    fn revive_property(&self, env: sys::napi_env, object: sys::napi_value) -> napi::Result<()> {
+        let mut value = ptr::null_mut();
+        check(unsafe { sys::napi_get_property(env, object, self.key, &mut value) })?;
+        if let Some(revived) = self.revive(env, value)? {
+            check(unsafe { sys::napi_set_property(env, object, self.key, revived) })?;
+        }
+        Ok(())
+    }
+}
+
+/// Call a hook, turning what it throws into the error.
+pub(crate) fn call(
+    env: sys::napi_env,
+    hook: &Hook,
+    args: &[sys::napi_value],
+) -> napi::Result<sys::napi_value> {
+    let function = hook.borrow_back(&Env::from_raw(env))?.raw();
+    let mut undefined = ptr::null_mut();
+    check(unsafe { sys::napi_get_undefined(env, &mut undefined) })?;
+    let mut result = ptr::null_mut();
+    let status = unsafe {
+        sys::napi_call_function(
+            env,
+            undefined,
+            function,
+            args.len(),
+            args.as_ptr(),
+            &mut result,
+        )
+    };
+    if status == sys::Status::napi_pending_exception {
+        let mut thrown = ptr::null_mut();
+        check(unsafe { sys::napi_get_and_clear_last_exception(env, &mut thrown) })?;
+        let thrown = unsafe { Unknown::from_napi_value(env, thrown)? };
+        return Err(napi::Error::from(thrown));
+    }
+    check(status)?;
+    Ok(result)
+}
+
+fn elements(env: sys::napi_env, array: sys::napi_value) -> napi::Result<Vec<sys::napi_value>> {
+    let mut len = 0u32;
+    check(unsafe { sys::napi_get_array_length(env, array, &mut len) })?;
+    (0..len)
+        .map(|i| {
+            let mut element = ptr::null_mut();
+            check(unsafe { sys::napi_get_element(env, array, i, &mut element) })?;
+            Ok(element)
+        })
+        .collect()
+}
+
+pub(crate) fn string(env: sys::napi_env, s: &str) -> napi::Result<sys::napi_value> {
+    let mut value = ptr::null_mut();
+    check(unsafe {
+        sys::napi_create_string_utf8(env, s.as_ptr().cast(), s.len() as isize, &mut value)
+    })?;
+    Ok(value)
+}
+
+fn value_type(env: sys::napi_env, value: sys::napi_value) -> napi::Result<sys::napi_valuetype> {
+    let mut ty = 0;
+    check(unsafe { sys::napi_typeof(env, value, &mut ty) })?;
+    Ok(ty)
+}
+
+fn is_object(env: sys::napi_env, value: sys::napi_value) -> napi::Result<bool> {
+    Ok(value_type(env, value)? == sys::ValueType::napi_object)
+}
+
+fn is_null(env: sys::napi_env, value: sys::napi_value) -> napi::Result<bool> {
+    let ty = value_type(env, value)?;
+    Ok(ty == sys::ValueType::napi_null || ty == sys::ValueType::napi_undefined)
+}
+
+fn is_undefined(env: sys::napi_env, value: sys::napi_value) -> napi::Result<bool> {
+    Ok(value_type(env, value)? == sys::ValueType::napi_undefined)
+}
@@ -0,0 +1,136 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Prepared statement usage behind `db.statementReport()`.
+//
+// Usage is kept per SQL text, so a query prepared in several places, or
+// prepared again on every request, shows up once. An entry is created the
+// first time its SQL is prepared and lives as long as the database handle,
+// whether or not its statements have been finalized or collected. A call
+// counts as an execution once its parameters have been bound, before it
+// runs; `explain()` without `analyze` doesn't run the statement and isn't
+// counted.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One prepared SQL text, returned by `db.statementReport()`.
+#[napi(object, object_from_js = false, use_nullable = true)]
+pub struct StatementUsageEntry {
+    pub sql: String,
+    /// Times the SQL was prepared.
+    pub prepared: u32,
+    /// Times its statements ran; each parameter set of `executeBatch` counts.
+    pub executions: i64,
+    /// When the SQL was first prepared, in milliseconds since the epoch.
+    pub prepared_at: f64,
+    /// When a statement last ran, in milliseconds since the epoch; null if
+    /// none has.
+    pub last_used_at: Option<f64>,
+}
+
+/// Options for `db.statementReport()`.
+#[napi(object, object_to_js = false)]
+pub struct StatementReportOptions {
+    /// Only include statements that haven't run for this many milliseconds,
+    /// counting from when they were prepared if they never have.
+    pub unused_for_ms: Option<f64>,
+}
+
+pub type StatementUsageRef = Arc<StatementUsage>;
+
+/// Usage of every SQL text prepared on a database.
+#[derive(Default)]
+pub struct StatementUsage {
+    entries: Mutex<HashMap<String, UsageRef>>,
+}
+
+pub type UsageRef = Arc<Usage>;
+
+/// Counters for one SQL text, shared by its statements.
+pub struct Usage {
+    prepared: AtomicU32,
+    executions: AtomicU64,
+    prepared_at: u64,
+    /// Milliseconds since the epoch; 0 while unused.
+    last_used_at: AtomicU64,
+}
+
+impl StatementUsage {
+    /// Count a `prepare()` of `sql` and return its counters.
+    pub fn prepared(&self, sql: &str) -> UsageRef {
+        let mut entries = lock(&self.entries);
+        let usage = entries.entry(sql.to_string()).or_insert_with(|| {
+            Arc::new(Usage {
+                prepared: AtomicU32::new(0),
+                executions: AtomicU64::new(0),
+                prepared_at: now_ms(),
+                last_used_at: AtomicU64::new(0),
+            })
+        });
+        usage.prepared.fetch_add(1, Ordering::Relaxed);
+        Arc::clone(usage)
+    }
+
+    /// Usage by SQL text, most executed first.
+    pub fn report(&self, options: Option<&StatementReportOptions>) -> Vec<StatementUsageEntry> {
+        let cutoff = options
+            .and_then(|o| o.unused_for_ms)
+            .map(|ms| now_ms().saturating_sub(ms.max(0.0) as u64));
+        let mut report: Vec<StatementUsageEntry> = lock(&self.entries)
+            .iter()
+            .filter(|(_, usage)| cutoff.is_none_or(|cutoff| usage.last_activity() <= cutoff))
+            .map(|(sql, usage)| StatementUsageEntry {
+                sql: sql.clone(),
+                prepared: usage.prepared.load(Ordering::Relaxed),
+                executions: usage.executions.load(Ordering::Relaxed) as i64,
+                prepared_at: usage.prepared_at as f64,
+                last_used_at: match usage.last_used_at.load(Ordering::Relaxed) {
+                    0 => None,
+                    at => Some(at as f64),
+                },
+            })
+            .collect();
+        report.sort_by(|a, b| b.executions.cmp(&a.executions).then(a.sql.cmp(&b.sql)));
+        report
+    }
+}
+
+impl Usage {
+    /// Count `executions` runs of a statement, starting now.
+    pub fn record(&self, executions: usize) {
+        self.executions
+            .fetch_add(executions as u64, Ordering::Relaxed);
+        self.last_used_at.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// When a statement last ran, or when the SQL was prepared.
+    fn last_activity(&self) -> u64 {
+        self.last_used_at
+            .load(Ordering::Relaxed)
+            .max(self.prepared_at)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn lock(mutex: &Mutex<HashMap<String, UsageRef>>) -> MutexGuard<'_, HashMap<String, UsageRef>> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -19,11 +19,29 @@
 //   v8::String::NewFromUtf8(..., kInternalized)            — cached column names
 //   v8::Array::New(isolate, elements, count)               — bulk array
 //
+// With low_memory set (the `lowMemory` query option), the streaming helpers
+// trade speed for peak memory: column names are plain strings, and each row
+// is appended to the result array in its own handle scope instead of every
+// row's handle being gathered before the array is created.
+//
+// Text known to be ASCII is copied as one-byte data, skipping UTF-8
+// decoding, and from EXTERNAL_TEXT_MIN bytes (see tasks.rs) it isn't copied
+// at all.
+//
+// A prepared statement's sync calls pass the ColumnKeys it keeps between
+// calls: its column names as persistent internalized strings, and
+// Object.prototype, so a repeated call allocates and hashes no names.
+//
 // Called from Rust via extern "C" FFI.
 
 #include <v8.h>
 #include <node_api.h>
 #include <cstring>
+#include <optional>
+#include <vector>
+#ifndef _WIN32
+#include <dlfcn.h>
+#endif
 
 // Cell type tags — must match Rust #[repr(u8)] CellTag
 enum CellTag : uint8_t {
@@ -35,6 +53,10 @@ enum CellTag : uint8_t {
     TAG_STRING        = 5,
     TAG_INT64         = 6,
     TAG_FLOAT32_ARRAY = 7,
+    TAG_ASCII_STRING  = 8,
+    TAG_EXTERNAL_ASCII = 9,
+    TAG_TIMESTAMP     = 10,
+    TAG_JS_VALUE      = 11,
 };
 
 // C-compatible cell data — must match Rust #[repr(C)] CellData layout
@@ -57,6 +79,66 @@ static inline napi_value from_v8(v8::Local<v8::Value> local) {
     return reinterpret_cast<napi_value>(*local);
 }
 
+// The reverse, for cells that hold a napi_value made already (as Node's
+// own N-API implementation converts them).
+static inline v8::Local<v8::Value> to_v8(int64_t value) {
+    v8::Local<v8::Value> local;
+    napi_value napi = reinterpret_cast<napi_value>(value);
+    memcpy(static_cast<void*>(&local), &napi, sizeof(napi));
+    return local;
+}
+
+// ----------------------------------------------------------------
+// Large ASCII text as external strings: V8 references the engine's buffer
+// instead of copying it. The resource holds a reference to the text, taken
+// from Rust when the string is made and dropped when V8 collects it.
+// ----------------------------------------------------------------
+
+extern "C" void* stoolap_text_retain(const void* text);
+extern "C" void stoolap_text_release(void* owner);
+
+class ExternalText : public v8::String::ExternalOneByteStringResource {
+public:
+    ExternalText(const char* data, size_t length, void* owner)
+        : data_(data), length_(length), owner_(owner) {}
+    ~ExternalText() override { stoolap_text_release(owner_); }
+    const char* data() const override { return data_; }
+    size_t length() const override { return length_; }
+
+private:
+    const char* data_;
+    size_t length_;
+    void* owner_;
+};
+
+// ----------------------------------------------------------------
+// TIMESTAMP results as Temporal.Instant (the `temporal` open option), made
+// by Temporal.Instant.from() on the RFC 3339 text. If it throws, the
+// exception stays pending and the call fails with it.
+// ----------------------------------------------------------------
+
+static v8::Local<v8::Value> temporal_instant(v8::Isolate* isolate,
+                                            v8::Local<v8::String> text) {
+    auto ctx = isolate->GetCurrentContext();
+    auto name = [isolate](const char* s) {
+        return v8::String::NewFromUtf8(
+            isolate, s, v8::NewStringType::kInternalized
+        ).ToLocalChecked();
+    };
+    v8::Local<v8::Value> temporal, instant, from, result;
+    v8::Local<v8::Value> args[] = { text };
+    if (ctx->Global()->Get(ctx, name("Temporal")).ToLocal(&temporal) &&
+        temporal->IsObject() &&
+        temporal.As<v8::Object>()->Get(ctx, name("Instant")).ToLocal(&instant) &&
+        instant->IsObject() &&
+        instant.As<v8::Object>()->Get(ctx, name("from")).ToLocal(&from) &&
+        from->IsFunction() &&
+        from.As<v8::Function>()->Call(ctx, instant, 1, args).ToLocal(&result)) {
+        return result;
+    }
+    return text;
+}
+
 // ----------------------------------------------------------------
 // Convert CellData to v8::Value using direct V8 API (~5ns vs ~30ns NAPI)
 // ----------------------------------------------------------------
@@ -79,6 +161,33 @@ static inline v8::Local<v8::Value> cell_to_v8(v8::Isolate* isolate,
                 isolate, cell.str_ptr,
                 v8::NewStringType::kNormal, cell.str_len
             ).ToLocalChecked();
+        case TAG_ASCII_STRING:
+            return v8::String::NewFromOneByte(
+                isolate, reinterpret_cast<const uint8_t*>(cell.str_ptr),
+                v8::NewStringType::kNormal, cell.str_len
+            ).ToLocalChecked();
+        case TAG_EXTERNAL_ASCII: {
+            auto owner = stoolap_text_retain(
+                reinterpret_cast<const void*>(cell.int_val));
+            auto resource = new ExternalText(cell.str_ptr, cell.str_len, owner);
+            v8::Local<v8::String> text;
+            if (v8::String::NewExternalOneByte(isolate, resource).ToLocal(&text)) {
+                return text;
+            }
+            delete resource;
+            return v8::String::NewFromOneByte(
+                isolate, reinterpret_cast<const uint8_t*>(cell.str_ptr),
+                v8::NewStringType::kNormal, cell.str_len
+            ).ToLocalChecked();
+        }
+        case TAG_TIMESTAMP:
+            return temporal_instant(isolate, v8::String::NewFromOneByte(
+                isolate, reinterpret_cast<const uint8_t*>(cell.str_ptr),
+                v8::NewStringType::kNormal, cell.str_len
+            ).ToLocalChecked());
+        case TAG_JS_VALUE:
+            // A value made through N-API, e.g. by a result filter
+            return to_v8(cell.int_val);
         case TAG_INT64:
             // Large integers outside i32 range — still a JS Number (double).
             // Matches napi_create_int64 behavior (converts to double).
@@ -95,6 +204,49 @@ static inline v8::Local<v8::Value> cell_to_v8(v8::Isolate* isolate,
     }
 }
 
+// ----------------------------------------------------------------
+// Column names kept by a prepared statement across its sync calls
+// ----------------------------------------------------------------
+
+struct ColumnKeys {
+    std::vector<v8::Global<v8::Name>> names;
+    v8::Global<v8::Value> proto;
+};
+
+static v8::Local<v8::Value> object_prototype(v8::Isolate* isolate,
+                                             v8::Local<v8::Context> ctx) {
+    auto obj_str = v8::String::NewFromUtf8(
+        isolate, "Object", v8::NewStringType::kInternalized
+    ).ToLocalChecked();
+    auto obj_ctor = ctx->Global()->Get(ctx, obj_str).ToLocalChecked();
+    auto proto_str = v8::String::NewFromUtf8(
+        isolate, "prototype", v8::NewStringType::kInternalized
+    ).ToLocalChecked();
+    return v8::Local<v8::Object>::Cast(obj_ctor)
+        ->Get(ctx, proto_str).ToLocalChecked();
+}
+
+// Column name strings: the cached ones, or new ones of name_type.
+static void column_names(v8::Isolate* isolate,
+                         const ColumnKeys* cached,
+                         int col_count,
+                         const char* const* col_ptrs,
+                         const int* col_lens,
+                         v8::NewStringType name_type,
+                         v8::LocalVector<v8::Name>& out) {
+    out.reserve(col_count);
+    for (int c = 0; c < col_count; c++) {
+        if (cached) {
+            out.push_back(cached->names[c].Get(isolate));
+            continue;
+        }
+        auto name = v8::String::NewFromUtf8(
+            isolate, col_ptrs[c], name_type, col_lens[c]
+        ).ToLocalChecked();
+        out.push_back(name.As<v8::Name>());
+    }
+}
+
 extern "C" {
 
 // Create a single row object using V8 bulk API.
@@ -103,25 +255,21 @@ napi_value v8_create_single_object(
     int col_count,
     const char* const* col_ptrs,
     const int* col_lens,
-    const CellData* cells
+    const CellData* cells,
+    const void* cached_keys
 ) {
     v8::Isolate* isolate = v8::Isolate::GetCurrent();
     v8::EscapableHandleScope scope(isolate);
+    auto cached = static_cast<const ColumnKeys*>(cached_keys);
 
     if (col_count == 0) {
         return from_v8(scope.Escape(v8::Object::New(isolate)));
     }
 
-    // Create internalized column name strings
+    // Create internalized column name strings, unless cached
     v8::LocalVector<v8::Name> keys(isolate);
-    keys.reserve(col_count);
-    for (int c = 0; c < col_count; c++) {
-        auto name = v8::String::NewFromUtf8(
-            isolate, col_ptrs[c],
-            v8::NewStringType::kInternalized, col_lens[c]
-        ).ToLocalChecked();
-        keys.push_back(name.As<v8::Name>());
-    }
+    column_names(isolate, cached, col_count, col_ptrs, col_lens,
+                 v8::NewStringType::kInternalized, keys);
 
     // Convert cell values
     v8::LocalVector<v8::Value> vals(isolate);
@@ -130,18 +278,8 @@ napi_value v8_create_single_object(
         vals.push_back(cell_to_v8(isolate, cells[c]));
     }
 
-    // Get Object.prototype
-    auto ctx = isolate->GetCurrentContext();
-    auto global = ctx->Global();
-    auto obj_str = v8::String::NewFromUtf8(
-        isolate, "Object", v8::NewStringType::kInternalized
-    ).ToLocalChecked();
-    auto obj_ctor = global->Get(ctx, obj_str).ToLocalChecked();
-    auto proto_str = v8::String::NewFromUtf8(
-        isolate, "prototype", v8::NewStringType::kInternalized
-    ).ToLocalChecked();
-    auto proto = v8::Local<v8::Object>::Cast(obj_ctor)
-        ->Get(ctx, proto_str).ToLocalChecked();
+    auto proto = cached ? cached->proto.Get(isolate)
+                        : object_prototype(isolate, isolate->GetCurrentContext());
 
     auto obj = v8::Object::New(
         isolate, proto, keys.data(), vals.data(), col_count
@@ -170,39 +308,29 @@ napi_value v8_create_rows_streaming(
     const char* const* col_ptrs,
     const int* col_lens,
     RowCallback next_row,
-    void* ctx
+    void* ctx,
+    int low_memory,
+    const void* cached_keys
 ) {
     v8::Isolate* isolate = v8::Isolate::GetCurrent();
     v8::EscapableHandleScope scope(isolate);
+    auto cached = static_cast<const ColumnKeys*>(cached_keys);
 
     if (col_count == 0) {
         auto arr = v8::Array::New(isolate, 0);
         return from_v8(scope.Escape(arr));
     }
 
-    // Create internalized column name strings (cached by V8)
+    // Create internalized column name strings (cached by V8), or plain ones
+    // in low-memory mode, unless the statement has them already
+    auto name_type = low_memory ? v8::NewStringType::kNormal
+                                : v8::NewStringType::kInternalized;
     v8::LocalVector<v8::Name> keys(isolate);
-    keys.reserve(col_count);
-    for (int c = 0; c < col_count; c++) {
-        auto name = v8::String::NewFromUtf8(
-            isolate, col_ptrs[c],
-            v8::NewStringType::kInternalized, col_lens[c]
-        ).ToLocalChecked();
-        keys.push_back(name.As<v8::Name>());
-    }
+    column_names(isolate, cached, col_count, col_ptrs, col_lens, name_type, keys);
 
-    // Get Object.prototype
     auto v8_ctx = isolate->GetCurrentContext();
-    auto global = v8_ctx->Global();
-    auto obj_str = v8::String::NewFromUtf8(
-        isolate, "Object", v8::NewStringType::kInternalized
-    ).ToLocalChecked();
-    auto obj_ctor = global->Get(v8_ctx, obj_str).ToLocalChecked();
-    auto proto_str = v8::String::NewFromUtf8(
-        isolate, "prototype", v8::NewStringType::kInternalized
-    ).ToLocalChecked();
-    auto proto = v8::Local<v8::Object>::Cast(obj_ctor)
-        ->Get(v8_ctx, proto_str).ToLocalChecked();
+    auto proto = cached ? cached->proto.Get(isolate)
+                        : object_prototype(isolate, v8_ctx);
 
     // Reusable per-row cell buffer (stack-sized for typical queries)
     CellData cells_buf[64];
@@ -211,8 +339,20 @@ napi_value v8_create_rows_streaming(
     v8::LocalVector<v8::Value> rows(isolate);
     v8::LocalVector<v8::Value> vals(isolate);
     vals.reserve(col_count);
+    v8::Local<v8::Array> arr;
+    if (low_memory) {
+        arr = v8::Array::New(isolate, 0);
+    }
+    uint32_t index = 0;
 
-    while (next_row(ctx, cells) != 0) {
+    for (;;) {
+        std::optional<v8::HandleScope> row_scope;
+        if (low_memory) {
+            row_scope.emplace(isolate);
+        }
+        if (next_row(ctx, cells) == 0) {
+            break;
+        }
         vals.clear();
         for (int c = 0; c < col_count; c++) {
             vals.push_back(cell_to_v8(isolate, cells[c]));
@@ -220,14 +360,20 @@ napi_value v8_create_rows_streaming(
         auto obj = v8::Object::New(
             isolate, proto, keys.data(), vals.data(), col_count
         );
-        rows.push_back(obj);
+        if (low_memory) {
+            arr->Set(v8_ctx, index++, obj).Check();
+        } else {
+            rows.push_back(obj);
+        }
     }
 
     if (cells != cells_buf) {
         delete[] cells;
     }
 
-    auto arr = v8::Array::New(isolate, rows.data(), rows.size());
+    if (!low_memory) {
+        arr = v8::Array::New(isolate, rows.data(), rows.size());
+    }
     return from_v8(scope.Escape(arr));
 }
 
@@ -241,20 +387,24 @@ napi_value v8_create_raw_streaming(
     const char* const* col_ptrs,
     const int* col_lens,
     RowCallback next_row,
-    void* ctx
+    void* ctx,
+    int low_memory,
+    const void* cached_keys
 ) {
     v8::Isolate* isolate = v8::Isolate::GetCurrent();
     v8::EscapableHandleScope scope(isolate);
+    auto v8_ctx = isolate->GetCurrentContext();
+    auto cached = static_cast<const ColumnKeys*>(cached_keys);
 
     // Create columns array
+    auto name_type = low_memory ? v8::NewStringType::kNormal
+                                : v8::NewStringType::kInternalized;
+    v8::LocalVector<v8::Name> names(isolate);
+    column_names(isolate, cached, col_count, col_ptrs, col_lens, name_type, names);
     v8::LocalVector<v8::Value> col_names(isolate);
     col_names.reserve(col_count);
     for (int c = 0; c < col_count; c++) {
-        auto name = v8::String::NewFromUtf8(
-            isolate, col_ptrs[c],
-            v8::NewStringType::kInternalized, col_lens[c]
-        ).ToLocalChecked();
-        col_names.push_back(name);
+        col_names.push_back(names[c]);
     }
     auto columns_arr = v8::Array::New(isolate, col_names.data(), col_names.size());
 
@@ -265,34 +415,43 @@ napi_value v8_create_raw_streaming(
     v8::LocalVector<v8::Value> rows(isolate);
     v8::LocalVector<v8::Value> vals(isolate);
     vals.reserve(col_count);
+    v8::Local<v8::Array> rows_arr;
+    if (low_memory) {
+        rows_arr = v8::Array::New(isolate, 0);
+    }
+    uint32_t index = 0;
 
-    while (next_row(ctx, cells) != 0) {
+    for (;;) {
+        std::optional<v8::HandleScope> row_scope;
+        if (low_memory) {
+            row_scope.emplace(isolate);
+        }
+        if (next_row(ctx, cells) == 0) {
+            break;
+        }
         vals.clear();
         for (int c = 0; c < col_count; c++) {
             vals.push_back(cell_to_v8(isolate, cells[c]));
         }
         auto row_arr = v8::Array::New(isolate, vals.data(), vals.size());
-        rows.push_back(row_arr);
+        if (low_memory) {
+            rows_arr->Set(v8_ctx, index++, row_arr).Check();
+        } else {
+            rows.push_back(row_arr);
+        }
     }
 
     if (cells != cells_buf) {
         delete[] cells;
     }
 
-    auto rows_arr = v8::Array::New(isolate, rows.data(), rows.size());
+    if (!low_memory) {
+        rows_arr = v8::Array::New(isolate, rows.data(), rows.size());
+    }
 
     // Create { columns, rows } result object
-    auto v8_ctx = isolate->GetCurrentContext();
-    auto global = v8_ctx->Global();
-    auto obj_str = v8::String::NewFromUtf8(
-        isolate, "Object", v8::NewStringType::kInternalized
-    ).ToLocalChecked();
-    auto obj_ctor = global->Get(v8_ctx, obj_str).ToLocalChecked();
-    auto proto_str = v8::String::NewFromUtf8(
-        isolate, "prototype", v8::NewStringType::kInternalized
-    ).ToLocalChecked();
-    auto proto = v8::Local<v8::Object>::Cast(obj_ctor)
-        ->Get(v8_ctx, proto_str).ToLocalChecked();
+    auto proto = cached ? cached->proto.Get(isolate)
+                        : object_prototype(isolate, v8_ctx);
 
     v8::LocalVector<v8::Name> keys(isolate);
     v8::LocalVector<v8::Value> values(isolate);
@@ -310,11 +469,143 @@ napi_value v8_create_raw_streaming(
 }
 
 // ----------------------------------------------------------------
-// RunResult: { changes: number } — single-property object for DML
-// Hot path for INSERT/UPDATE/DELETE: one V8 call instead of 3 NAPI calls
+// Pluck: the first column of each row as a flat array (stmt.pluck())
 // ----------------------------------------------------------------
 
-napi_value v8_create_run_result(int64_t changes) {
+napi_value v8_create_pluck_streaming(
+    int col_count,
+    RowCallback next_row,
+    void* ctx
+) {
+    v8::Isolate* isolate = v8::Isolate::GetCurrent();
+    v8::EscapableHandleScope scope(isolate);
+
+    if (col_count == 0) {
+        auto arr = v8::Array::New(isolate, 0);
+        return from_v8(scope.Escape(arr));
+    }
+
+    CellData cells_buf[64];
+    CellData* cells = (col_count <= 64) ? cells_buf : new CellData[col_count];
+
+    v8::LocalVector<v8::Value> values(isolate);
+    while (next_row(ctx, cells) != 0) {
+        values.push_back(cell_to_v8(isolate, cells[0]));
+    }
+
+    if (cells != cells_buf) {
+        delete[] cells;
+    }
+
+    auto arr = v8::Array::New(isolate, values.data(), values.size());
+    return from_v8(scope.Escape(arr));
+}
+
+// ----------------------------------------------------------------
+// Expand: one nested object per namespace (stmt.expand())
+// col_ns[c] is the namespace index of column c; key_ptrs/key_lens are the
+// column names within their namespace.
+// ----------------------------------------------------------------
+
+napi_value v8_create_expanded_streaming(
+    int col_count,
+    const char* const* key_ptrs,
+    const int* key_lens,
+    int ns_count,
+    const char* const* ns_ptrs,
+    const int* ns_lens,
+    const int* col_ns,
+    RowCallback next_row,
+    void* ctx
+) {
+    v8::Isolate* isolate = v8::Isolate::GetCurrent();
+    v8::EscapableHandleScope scope(isolate);
+
+    if (col_count == 0) {
+        auto arr = v8::Array::New(isolate, 0);
+        return from_v8(scope.Escape(arr));
+    }
+
+    // Namespace names, and each namespace's keys and columns in column order
+    v8::LocalVector<v8::Name> ns_names(isolate);
+    std::vector<v8::LocalVector<v8::Name>> ns_keys;
+    std::vector<std::vector<int>> ns_cols(ns_count);
+    ns_keys.reserve(ns_count);
+    for (int n = 0; n < ns_count; n++) {
+        ns_names.push_back(v8::String::NewFromUtf8(
+            isolate, ns_ptrs[n],
+            v8::NewStringType::kInternalized, ns_lens[n]
+        ).ToLocalChecked().As<v8::Name>());
+        ns_keys.emplace_back(isolate);
+    }
+    for (int c = 0; c < col_count; c++) {
+        int n = col_ns[c];
+        ns_keys[n].push_back(v8::String::NewFromUtf8(
+            isolate, key_ptrs[c],
+            v8::NewStringType::kInternalized, key_lens[c]
+        ).ToLocalChecked().As<v8::Name>());
+        ns_cols[n].push_back(c);
+    }
+
+    // Get Object.prototype
+    auto v8_ctx = isolate->GetCurrentContext();
+    auto global = v8_ctx->Global();
+    auto obj_str = v8::String::NewFromUtf8(
+        isolate, "Object", v8::NewStringType::kInternalized
+    ).ToLocalChecked();
+    auto obj_ctor = global->Get(v8_ctx, obj_str).ToLocalChecked();
+    auto proto_str = v8::String::NewFromUtf8(
+        isolate, "prototype", v8::NewStringType::kInternalized
+    ).ToLocalChecked();
+    auto proto = v8::Local<v8::Object>::Cast(obj_ctor)
+        ->Get(v8_ctx, proto_str).ToLocalChecked();
+
+    CellData cells_buf[64];
+    CellData* cells = (col_count <= 64) ? cells_buf : new CellData[col_count];
+
+    v8::LocalVector<v8::Value> rows(isolate);
+    v8::LocalVector<v8::Value> inner(isolate);
+    v8::LocalVector<v8::Value> vals(isolate);
+    inner.reserve(ns_count);
+    vals.reserve(col_count);
+
+    while (next_row(ctx, cells) != 0) {
+        inner.clear();
+        for (int n = 0; n < ns_count; n++) {
+            vals.clear();
+            for (int c : ns_cols[n]) {
+                vals.push_back(cell_to_v8(isolate, cells[c]));
+            }
+            inner.push_back(v8::Object::New(
+                isolate, proto, ns_keys[n].data(), vals.data(), vals.size()
+            ));
+        }
+        rows.push_back(v8::Object::New(
+            isolate, proto, ns_names.data(), inner.data(), ns_count
+        ));
+    }
+
+    if (cells != cells_buf) {
+        delete[] cells;
+    }
+
+    auto arr = v8::Array::New(isolate, rows.data(), rows.size());
+    return from_v8(scope.Escape(arr));
+}
+
+// ----------------------------------------------------------------
+// RunResult: { changes, lastInsertRowId, durationMs, warnings } for DML
+// Hot path for INSERT/UPDATE/DELETE: one V8 call instead of ~10 NAPI calls
+// last_id is a TAG_NULL or integer cell; warnings are TAG_STRING cells
+// ----------------------------------------------------------------
+
+napi_value v8_create_run_result(
+    int64_t changes,
+    const CellData* last_id,
+    double duration_ms,
+    const CellData* warnings,
+    int warning_count
+) {
     v8::Isolate* isolate = v8::Isolate::GetCurrent();
     v8::EscapableHandleScope scope(isolate);
 
@@ -330,21 +621,77 @@ napi_value v8_create_run_result(int64_t changes) {
     auto proto = v8::Local<v8::Object>::Cast(obj_ctor)
         ->Get(ctx, proto_str).ToLocalChecked();
 
+    const char* names[] = {"changes", "lastInsertRowId", "durationMs", "warnings"};
     v8::LocalVector<v8::Name> keys(isolate);
-    v8::LocalVector<v8::Value> values(isolate);
-
-    keys.push_back(v8::String::NewFromUtf8(
-        isolate, "changes", v8::NewStringType::kInternalized
-    ).ToLocalChecked().As<v8::Name>());
+    for (const char* name : names) {
+        keys.push_back(v8::String::NewFromUtf8(
+            isolate, name, v8::NewStringType::kInternalized
+        ).ToLocalChecked().As<v8::Name>());
+    }
 
+    v8::LocalVector<v8::Value> values(isolate);
     if (changes >= INT32_MIN && changes <= INT32_MAX) {
         values.push_back(v8::Int32::New(isolate, static_cast<int32_t>(changes)));
     } else {
         values.push_back(v8::Number::New(isolate, static_cast<double>(changes)));
     }
+    values.push_back(cell_to_v8(isolate, *last_id));
+    values.push_back(v8::Number::New(isolate, duration_ms));
+
+    v8::LocalVector<v8::Value> messages(isolate);
+    messages.reserve(warning_count);
+    for (int i = 0; i < warning_count; i++) {
+        messages.push_back(cell_to_v8(isolate, warnings[i]));
+    }
+    values.push_back(v8::Array::New(isolate, messages.data(), warning_count));
 
-    auto result = v8::Object::New(isolate, proto, keys.data(), values.data(), 1);
+    auto result = v8::Object::New(isolate, proto, keys.data(), values.data(), 4);
     return from_v8(scope.Escape(result));
 }
 
+// ----------------------------------------------------------------
+// Column keys of a prepared statement, made when its columns change and
+// freed with the statement, on the JS thread.
+// ----------------------------------------------------------------
+
+void* v8_column_keys_new(
+    int col_count,
+    const char* const* col_ptrs,
+    const int* col_lens
+) {
+    v8::Isolate* isolate = v8::Isolate::GetCurrent();
+    v8::HandleScope scope(isolate);
+    auto keys = new ColumnKeys();
+    keys->names.reserve(col_count);
+    for (int c = 0; c < col_count; c++) {
+        auto name = v8::String::NewFromUtf8(
+            isolate, col_ptrs[c],
+            v8::NewStringType::kInternalized, col_lens[c]
+        ).ToLocalChecked();
+        keys->names.emplace_back(isolate, name.As<v8::Name>());
+    }
+    keys->proto.Reset(isolate, object_prototype(isolate, isolate->GetCurrentContext()));
+    return keys;
+}
+
+void v8_column_keys_free(void* keys) {
+    delete static_cast<ColumnKeys*>(keys);
+}
+
+// ----------------------------------------------------------------
+// Probe: can the helpers above run in this process?
+// Checked once at module load before any of them is called. On Unix the
+// V8 symbols are resolved lazily, so a runtime that doesn't export them
+// would only fail at the first call; look the entry point up first.
+// ----------------------------------------------------------------
+
+int v8_helpers_probe(void) {
+#ifndef _WIN32
+    if (dlsym(RTLD_DEFAULT, "_ZN2v87Isolate10GetCurrentEv") == nullptr) {
+        return 0;
+    }
+#endif
+    return v8::Isolate::GetCurrent() != nullptr ? 1 : 0;
+}
+
 } // extern "C"
@@ -0,0 +1,311 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.vacuum()`.
+//
+// The engine keeps deleted rows and old row versions around until its
+// background cleanup drops them after the retention period. Vacuuming runs
+// VACUUM on each table, which drops every version no running transaction
+// can see at once. That frees the memory they held, but the WAL still holds
+// every change ever made: the engine's snapshots, which would let it be
+// truncated, don't keep indexes or views yet, so none is taken.
+//
+// With `into`, the live rows are also copied to a new database file, so the
+// files only hold what is left. The copy is made table by table, in one
+// transaction each: the schema from SHOW CREATE TABLE, the indexes from SHOW
+// INDEXES (index options such as HNSW parameters are not kept) and the views
+// from SHOW CREATE VIEW. It is closed once written, so it can be opened as
+// soon as the promise resolves.
+
+use std::path::Path;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use stoolap::api::Database;
+use stoolap::{Rows, Value};
+
+use crate::database::translate_path;
+use crate::error::to_napi;
+use crate::interrupt::Ticket;
+use crate::policy::{check_sql, PolicyRef};
+use crate::readers::Readers;
+use crate::sql::{parse_single, quote_ident};
+
+type Hook = FunctionRef<Unknown<'static>, Unknown<'static>>;
+
+/// Options for `db.vacuum()`.
+#[napi(object, object_to_js = false)]
+pub struct VacuumOptions {
+    /// Also write a compacted copy of the database to this new file path.
+    pub into: Option<String>,
+    /// Called after each table is vacuumed, and after each is copied.
+    #[napi(ts_type = "(progress: VacuumProgress) => void")]
+    pub on_progress: Option<Hook>,
+}
+
+/// How far `db.vacuum()` got, as `onProgress` gets it.
+#[napi(object, object_from_js = false)]
+pub struct VacuumProgress {
+    /// `'vacuum'` while tables are vacuumed, then `'copy'` while they are
+    /// copied `into` a new file.
+    #[napi(ts_type = "'vacuum' | 'copy'")]
+    pub phase: String,
+    /// The table just done.
+    pub table: String,
+    /// Tables done in this phase, this one included.
+    pub done: u32,
+    /// Tables in all.
+    pub total: u32,
+}
+
+/// What `db.vacuum()` resolves to.
+#[napi(object, object_from_js = false, use_nullable = true)]
+pub struct VacuumReport {
+    /// Tables vacuumed.
+    pub tables: u32,
+    /// Deleted rows dropped.
+    pub deleted_rows_cleaned: i64,
+    /// Old row versions dropped.
+    pub old_versions_cleaned: i64,
+    /// Finished transactions forgotten.
+    pub transactions_cleaned: i64,
+    /// Rows copied `into` the new file, if one was given.
+    pub rows_copied: Option<i64>,
+}
+
+pub type VacuumProgressSink =
+    ThreadsafeFunction<VacuumProgress, Unknown<'static>, VacuumProgress, Status, false, true>;
+
+/// Vacuum every table of `db`, then copy it `into` a new file if given.
+pub fn vacuum(
+    db: &Database,
+    policy: &PolicyRef,
+    readers: &Readers,
+    ticket: &Ticket,
+    into: Option<&str>,
+    progress: Option<&VacuumProgressSink>,
+) -> napi::Result<VacuumReport> {
+    let target = into.map(target).transpose()?;
+    let tables = names(db, "SHOW TABLES")?;
+    let total = tables.len() as u32;
+    let report_progress = |phase: &str, table: &str, done: usize| {
+        if let Some(progress) = progress {
+            let event = VacuumProgress {
+                phase: phase.to_string(),
+                table: table.to_string(),
+                done: done as u32,
+                total,
+            };
+            progress.call(event, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    };
+
+    let mut report = VacuumReport {
+        tables: total,
+        deleted_rows_cleaned: 0,
+        old_versions_cleaned: 0,
+        transactions_cleaned: 0,
+        rows_copied: None,
+    };
+    for (i, table) in tables.iter().enumerate() {
+        ticket.check()?;
+        let sql = format!("VACUUM {}", quote_ident(table));
+        check_sql(policy, &sql)?;
+        let cleaned = readers.write(|| db.query(&sql, ()).map_err(to_napi).map(first_row))?;
+        let count = |i: usize| match cleaned.get(i) {
+            Some(Value::Integer(n)) => *n,
+            _ => 0,
+        };
+        report.deleted_rows_cleaned += count(0);
+        report.old_versions_cleaned += count(1);
+        report.transactions_cleaned += count(2);
+        report_progress("vacuum", table, i + 1);
+    }
+
+    if let Some((dsn, path)) = target {
+        let copy = Database::open(&dsn).map_err(to_napi)?;
+        let copied = copy_into(db, &copy, &tables, ticket, |table, done| {
+            report_progress("copy", table, done)
+        });
+        let closed = copied.and_then(|rows| copy.close().map_err(to_napi).map(|()| rows));
+        match closed {
+            Ok(rows) => report.rows_copied = Some(rows),
+            Err(err) => {
+                let _ = copy.close();
+                let _ = std::fs::remove_dir_all(path);
+                return Err(err);
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// The DSN and path of the new file to vacuum `into`, which must not exist.
+fn target(into: &str) -> napi::Result<(String, String)> {
+    let dsn = translate_path(into);
+    let Some(path) = dsn.strip_prefix("file://") else {
+        return Err(napi::Error::from_reason("vacuum into must be a file path"));
+    };
+    let path = path.split('?').next().unwrap_or(path).to_string();
+    if path.is_empty() {
+        return Err(napi::Error::from_reason("vacuum into must be a file path"));
+    }
+    if Path::new(&path).exists() {
+        return Err(napi::Error::from_reason(format!(
+            "Cannot vacuum into '{into}': it already exists"
+        )));
+    }
+    Ok((dsn, path))
+}
+
+/// Copy the schema, rows, indexes and views of `tables` from `db` to `copy`.
+/// Returns the rows copied.
+fn copy_into(
+    db: &Database,
+    copy: &Database,
+    tables: &[String],
+    ticket: &Ticket,
+    mut progress: impl FnMut(&str, usize),
+) -> napi::Result<i64> {
+    let mut copied = 0;
+    for (i, table) in tables.iter().enumerate() {
+        ticket.check()?;
+        let name = quote_ident(table);
+        let create = first_row(
+            db.query(&format!("SHOW CREATE TABLE {name}"), ())
+                .map_err(to_napi)?,
+        );
+        let Some(Value::Text(create)) = create.get(1) else {
+            return Err(napi::Error::from_reason(format!(
+                "Cannot read the schema of table '{table}'"
+            )));
+        };
+        copy.execute(create, ()).map_err(to_napi)?;
+
+        let mut rows = db
+            .query(&format!("SELECT * FROM {name}"), ())
+            .map_err(to_napi)?;
+        let columns: Vec<String> = rows.columns().iter().map(|c| quote_ident(c)).collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${i}")).collect();
+        let insert = parse_single(&format!(
+            "INSERT INTO {name} ({}) VALUES ({})",
+            columns.join(", "),
+            placeholders.join(", ")
+        ))?;
+        let mut tx = copy.begin().map_err(to_napi)?;
+        while rows.advance() {
+            tx.execute_prepared(&insert, rows.current_row().as_slice().to_vec())
+                .map_err(to_napi)?;
+            copied += 1;
+        }
+        tx.commit().map_err(to_napi)?;
+
+        // Indexes the CREATE TABLE made, such as those of UNIQUE columns,
+        // already exist in the copy.
+        let existing = index_names(copy, table)?;
+        for index in indexes(db, table)? {
+            if !existing.contains(&index.name) {
+                copy.execute(&index.sql(table), ()).map_err(to_napi)?;
+            }
+        }
+        progress(table, i + 1);
+    }
+    for view in names(db, "SHOW VIEWS")? {
+        let create = first_row(
+            db.query(&format!("SHOW CREATE VIEW {}", quote_ident(&view)), ())
+                .map_err(to_napi)?,
+        );
+        if let Some(Value::Text(create)) = create.get(1) {
+            copy.execute(create, ()).map_err(to_napi)?;
+        }
+    }
+    Ok(copied)
+}
+
+/// An index as SHOW INDEXES lists it.
+struct Index {
+    name: String,
+    columns: String,
+    method: String,
+    unique: bool,
+}
+
+impl Index {
+    fn sql(&self, table: &str) -> String {
+        // Multi-column indexes list their columns as `(a, b)`.
+        let columns: Vec<String> = self
+            .columns
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split(',')
+            .map(|c| quote_ident(c.trim()))
+            .collect();
+        let using = match self.method.as_str() {
+            "BTREE" | "HASH" | "BITMAP" | "HNSW" => format!(" USING {}", self.method),
+            _ => String::new(),
+        };
+        format!(
+            "CREATE {}INDEX {} ON {} ({}){using}",
+            if self.unique { "UNIQUE " } else { "" },
+            quote_ident(&self.name),
+            quote_ident(table),
+            columns.join(", ")
+        )
+    }
+}
+
+fn indexes(db: &Database, table: &str) -> napi::Result<Vec<Index>> {
+    let mut rows = db
+        .query(&format!("SHOW INDEXES FROM {}", quote_ident(table)), ())
+        .map_err(to_napi)?;
+    let mut indexes = Vec::new();
+    while rows.advance() {
+        let row = rows.current_row();
+        let text = |i: usize| match row.get(i) {
+            Some(Value::Text(s)) => s.to_string(),
+            _ => String::new(),
+        };
+        indexes.push(Index {
+            name: text(1),
+            columns: text(2),
+            method: text(3),
+            unique: matches!(row.get(4), Some(Value::Boolean(true))),
+        });
+    }
+    Ok(indexes)
+}
+
+fn index_names(db: &Database, table: &str) -> napi::Result<Vec<String>> {
+    Ok(indexes(db, table)?.into_iter().map(|i| i.name).collect())
+}
+
+/// The first column of every row `sql` returns.
+fn names(db: &Database, sql: &str) -> napi::Result<Vec<String>> {
+    let mut rows = db.query(sql, ()).map_err(to_napi)?;
+    let mut names = Vec::new();
+    while rows.advance() {
+        if let Some(Value::Text(name)) = rows.current_row().get(0) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+fn first_row(mut rows: Rows) -> Vec<Value> {
+    if rows.advance() {
+        rows.current_row().as_slice().to_vec()
+    } else {
+        Vec::new()
+    }
+}
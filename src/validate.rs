@@ -0,0 +1,78 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.validate()`: parse errors of SQL, with where they are.
+//
+// The SQL is only parsed, so nothing runs and the schema isn't consulted: a
+// statement naming a missing table is valid here. The parser keeps going
+// after an error and reports every one it finds. Its lines and columns are
+// recomputed from each error's byte offset, as the lexer counts the bytes
+// of a quoted literal where elsewhere it counts characters; columns count
+// UTF-16 code units, as JS string indices do.
+
+use stoolap::parser::Parser;
+
+/// Result of `db.validate()`.
+#[napi(object, object_from_js = false)]
+pub struct Validation {
+    /// Whether the SQL parses.
+    pub ok: bool,
+    pub errors: Vec<ValidationError>,
+}
+
+/// A parse error of `db.validate()`.
+#[napi(object, object_from_js = false)]
+pub struct ValidationError {
+    pub message: String,
+    /// Line of the error, from 1.
+    pub line: u32,
+    /// Column of the error on its line, from 1.
+    pub column: u32,
+}
+
+/// The parse errors of `sql`.
+pub fn validate(sql: &str) -> Validation {
+    let errors = match Parser::new(sql).parse_program() {
+        Ok(_) => Vec::new(),
+        Err(e) => e
+            .errors
+            .into_iter()
+            .map(|error| {
+                let (line, column) = line_column(sql, error.position.offset);
+                ValidationError {
+                    message: error.message,
+                    line,
+                    column,
+                }
+            })
+            .collect(),
+    };
+    Validation {
+        ok: errors.is_empty(),
+        errors,
+    }
+}
+
+/// Line and UTF-16 column of byte `offset` in `sql`, both from 1.
+fn line_column(sql: &str, offset: usize) -> (u32, u32) {
+    let mut offset = offset.min(sql.len());
+    while !sql.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &sql[..offset];
+    let start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[start..].encode_utf16().count() + 1;
+    (line as u32, column as u32)
+}
@@ -17,6 +17,11 @@ use std::ffi::CString;
 use std::ptr;
 
 use stoolap::{ParamVec, Value};
+use stoolap_bindings_core::params;
+
+use crate::error::from_core;
+use crate::temporal;
+use crate::types::Types;
 
 /// Check napi status and return Result.
 #[inline(always)]
@@ -61,10 +66,7 @@ pub fn js_to_value(env: sys::napi_env, val: sys::napi_value) -> napi::Result<Val
     // Fast path: try extracting as double directly (saves 1 NAPI call for numbers)
     let mut f = 0.0;
     if unsafe { sys::napi_get_value_double(env, val, &mut f) } == sys::Status::napi_ok {
-        if f.fract() == 0.0 && f >= i64::MIN as f64 && f <= i64::MAX as f64 {
-            return Ok(Value::Integer(f as i64));
-        }
-        return Ok(Value::Float(f));
+        return Ok(params::number(f));
     }
     // Slow path: type-check for non-number values
     js_to_value_typed(env, val)
@@ -136,13 +138,7 @@ fn js_to_value_typed(env: sys::napi_env, val: sys::napi_value) -> napi::Result<V
             if is_date {
                 let mut ms = 0.0;
                 check(unsafe { sys::napi_get_date_value(env, val, &mut ms) })?;
-                let secs = (ms / 1000.0).floor() as i64;
-                let remaining_ms = ms - (secs as f64 * 1000.0);
-                let nsecs = (remaining_ms * 1_000_000.0).round() as u32;
-                if let Some(dt) = chrono::DateTime::from_timestamp(secs, nsecs) {
-                    return Ok(Value::Timestamp(dt));
-                }
-                return Ok(Value::null_unknown());
+                return Ok(params::date(ms));
             }
 
             // Check Buffer
@@ -153,10 +149,11 @@ fn js_to_value_typed(env: sys::napi_env, val: sys::napi_value) -> napi::Result<V
                 let mut len = 0;
                 check(unsafe { sys::napi_get_buffer_info(env, val, &mut data, &mut len) })?;
                 let slice = unsafe { std::slice::from_raw_parts(data as *const u8, len) };
-                let s = std::str::from_utf8(slice).map_err(|e| {
-                    napi::Error::from_reason(format!("Invalid UTF-8 in Buffer: {e}"))
-                })?;
-                return Ok(Value::text(s));
+                return params::utf8(slice).map_err(from_core);
+            }
+
+            if let Some(dt) = temporal::param(env, val)? {
+                return Ok(Value::Timestamp(dt));
             }
 
             // Plain object/array -> JSON string via JSON.stringify
@@ -197,9 +194,24 @@ fn js_to_value_typed(env: sys::napi_env, val: sys::napi_value) -> napi::Result<V
     }
 }
 
+/// Convert a parameter, after the registered type it belongs to, if any,
+/// has serialized it.
+fn param_to_value(
+    env: sys::napi_env,
+    val: sys::napi_value,
+    types: &Types,
+) -> napi::Result<(sys::napi_value, Value)> {
+    let val = types.serialize(env, val)?;
+    Ok((val, js_to_value(env, val)?))
+}
+
 /// Parse a JS array directly into ParamVec (stack-allocated for ≤8 params).
 /// Avoids heap allocation for queries with ≤8 parameters (the common case).
-pub fn parse_positional(env: sys::napi_env, arr: sys::napi_value) -> napi::Result<ParamVec> {
+pub fn parse_positional(
+    env: sys::napi_env,
+    arr: sys::napi_value,
+    types: &Types,
+) -> napi::Result<ParamVec> {
     let mut is_array = false;
     check(unsafe { sys::napi_is_array(env, arr, &mut is_array) })?;
     if !is_array {
@@ -213,7 +225,7 @@ pub fn parse_positional(env: sys::napi_env, arr: sys::napi_value) -> napi::Resul
     for i in 0..len {
         let mut elem = ptr::null_mut();
         check(unsafe { sys::napi_get_element(env, arr, i, &mut elem) })?;
-        values.push(js_to_value(env, elem)?);
+        values.push(param_to_value(env, elem, types)?.1);
     }
     Ok(values)
 }
@@ -224,6 +236,42 @@ pub enum BindParams {
     Named(Vec<(String, Value)>),
 }
 
+/// Which parameter an array was bound to.
+#[derive(PartialEq)]
+pub enum ParamSlot {
+    /// Zero-based position in a positional parameter array
+    Position(usize),
+    Name(String),
+}
+
+/// Elements of a parameter bound to a plain JS array. The parameter itself
+/// still binds the array's JSON value; the elements are kept so a placeholder
+/// that is the only item of an `IN (...)` list can be expanded.
+pub struct ArrayParam {
+    pub slot: ParamSlot,
+    pub values: ParamVec,
+}
+
+/// Elements of `val` if it is a plain JS array. Only values that converted
+/// to JSON can be arrays, so scalars skip the extra napi call.
+fn array_elements(
+    env: sys::napi_env,
+    val: sys::napi_value,
+    value: &Value,
+    types: &Types,
+) -> napi::Result<Option<ParamVec>> {
+    if value.as_json().is_none() {
+        return Ok(None);
+    }
+    let mut is_array = false;
+    check(unsafe { sys::napi_is_array(env, val, &mut is_array) })?;
+    if is_array {
+        parse_positional(env, val, types).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 /// Opaque JS value wrapper for use as `#[napi]` function parameter.
 /// Accepts any JS type without compat-mode.
 pub struct RawParam(pub sys::napi_value);
@@ -257,9 +305,14 @@ impl napi::bindgen_prelude::FromNapiValue for RawParam {
     }
 }
 
-/// Parse JS params (Array or Object) into BindParams.
+/// Parse JS params (Array or Object) into BindParams, plus the elements of
+/// any array-valued parameters.
 /// Optimistic: tries is_array first (most common), skipping typeof on the hot path.
-pub fn parse_params(env: sys::napi_env, val: sys::napi_value) -> napi::Result<BindParams> {
+pub fn parse_params(
+    env: sys::napi_env,
+    val: sys::napi_value,
+    types: &Types,
+) -> napi::Result<(BindParams, Vec<ArrayParam>)> {
     // Fast path: check array first (most common for prepared statements)
     let mut is_array = false;
     check(unsafe { sys::napi_is_array(env, val, &mut is_array) })?;
@@ -268,18 +321,26 @@ pub fn parse_params(env: sys::napi_env, val: sys::napi_value) -> napi::Result<Bi
         let mut len = 0u32;
         check(unsafe { sys::napi_get_array_length(env, val, &mut len) })?;
         let mut values = ParamVec::new();
+        let mut arrays = Vec::new();
         for i in 0..len {
             let mut elem = ptr::null_mut();
             check(unsafe { sys::napi_get_element(env, val, i, &mut elem) })?;
-            values.push(js_to_value(env, elem)?);
+            let (elem, value) = param_to_value(env, elem, types)?;
+            if let Some(elements) = array_elements(env, elem, &value, types)? {
+                arrays.push(ArrayParam {
+                    slot: ParamSlot::Position(i as usize),
+                    values: elements,
+                });
+            }
+            values.push(value);
         }
-        return Ok(BindParams::Positional(values));
+        return Ok((BindParams::Positional(values), arrays));
     }
 
     // Slow path: type check for null/undefined/object
     match get_type(env, val)? {
         napi::ValueType::Null | napi::ValueType::Undefined => {
-            Ok(BindParams::Positional(ParamVec::new()))
+            Ok((BindParams::Positional(ParamVec::new()), Vec::new()))
         }
 
         napi::ValueType::Object => {
@@ -289,6 +350,7 @@ pub fn parse_params(env: sys::napi_env, val: sys::napi_value) -> napi::Result<Bi
             let mut len = 0u32;
             check(unsafe { sys::napi_get_array_length(env, keys, &mut len) })?;
             let mut named = Vec::with_capacity(len as usize);
+            let mut arrays = Vec::new();
             for i in 0..len {
                 let mut key_val = ptr::null_mut();
                 check(unsafe { sys::napi_get_element(env, keys, i, &mut key_val) })?;
@@ -300,13 +362,19 @@ pub fn parse_params(env: sys::napi_env, val: sys::napi_value) -> napi::Result<Bi
                 check(unsafe {
                     sys::napi_get_named_property(env, val, key_cstr.as_ptr(), &mut prop_val)
                 })?;
-                let value = js_to_value(env, prop_val)?;
+                let (prop_val, value) = param_to_value(env, prop_val, types)?;
 
                 // Strip leading :, @, or $ from key
                 let clean = key.trim_start_matches([':', '@', '$']);
+                if let Some(elements) = array_elements(env, prop_val, &value, types)? {
+                    arrays.push(ArrayParam {
+                        slot: ParamSlot::Name(clean.to_string()),
+                        values: elements,
+                    });
+                }
                 named.push((clean.to_string(), value));
             }
-            Ok(BindParams::Named(named))
+            Ok((BindParams::Named(named), arrays))
         }
 
         _ => Err(napi::Error::from_reason(
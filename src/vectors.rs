@@ -0,0 +1,163 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `vectors` query option of `queryRaw()`.
+//
+// Vectors normally come back as one Float32Array per row. Libraries that take
+// a batch of embeddings as one tensor would then copy them together again, so
+// with `vectors: 'buffer'` every column whose values are all vectors (or
+// NULL) is packed into one Buffer, row after row, and described in the raw
+// result's `vectors` object under the column's key; its cells in `rows` are
+// null. A NULL vector takes its row's place in the buffer as zeros and is
+// listed in `nulls`. The packing is done where the rows are collected, off
+// the JS thread for async calls, and a column whose vectors differ in
+// dimension fails the call.
+
+use std::collections::HashMap;
+
+use napi::bindgen_prelude::{Buffer, ToNapiValue};
+use napi::sys;
+use stoolap::Value;
+
+use crate::column_names::ColumnNames;
+use crate::tasks::{check, CollectedRows};
+
+/// Tag byte of a vector in a `Value::Extension`.
+const VECTOR_TAG: u8 = 7;
+
+/// Bytes per element.
+const FLOAT32: usize = 4;
+
+/// One packed vector column of a raw result.
+#[napi(object, object_from_js = false)]
+pub struct VectorBuffer {
+    /// Every row's vector, one after the other.
+    pub buffer: Buffer,
+    /// Elements per vector.
+    pub dim: u32,
+    #[napi(ts_type = "'float32'")]
+    pub dtype: String,
+    /// Bytes from one row's vector to the next.
+    pub stride: u32,
+    /// Indexes of the rows whose vector is NULL; their place holds zeros.
+    pub nulls: Vec<u32>,
+}
+
+/// The `vectors` query option, checked: whether to pack vector columns.
+pub fn buffered(vectors: Option<&str>) -> napi::Result<bool> {
+    match vectors {
+        None | Some("typedArray") => Ok(false),
+        Some("buffer") => Ok(true),
+        Some(_) => Err(napi::Error::from_reason(
+            "vectors must be 'typedArray' or 'buffer'",
+        )),
+    }
+}
+
+/// A column packed by `pack()`.
+struct Packed {
+    column: usize,
+    dim: usize,
+    data: Vec<u8>,
+    nulls: Vec<u32>,
+}
+
+/// Vector columns taken out of collected rows, until they reach JS.
+#[derive(Default)]
+pub struct VectorColumns {
+    packed: Vec<Packed>,
+}
+
+impl VectorColumns {
+    /// Move the vectors of every vector column of `data` into buffers,
+    /// leaving NULL in their cells.
+    pub fn pack(&mut self, data: &mut CollectedRows) -> napi::Result<()> {
+        let (columns, rows) = data.parts_mut();
+        for (column, name) in columns.iter().enumerate() {
+            let Some(dim) = dimension(name, rows, column)? else {
+                continue;
+            };
+            let mut packed = Packed {
+                column,
+                dim,
+                data: Vec::with_capacity(rows.len() * dim * FLOAT32),
+                nulls: Vec::new(),
+            };
+            for (i, row) in rows.iter_mut().enumerate() {
+                match std::mem::replace(&mut row[column], Value::null_unknown()) {
+                    Value::Extension(bytes) => packed.data.extend_from_slice(&bytes[1..]),
+                    _ => {
+                        packed.nulls.push(i as u32);
+                        packed.data.resize(packed.data.len() + dim * FLOAT32, 0);
+                    }
+                }
+            }
+            self.packed.push(packed);
+        }
+        Ok(())
+    }
+
+    /// Set `vectors` on the raw result `raw`, whose `columns` are keyed as
+    /// `query()` keys them.
+    pub fn attach(
+        self,
+        env: sys::napi_env,
+        raw: sys::napi_value,
+        columns: &[String],
+        names: ColumnNames,
+    ) -> napi::Result<()> {
+        let keys = names.keys(columns)?;
+        let vectors: HashMap<String, VectorBuffer> = self
+            .packed
+            .into_iter()
+            .map(|packed| {
+                let buffer = VectorBuffer {
+                    buffer: Buffer::from(packed.data),
+                    dim: packed.dim as u32,
+                    dtype: "float32".to_string(),
+                    stride: (packed.dim * FLOAT32) as u32,
+                    nulls: packed.nulls,
+                };
+                (keys[packed.column].clone(), buffer)
+            })
+            .collect();
+        let value = unsafe { HashMap::to_napi_value(env, vectors)? };
+        check(unsafe { sys::napi_set_named_property(env, raw, c"vectors".as_ptr(), value) })
+    }
+}
+
+/// The dimension of `column` if every value in it is a vector or NULL and
+/// at least one is a vector.
+fn dimension(name: &str, rows: &[Vec<Value>], column: usize) -> napi::Result<Option<usize>> {
+    let mut dim = None;
+    for row in rows {
+        match &row[column] {
+            Value::Null(_) => {}
+            Value::Extension(bytes) if bytes.first() == Some(&VECTOR_TAG) => {
+                let len = (bytes.len() - 1) / FLOAT32;
+                match dim {
+                    None => dim = Some(len),
+                    Some(d) if d != len => {
+                        return Err(napi::Error::from_reason(format!(
+                            "Vector column '{name}' has vectors of {d} and {len} dimensions"
+                        )))
+                    }
+                    Some(_) => {}
+                }
+            }
+            _ => return Ok(None),
+        }
+    }
+    Ok(dim)
+}
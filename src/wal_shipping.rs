@@ -0,0 +1,361 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// `db.exportWalSegments()` and `Database.applyWalSegments()`.
+//
+// The engine has no API to read or apply its WAL, but it replays every
+// committed entry of the WAL files in its `wal/` directory when it opens a
+// database. Exporting reads those files as the engine wrote them, entry by
+// entry in LSN order, and returns the entries after `sinceLsn` as they are
+// on disk. It stops at the last entry after which no transaction is half
+// written, so every segment ends on whole transactions; the rest are
+// exported next time. Entries still in the engine's WAL buffer (with `sync:
+// 'none'`) are not on disk yet, and a snapshot deletes the entries it
+// covers, so exporting after one fails if `sinceLsn` is older.
+//
+// Applying writes the entries to a new WAL file of the standby, which must
+// not be open, so the engine replays them the next time the standby opens.
+// The standby has LSNs of its own, for the entries its own snapshots and
+// checkpoints write, so the entries are renumbered after its last one (the
+// checksum only covers an entry's body, not the LSNs in its header), and the
+// primary LSN applied so far is kept in `wal/shipped.lsn`. Entries at or
+// before it are skipped, so applying the same segments twice is harmless.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use napi::bindgen_prelude::Buffer;
+use stoolap::api::Database;
+use stoolap::storage::mvcc::file_lock::FileLock;
+use stoolap::storage::mvcc::wal_manager::{WalFlags, MARKER_TXN_ID};
+use stoolap::storage::mvcc::{CheckpointMetadata, WALEntry};
+
+use crate::database::translate_path;
+use crate::error::to_napi;
+
+/// First bytes of every WAL entry, as the engine frames them.
+const ENTRY_MAGIC: u32 = 0x454C_4157;
+/// Size of an entry header: magic, version, flags, header size, LSN,
+/// previous LSN, body size and 4 reserved bytes.
+const HEADER_SIZE: usize = 32;
+
+/// WAL entries of a primary, as `db.exportWalSegments()` returns them.
+#[napi(object)]
+pub struct WalSegment {
+    /// The LSN these entries follow: the `sinceLsn` of the export, or the
+    /// `toLsn` of the segment before.
+    pub from_lsn: i64,
+    /// LSN of the last entry.
+    pub to_lsn: i64,
+    /// Entries in `data`.
+    pub entries: u32,
+    /// The entries as the engine wrote them.
+    pub data: Buffer,
+}
+
+/// A segment read from the WAL files, for `WalSegment`.
+pub struct Segment {
+    pub from_lsn: u64,
+    pub to_lsn: u64,
+    pub entries: u32,
+    pub data: Vec<u8>,
+}
+
+impl From<Segment> for WalSegment {
+    fn from(segment: Segment) -> Self {
+        Self {
+            from_lsn: segment.from_lsn as i64,
+            to_lsn: segment.to_lsn as i64,
+            entries: segment.entries,
+            data: Buffer::from(segment.data),
+        }
+    }
+}
+
+impl From<&WalSegment> for Segment {
+    fn from(segment: &WalSegment) -> Self {
+        Self {
+            from_lsn: segment.from_lsn.max(0) as u64,
+            to_lsn: segment.to_lsn.max(0) as u64,
+            entries: segment.entries,
+            data: segment.data.to_vec(),
+        }
+    }
+}
+
+/// One WAL entry, still framed.
+struct Frame {
+    lsn: u64,
+    txn_id: i64,
+    /// A commit or rollback.
+    ends: bool,
+    bytes: Vec<u8>,
+}
+
+/// The directory of the file database `db`.
+pub fn database_dir(db: &Database) -> napi::Result<PathBuf> {
+    let config = db.engine().config();
+    match config.path {
+        Some(ref path) if config.persistence.enabled => Ok(PathBuf::from(path)),
+        _ => Err(napi::Error::from_reason(
+            "exportWalSegments needs a file database",
+        )),
+    }
+}
+
+/// Whether `since` is a valid LSN.
+pub fn check_lsn(since: Option<i64>) -> napi::Result<u64> {
+    match since {
+        None => Ok(0),
+        Some(lsn) if lsn >= 0 => Ok(lsn as u64),
+        Some(_) => Err(napi::Error::from_reason("sinceLsn must not be negative")),
+    }
+}
+
+/// The whole transactions of the WAL in `dir` after `since`, one segment per
+/// WAL file they were read from.
+pub fn export(dir: &Path, since: u64) -> napi::Result<Vec<Segment>> {
+    let files = wal_files(&dir.join("wal"))?;
+    if let Some(&(_, oldest)) = files.first() {
+        if since < oldest {
+            return Err(napi::Error::from_reason(format!(
+                "The WAL before LSN {oldest} was removed by a snapshot; start the standby \
+                 again from a copy of the database files"
+            )));
+        }
+    }
+
+    // Every frame after `since`, with the index of the file it came from.
+    let mut frames = Vec::new();
+    let mut last = since;
+    for (i, (path, _)) in files.iter().enumerate() {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            // Truncated away by a snapshot since it was listed.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(io_error(path, e)),
+        };
+        for frame in read_frames(&bytes)? {
+            // A truncated file can repeat entries of the one before.
+            if frame.lsn > last {
+                last = frame.lsn;
+                frames.push((i, frame));
+            }
+        }
+    }
+
+    // Cut after the last entry that leaves no transaction open.
+    let mut open = HashSet::new();
+    let mut whole = 0;
+    for (n, (_, frame)) in frames.iter().enumerate() {
+        if frame.ends {
+            open.remove(&frame.txn_id);
+        } else {
+            open.insert(frame.txn_id);
+        }
+        if open.is_empty() {
+            whole = n + 1;
+        }
+    }
+    frames.truncate(whole);
+
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut file = None;
+    for (i, frame) in frames {
+        match segments.last_mut() {
+            Some(segment) if file == Some(i) => {
+                segment.to_lsn = frame.lsn;
+                segment.entries += 1;
+                segment.data.extend_from_slice(&frame.bytes);
+            }
+            _ => {
+                let from_lsn = segments.last().map_or(since, |s| s.to_lsn);
+                segments.push(Segment {
+                    from_lsn,
+                    to_lsn: frame.lsn,
+                    entries: 1,
+                    data: frame.bytes,
+                });
+                file = Some(i);
+            }
+        }
+    }
+    Ok(segments)
+}
+
+/// Append `segments` to the WAL of the standby at `path`, creating it if it
+/// doesn't exist. Returns the primary LSN the standby has applied up to.
+pub fn apply(path: &str, segments: &[Segment]) -> napi::Result<u64> {
+    let dsn = translate_path(path);
+    let dir = dsn
+        .strip_prefix("file://")
+        .map(|rest| rest.split('?').next().unwrap_or(rest))
+        .filter(|dir| !dir.is_empty());
+    let Some(dir) = dir else {
+        return Err(napi::Error::from_reason(
+            "applyWalSegments needs a file path",
+        ));
+    };
+    let dir = Path::new(dir);
+    let _lock = FileLock::acquire(dir).map_err(|e| match e {
+        stoolap::Error::DatabaseLocked => {
+            napi::Error::from_reason(format!("Cannot apply WAL segments to '{path}': it is open"))
+        }
+        e => to_napi(e),
+    })?;
+    let wal = dir.join("wal");
+    fs::create_dir_all(&wal).map_err(|e| io_error(&wal, e))?;
+
+    let mut standby = 0;
+    for (file, name_lsn) in wal_files(&wal)? {
+        standby = standby.max(name_lsn);
+        let bytes = fs::read(&file).map_err(|e| io_error(&file, e))?;
+        for frame in read_frames(&bytes)? {
+            standby = standby.max(frame.lsn);
+        }
+    }
+    if let Ok(checkpoint) = CheckpointMetadata::read_from_file(&wal.join("checkpoint.meta")) {
+        standby = standby.max(checkpoint.lsn);
+    }
+    // A standby started from a copy of the primary's files shares its LSNs.
+    let shipped_path = wal.join("shipped.lsn");
+    let mut shipped = match fs::read_to_string(&shipped_path) {
+        Ok(text) => text.trim().parse().map_err(|_| {
+            napi::Error::from_reason(format!("'{}' is damaged", shipped_path.display()))
+        })?,
+        Err(_) => standby,
+    };
+
+    let mut data = Vec::new();
+    let mut lsn = standby;
+    for segment in segments {
+        if segment.to_lsn <= shipped {
+            continue;
+        }
+        if segment.from_lsn > shipped {
+            return Err(napi::Error::from_reason(format!(
+                "The segments start after LSN {}, but the standby has only applied up to LSN \
+                 {shipped}",
+                segment.from_lsn
+            )));
+        }
+        for mut frame in read_frames(&segment.data)? {
+            if frame.lsn <= shipped {
+                continue;
+            }
+            shipped = frame.lsn;
+            lsn += 1;
+            frame.bytes[8..16].copy_from_slice(&lsn.to_le_bytes());
+            frame.bytes[16..24].copy_from_slice(&(lsn - 1).to_le_bytes());
+            data.extend_from_slice(&frame.bytes);
+        }
+    }
+    if data.is_empty() {
+        return Ok(shipped);
+    }
+
+    // Files hold the entries after the LSN in their name.
+    let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    write_file(
+        &wal.join(format!("wal-{timestamp}-lsn-{standby}.log")),
+        &data,
+    )?;
+    write_file(&shipped_path, shipped.to_string().as_bytes())?;
+    Ok(shipped)
+}
+
+/// The WAL files in `wal`, in LSN order, with the LSN their name holds.
+fn wal_files(wal: &Path) -> napi::Result<Vec<(PathBuf, u64)>> {
+    let entries = match fs::read_dir(wal) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error(wal, e)),
+    };
+    let mut files = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !((name.starts_with("wal-") || name.starts_with("wal_")) && name.ends_with(".log")) {
+            continue;
+        }
+        let lsn = name
+            .find("lsn-")
+            .and_then(|start| name[start + 4..].split('.').next())
+            .and_then(|lsn| lsn.parse().ok())
+            .unwrap_or(0);
+        files.push((entry.path(), lsn));
+    }
+    files.sort_by_key(|&(_, lsn)| lsn);
+    Ok(files)
+}
+
+/// The entries of a WAL file, up to the first one not fully written yet.
+/// Rotation and snapshot markers are left out.
+fn read_frames(bytes: &[u8]) -> napi::Result<Vec<Frame>> {
+    let mut frames = Vec::new();
+    let mut pos = 0;
+    while pos + HEADER_SIZE <= bytes.len() {
+        let header = &bytes[pos..pos + HEADER_SIZE];
+        let u64_at = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap());
+        if u32::from_le_bytes(header[0..4].try_into().unwrap()) != ENTRY_MAGIC {
+            return Err(napi::Error::from_reason(
+                "Not a WAL entry: the WAL or segment is damaged",
+            ));
+        }
+        let header_size = (u16::from_le_bytes([header[6], header[7]]) as usize).max(HEADER_SIZE);
+        let body_size = u32::from_le_bytes(header[24..28].try_into().unwrap()) as usize;
+        let end = pos + header_size + body_size + 4;
+        if end > bytes.len() {
+            break;
+        }
+        let lsn = u64_at(8);
+        let entry = WALEntry::decode(
+            lsn,
+            u64_at(16),
+            WalFlags::from_byte(header[5]),
+            &bytes[pos + header_size..end],
+        )
+        .map_err(|e| {
+            napi::Error::from_reason(format!("The WAL entry at LSN {lsn} is damaged: {e}"))
+        })?;
+        if entry.txn_id != MARKER_TXN_ID {
+            frames.push(Frame {
+                lsn,
+                txn_id: entry.txn_id,
+                ends: entry.is_commit_marker()
+                    || entry.is_abort_marker()
+                    || entry.operation.is_transaction_end(),
+                bytes: bytes[pos..end].to_vec(),
+            });
+        }
+        pos = end;
+    }
+    Ok(frames)
+}
+
+/// Write `data` to `path` through a temporary file, so a crash leaves
+/// either the old file or the whole new one.
+fn write_file(path: &Path, data: &[u8]) -> napi::Result<()> {
+    let temp = path.with_extension("tmp");
+    let mut file = fs::File::create(&temp).map_err(|e| io_error(&temp, e))?;
+    file.write_all(data)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| io_error(&temp, e))?;
+    fs::rename(&temp, path).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> napi::Error {
+    napi::Error::from_reason(format!("'{}': {e}", path.display()))
+}
@@ -0,0 +1,153 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Windowed reads generated natively.
+//
+// `topNPerGroup` ranks each group's rows with ROW_NUMBER() (or RANK() with
+// `ties`) in a derived table and keeps the first `n`. The outer query lists
+// the table's columns explicitly so the rank column stays out of the rows.
+// Column names are checked against the table first, since the engine
+// doesn't reject unknown columns in a window's PARTITION BY.
+
+use napi::bindgen_prelude::Either;
+use stoolap::api::Database;
+use stoolap::{ParamVec, Value};
+
+use crate::policy::{check_sql, PolicyRef};
+use crate::sql::{quote_ident, table_columns};
+
+/// Name of the rank column added by the derived table.
+const RANK_COLUMN: &str = "$rank";
+
+/// Options for `topNPerGroup()` / `topNPerGroupSync()`.
+#[napi(object, object_to_js = false)]
+pub struct TopNOptions {
+    /// Column(s) that define a group.
+    pub group: Either<String, Vec<String>>,
+    /// Column(s) that rank rows within a group, each optionally followed by
+    /// `ASC` or `DESC`, e.g. `'score DESC'`.
+    pub order_by: Either<String, Vec<String>>,
+    /// Rows to keep per group.
+    pub n: u32,
+    /// Keep rows tied with the `n`th row too (RANK() instead of ROW_NUMBER()).
+    pub ties: Option<bool>,
+}
+
+/// A resolved `topNPerGroup` call.
+pub struct TopN {
+    table: String,
+    group: Vec<String>,
+    order_by: Vec<String>,
+    n: u32,
+    ties: bool,
+}
+
+impl TopN {
+    pub fn new(table: String, options: TopNOptions) -> napi::Result<Self> {
+        let group = list(options.group);
+        let order_by = list(options.order_by);
+        if group.is_empty() {
+            return Err(napi::Error::from_reason(
+                "group must name at least one column",
+            ));
+        }
+        if order_by.is_empty() {
+            return Err(napi::Error::from_reason(
+                "orderBy must name at least one column",
+            ));
+        }
+        if options.n == 0 {
+            return Err(napi::Error::from_reason("n must be at least 1"));
+        }
+        Ok(Self {
+            table,
+            group,
+            order_by,
+            n: options.n,
+            ties: options.ties.unwrap_or(false),
+        })
+    }
+
+    /// Build the ranking query and its parameters, checked against the policy.
+    pub fn sql(&self, db: &Database, policy: &PolicyRef) -> napi::Result<(String, ParamVec)> {
+        let columns = table_columns(db, &self.table)?;
+        let column = |name: &str| {
+            if columns.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+                Ok(quote_ident(name))
+            } else {
+                Err(napi::Error::from_reason(format!(
+                    "Column '{name}' not found in table '{}'",
+                    self.table
+                )))
+            }
+        };
+        let group = self
+            .group
+            .iter()
+            .map(|name| column(name))
+            .collect::<napi::Result<Vec<_>>>()?
+            .join(", ");
+        let order_by = self
+            .order_by
+            .iter()
+            .map(|term| {
+                let (name, direction) = order_term(term)?;
+                Ok(format!("{}{direction}", column(name)?))
+            })
+            .collect::<napi::Result<Vec<_>>>()?
+            .join(", ");
+        let select: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+        let rank = quote_ident(RANK_COLUMN);
+        // The outer ORDER BY repeats the ranking terms rather than using the
+        // rank column: the engine ignores sort keys missing from the select list.
+        let sql = format!(
+            "SELECT {select} FROM (SELECT *, {function}() OVER (PARTITION BY {group} ORDER BY {order_by}) AS {rank} FROM {table}) AS \"ranked\" \
+             WHERE {rank} <= $1 ORDER BY {group}, {order_by}",
+            select = select.join(", "),
+            function = if self.ties { "RANK" } else { "ROW_NUMBER" },
+            table = quote_ident(&self.table),
+        );
+        check_sql(policy, &sql)?;
+        let mut params = ParamVec::new();
+        params.push(Value::Integer(self.n as i64));
+        Ok((sql, params))
+    }
+}
+
+fn list(value: Either<String, Vec<String>>) -> Vec<String> {
+    match value {
+        Either::A(column) => vec![column],
+        Either::B(columns) => columns,
+    }
+}
+
+/// `score` or `score DESC` -> column name and ` ASC` / ` DESC` suffix.
+fn order_term(term: &str) -> napi::Result<(&str, &'static str)> {
+    let mut words = term.split_whitespace();
+    let (Some(column), direction, None) = (words.next(), words.next(), words.next()) else {
+        return Err(invalid_order_term(term));
+    };
+    match direction.map(str::to_ascii_uppercase).as_deref() {
+        None => Ok((column, "")),
+        Some("ASC") => Ok((column, " ASC")),
+        Some("DESC") => Ok((column, " DESC")),
+        Some(_) => Err(invalid_order_term(term)),
+    }
+}
+
+fn invalid_order_term(term: &str) -> napi::Error {
+    napi::Error::from_reason(format!(
+        "Invalid orderBy term '{term}': expected a column name, optionally followed by ASC or DESC"
+    ))
+}
@@ -0,0 +1,439 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `dedicatedThread` open option.
+//
+// By default async calls are queued on the libuv thread pool like any other
+// N-API async work, so calls on one database may run in any order and
+// compete with file system and DNS work for the pool's threads. With the
+// option, the database gets its own thread that runs its async calls one at
+// a time, in the order they were made. Each call still settles a promise on
+// the JS thread through a threadsafe function. The thread exits once the
+// database and everything created from it are garbage collected.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use napi::bindgen_prelude::{AsyncTask, ToNapiValue, TypeName};
+use napi::{sys, Env, JsValue, Task, ValueType};
+
+use crate::call_site::{js_stack, CallSite};
+use crate::closing::ClosingRef;
+use crate::column_names::ColumnNames;
+use crate::commit_window::{CommitWindow, CommitWindowRef, Joining};
+use crate::faults::FaultsRef;
+use crate::memory::MemoryBudgetRef;
+use crate::monitor::{Monitored, TaskMonitorRef};
+use crate::paging::MaxRows;
+use crate::parallel::Parallelism;
+use crate::sync_busy::SyncBusyRef;
+use crate::tasks::{ExecTask, RawJsValue};
+use crate::types::TypesRef;
+use crate::write_queue::{next_owner, TxLocksRef, WriteLock, WriteQueue, WriteQueueRef};
+
+/// A queued call. `false` means it will never run and must reject.
+type Job = Box<dyn FnOnce(bool) + Send>;
+
+/// Where a handle's async calls run, the counters they (and its sync calls)
+/// report to, the queue
+/// serialized writes wait in, the budget their results are held against and
+/// how their column names become keys and custom types convert. The default
+/// is the libuv pool.
+#[derive(Clone, Default)]
+pub struct WorkerRef {
+    worker: Option<Arc<Worker>>,
+    monitor: TaskMonitorRef,
+    /// `syncBusy`.
+    busy: SyncBusyRef,
+    writes: WriteQueueRef,
+    memory: MemoryBudgetRef,
+    /// `asyncStackTraces`.
+    call_sites: bool,
+    /// `columnCase` and `duplicateColumns`, for sync calls too.
+    column_names: ColumnNames,
+    /// `registerType()`, shared with the handle's statements, transactions
+    /// and pipelines.
+    types: TypesRef,
+    /// `maxRows` and `onMaxRows`, for queries without their own.
+    max_rows: Option<MaxRows>,
+    /// `enableFaultInjection()`, shared like `types`.
+    faults: FaultsRef,
+    /// `close()`, shared like `types`.
+    closing: ClosingRef,
+    /// `commitWindowMs`.
+    commits: Option<CommitWindowRef>,
+    /// `parallelism`, for calls without their own.
+    parallelism: Option<Parallelism>,
+}
+
+impl WorkerRef {
+    /// A handle with its own worker thread.
+    pub fn dedicated() -> napi::Result<Self> {
+        Ok(Self {
+            worker: Some(Arc::new(Worker::spawn()?)),
+            monitor: TaskMonitorRef::default(),
+            busy: SyncBusyRef::default(),
+            writes: WriteQueueRef::default(),
+            memory: MemoryBudgetRef::default(),
+            call_sites: false,
+            column_names: ColumnNames::default(),
+            types: TypesRef::default(),
+            max_rows: None,
+            faults: FaultsRef::default(),
+            closing: ClosingRef::default(),
+            commits: None,
+            parallelism: None,
+        })
+    }
+
+    /// Serialize async writes to `tables`.
+    pub fn serialize_writes(mut self, tables: Vec<String>) -> Self {
+        self.writes = Arc::new(WriteQueue::new(tables));
+        self
+    }
+
+    /// Capture the JS stack of every async call for errors of its work.
+    pub fn capture_call_sites(mut self, on: bool) -> Self {
+        self.call_sites = on;
+        self
+    }
+
+    pub fn column_names(mut self, names: ColumnNames) -> Self {
+        self.column_names = names;
+        self
+    }
+
+    pub fn limit_rows(mut self, max_rows: Option<MaxRows>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Commit auto-committed `execute()` calls made within `window` of each
+    /// other together.
+    pub fn commit_window(mut self, window: Option<Duration>) -> Self {
+        self.commits = window.map(|window| Arc::new(CommitWindow::new(window)));
+        self
+    }
+
+    pub fn parallelism(mut self, parallelism: Option<Parallelism>) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    pub fn is_dedicated(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    pub fn monitor(&self) -> &TaskMonitorRef {
+        &self.monitor
+    }
+
+    pub fn busy(&self) -> &SyncBusyRef {
+        &self.busy
+    }
+
+    pub fn writes(&self) -> &WriteQueueRef {
+        &self.writes
+    }
+
+    pub fn memory(&self) -> &MemoryBudgetRef {
+        &self.memory
+    }
+
+    pub fn names(&self) -> ColumnNames {
+        self.column_names
+    }
+
+    pub fn types(&self) -> &TypesRef {
+        &self.types
+    }
+
+    pub fn max_rows(&self) -> Option<MaxRows> {
+        self.max_rows
+    }
+
+    pub fn faults(&self) -> &FaultsRef {
+        &self.faults
+    }
+
+    pub fn closing(&self) -> &ClosingRef {
+        &self.closing
+    }
+
+    /// `parallelism` for a call with `own` of its own, if any.
+    pub fn threads(&self, own: Option<Parallelism>) -> Option<Parallelism> {
+        own.or(self.parallelism)
+    }
+
+    /// Run `task`, issued by `method`, on this handle's worker thread or on
+    /// the libuv pool.
+    pub fn schedule<T: Task + 'static>(&self, method: &'static str, task: T) -> Scheduled<T> {
+        self.schedule_on(method, task, None)
+    }
+
+    /// Like `schedule`, with the engine's parallel operators on
+    /// `parallelism` threads instead of the handle's.
+    pub fn schedule_on<T: Task + 'static>(
+        &self,
+        method: &'static str,
+        task: T,
+        parallelism: Option<Parallelism>,
+    ) -> Scheduled<T> {
+        let mut task = self.monitor.enqueue(method, task);
+        task.set_call_site(self.call_site());
+        self.place(task, parallelism)
+    }
+
+    /// Like `schedule`, for an auto-committed write to the serialized
+    /// `tables`: it runs once it holds them, and holds them until it is done.
+    pub fn schedule_write<T: Task + 'static>(
+        &self,
+        method: &'static str,
+        tables: Vec<usize>,
+        task: T,
+    ) -> Scheduled<T> {
+        self.serialized(method, tables, None, task)
+    }
+
+    /// Like `schedule_write`, for an `execute()` call, which joins a group
+    /// commit instead when `commitWindowMs` is set and it can.
+    pub fn schedule_execute(&self, tables: Vec<usize>, task: ExecTask) -> Scheduled<ExecTask> {
+        match self.commits {
+            Some(ref window)
+                if tables.is_empty()
+                    && !self.closing.is_closed()
+                    && CommitWindow::groups(&task) =>
+            {
+                Scheduled::Grouped(Box::new(Joining {
+                    window: Arc::clone(window),
+                    worker: self.clone(),
+                    task,
+                    call_site: self.call_site(),
+                }))
+            }
+            _ => self.schedule_write("execute", tables, task),
+        }
+    }
+
+    /// Like `schedule_write`, for a write in a transaction, which keeps the
+    /// tables until it ends.
+    pub fn schedule_tx_write<T: Task + 'static>(
+        &self,
+        method: &'static str,
+        tables: Vec<usize>,
+        locks: &TxLocksRef,
+        task: T,
+    ) -> Scheduled<T> {
+        self.serialized(method, tables, Some(Arc::clone(locks)), task)
+    }
+
+    fn serialized<T: Task + 'static>(
+        &self,
+        method: &'static str,
+        tables: Vec<usize>,
+        tx: Option<TxLocksRef>,
+        task: T,
+    ) -> Scheduled<T> {
+        if tables.is_empty() {
+            return self.schedule(method, task);
+        }
+        let owner = tx.as_ref().map_or_else(next_owner, |tx| tx.owner());
+        match self.writes.try_acquire(owner, &tables) {
+            Some(lock) => self.start(method, task, lock, tx, self.call_site()),
+            None => Scheduled::Waiting(Box::new(Waiting {
+                worker: self.clone(),
+                method,
+                owner,
+                tables,
+                tx,
+                task,
+            })),
+        }
+    }
+
+    /// Schedule `task` now that it holds `lock`.
+    fn start<T: Task + 'static>(
+        &self,
+        method: &'static str,
+        task: T,
+        lock: WriteLock,
+        tx: Option<TxLocksRef>,
+        call_site: CallSite,
+    ) -> Scheduled<T> {
+        let mut task = self.monitor.enqueue(method, task);
+        match tx {
+            Some(tx) => tx.hold(lock),
+            None => task.hold(lock),
+        }
+        task.set_call_site(call_site);
+        self.place(task, None)
+    }
+
+    fn place<T: Task + 'static>(
+        &self,
+        mut task: Monitored<T>,
+        parallelism: Option<Parallelism>,
+    ) -> Scheduled<T> {
+        task.set_types(Arc::clone(&self.types));
+        task.set_parallelism(self.threads(parallelism));
+        task.set_faults(Arc::clone(&self.faults));
+        task.set_closing(Arc::clone(&self.closing));
+        match self.worker {
+            Some(ref worker) => Scheduled::Worker(task, Arc::clone(worker)),
+            None => Scheduled::Pool(task),
+        }
+    }
+
+    /// The call site a new task starts with.
+    fn call_site(&self) -> CallSite {
+        if self.call_sites {
+            CallSite::Wanted
+        } else {
+            CallSite::Off
+        }
+    }
+}
+
+/// A serialized write waiting for its tables.
+pub struct Waiting<T> {
+    worker: WorkerRef,
+    method: &'static str,
+    owner: u64,
+    tables: Vec<usize>,
+    tx: Option<TxLocksRef>,
+    task: T,
+}
+
+impl<T: Task + 'static> Waiting<T> {
+    /// Queue the call and return a promise that follows it once it starts.
+    fn queue(self, env: sys::napi_env) -> napi::Result<sys::napi_value> {
+        type Resolver = Box<dyn FnOnce(Env) -> napi::Result<RawJsValue>>;
+        let env = Env::from_raw(env);
+        let (deferred, promise) = env.create_deferred::<RawJsValue, Resolver>()?;
+        let Waiting {
+            worker,
+            method,
+            owner,
+            tables,
+            tx,
+            task,
+        } = self;
+        // The stack of the call that queued the write; by the time it
+        // starts, JS is in a callback.
+        let call_site = if worker.call_sites {
+            CallSite::Captured(js_stack(&env)?)
+        } else {
+            CallSite::Off
+        };
+        let queue = Arc::clone(&worker.writes);
+        queue.wait(
+            owner,
+            tables,
+            Box::new(move |lock| {
+                deferred.resolve(Box::new(move |env| {
+                    let scheduled = worker.start(method, task, lock, tx, call_site);
+                    let promise = unsafe { Scheduled::to_napi_value(env.raw(), scheduled)? };
+                    Ok(RawJsValue(promise))
+                }));
+            }),
+        );
+        Ok(promise.raw())
+    }
+}
+
+pub struct Worker {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl Worker {
+    fn spawn() -> napi::Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("stoolap-worker".to_string())
+            .spawn(move || {
+                for job in queue {
+                    job(true);
+                }
+            })
+            .map_err(|e| napi::Error::from_reason(format!("Cannot start worker thread: {e}")))?;
+        Ok(Self { jobs })
+    }
+
+    /// Queue `task` and return its promise.
+    fn run<T: Task + 'static>(&self, env: sys::napi_env, task: T) -> napi::Result<sys::napi_value> {
+        type Resolver<T> = Box<dyn FnOnce(Env) -> napi::Result<<T as Task>::JsValue>>;
+        let env = Env::from_raw(env);
+        let (deferred, promise) = env.create_deferred::<T::JsValue, Resolver<T>>()?;
+        let mut task = task;
+        let job: Job = Box::new(move |run| {
+            if !run {
+                deferred.reject(napi::Error::from_reason("Database worker has stopped"));
+                return;
+            }
+            let output = panic::catch_unwind(AssertUnwindSafe(|| task.compute()))
+                .unwrap_or_else(|_| Err(napi::Error::from_reason("Database worker panicked")));
+            deferred.resolve(Box::new(move |env| {
+                let result = match output {
+                    Ok(output) => task.resolve(env, output),
+                    Err(e) => task.reject(env, e),
+                };
+                task.finally(env)?;
+                result
+            }));
+        });
+        if let Err(mpsc::SendError(job)) = self.jobs.send(job) {
+            job(false);
+        }
+        Ok(promise.raw())
+    }
+}
+
+/// An async call's promise, from the libuv pool or a dedicated worker, one
+/// that follows it once the write queue starts it, or one its group commit
+/// settles.
+pub enum Scheduled<T: Task + 'static> {
+    Pool(Monitored<T>),
+    Worker(Monitored<T>, Arc<Worker>),
+    Waiting(Box<Waiting<T>>),
+    Grouped(Box<Joining>),
+}
+
+impl<T: Task + 'static> TypeName for Scheduled<T> {
+    fn type_name() -> &'static str {
+        "Promise"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Object
+    }
+}
+
+impl<T: Task + 'static> ToNapiValue for Scheduled<T> {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        match val {
+            Scheduled::Pool(mut task) => {
+                task.capture_call_site(&Env::from_raw(env))?;
+                AsyncTask::to_napi_value(env, AsyncTask::new(task))
+            }
+            Scheduled::Worker(mut task, worker) => {
+                task.capture_call_site(&Env::from_raw(env))?;
+                worker.run(env, task)
+            }
+            Scheduled::Waiting(waiting) => waiting.queue(env),
+            Scheduled::Grouped(joining) => joining.join(env),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright 2025 Stoolap Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The `serializeWrites` open option.
+//
+// The engine fails a write that touches a row another transaction has
+// changed but not yet committed. For tables listed in `serializeWrites`, the
+// binding makes async writes take turns instead: an auto-committed write
+// holds its tables while it runs, and a transaction holds a table from its
+// first async write to it until it commits or rolls back. A call whose
+// tables are held waits in a first-in, first-out queue per table without
+// occupying a thread; when the holder releases them, the call is scheduled
+// like any other and its promise follows it. Sync methods and reads don't
+// take part.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+use crate::sql::dml_tables;
+
+/// Queue counters for one serialized table, returned by `db.writeQueue`.
+#[napi(object, object_from_js = false)]
+pub struct WriteQueueStats {
+    pub table: String,
+    /// Whether a write or transaction holds the table.
+    pub locked: bool,
+    /// Calls waiting for the table.
+    pub waiting: u32,
+    /// Calls that have taken the table, including those that waited.
+    pub writes: u32,
+    /// Calls that had to wait for the table.
+    pub waits: u32,
+    /// Total time calls spent waiting for the table.
+    pub total_wait_ms: f64,
+    /// Longest time a call waited for the table.
+    pub max_wait_ms: f64,
+}
+
+pub type WriteQueueRef = Arc<WriteQueue>;
+
+/// Identifies what holds a table: one auto-committed call, or a transaction.
+static NEXT_OWNER: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_owner() -> u64 {
+    NEXT_OWNER.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Starts a waiting call once it holds its tables.
+pub type Start = Box<dyn FnOnce(WriteLock) + Send>;
+
+/// The serialized tables of a database and the calls waiting for them. The
+/// default serializes nothing.
+#[derive(Default)]
+pub struct WriteQueue {
+    names: Vec<String>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    tables: Vec<Table>,
+    waiting: VecDeque<Waiter>,
+}
+
+#[derive(Default)]
+struct Table {
+    owner: Option<u64>,
+    writes: u32,
+    waits: u32,
+    total_wait_ms: f64,
+    max_wait_ms: f64,
+}
+
+struct Waiter {
+    owner: u64,
+    tables: Vec<usize>,
+    enqueued: Instant,
+    start: Start,
+}
+
+/// Tables taken by one call or transaction, released when dropped.
+pub struct WriteLock {
+    queue: WriteQueueRef,
+    owner: u64,
+    tables: Vec<usize>,
+}
+
+impl WriteQueue {
+    /// Serialize writes to `tables`.
+    pub fn new(tables: Vec<String>) -> Self {
+        let mut names: Vec<String> = Vec::with_capacity(tables.len());
+        for table in tables {
+            let name = table.to_lowercase();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        let tables = names.iter().map(|_| Table::default()).collect();
+        Self {
+            names,
+            state: Mutex::new(State {
+                tables,
+                waiting: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// The serialized tables `sql` writes to.
+    pub fn tables_in(&self, sql: &str) -> Vec<usize> {
+        if self.names.is_empty() {
+            return Vec::new();
+        }
+        let mut tables: Vec<usize> = dml_tables(sql)
+            .iter()
+            .filter_map(|name| self.index(name))
+            .collect();
+        tables.sort_unstable();
+        tables.dedup();
+        tables
+    }
+
+    /// `table`, if it is serialized.
+    pub fn table_named(&self, table: &str) -> Vec<usize> {
+        self.index(&table.to_lowercase()).into_iter().collect()
+    }
+
+    /// Take `tables` for `owner` if they are free and nobody is waiting for
+    /// them.
+    pub fn try_acquire(self: &Arc<Self>, owner: u64, tables: &[usize]) -> Option<WriteLock> {
+        let mut state = lock(&self.state);
+        let free = tables
+            .iter()
+            .all(|&t| state.is_free(t, owner, state.waiting.iter()));
+        free.then(|| state.take(self, owner, tables, None))
+    }
+
+    /// Queue `owner`'s call for `tables`; `start` runs once it holds them.
+    pub fn wait(self: &Arc<Self>, owner: u64, tables: Vec<usize>, start: Start) {
+        lock(&self.state).waiting.push_back(Waiter {
+            owner,
+            tables,
+            enqueued: Instant::now(),
+            start,
+        });
+        // The tables may have been released since `try_acquire`.
+        self.start_waiting();
+    }
+
+    pub fn stats(&self) -> Vec<WriteQueueStats> {
+        let state = lock(&self.state);
+        state
+            .tables
+            .iter()
+            .zip(&self.names)
+            .enumerate()
+            .map(|(i, (table, name))| WriteQueueStats {
+                table: name.clone(),
+                locked: table.owner.is_some(),
+                waiting: state
+                    .waiting
+                    .iter()
+                    .filter(|w| w.tables.contains(&i))
+                    .count() as u32,
+                writes: table.writes,
+                waits: table.waits,
+                total_wait_ms: table.total_wait_ms,
+                max_wait_ms: table.max_wait_ms,
+            })
+            .collect()
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    fn release(self: &Arc<Self>, owner: u64, tables: &[usize]) {
+        {
+            let mut state = lock(&self.state);
+            for &t in tables {
+                if state.tables[t].owner == Some(owner) {
+                    state.tables[t].owner = None;
+                }
+            }
+        }
+        self.start_waiting();
+    }
+
+    /// Start every waiting call whose tables are free, in queue order.
+    fn start_waiting(self: &Arc<Self>) {
+        let mut started = Vec::new();
+        {
+            let mut state = lock(&self.state);
+            let mut i = 0;
+            while i < state.waiting.len() {
+                let waiter = &state.waiting[i];
+                let ahead = state.waiting.range(..i);
+                let free = waiter
+                    .tables
+                    .iter()
+                    .all(|&t| state.is_free(t, waiter.owner, ahead.clone()));
+                if !free {
+                    i += 1;
+                    continue;
+                }
+                let waiter = state.waiting.remove(i).unwrap();
+                let waited = waiter.enqueued.elapsed().as_secs_f64() * 1000.0;
+                let lock = state.take(self, waiter.owner, &waiter.tables, Some(waited));
+                started.push((waiter.start, lock));
+            }
+        }
+        for (start, lock) in started {
+            start(lock);
+        }
+    }
+}
+
+impl State {
+    /// Whether `owner` may take table `t`: it holds it already, or the table
+    /// is unlocked and no other owner in `ahead` is waiting for it.
+    fn is_free<'a>(
+        &self,
+        t: usize,
+        owner: u64,
+        mut ahead: impl Iterator<Item = &'a Waiter>,
+    ) -> bool {
+        match self.tables[t].owner {
+            Some(holder) => holder == owner,
+            None => !ahead.any(|w| w.owner != owner && w.tables.contains(&t)),
+        }
+    }
+
+    /// Lock `tables` for `owner`, recording a wait of `waited` ms.
+    fn take(
+        &mut self,
+        queue: &WriteQueueRef,
+        owner: u64,
+        tables: &[usize],
+        waited: Option<f64>,
+    ) -> WriteLock {
+        let mut taken = Vec::new();
+        for &t in tables {
+            let table = &mut self.tables[t];
+            if table.owner.is_none() {
+                table.owner = Some(owner);
+                taken.push(t);
+            }
+            table.writes += 1;
+            if let Some(waited) = waited {
+                table.waits += 1;
+                table.total_wait_ms += waited;
+                table.max_wait_ms = table.max_wait_ms.max(waited);
+            }
+        }
+        WriteLock {
+            queue: Arc::clone(queue),
+            owner,
+            tables: taken,
+        }
+    }
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        if !self.tables.is_empty() {
+            self.queue.release(self.owner, &self.tables);
+        }
+    }
+}
+
+pub type TxLocksRef = Arc<TxLocks>;
+
+/// Tables a transaction holds until it commits or rolls back.
+pub struct TxLocks {
+    owner: u64,
+    held: Mutex<Vec<WriteLock>>,
+}
+
+impl Default for TxLocks {
+    fn default() -> Self {
+        Self {
+            owner: next_owner(),
+            held: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl TxLocks {
+    pub fn owner(&self) -> u64 {
+        self.owner
+    }
+
+    pub fn hold(&self, lock: WriteLock) {
+        lock_held(&self.held).push(lock);
+    }
+
+    /// Release the tables once the transaction has ended.
+    pub fn release(&self) {
+        let held = std::mem::take(&mut *lock_held(&self.held));
+        drop(held);
+    }
+}
+
+fn lock(mutex: &Mutex<State>) -> MutexGuard<'_, State> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn lock_held(mutex: &Mutex<Vec<WriteLock>>) -> MutexGuard<'_, Vec<WriteLock>> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}